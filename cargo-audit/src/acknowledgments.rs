@@ -0,0 +1,106 @@
+//! Time-boxed acknowledgments of advisories
+//!
+//! Between ignoring an advisory for good and failing the build, an advisory
+//! can be acknowledged until a deadline (e.g. 30 days after a ticket to
+//! remediate it was filed) with `cargo audit ack`. Its vulnerabilities are
+//! then reported in a separate section, along with the ticket and the days
+//! remaining, and don't fail the audit. Once the acknowledgment expires they
+//! are reported (and fail the audit) as usual again.
+
+use crate::config::Acknowledgment;
+use rustsec::{
+    advisory,
+    report::{AcknowledgedVulnerability, VulnerabilityInfo},
+    Report,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Seconds in a day
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Move the vulnerabilities for advisories with active acknowledgments out
+/// of the report's vulnerabilities, returning the expired acknowledgments
+/// of advisories which vulnerabilities were still found for
+pub fn apply<'a>(
+    report: &mut Report,
+    acknowledgments: &'a [Acknowledgment],
+    today: &advisory::Date,
+) -> Vec<&'a Acknowledgment> {
+    let mut expired = vec![];
+    let mut list = vec![];
+
+    for vulnerability in report.vulnerabilities.list.drain(..) {
+        let acknowledgment = acknowledgments
+            .iter()
+            .find(|ack| ack.id == vulnerability.advisory.id);
+
+        match acknowledgment {
+            Some(ack) if ack.is_active(today) => {
+                report
+                    .vulnerabilities
+                    .acknowledged
+                    .push(AcknowledgedVulnerability {
+                        vulnerability,
+                        ticket: ack.ticket.clone(),
+                        expires: ack.expires.clone(),
+                        days_remaining: days_between(today, &ack.expires),
+                    })
+            }
+            Some(ack) => {
+                if !expired.contains(&ack) {
+                    expired.push(ack);
+                }
+
+                list.push(vulnerability);
+            }
+            None => list.push(vulnerability),
+        }
+    }
+
+    if !report.vulnerabilities.acknowledged.is_empty() {
+        let ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+        let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+        let vex = std::mem::take(&mut report.vulnerabilities.vex);
+        report.vulnerabilities = VulnerabilityInfo::new(list);
+        report.vulnerabilities.ignored = ignored;
+        report.vulnerabilities.acknowledged = acknowledged;
+        report.vulnerabilities.vex = vex;
+        report.sort();
+    } else {
+        report.vulnerabilities.list = list;
+    }
+
+    expired
+}
+
+/// Date the given number of days after another
+pub fn days_after(date: &advisory::Date, days: u64) -> advisory::Date {
+    let time = start_of(date) + Duration::from_secs(days * DAY_SECS);
+    humantime::format_rfc3339(time).to_string()[..10]
+        .parse()
+        .expect("RFC 3339 timestamps start with a date")
+}
+
+/// Number of days from one date until a later one (0 if it isn't later)
+pub fn days_between(from: &advisory::Date, until: &advisory::Date) -> u64 {
+    start_of(until)
+        .duration_since(start_of(from))
+        .map(|duration| duration.as_secs() / DAY_SECS)
+        .unwrap_or(0)
+}
+
+/// Is the date one which exists in the calendar (e.g. not `2021-02-30`)?
+pub fn is_calendar_date(date: &advisory::Date) -> bool {
+    humantime::parse_rfc3339(&midnight(date)).is_ok()
+}
+
+/// Start of the given day (in UTC), or the epoch for dates which aren't in
+/// the calendar (which configurations are checked for)
+fn start_of(date: &advisory::Date) -> SystemTime {
+    humantime::parse_rfc3339(&midnight(date)).unwrap_or(UNIX_EPOCH)
+}
+
+/// RFC 3339 timestamp of the start of the given day (in UTC)
+fn midnight(date: &advisory::Date) -> String {
+    format!("{}T00:00:00Z", date.as_str())
+}
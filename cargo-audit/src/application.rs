@@ -2,13 +2,18 @@
 //!
 //! <https://docs.rs/abscissa_core>
 
-use crate::{commands::CargoAuditCommand, config::AuditConfig};
+use crate::{
+    commands::{self, CargoAuditCommand},
+    config::{AuditConfig, Color},
+    logging,
+};
 use abscissa_core::{
     application::{self, AppCell},
     config,
-    terminal::ColorChoice,
-    trace, Application, EntryPoint, FrameworkError, StandardPaths,
+    terminal::{component::Terminal, ColorChoice},
+    trace, Application, Component, EntryPoint, FrameworkError, FrameworkErrorKind, StandardPaths,
 };
+use std::path::Path;
 
 /// Application state
 pub static APPLICATION: AppCell<CargoAuditApplication> = AppCell::new();
@@ -80,10 +85,24 @@ impl Application for CargoAuditApplication {
         &mut self.state
     }
 
+    /// Load `audit.toml` layered over the user's (see
+    /// [`commands::config_paths`]), locating any problem with them by file,
+    /// line and key
+    fn load_config(&mut self, _path: &Path) -> Result<AuditConfig, FrameworkError> {
+        AuditConfig::load_layers(&commands::config_paths())
+            .map_err(|e| FrameworkErrorKind::ConfigError.context(e).into())
+    }
+
     /// Register all components used by this application.
+    ///
+    /// Rather than the framework's default tracing component (which logs to
+    /// STDOUT), install a subscriber which logs to STDERR.
     fn register_components(&mut self, command: &Self::Cmd) -> Result<(), FrameworkError> {
-        let components = self.framework_components(command)?;
-        self.state.components.register(components)
+        let color_choice = self.term_colors(command);
+        logging::init(self.tracing_filter(command), color_choice)?;
+
+        let terminal: Box<dyn Component<Self>> = Box::new(Terminal::new(color_choice));
+        self.state.components.register(vec![terminal])
     }
 
     /// Post-configuration lifecycle callback.
@@ -99,16 +118,25 @@ impl Application for CargoAuditApplication {
         entrypoint
             .command
             .as_ref()
-            .and_then(|cmd| cmd.color_config())
-            .unwrap_or(ColorChoice::Auto)
+            .map(|cmd| cmd.color_config())
+            .unwrap_or_else(|| Color::default().choice())
     }
 
     /// Get tracing configuration from command-line options
     fn tracing_config(&self, command: &EntryPoint<CargoAuditCommand>) -> trace::Config {
-        if command.verbose {
-            trace::Config::verbose()
-        } else {
-            trace::Config::default()
-        }
+        self.tracing_filter(command).into()
+    }
+}
+
+impl CargoAuditApplication {
+    /// Get the log filter from `RUST_LOG` or the number of `-v` flags given
+    fn tracing_filter(&self, command: &EntryPoint<CargoAuditCommand>) -> String {
+        let verbosity = command
+            .command
+            .as_ref()
+            .map(|cmd| cmd.verbosity())
+            .unwrap_or(0);
+
+        logging::filter(verbosity + command.verbose as u32)
     }
 }
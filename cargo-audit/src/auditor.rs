@@ -1,106 +1,655 @@
 //! Core auditing functionality
+//!
+//! [`Auditor`] can also be used to embed audits in other tools: create one
+//! with [`Auditor::try_new`] (or [`Auditor::with_database`]), which never
+//! prints or exits, and audit lockfiles with [`Auditor::audit_lockfile`] or
+//! lists of packages with [`Auditor::audit_packages`]:
+//!
+//! ```no_run
+//! use cargo_audit::{auditor::Auditor, config::AuditConfig};
+//! use std::path::Path;
+//!
+//! // Configuration is the same as in `audit.toml` (e.g. to ignore advisories)
+//! let mut config = AuditConfig::default();
+//! config.epss.enabled = true;
+//!
+//! // Fetches the advisory database (unless `config.database.fetch` is false)
+//! let mut auditor = Auditor::try_new(&config)?;
+//! let report = auditor.audit_lockfile(Path::new("Cargo.lock"))?;
+//!
+//! for vulnerability in &report.vulnerabilities.list {
+//!     println!(
+//!         "{} {}: {}",
+//!         vulnerability.package.name, vulnerability.package.version, vulnerability.advisory.id
+//!     );
+//! }
+//! # Ok::<(), rustsec::Error>(())
+//! ```
 
-use crate::{config::AuditConfig, lockfile, prelude::*, presenter::Presenter};
-use rustsec::{error, lockfile::Lockfile, registry, report, warning, Warning};
+use crate::{
+    acknowledgments,
+    cache::{self, Cache},
+    cargo_config::{self, Replacement, ReplacementIndex},
+    config::{
+        AuditConfig, DatabaseBackend, DatabaseConfig, DenyOption, MaxAge, NetConfig, NotifyOn,
+        ResolvedSource, StalePolicy, WarnOption,
+    },
+    db_history, duplicates, epss, features, fix_commits, graph, index, installed, kev,
+    lockfile::{self, CARGO_LOCK_FILE, CARGO_TOML_FILE},
+    members, notify, output,
+    prelude::*,
+    presenter::Presenter,
+    progress::ProgressBar,
+    reachability,
+    reporter::ReportRenderer,
+    retry::{Failure, Retry},
+    sbom, scoped_ignores,
+    state::{self, State},
+    suppressions,
+    triage::Decision,
+    verify, vex,
+};
+use rustsec::{
+    advisory,
+    cargo_lock::dependency::{graph::EdgeDirection, Dependency, Tree},
+    error,
+    lockfile::Lockfile,
+    package::Package,
+    registry,
+    report::{self, DatabaseInfo, Outcome},
+    repository::signature::Keyring,
+    vulnerability::{self, Explanation, Resolution, Scope, Scopes, Upgrade},
+    warning, Warning,
+};
+use sha2::{Digest, Sha256};
 use std::{
-    collections::btree_map as map,
+    collections::{btree_map as map, BTreeMap as Map, BTreeSet as Set},
     io::{self, Read},
-    path::Path,
+    path::{Path, PathBuf},
     process::exit,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 
-/// Name of `Cargo.lock`
-const CARGO_LOCK_FILE: &str = "Cargo.lock";
-
 /// Security vulnerability auditor
 pub struct Auditor {
     /// RustSec Advisory Database
     database: rustsec::Database,
 
-    /// Crates.io registry index
+    /// Commit IDs of the advisory database's sources (if all are known)
+    database_commit: Option<String>,
+
+    /// Crates.io registry index (loaded on first use)
     registry_index: Option<registry::Index>,
 
-    /// Presenter for displaying the report
-    presenter: Presenter,
+    /// Registry replacing crates.io in cargo's configuration (resolved on
+    /// first use)
+    crates_io_replacement: Option<Option<Replacement>>,
+
+    /// Client for looking up releases in the sparse index, shared by the
+    /// yanked checks and patched release lookups (created on first use)
+    registry_client: Option<Option<index::Client>>,
+
+    /// Cache of previous audit reports (if enabled)
+    cache: Option<Cache>,
+
+    /// Configuration the auditor was initialized with
+    config: AuditConfig,
+
+    /// Presenter for displaying the report (if any)
+    presenter: Option<Presenter>,
 
     /// Audit report settings
     report_settings: report::Settings,
+
+    /// Generate `Cargo.lock` without prompting if it's missing
+    generate_lockfile: bool,
+
+    /// Avoid printing extraneous information (e.g. prompts)
+    quiet: bool,
+
+    /// How long each phase of the audit took
+    timings: Timings,
+
+    /// Dependency tree of the most recently audited lockfile
+    dependency_tree: Option<Arc<Tree>>,
+
+    /// Did delivering a report to a webhook fail when that was required?
+    notification_failed: bool,
+
+    /// Severity of the most severe vulnerability which failed the most
+    /// recent audit (if any had a known severity), for its exit status
+    failed_severity: Option<advisory::Severity>,
+
+    /// Kinds of the denied warnings the most recent audit found, for its
+    /// exit status
+    denied_warnings: Vec<warning::Kind>,
+
+    /// VEX documents suppressing vulnerabilities (loaded on first use)
+    vex_documents: Option<Vec<vex::Document>>,
 }
 
 impl Auditor {
-    /// Initialize the auditor
+    /// Initialize the auditor for the `cargo audit` command, which prints
+    /// status messages and reports, and exits if the advisory database can't
+    /// be loaded.
+    ///
+    /// To embed audits in other tools, use [`Auditor::try_new`] instead.
     pub fn new(config: &AuditConfig) -> Self {
-        let advisory_db_url = config
-            .database
-            .url
-            .as_ref()
-            .map(AsRef::as_ref)
-            .unwrap_or(rustsec::repository::git::DEFAULT_URL);
+        let mut timings = Timings::default();
+        let (database, database_commit) =
+            Self::load_database_timed(config, config.output.is_quiet(), &mut timings)
+                .unwrap_or_else(|e| {
+                    status_err!("{}", e);
+                    exit(config.output.exit_codes.code(database_outcome(&e)));
+                });
 
-        let advisory_db_path = config
-            .database
-            .path
-            .as_ref()
-            .cloned()
-            .unwrap_or_else(rustsec::repository::git::Repository::default_path);
+        let mut auditor = Self::with_database(database, config);
+        auditor.database_commit = database_commit;
+        auditor.presenter = Some(Presenter::new(&config.output));
+        auditor.quiet = config.output.is_quiet();
+        auditor.timings = timings;
+        auditor
+    }
 
-        let database = if config.database.fetch {
-            if !config.output.is_quiet() {
-                status_ok!("Fetching", "advisory database from `{}`", advisory_db_url);
-            }
+    /// Initialize an auditor for embedding in other tools, loading (and
+    /// unless configured not to, fetching) the advisory database.
+    ///
+    /// The auditor has no presenter: it never prints anything or exits, and
+    /// returns reports to the caller instead.
+    pub fn try_new(config: &AuditConfig) -> Result<Self, error::Error> {
+        let mut timings = Timings::default();
+        let (database, database_commit) = Self::load_database_timed(config, true, &mut timings)?;
 
-            let advisory_db_repo = rustsec::repository::git::Repository::fetch(
-                advisory_db_url,
-                &advisory_db_path,
-                !config.database.stale,
-            )
+        let mut auditor = Self::with_database(database, config);
+        auditor.database_commit = database_commit;
+        auditor.timings = timings;
+        Ok(auditor)
+    }
+
+    /// Initialize an auditor which uses the given advisory database (e.g. one
+    /// shared between several auditors). Like [`Auditor::try_new`], it has no
+    /// presenter.
+    pub fn with_database(database: rustsec::Database, config: &AuditConfig) -> Self {
+        Self {
+            database_commit: database
+                .latest_commit()
+                .map(|commit| commit.commit_id.clone()),
+            database,
+            registry_index: None,
+            crates_io_replacement: None,
+            registry_client: None,
+            cache: if config.cache.enabled {
+                Some(Cache::from_config(&config.cache))
+            } else {
+                None
+            },
+            config: config.clone(),
+            presenter: None,
+            report_settings: config.report_settings(),
+            generate_lockfile: config.lockfile.generate,
+            quiet: true,
+            timings: Timings::default(),
+            dependency_tree: None,
+            notification_failed: false,
+            failed_severity: None,
+            denied_warnings: vec![],
+            vex_documents: None,
+        }
+    }
+
+    /// Advisory database used by the auditor
+    pub fn database(&self) -> &rustsec::Database {
+        &self.database
+    }
+
+    /// Load the advisory database again (fetching it first unless
+    /// configured not to), returning whether it changed. Databases whose
+    /// commits aren't known are assumed to have changed.
+    pub fn reload_database(&mut self) -> Result<bool, error::Error> {
+        let (database, commit) =
+            Self::load_database_timed(&self.config, self.quiet, &mut self.timings)?;
+        let changed = commit.is_none() || commit != self.database_commit;
+
+        self.database = database;
+        self.database_commit = commit;
+        Ok(changed)
+    }
+
+    /// Render reports with the given renderer rather than the configured
+    /// output format (if the auditor prints reports at all)
+    pub fn set_renderer(&mut self, renderer: Box<dyn ReportRenderer>) {
+        if let Some(presenter) = &mut self.presenter {
+            presenter.set_renderer(renderer);
+        }
+    }
+
+    /// Stop printing reports, returning the presenter (if any), e.g. to print
+    /// the differences between reports with it instead
+    pub fn take_presenter(&mut self) -> Option<Presenter> {
+        self.presenter.take()
+    }
+
+    /// Load the advisory database, fetching it first unless configured not
+    /// to, and exiting if that fails
+    pub fn load_database(config: &AuditConfig) -> rustsec::Database {
+        Self::load_database_timed(config, config.output.is_quiet(), &mut Timings::default())
+            .map(|(database, _)| database)
             .unwrap_or_else(|e| {
-                status_err!("couldn't fetch advisory database: {}", e);
-                exit(1);
+                status_err!("{}", e);
+                exit(config.output.exit_codes.code(database_outcome(&e)));
+            })
+    }
+
+    /// Load the advisory database (merging every configured source into
+    /// it), recording how long each step took. Also returns the commit IDs of
+    /// the sources, if they're all known.
+    fn load_database_timed(
+        config: &AuditConfig,
+        quiet: bool,
+        timings: &mut Timings,
+    ) -> Result<(rustsec::Database, Option<String>), error::Error> {
+        let sources = config.database.resolve_sources()?;
+        let keyring = Self::load_keyring(&config.database)?;
+        let mut database: Option<rustsec::Database> = None;
+        let mut commits = Some(vec![]);
+
+        for source in &sources {
+            let mut loaded =
+                Self::load_source(source, &config.net, keyring.as_ref(), quiet, timings)?;
+
+            // Databases as of a past date are old on purpose
+            loaded = match &config.database.as_of {
+                Some(date) => Self::database_as_of(source, loaded, date, quiet)?,
+                None => {
+                    Self::enforce_max_age(source, loaded, config, keyring.as_ref(), quiet, timings)?
+                }
+            };
+
+            // Findings only say which source their advisory came from when
+            // there's more than one
+            if sources.len() > 1 {
+                loaded.set_source(&source.label());
+            }
+
+            commits = match (commits, loaded.latest_commit()) {
+                (Some(mut commits), Some(commit)) => {
+                    commits.push(commit.commit_id.clone());
+                    Some(commits)
+                }
+                _ => None,
+            };
+
+            database = Some(match database {
+                Some(mut database) => {
+                    let skipped = database.merge(loaded);
+
+                    if skipped > 0 {
+                        tracing::debug!(
+                            "skipped {} advisories from {} already in other sources",
+                            skipped,
+                            source.path.display()
+                        );
+                    }
+
+                    database
+                }
+                None => loaded,
             });
+        }
 
-            rustsec::Database::load_from_repo(&advisory_db_repo).unwrap_or_else(|e| {
-                status_err!("error loading advisory database: {}", e);
-                exit(1);
-            })
-        } else {
-            rustsec::Database::open(&advisory_db_path).unwrap_or_else(|e| {
-                status_err!("error loading advisory database: {}", e);
-                exit(1);
-            })
-        };
+        let database = database.ok_or_else(|| {
+            error::Error::new(
+                error::ErrorKind::BadParam,
+                &"no advisory database sources to load",
+            )
+        })?;
+
+        if !quiet {
+            let paths = sources
+                .iter()
+                .map(|source| source.path.display().to_string())
+                .collect::<Vec<_>>();
 
-        if !config.output.is_quiet() {
             status_ok!(
                 "Loaded",
                 "{} security advisories (from {})",
                 database.iter().count(),
-                advisory_db_path.display()
+                paths.join(", ")
+            );
+        }
+
+        Ok((database, commits.map(|commits| commits.join("+"))))
+    }
+
+    /// Apply the `on-stale` policy to a database source whose latest commit
+    /// is older than `max-age`, returning the database to audit against.
+    /// Databases without git history can't be dated, so they never are.
+    fn enforce_max_age(
+        source: &ResolvedSource,
+        database: rustsec::Database,
+        config: &AuditConfig,
+        keyring: Option<&Keyring>,
+        quiet: bool,
+        timings: &mut Timings,
+    ) -> Result<rustsec::Database, error::Error> {
+        let max_age = match config.database.max_age {
+            Some(max_age) => max_age,
+            None => return Ok(database),
+        };
+
+        let problem = match stale_database(source, &database, max_age) {
+            Some(problem) => problem,
+            None => return Ok(database),
+        };
+
+        let refetch = match config.database.on_stale {
+            StalePolicy::Warn => false,
+            StalePolicy::Error => {
+                return Err(error::Error::new(error::ErrorKind::Stale, &problem));
+            }
+            StalePolicy::Refetch => {
+                // Databases which were just fetched wouldn't be any newer
+                !source.fetch && !config.net.offline && source.backend != DatabaseBackend::Local
+            }
+        };
+
+        if !refetch {
+            if !quiet {
+                status_warn!("{}", problem);
+            }
+
+            return Ok(database);
+        }
+
+        tracing::debug!("fetching stale {}", source.label());
+
+        let source = ResolvedSource {
+            fetch: true,
+            ..source.clone()
+        };
+        let database = Self::load_source(&source, &config.net, keyring, quiet, timings)?;
+
+        if let Some(problem) = stale_database(&source, &database, max_age) {
+            if !quiet {
+                status_warn!("{}", problem);
+            }
+        }
+
+        Ok(database)
+    }
+
+    /// Load the keys which fetched databases must be signed by, if they're to
+    /// be verified
+    fn load_keyring(config: &DatabaseConfig) -> Result<Option<Keyring>, error::Error> {
+        if !config.verify {
+            return Ok(None);
+        }
+
+        if config.signing_keys.is_empty() {
+            return Err(error::Error::new(
+                error::ErrorKind::BadParam,
+                &"can't verify the advisory database: no `signing_keys` are configured",
+            ));
+        }
+
+        Keyring::load(&config.signing_keys).map(Some)
+    }
+
+    /// Load a single advisory database source, fetching it first unless
+    /// configured not to (or offline). When given a keyring, fetched git
+    /// repositories must be signed by one of its keys.
+    fn load_source(
+        source: &ResolvedSource,
+        net: &NetConfig,
+        keyring: Option<&Keyring>,
+        quiet: bool,
+        timings: &mut Timings,
+    ) -> Result<rustsec::Database, error::Error> {
+        let name = match &source.name {
+            Some(name) => format!("advisory database `{}`", name),
+            None => "advisory database".to_owned(),
+        };
+
+        let database = if source.fetch && !net.offline {
+            let url = source.url.as_ref().ok_or_else(|| {
+                error::Error::new(
+                    error::ErrorKind::BadParam,
+                    &format!("couldn't fetch {}: no `url` is configured", name),
+                )
+            })?;
+
+            if !quiet {
+                status_ok!("Fetching", "{} from `{}`", name, url);
+            }
+
+            let started = Instant::now();
+            let retry = Retry::from_config(net);
+            let mut progress_bar = ProgressBar::new(quiet);
+            let fetch_error = |e: error::Error| {
+                error::Error::new(e.kind(), &format!("couldn't fetch {}: {}", name, e.msg()))
+            };
+
+            // Snapshots are plain directories, without a git repository
+            let advisory_db_repo = match source.backend {
+                DatabaseBackend::Tarball if keyring.is_some() => {
+                    return Err(fetch_error(error::Error::new(
+                        error::ErrorKind::BadParam,
+                        &"tarball snapshots can't be verified (use the `git` backend)",
+                    )));
+                }
+                DatabaseBackend::Tarball => {
+                    let snapshot = retry
+                        .run(&format!("fetching {}", name), || {
+                            let mut options = net
+                                .fetch_options()
+                                .progress(|progress| progress_bar.update(progress));
+
+                            rustsec::repository::tarball::Snapshot::fetch_with(
+                                url,
+                                &source.path,
+                                &mut options,
+                            )
+                            .map_err(Failure::git)
+                        })
+                        .map_err(fetch_error)?;
+
+                    progress_bar.finish();
+
+                    tracing::debug!(
+                        "fetched {} in {}ms ({})",
+                        name,
+                        timings.record("fetch", started),
+                        if snapshot.was_updated() {
+                            "downloaded"
+                        } else {
+                            "not modified"
+                        }
+                    );
+
+                    None
+                }
+                _ => {
+                    let repo = retry
+                        .run(&format!("fetching {}", name), || {
+                            let mut options = net
+                                .fetch_options()
+                                .progress(|progress| progress_bar.update(progress));
+
+                            match keyring {
+                                Some(keyring) => {
+                                    rustsec::repository::git::Repository::fetch_verified_with(
+                                        url,
+                                        &source.path,
+                                        !source.stale,
+                                        keyring,
+                                        &mut options,
+                                    )
+                                }
+                                None => rustsec::repository::git::Repository::fetch_with(
+                                    url,
+                                    &source.path,
+                                    !source.stale,
+                                    &mut options,
+                                ),
+                            }
+                            .map_err(Failure::git)
+                        })
+                        .map_err(fetch_error)?;
+
+                    progress_bar.finish();
+
+                    tracing::debug!("fetched {} in {}ms", name, timings.record("fetch", started));
+                    Some(repo)
+                }
+            };
+
+            let started = Instant::now();
+            let database = match &advisory_db_repo {
+                Some(repo) => rustsec::Database::load_from_repo(repo),
+                None => rustsec::Database::open(&source.path),
+            }
+            .map_err(load_database_error)?;
+
+            tracing::debug!(
+                "parsed {} advisories in {}ms",
+                database.iter().count(),
+                timings.record("parse", started)
+            );
+
+            database
+        } else if source.path.is_file() {
+            let started = Instant::now();
+            let path = unpack_bundle(&source.path).map_err(load_database_error)?;
+            let database = rustsec::Database::open(&path).map_err(load_database_error)?;
+
+            tracing::debug!(
+                "parsed {} advisories from bundle {} in {}ms",
+                database.iter().count(),
+                source.path.display(),
+                timings.record("parse", started)
+            );
+
+            database
+        } else {
+            let started = Instant::now();
+
+            // Load via the git repository when possible, so the database's
+            // latest commit is known (e.g. for keying cached reports)
+            let database = match rustsec::repository::git::Repository::open(&source.path) {
+                Ok(repo) => rustsec::Database::load_from_repo(&repo),
+                Err(e) => {
+                    tracing::trace!("opening {} without git: {}", name, e);
+                    rustsec::Database::open(&source.path)
+                }
+            }
+            .map_err(load_database_error)?;
+
+            tracing::debug!(
+                "parsed {} advisories in {}ms",
+                database.iter().count(),
+                timings.record("parse", started)
+            );
+
+            database
+        };
+
+        Ok(database)
+    }
+
+    /// The advisory database of a source as it was on the given date: as of
+    /// its last commit on or before it, when it's a git repository, or else
+    /// only the advisories issued by then
+    fn database_as_of(
+        source: &ResolvedSource,
+        database: rustsec::Database,
+        date: &advisory::Date,
+        quiet: bool,
+    ) -> Result<rustsec::Database, error::Error> {
+        let repo = match rustsec::repository::git::Repository::open(&source.path) {
+            Ok(repo) => repo,
+            Err(e) => {
+                tracing::debug!("{} has no git history: {}", source.path.display(), e);
+
+                if !quiet {
+                    status_warn!(
+                        "{} has no git history, so only advisories issued by {} are used \
+                         (including ones changed since)",
+                        source.path.display(),
+                        date.as_str()
+                    );
+                }
+
+                let mut database = database;
+                database.retain(|advisory| advisory.date() <= date);
+                return Ok(database);
+            }
+        };
+
+        let commit = repo
+            .commit_as_of(date)
+            .map_err(load_database_error)?
+            .ok_or_else(|| {
+                error::Error::new(
+                    error::ErrorKind::NotFound,
+                    &format!(
+                        "{} has no commits on or before {} (is it a shallow clone?)",
+                        source.path.display(),
+                        date.as_str()
+                    ),
+                )
+            })?;
+
+        if !quiet {
+            status_ok!(
+                "Rewinding",
+                "{} to commit {}, the last one on or before {}",
+                source.path.display(),
+                &commit.commit_id[..commit.commit_id.len().min(7)],
+                date.as_str()
             );
         }
 
+        rustsec::Database::load_from_commit(&repo, commit).map_err(load_database_error)
+    }
+
+    /// Open the git index replacing crates.io (fetching it first if
+    /// configured to) when auditing for yanked crates
+    fn load_registry_index(
+        config: &AuditConfig,
+        quiet: bool,
+        timings: &mut Timings,
+        url: &str,
+    ) -> Option<registry::Index> {
+        let started = Instant::now();
+        let name = format!("index {}", url);
         let registry_index = if config.yanked.enabled {
-            if config.yanked.update_index && config.database.fetch {
-                if !config.output.is_quiet() {
-                    status_ok!("Updating", "crates.io index");
+            if config.yanked.update_index && config.database.fetch && !config.net.offline {
+                if !quiet {
+                    status_ok!("Updating", "{}", name);
                 }
 
-                match registry::Index::fetch() {
+                let fetched = Retry::from_config(&config.net)
+                    .run(&format!("updating the {}", name), || {
+                        registry::Index::fetch_url(url).map_err(Failure::git)
+                    });
+
+                match fetched {
                     Ok(index) => Some(index),
                     Err(err) => {
-                        if !config.output.is_quiet() {
-                            status_warn!("couldn't update crates.io index: {}", err);
+                        if !quiet {
+                            status_warn!("couldn't update {}: {}", name, err);
                         }
 
                         None
                     }
                 }
             } else {
-                match registry::Index::open() {
+                match registry::Index::open_url(url) {
                     Ok(index) => Some(index),
                     Err(err) => {
-                        if !config.output.is_quiet() {
-                            status_warn!("couldn't open crates.io index: {}", err);
+                        if !quiet {
+                            status_warn!("couldn't open {}: {}", name, err);
                         }
 
                         None
@@ -111,103 +660,2254 @@ impl Auditor {
             None
         };
 
-        Self {
-            database,
-            registry_index,
-            presenter: Presenter::new(&config.output),
-            report_settings: config.report_settings(),
+        if registry_index.is_some() {
+            tracing::debug!("loaded {} in {}ms", name, timings.record("index", started));
+        }
+
+        registry_index
+    }
+
+    /// Get the registry replacing crates.io in cargo's configuration (if
+    /// any), resolving it on first use.
+    ///
+    /// Configuration which can't be loaded only results in a warning, and
+    /// lookups going to crates.io.
+    fn crates_io_replacement(&mut self) -> Option<Replacement> {
+        if self.crates_io_replacement.is_none() {
+            let replacement = match cargo_config::crates_io_replacement(self.config.registry.debug)
+            {
+                Ok(replacement) => replacement,
+                Err(e) => {
+                    if !self.quiet {
+                        status_warn!("couldn't load cargo's source replacement: {}", e);
+                    }
+
+                    None
+                }
+            };
+
+            self.crates_io_replacement = Some(replacement);
+        }
+
+        self.crates_io_replacement.clone().flatten()
+    }
+
+    /// Get the client for looking up releases in the sparse index (creating
+    /// it on first use): the one configured in `audit.toml`, or the one
+    /// replacing crates.io in cargo's configuration, or crates.io's. In
+    /// offline mode, it only serves cached releases.
+    ///
+    /// Returns `None` if crates.io is replaced with a git index or a local
+    /// source, which have no sparse index to look releases up in.
+    fn registry_client(&mut self) -> Option<index::Client> {
+        if self.registry_client.is_none() {
+            let replacement = if self.config.registry.url.is_some() {
+                None
+            } else {
+                self.crates_io_replacement()
+            };
+
+            self.registry_client = Some(index::Client::for_crates_io(&self.config, replacement));
+        }
+
+        self.registry_client.clone().flatten()
+    }
+
+    /// Log the counters of the registry client (in verbose mode), if any
+    /// lookups were made
+    fn log_registry_stats(&self) {
+        if let Some(Some(client)) = &self.registry_client {
+            let stats = client.stats();
+            tracing::debug!(
+                "registry lookups: {} requests ({} revalidated), {} cache hits",
+                stats.requests,
+                stats.revalidated,
+                stats.cache_hits
+            );
         }
     }
 
-    /// Perform audit
+    /// Audit the lockfile at the given path (default: `Cargo.lock`, or `-`
+    /// for STDIN), printing the report if the auditor has a presenter
     pub fn audit(
         &mut self,
         maybe_lockfile_path: Option<&Path>,
     ) -> Result<rustsec::Report, error::Error> {
-        let lockfile_path = match maybe_lockfile_path {
-            Some(p) => p,
-            None => {
-                let path = Path::new(CARGO_LOCK_FILE);
-                if !path.exists() && Path::new("Cargo.toml").exists() {
-                    lockfile::generate()?;
-                }
-                path
+        let lockfile_path = maybe_lockfile_path.unwrap_or_else(|| Path::new(CARGO_LOCK_FILE));
+
+        if lockfile_path != Path::new("-") && !lockfile_path.exists() {
+            let manifest_path = lockfile_path.with_file_name(CARGO_TOML_FILE);
+
+            if manifest_path.exists() {
+                self.generate_lockfile(lockfile_path, &manifest_path)?;
             }
+        } else if lockfile_path != Path::new("-") {
+            self.check_up_to_date(lockfile_path)?;
+        }
+
+        let started = Instant::now();
+        let lockfile_toml = self.read_lockfile(lockfile_path)?;
+        let mut lockfile: Lockfile = lockfile_toml.parse().map_err(|e| {
+            error::Error::new(
+                error::ErrorKind::NotFound,
+                &format!("Couldn't load {}: {}", lockfile_path.display(), e),
+            )
+        })?;
+
+        tracing::debug!(
+            "loaded lockfile: {} packages in {}ms",
+            lockfile.packages.len(),
+            self.timings.record("lockfile", started)
+        );
+
+        // Packages which aren't compiled with the selected features are
+        // pruned, and the report cached for what remains, unless their
+        // vulnerabilities are to be reported as warnings instead
+        let mut pruned = 0;
+        let optional = if self.config.features.feature_aware {
+            Some(self.resolve_optional(lockfile_path)?)
+        } else {
+            None
+        };
+
+        let lockfile_toml = if optional.is_none() && self.config.features.is_selected() {
+            pruned = self.prune_features(&mut lockfile, lockfile_path)?;
+            lockfile.to_string()
+        } else {
+            lockfile_toml
+        };
+
+        let scopes = if self.config.output.fail_policy().is_some() {
+            Some(self.resolve_scopes(lockfile_path)?)
+        } else {
+            None
         };
 
-        let lockfile = match self.load_lockfile(lockfile_path) {
-            Ok(l) => l,
+        // Build the dependency tree once, for use by every later phase.
+        // If the lockfile's dependency graph is invalid, the report is still
+        // generated and printed (without trees) before returning the error.
+        let started = Instant::now();
+        let (tree, tree_error) = match lockfile.dependency_tree() {
+            Ok(tree) => {
+                tracing::debug!(
+                    "built dependency graph: {} nodes, {} edges in {}ms",
+                    tree.graph().node_count(),
+                    tree.graph().edge_count(),
+                    self.timings.record("graph", started)
+                );
+
+                (Some(Arc::new(tree)), None)
+            }
             Err(e) => {
-                return Err(error::Error::new(
-                    error::ErrorKind::NotFound,
-                    &format!("Couldn't load {}: {}", lockfile_path.display(), e),
-                ))
+                let error = error::Error::new(
+                    error::ErrorKind::Parse,
+                    &format!(
+                        "invalid dependency tree in {}: {}",
+                        lockfile_path.display(),
+                        e.msg()
+                    ),
+                );
+
+                (None, Some(error))
             }
         };
 
-        self.presenter.before_report(&lockfile_path, &lockfile);
+        self.dependency_tree = tree.clone();
 
-        let mut report =
-            rustsec::Report::generate(&self.database, &lockfile, &self.report_settings);
+        let cache_key = self.cache_key(&lockfile_toml);
+        let cached = match (&self.cache, &cache_key) {
+            (Some(cache), Some(key)) => cache.get(lockfile_path, key),
+            _ => None,
+        };
 
-        // Warn for yanked crates
-        // TODO(tarcieri): move this logic into the `rustsec` crate?
-        if let Some(index) = &self.registry_index {
-            for package in &lockfile.packages {
-                if let Ok(pkg) = index.find(&package.name, &package.version) {
-                    if pkg.is_yanked {
-                        let warning = Warning::new(warning::Kind::Yanked, package, None, None);
-                        match report.warnings.entry(warning::Kind::Yanked) {
-                            map::Entry::Occupied(entry) => (*entry.into_mut()).push(warning),
-                            map::Entry::Vacant(entry) => {
-                                entry.insert(vec![warning]);
-                            }
+        if let Some(presenter) = &mut self.presenter {
+            presenter.before_report(
+                lockfile_path,
+                &lockfile,
+                &DatabaseInfo::new(&self.database),
+                cached.is_some(),
+            );
+        }
+
+        let (mut report, self_advisories) = match cached {
+            Some(entry) => {
+                tracing::debug!(
+                    "replaying cached report for {} (advisory database at {})",
+                    lockfile_path.display(),
+                    entry.key.database_commit
+                );
+
+                (entry.report, entry.self_advisories)
+            }
+            None => {
+                let report = self.match_report(&lockfile, Some(lockfile_path));
+                let self_advisories = self.self_advisories();
+
+                // Scores are cached separately (with their own TTL), so cached
+                // reports never include them
+                if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                    let entry = cache::Entry {
+                        key,
+                        report: report.clone(),
+                        self_advisories: self_advisories.clone(),
+                    };
+
+                    if let Err(e) = cache.insert(lockfile_path, &entry) {
+                        if !self.quiet {
+                            status_warn!(
+                                "couldn't cache report in {}: {}",
+                                cache.dir().display(),
+                                e
+                            );
                         }
                     }
                 }
+
+                (report, self_advisories)
             }
+        };
+
+        let changes = self.process_report(
+            &mut report,
+            &Audited {
+                lockfile: &lockfile,
+                lockfile_path: Some(lockfile_path),
+                tree: tree.as_deref(),
+                optional: optional.as_ref(),
+                scopes: scopes.as_ref(),
+                pruned,
+                self_advisories: &self_advisories,
+                record: true,
+            },
+        )?;
+
+        let graph_error = self.present(
+            &report,
+            &lockfile,
+            &self_advisories,
+            tree.as_deref(),
+            changes,
+        )?;
+
+        match tree_error.or(graph_error) {
+            Some(e) => Err(e),
+            None => Ok(report),
         }
+    }
 
-        let self_advisories = self.self_advisories();
+    /// Audit the lockfile at the given path, using the report cache (if
+    /// enabled) and adding any enabled enrichments (e.g. EPSS scores).
+    ///
+    /// If the lockfile's dependency graph is invalid, an error is returned.
+    pub fn audit_lockfile(
+        &mut self,
+        lockfile_path: &Path,
+    ) -> Result<rustsec::Report, error::Error> {
+        self.audit(Some(lockfile_path))
+    }
 
-        self.presenter
-            .print_report(&report, self_advisories.as_slice(), &lockfile);
+    /// Audit the given packages (e.g. from a lockfile loaded by the caller, or
+    /// a software bill of materials), as if they were in a lockfile
+    pub fn audit_packages(&mut self, packages: &[Package]) -> rustsec::Report {
+        let lockfile = Lockfile {
+            version: Default::default(),
+            packages: packages.to_vec(),
+            root: None,
+            metadata: Default::default(),
+            patch: Default::default(),
+        };
 
-        Ok(report)
+        self.generate_report(&lockfile)
     }
 
-    /// Load the lockfile to be audited
-    fn load_lockfile(&self, lockfile_path: &Path) -> Result<Lockfile, error::Error> {
-        if lockfile_path == Path::new("-") {
-            // Read Cargo.lock from STDIN
-            let mut lockfile_toml = String::new();
-            io::stdin().read_to_string(&mut lockfile_toml)?;
-            Ok(lockfile_toml.parse()?)
-        } else {
-            Ok(Lockfile::load(lockfile_path)?)
+    /// Audit the packages a binary was built from, as embedded in it by
+    /// cargo-auditable, printing the report (along with the dependency tree
+    /// the audit data records) if the auditor has a presenter
+    pub fn audit_binary(&mut self, binary_path: &Path) -> Result<rustsec::Report, error::Error> {
+        let lockfile = installed::read_binary(binary_path)?;
+        self.audit_listed(binary_path, &lockfile)
+    }
+
+    /// Audit the crates listed in a CycloneDX SBOM, printing the report
+    /// (along with the dependency tree from the SBOM's dependency graph) if
+    /// the auditor has a presenter
+    pub fn audit_sbom(&mut self, sbom_path: &Path) -> Result<rustsec::Report, error::Error> {
+        let lockfile = sbom::read(sbom_path)?;
+        self.audit_listed(sbom_path, &lockfile)
+    }
+
+    /// Audit the dependency graph in the output of `cargo metadata` at the
+    /// given path (or `-` for STDIN), printing the report if the auditor has
+    /// a presenter
+    pub fn audit_metadata(
+        &mut self,
+        metadata_path: &Path,
+    ) -> Result<rustsec::Report, error::Error> {
+        let lockfile = features::read_lockfile(metadata_path)?;
+        self.audit_listed(metadata_path, &lockfile)
+    }
+
+    /// Audit packages listed somewhere other than a lockfile (e.g. a binary
+    /// or an SBOM at the given path), printing the report if the auditor has
+    /// a presenter
+    fn audit_listed(
+        &mut self,
+        path: &Path,
+        lockfile: &Lockfile,
+    ) -> Result<rustsec::Report, error::Error> {
+        if let Some(presenter) = &mut self.presenter {
+            presenter.before_report(path, lockfile, &DatabaseInfo::new(&self.database), false);
+        }
+
+        let tree = lockfile.dependency_tree().ok().map(Arc::new);
+        self.dependency_tree = tree.clone();
+
+        let mut report = self.match_report(lockfile, None);
+        let self_advisories = self.self_advisories();
+
+        // Everything listed is compiled in, so every dependency counts as a
+        // normal one for the fail-on policy
+        let changes = self.process_report(
+            &mut report,
+            &Audited {
+                lockfile,
+                lockfile_path: None,
+                tree: tree.as_deref(),
+                optional: None,
+                scopes: Some(&Map::new()),
+                pruned: 0,
+                self_advisories: &self_advisories,
+                record: true,
+            },
+        )?;
+
+        match self.present(
+            &report,
+            lockfile,
+            &self_advisories,
+            tree.as_deref(),
+            changes,
+        )? {
+            Some(e) => Err(e),
+            None => Ok(report),
         }
     }
 
-    /// Query the database for advisories about `cargo-audit` or `rustsec` itself
-    fn self_advisories(&mut self) -> Vec<rustsec::Advisory> {
-        let mut results = vec![];
+    /// Generate a report for the given lockfile without printing it,
+    /// including yanked crate warnings and any enabled enrichments (e.g.
+    /// EPSS scores).
+    ///
+    /// Such reports (e.g. of past versions of a lockfile) aren't recorded
+    /// in the state file or sent to webhooks, and problems enriching them
+    /// only result in warnings.
+    pub fn generate_report(&mut self, lockfile: &Lockfile) -> rustsec::Report {
+        let mut report = self.match_report(lockfile, None);
+        let tree = lockfile.dependency_tree().ok();
 
-        for (package_str, version_str) in &[
-            ("cargo-audit", crate::VERSION),
-            ("rustsec", rustsec::VERSION),
-        ] {
-            let package: rustsec::package::Name = package_str.parse().unwrap();
-            let version: rustsec::Version = version_str.parse().unwrap();
-            let query = rustsec::database::Query::crate_scope();
+        let processed = self.process_report(
+            &mut report,
+            &Audited {
+                lockfile,
+                lockfile_path: None,
+                tree: tree.as_ref(),
+                optional: None,
+                scopes: Some(&Map::new()),
+                pruned: 0,
+                self_advisories: &[],
+                record: false,
+            },
+        );
 
-            for advisory in self
-                .database
-                .query(&query.package_version(package, version))
-            {
-                results.push(advisory.clone());
+        if let Err(e) = processed {
+            if !self.quiet {
+                status_warn!("{}", e);
             }
         }
 
-        results
+        report
+    }
+
+    /// Match the lockfile's packages against the advisory database, adding
+    /// yanked crate warnings and checking fixes' commits
+    fn match_report(
+        &mut self,
+        lockfile: &Lockfile,
+        lockfile_path: Option<&Path>,
+    ) -> rustsec::Report {
+        let started = Instant::now();
+        let mut report = rustsec::Report::generate(&self.database, lockfile, &self.report_settings);
+
+        tracing::debug!(
+            "matched vulnerabilities in {}ms ({} found)",
+            self.timings.record("match", started),
+            report.vulnerabilities.count
+        );
+
+        self.add_yanked_warnings(&mut report, lockfile);
+        self.check_fix_commits(&mut report);
+
+        if let Some(path) = lockfile_path.filter(|path| *path != Path::new("-")) {
+            report.lockfile.path = Some(path.to_owned());
+        }
+
+        report
+    }
+
+    /// Post-process a report, however it was generated (or replayed from the
+    /// cache): add the enrichments, summarize it, apply the fail-on policy,
+    /// then (if the audit is recorded) compare its findings to the state
+    /// file's and send it to webhooks, and finally decide its outcome
+    fn process_report(
+        &mut self,
+        report: &mut rustsec::Report,
+        audited: &Audited<'_>,
+    ) -> Result<Option<state::Changes>, error::Error> {
+        let lockfile = audited.lockfile;
+        let tree = audited.tree;
+
+        add_optional_warnings(report, audited.optional);
+        self.add_duplicate_warnings(report, lockfile, tree);
+        self.add_affected_members(report, audited.lockfile_path, tree);
+        self.apply_scoped_ignores(report, tree);
+        self.add_known_exploited(report, lockfile);
+        self.add_epss_scores(report);
+        self.add_first_published(report);
+
+        if let Some(lockfile_path) = audited.lockfile_path {
+            self.add_reachability(report, lockfile_path);
+        }
+
+        self.add_patched_releases(report);
+
+        if let Some(lockfile_path) = audited.lockfile_path {
+            self.add_resolutions(report, lockfile_path);
+        }
+
+        self.add_acknowledgments(report, &state::today());
+        self.add_vex_statements(report, lockfile)?;
+        self.add_inventory(report, lockfile, audited.pruned);
+        summarize(report, tree);
+        self.apply_fail_on(report, audited.scopes);
+
+        let changes = if audited.record {
+            let changes = self.track_state(report)?;
+            self.notify(report, changes.as_ref());
+            changes
+        } else {
+            None
+        };
+
+        report.outcome = Some(self.outcome(report, audited.self_advisories, changes.as_ref()));
+        Ok(changes)
+    }
+
+    /// Write the dependency graph (if configured) and print the processed
+    /// report (if the auditor has a presenter), returning any error writing
+    /// the graph, which is only reported after the report is printed
+    fn present(
+        &mut self,
+        report: &rustsec::Report,
+        lockfile: &Lockfile,
+        self_advisories: &[rustsec::Advisory],
+        tree: Option<&Tree>,
+        changes: Option<state::Changes>,
+    ) -> Result<Option<error::Error>, error::Error> {
+        let graph_error = self.emit_graph(report, tree).err();
+        self.timings.log_summary();
+        self.log_registry_stats();
+        let exit_code = self.exit_code(report);
+        let suppressions = self.suppressions(lockfile);
+
+        if let Some(presenter) = &mut self.presenter {
+            presenter.show_changes(changes);
+            presenter.show_suppressions(suppressions);
+            presenter.set_exit_code(exit_code);
+            presenter.print_report(report, self_advisories, tree)?;
+        }
+
+        Ok(graph_error)
+    }
+
+    /// Explain how each of the packages in the lockfile at the given path
+    /// (default: `Cargo.lock`) that the given advisory is about was matched
+    /// against it, printing the explanations if the auditor has a presenter
+    pub fn explain(
+        &mut self,
+        id: &advisory::Id,
+        maybe_lockfile_path: Option<&Path>,
+    ) -> Result<Vec<(Package, Explanation)>, error::Error> {
+        let lockfile_path = maybe_lockfile_path.unwrap_or_else(|| Path::new(CARGO_LOCK_FILE));
+
+        let advisory = self.database.get(id).cloned().ok_or_else(|| {
+            error::Error::new(
+                error::ErrorKind::NotFound,
+                &format!("no advisory {} in the advisory database", id),
+            )
+        })?;
+
+        let lockfile: Lockfile = self.read_lockfile(lockfile_path)?.parse().map_err(|e| {
+            error::Error::new(
+                error::ErrorKind::NotFound,
+                &format!("Couldn't load {}: {}", lockfile_path.display(), e),
+            )
+        })?;
+
+        let explanations = lockfile
+            .packages
+            .iter()
+            .filter(|package| package.name == advisory.metadata.package)
+            .map(|package| {
+                (
+                    package.clone(),
+                    vulnerability::explain(&advisory, &package.version),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(presenter) = &mut self.presenter {
+            presenter.print_explanations(&advisory, lockfile_path, &explanations)?;
+        }
+
+        Ok(explanations)
+    }
+
+    /// Warn for yanked crates (if enabled), looking them up wherever cargo's
+    /// source replacement configuration says crates.io packages come from
+    fn add_yanked_warnings(&mut self, report: &mut rustsec::Report, lockfile: &Lockfile) {
+        if !self.config.yanked.enabled {
+            return;
+        }
+
+        // Only crates.io packages can be yanked from the crates.io index, and
+        // e.g. a git fork may share its name and version with a yanked release
+        let crates_io_packages: Vec<&Package> = lockfile
+            .packages
+            .iter()
+            .filter(|package| {
+                package
+                    .source
+                    .iter()
+                    .any(|source| source.is_default_registry())
+            })
+            .collect();
+
+        let started = Instant::now();
+        let yanked = match self
+            .crates_io_replacement()
+            .map(|replacement| replacement.index)
+        {
+            Some(ReplacementIndex::Sparse(_)) => self.find_yanked_sparse(&crates_io_packages),
+            Some(ReplacementIndex::Local(path)) => {
+                if !self.quiet {
+                    status_warn!(
+                        "not checking for yanked crates, as crates.io is replaced with local source {}",
+                        path.display()
+                    );
+                }
+
+                return;
+            }
+            Some(ReplacementIndex::Git(url)) => self.find_yanked_git(&crates_io_packages, &url),
+            None => self.find_yanked_sparse(&crates_io_packages),
+        };
+
+        let yanked = match yanked {
+            Some(yanked) => yanked,
+            None => return,
+        };
+
+        // TODO(tarcieri): move this logic into the `rustsec` crate?
+        for package in yanked {
+            let warning = Warning::new(warning::Kind::Yanked, package, None, None);
+            match report.warnings.entry(warning::Kind::Yanked) {
+                map::Entry::Occupied(entry) => (*entry.into_mut()).push(warning),
+                map::Entry::Vacant(entry) => {
+                    entry.insert(vec![warning]);
+                }
+            }
+        }
+
+        tracing::debug!(
+            "checked {} packages for yanked releases in {}ms",
+            lockfile.packages.len(),
+            self.timings.record("yanked", started)
+        );
+
+        // Keep yanked warnings in the same order as the others
+        report.sort();
+    }
+
+    /// Find the yanked packages in the git index at the given URL replacing
+    /// crates.io (`None` if the index can't be loaded)
+    fn find_yanked_git<'a>(
+        &mut self,
+        packages: &[&'a Package],
+        index_url: &str,
+    ) -> Option<Vec<&'a Package>> {
+        if self.registry_index.is_none() {
+            self.registry_index =
+                Self::load_registry_index(&self.config, self.quiet, &mut self.timings, index_url);
+        }
+
+        let index = self.registry_index.as_ref()?;
+        let mut yanked = vec![];
+
+        for package in packages {
+            if self.config.registry.debug {
+                tracing::info!(
+                    "looking up {} {} in {}",
+                    package.name,
+                    package.version,
+                    index_url
+                );
+            }
+
+            match index.find(&package.name, &package.version) {
+                Ok(pkg) => {
+                    if pkg.is_yanked {
+                        yanked.push(*package);
+                    }
+                }
+                Err(e) => tracing::trace!(
+                    "couldn't find {} {} in {}: {}",
+                    package.name,
+                    package.version,
+                    index_url,
+                    e
+                ),
+            }
+        }
+
+        Some(yanked)
+    }
+
+    /// Find the yanked packages in the sparse index (of crates.io or a
+    /// registry replacing it). Unless the index is configured to be updated
+    /// (and fetching isn't disabled), only cached releases are checked.
+    fn find_yanked_sparse<'a>(&mut self, packages: &[&'a Package]) -> Option<Vec<&'a Package>> {
+        let update = self.config.yanked.update_index && self.config.database.fetch;
+        let client = self
+            .registry_client()?
+            .offline(self.config.net.offline || !update);
+        let mut names: Vec<_> = packages.iter().map(|package| &package.name).collect();
+        names.sort();
+        names.dedup();
+
+        let (releases, error) = client.releases(&names);
+
+        if let Some(e) = error {
+            if !self.quiet {
+                status_warn!(
+                    "couldn't check for yanked crates in {}: {}",
+                    client.url(),
+                    e
+                );
+            }
+        }
+
+        let yanked = packages
+            .iter()
+            .filter(|package| match releases.get(&package.name) {
+                Some(releases) => releases
+                    .iter()
+                    .any(|release| release.version == package.version && release.yanked),
+                None => false,
+            })
+            .copied()
+            .collect();
+
+        Some(yanked)
+    }
+
+    /// Check the locked revisions of vulnerable git dependencies against the
+    /// commits which fix their advisories (if listed), so they're reported
+    /// as patched or vulnerable rather than `overridden` warnings. Offline,
+    /// they're left as they are.
+    fn check_fix_commits(&mut self, report: &mut rustsec::Report) {
+        if self.config.net.offline {
+            return;
+        }
+
+        let settings = fix_commits::Settings {
+            git_dir: Cache::from_config(&self.config.cache)
+                .dir()
+                .join(verify::GIT_DIR),
+            retry: Retry::from_config(&self.config.net),
+        };
+
+        let started = Instant::now();
+        let errors = fix_commits::apply(report, &self.database, &settings);
+
+        tracing::debug!(
+            "checked git dependencies against fix commits in {}ms",
+            self.timings.record("fix-commits", started)
+        );
+
+        if !self.quiet {
+            for e in errors {
+                status_warn!("couldn't check git dependency against fix commits: {}", e);
+            }
+        }
+    }
+
+    /// Warn about older versions of crates which are also locked at a newer
+    /// version (if enabled with `--warn duplicate-versions`). Like the other
+    /// enrichments, these warnings aren't cached.
+    fn add_duplicate_warnings(
+        &self,
+        report: &mut rustsec::Report,
+        lockfile: &Lockfile,
+        tree: Option<&Tree>,
+    ) {
+        if !self.config.output.is_warned(WarnOption::DuplicateVersions) {
+            return;
+        }
+
+        let warnings = duplicates::warnings(lockfile, report, tree);
+
+        if !warnings.is_empty() {
+            report
+                .warnings
+                .entry(warning::Kind::DuplicateVersions)
+                .or_default()
+                .extend(warnings);
+        }
+    }
+
+    /// Leave the vulnerabilities and warnings which ignores scoped to
+    /// packages or dependency paths apply to out of the report
+    fn apply_scoped_ignores(&self, report: &mut rustsec::Report, tree: Option<&Tree>) {
+        let ignored = scoped_ignores::apply(
+            report,
+            &self.config.advisories.ignore,
+            tree,
+            &state::today(),
+        );
+
+        if ignored > 0 {
+            tracing::debug!("{} vulnerabilities left out by scoped ignores", ignored);
+        }
+    }
+
+    /// Move the vulnerabilities of advisories acknowledged until a deadline
+    /// which hasn't passed yet on the given date to the report's acknowledged
+    /// vulnerabilities, warning about expired acknowledgments which are
+    /// failing again
+    fn add_acknowledgments(&mut self, report: &mut rustsec::Report, today: &advisory::Date) {
+        let acknowledgments = &self.config.advisories.acknowledged;
+
+        if acknowledgments.is_empty() {
+            return;
+        }
+
+        let expired = acknowledgments::apply(report, acknowledgments, today);
+
+        if !self.quiet {
+            for acknowledgment in expired {
+                status_warn!(
+                    "acknowledgment of {} (ticket {}) expired on {}",
+                    acknowledgment.id,
+                    acknowledgment.ticket,
+                    acknowledgment.expires.as_str()
+                );
+            }
+        }
+    }
+
+    /// Move the vulnerabilities which the configured VEX documents say the
+    /// lockfile's packages aren't affected by to the report's VEX-suppressed
+    /// vulnerabilities, loading the documents on first use
+    fn add_vex_statements(
+        &mut self,
+        report: &mut rustsec::Report,
+        lockfile: &Lockfile,
+    ) -> Result<(), error::Error> {
+        if self.config.advisories.vex.is_empty() {
+            return Ok(());
+        }
+
+        if self.vex_documents.is_none() {
+            let documents = self
+                .config
+                .advisories
+                .vex
+                .iter()
+                .map(|path| vex::Document::load(path))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            self.vex_documents = Some(documents);
+        }
+
+        vex::apply(
+            report,
+            lockfile,
+            self.vex_documents.as_deref().unwrap_or_default(),
+        );
+        Ok(())
+    }
+
+    /// Find the ignored advisories which match packages of the lockfile, to
+    /// summarize in terminal reports, warning about expired ignores whose
+    /// advisories are failing again
+    fn suppressions(&self, lockfile: &Lockfile) -> Vec<suppressions::Suppression> {
+        let ignore = &self.config.advisories.ignore;
+
+        if ignore.is_empty() {
+            return vec![];
+        }
+
+        let today = state::today();
+
+        if !self.quiet {
+            for ignored in suppressions::expired(&self.database, lockfile, ignore, &today) {
+                status_warn!(
+                    "ignore of {} expired on {}, so it's reported again",
+                    ignored.id,
+                    ignored
+                        .expires
+                        .as_ref()
+                        .map(advisory::Date::as_str)
+                        .unwrap_or_default()
+                );
+            }
+        }
+
+        suppressions::suppressions(&self.database, lockfile, ignore, &today)
+    }
+
+    /// Add the inventory of the packages which were audited, and how many
+    /// packages of the lockfile were skipped by each mechanism, to the report
+    /// (if it was requested)
+    fn add_inventory(&mut self, report: &mut rustsec::Report, lockfile: &Lockfile, pruned: usize) {
+        if !self.config.output.include_inventory {
+            return;
+        }
+
+        let scope = self
+            .report_settings
+            .package_scope
+            .clone()
+            .unwrap_or_default();
+
+        let mut inventory = report::Inventory::default();
+        inventory.skipped.features = pruned;
+
+        for package in &lockfile.packages {
+            if scope.includes(package) {
+                inventory.packages.push(package.into());
+            } else if package.source.is_none() {
+                inventory.skipped.source_scope += 1;
+            } else {
+                inventory.skipped.git += 1;
+            }
+        }
+
+        // As in `add_yanked_warnings`, which only checks crates.io packages
+        let locally_replaced = matches!(
+            self.crates_io_replacement()
+                .map(|replacement| replacement.index),
+            Some(ReplacementIndex::Local(_))
+        );
+
+        if self.config.yanked.enabled && !locally_replaced {
+            inventory.skipped.yanked_check = Some(
+                lockfile
+                    .packages
+                    .iter()
+                    .filter(|package| {
+                        !package
+                            .source
+                            .iter()
+                            .any(|source| source.is_default_registry())
+                    })
+                    .count(),
+            );
+        }
+
+        report.inventory = Some(inventory);
+    }
+
+    /// Mark vulnerabilities with a CVE alias in the CISA Known Exploited
+    /// Vulnerabilities catalog (if enabled).
+    ///
+    /// With `--deny known-exploited`, known exploited vulnerabilities below
+    /// the severity threshold are added to the report, so the audit fails.
+    /// Failing to get the catalog only results in a warning.
+    fn add_known_exploited(&mut self, report: &mut rustsec::Report, lockfile: &Lockfile) {
+        if !self.config.kev.is_enabled() {
+            return;
+        }
+
+        let started = Instant::now();
+
+        let (catalog, error) = match &self.config.kev.file {
+            Some(path) => match kev::Catalog::load(path) {
+                Ok(catalog) => (Some(catalog), None),
+                Err(e) => (None, Some(e)),
+            },
+            None => {
+                kev::Client::from_config(&self.config.kev, &Cache::from_config(&self.config.cache))
+                    .offline(self.config.net.offline)
+                    .retry(Retry::from_config(&self.config.net))
+                    .catalog()
+            }
+        };
+
+        if let Some(e) = error {
+            if !self.quiet {
+                status_warn!(
+                    "couldn't get the Known Exploited Vulnerabilities catalog: {}",
+                    e
+                );
+            }
+        }
+
+        let catalog = match catalog {
+            Some(catalog) => catalog,
+            None => return,
+        };
+
+        let is_known_exploited =
+            |vuln: &rustsec::Vulnerability| vuln.cve_ids().any(|cve| catalog.contains(cve));
+
+        if self
+            .config
+            .output
+            .deny
+            .contains(&DenyOption::KnownExploited)
+            && self.report_settings.severity.is_some()
+        {
+            let mut settings = self.report_settings.clone();
+            settings.severity = None;
+
+            let below_threshold: Vec<_> =
+                rustsec::Report::generate(&self.database, lockfile, &settings)
+                    .vulnerabilities
+                    .list
+                    .into_iter()
+                    .filter(|vuln| {
+                        is_known_exploited(vuln) && !report.vulnerabilities.list.contains(vuln)
+                    })
+                    .collect();
+
+            if !below_threshold.is_empty() {
+                let mut list = report.vulnerabilities.list.clone();
+                list.extend(below_threshold);
+                let ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+                let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+                let vex = std::mem::take(&mut report.vulnerabilities.vex);
+                report.vulnerabilities = report::VulnerabilityInfo::new(list);
+                report.vulnerabilities.ignored = ignored;
+                report.vulnerabilities.acknowledged = acknowledged;
+                report.vulnerabilities.vex = vex;
+                report.sort();
+            }
+        }
+
+        for vuln in &mut report.vulnerabilities.list {
+            vuln.known_exploited = Some(is_known_exploited(vuln));
+        }
+
+        tracing::debug!(
+            "checked vulnerabilities against {} known exploited CVEs in {}ms",
+            catalog.len(),
+            self.timings.record("kev", started)
+        );
+    }
+
+    /// Annotate vulnerabilities with EPSS scores for their CVE aliases (if
+    /// enabled), using the highest score if an advisory has several.
+    ///
+    /// With an EPSS threshold, vulnerabilities scored below it are removed
+    /// from the report (unless they're known to be exploited). Failing to
+    /// fetch scores only results in a warning, and nothing being removed.
+    fn add_epss_scores(&mut self, report: &mut rustsec::Report) {
+        if !self.config.epss.is_enabled() || self.config.net.offline {
+            return;
+        }
+
+        let mut cves: Vec<_> = report
+            .vulnerabilities
+            .list
+            .iter()
+            .flat_map(|vuln| vuln.cve_ids().cloned())
+            .collect();
+
+        cves.sort();
+        cves.dedup();
+
+        if cves.is_empty() {
+            return;
+        }
+
+        let started = Instant::now();
+        let client =
+            epss::Client::from_config(&self.config.epss, &Cache::from_config(&self.config.cache))
+                .retry(Retry::from_config(&self.config.net));
+        let (scores, error) = client.scores(&cves);
+
+        if let Some(e) = error {
+            if !self.quiet {
+                status_warn!("couldn't fetch EPSS scores: {}", e);
+            }
+        }
+
+        for vuln in &mut report.vulnerabilities.list {
+            vuln.epss = vuln
+                .cve_ids()
+                .filter_map(|cve| scores.get(cve))
+                .max_by(|a, b| {
+                    a.score
+                        .partial_cmp(&b.score)
+                        .expect("EPSS score is a number")
+                })
+                .cloned();
+        }
+
+        tracing::debug!(
+            "found EPSS scores for {} of {} CVEs in {}ms",
+            scores.len(),
+            cves.len(),
+            self.timings.record("epss", started)
+        );
+
+        if let Some(threshold) = self.config.epss.threshold {
+            let count = report.vulnerabilities.list.len();
+            let list: Vec<_> = report
+                .vulnerabilities
+                .list
+                .drain(..)
+                .filter(|vuln| {
+                    vuln.known_exploited == Some(true)
+                        || vuln
+                            .epss
+                            .as_ref()
+                            .map_or(true, |epss| epss.score >= threshold)
+                })
+                .collect();
+
+            tracing::debug!(
+                "removed {} vulnerabilities with an EPSS score below {}",
+                count - list.len(),
+                threshold
+            );
+
+            let ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+            let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+            let vex = std::mem::take(&mut report.vulnerabilities.vex);
+            report.vulnerabilities = report::VulnerabilityInfo::new(list);
+            report.vulnerabilities.ignored = ignored;
+            report.vulnerabilities.acknowledged = acknowledged;
+            report.vulnerabilities.vex = vex;
+        }
+    }
+
+    /// Annotate vulnerabilities with whether the functions their advisories
+    /// list as affected are referenced in the source code of the workspace
+    /// the lockfile is in (if enabled).
+    ///
+    /// Failing to read the source code only results in a warning.
+    fn add_reachability(&mut self, report: &mut rustsec::Report, lockfile_path: &Path) {
+        if !self.config.advisories.reachability || lockfile_path == Path::new("-") {
+            return;
+        }
+
+        if !report
+            .vulnerabilities
+            .list
+            .iter()
+            .any(|vuln| vuln.affected_functions().map_or(false, |f| !f.is_empty()))
+        {
+            return;
+        }
+
+        let root = match lockfile_path.parent() {
+            Some(dir) if dir != Path::new("") => dir,
+            _ => Path::new("."),
+        };
+
+        let started = Instant::now();
+
+        let sources = match reachability::Sources::load(root) {
+            Ok(sources) => sources,
+            Err(e) => {
+                if !self.quiet {
+                    status_warn!(
+                        "couldn't check whether affected functions are referenced: {}",
+                        e
+                    );
+                }
+
+                return;
+            }
+        };
+
+        reachability::annotate(report, &sources);
+
+        tracing::debug!(
+            "searched {} source files for affected functions in {}ms",
+            sources.len(),
+            self.timings.record("reachability", started)
+        );
+    }
+
+    /// Annotate findings with when their advisories were first added to the
+    /// advisory database's git history (if enabled). Only the advisories
+    /// found are looked up.
+    ///
+    /// Failing to read the history only results in a warning.
+    fn add_first_published(&mut self, report: &mut rustsec::Report) {
+        if !self.config.database.first_published {
+            return;
+        }
+
+        let ids: Set<advisory::Id> = report
+            .vulnerabilities
+            .list
+            .iter()
+            .chain(&report.vulnerabilities.ignored)
+            .map(|vuln| vuln.advisory.id.clone())
+            .chain(
+                report
+                    .warnings
+                    .values()
+                    .flatten()
+                    .filter_map(|warning| warning.advisory.as_ref())
+                    .map(|advisory| advisory.id.clone()),
+            )
+            .collect();
+
+        if ids.is_empty() {
+            return;
+        }
+
+        let started = Instant::now();
+        let result = self
+            .config
+            .database
+            .resolve_sources()
+            .and_then(|sources| db_history::first_published(&sources, &ids));
+
+        let first_published = match result {
+            Ok(first_published) => first_published,
+            Err(e) => {
+                if !self.quiet {
+                    status_warn!(
+                        "couldn't look up when advisories were first published: {}",
+                        e
+                    );
+                }
+
+                return;
+            }
+        };
+
+        for path in &first_published.missing_history {
+            tracing::debug!("{} has no git history", path.display());
+        }
+
+        if !first_published.missing_history.is_empty() {
+            report.database.first_published_note = Some(db_history::NO_HISTORY_NOTE.to_owned());
+        }
+
+        let dates = &first_published.dates;
+
+        for vuln in report
+            .vulnerabilities
+            .list
+            .iter_mut()
+            .chain(report.vulnerabilities.ignored.iter_mut())
+        {
+            vuln.first_published_in_db = Some(dates.get(&vuln.advisory.id).cloned());
+        }
+
+        for warning in report.warnings.values_mut().flatten() {
+            if let Some(advisory) = &warning.advisory {
+                warning.first_published_in_db = Some(dates.get(&advisory.id).cloned());
+            }
+        }
+
+        tracing::debug!(
+            "looked up when {} of {} advisories were first published in {}ms",
+            dates.len(),
+            ids.len(),
+            self.timings.record("first-published", started)
+        );
+    }
+
+    /// Annotate vulnerabilities in crates.io packages with whether a release
+    /// satisfying the advisory's patched versions is published (unless
+    /// disabled). In offline mode, only cached releases are used.
+    ///
+    /// Failing to look up releases only results in a warning.
+    fn add_patched_releases(&mut self, report: &mut rustsec::Report) {
+        if !self.config.registry.lookup {
+            return;
+        }
+
+        let is_crates_io = |vuln: &rustsec::Vulnerability| {
+            vuln.package
+                .source
+                .iter()
+                .any(|source| source.is_default_registry())
+        };
+
+        let mut names: Vec<_> = report
+            .vulnerabilities
+            .list
+            .iter()
+            .filter(|vuln| is_crates_io(vuln) && !vuln.versions.patched().is_empty())
+            .map(|vuln| &vuln.package.name)
+            .collect();
+
+        names.sort();
+        names.dedup();
+
+        if names.is_empty() {
+            return;
+        }
+
+        let client = match self.registry_client() {
+            Some(client) => client,
+            None => return,
+        };
+
+        let started = Instant::now();
+        let (releases, error) = client.releases(&names);
+        let count = names.len();
+
+        if let Some(e) = error {
+            if !self.quiet {
+                status_warn!("couldn't look up patched releases: {}", e);
+            }
+        }
+
+        for vuln in &mut report.vulnerabilities.list {
+            if !is_crates_io(vuln) || vuln.versions.patched().is_empty() {
+                continue;
+            }
+
+            let releases = match releases.get(&vuln.package.name) {
+                Some(releases) => releases,
+                None => continue,
+            };
+
+            vuln.patched_release = Some(index::patched_release(releases, &vuln.versions));
+            vuln.upgrade = Upgrade::from_releases(
+                &vuln.package.version,
+                &vuln.versions,
+                releases
+                    .iter()
+                    .filter(|release| !release.yanked)
+                    .map(|release| &release.version),
+            );
+        }
+
+        tracing::debug!(
+            "looked up releases of {} of {} crates in {}ms",
+            releases.len(),
+            count,
+            self.timings.record("registry-lookup", started)
+        );
+    }
+
+    /// Annotate vulnerabilities with how their recommended upgrade can be
+    /// applied, given the requirements their package's dependents declare
+    /// for it. The requirements are resolved with `cargo metadata` in
+    /// offline mode, so nothing is annotated if the dependencies' sources
+    /// haven't been downloaded (or the lockfile's `Cargo.toml` isn't found).
+    fn add_resolutions(&mut self, report: &mut rustsec::Report, lockfile_path: &Path) {
+        if report
+            .vulnerabilities
+            .list
+            .iter()
+            .all(|vuln| vuln.versions.patched().is_empty())
+        {
+            return;
+        }
+
+        let manifest_path = match self
+            .manifest_path(lockfile_path, "resolving dependency requirements")
+            .ok()
+            .filter(|path| path.is_file())
+        {
+            Some(manifest_path) => manifest_path,
+            None => return,
+        };
+
+        let started = Instant::now();
+
+        let dependents = match features::dependents(&manifest_path) {
+            Ok(dependents) => dependents,
+            Err(e) => {
+                tracing::debug!("couldn't resolve dependency requirements: {}", e);
+                return;
+            }
+        };
+
+        for vuln in &mut report.vulnerabilities.list {
+            let dependents = match dependents.get(&Dependency::from(&vuln.package)) {
+                Some(dependents) => dependents,
+                None => continue,
+            };
+
+            // Without a registry lookup, the upgrade is to the lowest
+            // versions of the advisory's ranges
+            let upgrade = match (&vuln.upgrade, &vuln.patched_release) {
+                (Some(upgrade), _) => Some(upgrade.clone()),
+                (None, None) => Upgrade::from_ranges(&vuln.package.version, &vuln.versions),
+                (None, Some(_)) => None,
+            };
+
+            vuln.resolution = upgrade.and_then(|upgrade| Resolution::new(&upgrade, dependents));
+        }
+
+        tracing::debug!(
+            "resolved dependency requirements of {} packages in {}ms",
+            dependents.len(),
+            self.timings.record("resolutions", started)
+        );
+    }
+
+    /// Send the report to the configured webhooks (if any). This happens
+    /// before printing the report, which may exit. With `--notify-on new`,
+    /// only the findings which are new since the recorded run are sent.
+    ///
+    /// Delivery failures are printed to STDERR, and only fail the audit if
+    /// notifications are required.
+    fn notify(&mut self, report: &rustsec::Report, changes: Option<&state::Changes>) {
+        let notifier = notify::Notifier::from_config(&self.config.notify);
+
+        let new_findings = match (self.config.notify.on, changes) {
+            (NotifyOn::New, Some(changes)) => Some(notify::new_findings(report, changes)),
+            _ => None,
+        };
+
+        let report = new_findings.as_ref().unwrap_or(report);
+
+        if !notifier.should_notify(report) {
+            return;
+        }
+
+        if self.config.net.offline {
+            if !self.quiet {
+                output::warn_stderr("not sending report to webhooks in offline mode");
+            }
+
+            return;
+        }
+
+        let started = Instant::now();
+        let errors = notifier.notify(report);
+
+        tracing::debug!(
+            "sent report to {} of {} webhooks in {}ms",
+            notifier.urls().len() - errors.len(),
+            notifier.urls().len(),
+            self.timings.record("notify", started)
+        );
+
+        for e in &errors {
+            if self.config.notify.required && self.presenter.is_some() {
+                status_err!("{}", e);
+            } else if !self.quiet {
+                output::warn_stderr(e.to_string());
+            }
+        }
+
+        if !errors.is_empty() && self.config.notify.required {
+            self.notification_failed = true;
+        }
+    }
+
+    /// Write the dependency graph of the report's findings to the configured
+    /// DOT file (if any).
+    ///
+    /// This happens before the report is printed, as printing it may exit
+    /// the process, but errors are only returned after printing it.
+    fn emit_graph(
+        &mut self,
+        report: &rustsec::Report,
+        tree: Option<&Tree>,
+    ) -> Result<(), error::Error> {
+        let (path, tree) = match (&self.config.output.graph, tree) {
+            (Some(path), Some(tree)) => (path, tree),
+            _ => return Ok(()),
+        };
+
+        let started = Instant::now();
+        graph::save(path, tree, report, self.config.output.graph_full)?;
+
+        tracing::debug!(
+            "wrote dependency graph to {} in {}ms",
+            path.display(),
+            self.timings.record("graph-export", started)
+        );
+
+        Ok(())
+    }
+
+    /// Did delivering the report to a webhook fail when notifications are
+    /// required? (in which case the report's outcome is `notify-failed`)
+    pub fn notification_failed(&self) -> bool {
+        self.notification_failed
+    }
+
+    /// Outcome of the audit which generated the given report: a failed
+    /// required notification takes precedence over vulnerabilities, which
+    /// take precedence over denied warnings
+    fn outcome(
+        &mut self,
+        report: &rustsec::Report,
+        self_advisories: &[rustsec::Advisory],
+        changes: Option<&state::Changes>,
+    ) -> Outcome {
+        // With `--fail-on new` (or a baseline), only findings which are new
+        // since the last run recorded in the state file (or the baseline)
+        // count
+        let fail_on_new = if self.config.state.only_new_fail() {
+            changes
+        } else {
+            None
+        };
+
+        let deny = &self.config.output.deny;
+        let denied_warnings: Vec<warning::Kind> = report
+            .warnings
+            .iter()
+            .filter(|(kind, warnings)| {
+                warnings.iter().any(|warning| match fail_on_new {
+                    Some(changes) => changes.is_new_warning(warning),
+                    None => true,
+                }) && deny
+                    .iter()
+                    .any(|option| option.get_warning_kind() == Some(**kind))
+            })
+            .map(|(kind, _)| *kind)
+            .collect();
+
+        let failing_vulnerabilities: Vec<&rustsec::Vulnerability> = report
+            .vulnerabilities
+            .list
+            .iter()
+            .filter(|vuln| {
+                let new = match fail_on_new {
+                    Some(changes) => changes.is_new_vulnerability(vuln),
+                    None => true,
+                };
+
+                new && !self.allowed_by_fail_on(vuln)
+                    && !self.config.output.is_below_fail_severity(vuln)
+            })
+            .collect();
+
+        self.failed_severity = failing_vulnerabilities
+            .iter()
+            .filter_map(|vuln| vuln.effective_severity)
+            .max();
+
+        let outcome = if self.notification_failed {
+            Outcome::NotifyFailed
+        } else if !failing_vulnerabilities.is_empty() {
+            Outcome::VulnerabilitiesFound
+        } else if !denied_warnings.is_empty()
+            || (!self_advisories.is_empty() && deny.contains(&DenyOption::Warnings))
+        {
+            Outcome::DeniedWarnings
+        } else {
+            Outcome::Clean
+        };
+
+        self.denied_warnings = denied_warnings;
+        outcome
+    }
+
+    /// Exit status for the given report of the most recent audit: the one
+    /// configured for its outcome, or for the severity of the most severe
+    /// vulnerability which failed it or the kind of denied warning it found
+    pub fn exit_code(&self, report: &rustsec::Report) -> i32 {
+        self.config.output.exit_codes.failure_code(
+            report.outcome.unwrap_or(Outcome::Clean),
+            self.failed_severity,
+            &self.denied_warnings,
+        )
+    }
+
+    /// Apply the ignores and acknowledgments chosen when triaging the
+    /// findings of the given report (`--interactive`) on the given date to it
+    /// and to later audits, determining the report's outcome again
+    pub fn apply_triage(
+        &mut self,
+        report: &mut rustsec::Report,
+        decisions: &[Decision],
+        today: &advisory::Date,
+    ) {
+        let mut ignored = Set::new();
+
+        for decision in decisions {
+            match decision {
+                Decision::Ignore(advisory) => {
+                    ignored.insert(advisory.id.clone());
+                    self.config.advisories.ignore.push(advisory.clone());
+                }
+                Decision::FixLater(acknowledgment) => self
+                    .config
+                    .advisories
+                    .acknowledged
+                    .push(acknowledgment.clone()),
+                Decision::Fail => (),
+            }
+        }
+
+        self.report_settings = self.config.report_settings();
+
+        let (ignored_vulns, list): (Vec<_>, Vec<_>) = report
+            .vulnerabilities
+            .list
+            .drain(..)
+            .partition(|vuln| ignored.contains(&vuln.advisory.id));
+
+        let mut ignored_list = std::mem::take(&mut report.vulnerabilities.ignored);
+        ignored_list.extend(ignored_vulns);
+        let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+        let vex = std::mem::take(&mut report.vulnerabilities.vex);
+        report.vulnerabilities = report::VulnerabilityInfo::new(list);
+        report.vulnerabilities.ignored = ignored_list;
+        report.vulnerabilities.acknowledged = acknowledged;
+        report.vulnerabilities.vex = vex;
+
+        for warnings in report.warnings.values_mut() {
+            warnings.retain(|warning| match &warning.advisory {
+                Some(advisory) => !ignored.contains(&advisory.id),
+                None => true,
+            });
+        }
+
+        report.warnings.retain(|_, warnings| !warnings.is_empty());
+        self.add_acknowledgments(report, today);
+        report.sort();
+
+        // With `--fail-on new`, which findings are new isn't known anymore
+        if report.outcome != Some(Outcome::Clean) && !self.config.state.only_new_fail() {
+            report.outcome = Some(self.outcome(report, &[], None));
+        }
+    }
+
+    /// Does the `fail-on` policy allow the given vulnerability?
+    fn allowed_by_fail_on(&self, vuln: &rustsec::Vulnerability) -> bool {
+        self.config.output.fail_policy().is_some()
+            && matches!(&vuln.scopes, Some(scopes) if scopes.failed.is_none())
+    }
+
+    /// Compare the report's findings to those of the baseline, or those
+    /// recorded in the state file (if one is configured), then record the
+    /// report's findings in the baseline file being written (if any) or the
+    /// state file (unless updating it is disabled)
+    fn track_state(
+        &self,
+        report: &rustsec::Report,
+    ) -> Result<Option<state::Changes>, error::Error> {
+        let state_config = &self.config.state;
+
+        // When a baseline is only written, findings are compared to it
+        if let Some(baseline_path) = state_config
+            .baseline
+            .as_ref()
+            .or_else(|| state_config.write_baseline.as_ref())
+        {
+            if state_config.file.is_some() {
+                return Err(error::Error::new(
+                    error::ErrorKind::BadParam,
+                    &"--baseline and --write-baseline can't be used with a state file (--state-file)",
+                ));
+            }
+
+            // Read the baseline before it's (possibly) overwritten
+            let baseline = match &state_config.baseline {
+                Some(path) => state::load_baseline(path)?,
+                None => State::baseline(report),
+            };
+
+            if let Some(path) = &state_config.write_baseline {
+                State::baseline(report).save(path)?;
+            }
+
+            return Ok(Some(State::baseline_changes(
+                baseline_path,
+                &baseline,
+                report,
+            )));
+        }
+
+        let path = match &self.config.state.file {
+            Some(path) => path,
+            None if self.config.state.fail_on_new => {
+                return Err(error::Error::new(
+                    error::ErrorKind::BadParam,
+                    &"--fail-on new requires a state file (--state-file) or --baseline",
+                ));
+            }
+            None if self.config.notify.on == NotifyOn::New => {
+                return Err(error::Error::new(
+                    error::ErrorKind::BadParam,
+                    &"--notify-on new requires a state file (--state-file) or --baseline",
+                ));
+            }
+            None => return Ok(None),
+        };
+
+        let previous = State::load(path)?;
+        let changes = State::changes(previous.as_ref(), report);
+
+        if self.config.state.update.unwrap_or(true) {
+            State::update(previous.as_ref(), report, &state::today()).save(path)?;
+        }
+
+        Ok(Some(changes))
+    }
+
+    /// Compute the key to cache the report for the given lockfile under, if
+    /// caching is enabled and the advisory database's commit is known
+    fn cache_key(&self, lockfile_toml: &str) -> Option<cache::Key> {
+        self.cache.as_ref()?;
+
+        let (commit, commit_ids) = match (self.database.latest_commit(), &self.database_commit) {
+            (Some(commit), Some(commit_ids)) => (commit, commit_ids),
+            _ => {
+                tracing::debug!("not caching: advisory database commit is unknown");
+                return None;
+            }
+        };
+
+        // A stale database would be rejected unless `--stale` is given, so
+        // never let a cached report bypass that check
+        if !self.config.database.stale && !commit.is_fresh() {
+            tracing::debug!("not caching: advisory database is stale");
+            return None;
+        }
+
+        Some(cache::Key::new(lockfile_toml, commit_ids, &self.config))
+    }
+
+    /// Remove the packages which aren't compiled with the selected features
+    /// from the lockfile, returning how many were removed
+    fn prune_features(
+        &mut self,
+        lockfile: &mut Lockfile,
+        lockfile_path: &Path,
+    ) -> Result<usize, error::Error> {
+        let manifest_path = self.manifest_path(lockfile_path, "selecting features")?;
+        let started = Instant::now();
+        let compiled = features::resolve(
+            &manifest_path,
+            &self.config.features,
+            self.config.net.offline,
+        )?;
+        let pruned = features::prune(lockfile, &compiled);
+
+        tracing::debug!(
+            "pruned {} packages not compiled with the selected features in {}ms",
+            pruned,
+            self.timings.record("features", started)
+        );
+
+        if !self.quiet {
+            status_ok!(
+                "Pruned",
+                "{} packages not compiled with the selected features",
+                pruned
+            );
+        }
+
+        Ok(pruned)
+    }
+
+    /// Resolve the packages which are only compiled with features that
+    /// aren't enabled, for `--feature-aware`
+    fn resolve_optional(&mut self, lockfile_path: &Path) -> Result<Set<Dependency>, error::Error> {
+        let manifest_path = self.manifest_path(lockfile_path, "--feature-aware")?;
+        let started = Instant::now();
+        let optional = features::optional(
+            &manifest_path,
+            &self.config.features,
+            self.config.net.offline,
+        )?;
+
+        tracing::debug!(
+            "resolved {} packages only compiled with features which aren't enabled in {}ms",
+            optional.len(),
+            self.timings.record("features", started)
+        );
+
+        Ok(optional)
+    }
+
+    /// Resolve the kinds of dependency each package in the lockfile is
+    /// reachable through, for the `fail-on` policy
+    fn resolve_scopes(
+        &mut self,
+        lockfile_path: &Path,
+    ) -> Result<Map<Dependency, Set<Scope>>, error::Error> {
+        let manifest_path = self.manifest_path(lockfile_path, "a fail-on policy")?;
+        let started = Instant::now();
+        let scopes = features::scopes(
+            &manifest_path,
+            &self.config.features,
+            self.config.net.offline,
+        )?;
+
+        tracing::debug!(
+            "resolved dependency kinds of {} packages in {}ms",
+            scopes.len(),
+            self.timings.record("scopes", started)
+        );
+
+        Ok(scopes)
+    }
+
+    /// Note which workspace members each finding affects, and whether it's in
+    /// a direct dependency (if anything was found and the dependency graph is
+    /// known)
+    fn add_affected_members(
+        &mut self,
+        report: &mut rustsec::Report,
+        lockfile_path: Option<&Path>,
+        tree: Option<&Tree>,
+    ) {
+        let tree = match tree {
+            Some(tree) => tree,
+            None => return,
+        };
+
+        if report.vulnerabilities.list.is_empty() && report.warnings.is_empty() {
+            return;
+        }
+
+        let members = lockfile_path.and_then(|path| self.workspace_members(path));
+        members::annotate(report, tree, members.as_ref());
+    }
+
+    /// Members of the workspace the given lockfile belongs to, according to
+    /// `cargo metadata` (if its `Cargo.toml` can be found and loaded)
+    fn workspace_members(&mut self, lockfile_path: &Path) -> Option<Set<Dependency>> {
+        let manifest_path = self
+            .manifest_path(lockfile_path, "listing workspace members")
+            .ok()
+            .filter(|path| path.is_file())?;
+
+        let started = Instant::now();
+
+        match features::workspace_members(&manifest_path) {
+            Ok(members) => {
+                tracing::debug!(
+                    "listed {} workspace members in {}ms",
+                    members.len(),
+                    self.timings.record("members", started)
+                );
+
+                Some(members)
+            }
+            Err(e) => {
+                tracing::debug!(
+                    "couldn't list workspace members, using the dependency graph's roots: {}",
+                    e
+                );
+
+                None
+            }
+        }
+    }
+
+    /// `Cargo.toml` of the workspace the given lockfile belongs to, which
+    /// `cargo metadata` needs for `what` (e.g. `selecting features`)
+    fn manifest_path(&self, lockfile_path: &Path, what: &str) -> Result<PathBuf, error::Error> {
+        match &self.config.features.manifest_path {
+            Some(manifest_path) => Ok(manifest_path.clone()),
+            None if lockfile_path == Path::new("-") => Err(error::Error::new(
+                error::ErrorKind::BadParam,
+                &format!(
+                    "{} requires --manifest-path when reading Cargo.lock from STDIN",
+                    what
+                ),
+            )),
+            None => Ok(lockfile_path.with_file_name(CARGO_TOML_FILE)),
+        }
+    }
+
+    /// Judge each vulnerability by the `fail-on` policy (if configured, or
+    /// implied by `dependency_kinds`),
+    /// according to the kinds of dependency its package is reachable through,
+    /// and count them by kind in the summary. Packages `cargo metadata` didn't
+    /// classify are judged as regular dependencies.
+    fn apply_fail_on(
+        &self,
+        report: &mut rustsec::Report,
+        scopes: Option<&Map<Dependency, Set<Scope>>>,
+    ) {
+        let (fail_on, scopes) = match (self.config.output.fail_policy(), scopes) {
+            (Some(fail_on), Some(scopes)) => (fail_on, scopes),
+            _ => return,
+        };
+
+        let mut summary: Map<Scope, report::ScopeSummary> = Scope::all()
+            .into_iter()
+            .map(|scope| (scope, report::ScopeSummary::default()))
+            .collect();
+
+        for vuln in &mut report.vulnerabilities.list {
+            let reachable: Vec<Scope> = match scopes.get(&Dependency::from(&vuln.package)) {
+                Some(scopes) => scopes.iter().copied().collect(),
+                None => vec![Scope::Normal],
+            };
+
+            let failed = fail_on.failed(&reachable, vuln.effective_severity);
+
+            for scope in &reachable {
+                let counts = summary.entry(*scope).or_default();
+                counts.vulnerabilities += 1;
+
+                if failed == Some(*scope) {
+                    counts.failed += 1;
+                }
+            }
+
+            vuln.scopes = Some(Scopes { reachable, failed });
+        }
+
+        report.summary.scopes = Some(summary);
+    }
+
+    /// Dependency tree of the most recently audited lockfile (if any)
+    pub fn dependency_tree(&self) -> Option<Arc<Tree>> {
+        self.dependency_tree.clone()
+    }
+
+    /// Generate `Cargo.lock` for a project which doesn't have one, provided
+    /// the user opted in (`--generate-lockfile`) or confirmed when prompted
+    fn generate_lockfile(
+        &self,
+        lockfile_path: &Path,
+        manifest_path: &Path,
+    ) -> Result<(), error::Error> {
+        if self.config.lockfile.locked {
+            return Err(error::Error::new(
+                error::ErrorKind::NotFound,
+                &format!(
+                    "Couldn't load {}: no lockfile found next to {}, \
+                     and --locked prevents generating one",
+                    lockfile_path.display(),
+                    manifest_path.display()
+                ),
+            ));
+        }
+
+        if !self.generate_lockfile && (self.quiet || !lockfile::confirm_generate()) {
+            return Err(error::Error::new(
+                error::ErrorKind::NotFound,
+                &format!(
+                    "Couldn't load {}: no lockfile found next to {} \
+                     (rerun with --generate-lockfile to create one)",
+                    lockfile_path.display(),
+                    manifest_path.display()
+                ),
+            ));
+        }
+
+        if !self.quiet {
+            status_ok!(
+                "Generating",
+                "{} for {}",
+                lockfile_path.display(),
+                manifest_path.display()
+            );
+        }
+
+        lockfile::generate(manifest_path)?;
+
+        if !self.quiet {
+            status_warn!(
+                "{} was freshly generated: it reflects the current dependency \
+                 resolution rather than any previously pinned state",
+                lockfile_path.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check that the lockfile is up to date with the `Cargo.toml` next to
+    /// it, since a stale lockfile isn't what the next build will resolve.
+    ///
+    /// With `--locked`, a stale lockfile (or one which can't be checked) is
+    /// an error; otherwise it's only a warning, and lockfiles which can't be
+    /// checked (e.g. without a manifest next to them) are audited anyway.
+    fn check_up_to_date(&mut self, lockfile_path: &Path) -> Result<(), error::Error> {
+        let locked = self.config.lockfile.locked;
+        let manifest_path = lockfile_path.with_file_name(CARGO_TOML_FILE);
+
+        if !manifest_path.exists() {
+            if locked {
+                return Err(error::Error::new(
+                    error::ErrorKind::NotFound,
+                    &format!(
+                        "can't check whether {} is up to date: no {} next to it",
+                        lockfile_path.display(),
+                        CARGO_TOML_FILE
+                    ),
+                ));
+            }
+
+            return Ok(());
+        }
+
+        // Only `--locked` checks may access the network (e.g. to download
+        // dependencies' manifests), so plain audits don't get slower
+        let started = Instant::now();
+        let result = lockfile::is_up_to_date(&manifest_path, self.config.net.offline || !locked);
+
+        tracing::debug!(
+            "checked whether {} is up to date in {}ms",
+            lockfile_path.display(),
+            self.timings.record("lockfile-check", started)
+        );
+
+        let stale_message = format!(
+            "{} is out of date with {} - run `cargo update` or `cargo generate-lockfile`",
+            lockfile_path.display(),
+            manifest_path.display()
+        );
+
+        match result {
+            Ok(true) => Ok(()),
+            Ok(false) if locked => Err(error::Error::new(
+                error::ErrorKind::BadParam,
+                &stale_message,
+            )),
+            Ok(false) => {
+                if !self.quiet {
+                    status_warn!(
+                        "{} (the next build will resolve different versions than are audited)",
+                        stale_message
+                    );
+                }
+
+                Ok(())
+            }
+            Err(e) if locked => Err(error::Error::new(
+                error::ErrorKind::Io,
+                &format!(
+                    "couldn't check whether {} is up to date: {}",
+                    lockfile_path.display(),
+                    e
+                ),
+            )),
+            Err(e) => {
+                tracing::debug!(
+                    "couldn't check whether {} is up to date: {}",
+                    lockfile_path.display(),
+                    e
+                );
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Read the contents of the lockfile to be audited
+    fn read_lockfile(&self, lockfile_path: &Path) -> Result<String, error::Error> {
+        let result = if lockfile_path == Path::new("-") {
+            // Read Cargo.lock from STDIN
+            let mut lockfile_toml = String::new();
+            io::stdin()
+                .read_to_string(&mut lockfile_toml)
+                .map(|_| lockfile_toml)
+        } else {
+            std::fs::read_to_string(lockfile_path)
+        };
+
+        result.map_err(|e| {
+            error::Error::new(
+                error::ErrorKind::NotFound,
+                &format!("Couldn't load {}: {}", lockfile_path.display(), e),
+            )
+        })
+    }
+
+    /// Query the database for advisories about `cargo-audit` or `rustsec` itself
+    fn self_advisories(&mut self) -> Vec<rustsec::Advisory> {
+        let mut results = vec![];
+
+        for (package_str, version_str) in &[
+            ("cargo-audit", crate::VERSION),
+            ("rustsec", rustsec::VERSION),
+        ] {
+            let package: rustsec::package::Name = package_str.parse().unwrap();
+            let version: rustsec::Version = version_str.parse().unwrap();
+            let query = rustsec::database::Query::crate_scope();
+
+            for advisory in self
+                .database
+                .query(&query.package_version(package, version))
+            {
+                results.push(advisory.clone());
+            }
+        }
+
+        results
+    }
+}
+
+/// Report the vulnerabilities in packages which are only compiled with
+/// features that aren't enabled (if resolved, for `--feature-aware`) as
+/// `optional` warnings, which only fail the audit if they're denied
+fn add_optional_warnings(report: &mut rustsec::Report, optional: Option<&Set<Dependency>>) {
+    let optional = match optional {
+        Some(optional) if !optional.is_empty() => optional,
+        _ => return,
+    };
+
+    let (warned, list): (Vec<_>, Vec<_>) = report
+        .vulnerabilities
+        .list
+        .drain(..)
+        .partition(|vuln| optional.contains(&Dependency::from(&vuln.package)));
+
+    if warned.is_empty() {
+        report.vulnerabilities.list = list;
+        return;
+    }
+
+    let ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+    let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+    let vex = std::mem::take(&mut report.vulnerabilities.vex);
+    report.vulnerabilities = report::VulnerabilityInfo::new(list);
+    report.vulnerabilities.ignored = ignored;
+    report.vulnerabilities.acknowledged = acknowledged;
+    report.vulnerabilities.vex = vex;
+
+    report
+        .warnings
+        .entry(warning::Kind::Optional)
+        .or_default()
+        .extend(warned.into_iter().map(|vuln| {
+            let mut warning = Warning::new(
+                warning::Kind::Optional,
+                &vuln.package,
+                Some(vuln.advisory),
+                Some(vuln.versions),
+            );
+            warning.source = vuln.source;
+            warning
+        }));
+
+    report.sort();
+}
+
+/// Summarize the report's vulnerabilities, including how many direct
+/// dependencies of the workspace pull them in, and how many are in direct
+/// dependencies themselves (if the dependency graph is known). Done last, as enrichments may add vulnerabilities.
+fn summarize(report: &mut rustsec::Report, tree: Option<&Tree>) {
+    report.summary = report::Summary::new(&report.vulnerabilities);
+    report.summary.count_warnings(&report.warnings);
+    report.summary.direct_dependencies = tree.map(|tree| {
+        let graph = tree.graph();
+
+        // Every package which is, or transitively depends on, a vulnerable one
+        let vulnerable: Set<_> = report
+            .vulnerabilities
+            .list
+            .iter()
+            .filter_map(|vuln| tree.nodes().get(&Dependency::from(&vuln.package)))
+            .copied()
+            .collect();
+
+        let mut affected = Set::new();
+        let mut pending: Vec<_> = vulnerable.iter().copied().collect();
+
+        while let Some(node) = pending.pop() {
+            if affected.insert(node) {
+                pending.extend(graph.neighbors_directed(node, EdgeDirection::Incoming));
+            }
+        }
+
+        // Vulnerable roots (e.g. in a lockfile without the workspace's own
+        // packages) are direct dependencies themselves
+        let direct: Set<_> = tree
+            .roots()
+            .into_iter()
+            .flat_map(|root| {
+                graph
+                    .neighbors_directed(root, EdgeDirection::Outgoing)
+                    .chain(if vulnerable.contains(&root) {
+                        Some(root)
+                    } else {
+                        None
+                    })
+            })
+            .filter(|node| affected.contains(node))
+            .collect();
+
+        direct.len()
+    });
+
+    if tree.is_some() {
+        // As annotated on each vulnerability (see `members::annotate`)
+        let count = |direct| {
+            report
+                .vulnerabilities
+                .list
+                .iter()
+                .filter(|vuln| vuln.direct == Some(direct))
+                .count()
+        };
+
+        let (direct, transitive) = (count(true), count(false));
+        report.summary.direct_vulnerabilities = Some(direct);
+        report.summary.transitive_vulnerabilities = Some(transitive);
+    }
+}
+
+/// Outcome of failing to load the advisory database with the given error
+pub fn database_outcome(error: &error::Error) -> Outcome {
+    if error.kind() == error::ErrorKind::Stale {
+        Outcome::StaleDb
+    } else {
+        Outcome::DatabaseError
+    }
+}
+
+/// Describe how stale a database source is, if its latest commit is older
+/// than the given maximum age
+fn stale_database(
+    source: &ResolvedSource,
+    database: &rustsec::Database,
+    max_age: MaxAge,
+) -> Option<String> {
+    let updated = database.latest_commit()?.timestamp;
+    let age = SystemTime::now().duration_since(updated).ok()?;
+
+    if age <= max_age.0 {
+        return None;
+    }
+
+    let name = match &source.name {
+        Some(name) => format!("advisory database `{}`", name),
+        None => "advisory database".to_owned(),
+    };
+
+    Some(format!(
+        "{} was last updated on {} ({} days ago), longer ago than its max-age of {}",
+        name,
+        &humantime::format_rfc3339(updated).to_string()[..10],
+        age.as_secs() / 86400,
+        max_age
+    ))
+}
+
+/// Error for an advisory database which couldn't be loaded
+/// Unpack a bundle of the advisory database (a `.tar.gz` file, e.g. vendored
+/// for air-gapped builds) unless it's already been, returning the directory
+/// it's unpacked in. Bundles are keyed by the digest of their contents.
+fn unpack_bundle(path: &Path) -> Result<PathBuf, error::Error> {
+    let archive = rustsec::fs::read(path)?;
+    let digest: String = Sha256::digest(&archive)
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    let bundles_dir = DatabaseConfig::default_bundles_dir();
+    let dir = bundles_dir.join(&digest);
+
+    if dir.is_dir() {
+        return Ok(dir);
+    }
+
+    // Unpack next to the final directory, so an interrupted run never leaves
+    // a partial database behind
+    let staging = bundles_dir.join(format!("{}.partial", digest));
+
+    if staging.exists() {
+        rustsec::fs::remove_dir_all(&staging)?;
+    }
+
+    rustsec::repository::tarball::unpack(&archive, &staging).map_err(|e| {
+        let _ = rustsec::fs::remove_dir_all(&staging);
+        error::Error::new(
+            e.kind(),
+            &format!("couldn't unpack {}: {}", path.display(), e.msg()),
+        )
+    })?;
+
+    rustsec::fs::rename(&staging, &dir)?;
+    Ok(dir)
+}
+
+fn load_database_error(error: error::Error) -> error::Error {
+    error::Error::new(
+        error.kind(),
+        &format!("error loading advisory database: {}", error.msg()),
+    )
+}
+
+/// Wall-clock durations of each phase of an audit, logged in verbose mode
+/// What was audited, for post-processing its report (see
+/// [`Auditor::process_report`])
+struct Audited<'a> {
+    /// Packages which were audited
+    lockfile: &'a Lockfile,
+
+    /// Path of the lockfile, if it's that of a workspace (rather than e.g.
+    /// packages listed in a binary or an SBOM)
+    lockfile_path: Option<&'a Path>,
+
+    /// Dependency tree of the packages (if known)
+    tree: Option<&'a Tree>,
+
+    /// Packages only compiled with features which aren't enabled (with
+    /// `--feature-aware`)
+    optional: Option<&'a Set<Dependency>>,
+
+    /// Kinds of dependency each package is reachable through (if resolved,
+    /// for the fail-on policy)
+    scopes: Option<&'a Map<Dependency, Set<Scope>>>,
+
+    /// How many packages were pruned for not being compiled with the
+    /// selected features
+    pruned: usize,
+
+    /// Advisories about `cargo-audit` or `rustsec` itself
+    self_advisories: &'a [rustsec::Advisory],
+
+    /// Record the findings in the state file and send the report to webhooks?
+    record: bool,
+}
+
+#[derive(Debug, Default)]
+struct Timings(Vec<(&'static str, Duration)>);
+
+impl Timings {
+    /// Record the time elapsed since `started` for the given phase,
+    /// returning it in milliseconds
+    fn record(&mut self, phase: &'static str, started: Instant) -> u128 {
+        let elapsed = started.elapsed();
+        self.0.push((phase, elapsed));
+        elapsed.as_millis()
+    }
+
+    /// Log a summary of the time spent in each phase
+    fn log_summary(&self) {
+        let total: Duration = self.0.iter().map(|(_, elapsed)| *elapsed).sum();
+        let phases = self
+            .0
+            .iter()
+            .map(|(phase, elapsed)| format!("{} {}ms", phase, elapsed.as_millis()))
+            .collect::<Vec<_>>();
+
+        tracing::debug!(
+            "timing summary: {}ms total ({})",
+            total.as_millis(),
+            phases.join(", ")
+        );
     }
 }
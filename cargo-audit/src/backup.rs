@@ -0,0 +1,259 @@
+//! Backups of the files `cargo audit fix` modifies
+//!
+//! Before editing anything, `cargo audit fix` copies each file it's about to
+//! modify into `audit-fix-backup/<timestamp>/` in the workspace's target
+//! directory (`target`, unless `CARGO_TARGET_DIR` or cargo's
+//! `build.target-dir` say otherwise), so the changes can be rolled back if they leave the workspace broken (or on
+//! demand with `cargo audit fix --rollback`). Copies are stored with an
+//! `.audit-bak` suffix, so cargo never mistakes them for manifests.
+
+use crate::{cargo_config::CargoConfig, lockfile};
+use rustsec::{
+    error::{Error, ErrorKind},
+    fs,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the directory backups are stored in (in the workspace's target
+/// directory)
+pub const BACKUP_DIR: &str = "audit-fix-backup";
+
+/// Name of the file listing the backed up files
+pub const INDEX_FILE: &str = "backup.json";
+
+/// Suffix of backed up copies
+pub const BACKUP_SUFFIX: &str = ".audit-bak";
+
+/// Backup of a set of files
+#[derive(Clone, Debug)]
+pub struct Backup {
+    /// Directory the backup is stored in
+    dir: PathBuf,
+
+    /// Files in the backup
+    files: Vec<BackedUpFile>,
+}
+
+/// File in a backup
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BackedUpFile {
+    /// Absolute path to the original file
+    pub path: PathBuf,
+
+    /// Name of the copy in the backup directory, or `None` if the file
+    /// didn't exist (in which case restoring the backup removes it)
+    pub copy: Option<String>,
+}
+
+/// Contents of the backup's index file
+#[derive(Debug, Deserialize, Serialize)]
+struct Index {
+    /// Files in the backup
+    files: Vec<BackedUpFile>,
+}
+
+impl Backup {
+    /// Back up the given files into a new directory under `backups_dir`
+    pub fn create(backups_dir: &Path, paths: &[PathBuf]) -> Result<Self, Error> {
+        let dir = create_unique_dir(backups_dir)?;
+        let mut files: Vec<BackedUpFile> = vec![];
+
+        for path in paths {
+            let path = absolute(path)?;
+
+            if files.iter().any(|file| file.path == path) {
+                continue;
+            }
+
+            let copy = if path.exists() {
+                let name = format!(
+                    "{}-{}{}",
+                    files.len(),
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    BACKUP_SUFFIX
+                );
+                fs::copy(&path, dir.join(&name))?;
+                Some(name)
+            } else {
+                None
+            };
+
+            files.push(BackedUpFile { path, copy });
+        }
+
+        let index = serde_json::to_string_pretty(&Index {
+            files: files.clone(),
+        })
+        .map_err(|e| Error::new(ErrorKind::Parse, &e))?;
+        fs::write(dir.join(INDEX_FILE), index)?;
+
+        Ok(Self { dir, files })
+    }
+
+    /// Open the most recent backup under `backups_dir` (if there is one)
+    pub fn latest(backups_dir: &Path) -> Result<Option<Self>, Error> {
+        let entries = match fs::read_dir(backups_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut latest: Option<(u128, PathBuf)> = None;
+
+        for entry in entries {
+            let path = entry?.path();
+            let timestamp = match path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<u128>().ok())
+            {
+                Some(timestamp) => timestamp,
+                None => continue,
+            };
+
+            if latest
+                .as_ref()
+                .map_or(true, |(other, _)| timestamp > *other)
+            {
+                latest = Some((timestamp, path));
+            }
+        }
+
+        match latest {
+            Some((_, dir)) => Self::open(dir).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Open the backup stored in the given directory
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, Error> {
+        let dir = dir.into();
+        let index_path = dir.join(INDEX_FILE);
+        let index: Index =
+            serde_json::from_str(&fs::read_to_string(&index_path)?).map_err(|e| {
+                Error::new(
+                    ErrorKind::Parse,
+                    &format!("couldn't parse {}: {}", index_path.display(), e),
+                )
+            })?;
+
+        Ok(Self {
+            dir,
+            files: index.files,
+        })
+    }
+
+    /// Directory the backup is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Files in the backup
+    pub fn files(&self) -> &[BackedUpFile] {
+        &self.files
+    }
+
+    /// Restore every file to its backed up contents, removing those which
+    /// didn't exist when the backup was made
+    pub fn restore(&self) -> Result<(), Error> {
+        for file in &self.files {
+            match &file.copy {
+                Some(copy) => {
+                    fs::copy(self.dir.join(copy), &file.path)?;
+                }
+                None => match fs::remove_file(&file.path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => (),
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete the backup
+    pub fn remove(self) -> Result<(), Error> {
+        fs::remove_dir_all(&self.dir)?;
+        Ok(())
+    }
+}
+
+/// Directory backups for the workspace containing the given `Cargo.toml`
+/// are stored in: `audit-fix-backup` in the target directory cargo is
+/// configured to use, or in `target` next to the workspace's root manifest
+pub fn backups_dir(manifest_path: &Path) -> Result<PathBuf, Error> {
+    if let Some(target_dir) = configured_target_dir()? {
+        return Ok(target_dir.join(BACKUP_DIR));
+    }
+
+    let root = lockfile::workspace_root(manifest_path)?;
+    let root_dir = root.parent().unwrap_or_else(|| Path::new("."));
+    Ok(root_dir.join("target").join(BACKUP_DIR))
+}
+
+/// Find the directory backups for the workspace containing the given
+/// `Cargo.toml` are stored in, without parsing any manifests (which a broken
+/// fix may have left invalid): `audit-fix-backup` in the target directory
+/// cargo is configured to use, or the closest `target/audit-fix-backup` in
+/// the manifest's directory or its ancestors
+pub fn find_backups_dir(manifest_path: &Path) -> Result<Option<PathBuf>, Error> {
+    if let Some(target_dir) = configured_target_dir()? {
+        let dir = target_dir.join(BACKUP_DIR);
+        return Ok(Some(dir).filter(|dir| dir.is_dir()));
+    }
+
+    let manifest_path = absolute(manifest_path)?;
+
+    Ok(manifest_path
+        .parent()
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .map(|dir| dir.join("target").join(BACKUP_DIR))
+        .find(|dir| dir.is_dir()))
+}
+
+/// Target directory cargo is configured to use in the current directory
+/// (as cargo would), i.e. `CARGO_TARGET_DIR` or `build.target-dir` (if set)
+fn configured_target_dir() -> Result<Option<PathBuf>, Error> {
+    match env::var_os("CARGO_TARGET_DIR") {
+        Some(dir) if !dir.is_empty() => absolute(Path::new(&dir)).map(Some),
+        _ => Ok(CargoConfig::load()?.target_dir().map(Path::to_owned)),
+    }
+}
+
+/// Create a new directory under `parent` named after the current time (in
+/// milliseconds since the UNIX epoch), so backups sort chronologically
+fn create_unique_dir(parent: &Path) -> Result<PathBuf, Error> {
+    fs::create_dir_all(parent)?;
+
+    let mut timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+
+    loop {
+        let dir = parent.join(timestamp.to_string());
+
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => timestamp += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Absolute form of the given path (which may not exist yet)
+fn absolute(path: &Path) -> Result<PathBuf, Error> {
+    if path.is_absolute() {
+        Ok(path.to_owned())
+    } else {
+        Ok(env::current_dir()?.join(path))
+    }
+}
@@ -0,0 +1,87 @@
+//! Status badges
+//!
+//! The `badge` format summarizes a report as the JSON a [shields.io endpoint]
+//! expects, so a badge with the audit status of a repository can be shown in
+//! its README (e.g. by publishing the output of
+//! `cargo audit --format badge --output-file badge.json` from CI).
+//!
+//! [shields.io endpoint]: https://shields.io/endpoint
+
+use crate::config::BadgeConfig;
+use rustsec::{report::Outcome, warning, Report};
+use serde::{Deserialize, Serialize};
+
+/// Default label of badges
+pub const DEFAULT_LABEL: &str = "cargo audit";
+
+/// shields.io endpoint badge
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Badge {
+    /// Version of the endpoint schema (always 1)
+    pub schema_version: u8,
+
+    /// Label on the left of the badge
+    pub label: String,
+
+    /// Message on the right of the badge, e.g. `3 vulnerabilities`
+    pub message: String,
+
+    /// Color of the message, e.g. `red`
+    pub color: String,
+}
+
+impl Badge {
+    /// Summarize the given report: vulnerabilities (or warnings, if they're
+    /// denied) fail the audit, other warnings are shown unless disabled, and
+    /// otherwise there are no known issues
+    pub fn new(report: &Report, config: &BadgeConfig) -> Self {
+        let vulnerabilities = report.vulnerabilities.list.len();
+        // Warnings for the same advisory count once, however many package
+        // versions they're about
+        let warnings = warning::finding_count(report.warnings.values().flatten());
+        let denied = report.outcome == Some(Outcome::DeniedWarnings);
+
+        let (message, color) = if vulnerabilities > 0 {
+            (
+                count(vulnerabilities, "vulnerability", "vulnerabilities"),
+                color(&config.failure_color, "red"),
+            )
+        } else if warnings > 0 && denied {
+            (
+                count(warnings, "warning", "warnings"),
+                color(&config.failure_color, "red"),
+            )
+        } else if warnings > 0 && config.warnings.unwrap_or(true) {
+            (
+                count(warnings, "warning", "warnings"),
+                color(&config.warning_color, "yellow"),
+            )
+        } else {
+            (
+                "no known issues".to_owned(),
+                color(&config.clean_color, "green"),
+            )
+        };
+
+        Self {
+            schema_version: 1,
+            label: config
+                .label
+                .clone()
+                .unwrap_or_else(|| DEFAULT_LABEL.to_owned()),
+            message,
+            color,
+        }
+    }
+}
+
+/// Format a count along with the singular or plural noun
+fn count(n: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", n, if n == 1 { singular } else { plural })
+}
+
+/// Configured color, or the default one
+fn color(configured: &Option<String>, default: &str) -> String {
+    configured.clone().unwrap_or_else(|| default.to_owned())
+}
@@ -0,0 +1,205 @@
+//! Audits of every Rust binary in a set of files and directories
+//!
+//! `cargo audit bin` with several paths, glob patterns (e.g.
+//! `/opt/*/bin/*`) or `--recursive` sweeps them for executables (ELF, PE,
+//! Mach-O or WebAssembly modules) with audit data embedded by
+//! [cargo-auditable], e.g. to audit the tools deployed on a host. Directories
+//! are only searched with `--recursive`, in which case their subdirectories
+//! are searched too. Symlinks in them aren't followed, so each binary is only
+//! audited once.
+//!
+//! The executables are read and their audit data extracted in parallel, and
+//! each binary is then audited like a lockfile against the advisory
+//! database, which is loaded once for them all. Executables without audit
+//! data (and WebAssembly components, whose audit data isn't supported yet)
+//! are counted as skipped, and other files are ignored.
+//!
+//! [cargo-auditable]: https://github.com/rust-secure-code/cargo-auditable
+
+use crate::{
+    auditor::Auditor,
+    installed::{self, Binary, Found},
+};
+use rayon::prelude::*;
+use rustsec::{
+    error::{Error, ErrorKind},
+    report::DatabaseInfo,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeSet as Set,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Characters which make a path a glob pattern
+const GLOB_CHARS: &[char] = &['*', '?', '['];
+
+/// Combined report of the audits of the binaries in the given paths
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `binaries`, to tell these reports apart from lockfile audits
+    pub mode: &'static str,
+
+    /// Paths (or glob patterns) which were searched for binaries
+    pub paths: Vec<PathBuf>,
+
+    /// Information about the advisory database
+    pub database: DatabaseInfo,
+
+    /// Audited binaries, by path
+    pub binaries: Vec<Binary>,
+
+    /// Number of executables without audit data, which were skipped
+    pub skipped: usize,
+}
+
+impl Report {
+    /// Number of vulnerabilities found in all of the binaries
+    pub fn vulnerability_count(&self) -> usize {
+        self.binaries
+            .iter()
+            .map(|binary| binary.report.vulnerabilities.count)
+            .sum()
+    }
+
+    /// Number of binaries with vulnerabilities
+    pub fn vulnerable_count(&self) -> usize {
+        self.binaries
+            .iter()
+            .filter(|binary| binary.report.vulnerabilities.found)
+            .count()
+    }
+}
+
+/// Is the given path a glob pattern, rather than the path of a file?
+pub fn is_pattern(path: &Path) -> bool {
+    path.to_str()
+        .map(|path| path.contains(GLOB_CHARS))
+        .unwrap_or(false)
+}
+
+/// Find the files to check for audit data: the given files, the files
+/// matching the given glob patterns, and (if `recursive`) the files under
+/// the given directories
+pub fn discover(paths: &[PathBuf], recursive: bool) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Set::new();
+
+    for path in paths {
+        let matches = if is_pattern(path) {
+            expand(path)?
+        } else {
+            vec![path.clone()]
+        };
+
+        for path in matches {
+            let metadata = fs::metadata(&path).map_err(|e| read_error(&path, &e))?;
+
+            if metadata.is_dir() {
+                if !recursive {
+                    return Err(Error::new(
+                        ErrorKind::BadParam,
+                        &format!(
+                            "{} is a directory (use --recursive to audit the binaries in it)",
+                            path.display()
+                        ),
+                    ));
+                }
+
+                search(&path, &mut files)?;
+            } else if metadata.is_file() {
+                files.insert(path);
+            }
+        }
+    }
+
+    Ok(files.into_iter().collect())
+}
+
+/// Audit each binary with audit data among the given paths (see
+/// [`discover`]), reading them in parallel
+pub fn audit(auditor: &mut Auditor, paths: &[PathBuf], recursive: bool) -> Result<Report, Error> {
+    let files = discover(paths, recursive)?;
+
+    let executables = files
+        .par_iter()
+        .map(|path| installed::scan(path).map(|found| (path, found)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut report = Report {
+        mode: "binaries",
+        paths: paths.to_vec(),
+        database: DatabaseInfo::new(auditor.database()),
+        binaries: vec![],
+        skipped: 0,
+    };
+
+    for (path, found) in executables {
+        match found {
+            Found::AuditData(compressed) => {
+                report
+                    .binaries
+                    .push(installed::audit_embedded(auditor, path, &compressed)?);
+            }
+            Found::Executable | Found::WasmComponent => report.skipped += 1,
+            Found::Other => (),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Expand a glob pattern into the paths matching it, failing if none do
+fn expand(pattern: &Path) -> Result<Vec<PathBuf>, Error> {
+    let pattern = pattern.to_string_lossy();
+    let invalid = |e: &dyn std::fmt::Display| {
+        Error::new(
+            ErrorKind::BadParam,
+            &format!("invalid pattern {}: {}", pattern, e),
+        )
+    };
+
+    let paths = glob::glob(&pattern)
+        .map_err(|e| invalid(&e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(&e))?;
+
+    if paths.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            &format!("no files match {}", pattern),
+        ));
+    }
+
+    Ok(paths)
+}
+
+/// Add the files in the given directory and its subdirectories, without
+/// following symlinks
+fn search(dir: &Path, files: &mut Set<PathBuf>) -> Result<(), Error> {
+    let entries = fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| read_error(dir, &e))?;
+
+    for entry in entries {
+        let file_type = entry
+            .file_type()
+            .map_err(|e| read_error(&entry.path(), &e))?;
+
+        if file_type.is_dir() {
+            search(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            files.insert(entry.path());
+        }
+    }
+
+    Ok(())
+}
+
+/// Error for a file or directory which couldn't be read
+fn read_error(path: &Path, e: &io::Error) -> Error {
+    Error::new(
+        ErrorKind::Io,
+        &format!("couldn't read {}: {}", path.display(), e),
+    )
+}
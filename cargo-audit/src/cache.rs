@@ -0,0 +1,168 @@
+//! Cache of audit reports, used to skip re-auditing unchanged lockfiles
+//!
+//! Each audited lockfile has a single cache entry, which is only replayed if
+//! the lockfile's contents, the advisory database commit, and the effective
+//! configuration are all unchanged since the report was generated.
+
+use crate::config::{AuditConfig, CacheConfig};
+use rustsec::{
+    error::{Error, ErrorKind},
+    fs, Advisory, Report,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Name of the cache directory (located in `~/.cargo`)
+pub const CACHE_DIR: &str = "audit-cache";
+
+/// Cache of audit reports
+#[derive(Clone, Debug)]
+pub struct Cache {
+    /// Directory cache entries are stored in
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Open the cache located in the given directory (created on first use)
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Open the cache configured in `audit.toml`
+    pub fn from_config(config: &CacheConfig) -> Self {
+        Self::new(config.path.clone().unwrap_or_else(Self::default_path))
+    }
+
+    /// Default location of the cache: `~/.cargo/audit-cache`
+    pub fn default_path() -> PathBuf {
+        home::cargo_home()
+            .map(|cargo_home| cargo_home.join(CACHE_DIR))
+            .unwrap_or_else(|_| PathBuf::from(CACHE_DIR))
+    }
+
+    /// Directory cache entries are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the cached report for the given lockfile, if its key still matches
+    pub fn get(&self, lockfile_path: &Path, key: &Key) -> Option<Entry> {
+        let contents = fs::read_to_string(self.entry_path(lockfile_path)).ok()?;
+
+        match serde_json::from_str::<Entry>(&contents) {
+            Ok(entry) if entry.key == *key => Some(entry),
+            Ok(_) => {
+                tracing::debug!(
+                    "cached report for {} is out of date",
+                    lockfile_path.display()
+                );
+                None
+            }
+            Err(e) => {
+                tracing::debug!("ignoring invalid cache entry: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Store the report for the given lockfile, replacing any previous entry
+    pub fn insert(&self, lockfile_path: &Path, entry: &Entry) -> Result<(), Error> {
+        let json = serde_json::to_string(entry).map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't serialize cache entry: {}", e),
+            )
+        })?;
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.entry_path(lockfile_path), json)?;
+        Ok(())
+    }
+
+    /// Remove all cache entries, returning how many were removed
+    pub fn clear(&self) -> Result<usize, Error> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Path to the cache entry for the given lockfile
+    fn entry_path(&self, lockfile_path: &Path) -> PathBuf {
+        let lockfile_path =
+            fs::canonicalize(lockfile_path).unwrap_or_else(|_| lockfile_path.to_owned());
+
+        let digest = sha256(lockfile_path.to_string_lossy().as_bytes());
+        self.dir.join(format!("{}.json", digest))
+    }
+}
+
+/// Inputs which determine the outcome of an audit
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Key {
+    /// SHA-256 digest of the lockfile's contents
+    pub lockfile: String,
+
+    /// Commit ID of the advisory database (the IDs of each source, joined
+    /// with `+`, if there are several)
+    pub database_commit: String,
+
+    /// SHA-256 digest of the configuration options which affect the report
+    /// (and of the report's schema version)
+    pub config: String,
+}
+
+impl Key {
+    /// Compute the cache key for the given lockfile contents, advisory
+    /// database commit, and configuration
+    pub fn new(lockfile: &str, database_commit: &str, config: &AuditConfig) -> Self {
+        let config_json = serde_json::to_string(&(
+            rustsec::report::SCHEMA_VERSION,
+            config.report_settings(),
+            config.yanked.enabled,
+            config.database.stale,
+            &config.features,
+        ))
+        .expect("config serializes as JSON");
+
+        Self {
+            lockfile: sha256(lockfile.as_bytes()),
+            database_commit: database_commit.to_owned(),
+            config: sha256(config_json.as_bytes()),
+        }
+    }
+}
+
+/// Cached audit results for a lockfile
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+    /// Key the report was generated for
+    pub key: Key,
+
+    /// Audit report
+    pub report: Report,
+
+    /// Advisories about `cargo-audit` or `rustsec` themselves
+    pub self_advisories: Vec<Advisory>,
+}
+
+/// Compute the hex-encoded SHA-256 digest of the given data
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,357 @@
+//! Cargo's configuration of where crates.io packages come from
+//!
+//! Organizations which can't reach crates.io directly replace it with a
+//! mirror via cargo's source replacement, e.g.:
+//!
+//! ```toml
+//! [source.crates-io]
+//! replace-with = "internal"
+//!
+//! [source.internal]
+//! registry = "sparse+https://crates.example.com/index/"
+//! ```
+//!
+//! So registry lookups (e.g. for yanked crates and patched releases) go to
+//! the same place as cargo's, cargo's configuration is loaded the same way
+//! cargo loads it: from `.cargo/config.toml` in the current directory and
+//! each of its parents, then from `$CARGO_HOME`, with the nearest files
+//! taking precedence. Tokens for sparse registries are read from cargo's
+//! credentials (`$CARGO_HOME/credentials.toml`) or
+//! `CARGO_REGISTRIES_<NAME>_TOKEN`.
+
+use rustsec::error::{Error, ErrorKind};
+use std::{
+    collections::BTreeSet as Set,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the crates.io source in cargo's configuration
+pub const CRATES_IO: &str = "crates-io";
+
+/// Cargo's configuration, merged from every file it's loaded from
+#[derive(Clone, Debug, Default)]
+pub struct CargoConfig {
+    /// Merged configuration
+    table: toml::value::Table,
+
+    /// Files the configuration was loaded from, with the most precedent first
+    files: Vec<PathBuf>,
+
+    /// Target directory set by `build.target-dir` (if any), resolved
+    /// relative to the file which set it
+    target_dir: Option<PathBuf>,
+}
+
+/// Registry which replaces crates.io
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Replacement {
+    /// Name of the replacement source
+    pub source: String,
+
+    /// Index of the replacement
+    pub index: ReplacementIndex,
+
+    /// Token to authenticate to the index with (if it's a sparse index with
+    /// a configured token)
+    pub token: Option<Token>,
+}
+
+/// Index of a registry replacing crates.io
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReplacementIndex {
+    /// Git index, with its URL
+    Git(String),
+
+    /// Sparse index, with its URL (without the `sparse+` prefix)
+    Sparse(String),
+
+    /// Local registry or directory of vendored crates, which has no index to
+    /// look releases up in
+    Local(PathBuf),
+}
+
+impl fmt::Display for ReplacementIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplacementIndex::Git(url) => write!(f, "git index {}", url),
+            ReplacementIndex::Sparse(url) => write!(f, "sparse index {}", url),
+            ReplacementIndex::Local(path) => write!(f, "local source {}", path.display()),
+        }
+    }
+}
+
+/// Registry token, which is never displayed
+#[derive(Clone, Eq, PartialEq)]
+pub struct Token(String);
+
+impl Token {
+    /// Get the token
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Token(..)")
+    }
+}
+
+impl CargoConfig {
+    /// Load cargo's configuration as cargo would in the current directory
+    pub fn load() -> Result<Self, Error> {
+        let cargo_home = home::cargo_home().ok();
+        Self::load_from(&env::current_dir()?, cargo_home.as_deref())
+    }
+
+    /// Load cargo's configuration as cargo would in the given directory,
+    /// with the given `$CARGO_HOME`
+    pub fn load_from(dir: &Path, cargo_home: Option<&Path>) -> Result<Self, Error> {
+        let mut files = vec![];
+
+        for ancestor in dir.ancestors() {
+            files.extend(config_file(&ancestor.join(".cargo"), "config"));
+        }
+
+        if let Some(cargo_home) = cargo_home {
+            let home_config = config_file(cargo_home, "config");
+
+            if let Some(home_config) = home_config {
+                // `$CARGO_HOME` may be the `.cargo` directory of an ancestor
+                if !files.contains(&home_config) {
+                    files.push(home_config);
+                }
+            }
+        }
+
+        let mut config = Self::default();
+
+        // Credentials take precedence over the configuration files
+        let credentials = cargo_home.and_then(|cargo_home| config_file(cargo_home, "credentials"));
+
+        for path in files.iter().rev().chain(credentials.iter()) {
+            let contents = fs::read_to_string(path).map_err(|e| {
+                Error::new(
+                    ErrorKind::Io,
+                    &format!("couldn't read {}: {}", path.display(), e),
+                )
+            })?;
+
+            let table: toml::value::Table = toml::from_str(&contents).map_err(|e| {
+                Error::new(
+                    ErrorKind::Parse,
+                    &format!("couldn't parse {}: {}", path.display(), e),
+                )
+            })?;
+
+            // Relative paths are relative to the directory containing the
+            // `.cargo` directory the file is in
+            if let Some(target_dir) = table
+                .get("build")
+                .and_then(|build| build.get("target-dir"))
+                .and_then(toml::Value::as_str)
+            {
+                let base = path.parent().and_then(Path::parent).unwrap_or(dir);
+                config.target_dir = Some(base.join(target_dir));
+            }
+
+            merge(&mut config.table, table);
+        }
+
+        config.files = files;
+        Ok(config)
+    }
+
+    /// Files the configuration was loaded from, with the most precedent first
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Target directory set by `build.target-dir` (if any)
+    pub fn target_dir(&self) -> Option<&Path> {
+        self.target_dir.as_deref()
+    }
+
+    /// Find the registry replacing crates.io (if any), following chains of
+    /// replacements as cargo does
+    pub fn crates_io_replacement(&self) -> Result<Option<Replacement>, Error> {
+        let mut name = CRATES_IO.to_owned();
+        let mut seen = Set::new();
+
+        while let Some(next) = self.source_str(&name, "replace-with") {
+            seen.insert(name);
+
+            if seen.contains(next) {
+                return Err(Error::new(
+                    ErrorKind::BadParam,
+                    &format!("source replacement of `{}` is circular", CRATES_IO),
+                ));
+            }
+
+            name = next.to_owned();
+        }
+
+        if name == CRATES_IO {
+            return Ok(None);
+        }
+
+        let index = if let Some(url) = self.source_str(&name, "registry") {
+            index_url(url)
+        } else if let Some(path) = self
+            .source_str(&name, "local-registry")
+            .or_else(|| self.source_str(&name, "directory"))
+        {
+            ReplacementIndex::Local(PathBuf::from(path))
+        } else if let Some(url) = self.registry_index(&name) {
+            index_url(&url)
+        } else {
+            return Err(Error::new(
+                ErrorKind::BadParam,
+                &format!(
+                    "`{}` is replaced with source `{}`, which has no registry index configured",
+                    CRATES_IO, name
+                ),
+            ));
+        };
+
+        let token = match &index {
+            ReplacementIndex::Sparse(url) => self.token_for(&name, url),
+            _ => None,
+        };
+
+        Ok(Some(Replacement {
+            source: name,
+            index,
+            token,
+        }))
+    }
+
+    /// Get a string value of the given source's table
+    fn source_str(&self, source: &str, key: &str) -> Option<&str> {
+        self.table
+            .get("source")
+            .and_then(|sources| sources.get(source))
+            .and_then(|source| source.get(key))
+            .and_then(toml::Value::as_str)
+    }
+
+    /// Get a string value of the given registry's table
+    fn registry_str(&self, registry: &str, key: &str) -> Option<&str> {
+        self.table
+            .get("registries")
+            .and_then(|registries| registries.get(registry))
+            .and_then(|registry| registry.get(key))
+            .and_then(toml::Value::as_str)
+    }
+
+    /// Index URL of the registry with the given name, from the environment
+    /// or the configuration
+    fn registry_index(&self, registry: &str) -> Option<String> {
+        env::var(registry_env_var(registry, "INDEX"))
+            .ok()
+            .or_else(|| self.registry_str(registry, "index").map(ToOwned::to_owned))
+    }
+
+    /// Token of the registry with the given name, or if there's no such
+    /// registry, of the one with the given (sparse) index URL
+    fn token_for(&self, registry: &str, url: &str) -> Option<Token> {
+        let mut names = vec![registry.to_owned()];
+
+        if let Some(registries) = self.table.get("registries").and_then(toml::Value::as_table) {
+            for name in registries.keys() {
+                let same_index = match self.registry_index(name) {
+                    Some(index) => index_url(&index) == ReplacementIndex::Sparse(url.to_owned()),
+                    None => false,
+                };
+
+                if same_index && name != registry {
+                    names.push(name.clone());
+                }
+            }
+        }
+
+        names.iter().find_map(|name| {
+            env::var(registry_env_var(name, "TOKEN"))
+                .ok()
+                .or_else(|| self.registry_str(name, "token").map(ToOwned::to_owned))
+                .map(Token)
+        })
+    }
+}
+
+/// Find the registry replacing crates.io in cargo's configuration for the
+/// current directory (if any), logging it (at the info level if `debug`)
+pub fn crates_io_replacement(debug: bool) -> Result<Option<Replacement>, Error> {
+    let replacement = CargoConfig::load()?.crates_io_replacement()?;
+
+    if let Some(replacement) = &replacement {
+        let message = format!(
+            "crates.io is replaced with source `{}` ({})",
+            replacement.source, replacement.index
+        );
+
+        if debug {
+            tracing::info!("{}", message);
+        } else {
+            tracing::debug!("{}", message);
+        }
+    }
+
+    Ok(replacement)
+}
+
+/// Path to the configuration file with the given name in a directory, which
+/// may or may not have a `.toml` extension (cargo prefers the file without)
+fn config_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    let without_extension = dir.join(name);
+
+    if without_extension.is_file() {
+        return Some(without_extension);
+    }
+
+    let with_extension = dir.join(format!("{}.toml", name));
+
+    if with_extension.is_file() {
+        Some(with_extension)
+    } else {
+        None
+    }
+}
+
+/// Merge a more precedent configuration table into another, as cargo does
+fn merge(table: &mut toml::value::Table, other: toml::value::Table) {
+    for (key, value) in other {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+                merge(existing, value)
+            }
+            (Some(toml::Value::Array(existing)), toml::Value::Array(value)) => {
+                existing.extend(value)
+            }
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Parse the URL of a registry's index, which is a git index unless it has
+/// a `sparse+` prefix
+fn index_url(url: &str) -> ReplacementIndex {
+    if url.starts_with("sparse+") {
+        ReplacementIndex::Sparse(url.trim_start_matches("sparse+").to_owned())
+    } else {
+        ReplacementIndex::Git(url.trim_start_matches("registry+").to_owned())
+    }
+}
+
+/// Name of the environment variable cargo reads the given setting of a
+/// registry from, e.g. `CARGO_REGISTRIES_MY_REGISTRY_TOKEN`
+fn registry_env_var(registry: &str, setting: &str) -> String {
+    format!(
+        "CARGO_REGISTRIES_{}_{}",
+        registry.to_ascii_uppercase().replace('-', "_"),
+        setting
+    )
+}
@@ -4,8 +4,10 @@ mod audit;
 
 use self::audit::AuditCommand;
 use crate::config::AuditConfig;
-use abscissa_core::{config::Override, Command, Configurable, FrameworkError, Options, Runnable};
-use std::{ops::Deref, path::PathBuf};
+use abscissa_core::{
+    config::Override, Command, Configurable, FrameworkError, FrameworkErrorKind, Options, Runnable,
+};
+use std::{env, ops::Deref, path::PathBuf};
 
 /// Name of the configuration file (located in `~/.cargo`)
 ///
@@ -20,36 +22,91 @@ pub enum CargoAuditCommand {
     Audit(AuditCommand),
 }
 
+/// Location of the workspace's `audit.toml`: in the `.cargo` directory of
+/// the current directory, or of the nearest parent directory with one (like
+/// Cargo's own configuration files)
+pub fn project_config_path() -> PathBuf {
+    let current_dir = match env::current_dir() {
+        Ok(dir) => dir,
+        Err(_) => return PathBuf::from("./.cargo").join(CONFIG_FILE),
+    };
+
+    let home_config = home_config_path();
+
+    current_dir
+        .ancestors()
+        .map(|dir| dir.join(".cargo").join(CONFIG_FILE))
+        .find(|path| path.exists() && Some(path) != home_config.as_ref())
+        .unwrap_or_else(|| current_dir.join(".cargo").join(CONFIG_FILE))
+}
+
+/// Location of the current user's `audit.toml` (in `~/.cargo`)
+pub fn home_config_path() -> Option<PathBuf> {
+    home::cargo_home()
+        .ok()
+        .map(|cargo_home| cargo_home.join(CONFIG_FILE))
+}
+
+/// Locations of the `audit.toml` files to load (those which exist), in
+/// increasing order of precedence:
+///
+/// 1. The current user's `~/.cargo/audit.toml`, e.g. for organization-wide
+///    defaults.
+/// 2. The current workspace's `.cargo/audit.toml`, which overrides them.
+pub fn config_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = home_config_path()
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect();
+
+    let project_config = project_config_path();
+
+    if project_config.exists() && !paths.contains(&project_config) {
+        paths.push(project_config);
+    }
+
+    paths
+}
+
+/// Location of the `audit.toml` with the highest precedence (if any exist),
+/// which is the one edited to record ignores and acknowledgments
+pub fn config_path() -> Option<PathBuf> {
+    config_paths().pop()
+}
+
 impl Configurable<AuditConfig> for CargoAuditCommand {
-    /// Location of `audit.toml` (if it exists)
+    /// Location of the `audit.toml` with the highest precedence (if any
+    /// exist), which is loaded layered over the others
     fn config_path(&self) -> Option<PathBuf> {
-        // Check if the config file exists, and if it does not, ignore it.
-        //
-        // The order of precedence for which config file to use is:
-        // 1. The current project's `.cargo` configuration directory.
-        // 2. The current user's home directory configuration.
-
-        let project_config_filename = PathBuf::from("./.cargo").join(CONFIG_FILE);
-        if project_config_filename.exists() {
-            return Some(project_config_filename);
+        // `cargo audit config check` loads the configuration itself, so it
+        // can report every problem with it (rather than failing on the first)
+        if self.is_config_check() {
+            return None;
         }
 
-        let home_config_filename = home::cargo_home()
-            .ok()
-            .map(|cargo_home| cargo_home.join(CONFIG_FILE))?;
-
-        if home_config_filename.exists() {
-            Some(home_config_filename)
-        } else {
-            None
-        }
+        config_path()
     }
 
-    /// Override loaded config with explicit command-line arguments
-    fn process_config(&self, config: AuditConfig) -> Result<AuditConfig, FrameworkError> {
-        match self {
-            CargoAuditCommand::Audit(cmd) => cmd.override_config(config),
+    /// Override loaded config with environment variables and explicit
+    /// command-line arguments (in that order of increasing precedence)
+    fn process_config(&self, mut config: AuditConfig) -> Result<AuditConfig, FrameworkError> {
+        if self.is_config_check() {
+            return Ok(config);
         }
+
+        config
+            .override_from_env()
+            .map_err(|e| FrameworkErrorKind::ConfigError.context(e))?;
+
+        let config = match self {
+            CargoAuditCommand::Audit(cmd) => cmd.override_config(config)?,
+        };
+
+        config
+            .validate()
+            .map_err(|e| FrameworkErrorKind::ConfigError.context(e))?;
+
+        Ok(config)
     }
 }
 
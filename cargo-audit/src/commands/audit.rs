@@ -1,28 +1,80 @@
 //! The `cargo audit` subcommand
 
+mod ack;
+mod bin;
+mod cache;
+mod config;
+mod db;
+mod diff;
 #[cfg(feature = "fix")]
 mod fix;
+mod history;
+mod ignore;
+mod image;
+mod installed;
+mod lint;
+mod manifest;
+mod search;
+mod verify;
+mod vex;
+mod whats_new;
 
 use super::CargoAuditCommand;
 use crate::{
     auditor::Auditor,
-    config::{AuditConfig, DenyOption, OutputFormat},
+    commands,
+    config::{
+        AuditConfig, Color, ConfigEditor, DenyOption, Hyperlinks, NotifyFormat, NotifyOn,
+        OutputFormat, SortOrder, WarnOption,
+    },
+    lockfile::{self, CARGO_LOCK_FILE},
+    output,
     prelude::*,
+    presenter::Presenter,
+    state,
+    triage::{self, Triage},
+    watch::Watcher,
+    workspaces,
 };
-use abscissa_core::{config::Override, terminal::ColorChoice, FrameworkError};
+use abscissa_core::{command::Usage, config::Override, terminal::ColorChoice, FrameworkError};
 use gumdrop::Options;
-use rustsec::database::scope;
 use rustsec::platforms::target::{Arch, OS};
-use std::{path::PathBuf, process::exit};
+use rustsec::{
+    advisory,
+    database::scope,
+    report::{Outcome, OverriddenAction},
+    vulnerability::Scope,
+    Error,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::exit,
+};
 
+use self::ack::AckCommand;
+use self::bin::BinCommand;
+use self::cache::CacheCommand;
+use self::config::ConfigCommand;
+use self::db::{DbCommand, StatsCommand};
+use self::diff::DiffCommand;
 #[cfg(feature = "fix")]
 use self::fix::FixCommand;
+use self::history::HistoryCommand;
+use self::ignore::IgnoreCommand;
+use self::image::ImageCommand;
+use self::installed::InstalledCommand;
+use self::lint::LintCommand;
+use self::manifest::ManifestCommand;
+use self::search::SearchCommand;
+use self::verify::VerifyCommand;
+use self::vex::VexCommand;
+use self::whats_new::WhatsNewCommand;
 
 /// The `cargo audit` subcommand
 #[derive(Command, Default, Debug, Options)]
 pub struct AuditCommand {
-    /// Optional subcommand (used for `cargo audit fix`)
-    #[cfg(feature = "fix")]
+    /// Optional subcommand (e.g. `cargo audit fix`)
     #[options(command)]
     subcommand: Option<AuditSubcommand>,
 
@@ -38,25 +90,56 @@ pub struct AuditCommand {
     #[options(
         short = "c",
         long = "color",
-        help = "color configuration: always, never (default: auto)"
+        meta = "WHEN",
+        help = "color configuration: auto, always, never (default: auto, which honors NO_COLOR)"
     )]
-    color: Option<String>,
+    color: Option<Color>,
 
-    /// Filesystem path to the advisory database git repository
+    /// Advisory database sources to use (or a path, as with `--db-path`, or
+    /// the URL of another database to merge)
     #[options(
         long = "db",
-        help = "advisory database git repo path (default: ~/.cargo/advisory-db)"
+        meta = "NAME",
+        help = "only use this configured advisory database source (repeatable), a path as with --db-path, or the git URL of another database to merge (repeatable)"
+    )]
+    db: Vec<String>,
+
+    /// Filesystem path to the advisory database: a git repository, or a
+    /// vendored directory or bundle
+    #[options(
+        no_short,
+        long = "db-path",
+        meta = "PATH",
+        help = "advisory database path: a git repo, or a vendored directory or .tar.gz bundle (default: $CARGO_HOME/advisory-db)"
     )]
-    db: Option<PathBuf>,
+    db_path: Option<PathBuf>,
 
     /// Deny flag
     #[options(
         short = "D",
         long = "deny",
-        help = "exit with an error on: warnings (any), unmaintained, unsound, yanked"
+        help = "exit with an error on: warnings (any), notice, unmaintained, unsound, yanked, overridden, duplicate-versions, optional, other-platform, known-exploited (warnings:KIND also accepted)"
     )]
     deny: Vec<DenyOption>,
 
+    /// Optional checks which produce warnings
+    #[options(
+        no_short,
+        long = "warn",
+        meta = "CHECK",
+        help = "also warn about: notice, unmaintained, unsound (informational advisories), duplicate-versions (older versions of crates which are also locked at a newer version)"
+    )]
+    warn: Vec<WarnOption>,
+
+    /// Warnings to silence
+    #[options(
+        no_short,
+        long = "allow",
+        meta = "CHECK",
+        help = "don't warn about (even if warned about or denied otherwise): notice, unmaintained, unsound, duplicate-versions"
+    )]
+    allow: Vec<WarnOption>,
+
     /// Deny warnings (legacy)
     #[options(
         no_short,
@@ -73,6 +156,110 @@ pub struct AuditCommand {
     )]
     file: Option<PathBuf>,
 
+    /// CycloneDX SBOM to audit instead of `Cargo.lock`
+    #[options(
+        no_short,
+        long = "sbom",
+        help = "audit the crates in a CycloneDX SBOM (JSON or XML) instead of Cargo.lock"
+    )]
+    sbom: Option<PathBuf>,
+
+    /// Output of `cargo metadata` to audit instead of `Cargo.lock`
+    #[options(
+        no_short,
+        long = "from-metadata",
+        meta = "PATH",
+        help = "audit the dependency graph in `cargo metadata --locked` output (or `-` for STDIN) instead of Cargo.lock"
+    )]
+    from_metadata: Option<PathBuf>,
+
+    /// Audit every lockfile under the current directory
+    #[options(
+        no_short,
+        long = "recursive",
+        help = "audit every Cargo.lock under the current directory (e.g. the workspaces of a monorepo)"
+    )]
+    recursive: bool,
+
+    /// Same as `--recursive`
+    #[options(no_short, long = "workspace", help = "same as --recursive")]
+    workspace: bool,
+
+    /// Generate `Cargo.lock` if it's missing
+    #[options(
+        no_short,
+        long = "generate-lockfile",
+        help = "run `cargo generate-lockfile` if Cargo.lock is missing"
+    )]
+    generate_lockfile: bool,
+
+    /// Fail if `Cargo.lock` is out of date with `Cargo.toml`
+    #[options(
+        no_short,
+        long = "locked",
+        help = "fail if Cargo.lock is out of date with Cargo.toml (instead of warning)"
+    )]
+    locked: bool,
+
+    /// Equivalent to `--locked --offline`
+    #[options(
+        no_short,
+        long = "frozen",
+        help = "equivalent to specifying both --locked and --offline"
+    )]
+    frozen: bool,
+
+    /// Path to `Cargo.toml`
+    #[options(
+        no_short,
+        long = "manifest-path",
+        meta = "PATH",
+        help = "path to Cargo.toml (Cargo.lock is found at the workspace root)"
+    )]
+    manifest_path: Option<PathBuf>,
+
+    /// Features to enable
+    #[options(
+        no_short,
+        long = "features",
+        meta = "FEATURES",
+        help = "only audit packages compiled with these features (space or comma separated)"
+    )]
+    features: Vec<String>,
+
+    /// Enable all features
+    #[options(
+        no_short,
+        long = "all-features",
+        help = "only audit packages compiled with all features enabled"
+    )]
+    all_features: bool,
+
+    /// Don't enable the `default` feature
+    #[options(
+        no_short,
+        long = "no-default-features",
+        help = "only audit packages compiled without the `default` feature"
+    )]
+    no_default_features: bool,
+
+    /// Report vulnerabilities in packages which aren't compiled as warnings
+    #[options(
+        no_short,
+        long = "feature-aware",
+        help = "warn about (rather than prune) packages not compiled with the selected or default features"
+    )]
+    feature_aware: bool,
+
+    /// Number of threads to use
+    #[options(
+        short = "j",
+        long = "jobs",
+        meta = "N",
+        help = "number of parallel jobs (default: number of CPUs)"
+    )]
+    jobs: Option<usize>,
+
     /// Advisory IDs to ignore
     #[options(
         no_short,
@@ -82,6 +269,58 @@ pub struct AuditCommand {
     )]
     ignore: Vec<String>,
 
+    /// Advisory categories to ignore
+    #[options(
+        no_short,
+        long = "ignore-category",
+        meta = "CATEGORY",
+        help = "ignore vulnerabilities whose advisories only have this category, e.g. denial-of-service (can be specified multiple times)"
+    )]
+    ignore_category: Vec<advisory::Category>,
+
+    /// Advisory categories to report
+    #[options(
+        no_short,
+        long = "categories",
+        meta = "CATEGORIES",
+        help = "only report vulnerabilities whose advisories have one of these categories, e.g. memory-corruption,crypto-failure (comma separated)"
+    )]
+    categories: Vec<String>,
+
+    /// Advisory categories not to report
+    #[options(
+        no_short,
+        long = "exclude-categories",
+        meta = "LIST",
+        help = "don't report vulnerabilities whose advisories have any of these categories (comma separated)"
+    )]
+    exclude_categories: Vec<String>,
+
+    /// OpenVEX documents to suppress vulnerabilities with
+    #[options(
+        no_short,
+        long = "vex",
+        meta = "PATH",
+        help = "suppress the vulnerabilities this OpenVEX document marks not_affected or fixed (can be specified multiple times)"
+    )]
+    vex: Vec<PathBuf>,
+
+    /// Match withdrawn advisories
+    #[options(
+        no_short,
+        long = "include-withdrawn",
+        help = "also match withdrawn advisories, which were filed in error"
+    )]
+    include_withdrawn: bool,
+
+    /// Check whether affected functions are referenced in the workspace
+    #[options(
+        no_short,
+        long = "reachability",
+        help = "check whether the functions advisories list as affected are referenced in the workspace's source code"
+    )]
+    reachability: bool,
+
     /// Skip fetching the advisory database git repository
     #[options(
         short = "n",
@@ -94,11 +333,257 @@ pub struct AuditCommand {
     #[options(no_short, long = "stale", help = "allow stale database")]
     stale: bool,
 
+    /// Verify the advisory database's signatures
+    #[options(
+        no_short,
+        long = "verify-db",
+        help = "fail unless the fetched advisory database's latest commit is signed by one of `database.signing_keys`"
+    )]
+    verify_db: bool,
+
+    /// Cache audit reports
+    #[options(
+        no_short,
+        long = "cache",
+        help = "replay the cached report if nothing changed since the last audit"
+    )]
+    cache: bool,
+
+    /// Don't use cached audit reports
+    #[options(
+        no_short,
+        long = "no-cache",
+        help = "always audit, even if report caching is enabled"
+    )]
+    no_cache: bool,
+
+    /// Fetch EPSS scores for vulnerabilities
+    #[options(
+        no_short,
+        long = "epss",
+        help = "show EPSS scores (likelihood of exploitation) for CVEs"
+    )]
+    epss: bool,
+
+    /// Only report vulnerabilities at least this likely to be exploited
+    #[options(
+        no_short,
+        long = "epss-threshold",
+        meta = "SCORE",
+        help = "only report vulnerabilities with an EPSS score of at least SCORE (0-1; implies --epss)"
+    )]
+    epss_threshold: Option<f64>,
+
+    /// Look up when matched advisories were first added to the database
+    #[options(
+        no_short,
+        long = "first-published",
+        help = "look up when each matched advisory was first added to the advisory database's git history"
+    )]
+    first_published: bool,
+
+    /// Audit against the advisory database as of the given date
+    #[options(
+        no_short,
+        long = "as-of",
+        meta = "DATE",
+        help = "audit against the advisory database as it was on this date (YYYY-MM-DD)"
+    )]
+    as_of: Option<String>,
+
+    /// Check vulnerabilities against the Known Exploited Vulnerabilities catalog
+    #[options(
+        no_short,
+        long = "kev",
+        help = "flag vulnerabilities in CISA's Known Exploited Vulnerabilities catalog"
+    )]
+    kev: bool,
+
+    /// Path to a local copy of the Known Exploited Vulnerabilities catalog
+    #[options(
+        no_short,
+        long = "kev-file",
+        meta = "PATH",
+        help = "KEV catalog JSON file to use instead of downloading it (implies --kev)"
+    )]
+    kev_file: Option<PathBuf>,
+
+    /// Don't access the network
+    #[options(
+        no_short,
+        long = "offline",
+        help = "don't access the network (implies --no-fetch, skips EPSS scores and registry lookups)"
+    )]
+    offline: bool,
+
+    /// Fail if anything would access the network
+    #[options(
+        no_short,
+        long = "strict-offline",
+        help = "like --offline, but fail if the configuration would otherwise access the network"
+    )]
+    strict_offline: bool,
+
+    /// Don't look up patched releases in the registry
+    #[options(
+        no_short,
+        long = "no-registry-lookup",
+        help = "don't check the crates.io index for published patched releases"
+    )]
+    no_registry_lookup: bool,
+
+    /// Log which index each registry lookup uses
+    #[options(
+        no_short,
+        long = "registry-debug",
+        help = "log which index each registry lookup uses (e.g. a crates.io mirror)"
+    )]
+    registry_debug: bool,
+
+    /// Webhooks to POST the JSON report to
+    #[options(
+        no_short,
+        long = "notify-url",
+        meta = "URL",
+        help = "POST the JSON report to the given URL (repeatable)"
+    )]
+    notify_url: Vec<String>,
+
+    /// Extra HTTP headers to send to webhooks
+    #[options(
+        no_short,
+        long = "notify-header",
+        meta = "NAME:VALUE",
+        help = "send the given header to webhooks, substituting ${VAR}s (repeatable)"
+    )]
+    notify_header: Vec<String>,
+
+    /// When to send reports to webhooks
+    #[options(
+        no_short,
+        long = "notify-on",
+        meta = "WHEN",
+        help = "send reports to webhooks on: findings (default), always, new (only new findings)"
+    )]
+    notify_on: Option<NotifyOn>,
+
+    /// Payload to send to webhooks
+    #[options(
+        no_short,
+        long = "notify-format",
+        meta = "FORMAT",
+        help = "payload to send to webhooks: json (the report, default), slack"
+    )]
+    notify_format: Option<NotifyFormat>,
+
+    /// Fail if a report can't be delivered to a webhook
+    #[options(
+        no_short,
+        long = "notify-required",
+        help = "exit with status 3 if a report can't be delivered to a webhook"
+    )]
+    notify_required: bool,
+
+    /// File recording findings across runs
+    #[options(
+        no_short,
+        long = "state-file",
+        meta = "PATH",
+        help = "mark findings which are new since the run recorded in this file (and update it)"
+    )]
+    state_file: Option<PathBuf>,
+
+    /// Don't update the state file
+    #[options(
+        no_short,
+        long = "no-update-state",
+        help = "compare findings to the state file without updating it"
+    )]
+    no_update_state: bool,
+
+    /// Which findings fail the audit
+    #[options(
+        no_short,
+        long = "fail-on",
+        meta = "WHICH",
+        help = "only fail on findings which are: new (since --state-file or --baseline)"
+    )]
+    fail_on: Option<String>,
+
+    /// Don't fail the audit on vulnerabilities only in dev-dependencies
+    #[options(
+        no_short,
+        long = "no-dev-deps",
+        help = "don't fail the audit on vulnerabilities only reachable through dev-dependencies"
+    )]
+    no_dev_deps: bool,
+
+    /// Baseline to compare findings to
+    #[options(
+        no_short,
+        long = "baseline",
+        meta = "PATH",
+        help = "only fail on findings which aren't in this baseline (from --write-baseline, or `cargo audit --json`)"
+    )]
+    baseline: Option<PathBuf>,
+
+    /// Record the findings in a baseline
+    #[options(
+        no_short,
+        long = "write-baseline",
+        meta = "PATH",
+        help = "record the findings in a baseline file, for later runs with --baseline"
+    )]
+    write_baseline: Option<PathBuf>,
+
+    /// Audit again whenever the lockfile or advisory database changes
+    #[options(
+        no_short,
+        long = "watch",
+        help = "keep auditing whenever Cargo.lock or the advisory database changes, printing what changed"
+    )]
+    watch: bool,
+
+    /// Minutes between fetches of the advisory database in watch mode
+    #[options(
+        no_short,
+        long = "watch-interval",
+        meta = "MINUTES",
+        help = "fetch the advisory database every MINUTES in watch mode (default: 60)"
+    )]
+    watch_interval: Option<u64>,
+
+    /// Triage each finding interactively, recording ignores in audit.toml
+    #[options(
+        no_short,
+        long = "interactive",
+        help = "step through the findings, choosing to ignore, fix later or fail each (requires a TTY)"
+    )]
+    interactive: bool,
+
+    /// Severity below which vulnerabilities don't fail the audit
+    #[options(
+        no_short,
+        long = "severity",
+        meta = "SEVERITY",
+        help = "only fail on vulnerabilities of at least: none, low, medium, high, critical (default: none)"
+    )]
+    severity: Option<advisory::Severity>,
+
+    /// Target triple to find vulnerabilities for
+    #[options(
+        no_short,
+        long = "target",
+        meta = "TRIPLE",
+        help = "report vulnerabilities for other platforms than this target as warnings"
+    )]
+    target: Option<String>,
+
     /// Target CPU architecture to find vulnerabilities for
     #[options(
         no_short,
         long = "target-arch",
-        help = "filter vulnerabilities by CPU (default: no filter)"
+        help = "report vulnerabilities for other CPUs as warnings (default: no filter)"
     )]
     target_arch: Option<Arch>,
 
@@ -106,7 +591,7 @@ pub struct AuditCommand {
     #[options(
         no_short,
         long = "target-os",
-        help = "filter vulnerabilities by OS (default: no filter)"
+        help = "report vulnerabilities for other OSes as warnings (default: no filter)"
     )]
     target_os: Option<OS>,
 
@@ -122,57 +607,509 @@ pub struct AuditCommand {
     )]
     quiet: bool,
 
+    /// Verbose mode - log progress and timings to STDERR
+    #[options(
+        short = "v",
+        long = "verbose",
+        count,
+        help = "Log each phase with timings to STDERR (-vv for more detail)"
+    )]
+    verbose: u32,
+
+    /// Order of vulnerabilities and warnings in terminal reports
+    #[options(
+        no_short,
+        long = "sort",
+        meta = "ORDER",
+        help = "sort terminal reports by: id, package, severity (default: severity)"
+    )]
+    sort: Option<SortOrder>,
+
+    /// Render advisory IDs and URLs as hyperlinks
+    #[options(
+        no_short,
+        long = "hyperlinks",
+        meta = "WHEN",
+        help = "render advisory IDs and URLs as terminal hyperlinks: auto, always, never (default: auto)"
+    )]
+    hyperlinks: Option<Hyperlinks>,
+
+    /// Render terminal reports as plain text which doesn't depend on the
+    /// terminal
+    #[options(
+        no_short,
+        long = "plain",
+        help = "render terminal reports as plain text for pipes and CI logs (no colors, hyperlinks or wrapping)"
+    )]
+    plain: bool,
+
+    /// Template to render vulnerabilities in terminal reports with
+    #[options(
+        no_short,
+        long = "template",
+        meta = "PATH",
+        help = "render each vulnerability in terminal reports with this template file"
+    )]
+    template: Option<PathBuf>,
+
     /// Output reports as JSON
     #[options(no_short, long = "json", help = "Output report in JSON format")]
     output_json: bool,
 
-    /// Vulnerability querying does not consider local crates
+    /// Output format
     #[options(
         no_short,
-        long = "no-local-crates",
-        help = "Vulnerability querying does not consider local crates"
+        long = "format",
+        meta = "FORMAT",
+        help = "output format: terminal (default), json, ndjson (one line per finding), badge (shields.io endpoint JSON), sarif (SARIF 2.1.0 for code scanning), markdown (tables for pull request comments), junit (JUnit XML for test dashboards), osv (OSV advisories, as osv-scanner reports them), spdx (SPDX 2.3 document), fix-plan (JSON array of remediation actions), metrics (Prometheus metrics), html (standalone page for archiving or emailing)"
     )]
-    no_local_crates: bool,
-}
+    format: Option<OutputFormat>,
 
-/// Subcommands of `cargo audit`
-#[cfg(feature = "fix")]
-#[derive(Command, Debug, Options, Runnable)]
-pub enum AuditSubcommand {
-    /// `cargo audit fix` subcommand
-    #[options(help = "automatically upgrade vulnerable dependencies")]
-    Fix(FixCommand),
-}
+    /// Program to render the report with
+    #[options(
+        no_short,
+        long = "reporter",
+        meta = "PROGRAM",
+        help = "render the report with a program, which reads the findings as NDJSON on STDIN"
+    )]
+    reporter: Option<String>,
 
-impl AuditCommand {
-    /// Get the color configuration
-    pub fn color_config(&self) -> Option<ColorChoice> {
-        self.color.as_ref().map(|colors| match colors.as_ref() {
-            "always" => ColorChoice::Always,
-            "never" => ColorChoice::Never,
-            _ => panic!("invalid color choice setting: {}", &colors),
-        })
-    }
-}
+    /// Arguments to run the reporter with
+    #[options(
+        no_short,
+        long = "reporter-arg",
+        meta = "ARG",
+        help = "run the --reporter program with the given argument (repeatable)"
+    )]
+    reporter_arg: Vec<String>,
 
-impl Override<AuditConfig> for AuditCommand {
-    fn override_config(&self, mut config: AuditConfig) -> Result<AuditConfig, FrameworkError> {
-        if let Some(db) = &self.db {
-            config.database.path = Some(db.into());
-        }
+    /// Write machine-readable reports to a file
+    #[options(
+        no_short,
+        long = "output-file",
+        meta = "PATH",
+        help = "write the report to a file rather than STDOUT (with terminal reports, also write the JSON report to it)"
+    )]
+    output_file: Option<PathBuf>,
 
-        for advisory_id in &self.ignore {
-            config
-                .advisories
-                .ignore
-                .push(advisory_id.parse().unwrap_or_else(|e| {
-                    status_err!("error parsing {}: {}", advisory_id, e);
-                    exit(1);
-                }));
-        }
+    /// Write a DOT graph of the dependency paths to vulnerable packages
+    #[options(
+        no_short,
+        long = "emit-graph",
+        meta = "PATH",
+        help = "write the dependency paths to vulnerable/warned crates to a Graphviz (DOT) file"
+    )]
+    emit_graph: Option<PathBuf>,
+
+    /// Include the whole dependency graph in the DOT graph
+    #[options(
+        no_short,
+        long = "emit-graph-full",
+        help = "include the whole dependency graph with --emit-graph"
+    )]
+    emit_graph_full: bool,
+
+    /// Show advisory descriptions
+    #[options(
+        no_short,
+        long = "show-description",
+        help = "show the description of each advisory found"
+    )]
+    show_description: bool,
+
+    /// Include the inventory of audited packages in the report
+    #[options(
+        no_short,
+        long = "include-inventory",
+        help = "include the packages audited (and counts of those skipped) in the report"
+    )]
+    include_inventory: bool,
+
+    /// Depth to show dependency trees to
+    #[options(
+        no_short,
+        long = "tree-depth",
+        meta = "N",
+        help = "show dependency trees down to this depth"
+    )]
+    tree_depth: Option<usize>,
+
+    /// Show only the shortest path to each affected package
+    #[options(
+        no_short,
+        long = "shortest-path",
+        help = "show only the shortest path from each affected package to a workspace member"
+    )]
+    shortest_path: bool,
+
+    /// Width to wrap the report to
+    #[options(
+        no_short,
+        long = "width",
+        meta = "COLUMNS",
+        help = "wrap the report to this width (default: terminal width, or 80)"
+    )]
+    width: Option<usize>,
+
+    /// Vulnerability querying does not consider local crates
+    #[options(
+        no_short,
+        long = "no-local-crates",
+        help = "Vulnerability querying does not consider local crates"
+    )]
+    no_local_crates: bool,
+
+    /// Vulnerability querying does not consider git crates
+    #[options(
+        no_short,
+        long = "no-git-crates",
+        help = "Vulnerability querying does not consider git crates (e.g. patched forks)"
+    )]
+    no_git_crates: bool,
+
+    /// How to report vulnerabilities in git or path dependencies
+    #[options(
+        no_short,
+        long = "overridden",
+        meta = "ACTION",
+        help = "report vulnerable git/path dependencies (e.g. [patch] forks) as: fail, warn (default), ignore"
+    )]
+    overridden: Option<OverriddenAction>,
+
+    /// Explain how the lockfile's packages were matched against an advisory
+    #[options(
+        no_short,
+        long = "explain",
+        meta = "ADVISORY_ID",
+        help = "explain how the lockfile's packages were matched against an advisory, and exit"
+    )]
+    explain: Option<String>,
+}
+
+/// Subcommands of `cargo audit`
+#[derive(Command, Debug, Options, Runnable)]
+pub enum AuditSubcommand {
+    /// `cargo audit ack` subcommand
+    #[options(help = "acknowledge an advisory until a deadline, recording it in audit.toml")]
+    Ack(AckCommand),
+
+    /// `cargo audit bin` subcommand
+    #[options(help = "audit a binary built with cargo auditable, e.g. a deployed artifact")]
+    Bin(BinCommand),
+
+    /// `cargo audit cache` subcommand
+    #[options(help = "manage the cache of audit reports")]
+    Cache(CacheCommand),
+
+    /// `cargo audit config` subcommand
+    #[options(help = "check or show the audit.toml configuration")]
+    Config(ConfigCommand),
+
+    /// `cargo audit db` subcommand
+    #[options(help = "inspect the advisory database")]
+    Db(DbCommand),
+
+    /// `cargo audit diff` subcommand
+    #[options(help = "compare the findings for two lockfiles or git revisions")]
+    Diff(DiffCommand),
+
+    /// `cargo audit fix` subcommand
+    #[cfg(feature = "fix")]
+    #[options(help = "automatically upgrade vulnerable dependencies")]
+    Fix(FixCommand),
+
+    /// `cargo audit history` subcommand
+    #[options(help = "audit the lockfile at past git revisions, showing when advisories matched")]
+    History(HistoryCommand),
+
+    /// `cargo audit ignore` subcommand
+    #[options(help = "ignore an advisory by recording it in audit.toml")]
+    Ignore(IgnoreCommand),
+
+    /// `cargo audit image` subcommand
+    #[options(
+        help = "audit the Rust binaries in a container image (tarball or registry reference)"
+    )]
+    Image(ImageCommand),
+
+    /// `cargo audit installed` subcommand
+    #[options(help = "audit the binaries installed with cargo install")]
+    Installed(InstalledCommand),
+
+    /// `cargo audit lint` subcommand
+    #[options(help = "lint advisory files before submitting them to an advisory database")]
+    Lint(LintCommand),
+
+    /// `cargo audit manifest` subcommand
+    #[options(help = "check a Cargo.toml's dependency requirements without a lockfile")]
+    Manifest(ManifestCommand),
+
+    /// `cargo audit search` subcommand
+    #[options(
+        help = "search the advisory database by text, crate, ID, severity, date or category"
+    )]
+    Search(SearchCommand),
+
+    /// `cargo audit stats` subcommand (same as `cargo audit db stats`)
+    #[options(help = "show statistics about the advisory database (same as `db stats`)")]
+    Stats(StatsCommand),
+
+    /// `cargo audit verify` subcommand
+    #[options(
+        help = "check the integrity of a lockfile (checksums, yanked crates, git revisions, resolution)"
+    )]
+    Verify(VerifyCommand),
+
+    /// `cargo audit vex` subcommand
+    #[options(help = "export the advisories ignored in audit.toml as an OpenVEX document")]
+    Vex(VexCommand),
+
+    /// `cargo audit whats-new` subcommand
+    #[options(help = "list the advisories added since a commit, date or snapshot of the database")]
+    WhatsNew(WhatsNewCommand),
+}
+
+impl AuditCommand {
+    /// Get the color configuration: from the command line, the environment
+    /// or `audit.toml`. The terminal is set up before the configuration is
+    /// loaded (to report any problems with it), so this reads it ahead,
+    /// ignoring problems until they're reported.
+    pub fn color_config(&self) -> ColorChoice {
+        let mut config = AuditConfig::load_layers(&commands::config_paths()).unwrap_or_default();
+
+        // Invalid environment variables are reported when the configuration
+        // is loaded, too
+        let _ = config.override_from_env();
+
+        if let Some(color) = self.color {
+            config.output.color = color;
+        }
+
+        config.output.plain |= self.plain;
+        config.output.color_choice()
+    }
+
+    /// Get the number of times `-v` was given
+    pub fn verbosity(&self) -> u32 {
+        self.verbose
+    }
+
+    /// Is this `cargo audit config check`, which loads the configuration
+    /// itself?
+    pub fn is_config_check(&self) -> bool {
+        matches!(&self.subcommand, Some(AuditSubcommand::Config(config)) if config.is_check())
+    }
+}
+
+impl Override<AuditConfig> for AuditCommand {
+    fn override_config(&self, mut config: AuditConfig) -> Result<AuditConfig, FrameworkError> {
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        if let Some(format) = self.format {
+            config.output.format = format;
+        }
+
+        if let Some(program) = &self.reporter {
+            config.output.reporter = Some(program.clone());
+        }
+
+        config
+            .output
+            .reporter_args
+            .extend(self.reporter_arg.iter().cloned());
+
+        if let Some(path) = &self.output_file {
+            config.output.file = Some(path.clone());
+        }
+
+        // Keep STDOUT free of anything but machine-readable reports
+        output::status_to_stderr(config.output.is_machine_readable());
+
+        for db in &self.db {
+            if config.database.sources.contains_key(db) {
+                config.database.only.push(db.clone());
+            } else if db.contains("://") || db.starts_with("git@") {
+                config.database.urls.push(db.clone());
+            } else {
+                config.database.path = Some(db.into());
+            }
+        }
+
+        if let Some(db_path) = &self.db_path {
+            config.database.path = Some(db_path.clone());
+        }
+
+        for advisory_id in &self.ignore {
+            let advisory_id: advisory::Id = advisory_id.parse().unwrap_or_else(|e| {
+                status_err!("error parsing {}: {}", advisory_id, e);
+                exit(1);
+            });
+
+            config.advisories.ignore.push(advisory_id.into());
+        }
+
+        config
+            .advisories
+            .ignore_categories
+            .extend(self.ignore_category.iter().cloned());
+
+        config
+            .advisories
+            .categories
+            .extend(category_list(&self.categories));
+
+        config
+            .advisories
+            .exclude_categories
+            .extend(category_list(&self.exclude_categories));
+
+        config.advisories.vex.extend(self.vex.iter().cloned());
+        config.advisories.include_withdrawn |= self.include_withdrawn;
+        config.advisories.reachability |= self.reachability;
+
+        if self.no_fetch {
+            config.database.fetch = false;
+        }
 
-        config.database.fetch |= !self.no_fetch;
         config.database.stale |= self.stale;
+        config.database.verify |= self.verify_db;
+
+        if self.no_cache {
+            config.cache.enabled = false;
+        } else if self.cache {
+            config.cache.enabled = true;
+        }
+
+        config.epss.enabled |= self.epss;
+
+        if let Some(threshold) = self.epss_threshold {
+            config.epss.threshold = Some(threshold);
+        }
+
+        config.database.first_published |= self.first_published;
+
+        if let Some(as_of) = &self.as_of {
+            config.database.as_of = Some(as_of.parse().unwrap_or_else(|e| {
+                status_err!("invalid --as-of date {}: {}", as_of, e);
+                exit(2);
+            }));
+        }
+        config.kev.enabled |= self.kev;
+
+        if let Some(kev_file) = &self.kev_file {
+            config.kev.file = Some(kev_file.clone());
+        }
+
+        config.net.offline |= self.offline || self.frozen;
+        config.net.strict_offline |= self.strict_offline;
+
+        if self.no_registry_lookup {
+            config.registry.lookup = false;
+        }
+
+        if self.registry_debug {
+            config.registry.debug = true;
+        }
+
+        config.notify.urls.extend(self.notify_url.iter().cloned());
+
+        for header in &self.notify_header {
+            let mut parts = header.splitn(2, ':');
+
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) if !name.trim().is_empty() => {
+                    config
+                        .notify
+                        .headers
+                        .insert(name.trim().to_owned(), value.trim().to_owned());
+                }
+                _ => {
+                    status_err!("invalid --notify-header (expected NAME:VALUE): {}", header);
+                    exit(2);
+                }
+            }
+        }
+
+        if let Some(notify_on) = self.notify_on {
+            config.notify.on = notify_on;
+        }
+
+        if let Some(notify_format) = self.notify_format {
+            config.notify.format = notify_format;
+        }
+
+        config.notify.required |= self.notify_required;
+
+        if let Some(path) = &self.state_file {
+            config.state.file = Some(path.clone());
+        }
+
+        if self.no_update_state {
+            config.state.update = Some(false);
+        }
+
+        if let Some(path) = &self.baseline {
+            config.state.baseline = Some(path.clone());
+        }
+
+        if let Some(path) = &self.write_baseline {
+            config.state.write_baseline = Some(path.clone());
+        }
+
+        if let Some(minutes) = self.watch_interval {
+            config.watch.fetch_interval_minutes = Some(minutes);
+        }
+
+        match self.fail_on.as_deref() {
+            Some("new") => config.state.fail_on_new = true,
+            Some(other) => {
+                status_err!("invalid --fail-on (expected `new`): {}", other);
+                exit(2);
+            }
+            None => (),
+        }
+
+        if self.no_dev_deps {
+            let kinds = config
+                .output
+                .dependency_kinds
+                .take()
+                .unwrap_or_else(Scope::all);
+            config.output.dependency_kinds = Some(
+                kinds
+                    .into_iter()
+                    .filter(|&kind| kind != Scope::Dev)
+                    .collect(),
+            );
+        }
+
+        if let Some(severity) = self.severity {
+            config.output.fail_severity = Some(severity);
+        }
+
+        config.lockfile.generate |= self.generate_lockfile;
+        config.lockfile.locked |= self.locked || self.frozen;
+
+        config.features.features.extend(
+            self.features
+                .iter()
+                .flat_map(|features| features.split(|c: char| c == ',' || c.is_whitespace()))
+                .filter(|feature| !feature.is_empty())
+                .map(ToOwned::to_owned),
+        );
+        config.features.all_features |= self.all_features;
+        config.features.no_default_features |= self.no_default_features;
+        config.features.feature_aware |= self.feature_aware;
+
+        if let Some(manifest_path) = &self.manifest_path {
+            config.features.manifest_path = Some(manifest_path.clone());
+        }
+
+        if let Some(target) = &self.target {
+            config.target.triple = Some(target.clone());
+        }
 
         if let Some(target_arch) = self.target_arch {
             config.target.arch = Some(target_arch);
@@ -189,44 +1126,113 @@ impl Override<AuditConfig> for AuditCommand {
         if self.deny_warnings {
             // TODO(tarcieri): remove this in the next release of cargo-audit
             status_warn!("the --deny-warnings flag is deprecated. Please use: --deny warnings");
-            config.output.deny = DenyOption::all();
+            config.output.deny(DenyOption::Warnings);
         } else {
             for kind in &self.deny {
-                if *kind == DenyOption::Warnings {
-                    config.output.deny = DenyOption::all();
-                } else {
-                    config.output.deny.push(*kind);
-                }
+                config.output.deny(*kind);
+            }
+        }
+
+        for option in &self.warn {
+            if !config.output.warn.contains(option) {
+                config.output.warn.push(*option);
+            }
+        }
+
+        for option in &self.allow {
+            if !config.output.allow.contains(option) {
+                config.output.allow.push(*option);
             }
         }
 
         config.output.quiet |= self.quiet;
+        config.output.verbose |= self.verbose > 0;
 
-        if self.output_json {
-            config.output.format = OutputFormat::Json;
+        if let Some(path) = &self.emit_graph {
+            config.output.graph = Some(path.clone());
+        }
+
+        config.output.graph_full |= self.emit_graph_full;
+        config.output.show_description |= self.show_description;
+        config.output.include_inventory |= self.include_inventory;
+        config.output.shortest_path |= self.shortest_path;
+
+        if let Some(depth) = self.tree_depth {
+            config.output.tree_depth = Some(depth);
+        }
+
+        if let Some(width) = self.width {
+            config.output.width = Some(width);
+        }
+
+        if let Some(sort) = self.sort {
+            config.output.sort = Some(sort);
+        }
+
+        if let Some(hyperlinks) = self.hyperlinks {
+            config.output.hyperlinks = hyperlinks;
+        }
+
+        if let Some(color) = self.color {
+            config.output.color = color;
+        }
+
+        config.output.plain |= self.plain;
+
+        if let Some(template) = &self.template {
+            config.output.template = Some(template.clone());
         }
 
         if self.no_local_crates {
             config.packages.source = Some(scope::Registry::Public)
         }
 
+        config.packages.skip_git |= self.no_git_crates;
+
+        if let Some(overridden) = self.overridden {
+            config.packages.overridden = overridden;
+        }
+
+        if config.net.strict_offline {
+            let uses = config.network_uses();
+
+            if !uses.is_empty() {
+                for network_use in &uses {
+                    status_err!(
+                        "--strict-offline: would access the network for {}",
+                        network_use
+                    );
+                }
+
+                exit(2);
+            }
+
+            config.net.offline = true;
+        }
+
         Ok(config)
     }
 }
 
 impl Runnable for AuditCommand {
     fn run(&self) {
-        #[cfg(feature = "fix")]
-        match &self.subcommand {
-            Some(AuditSubcommand::Fix(fix)) => {
-                fix.run();
-                exit(0)
+        if let Some(jobs) = self.jobs {
+            if let Err(e) = rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build_global()
+            {
+                status_err!("couldn't start {} jobs: {}", jobs, e);
+                exit(2);
             }
-            None => (),
+        }
+
+        if let Some(subcommand) = &self.subcommand {
+            subcommand.run();
+            exit(0)
         }
 
         if self.help {
-            Self::print_usage_and_exit(&[]);
+            self.print_help();
         }
 
         if self.version {
@@ -234,28 +1240,266 @@ impl Runnable for AuditCommand {
             exit(0);
         }
 
-        let lockfile_path = self.file.as_deref();
-        let report = self.auditor().audit(lockfile_path);
+        let lockfile_path = match self.lockfile_path() {
+            Ok(path) => path,
+            Err(e) => {
+                status_err!("{}", e);
+                exit(2);
+            }
+        };
+
+        let lockfile_options =
+            self.file.is_some() || self.manifest_path.is_some() || self.explain.is_some();
+
+        if self.sbom.is_some() && lockfile_options {
+            status_err!("--sbom can't be used with --file, --manifest-path or --explain");
+            exit(2);
+        }
+
+        if self.from_metadata.is_some() && (self.sbom.is_some() || lockfile_options) {
+            status_err!(
+                "--from-metadata can't be used with --sbom, --file, --manifest-path or --explain"
+            );
+            exit(2);
+        }
+
+        if self.watch {
+            if self.sbom.is_some()
+                || self.from_metadata.is_some()
+                || self.explain.is_some()
+                || self.recursive
+                || self.workspace
+            {
+                status_err!(
+                    "--watch can't be used with --sbom, --from-metadata, --recursive or --explain"
+                );
+                exit(2);
+            }
+
+            if lockfile_path.as_deref() == Some(Path::new("-")) {
+                status_err!("--watch can't audit a lockfile read from STDIN");
+                exit(2);
+            }
+        }
+
+        if self.interactive {
+            if self.watch || self.recursive || self.workspace || self.explain.is_some() {
+                status_err!("--interactive can't be used with --watch, --recursive or --explain");
+                exit(2);
+            }
+
+            if app_config().output.is_machine_readable() {
+                status_err!(
+                    "--interactive can't be used with machine-readable output formats or --reporter"
+                );
+                exit(2);
+            }
+
+            if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stdout) {
+                status_err!("--interactive can only be used from a terminal");
+                exit(2);
+            }
+        }
+
+        if self.recursive || self.workspace {
+            if self.sbom.is_some() || self.from_metadata.is_some() || lockfile_options {
+                status_err!(
+                    "--recursive can't be used with --sbom, --from-metadata, --file, \
+                     --manifest-path or --explain"
+                );
+                exit(2);
+            }
+
+            self.audit_workspaces();
+        }
+
+        if let Some(id) = &self.explain {
+            self.explain(id, lockfile_path.as_deref());
+        }
+
+        let exit_codes = app_config().output.exit_codes.clone();
+        let mut auditor = self.auditor();
+
+        if self.watch {
+            let path = lockfile_path.unwrap_or_else(|| PathBuf::from(CARGO_LOCK_FILE));
+            let mut watcher = Watcher::new(auditor, &path, &app_config().watch);
+
+            if let Err(e) = watcher.start() {
+                status_err!("{}", e);
+                exit(exit_codes.code(Outcome::OperationalError));
+            }
+
+            watcher.run();
+        }
+
+        let report = match (&self.sbom, &self.from_metadata) {
+            (Some(sbom_path), _) => auditor.audit_sbom(sbom_path),
+            (_, Some(metadata_path)) => auditor.audit_metadata(metadata_path),
+            (None, None) => auditor.audit(lockfile_path.as_deref()),
+        };
 
         match report {
-            Ok(report) => {
-                if report.vulnerabilities.found {
-                    exit(1);
+            Ok(mut report) => {
+                if self.interactive {
+                    self.triage(&mut auditor, &mut report);
                 }
-                exit(0);
+
+                exit(auditor.exit_code(&report))
             }
             Err(e) => {
                 status_err!("{}", e);
-                exit(2);
+                exit(exit_codes.code(Outcome::OperationalError));
             }
         };
     }
 }
 
 impl AuditCommand {
+    /// Print usage information, along with the exit status of each outcome
+    /// (as configured), and exit
+    fn print_help(&self) -> ! {
+        if Usage::for_command::<Self>().print_subcommand(&[]).is_err() {
+            exit(1);
+        }
+
+        let exit_codes = &app_config().output.exit_codes;
+        println!("EXIT CODES:");
+
+        for outcome in Outcome::all() {
+            println!("    {:<24}{}", outcome.as_str(), exit_codes.code(outcome));
+        }
+
+        exit(0);
+    }
+
+    /// Triage the report's findings interactively, writing the ignores and
+    /// acknowledgments chosen to the config file and applying them to the
+    /// report
+    fn triage(&self, auditor: &mut Auditor, report: &mut rustsec::Report) {
+        let exit_codes = app_config().output.exit_codes.clone();
+        let today = state::today();
+        let tree = auditor.dependency_tree();
+
+        let findings = triage::findings(report);
+
+        if findings.is_empty() {
+            return;
+        }
+
+        let stdin = io::stdin();
+        let decisions = Triage::new(stdin.lock(), io::stdout(), today.clone())
+            .run(&findings, tree.as_deref())
+            .unwrap_or_else(|e| {
+                status_err!("{}", e);
+                exit(exit_codes.code(Outcome::OperationalError));
+            });
+
+        let config_path = commands::config_path().unwrap_or_else(commands::project_config_path);
+
+        let saved = ConfigEditor::open(&config_path).and_then(|mut editor| {
+            let saved = triage::save(&mut editor, &findings, &decisions, &today)?;
+
+            if saved > 0 {
+                editor.save()?;
+            }
+
+            Ok(saved)
+        });
+
+        match saved {
+            Ok(0) => (),
+            Ok(saved) => status_ok!(
+                "Saved",
+                "{} triaged {} to {}",
+                saved,
+                if saved == 1 { "advisory" } else { "advisories" },
+                config_path.display()
+            ),
+            Err(e) => {
+                status_err!("{}", e);
+                exit(exit_codes.code(Outcome::OperationalError));
+            }
+        }
+
+        auditor.apply_triage(report, &decisions, &today);
+    }
+
+    /// Explain how the lockfile's packages were matched against the advisory
+    /// with the given ID, and exit
+    fn explain(&self, id: &str, lockfile_path: Option<&Path>) -> ! {
+        let exit_codes = app_config().output.exit_codes.clone();
+
+        let result = id
+            .parse::<advisory::Id>()
+            .and_then(|id| self.auditor().explain(&id, lockfile_path));
+
+        match result {
+            Ok(_) => exit(0),
+            Err(e) => {
+                status_err!("{}", e);
+                exit(exit_codes.code(Outcome::OperationalError));
+            }
+        }
+    }
+
+    /// Audit every lockfile under the current directory, print the combined
+    /// report, and exit
+    fn audit_workspaces(&self) -> ! {
+        let config = app_config();
+        let exit_codes = &config.output.exit_codes;
+
+        // Each lockfile's findings would be compared against the others'
+        if config.state.file.is_some()
+            || config.state.baseline.is_some()
+            || config.state.write_baseline.is_some()
+            || config.state.fail_on_new
+        {
+            status_err!(
+                "--recursive can't be used with a state file, --baseline, --write-baseline or --fail-on new"
+            );
+            exit(2);
+        }
+
+        let mut auditor = Auditor::with_database(Auditor::load_database(&config), &config);
+
+        let report =
+            workspaces::audit(&mut auditor, Path::new("."), exit_codes).unwrap_or_else(|e| {
+                status_err!("{}", e);
+                exit(exit_codes.code(Outcome::OperationalError));
+            });
+
+        if let Err(e) = Presenter::new(&config.output).print_workspaces(&report) {
+            status_err!("{}", e);
+            exit(exit_codes.code(Outcome::OperationalError));
+        }
+
+        exit(report.exit_code);
+    }
+
     /// Initialize `Auditor`
     pub fn auditor(&self) -> Auditor {
         let config = app_config();
         Auditor::new(&config)
     }
+
+    /// Locate `Cargo.lock` from the `--file` and `--manifest-path` options
+    pub fn lockfile_path(&self) -> Result<Option<PathBuf>, Error> {
+        lockfile::resolve(self.file.as_deref(), self.manifest_path.as_deref())
+    }
+}
+
+/// Parse lists of advisory categories separated by commas or whitespace
+/// (e.g. given with `--categories`)
+fn category_list(lists: &[String]) -> Vec<advisory::Category> {
+    lists
+        .iter()
+        .flat_map(|list| list.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|category| !category.is_empty())
+        .map(|category| {
+            category.parse().unwrap_or_else(|e| {
+                status_err!("error parsing {}: {}", category, e);
+                exit(1);
+            })
+        })
+        .collect()
 }
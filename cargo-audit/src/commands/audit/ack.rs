@@ -0,0 +1,191 @@
+//! The `cargo audit ack` subcommand
+
+use crate::{
+    acknowledgments,
+    auditor::Auditor,
+    commands,
+    config::{advisory_id_problem, Acknowledgment, ConfigEditor},
+    prelude::*,
+    state,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::advisory;
+use std::process::exit;
+
+#[derive(Command, Default, Debug, Options)]
+pub struct AckCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Ticket tracking the remediation of the advisory
+    #[options(
+        short = "t",
+        long = "ticket",
+        help = "ticket tracking the remediation of the advisory, e.g. JIRA-123 (required)"
+    )]
+    ticket: Option<String>,
+
+    /// Number of days the advisory is acknowledged for
+    #[options(
+        short = "d",
+        long = "days",
+        meta = "N",
+        help = "number of days until the acknowledgment expires (required)"
+    )]
+    days: Option<u64>,
+
+    /// Perform a dry run
+    #[options(
+        no_short,
+        long = "dry-run",
+        help = "print the entry instead of writing it to audit.toml"
+    )]
+    dry_run: bool,
+
+    /// List the acknowledgments instead of adding one
+    #[options(
+        no_short,
+        long = "list-acks",
+        help = "list the acknowledgments in audit.toml and when they expire"
+    )]
+    list_acks: bool,
+
+    /// Advisory ID to acknowledge
+    #[options(free, help = "advisory ID to acknowledge")]
+    advisory_id: Vec<String>,
+}
+
+impl AckCommand {
+    /// Parse and validate the acknowledgment to be recorded
+    fn acknowledgment(&self) -> Acknowledgment {
+        let advisory_id = match self.advisory_id.as_slice() {
+            [advisory_id] => advisory_id,
+            _ => {
+                status_err!("expected exactly one advisory ID (e.g. RUSTSEC-2019-0001)");
+                exit(2);
+            }
+        };
+
+        let id: advisory::Id = advisory_id.parse().unwrap_or_else(|e| {
+            status_err!("invalid advisory ID {:?}: {}", advisory_id, e);
+            exit(2);
+        });
+
+        if let Some(problem) = advisory_id_problem(&id) {
+            status_err!("{}", problem);
+            exit(2);
+        }
+
+        let ticket = match &self.ticket {
+            Some(ticket) if !ticket.trim().is_empty() => ticket.trim().to_owned(),
+            _ => {
+                status_err!("a --ticket tracking the remediation of {} is required", id);
+                exit(2);
+            }
+        };
+
+        let days = match self.days {
+            Some(days) if days > 0 => days,
+            _ => {
+                status_err!("the number of --days to acknowledge {} for is required", id);
+                exit(2);
+            }
+        };
+
+        let today = state::today();
+
+        Acknowledgment {
+            id,
+            ticket,
+            expires: acknowledgments::days_after(&today, days),
+            date: Some(today),
+        }
+    }
+
+    /// List the configured acknowledgments
+    fn list(&self) {
+        let config = app_config();
+        let today = state::today();
+
+        if config.advisories.acknowledged.is_empty() {
+            status_ok!("Acknowledged", "no advisories");
+            return;
+        }
+
+        for acknowledgment in &config.advisories.acknowledged {
+            let remaining = if acknowledgment.is_active(&today) {
+                match acknowledgments::days_between(&today, &acknowledgment.expires) {
+                    1 => "1 day remaining".to_owned(),
+                    days => format!("{} days remaining", days),
+                }
+            } else {
+                "expired".to_owned()
+            };
+
+            println!(
+                "{}  ticket {}  expires {} ({})",
+                acknowledgment.id,
+                acknowledgment.ticket,
+                acknowledgment.expires.as_str(),
+                remaining
+            );
+        }
+    }
+}
+
+impl Runnable for AckCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        if self.list_acks {
+            self.list();
+            return;
+        }
+
+        let acknowledgment = self.acknowledgment();
+        let database = Auditor::load_database(&app_config());
+
+        if database.get(&acknowledgment.id).is_none() {
+            status_err!(
+                "{:?} was not found in the advisory database",
+                acknowledgment.id.as_str()
+            );
+            exit(1);
+        }
+
+        let config_path = commands::config_path().unwrap_or_else(commands::project_config_path);
+
+        let mut editor = ConfigEditor::open(&config_path).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(1);
+        });
+
+        let updated = editor.acknowledge(&acknowledgment).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(1);
+        });
+
+        if self.dry_run {
+            println!("{}", acknowledgment.to_toml());
+            return;
+        }
+
+        if let Err(e) = editor.save() {
+            status_err!("couldn't write {}: {}", config_path.display(), e);
+            exit(1);
+        }
+
+        status_ok!(
+            if updated { "Updated" } else { "Acknowledged" },
+            "{} until {} (ticket {}) in {}",
+            acknowledgment.id,
+            acknowledgment.expires.as_str(),
+            acknowledgment.ticket,
+            config_path.display()
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! The `cargo audit bin` subcommand
+
+use crate::{
+    auditor::Auditor,
+    binaries,
+    config::{AuditConfig, OutputFormat},
+    output,
+    prelude::*,
+    presenter::Presenter,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::report::Outcome;
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit bin` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct BinCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the report as JSON")]
+    output_json: bool,
+
+    /// Audit the binaries in directories and their subdirectories
+    #[options(
+        short = "r",
+        long = "recursive",
+        help = "audit the binaries in the given directories and their subdirectories"
+    )]
+    recursive: bool,
+
+    /// Binaries (or glob patterns, or directories with `--recursive`) to audit
+    #[options(
+        free,
+        help = "executables built with cargo auditable (ELF, PE, Mach-O or WebAssembly), glob patterns, or directories with --recursive"
+    )]
+    paths: Vec<PathBuf>,
+}
+
+impl Runnable for BinCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        if self.paths.is_empty() {
+            status_err!("the path of a binary to audit is required");
+            exit(2);
+        }
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        // Anything but a single binary is audited as a set of binaries
+        let path = match self.paths.as_slice() {
+            [path] if !self.recursive && !binaries::is_pattern(path) => path,
+            paths => self.audit_binaries(&config, paths),
+        };
+
+        let exit_codes = config.output.exit_codes.clone();
+        let mut auditor = Auditor::new(&config);
+
+        match auditor.audit_binary(path) {
+            Ok(report) => exit(auditor.exit_code(&report)),
+            Err(e) => {
+                status_err!("{}", e);
+                exit(exit_codes.code(Outcome::OperationalError));
+            }
+        }
+    }
+}
+
+impl BinCommand {
+    /// Audit each binary among the given paths, printing a combined report
+    fn audit_binaries(&self, config: &AuditConfig, paths: &[PathBuf]) -> ! {
+        let mut auditor = Auditor::with_database(Auditor::load_database(config), config);
+
+        let report = binaries::audit(&mut auditor, paths, self.recursive).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        });
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_binaries(&report) {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        }
+
+        let outcome = if report.vulnerable_count() > 0 {
+            Outcome::VulnerabilitiesFound
+        } else {
+            Outcome::Clean
+        };
+
+        exit(config.output.exit_codes.code(outcome));
+    }
+}
@@ -0,0 +1,67 @@
+//! The `cargo audit cache` subcommand
+
+use crate::{cache::Cache, prelude::*};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use std::process::exit;
+
+/// The `cargo audit cache` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct CacheCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Cache housekeeping action to perform
+    #[options(command)]
+    subcommand: Option<CacheSubcommand>,
+}
+
+/// Subcommands of `cargo audit cache`
+#[derive(Command, Debug, Options, Runnable)]
+pub enum CacheSubcommand {
+    /// `cargo audit cache clear` subcommand
+    #[options(help = "remove all cached audit reports")]
+    Clear(ClearCommand),
+}
+
+impl Runnable for CacheCommand {
+    fn run(&self) {
+        match &self.subcommand {
+            Some(subcommand) => subcommand.run(),
+            None => Self::print_usage_and_exit(&[]),
+        }
+    }
+}
+
+/// The `cargo audit cache clear` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct ClearCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+}
+
+impl Runnable for ClearCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let cache = Cache::from_config(&app_config().cache);
+
+        match cache.clear() {
+            Ok(removed) => status_ok!(
+                "Cleared",
+                "{} cached {} from {}",
+                removed,
+                if removed == 1 { "report" } else { "reports" },
+                cache.dir().display()
+            ),
+            Err(e) => {
+                status_err!("couldn't clear {}: {}", cache.dir().display(), e);
+                exit(1);
+            }
+        }
+    }
+}
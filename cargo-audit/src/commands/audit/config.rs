@@ -0,0 +1,134 @@
+//! The `cargo audit config` subcommand
+
+use crate::{commands, config::AuditConfig, prelude::*};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit config` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct ConfigCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Print the effective configuration
+    #[options(
+        no_short,
+        long = "show",
+        help = "print the effective configuration: audit.toml files merged with CARGO_AUDIT_* variables and flags"
+    )]
+    show: bool,
+
+    /// Configuration action to perform
+    #[options(command)]
+    subcommand: Option<ConfigSubcommand>,
+}
+
+/// Subcommands of `cargo audit config`
+#[derive(Command, Debug, Options, Runnable)]
+pub enum ConfigSubcommand {
+    /// `cargo audit config check` subcommand
+    #[options(help = "check audit.toml and CARGO_AUDIT_* variables for problems")]
+    Check(CheckCommand),
+}
+
+impl ConfigCommand {
+    /// Is this `cargo audit config check`, which loads the configuration
+    /// itself?
+    pub fn is_check(&self) -> bool {
+        matches!(self.subcommand, Some(ConfigSubcommand::Check(_)))
+    }
+
+    /// Print the effective configuration as TOML, noting the files it was
+    /// loaded from
+    fn show(&self) {
+        let toml = toml::Value::try_from(&*app_config())
+            .and_then(|value| toml::to_string_pretty(&value))
+            .unwrap_or_else(|e| {
+                status_err!("couldn't serialize the configuration: {}", e);
+                exit(1);
+            });
+
+        let paths = commands::config_paths();
+
+        if paths.is_empty() {
+            println!("# No audit.toml files were found");
+        } else {
+            println!("# Merged from (in increasing order of precedence):");
+
+            for path in &paths {
+                println!("# - {}", path.display());
+            }
+        }
+
+        println!("# - CARGO_AUDIT_* environment variables and command-line flags");
+        println!();
+        print!("{}", toml);
+    }
+}
+
+impl Runnable for ConfigCommand {
+    fn run(&self) {
+        match &self.subcommand {
+            Some(subcommand) => subcommand.run(),
+            None if self.show => self.show(),
+            None => Self::print_usage_and_exit(&[]),
+        }
+    }
+}
+
+/// The `cargo audit config check` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct CheckCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Configuration file to check
+    #[options(
+        no_short,
+        long = "file",
+        meta = "PATH",
+        help = "audit.toml to check (default: the ones cargo audit would load)"
+    )]
+    file: Option<PathBuf>,
+}
+
+impl Runnable for CheckCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let paths = match &self.file {
+            Some(file) => vec![file.clone()],
+            None => commands::config_paths(),
+        };
+        let problems = AuditConfig::check(&paths);
+
+        for problem in &problems {
+            status_err!("{}", problem);
+        }
+
+        let checked = if paths.is_empty() {
+            "the default configuration".to_owned()
+        } else {
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match problems.len() {
+            0 => status_ok!("Checked", "{}: no problems found", checked),
+            1 => status_err!("{}: 1 problem found", checked),
+            n => status_err!("{}: {} problems found", checked, n),
+        }
+
+        if !problems.is_empty() {
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! The `cargo audit db` subcommand
+
+use crate::{auditor::Auditor, config::OutputFormat, output, prelude::*};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::database::Stats;
+use std::{
+    collections::BTreeMap as Map, fmt::Display, path::PathBuf, process::exit, time::SystemTime,
+};
+
+/// The `cargo audit db` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct DbCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Advisory database action to perform
+    #[options(command)]
+    subcommand: Option<DbSubcommand>,
+}
+
+/// Subcommands of `cargo audit db`
+#[derive(Command, Debug, Options, Runnable)]
+pub enum DbSubcommand {
+    /// `cargo audit db stats` subcommand
+    #[options(help = "show statistics about the advisory database")]
+    Stats(StatsCommand),
+}
+
+impl Runnable for DbCommand {
+    fn run(&self) {
+        match &self.subcommand {
+            Some(subcommand) => subcommand.run(),
+            None => Self::print_usage_and_exit(&[]),
+        }
+    }
+}
+
+/// The `cargo audit db stats` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct StatsCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Advisory database checkout to inspect
+    #[options(
+        no_short,
+        long = "db-path",
+        meta = "PATH",
+        help = "advisory database checkout to inspect as-is (without fetching it)"
+    )]
+    db_path: Option<PathBuf>,
+
+    /// Don't fetch the advisory database
+    #[options(
+        no_short,
+        long = "offline",
+        help = "use the local copy of the advisory database without fetching it"
+    )]
+    offline: bool,
+
+    /// Output format
+    #[options(
+        no_short,
+        long = "format",
+        meta = "FORMAT",
+        help = "output format: terminal (default) or json"
+    )]
+    format: Option<OutputFormat>,
+}
+
+impl Runnable for StatsCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let format = self.format.unwrap_or(OutputFormat::Terminal);
+
+        if !matches!(format, OutputFormat::Json | OutputFormat::Terminal) {
+            status_err!("database statistics can only be shown as terminal or json");
+            exit(2);
+        }
+
+        let mut config = app_config().clone();
+        config.net.offline |= self.offline;
+
+        // Checkouts given explicitly (e.g. mirrors) are inspected as they are
+        if let Some(path) = &self.db_path {
+            config.database.path = Some(path.clone());
+            config.database.fetch = false;
+        }
+
+        output::status_to_stderr(format.is_machine_readable());
+        let stats = Auditor::load_database(&config).stats();
+
+        if format == OutputFormat::Json {
+            let json = serde_json::to_string(&stats).expect("statistics serialize");
+            println!("{}", json);
+        } else {
+            print_stats(&stats);
+        }
+    }
+}
+
+/// Print the statistics as tables
+fn print_stats(stats: &Stats) {
+    print_row("Advisories", stats.advisories);
+    print_row("With CVSS score", stats.with_cvss);
+    print_row("Withdrawn", stats.withdrawn);
+    print_row(
+        "Newest advisory",
+        stats.newest.as_ref().map_or("none", |date| date.as_str()),
+    );
+    print_row("Commit", stats.commit.as_deref().unwrap_or("unknown"));
+    print_row(
+        "Last updated",
+        stats
+            .last_updated
+            .as_deref()
+            .map_or_else(|| "unknown".to_owned(), last_updated),
+    );
+
+    print_table("Collection", &stats.collections);
+    print_table("Year", &stats.years);
+    print_table("Informational", &stats.informational);
+    print_table("Category", &stats.categories);
+}
+
+/// Describe when the database was last updated, including how long ago
+fn last_updated(timestamp: &str) -> String {
+    let age = humantime::parse_rfc3339(timestamp)
+        .ok()
+        .and_then(|time| SystemTime::now().duration_since(time).ok());
+
+    match age.map(|age| age.as_secs() / (24 * 60 * 60)) {
+        Some(0) => format!("{} (today)", timestamp),
+        Some(1) => format!("{} (1 day ago)", timestamp),
+        Some(days) => format!("{} ({} days ago)", timestamp, days),
+        None => timestamp.to_owned(),
+    }
+}
+
+/// Print one row of a table
+fn print_row(label: impl Display, value: impl Display) {
+    println!("{:<24} {}", label.to_string(), value);
+}
+
+/// Print a table of counts (unless it's empty)
+fn print_table<K: Display>(title: &str, counts: &Map<K, usize>) {
+    if counts.is_empty() {
+        return;
+    }
+
+    println!();
+    print_row(title, "Advisories");
+
+    for (key, count) in counts {
+        print_row(key, count);
+    }
+}
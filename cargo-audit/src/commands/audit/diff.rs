@@ -0,0 +1,259 @@
+//! The `cargo audit diff` subcommand
+
+use crate::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    lockfile::{self, CARGO_LOCK_FILE},
+    output,
+    prelude::*,
+    presenter::Presenter,
+    state,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::{
+    error::{Error, ErrorKind},
+    fs,
+    lockfile::Lockfile,
+    report::{self, DatabaseInfo},
+};
+use std::{
+    path::{Path, PathBuf},
+    process::exit,
+    str::FromStr,
+};
+
+/// The `cargo audit diff` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct DiffCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Lockfile or git revision to compare from
+    #[options(
+        no_short,
+        long = "from",
+        meta = "REV|PATH",
+        help = "git revision or lockfile to compare from (required)"
+    )]
+    from: Option<String>,
+
+    /// Lockfile or git revision to compare to
+    #[options(
+        no_short,
+        long = "to",
+        meta = "REV|PATH",
+        help = "git revision or lockfile to compare to (default: the current lockfile)"
+    )]
+    to: Option<String>,
+
+    /// Path to the lockfile (in the working tree and at git revisions)
+    #[options(
+        short = "f",
+        long = "file",
+        help = "Cargo lockfile to compare at git revisions (default: Cargo.lock)"
+    )]
+    file: Option<PathBuf>,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the diff as JSON")]
+    output_json: bool,
+
+    /// When to exit with an error
+    #[options(
+        no_short,
+        long = "fail-on",
+        meta = "WHEN",
+        help = "exit with an error on: introduced (default), any, never"
+    )]
+    fail_on: Option<FailOn>,
+
+    /// Reports to compare
+    #[options(
+        free,
+        help = "JSON reports (from `cargo audit --json`) to compare, older first"
+    )]
+    reports: Vec<PathBuf>,
+}
+
+impl DiffCommand {
+    /// Audit the lockfiles to compare, and compare their findings
+    fn diff_lockfiles(&self, config: &AuditConfig, presenter: &mut Presenter) -> report::Diff {
+        let from = self.from.as_deref().unwrap_or_else(|| {
+            status_err!(
+                "a --from git revision or lockfile (or two reports) to compare is required"
+            );
+            exit(2);
+        });
+
+        let loaded: Vec<_> = [Some(from), self.to.as_deref()]
+            .iter()
+            .map(|file_or_revision| {
+                self.load(*file_or_revision).unwrap_or_else(|e| {
+                    status_err!("{}", e);
+                    exit(2);
+                })
+            })
+            .collect();
+
+        let mut auditor = Auditor::new(config);
+        let mut reports = vec![];
+        let database = DatabaseInfo::new(auditor.database());
+
+        for (description, lockfile) in &loaded {
+            presenter.before_report(Path::new(description), lockfile, &database, false);
+            reports.push(auditor.generate_report(lockfile));
+        }
+
+        report::Diff::new(&reports[0], &reports[1])
+    }
+
+    /// Compare the findings of the given reports, which were generated
+    /// beforehand (so the advisory database isn't needed)
+    fn diff_reports(&self) -> report::Diff {
+        if self.reports.len() != 2 {
+            status_err!("expected two reports to compare, older first");
+            exit(2);
+        }
+
+        if self.from.is_some() || self.to.is_some() || self.file.is_some() {
+            status_err!("reports can't be compared with --from, --to or --file");
+            exit(2);
+        }
+
+        let reports: Vec<_> = self
+            .reports
+            .iter()
+            .map(|path| {
+                state::load_report(path).unwrap_or_else(|e| {
+                    status_err!("{}", e);
+                    exit(2);
+                })
+            })
+            .collect();
+
+        report::Diff::new(&reports[0], &reports[1])
+    }
+
+    /// Path to the lockfile in the working tree
+    fn lockfile_path(&self) -> &Path {
+        self.file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(CARGO_LOCK_FILE))
+    }
+
+    /// Load the lockfile to compare from the given file, or the given git
+    /// revision if no such file exists (or the lockfile in the working tree
+    /// if neither is given), along with a description of it
+    fn load(&self, file_or_revision: Option<&str>) -> Result<(String, Lockfile), Error> {
+        let (description, lockfile_toml) = match file_or_revision {
+            Some(path) if Path::new(path).is_file() => (path.to_owned(), fs::read_to_string(path)?),
+            Some(revision) => (
+                format!("{} at {}", self.lockfile_path().display(), revision),
+                lockfile::read_revision(self.lockfile_path(), revision)?,
+            ),
+            None => (
+                self.lockfile_path().display().to_string(),
+                fs::read_to_string(self.lockfile_path())?,
+            ),
+        };
+
+        let lockfile = lockfile_toml.parse().map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("couldn't parse {}: {}", description, e),
+            )
+        })?;
+
+        Ok((description, lockfile))
+    }
+}
+
+impl Runnable for DiffCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        let mut presenter = Presenter::new(&config.output);
+
+        let diff = if self.reports.is_empty() {
+            self.diff_lockfiles(&config, &mut presenter)
+        } else {
+            self.diff_reports()
+        };
+
+        if let Err(e) = presenter.print_diff(&diff) {
+            status_err!("{}", e);
+            exit(2);
+        }
+
+        let deny_warning_kinds: Vec<_> = config
+            .output
+            .deny
+            .iter()
+            .filter_map(|kind| kind.get_warning_kind())
+            .collect();
+
+        // Warnings only count if they're denied, as in a regular audit
+        let failed = |findings: &report::Findings| {
+            !findings.vulnerabilities.is_empty()
+                || findings
+                    .warnings
+                    .iter()
+                    .any(|warning| deny_warning_kinds.contains(&warning.kind))
+        };
+
+        let fail = match self.fail_on.unwrap_or_default() {
+            FailOn::Introduced => failed(&diff.introduced),
+            FailOn::Any => failed(&diff.introduced) || failed(&diff.unchanged),
+            FailOn::Never => false,
+        };
+
+        exit(if fail { 1 } else { 0 });
+    }
+}
+
+/// When `cargo audit diff` exits with an error
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FailOn {
+    /// Findings were introduced
+    Introduced,
+
+    /// Findings were introduced or are still present
+    Any,
+
+    /// Never (unless the diff couldn't be generated)
+    Never,
+}
+
+impl Default for FailOn {
+    fn default() -> Self {
+        FailOn::Introduced
+    }
+}
+
+impl FromStr for FailOn {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "introduced" => Ok(FailOn::Introduced),
+            "any" => Ok(FailOn::Any),
+            "never" => Ok(FailOn::Never),
+            other => Err(Error::new(
+                ErrorKind::Parse,
+                &format!("invalid --fail-on option: {}", other),
+            )),
+        }
+    }
+}
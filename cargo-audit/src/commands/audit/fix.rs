@@ -1,10 +1,23 @@
 //! The `cargo audit fix` subcommand
 
-use crate::{auditor::Auditor, lockfile, prelude::*};
+use crate::{
+    auditor::Auditor,
+    backup::{self, Backup},
+    lockfile, output,
+    patch::{self, GitPatch, PatchEditor},
+    prelude::*,
+};
 use abscissa_core::{Command, Runnable};
 use gumdrop::Options;
-use rustsec::fixer::Fixer;
+use rustsec::{
+    fixer::{Fix, Fixer},
+    package::{self, Package},
+    vulnerability::PatchedRelease,
+    Error, Vulnerability,
+};
 use std::{
+    collections::BTreeMap as Map,
+    io::{self, Write},
     path::{Path, PathBuf},
     process::exit,
 };
@@ -19,9 +32,42 @@ pub struct FixCommand {
     #[options(short = "f", long = "file", help = "Cargo lockfile to inspect")]
     file: Option<PathBuf>,
 
+    /// Path to `Cargo.toml`
+    #[options(
+        no_short,
+        long = "manifest-path",
+        meta = "PATH",
+        help = "path to Cargo.toml (default: Cargo.toml)"
+    )]
+    manifest_path: Option<PathBuf>,
+
     /// Perform a dry run
     #[options(no_short, long = "dry-run", help = "perform a dry run for the fix")]
     dry_run: bool,
+
+    /// Choose which fixes to apply
+    #[options(
+        short = "i",
+        long = "interactive",
+        help = "interactively choose which fixes to apply (requires a TTY)"
+    )]
+    interactive: bool,
+
+    /// Patch crates with fixes which are only in upstream git
+    #[options(
+        no_short,
+        long = "allow-git-patches",
+        help = "add temporary [patch.crates-io] entries for fixes which are only in upstream git"
+    )]
+    allow_git_patches: bool,
+
+    /// Restore the most recent backup
+    #[options(
+        no_short,
+        long = "rollback",
+        help = "restore the files changed by the most recent fix from its backup"
+    )]
+    rollback: bool,
 }
 
 impl FixCommand {
@@ -32,14 +78,29 @@ impl FixCommand {
     }
 
     /// Locate `Cargo.toml`
-    // TODO(tarcieri): ability to specify path
     pub fn cargo_toml_path(&self) -> PathBuf {
-        PathBuf::from("Cargo.toml")
+        self.manifest_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(lockfile::CARGO_TOML_FILE))
     }
 
     /// Locate `Cargo.lock`
-    pub fn cargo_lock_path(&self) -> Option<&Path> {
-        self.file.as_deref()
+    pub fn cargo_lock_path(&self) -> Result<Option<PathBuf>, Error> {
+        lockfile::resolve(self.file.as_deref(), self.manifest_path.as_deref())
+    }
+
+    /// Locate the `Cargo.lock` regenerated after fixing `Cargo.toml`
+    fn generated_lockfile_path(&self) -> PathBuf {
+        lockfile::locate(&self.cargo_toml_path())
+            .unwrap_or_else(|_| PathBuf::from(lockfile::CARGO_LOCK_FILE))
+    }
+
+    /// Directory backups are stored in
+    fn backups_dir(&self) -> PathBuf {
+        backup::backups_dir(&self.cargo_toml_path()).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(1);
+        })
     }
 }
 
@@ -49,7 +110,25 @@ impl Runnable for FixCommand {
             Self::print_usage_and_exit(&[]);
         }
 
-        let report = self.auditor().audit(self.cargo_lock_path());
+        if self.rollback {
+            self.restore_latest_backup();
+            return;
+        }
+
+        if self.interactive && (!atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stdout)) {
+            status_err!("--interactive can only be used from a terminal");
+            exit(2);
+        }
+
+        let cargo_lock_path = match self.cargo_lock_path() {
+            Ok(path) => path,
+            Err(e) => {
+                status_err!("{}", e);
+                exit(2);
+            }
+        };
+
+        let report = self.auditor().audit(cargo_lock_path.as_deref());
 
         let report = match report {
             Ok(report) => {
@@ -83,15 +162,665 @@ impl Runnable for FixCommand {
             dry_run_info
         );
 
-        for vulnerability in &report.vulnerabilities.list {
-            if let Err(e) = fixer.fix(vulnerability, dry_run) {
-                status_warn!("{}", e);
+        let mut git_patches = GitPatches::new(&self.cargo_toml_path());
+        let mut updates = Updates::default();
+
+        if !self.interactive {
+            let backup = self.back_up(&git_patches, dry_run);
+
+            for vulnerability in &report.vulnerabilities.list {
+                if self.allow_git_patches && has_no_release(vulnerability) {
+                    if git_patches.push(vulnerability) {
+                        updates.push_patched(&vulnerability.package);
+                    }
+                    continue;
+                }
+
+                if is_unreleased(vulnerability) {
+                    warn_unreleased(vulnerability);
+                    continue;
+                }
+
+                match fixer.fix(vulnerability, dry_run) {
+                    Ok(fix) => updates.push(fix),
+                    Err(e) => status_warn!("{}: {}", vulnerability.advisory.id, e),
+                }
             }
+
+            self.finish(&git_patches, &updates, backup.as_ref(), dry_run);
+            return;
         }
 
-        if let Err(e) = lockfile::generate() {
-            status_err!("{}", e);
-            exit(2);
+        let remediations = Remediation::group(&report.vulnerabilities.list, self.allow_git_patches);
+        let lockfile_path =
+            cargo_lock_path.unwrap_or_else(|| PathBuf::from(lockfile::CARGO_LOCK_FILE));
+        let files = [self.cargo_toml_path(), lockfile_path];
+        let selected = choose(&remediations, &fixer, &files);
+
+        // Nothing is written until the whole selection has been confirmed
+        if selected.is_empty() || !confirm("Apply the selected remediations?") {
+            status_ok!(
+                "Skipped",
+                "all {} remediations (nothing was changed)",
+                remediations.len()
+            );
+            exit(0);
+        }
+
+        let backup = self.back_up(&git_patches, dry_run);
+        let mut applied: usize = 0;
+
+        for remediation in &selected {
+            let mut fixed = true;
+
+            for vulnerability in &remediation.vulnerabilities {
+                if self.allow_git_patches && has_no_release(vulnerability) {
+                    if git_patches.push(vulnerability) {
+                        updates.push_patched(&vulnerability.package);
+                    } else {
+                        fixed = false;
+                    }
+                    continue;
+                }
+
+                if is_unreleased(vulnerability) {
+                    warn_unreleased(vulnerability);
+                    fixed = false;
+                    continue;
+                }
+
+                match fixer.fix(vulnerability, dry_run) {
+                    Ok(fix) => updates.push(fix),
+                    Err(e) => {
+                        status_warn!("{}: {}", vulnerability.advisory.id, e);
+                        fixed = false;
+                    }
+                }
+            }
+
+            if fixed {
+                applied += 1;
+            }
+        }
+
+        let refused = self.finish(&git_patches, &updates, backup.as_ref(), dry_run);
+
+        // Remediations whose patch entry was refused weren't applied either
+        let applied = applied.saturating_sub(refused);
+
+        status_ok!(
+            "Applied",
+            "{} remediations{} ({} skipped, {} failed)",
+            applied,
+            dry_run_info,
+            remediations.len() - selected.len(),
+            selected.len() - applied
+        );
+    }
+}
+
+impl FixCommand {
+    /// Back up the files the fix may modify (unless it's a dry run)
+    fn back_up(&self, git_patches: &GitPatches, dry_run: bool) -> Option<Backup> {
+        if dry_run {
+            return None;
+        }
+
+        let files = [
+            self.cargo_toml_path(),
+            git_patches.manifest_path.clone(),
+            self.generated_lockfile_path(),
+        ];
+
+        let backup = Backup::create(&self.backups_dir(), &files).unwrap_or_else(|e| {
+            status_err!("couldn't back up the files to fix: {}", e);
+            exit(1);
+        });
+
+        status_ok!(
+            "Backed up",
+            "{} files to `{}` (restore them with `cargo audit fix --rollback`)",
+            backup.files().len(),
+            backup.dir().display()
+        );
+
+        Some(backup)
+    }
+
+    /// Add any git patches, update the fixed packages in `Cargo.lock` and
+    /// check the workspace still resolves, rolling back every change from
+    /// the backup if anything fails. Returns how many git patches were
+    /// refused.
+    fn finish(
+        &self,
+        git_patches: &GitPatches,
+        updates: &Updates,
+        backup: Option<&Backup>,
+        dry_run: bool,
+    ) -> usize {
+        let result = git_patches.apply(dry_run).and_then(|refused| {
+            updates.apply(
+                &self.cargo_toml_path(),
+                &self.generated_lockfile_path(),
+                dry_run,
+            )?;
+
+            if backup.is_some() {
+                lockfile::verify(&self.cargo_toml_path(), &self.generated_lockfile_path())?;
+            }
+
+            Ok(refused)
+        });
+
+        let e = match result {
+            Ok(refused) => return refused,
+            Err(e) => e,
+        };
+
+        status_err!("{}", e);
+
+        let backup = match backup {
+            Some(backup) => backup,
+            None => exit(2),
+        };
+
+        if let Err(e) = backup.restore() {
+            status_err!(
+                "couldn't roll back the fix: {} (the original files are in `{}`)",
+                e,
+                backup.dir().display()
+            );
+            exit(1);
+        }
+
+        status_warn!("the fix left the workspace broken, so it was rolled back");
+        print_restored(backup);
+
+        if let Err(e) = backup.clone().remove() {
+            status_warn!("couldn't remove `{}`: {}", backup.dir().display(), e);
+        }
+
+        exit(1);
+    }
+
+    /// Restore the most recent backup (with `--rollback`), then remove it
+    fn restore_latest_backup(&self) {
+        let backup = match backup::find_backups_dir(&self.cargo_toml_path())
+            .and_then(|dir| dir.map_or(Ok(None), |dir| Backup::latest(&dir)))
+        {
+            Ok(Some(backup)) => backup,
+            Ok(None) => {
+                status_err!(
+                    "no backups found for `{}`",
+                    self.cargo_toml_path().display()
+                );
+                exit(1);
+            }
+            Err(e) => {
+                status_err!("{}", e);
+                exit(1);
+            }
+        };
+
+        if let Err(e) = backup.restore() {
+            status_err!("couldn't restore `{}`: {}", backup.dir().display(), e);
+            exit(1);
+        }
+
+        print_restored(&backup);
+
+        if let Err(e) = backup.clone().remove() {
+            status_warn!("couldn't remove `{}`: {}", backup.dir().display(), e);
+        }
+    }
+}
+
+/// List the files restored from a backup
+fn print_restored(backup: &Backup) {
+    for file in backup.files() {
+        if file.copy.is_some() {
+            status_ok!("Restored", "`{}`", file.path.display());
+        } else {
+            status_ok!("Removed", "`{}`", file.path.display());
+        }
+    }
+}
+
+/// Does the registry have no release satisfying the patched versions yet?
+/// (upgrading to them would fail to resolve)
+fn is_unreleased(vulnerability: &Vulnerability) -> bool {
+    vulnerability.patched_release == Some(PatchedRelease::Unreleased)
+}
+
+/// Is there no release which fixes the vulnerability? (either because none
+/// has been published yet, or because the advisory has no patched versions)
+fn has_no_release(vulnerability: &Vulnerability) -> bool {
+    is_unreleased(vulnerability) || vulnerability.versions.patched().is_empty()
+}
+
+/// Warn that a vulnerability can't be fixed by upgrading yet
+fn warn_unreleased(vulnerability: &Vulnerability) {
+    status_warn!(
+        "{}: no published release of {} satisfies the patched versions yet \
+         (consider the advisory's workaround)",
+        vulnerability.advisory.id,
+        vulnerability.package.name
+    );
+}
+
+/// Packages to update in `Cargo.lock`: each vulnerable version, and the
+/// oldest version which fixes every vulnerability in it (if known)
+#[derive(Default)]
+struct Updates {
+    /// Versions to update to, by package and vulnerable version (`None` to
+    /// update to the newest version the requirements allow)
+    packages: Map<(package::Name, package::Version), Option<Target>>,
+}
+
+/// Version a package is updated to
+struct Target {
+    /// The version
+    version: package::Version,
+
+    /// Is it a published release? (otherwise cargo chooses one, as it's
+    /// only the lower bound of the patched versions)
+    published: bool,
+}
+
+impl Updates {
+    /// Plan to update the package to the version which fixes it (or a later
+    /// one needed by another fix)
+    fn push(&mut self, fix: Fix) {
+        let Fix {
+            package,
+            from,
+            to,
+            published,
+            ..
+        } = fix;
+
+        let target = self.packages.entry((package, from)).or_insert(None);
+
+        if target.as_ref().map_or(true, |target| target.version < to) {
+            *target = Some(Target {
+                version: to,
+                published,
+            });
+        }
+    }
+
+    /// Plan to update a package patched with a git revision (to pick the
+    /// patch up)
+    fn push_patched(&mut self, package: &Package) {
+        self.packages
+            .entry((package.name.clone(), package.version.clone()))
+            .or_insert(None);
+    }
+
+    /// Update each package in `Cargo.lock`, leaving the others as they're
+    /// locked (or generate it, if there isn't one yet)
+    fn apply(
+        &self,
+        cargo_toml_path: &Path,
+        lockfile_path: &Path,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        let dry_run_info = if dry_run { " (dry run)" } else { "" };
+
+        if !lockfile_path.exists() {
+            if !dry_run {
+                lockfile::generate(cargo_toml_path)?;
+            }
+
+            return Ok(());
+        }
+
+        for ((package, from), target) in &self.packages {
+            let precise = target
+                .as_ref()
+                .filter(|target| target.published)
+                .map(|target| &target.version);
+
+            match precise {
+                Some(to) => status_ok!(
+                    "Updating",
+                    "{} v{} -> v{} in `{}`{}",
+                    package,
+                    from,
+                    to,
+                    lockfile_path.display(),
+                    dry_run_info
+                ),
+                None => status_ok!(
+                    "Updating",
+                    "{} v{} in `{}`{}",
+                    package,
+                    from,
+                    lockfile_path.display(),
+                    dry_run_info
+                ),
+            }
+
+            if !dry_run && !lockfile::update(cargo_toml_path, package, from, precise)? {
+                status_warn!(
+                    "{} v{} is no longer locked (it was updated along with another package)",
+                    package,
+                    from
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Temporary `[patch.crates-io]` entries for vulnerabilities which are only
+/// fixed in upstream git (with `--allow-git-patches`)
+struct GitPatches {
+    /// Workspace manifest to add the entries to
+    manifest_path: PathBuf,
+
+    /// Entries to add
+    patches: Vec<GitPatch>,
+}
+
+impl GitPatches {
+    /// Collect entries to add to the workspace of the given `Cargo.toml`
+    fn new(cargo_toml_path: &Path) -> Self {
+        Self {
+            manifest_path: patch::workspace_manifest(cargo_toml_path),
+            patches: vec![],
         }
     }
+
+    /// Plan to patch the given vulnerable crate with the git commit (or
+    /// branch) its advisory references, returning whether it will be
+    fn push(&mut self, vulnerability: &Vulnerability) -> bool {
+        let id = &vulnerability.advisory.id;
+        let package = &vulnerability.package;
+
+        if !package
+            .source
+            .as_ref()
+            .map_or(false, |source| source.is_default_registry())
+        {
+            status_warn!(
+                "{}: {} isn't from crates.io, so it can't be patched with [patch.crates-io]",
+                id,
+                package.name
+            );
+            return false;
+        }
+
+        let patch = match GitPatch::from_advisory(&vulnerability.advisory) {
+            Some(patch) => patch,
+            None => {
+                status_warn!(
+                    "{}: no published release of {} fixes this, and the advisory doesn't \
+                     reference a fixing git commit or branch",
+                    id,
+                    package.name
+                );
+                return false;
+            }
+        };
+
+        if let Some(existing) = self.patches.iter().find(|p| p.package == patch.package) {
+            if *existing != patch {
+                status_warn!(
+                    "{}: {} is already being patched with {}, which may not contain the fix in {}",
+                    id,
+                    package.name,
+                    existing,
+                    patch
+                );
+                return false;
+            }
+
+            return true;
+        }
+
+        self.patches.push(patch);
+        true
+    }
+
+    /// Add the planned entries to the workspace manifest (after any upgrades
+    /// have been written to it) and list them, returning how many were
+    /// refused because the manifest already patches the crate
+    fn apply(&self, dry_run: bool) -> Result<usize, Error> {
+        if self.patches.is_empty() {
+            return Ok(0);
+        }
+
+        let mut editor = PatchEditor::open(&self.manifest_path)?;
+
+        let mut added = vec![];
+
+        for patch in &self.patches {
+            match editor.add(patch) {
+                Ok(()) => added.push(patch),
+                Err(e) => status_warn!("not patching {}: {}", patch.package, e),
+            }
+        }
+
+        if added.is_empty() {
+            return Ok(self.patches.len());
+        }
+
+        if !dry_run {
+            editor.save()?;
+        }
+
+        status_warn!(
+            "{} {} temporary [patch.crates-io] {} to `{}`, for fixes which aren't released yet \
+             (remove them once fixed releases are published):",
+            if dry_run { "would add" } else { "added" },
+            added.len(),
+            if added.len() == 1 { "entry" } else { "entries" },
+            self.manifest_path.display()
+        );
+
+        println!("[patch.crates-io]");
+
+        for patch in &added {
+            println!("{}", patch.to_toml());
+        }
+
+        Ok(self.patches.len() - added.len())
+    }
+}
+
+/// Interactively prompt for each remediation, returning the ones to apply
+fn choose<'a, 'b>(
+    remediations: &'b [Remediation<'a>],
+    fixer: &Fixer,
+    files: &[PathBuf],
+) -> Vec<&'b Remediation<'a>> {
+    let mut selected = vec![];
+    let mut apply_all = false;
+
+    for (i, remediation) in remediations.iter().enumerate() {
+        if !apply_all {
+            println!();
+            remediation.print(fixer, files);
+
+            match prompt(&format!(
+                "({}/{}) Apply this remediation [y,n,a,q]? ",
+                i + 1,
+                remediations.len()
+            )) {
+                Choice::Yes => (),
+                Choice::No => continue,
+                Choice::All => apply_all = true,
+                Choice::Quit => break,
+            }
+        }
+
+        selected.push(remediation);
+    }
+
+    selected
+}
+
+/// A proposed fix: upgrading a single dependency (or patching it with an
+/// unreleased fix from git), along with all of the vulnerabilities doing so
+/// resolves
+struct Remediation<'a> {
+    /// Name of the dependency to upgrade
+    package: &'a package::Name,
+
+    /// Vulnerabilities in that dependency
+    vulnerabilities: Vec<&'a Vulnerability>,
+
+    /// Temporary patch for the vulnerabilities no release fixes (with
+    /// `--allow-git-patches`)
+    git_patch: Option<GitPatch>,
+}
+
+impl<'a> Remediation<'a> {
+    /// Group vulnerabilities by the dependency which needs to be upgraded
+    fn group(vulnerabilities: &'a [Vulnerability], allow_git_patches: bool) -> Vec<Self> {
+        let mut packages: Map<&package::Name, Vec<&Vulnerability>> = Map::new();
+
+        for vulnerability in vulnerabilities {
+            packages
+                .entry(&vulnerability.package.name)
+                .or_default()
+                .push(vulnerability);
+        }
+
+        packages
+            .into_iter()
+            .map(|(package, vulnerabilities)| {
+                let git_patch = if allow_git_patches {
+                    vulnerabilities
+                        .iter()
+                        .filter(|vulnerability| has_no_release(vulnerability))
+                        .find_map(|vulnerability| GitPatch::from_advisory(&vulnerability.advisory))
+                } else {
+                    None
+                };
+
+                Self {
+                    package,
+                    vulnerabilities,
+                    git_patch,
+                }
+            })
+            .collect()
+    }
+
+    /// Display the advisories this remediation resolves, the version change
+    /// it makes, and the files it affects
+    fn print(&self, fixer: &Fixer, files: &[PathBuf]) {
+        match &self.git_patch {
+            Some(patch) => println!(
+                "Patch `{}` with {} (temporary, until a fixed release is published), resolving:",
+                self.package, patch
+            ),
+            None => println!("Upgrade `{}`, resolving:", self.package),
+        }
+
+        for vulnerability in &self.vulnerabilities {
+            let fix = fixer
+                .plan(vulnerability)
+                .ok()
+                .filter(|_| self.git_patch.is_none());
+
+            let fixed_version = match (fix, &vulnerability.patched_release) {
+                (Some(fix), _) => match &fix.requirement {
+                    Some(requirement) => format!(
+                        "{}, raising the requirement in Cargo.toml to {}",
+                        fix.to, requirement
+                    ),
+                    None => format!("{} in Cargo.lock", fix.to),
+                },
+                (None, Some(PatchedRelease::Published { version, .. })) => version.to_string(),
+                (None, Some(PatchedRelease::Unreleased)) => {
+                    "no patched release published yet".to_owned()
+                }
+                (None, None) => vulnerability
+                    .versions
+                    .patched()
+                    .first()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| "no fixed version available".to_owned()),
+            };
+
+            println!(
+                "  {}: {} ({} -> {})",
+                vulnerability.advisory.id,
+                vulnerability.advisory.title,
+                vulnerability.package.version,
+                fixed_version
+            );
+        }
+
+        if let Some(patch) = &self.git_patch {
+            println!("Adds: [patch.crates-io] {}", patch.to_toml());
+        }
+
+        let files = files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+
+        println!("Files affected: {}", files.join(", "));
+    }
+}
+
+/// Answers to the per-remediation prompt
+enum Choice {
+    /// Apply this remediation
+    Yes,
+
+    /// Skip this remediation
+    No,
+
+    /// Apply this and all remaining remediations
+    All,
+
+    /// Skip this and all remaining remediations
+    Quit,
+}
+
+/// Prompt until a valid answer is given (EOF is treated as quitting)
+fn prompt(question: &str) -> Choice {
+    loop {
+        print!("{}", question);
+        if let Err(e) = io::stdout().flush() {
+            output::exit_on_write_error(e);
+        }
+
+        let mut answer = String::new();
+
+        if io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+            println!();
+            return Choice::Quit;
+        }
+
+        match answer.trim() {
+            "y" | "Y" => return Choice::Yes,
+            "n" | "N" => return Choice::No,
+            "a" | "A" => return Choice::All,
+            "q" | "Q" => return Choice::Quit,
+            _ => {
+                println!("y - apply this remediation");
+                println!("n - skip this remediation");
+                println!("a - apply this and all remaining remediations");
+                println!("q - skip this and all remaining remediations");
+            }
+        }
+    }
+}
+
+/// Ask a yes/no question, defaulting to no
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    if let Err(e) = io::stdout().flush() {
+        output::exit_on_write_error(e);
+    }
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).is_ok() && matches!(answer.trim(), "y" | "Y" | "yes")
 }
@@ -0,0 +1,110 @@
+//! The `cargo audit history` subcommand
+
+use crate::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    history::{self, Revisions},
+    lockfile::CARGO_LOCK_FILE,
+    output,
+    prelude::*,
+    presenter::Presenter,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::report::DatabaseInfo;
+use std::{
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+/// The `cargo audit history` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct HistoryCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Range of git revisions to audit
+    #[options(
+        no_short,
+        long = "rev",
+        meta = "FROM..TO",
+        help = "audit the lockfile at the revisions of this range which changed it (or at one revision)"
+    )]
+    rev: Option<String>,
+
+    /// Pattern of git tags to audit
+    #[options(
+        no_short,
+        long = "tags",
+        meta = "PATTERN",
+        help = "audit the lockfile at the tags matching this glob pattern, e.g. 'v*'"
+    )]
+    tags: Option<String>,
+
+    /// Path to the lockfile
+    #[options(
+        short = "f",
+        long = "file",
+        help = "Cargo lockfile to audit at each revision (default: Cargo.lock)"
+    )]
+    file: Option<PathBuf>,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the timeline as JSON")]
+    output_json: bool,
+}
+
+impl HistoryCommand {
+    /// Revisions to audit, if exactly one way of giving them is used
+    fn revisions(&self) -> Result<Revisions, &'static str> {
+        match (&self.rev, &self.tags) {
+            (Some(range), None) => Ok(Revisions::Range(range.clone())),
+            (None, Some(pattern)) => Ok(Revisions::Tags(pattern.clone())),
+            (Some(_), Some(_)) => Err("only one of --rev and --tags can be given"),
+            (None, None) => Err("a --rev range or --tags pattern to audit is required"),
+        }
+    }
+}
+
+impl Runnable for HistoryCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let revisions = self.revisions().unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(2);
+        });
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        let lockfile_path = self
+            .file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(CARGO_LOCK_FILE));
+
+        // The database is loaded once, and every revision audited against it
+        let mut auditor = Auditor::new(&config);
+        let database = DatabaseInfo::new(auditor.database());
+
+        let audited = history::audit(&mut auditor, lockfile_path, &revisions).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(2);
+        });
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_history(lockfile_path, &database, &audited) {
+            status_err!("{}", e);
+            exit(2);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! The `cargo audit ignore` subcommand
+
+use crate::{
+    auditor::Auditor,
+    commands,
+    config::{advisory_id_problem, ConfigEditor, IgnoredAdvisory},
+    prelude::*,
+    vex::Justification,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::advisory;
+use std::process::exit;
+
+#[derive(Command, Default, Debug, Options)]
+pub struct IgnoreCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Why the advisory is being ignored
+    #[options(
+        short = "r",
+        long = "reason",
+        help = "why the advisory is being ignored (required)"
+    )]
+    reason: Option<String>,
+
+    /// Date until which the advisory is ignored
+    #[options(
+        no_short,
+        long = "expires",
+        meta = "YYYY-MM-DD",
+        help = "date until which the advisory is ignored"
+    )]
+    expires: Option<String>,
+
+    /// Why the workspace isn't affected, for OpenVEX documents
+    #[options(
+        no_short,
+        long = "justification",
+        meta = "JUSTIFICATION",
+        help = "OpenVEX justification for `cargo audit vex`, e.g. vulnerable_code_not_in_execute_path"
+    )]
+    justification: Option<Justification>,
+
+    /// Perform a dry run
+    #[options(
+        no_short,
+        long = "dry-run",
+        help = "print the entry instead of writing it to audit.toml"
+    )]
+    dry_run: bool,
+
+    /// Advisory ID to ignore
+    #[options(free, help = "advisory ID to ignore")]
+    advisory_id: Vec<String>,
+}
+
+impl IgnoreCommand {
+    /// Parse and validate the advisory to be ignored
+    fn ignored_advisory(&self) -> IgnoredAdvisory {
+        let advisory_id = match self.advisory_id.as_slice() {
+            [advisory_id] => advisory_id,
+            _ => {
+                status_err!("expected exactly one advisory ID (e.g. RUSTSEC-2019-0001)");
+                exit(2);
+            }
+        };
+
+        let id: advisory::Id = advisory_id.parse().unwrap_or_else(|e| {
+            status_err!("invalid advisory ID {:?}: {}", advisory_id, e);
+            exit(2);
+        });
+
+        if let Some(problem) = advisory_id_problem(&id) {
+            status_err!("{}", problem);
+            exit(2);
+        }
+
+        let reason = match &self.reason {
+            Some(reason) if !reason.trim().is_empty() => reason.trim().to_owned(),
+            _ => {
+                status_err!("a --reason for ignoring {} is required", id);
+                exit(2);
+            }
+        };
+
+        let expires = self.expires.as_ref().map(|date| {
+            date.parse::<advisory::Date>().unwrap_or_else(|e| {
+                status_err!("invalid --expires date {:?}: {}", date, e);
+                exit(2);
+            })
+        });
+
+        IgnoredAdvisory {
+            id,
+            reason: Some(reason),
+            expires,
+            justification: self.justification,
+            package: None,
+            version: None,
+            via: vec![],
+        }
+    }
+}
+
+impl Runnable for IgnoreCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let advisory = self.ignored_advisory();
+        let database = Auditor::load_database(&app_config());
+
+        if database.get(&advisory.id).is_none() {
+            status_err!(
+                "{:?} was not found in the advisory database",
+                advisory.id.as_str()
+            );
+            exit(1);
+        }
+
+        let config_path = commands::config_path().unwrap_or_else(commands::project_config_path);
+
+        let mut editor = ConfigEditor::open(&config_path).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(1);
+        });
+
+        let updated = editor.ignore(&advisory).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(1);
+        });
+
+        if self.dry_run {
+            println!("{}", advisory.to_toml());
+            return;
+        }
+
+        if let Err(e) = editor.save() {
+            status_err!("couldn't write {}: {}", config_path.display(), e);
+            exit(1);
+        }
+
+        status_ok!(
+            if updated { "Updated" } else { "Ignored" },
+            "{} in {}",
+            advisory.id,
+            config_path.display()
+        );
+    }
+}
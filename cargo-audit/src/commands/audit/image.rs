@@ -0,0 +1,101 @@
+//! The `cargo audit image` subcommand
+
+use crate::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    image, output,
+    prelude::*,
+    presenter::Presenter,
+    retry::Retry,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::report::Outcome;
+use std::process::exit;
+
+/// The `cargo audit image` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct ImageCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Platform of multi-platform images to audit
+    #[options(
+        no_short,
+        long = "platform",
+        meta = "OS/ARCH",
+        help = "platform to audit multi-platform images for (default: linux/amd64)"
+    )]
+    platform: Option<String>,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the report as JSON")]
+    output_json: bool,
+
+    /// Image to audit
+    #[options(
+        free,
+        help = "image tarball (docker save or OCI layout) or registry reference"
+    )]
+    images: Vec<String>,
+}
+
+impl Runnable for ImageCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let image = match self.images.as_slice() {
+            [image] => image,
+            [] => {
+                status_err!("the image to audit is required");
+                exit(2);
+            }
+            _ => {
+                status_err!("only one image can be audited at a time");
+                exit(2);
+            }
+        };
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        let platform = self.platform.as_deref().unwrap_or(image::DEFAULT_PLATFORM);
+
+        let mut auditor = Auditor::with_database(Auditor::load_database(&config), &config);
+
+        let report = image::audit(
+            &mut auditor,
+            image,
+            platform,
+            config.net.offline,
+            Retry::from_config(&config.net),
+        )
+        .unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        });
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_image(&report) {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        }
+
+        let outcome = if report.vulnerable_count() > 0 {
+            Outcome::VulnerabilitiesFound
+        } else {
+            Outcome::Clean
+        };
+
+        exit(config.output.exit_codes.code(outcome));
+    }
+}
@@ -0,0 +1,80 @@
+//! The `cargo audit installed` subcommand
+
+use crate::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    installed, output,
+    prelude::*,
+    presenter::Presenter,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::report::Outcome;
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit installed` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct InstalledCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Install root to audit the binaries of
+    #[options(
+        no_short,
+        long = "root",
+        meta = "DIR",
+        help = "install root whose binaries to audit (default: $CARGO_HOME)"
+    )]
+    root: Option<PathBuf>,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the report as JSON")]
+    output_json: bool,
+}
+
+impl Runnable for InstalledCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => installed::default_root().unwrap_or_else(|e| {
+                status_err!("{}", e);
+                exit(config.output.exit_codes.code(Outcome::OperationalError));
+            }),
+        };
+
+        let mut auditor = Auditor::with_database(Auditor::load_database(&config), &config);
+
+        let report = installed::audit(&mut auditor, &root).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        });
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_installed(&report) {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        }
+
+        let outcome = if report.vulnerable_count() > 0 {
+            Outcome::VulnerabilitiesFound
+        } else {
+            Outcome::Clean
+        };
+
+        exit(config.output.exit_codes.code(outcome));
+    }
+}
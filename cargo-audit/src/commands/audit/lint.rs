@@ -0,0 +1,84 @@
+//! The `cargo audit lint` subcommand
+
+use crate::{
+    config::{AuditConfig, OutputFormat},
+    lint, output,
+    prelude::*,
+    presenter::Presenter,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit lint` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct LintCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Check that URLs are reachable
+    #[options(
+        no_short,
+        long = "check-urls",
+        help = "check that the advisories' URLs are reachable"
+    )]
+    check_urls: bool,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the report as JSON")]
+    output_json: bool,
+
+    /// Advisory files to lint
+    #[options(
+        free,
+        help = "advisory files, or directories to search for them (default: the current directory)"
+    )]
+    paths: Vec<PathBuf>,
+}
+
+impl Runnable for LintCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        if self.check_urls && config.net.offline {
+            status_warn!("not checking URLs while offline");
+        }
+
+        let paths = if self.paths.is_empty() {
+            vec![PathBuf::from(".")]
+        } else {
+            self.paths.clone()
+        };
+
+        let settings = lint::Settings {
+            check_urls: self.check_urls && !config.net.offline,
+        };
+
+        let report = lint::lint(&paths, &settings).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(2);
+        });
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_lint(&report) {
+            status_err!("{}", e);
+            exit(2);
+        }
+
+        if report.is_failure() {
+            exit(1);
+        }
+    }
+}
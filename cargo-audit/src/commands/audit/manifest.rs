@@ -0,0 +1,80 @@
+//! The `cargo audit manifest` subcommand
+
+use crate::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    lockfile::CARGO_TOML_FILE,
+    manifest, output,
+    prelude::*,
+    presenter::Presenter,
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::report::Outcome;
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit manifest` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct ManifestCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the report as JSON")]
+    output_json: bool,
+
+    /// Path to the manifest
+    #[options(free, help = "Cargo.toml to audit (default: Cargo.toml)")]
+    path: Vec<PathBuf>,
+}
+
+impl Runnable for ManifestCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        if self.path.len() > 1 {
+            status_err!("only one Cargo.toml can be audited at a time");
+            exit(2);
+        }
+
+        let path = self
+            .path
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from(CARGO_TOML_FILE));
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        let database = Auditor::load_database(&config);
+        let ignore = config.report_settings().ignore;
+
+        let report = manifest::audit(&database, &path, &ignore).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        });
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_manifest_report(&report) {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        }
+
+        let outcome = if report.matches.is_empty() {
+            Outcome::Clean
+        } else {
+            Outcome::VulnerabilitiesFound
+        };
+
+        exit(config.output.exit_codes.code(outcome));
+    }
+}
@@ -0,0 +1,183 @@
+//! The `cargo audit search` subcommand
+
+use crate::{auditor::Auditor, config::OutputFormat, output, prelude::*, presenter::Presenter};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::{
+    advisory::{self, Category, Severity},
+    database::Query,
+    package, Advisory,
+};
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit search` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct SearchCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Text to search for
+    #[options(
+        free,
+        help = "text the advisories mention (in their ID, aliases, crate, title, description or keywords)"
+    )]
+    text: Vec<String>,
+
+    /// Crate the advisories are about
+    #[options(
+        no_short,
+        long = "crate",
+        meta = "NAME",
+        help = "only list advisories about this crate"
+    )]
+    crate_name: Option<package::Name>,
+
+    /// Advisory ID or alias
+    #[options(
+        no_short,
+        long = "id",
+        meta = "ID",
+        help = "only list the advisory with this ID or alias (e.g. a CVE or GHSA ID)"
+    )]
+    id: Option<String>,
+
+    /// Minimum severity
+    #[options(
+        no_short,
+        long = "severity",
+        meta = "SEVERITY",
+        help = "only list advisories with a CVSS severity of at least this (low, medium, high, critical)"
+    )]
+    severity: Option<Severity>,
+
+    /// Earliest date the advisories were reported on
+    #[options(
+        no_short,
+        long = "since",
+        meta = "YYYY-MM-DD",
+        help = "only list advisories reported on or after this date"
+    )]
+    since: Option<advisory::Date>,
+
+    /// Latest date the advisories were reported on
+    #[options(
+        no_short,
+        long = "until",
+        meta = "YYYY-MM-DD",
+        help = "only list advisories reported on or before this date"
+    )]
+    until: Option<advisory::Date>,
+
+    /// Categories of the advisories
+    #[options(
+        no_short,
+        long = "category",
+        meta = "CATEGORY",
+        help = "only list advisories in this category, e.g. memory-corruption (repeatable)"
+    )]
+    category: Vec<Category>,
+
+    /// Advisory database checkout to search
+    #[options(
+        no_short,
+        long = "db-path",
+        meta = "PATH",
+        help = "advisory database checkout to search as-is (without fetching it)"
+    )]
+    db_path: Option<PathBuf>,
+
+    /// Don't fetch the advisory database
+    #[options(
+        no_short,
+        long = "offline",
+        help = "use the local copy of the advisory database without fetching it"
+    )]
+    offline: bool,
+
+    /// Output the advisories as JSON
+    #[options(no_short, long = "json", help = "output the advisories as JSON")]
+    output_json: bool,
+}
+
+impl SearchCommand {
+    /// Query for the advisories matching the filters (withdrawn ones never
+    /// match)
+    fn query(&self) -> Query {
+        let mut query = Query::new().withdrawn(false);
+
+        for text in &self.text {
+            query = query.text(text);
+        }
+
+        if let Some(name) = &self.crate_name {
+            query = query.package(name.clone());
+        }
+
+        if let Some(severity) = self.severity {
+            query = query.severity(severity);
+        }
+
+        if let Some(date) = &self.since {
+            query = query.since(date.clone());
+        }
+
+        if let Some(date) = &self.until {
+            query = query.until(date.clone());
+        }
+
+        for category in &self.category {
+            query = query.category(category.clone());
+        }
+
+        query
+    }
+}
+
+impl Runnable for SearchCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let mut config = app_config().clone();
+        config.net.offline |= self.offline;
+
+        // Checkouts given explicitly (e.g. mirrors) are searched as they are
+        if let Some(path) = &self.db_path {
+            config.database.path = Some(path.clone());
+            config.database.fetch = false;
+        }
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+        let database = Auditor::load_database(&config);
+        let query = self.query();
+
+        let candidates = match &self.id {
+            Some(id) => database.query_by_id(id),
+            None => database.query(&query),
+        };
+
+        // Advisories without a CVSS severity can't be said to be at least as
+        // severe as the one given, so they aren't listed
+        let mut advisories: Vec<&Advisory> = candidates
+            .into_iter()
+            .filter(|advisory| query.matches(advisory))
+            .filter(|advisory| self.severity.is_none() || advisory.severity().is_some())
+            .collect();
+
+        // Newest first
+        advisories.sort_by(|a, b| (b.date(), b.id()).cmp(&(a.date(), a.id())));
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_search(&advisories) {
+            status_err!("{}", e);
+            exit(2);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! The `cargo audit verify` subcommand
+
+use crate::{
+    cache::Cache,
+    cargo_config,
+    config::{AuditConfig, OutputFormat},
+    index,
+    lockfile::CARGO_LOCK_FILE,
+    output,
+    prelude::*,
+    presenter::Presenter,
+    retry::Retry,
+    verify::{self, Check},
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::report::Outcome;
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit verify` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct VerifyCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Path to the lockfile
+    #[options(
+        short = "f",
+        long = "file",
+        meta = "PATH",
+        help = "Cargo lockfile to verify (default: Cargo.lock)"
+    )]
+    file: Option<PathBuf>,
+
+    /// Skip checking checksums against the index
+    #[options(
+        no_short,
+        long = "skip-checksums",
+        help = "don't check crates.io checksums against the index"
+    )]
+    skip_checksums: bool,
+
+    /// Skip checking for yanked releases
+    #[options(
+        no_short,
+        long = "skip-yanked",
+        help = "don't check for yanked crates.io releases"
+    )]
+    skip_yanked: bool,
+
+    /// Skip checking git dependencies' revisions
+    #[options(
+        no_short,
+        long = "skip-git-revs",
+        help = "don't check that git dependencies' revisions still exist upstream"
+    )]
+    skip_git_revs: bool,
+
+    /// Skip checking the lockfile against the workspace's manifests
+    #[options(
+        no_short,
+        long = "skip-resolution",
+        help = "don't check that the lockfile is consistent with the workspace's manifests"
+    )]
+    skip_resolution: bool,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the report as JSON")]
+    output_json: bool,
+}
+
+impl Runnable for VerifyCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        let lockfile_path = self
+            .file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(CARGO_LOCK_FILE));
+
+        let skip = [
+            (Check::Checksums, self.skip_checksums),
+            (Check::Yanked, self.skip_yanked),
+            (Check::GitRevs, self.skip_git_revs),
+            (Check::Resolution, self.skip_resolution),
+        ];
+
+        let settings = verify::Settings {
+            skip: skip
+                .iter()
+                .filter(|(_, skipped)| *skipped)
+                .map(|(check, _)| *check)
+                .collect(),
+            offline: config.net.offline,
+            git_dir: Cache::from_config(&config.cache)
+                .dir()
+                .join(verify::GIT_DIR),
+            retry: Retry::from_config(&config.net),
+        };
+
+        let replacement = cargo_config::crates_io_replacement(config.registry.debug)
+            .unwrap_or_else(|e| {
+                status_warn!("couldn't load cargo's source replacement: {}", e);
+                None
+            });
+
+        let client = index::Client::for_crates_io(&config, replacement);
+
+        let report =
+            verify::verify(&lockfile_path, client.as_ref(), &settings).unwrap_or_else(|e| {
+                status_err!("{}", e);
+                exit(config.output.exit_codes.code(Outcome::OperationalError));
+            });
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_verify(&report) {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        }
+
+        let outcome = if report.is_failure() {
+            Outcome::IntegrityFailed
+        } else {
+            Outcome::Clean
+        };
+
+        exit(config.output.exit_codes.code(outcome));
+    }
+}
@@ -0,0 +1,104 @@
+//! The `cargo audit vex` subcommand
+
+use crate::{
+    auditor::Auditor,
+    lockfile::CARGO_LOCK_FILE,
+    prelude::*,
+    state, suppressions,
+    vex::{self, Document},
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::lockfile::Lockfile;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+/// The `cargo audit vex` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct VexCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Path to the lockfile
+    #[options(
+        short = "f",
+        long = "file",
+        help = "Cargo lockfile whose ignored advisories to export (default: Cargo.lock)"
+    )]
+    file: Option<PathBuf>,
+
+    /// File to write the document to
+    #[options(
+        short = "o",
+        long = "output",
+        meta = "PATH",
+        help = "file to write the OpenVEX document to (default: STDOUT)"
+    )]
+    output: Option<PathBuf>,
+
+    /// Author of the document
+    #[options(
+        no_short,
+        long = "author",
+        meta = "NAME",
+        help = "person or organization authoring the document (default: cargo-audit)"
+    )]
+    author: Option<String>,
+}
+
+impl Runnable for VexCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let config = app_config();
+        let lockfile_path = self
+            .file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(CARGO_LOCK_FILE));
+
+        let lockfile = Lockfile::load(lockfile_path).unwrap_or_else(|e| {
+            status_err!("couldn't load {}: {}", lockfile_path.display(), e);
+            exit(2);
+        });
+
+        let database = Auditor::load_database(&config);
+        let suppressions = suppressions::suppressions(
+            &database,
+            &lockfile,
+            &config.advisories.ignore,
+            &state::today(),
+        );
+
+        let author = self.author.as_deref().unwrap_or(vex::DEFAULT_AUTHOR);
+        let document = Document::new(&lockfile, &suppressions, author);
+        let json = serde_json::to_string_pretty(&document).expect("document serializes");
+
+        match &self.output {
+            Some(path) => {
+                if let Err(e) = fs::write(path, format!("{}\n", json)) {
+                    status_err!("couldn't write {}: {}", path.display(), e);
+                    exit(2);
+                }
+
+                status_ok!(
+                    "Wrote",
+                    "{} VEX {} to {}",
+                    document.statements.len(),
+                    if document.statements.len() == 1 {
+                        "statement"
+                    } else {
+                        "statements"
+                    },
+                    path.display()
+                );
+            }
+            None => println!("{}", json),
+        }
+    }
+}
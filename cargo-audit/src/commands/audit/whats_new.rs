@@ -0,0 +1,192 @@
+//! The `cargo audit whats-new` subcommand
+
+use crate::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    output,
+    prelude::*,
+    presenter::Presenter,
+    whats_new::{self, Since, Snapshot},
+};
+use abscissa_core::{Command, Runnable};
+use gumdrop::Options;
+use rustsec::{advisory, lockfile::Lockfile, report::Outcome};
+use std::{path::PathBuf, process::exit};
+
+/// The `cargo audit whats-new` subcommand
+#[derive(Command, Default, Debug, Options)]
+pub struct WhatsNewCommand {
+    /// Get help information
+    #[options(short = "h", long = "help", help = "output help information and exit")]
+    help: bool,
+
+    /// Commit of the advisory database to compare against
+    #[options(
+        no_short,
+        long = "since-commit",
+        meta = "SHA",
+        help = "list advisories added since this commit of the advisory database"
+    )]
+    since_commit: Option<String>,
+
+    /// Date to compare the advisory database as of
+    #[options(
+        no_short,
+        long = "since-date",
+        meta = "YYYY-MM-DD",
+        help = "list advisories added to the advisory database on or after this date"
+    )]
+    since_date: Option<advisory::Date>,
+
+    /// Snapshot of the advisory IDs to compare against
+    #[options(
+        no_short,
+        long = "since-snapshot",
+        meta = "PATH",
+        help = "list advisories which aren't in this snapshot (see --save-snapshot)"
+    )]
+    since_snapshot: Option<PathBuf>,
+
+    /// File to save a snapshot of the current advisory IDs to
+    #[options(
+        no_short,
+        long = "save-snapshot",
+        meta = "PATH",
+        help = "save a snapshot of the current advisory IDs to compare against later"
+    )]
+    save_snapshot: Option<PathBuf>,
+
+    /// Lockfiles to cross-reference the new advisories against
+    #[options(
+        short = "f",
+        long = "file",
+        help = "flag the new advisories affecting this Cargo lockfile (repeatable)"
+    )]
+    file: Vec<PathBuf>,
+
+    /// Output reports as JSON
+    #[options(no_short, long = "json", help = "output the report as JSON")]
+    output_json: bool,
+}
+
+impl WhatsNewCommand {
+    /// Reference point to compare against, if exactly one is given
+    fn since(&self) -> Result<Option<Since>, &'static str> {
+        let mut given = vec![];
+
+        if let Some(commit) = &self.since_commit {
+            given.push(Since::Commit(commit.clone()));
+        }
+
+        if let Some(date) = &self.since_date {
+            given.push(Since::Date(date.clone()));
+        }
+
+        if let Some(path) = &self.since_snapshot {
+            given.push(Since::Snapshot(path.clone()));
+        }
+
+        if given.len() > 1 {
+            return Err(
+                "only one of --since-commit, --since-date and --since-snapshot can be given",
+            );
+        }
+
+        Ok(given.pop())
+    }
+}
+
+impl Runnable for WhatsNewCommand {
+    fn run(&self) {
+        if self.help {
+            Self::print_usage_and_exit(&[]);
+        }
+
+        let since = self.since().unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(2);
+        });
+
+        if since.is_none() && self.save_snapshot.is_none() {
+            status_err!("--since-commit, --since-date or --since-snapshot is required");
+            exit(2);
+        }
+
+        let mut config = AuditConfig::clone(&app_config());
+
+        if self.output_json {
+            config.output.format = OutputFormat::Json;
+        }
+
+        output::status_to_stderr(config.output.format.is_machine_readable());
+
+        let lockfiles: Vec<_> = self
+            .file
+            .iter()
+            .map(|path| {
+                let lockfile = Lockfile::load(path).unwrap_or_else(|e| {
+                    status_err!("couldn't load {}: {}", path.display(), e);
+                    exit(2);
+                });
+
+                (path, lockfile)
+            })
+            .collect();
+
+        let sources = config.database.resolve_sources().unwrap_or_else(|e| {
+            status_err!("{}", e);
+            exit(2);
+        });
+
+        let mut auditor = Auditor::new(&config);
+
+        // Compare before saving, so the snapshot compared against can be
+        // replaced with the current one
+        let report = since.map(|since| {
+            whats_new::compare(auditor.database(), &sources, since).unwrap_or_else(|e| {
+                status_err!("{}", e);
+                exit(config.output.exit_codes.code(Outcome::OperationalError));
+            })
+        });
+
+        if let Some(path) = &self.save_snapshot {
+            if let Err(e) = Snapshot::new(auditor.database()).save(path) {
+                status_err!("{}", e);
+                exit(config.output.exit_codes.code(Outcome::OperationalError));
+            }
+
+            if !config.output.is_quiet() {
+                status_ok!(
+                    "Saved",
+                    "snapshot of the advisory IDs to {}",
+                    path.display()
+                );
+            }
+        }
+
+        let mut report = match report {
+            Some(report) => report,
+            None => exit(0),
+        };
+
+        for (path, lockfile) in &lockfiles {
+            let audit = auditor.generate_report(lockfile);
+            whats_new::add_matches(&mut report, path, &audit);
+        }
+
+        let mut presenter = Presenter::new(&config.output);
+
+        if let Err(e) = presenter.print_whats_new(&report) {
+            status_err!("{}", e);
+            exit(config.output.exit_codes.code(Outcome::OperationalError));
+        }
+
+        let outcome = if report.matching_count() > 0 {
+            Outcome::VulnerabilitiesFound
+        } else {
+            Outcome::Clean
+        };
+
+        exit(config.output.exit_codes.code(outcome));
+    }
+}
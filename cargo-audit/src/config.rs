@@ -1,15 +1,46 @@
 //! The `~/.cargo/audit.toml` configuration file
 
+mod editor;
+mod layers;
+mod validate;
+
+pub use self::{
+    editor::ConfigEditor,
+    validate::{advisory_id_problem, ConfigProblem},
+};
+
+use crate::{notify, state, text, vex::Justification};
+use abscissa_core::terminal::ColorChoice;
 use rustsec::warning;
 use rustsec::{
     advisory,
     database::scope,
-    platforms::target::{Arch, OS},
-    report, Error, ErrorKind,
+    package,
+    platforms::{
+        target::{Arch, OS},
+        Platform,
+    },
+    report::{self, Outcome, OverriddenAction},
+    repository::FetchOptions,
+    vulnerability::Scope,
+    Error, ErrorKind, Version,
+};
+use serde::{
+    de::{self, DeserializeOwned},
+    Deserialize, Deserializer, Serialize,
 };
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap as Map;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
+
+/// Prefix of environment variables which override configuration options
+pub const ENV_PREFIX: &str = "CARGO_AUDIT_";
+
+/// Separator between items in list-valued environment variables
+pub const ENV_LIST_SEPARATOR: char = ',';
 
 /// `cargo audit` configuration:
 ///
@@ -25,6 +56,26 @@ pub struct AuditConfig {
     #[serde(default)]
     pub database: DatabaseConfig,
 
+    /// Report cache configuration
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    /// EPSS score enrichment configuration
+    #[serde(default)]
+    pub epss: EpssConfig,
+
+    /// Known Exploited Vulnerabilities catalog configuration
+    #[serde(default)]
+    pub kev: KevConfig,
+
+    /// Lockfile-related configuration
+    #[serde(default)]
+    pub lockfile: LockfileConfig,
+
+    /// Feature selection to restrict auditing to
+    #[serde(default)]
+    pub features: FeaturesConfig,
+
     /// Output configuration
     #[serde(default)]
     pub output: OutputConfig,
@@ -37,24 +88,65 @@ pub struct AuditConfig {
     #[serde(default)]
     pub packages: PackageConfig,
 
+    /// Registry lookup configuration
+    #[serde(default)]
+    pub registry: RegistryConfig,
+
     /// Configuration for auditing for yanked crates
     #[serde(default)]
     pub yanked: YankedConfig,
+
+    /// Network configuration
+    #[serde(default)]
+    pub net: NetConfig,
+
+    /// Webhook notification configuration
+    #[serde(default)]
+    pub notify: NotifyConfig,
+
+    /// Configuration for tracking findings across runs
+    #[serde(default)]
+    pub state: StateConfig,
+
+    /// Watch mode (`--watch`) configuration
+    #[serde(default)]
+    pub watch: WatchConfig,
 }
 
 impl AuditConfig {
     /// Get audit report settings from the configuration
     pub fn report_settings(&self) -> report::Settings {
+        let today = state::today();
         let mut settings = rustsec::report::Settings::default();
-        settings.ignore = self.advisories.ignore.clone();
+        settings.ignore = self
+            .advisories
+            .ignore
+            .iter()
+            .filter(|advisory| advisory.is_active(&today) && !advisory.is_scoped())
+            .map(|advisory| advisory.id.clone())
+            .collect();
+        settings.ignore_categories = self.advisories.ignore_categories.clone();
+        settings.categories = self.advisories.categories.clone();
+        settings.exclude_categories = self.advisories.exclude_categories.clone();
         settings.severity = self.advisories.severity_threshold;
-        settings.target_arch = self.target.arch;
-        settings.target_os = self.target.os;
+        settings.unscored_severity = self.advisories.unscored_severity.assumed();
+        settings.include_withdrawn = self.advisories.include_withdrawn;
+        settings.target_arch = self.target.arch();
+        settings.target_os = self.target.os();
 
         if let Some(source) = &self.packages.source {
             settings.package_scope = Some(source.clone().into());
         }
 
+        settings.overridden = self.packages.overridden;
+
+        if self.packages.skip_git {
+            settings
+                .package_scope
+                .get_or_insert_with(Default::default)
+                .skip_git = true;
+        }
+
         if let Some(informational_warnings) = &self.advisories.informational_warnings {
             settings.informational_warnings = informational_warnings.clone();
         } else {
@@ -75,32 +167,389 @@ impl AuditConfig {
         };
 
         for deny in &self.output.deny {
-            match deny {
-                DenyOption::Warnings => {
-                    insert_if_not_present(advisory::Informational::Notice);
-                    insert_if_not_present(advisory::Informational::Unmaintained);
-                    insert_if_not_present(advisory::Informational::Unsound);
-                    break;
+            if *deny == DenyOption::Warnings {
+                insert_if_not_present(advisory::Informational::Notice);
+                insert_if_not_present(advisory::Informational::Unmaintained);
+                insert_if_not_present(advisory::Informational::Unsound);
+            } else if let Some(informational) = deny.informational() {
+                insert_if_not_present(informational);
+            }
+        }
+
+        // Categories can also be warned about without denying them, or
+        // silenced (even if they're warned about by default or denied)
+        for warn in &self.output.warn {
+            if let Some(informational) = warn.informational() {
+                insert_if_not_present(informational);
+            }
+        }
+
+        let allowed: Vec<_> = self
+            .output
+            .allow
+            .iter()
+            .filter_map(|allow| allow.informational())
+            .collect();
+
+        settings
+            .informational_warnings
+            .retain(|informational| !allowed.contains(informational));
+
+        settings
+    }
+
+    /// What the configuration would access the network for (unless offline),
+    /// along with how to avoid it. Used by `net.strict_offline` to refuse to
+    /// run rather than silently skipping these.
+    pub fn network_uses(&self) -> Vec<String> {
+        let mut uses = vec![];
+
+        // Misconfigured sources are reported when the database is loaded
+        for source in self.database.resolve_sources().unwrap_or_default() {
+            if source.fetch {
+                uses.push(format!(
+                    "fetching the advisory database `{}` (use --no-fetch, or --db-path with a vendored copy)",
+                    source.label()
+                ));
+            }
+        }
+
+        if self.yanked.enabled && self.yanked.update_index && self.database.fetch {
+            uses.push("updating the crates.io index (use --no-fetch)".to_owned());
+        }
+
+        if self.registry.lookup {
+            uses.push(
+                "looking up patched releases in the registry (use --no-registry-lookup)".to_owned(),
+            );
+        }
+
+        if self.epss.is_enabled() {
+            uses.push("fetching EPSS scores (don't use --epss)".to_owned());
+        }
+
+        if self.kev.enabled && self.kev.file.is_none() {
+            uses.push("downloading the KEV catalog (use --kev-file)".to_owned());
+        }
+
+        if !self.notify.urls.is_empty() {
+            uses.push("sending the report to webhooks (remove `notify.urls`)".to_owned());
+        }
+
+        uses
+    }
+
+    /// Override configuration options with `CARGO_AUDIT_*` environment variables
+    pub fn override_from_env(&mut self) -> Result<(), Error> {
+        let mut vars = vec![];
+
+        for (name, value) in env::vars_os() {
+            let name = match name.into_string() {
+                Ok(name) if name.starts_with(ENV_PREFIX) => name,
+                _ => continue,
+            };
+
+            let value = value
+                .into_string()
+                .map_err(|value| env_error(&name, &value.to_string_lossy(), "not UTF-8"))?;
+
+            vars.push((name, value));
+        }
+
+        self.override_from_vars(vars)
+    }
+
+    /// Override configuration options from the given `CARGO_AUDIT_*` variables.
+    ///
+    /// Variables map onto the config file structure as follows (list-valued
+    /// variables are separated by [`ENV_LIST_SEPARATOR`]; lists are appended
+    /// to, other values replace the ones from the config file):
+    ///
+    /// - `CARGO_AUDIT_IGNORE`: `advisories.ignore` (list)
+    /// - `CARGO_AUDIT_IGNORE_CATEGORIES`: `advisories.ignore-categories` (list)
+    /// - `CARGO_AUDIT_CATEGORIES`: `advisories.categories` (list)
+    /// - `CARGO_AUDIT_EXCLUDE_CATEGORIES`: `advisories.exclude-categories` (list)
+    /// - `CARGO_AUDIT_VEX`: `advisories.vex` (list)
+    /// - `CARGO_AUDIT_INFORMATIONAL_WARNINGS`: `advisories.informational_warnings` (list)
+    /// - `CARGO_AUDIT_SEVERITY_THRESHOLD`: `advisories.severity_threshold`
+    /// - `CARGO_AUDIT_UNSCORED_SEVERITY`: `advisories.unscored-severity`
+    /// - `CARGO_AUDIT_INCLUDE_WITHDRAWN`: `advisories.include-withdrawn`
+    /// - `CARGO_AUDIT_REACHABILITY`: `advisories.reachability`
+    /// - `CARGO_AUDIT_DB_PATH`: `database.path`
+    /// - `CARGO_AUDIT_DB_URL`: `database.url`
+    /// - `CARGO_AUDIT_DB_BACKEND`: `database.backend`
+    /// - `CARGO_AUDIT_DB_URLS`: `database.urls` (list)
+    /// - `CARGO_AUDIT_DB_FETCH`: `database.fetch`
+    /// - `CARGO_AUDIT_DB_STALE`: `database.stale`
+    /// - `CARGO_AUDIT_DB_MAX_AGE`: `database.max-age`
+    /// - `CARGO_AUDIT_DB_ON_STALE`: `database.on-stale`
+    /// - `CARGO_AUDIT_DB_FIRST_PUBLISHED`: `database.first_published`
+    /// - `CARGO_AUDIT_DB_VERIFY`: `database.verify`
+    /// - `CARGO_AUDIT_DB_SIGNING_KEYS`: `database.signing_keys` (list)
+    /// - `CARGO_AUDIT_DB_AS_OF`: `database.as_of`
+    /// - `CARGO_AUDIT_CACHE`: `cache.enabled`
+    /// - `CARGO_AUDIT_EPSS`: `epss.enabled`
+    /// - `CARGO_AUDIT_EPSS_THRESHOLD`: `epss.threshold`
+    /// - `CARGO_AUDIT_KEV`: `kev.enabled`
+    /// - `CARGO_AUDIT_KEV_FILE`: `kev.file`
+    /// - `CARGO_AUDIT_GENERATE_LOCKFILE`: `lockfile.generate`
+    /// - `CARGO_AUDIT_LOCKED`: `lockfile.locked`
+    /// - `CARGO_AUDIT_FEATURES`: `features.features` (list)
+    /// - `CARGO_AUDIT_ALL_FEATURES`: `features.all_features`
+    /// - `CARGO_AUDIT_NO_DEFAULT_FEATURES`: `features.no_default_features`
+    /// - `CARGO_AUDIT_FEATURE_AWARE`: `features.feature_aware`
+    /// - `CARGO_AUDIT_DENY`: `output.deny` (list)
+    /// - `CARGO_AUDIT_WARN`: `output.warn` (list)
+    /// - `CARGO_AUDIT_ALLOW`: `output.allow` (list)
+    /// - `CARGO_AUDIT_FORMAT`: `output.format`
+    /// - `CARGO_AUDIT_REPORTER`: `output.reporter` (the `--reporter` program)
+    /// - `CARGO_AUDIT_OUTPUT_FILE`: `output.file`
+    /// - `CARGO_AUDIT_QUIET`: `output.quiet`
+    /// - `CARGO_AUDIT_SHOW_TREE`: `output.show_tree`
+    /// - `CARGO_AUDIT_TREE_DEPTH`: `output.tree_depth`
+    /// - `CARGO_AUDIT_SHORTEST_PATH`: `output.shortest_path`
+    /// - `CARGO_AUDIT_SORT`: `output.sort`
+    /// - `CARGO_AUDIT_HYPERLINKS`: `output.hyperlinks`
+    /// - `CARGO_AUDIT_COLOR`: `output.color`
+    /// - `CARGO_AUDIT_PLAIN`: `output.plain`
+    /// - `CARGO_AUDIT_TEMPLATE`: `output.template`
+    /// - `CARGO_AUDIT_INCLUDE_INVENTORY`: `output.include_inventory`
+    /// - `CARGO_AUDIT_FAIL_SEVERITY`: `output.fail-severity`
+    /// - `CARGO_AUDIT_DEPENDENCY_KINDS`: `output.dependency_kinds` (list)
+    /// - `CARGO_AUDIT_TARGET`: `target.triple`
+    /// - `CARGO_AUDIT_TARGET_ARCH`: `target.arch`
+    /// - `CARGO_AUDIT_TARGET_OS`: `target.os`
+    /// - `CARGO_AUDIT_PACKAGES_SOURCE`: `packages.source`
+    /// - `CARGO_AUDIT_PACKAGES_SKIP_GIT`: `packages.skip_git`
+    /// - `CARGO_AUDIT_PACKAGES_OVERRIDDEN`: `packages.overridden`
+    /// - `CARGO_AUDIT_REGISTRY_LOOKUP`: `registry.lookup`
+    /// - `CARGO_AUDIT_YANKED_ENABLED`: `yanked.enabled`
+    /// - `CARGO_AUDIT_YANKED_UPDATE_INDEX`: `yanked.update_index`
+    /// - `CARGO_AUDIT_OFFLINE`: `net.offline`
+    /// - `CARGO_AUDIT_STRICT_OFFLINE`: `net.strict_offline`
+    /// - `CARGO_AUDIT_NET_ATTEMPTS`: `net.attempts`
+    /// - `CARGO_AUDIT_NET_TIMEOUT_SECS`: `net.timeout_secs`
+    /// - `CARGO_AUDIT_NET_PROXY`: `net.proxy`
+    /// - `CARGO_AUDIT_NOTIFY_URL`: `notify.urls` (list)
+    /// - `CARGO_AUDIT_NOTIFY_ON`: `notify.on`
+    /// - `CARGO_AUDIT_NOTIFY_FORMAT`: `notify.format`
+    /// - `CARGO_AUDIT_NOTIFY_REQUIRED`: `notify.required`
+    /// - `CARGO_AUDIT_STATE_FILE`: `state.file`
+    /// - `CARGO_AUDIT_STATE_UPDATE`: `state.update`
+    /// - `CARGO_AUDIT_STATE_FAIL_ON_NEW`: `state.fail-on-new`
+    /// - `CARGO_AUDIT_STATE_BASELINE`: `state.baseline`
+    /// - `CARGO_AUDIT_WATCH_FETCH_INTERVAL`: `watch.fetch_interval_minutes`
+    ///
+    /// Variables without the `CARGO_AUDIT_` prefix are ignored, as are those
+    /// with an unrecognized name (with a warning, e.g. for typos).
+    pub fn override_from_vars<I, K, V>(&mut self, vars: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (name, value) in vars {
+            let (name, value) = (name.as_ref(), value.as_ref());
+
+            if !name.starts_with(ENV_PREFIX) {
+                continue;
+            }
+
+            match &name[ENV_PREFIX.len()..] {
+                "IGNORE" => self.advisories.ignore.extend(env_list(name, value)?),
+                "IGNORE_CATEGORIES" => self
+                    .advisories
+                    .ignore_categories
+                    .extend(env_list(name, value)?),
+                "CATEGORIES" => self.advisories.categories.extend(env_list(name, value)?),
+                "EXCLUDE_CATEGORIES" => self
+                    .advisories
+                    .exclude_categories
+                    .extend(env_list(name, value)?),
+                "VEX" => self.advisories.vex.extend(env_list(name, value)?),
+                "INFORMATIONAL_WARNINGS" => {
+                    self.advisories.informational_warnings = Some(env_list(name, value)?)
                 }
-                DenyOption::Unmaintained => {
-                    insert_if_not_present(advisory::Informational::Unmaintained)
+                "SEVERITY_THRESHOLD" => {
+                    self.advisories.severity_threshold = Some(env_value(name, value)?)
                 }
-                DenyOption::Unsound => insert_if_not_present(advisory::Informational::Unsound),
-                DenyOption::Yanked => continue,
-            };
+                "UNSCORED_SEVERITY" => self.advisories.unscored_severity = env_value(name, value)?,
+                "INCLUDE_WITHDRAWN" => self.advisories.include_withdrawn = env_bool(name, value)?,
+                "REACHABILITY" => self.advisories.reachability = env_bool(name, value)?,
+                "DB_PATH" => self.database.path = Some(value.into()),
+                "DB_URL" => self.database.url = Some(value.to_owned()),
+                "DB_BACKEND" => self.database.backend = Some(env_value(name, value)?),
+                "DB_URLS" => self.database.urls.extend(env_list(name, value)?),
+                "DB_FETCH" => self.database.fetch = env_bool(name, value)?,
+                "DB_STALE" => self.database.stale = env_bool(name, value)?,
+                "DB_MAX_AGE" => self.database.max_age = Some(env_value(name, value)?),
+                "DB_ON_STALE" => self.database.on_stale = env_value(name, value)?,
+                "DB_FIRST_PUBLISHED" => self.database.first_published = env_bool(name, value)?,
+                "DB_VERIFY" => self.database.verify = env_bool(name, value)?,
+                "DB_SIGNING_KEYS" => self.database.signing_keys.extend(env_list(name, value)?),
+                "DB_AS_OF" => self.database.as_of = Some(env_value(name, value)?),
+                "CACHE" => self.cache.enabled = env_bool(name, value)?,
+                "EPSS" => self.epss.enabled = env_bool(name, value)?,
+                "EPSS_THRESHOLD" => self.epss.threshold = Some(env_parse(name, value)?),
+                "KEV" => self.kev.enabled = env_bool(name, value)?,
+                "KEV_FILE" => self.kev.file = Some(value.into()),
+                "GENERATE_LOCKFILE" => self.lockfile.generate = env_bool(name, value)?,
+                "LOCKED" => self.lockfile.locked = env_bool(name, value)?,
+                "FEATURES" => self.features.features.extend(env_list(name, value)?),
+                "ALL_FEATURES" => self.features.all_features = env_bool(name, value)?,
+                "NO_DEFAULT_FEATURES" => self.features.no_default_features = env_bool(name, value)?,
+                "FEATURE_AWARE" => self.features.feature_aware = env_bool(name, value)?,
+                "DENY" => {
+                    for kind in env_list(name, value)? {
+                        self.output.deny(kind);
+                    }
+                }
+                "WARN" => {
+                    for option in env_list(name, value)? {
+                        if !self.output.warn.contains(&option) {
+                            self.output.warn.push(option);
+                        }
+                    }
+                }
+                "ALLOW" => {
+                    for option in env_list(name, value)? {
+                        if !self.output.allow.contains(&option) {
+                            self.output.allow.push(option);
+                        }
+                    }
+                }
+                "FORMAT" => self.output.format = env_value(name, value)?,
+                "REPORTER" => self.output.reporter = Some(value.into()),
+                "OUTPUT_FILE" => self.output.file = Some(value.into()),
+                "QUIET" => self.output.quiet = env_bool(name, value)?,
+                "SHOW_TREE" => self.output.show_tree = Some(env_bool(name, value)?),
+                "TREE_DEPTH" => self.output.tree_depth = Some(env_parse(name, value)?),
+                "SHORTEST_PATH" => self.output.shortest_path = env_bool(name, value)?,
+                "SORT" => self.output.sort = Some(env_value(name, value)?),
+                "HYPERLINKS" => self.output.hyperlinks = env_value(name, value)?,
+                "COLOR" => self.output.color = env_value(name, value)?,
+                "PLAIN" => self.output.plain = env_bool(name, value)?,
+                "TEMPLATE" => self.output.template = Some(value.into()),
+                "INCLUDE_INVENTORY" => self.output.include_inventory = env_bool(name, value)?,
+                "FAIL_SEVERITY" => self.output.fail_severity = Some(env_value(name, value)?),
+                "DEPENDENCY_KINDS" => self.output.dependency_kinds = Some(env_list(name, value)?),
+                "TARGET" => self.target.triple = Some(value.trim().to_owned()),
+                "TARGET_ARCH" => self.target.arch = Some(env_value(name, value)?),
+                "TARGET_OS" => self.target.os = Some(env_value(name, value)?),
+                "PACKAGES_SOURCE" => self.packages.source = Some(env_value(name, value)?),
+                "PACKAGES_SKIP_GIT" => self.packages.skip_git = env_bool(name, value)?,
+                "PACKAGES_OVERRIDDEN" => self.packages.overridden = env_value(name, value)?,
+                "REGISTRY_LOOKUP" => self.registry.lookup = env_bool(name, value)?,
+                "YANKED_ENABLED" => self.yanked.enabled = env_bool(name, value)?,
+                "YANKED_UPDATE_INDEX" => self.yanked.update_index = env_bool(name, value)?,
+                "OFFLINE" => self.net.offline = env_bool(name, value)?,
+                "STRICT_OFFLINE" => self.net.strict_offline = env_bool(name, value)?,
+                "NET_ATTEMPTS" => self.net.attempts = Some(env_parse(name, value)?),
+                "NET_TIMEOUT_SECS" => self.net.timeout_secs = Some(env_parse(name, value)?),
+                "NET_PROXY" => self.net.proxy = Some(value.to_owned()),
+                "NOTIFY_URL" => self.notify.urls.extend(env_list(name, value)?),
+                "NOTIFY_ON" => self.notify.on = env_value(name, value)?,
+                "NOTIFY_FORMAT" => self.notify.format = env_value(name, value)?,
+                "NOTIFY_REQUIRED" => self.notify.required = env_bool(name, value)?,
+                "STATE_FILE" => self.state.file = Some(value.into()),
+                "STATE_UPDATE" => self.state.update = Some(env_bool(name, value)?),
+                "STATE_FAIL_ON_NEW" => self.state.fail_on_new = env_bool(name, value)?,
+                "STATE_BASELINE" => self.state.baseline = Some(value.into()),
+                "WATCH_FETCH_INTERVAL" => {
+                    self.watch.fetch_interval_minutes = Some(env_parse(name, value)?)
+                }
+                _ => tracing::warn!("ignoring unrecognized environment variable {}", name),
+            }
         }
 
-        settings
+        Ok(())
+    }
+}
+
+/// Parse the value of an environment variable the same way as the
+/// corresponding config file option
+fn env_value<T: DeserializeOwned>(name: &str, value: &str) -> Result<T, Error> {
+    toml::Value::String(value.trim().to_owned())
+        .try_into()
+        .map_err(|e| env_error(name, value, e))
+}
+
+/// Parse a list-valued environment variable
+fn env_list<T: DeserializeOwned>(name: &str, value: &str) -> Result<Vec<T>, Error> {
+    value
+        .split(ENV_LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| env_value(name, item))
+        .collect()
+}
+
+/// Parse a boolean-valued environment variable, which may also be `1` or `0`
+fn env_bool(name: &str, value: &str) -> Result<bool, Error> {
+    match value.trim() {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        _ => env_parse(name, value),
     }
 }
 
+/// Parse a number- or boolean-valued environment variable
+fn env_parse<T>(name: &str, value: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value.trim().parse().map_err(|e| env_error(name, value, e))
+}
+
+/// Error for an invalid environment variable value
+fn env_error(name: &str, value: &str, reason: impl ToString) -> Error {
+    Error::new(
+        ErrorKind::Parse,
+        &format!(
+            "invalid value for {}: {:?} ({})",
+            name,
+            value,
+            reason.to_string()
+        ),
+    )
+}
+
 /// Advisory-related configuration.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct AdvisoryConfig {
     /// Ignore advisories for the given IDs
     #[serde(default)]
-    pub ignore: Vec<advisory::Id>,
+    pub ignore: Vec<IgnoredAdvisory>,
+
+    /// Advisories acknowledged until a deadline, whose vulnerabilities are
+    /// reported separately and don't fail the audit until then
+    #[serde(default)]
+    pub acknowledged: Vec<Acknowledgment>,
+
+    /// Ignore vulnerabilities whose advisories only have categories in this
+    /// list (e.g. `denial-of-service`), reporting them separately instead
+    #[serde(default, rename = "ignore-categories")]
+    pub ignore_categories: Vec<advisory::Category>,
+
+    /// Only report vulnerabilities whose advisories have at least one of
+    /// these categories (if any are given, e.g. to only gate releases on
+    /// `memory-corruption` and `crypto-failure`), reporting the others
+    /// separately
+    #[serde(default)]
+    pub categories: Vec<advisory::Category>,
+
+    /// Report vulnerabilities whose advisories have any of these categories
+    /// separately, rather than as found
+    #[serde(default, rename = "exclude-categories")]
+    pub exclude_categories: Vec<advisory::Category>,
+
+    /// OpenVEX documents whose `not_affected` and `fixed` statements
+    /// suppress the vulnerabilities they're about, reporting them separately
+    /// instead
+    #[serde(default)]
+    pub vex: Vec<PathBuf>,
 
     /// Warn for the given types of informational advisories
     pub informational_warnings: Option<Vec<advisory::Informational>>,
@@ -110,161 +559,1904 @@ pub struct AdvisoryConfig {
     /// Vulnerabilities with explicit CVSS info which have a severity below
     /// this threshold will be ignored.
     pub severity_threshold: Option<advisory::Severity>,
+
+    /// Severity to assume for advisories without a CVSS score, for severity
+    /// thresholds, `fail-on` policies and sorting (default: `unknown`, i.e.
+    /// they're reported and fail the audit whatever the threshold)
+    #[serde(default, rename = "unscored-severity")]
+    pub unscored_severity: UnscoredSeverity,
+
+    /// Match withdrawn advisories too, which were filed in error (e.g. to
+    /// research them)
+    #[serde(default, rename = "include-withdrawn")]
+    pub include_withdrawn: bool,
+
+    /// Check whether the functions advisories list as affected are referenced
+    /// in the workspace's source code, as a hint for triage (default: false)
+    #[serde(default)]
+    pub reachability: bool,
 }
 
-/// Advisory Database configuration.
-///
-/// The advisory database is stored in a Git repository. This section of the
-/// configuration stores settings related to it.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
-pub struct DatabaseConfig {
-    /// Path to the local copy of advisory database's git repo (default: ~/.cargo/advisory-db)
-    pub path: Option<PathBuf>,
+/// Severity assumed for advisories without a CVSS score
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UnscoredSeverity {
+    /// Their severity is unknown, so they're over any threshold
+    Unknown,
 
-    /// URL to the advisory database's git repo (default: https://github.com/RustSec/advisory-db)
-    pub url: Option<String>,
+    /// They're assumed to have this severity
+    Assumed(advisory::Severity),
+}
 
-    /// Perform a `git fetch` before auditing (default: true)
-    pub fetch: bool,
+impl UnscoredSeverity {
+    /// Severity which is assumed (if any)
+    pub fn assumed(self) -> Option<advisory::Severity> {
+        match self {
+            UnscoredSeverity::Unknown => None,
+            UnscoredSeverity::Assumed(severity) => Some(severity),
+        }
+    }
+}
 
-    /// Allow a stale advisory database? (i.e. one which hasn't been updated in 90 days)
-    pub stale: bool,
+impl Default for UnscoredSeverity {
+    fn default() -> Self {
+        UnscoredSeverity::Unknown
+    }
 }
 
-/// Output configuration
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
-pub struct OutputConfig {
-    /// Disallow advisories which trigger warnings
-    #[serde(default)]
-    pub deny: Vec<DenyOption>,
+impl fmt::Display for UnscoredSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnscoredSeverity::Unknown => f.write_str("unknown"),
+            UnscoredSeverity::Assumed(severity) => write!(f, "{}", severity),
+        }
+    }
+}
 
-    /// Output format to use
-    #[serde(default)]
-    pub format: OutputFormat,
+impl FromStr for UnscoredSeverity {
+    type Err = Error;
 
-    /// Enable quiet mode
-    pub quiet: bool,
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "unknown" => Ok(UnscoredSeverity::Unknown),
+            _ => s.parse().map(UnscoredSeverity::Assumed).map_err(|_| {
+                Error::new(
+                    ErrorKind::Parse,
+                    &format!(
+                        "invalid unscored-severity: {} (expected a severity or `unknown`)",
+                        s
+                    ),
+                )
+            }),
+        }
+    }
+}
 
-    /// Show inverse dependency trees along with advisories (default: true)
-    pub show_tree: Option<bool>,
+impl<'de> Deserialize<'de> for UnscoredSeverity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
 }
 
-impl OutputConfig {
-    /// Is quiet mode enabled?
-    pub fn is_quiet(&self) -> bool {
-        self.quiet || self.format == OutputFormat::Json
+impl Serialize for UnscoredSeverity {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
     }
 }
 
-/// Warning kinds
-#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Serialize, Ord)]
-pub enum DenyOption {
-    /// Deny all warnings
-    #[serde(rename = "warnings")]
-    Warnings,
+/// Advisory to ignore, either given as a bare advisory ID or as a table
+/// which also records why (and optionally until when) it's ignored:
+///
+/// ```toml
+/// ignore = [{ id = "RUSTSEC-2019-0001", reason = "not reachable", expires = "2024-12-31" }]
+/// ```
+///
+/// Ignores which expire need a `reason`. Once an ignore expires, the
+/// advisory is reported (and fails the audit) as usual again. An OpenVEX
+/// `justification` (e.g. `vulnerable_code_not_in_execute_path`) can be
+/// recorded for `cargo audit vex`.
+///
+/// Ignores can be scoped to a `package` (and `version`) of it, or to
+/// packages the workspace only depends on `via` the given ones (see
+/// [`scoped_ignores`][crate::scoped_ignores]):
+///
+/// ```toml
+/// ignore = [{ id = "RUSTSEC-2021-0145", via = ["criterion"], reason = "benchmarks only" }]
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct IgnoredAdvisory {
+    /// ID of the advisory to ignore
+    pub id: advisory::Id,
 
-    /// Deny unmaintained dependency warnings
-    #[serde(rename = "unmaintained")]
-    Unmaintained,
+    /// Why the advisory is ignored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
 
-    /// Deny unsound dependency warnings
-    #[serde(rename = "unsound")]
-    Unsound,
+    /// Date until which the advisory is ignored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<advisory::Date>,
 
-    /// Deny yanked dependency warnings
-    #[serde(rename = "yanked")]
-    Yanked,
+    /// Why the workspace isn't affected, for OpenVEX documents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub justification: Option<Justification>,
+
+    /// Only ignore the advisory for the package with this name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<package::Name>,
+
+    /// Only ignore the advisory for this version of the package
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+
+    /// Only ignore the advisory for packages which the workspace only
+    /// depends on through (any of) these packages
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub via: Vec<package::Name>,
 }
 
-impl DenyOption {
-    /// Get all of the possible warnings to be denied
-    pub fn all() -> Vec<Self> {
-        vec![
-            DenyOption::Warnings,
-            DenyOption::Unmaintained,
-            DenyOption::Unsound,
-            DenyOption::Yanked,
-        ]
+impl IgnoredAdvisory {
+    /// Is the ignore scoped to some packages or dependency paths, rather than
+    /// ignoring the advisory altogether?
+    pub fn is_scoped(&self) -> bool {
+        self.package.is_some() || self.version.is_some() || !self.via.is_empty()
     }
-    /// Get the warning::Kind that corresponds to self, if applicable
-    pub fn get_warning_kind(self) -> Option<warning::Kind> {
-        match self {
-            DenyOption::Warnings => None,
-            DenyOption::Unmaintained => Some(warning::Kind::Unmaintained),
-            DenyOption::Unsound => Some(warning::Kind::Unsound),
-            DenyOption::Yanked => Some(warning::Kind::Yanked),
+
+    /// Is the advisory still ignored on the given date? Advisories ignored
+    /// without an expiry date always are.
+    pub fn is_active(&self, today: &advisory::Date) -> bool {
+        match &self.expires {
+            Some(expires) => today.as_str() <= expires.as_str(),
+            None => true,
         }
     }
 }
 
-impl FromStr for DenyOption {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Error> {
-        match s {
-            "warnings" => Ok(DenyOption::Warnings),
-            "unmaintained" => Ok(DenyOption::Unmaintained),
-            "unsound" => Ok(DenyOption::Unsound),
-            "yanked" => Ok(DenyOption::Yanked),
-            other => Err(Error::new(
-                ErrorKind::Parse,
-                &format!("invalid deny option: {}", other),
-            )),
+impl From<advisory::Id> for IgnoredAdvisory {
+    fn from(id: advisory::Id) -> Self {
+        Self {
+            id,
+            reason: None,
+            expires: None,
+            justification: None,
+            package: None,
+            version: None,
+            via: vec![],
         }
     }
 }
 
-/// Output format
-#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum OutputFormat {
-    /// Display JSON
-    #[serde(rename = "json")]
-    Json,
+impl<'de> Deserialize<'de> for IgnoredAdvisory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Table form of an ignored advisory
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Entry {
+            id: advisory::Id,
+            reason: Option<String>,
+            expires: Option<advisory::Date>,
+            justification: Option<Justification>,
+            package: Option<package::Name>,
+            version: Option<Version>,
+            #[serde(default)]
+            via: Vec<package::Name>,
+        }
 
-    /// Display human-readable output to the terminal
-    #[serde(rename = "terminal")]
-    Terminal,
-}
+        struct IgnoredAdvisoryVisitor;
 
-impl Default for OutputFormat {
-    fn default() -> Self {
-        OutputFormat::Terminal
+        impl<'de> de::Visitor<'de> for IgnoredAdvisoryVisitor {
+            type Value = IgnoredAdvisory;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("an advisory ID or a table with an `id` key")
+            }
+
+            fn visit_str<E: de::Error>(self, id: &str) -> Result<IgnoredAdvisory, E> {
+                id.parse::<advisory::Id>()
+                    .map(Into::into)
+                    .map_err(E::custom)
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<IgnoredAdvisory, A::Error> {
+                let entry = Entry::deserialize(de::value::MapAccessDeserializer::new(map))?;
+
+                if entry.expires.is_some() && entry.reason.is_none() {
+                    return Err(de::Error::custom(format!(
+                        "the ignore of {} expires, so it needs a `reason`",
+                        entry.id
+                    )));
+                }
+
+                if entry.version.is_some() && entry.package.is_none() {
+                    return Err(de::Error::custom(format!(
+                        "the ignore of {} has a `version`, so it needs a `package`",
+                        entry.id
+                    )));
+                }
+
+                Ok(IgnoredAdvisory {
+                    id: entry.id,
+                    reason: entry.reason,
+                    expires: entry.expires,
+                    justification: entry.justification,
+                    package: entry.package,
+                    version: entry.version,
+                    via: entry.via,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(IgnoredAdvisoryVisitor)
     }
 }
 
-/// Target configuration
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+/// Advisory acknowledged until a deadline (e.g. while a ticket to remediate
+/// it is worked on), written by `cargo audit ack`:
+///
+/// ```toml
+/// acknowledged = [{ id = "RUSTSEC-2019-0001", ticket = "JIRA-123", date = "2024-12-01", expires = "2024-12-31" }]
+/// ```
+///
+/// Unlike ignored advisories, acknowledged ones are still reported (along
+/// with their ticket), and fail the audit again once they expire.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
-pub struct TargetConfig {
-    /// Target architecture to find vulnerabilities for
-    pub arch: Option<Arch>,
+pub struct Acknowledgment {
+    /// ID of the acknowledged advisory
+    pub id: advisory::Id,
 
-    /// Target OS to find vulnerabilities for
-    pub os: Option<OS>,
+    /// Reference to the ticket tracking the remediation
+    pub ticket: String,
+
+    /// Date the advisory was acknowledged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<advisory::Date>,
+
+    /// Last day the acknowledgment is in effect
+    pub expires: advisory::Date,
 }
 
-/// Packages configuration
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
-pub struct PackageConfig {
-    /// Package scope which should be considered for querying for vulnerabilities.
-    pub source: Option<scope::Registry>,
+impl Acknowledgment {
+    /// Is the acknowledgment still in effect on the given date?
+    pub fn is_active(&self, today: &advisory::Date) -> bool {
+        today.as_str() <= self.expires.as_str()
+    }
 }
 
-/// Configuration for auditing for yanked crates
+/// Advisory Database configuration.
+///
+/// The advisory database is stored in a Git repository. This section of the
+/// configuration stores settings related to it.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
-pub struct YankedConfig {
-    /// Is auditing for yanked crates enabled?
-    #[serde(default = "default_true")]
-    pub enabled: bool,
+pub struct DatabaseConfig {
+    /// Path to the local copy of advisory database's git repo (default: `$CARGO_HOME/advisory-db`)
+    pub path: Option<PathBuf>,
 
-    /// Should the crates.io index be updated before checking for yanked crates?
-    #[serde(default = "default_true")]
-    pub update_index: bool,
-}
+    /// URL to the advisory database's git repo (default: https://github.com/RustSec/advisory-db),
+    /// or to a tarball snapshot of it with the `tarball` backend
+    pub url: Option<String>,
+
+    /// How the database is fetched (default: `git`)
+    pub backend: Option<DatabaseBackend>,
+
+    /// Perform a `git fetch` before auditing (default: true)
+    #[serde(default = "default_true")]
+    pub fetch: bool,
+
+    /// Allow a stale advisory database? (i.e. one which hasn't been updated in 90 days)
+    #[serde(default)]
+    pub stale: bool,
+
+    /// How long ago the advisory database's latest commit may be, e.g. `7d`,
+    /// before `on-stale` applies (default: no limit). This is checked even
+    /// when the database isn't fetched, e.g. for a cached copy on an offline
+    /// CI runner.
+    #[serde(rename = "max-age")]
+    pub max_age: Option<MaxAge>,
+
+    /// What to do when the advisory database is older than `max-age`
+    /// (default: `warn`)
+    #[serde(rename = "on-stale", default)]
+    pub on_stale: StalePolicy,
+
+    /// Look up when each matched advisory was first added to the database's
+    /// git history (default: false)
+    #[serde(default)]
+    pub first_published: bool,
+
+    /// Only use fetched git databases whose latest commit is signed by one
+    /// of the `signing_keys` (default: false)
+    #[serde(default)]
+    pub verify: bool,
+
+    /// Paths to the ASCII-armored OpenPGP public keys which `verify` trusts
+    #[serde(default)]
+    pub signing_keys: Vec<PathBuf>,
+
+    /// Named advisory databases to load instead of the one configured above,
+    /// which are merged into a single database
+    #[serde(default)]
+    pub sources: Map<String, DatabaseSource>,
+
+    /// URLs of additional advisory databases' git repos, which are merged
+    /// into the database (or sources) configured above, e.g. an internal one
+    #[serde(default)]
+    pub urls: Vec<String>,
+
+    /// Audit against the database as it was on this date (`YYYY-MM-DD`):
+    /// git sources are loaded at their last commit on or before it, and
+    /// other sources only keep the advisories issued by then
+    pub as_of: Option<advisory::Date>,
+
+    /// Only load these named sources (set by `--db` rather than the config
+    /// file)
+    #[serde(skip)]
+    pub only: Vec<String>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            url: None,
+            backend: None,
+            fetch: true,
+            stale: false,
+            max_age: None,
+            on_stale: StalePolicy::default(),
+            first_published: false,
+            verify: false,
+            signing_keys: vec![],
+            sources: Map::new(),
+            urls: vec![],
+            as_of: None,
+            only: vec![],
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Directory named sources are stored in by default:
+    /// `$CARGO_HOME/advisory-dbs`
+    pub fn default_sources_dir() -> PathBuf {
+        home::cargo_home()
+            .map(|cargo_home| cargo_home.join(DATABASE_SOURCES_DIR))
+            .unwrap_or_else(|_| PathBuf::from(DATABASE_SOURCES_DIR))
+    }
+
+    /// Directory bundles of the advisory database are unpacked into:
+    /// `$CARGO_HOME/advisory-db-bundles`
+    pub fn default_bundles_dir() -> PathBuf {
+        home::cargo_home()
+            .map(|cargo_home| cargo_home.join(DATABASE_BUNDLES_DIR))
+            .unwrap_or_else(|_| PathBuf::from(DATABASE_BUNDLES_DIR))
+    }
+
+    /// Advisory databases to load, in the order they're merged (advisories
+    /// from earlier sources take precedence over ones with the same ID).
+    ///
+    /// If a `path` is given (e.g. with `--db-path`), or no sources are
+    /// configured, this is the single database configured by `path` and
+    /// `url`. Otherwise it's the configured sources (in order of their
+    /// names), or those selected with `--db`. The databases in `urls` are
+    /// merged into either, in order.
+    pub fn resolve_sources(&self) -> Result<Vec<ResolvedSource>, Error> {
+        let mut sources = self.configured_sources()?;
+
+        for url in &self.urls {
+            if sources
+                .iter()
+                .any(|source| source.url.as_deref() == Some(url.as_str()))
+            {
+                continue;
+            }
+
+            let name = url_source_name(url);
+
+            sources.push(ResolvedSource {
+                path: Self::default_sources_dir().join(&name),
+                name: Some(name),
+                url: Some(url.clone()),
+                backend: DatabaseBackend::Git,
+                fetch: self.fetch,
+                stale: self.stale,
+            });
+        }
+
+        Ok(sources)
+    }
+
+    /// Databases configured by `path` and `url`, or by named sources
+    fn configured_sources(&self) -> Result<Vec<ResolvedSource>, Error> {
+        if self.path.is_some() || self.sources.is_empty() {
+            if let Some(name) = self.only.first() {
+                return Err(if self.path.is_some() {
+                    Error::new(
+                        ErrorKind::BadParam,
+                        &format!(
+                            "can't select the `{}` database source along with a database path",
+                            name
+                        ),
+                    )
+                } else {
+                    unknown_source(name)
+                });
+            }
+
+            let backend = self.backend.unwrap_or_else(|| match &self.path {
+                Some(path) if is_vendored(path) => DatabaseBackend::Local,
+                _ => DatabaseBackend::Git,
+            });
+
+            let (default_url, default_path): (&str, fn() -> PathBuf) = match backend {
+                DatabaseBackend::Tarball => (
+                    rustsec::repository::tarball::DEFAULT_URL,
+                    rustsec::repository::tarball::Snapshot::default_path,
+                ),
+                _ => (
+                    rustsec::repository::git::DEFAULT_URL,
+                    rustsec::repository::git::Repository::default_path,
+                ),
+            };
+
+            return Ok(vec![ResolvedSource {
+                name: None,
+                url: Some(self.url.clone().unwrap_or_else(|| default_url.to_owned())),
+                path: self.path.clone().unwrap_or_else(default_path),
+                backend,
+                fetch: self.fetch && backend != DatabaseBackend::Local,
+                stale: self.stale,
+            }]);
+        }
+
+        if let Some(name) = self
+            .only
+            .iter()
+            .find(|name| !self.sources.contains_key(*name))
+        {
+            return Err(unknown_source(name));
+        }
+
+        Ok(self
+            .sources
+            .iter()
+            .filter(|(name, _)| self.only.is_empty() || self.only.contains(name))
+            .map(|(name, source)| {
+                let backend = source.backend.unwrap_or_else(|| match &source.path {
+                    Some(path) if is_vendored(path) => DatabaseBackend::Local,
+                    _ => DatabaseBackend::Git,
+                });
+
+                ResolvedSource {
+                    name: Some(name.clone()),
+                    url: source.url.clone(),
+                    path: source
+                        .path
+                        .clone()
+                        .unwrap_or_else(|| Self::default_sources_dir().join(name)),
+                    backend,
+                    fetch: self.fetch
+                        && source.fetch.unwrap_or(true)
+                        && backend != DatabaseBackend::Local,
+                    stale: self.stale || source.stale.unwrap_or(false),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Is the given path a vendored copy of an advisory database, which is used
+/// as-is rather than fetched: a bundle of it packed into a single `.tar.gz`
+/// file, or a directory of advisories without a git repository?
+pub fn is_vendored(path: &Path) -> bool {
+    path.is_file() || (path.join("crates").is_dir() && !path.join(".git").exists())
+}
+
+/// Name of the source for an advisory database in `urls`, which is also the
+/// name of the directory it's cloned to, e.g. `git.example.com-security-advisory-db`
+/// for `https://git.example.com/security/advisory-db.git`
+pub fn url_source_name(url: &str) -> String {
+    let url = match url.find("://") {
+        Some(scheme_end) => &url[scheme_end + 3..],
+        None => url,
+    };
+
+    let url = url.trim_end_matches('/');
+    let url = url.trim_end_matches(".git");
+
+    url.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Error for a `--db` selection which doesn't name a configured source
+fn unknown_source(name: &str) -> Error {
+    Error::new(
+        ErrorKind::BadParam,
+        &format!("no advisory database source named `{}` is configured", name),
+    )
+}
+
+/// Name of the directory named database sources are stored in by default
+/// (located in `$CARGO_HOME`)
+pub const DATABASE_SOURCES_DIR: &str = "advisory-dbs";
+
+/// Name of the directory bundles of the advisory database are unpacked into
+/// (located in `$CARGO_HOME`), one subdirectory per bundle
+pub const DATABASE_BUNDLES_DIR: &str = "advisory-db-bundles";
+
+/// Named advisory database, e.g. `[database.sources.internal]`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseSource {
+    /// URL of the database's git repo, or of a tarball snapshot of it with
+    /// the `tarball` backend (required to fetch it)
+    pub url: Option<String>,
+
+    /// Path to the local copy of the database (default:
+    /// `$CARGO_HOME/advisory-dbs/<name>`)
+    pub path: Option<PathBuf>,
+
+    /// How the database is fetched (default: `git`)
+    pub backend: Option<DatabaseBackend>,
+
+    /// Fetch the database before auditing (default: true, unless
+    /// `database.fetch` is false)
+    pub fetch: Option<bool>,
+
+    /// Allow the database to be stale (default: false, unless
+    /// `database.stale` is true)
+    pub stale: Option<bool>,
+}
+
+/// How an advisory database source is fetched
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum DatabaseBackend {
+    /// `git fetch` the database's repo
+    #[serde(rename = "git")]
+    Git,
+
+    /// Never fetch the database: it's a local directory kept up to date by
+    /// other means (e.g. a mirror sync job), or a bundle of it packed into a
+    /// single `.tar.gz` file. This is the default for vendored copies (see
+    /// [`is_vendored`]).
+    #[serde(rename = "local")]
+    Local,
+
+    /// Download a tarball snapshot of the database over HTTPS (e.g. from
+    /// GitHub's codeload service), which doesn't need git. The snapshot is
+    /// only downloaded again once its `ETag` changes.
+    #[serde(rename = "tarball")]
+    Tarball,
+}
+
+/// Maximum age of the advisory database, written like `7d` or `12h`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MaxAge(pub Duration);
+
+impl fmt::Display for MaxAge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", humantime::format_duration(self.0))
+    }
+}
+
+impl FromStr for MaxAge {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        humantime::parse_duration(s).map(MaxAge).map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("invalid max-age: {} ({}, e.g. `7d`)", s, e),
+            )
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for MaxAge {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for MaxAge {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// What to do when the advisory database is older than `max-age`
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StalePolicy {
+    /// Print a warning, and audit against the database anyway
+    #[serde(rename = "warn")]
+    Warn,
+
+    /// Fail with the `stale-db` outcome
+    #[serde(rename = "error")]
+    Error,
+
+    /// Fetch the database (even with `--no-fetch`), and warn if it's still
+    /// too old afterwards, e.g. because the network can't be accessed
+    #[serde(rename = "refetch")]
+    Refetch,
+}
+
+impl Default for StalePolicy {
+    fn default() -> Self {
+        StalePolicy::Warn
+    }
+}
+
+/// Advisory database to load, with the defaults applied
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolvedSource {
+    /// Name of the source (`None` for the database configured by `path` and
+    /// `url`)
+    pub name: Option<String>,
+
+    /// URL of the database's git repo (or tarball)
+    pub url: Option<String>,
+
+    /// Path to the local copy of the database
+    pub path: PathBuf,
+
+    /// How the database is fetched
+    pub backend: DatabaseBackend,
+
+    /// Fetch the database before auditing
+    pub fetch: bool,
+
+    /// Allow the database to be stale
+    pub stale: bool,
+}
+
+impl ResolvedSource {
+    /// Name of the source in reports: its name, or else its URL or path
+    pub fn label(&self) -> String {
+        match (&self.name, &self.url) {
+            (Some(name), _) => name.clone(),
+            (None, Some(url)) => url.clone(),
+            (None, None) => self.path.display().to_string(),
+        }
+    }
+}
+
+/// Report cache configuration.
+///
+/// When enabled, the report for a lockfile is cached and replayed as long as
+/// the lockfile, the advisory database commit, and the configuration are all
+/// unchanged.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Cache audit reports (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path to the cache directory (default: `~/.cargo/audit-cache`)
+    pub path: Option<PathBuf>,
+}
+
+/// Configuration for tracking findings across runs
+///
+/// When a state file is configured, each audit compares its findings to the
+/// ones recorded by the last audit, marking the new ones and listing those
+/// which were resolved, then records its own findings.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateConfig {
+    /// File recording the findings of the last audit
+    pub file: Option<PathBuf>,
+
+    /// Update the state file after each audit (default: true)
+    pub update: Option<bool>,
+
+    /// Only fail the audit because of findings which are new since the last
+    /// audit (default: false)
+    #[serde(default, rename = "fail-on-new")]
+    pub fail_on_new: bool,
+
+    /// Baseline (from `--write-baseline`, or a report from
+    /// `cargo audit --json`) to compare findings to, instead of a state file.
+    /// Only findings which aren't in the baseline fail the audit.
+    pub baseline: Option<PathBuf>,
+
+    /// Record the findings in this baseline file (set by `--write-baseline`
+    /// rather than the config file)
+    #[serde(skip)]
+    pub write_baseline: Option<PathBuf>,
+}
+
+impl StateConfig {
+    /// Do only findings which are new since the last audit (or the
+    /// baseline) fail the audit?
+    pub fn only_new_fail(&self) -> bool {
+        self.fail_on_new || self.baseline.is_some() || self.write_baseline.is_some()
+    }
+}
+
+/// Watch mode (`--watch`) configuration
+///
+/// In watch mode, the lockfile is audited again whenever it changes, and the
+/// advisory database is fetched again periodically (auditing the lockfile
+/// again if it changed).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchConfig {
+    /// How often the advisory database is fetched again, in minutes
+    /// (default: 60)
+    pub fetch_interval_minutes: Option<u64>,
+}
+
+/// EPSS score enrichment configuration
+///
+/// When enabled, vulnerabilities whose advisories have CVE aliases are
+/// annotated with their Exploit Prediction Scoring System (EPSS) score,
+/// fetched from the FIRST API and cached in the report cache directory.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EpssConfig {
+    /// Fetch EPSS scores for vulnerabilities (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// URL of the EPSS API (default: <https://api.first.org/data/v1/epss>)
+    pub url: Option<String>,
+
+    /// How long fetched scores are cached for, in hours (default: 24)
+    pub ttl_hours: Option<u64>,
+
+    /// Only report vulnerabilities at least this likely to be exploited (a
+    /// probability, 0-1). Vulnerabilities without a score (e.g. whose
+    /// advisories have no CVE alias) and known exploited ones are always
+    /// reported. Setting it enables fetching scores.
+    pub threshold: Option<f64>,
+}
+
+impl EpssConfig {
+    /// Is fetching scores enabled? (giving a threshold enables it)
+    pub fn is_enabled(&self) -> bool {
+        self.enabled || self.threshold.is_some()
+    }
+}
+
+/// CISA Known Exploited Vulnerabilities (KEV) catalog configuration
+///
+/// When enabled, vulnerabilities whose advisories have a CVE alias in the
+/// catalog are marked as known to be exploited. The catalog is downloaded
+/// to the report cache directory, unless a local copy is given.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KevConfig {
+    /// Check vulnerabilities against the catalog (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path to a local copy of the catalog, which is used instead of
+    /// downloading it (e.g. in air-gapped environments)
+    pub file: Option<PathBuf>,
+
+    /// URL of the catalog (default: the JSON feed on <https://www.cisa.gov>)
+    pub url: Option<String>,
+
+    /// How long the downloaded catalog is used for, in hours (default: 24)
+    pub ttl_hours: Option<u64>,
+}
+
+impl KevConfig {
+    /// Is checking against the catalog enabled? (giving a local copy of the
+    /// catalog enables it)
+    pub fn is_enabled(&self) -> bool {
+        self.enabled || self.file.is_some()
+    }
+}
+
+/// Lockfile-related configuration
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockfileConfig {
+    /// Run `cargo generate-lockfile` if `Cargo.lock` is missing (default: false)
+    #[serde(default)]
+    pub generate: bool,
+
+    /// Fail if `Cargo.lock` is out of date with the manifests, rather than
+    /// only warning (default: false)
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// Feature selection configuration: if any is given, only the packages
+/// `cargo build` would compile with it are audited
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// Features to enable (as with `cargo build --features`)
+    #[serde(default)]
+    pub features: Vec<String>,
+
+    /// Enable all features (as with `cargo build --all-features`)
+    #[serde(default)]
+    pub all_features: bool,
+
+    /// Don't enable the `default` feature (as with `cargo build --no-default-features`)
+    #[serde(default)]
+    pub no_default_features: bool,
+
+    /// Rather than pruning the packages which aren't compiled with the
+    /// feature selection (or the default features, if none is given), report
+    /// their vulnerabilities as `optional` warnings
+    #[serde(default)]
+    pub feature_aware: bool,
+
+    /// `Cargo.toml` to resolve the features of (default: the one next to the
+    /// lockfile; set by `--manifest-path` rather than the config file)
+    #[serde(skip)]
+    pub manifest_path: Option<PathBuf>,
+}
+
+impl FeaturesConfig {
+    /// Was a feature selection given?
+    pub fn is_selected(&self) -> bool {
+        !self.features.is_empty() || self.all_features || self.no_default_features
+    }
+}
+
+/// Output configuration
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Disallow advisories which trigger warnings
+    #[serde(default)]
+    pub deny: Vec<DenyOption>,
+
+    /// Optional checks which produce warnings
+    #[serde(default)]
+    pub warn: Vec<WarnOption>,
+
+    /// Warnings to silence, overriding `warn` and `deny`
+    #[serde(default)]
+    pub allow: Vec<WarnOption>,
+
+    /// Output format to use
+    #[serde(default)]
+    pub format: OutputFormat,
+
+    /// Program to render reports with instead of the output format, which is
+    /// given the findings as NDJSON on STDIN, and whose output is the report
+    /// (set by `--reporter` or `CARGO_AUDIT_REPORTER` rather than the config
+    /// file, so auditing a workspace can't run commands of its choosing)
+    #[serde(skip)]
+    pub reporter: Option<String>,
+
+    /// Arguments to run the reporter with (set by `--reporter-arg`)
+    #[serde(skip)]
+    pub reporter_args: Vec<String>,
+
+    /// Enable quiet mode
+    #[serde(default)]
+    pub quiet: bool,
+
+    /// Show inverse dependency trees along with advisories (default: true)
+    pub show_tree: Option<bool>,
+
+    /// Depth to which inverse dependency trees are shown (default: all of
+    /// it)
+    pub tree_depth: Option<usize>,
+
+    /// Show only the shortest path from each affected package to a workspace
+    /// member, rather than its whole inverse dependency tree
+    #[serde(default)]
+    pub shortest_path: bool,
+
+    /// Order of vulnerabilities and warnings in terminal reports (default:
+    /// vulnerabilities by severity, and otherwise the order of JSON reports,
+    /// i.e. by advisory ID, then package)
+    pub sort: Option<SortOrder>,
+
+    /// Show the dependency tree of every affected version of a package
+    /// (set by `--verbose` rather than the config file)
+    #[serde(skip)]
+    pub verbose: bool,
+
+    /// Write the dependency paths from vulnerable and warned packages up to
+    /// the roots to this file, as a Graphviz (DOT) graph
+    pub graph: Option<PathBuf>,
+
+    /// Include the whole dependency graph in the DOT graph
+    #[serde(default)]
+    pub graph_full: bool,
+
+    /// Show the description of each advisory found
+    #[serde(default)]
+    pub show_description: bool,
+
+    /// Include the packages which were audited, and how many were skipped,
+    /// in reports (default: false)
+    #[serde(default)]
+    pub include_inventory: bool,
+
+    /// Width to wrap terminal reports to (default: the terminal's width, or
+    /// 80 columns if STDOUT isn't a terminal)
+    pub width: Option<usize>,
+
+    /// Render advisory IDs and URLs in terminal reports as hyperlinks
+    /// (default: auto)
+    #[serde(default)]
+    pub hyperlinks: Hyperlinks,
+
+    /// Color terminal reports and messages (default: auto, i.e. if STDOUT
+    /// is a terminal and `NO_COLOR` isn't set)
+    #[serde(default)]
+    pub color: Color,
+
+    /// Render terminal reports as plain text which doesn't depend on the
+    /// terminal, e.g. for CI logs: without colors or hyperlinks, with each
+    /// attribute on one line (whatever the width), and with dependency trees
+    /// in full rather than truncated to the terminal's width
+    #[serde(default)]
+    pub plain: bool,
+
+    /// Render each vulnerability in terminal reports with the template in
+    /// this file rather than the built-in attributes, e.g. to translate
+    /// their labels or add links to internal runbooks
+    pub template: Option<PathBuf>,
+
+    /// Exit statuses to use for the outcomes of an audit
+    #[serde(default, rename = "exit-codes")]
+    pub exit_codes: ExitCodes,
+
+    /// Write machine-readable reports to this file rather than STDOUT (or,
+    /// with terminal reports, also write the JSON report to it)
+    pub file: Option<PathBuf>,
+
+    /// Badge settings (for the `badge` format)
+    #[serde(default)]
+    pub badge: BadgeConfig,
+
+    /// Severity thresholds at which vulnerabilities fail the audit, for each
+    /// kind of dependency they're reachable through (default: every
+    /// vulnerability fails it)
+    #[serde(rename = "fail-on")]
+    pub fail_on: Option<FailOn>,
+
+    /// Kinds of dependency whose vulnerabilities fail the audit: those only
+    /// reachable through other kinds (e.g. dev-dependencies) are reported,
+    /// but never fail it, whatever their `fail-on` rule (default: all kinds)
+    pub dependency_kinds: Option<Vec<Scope>>,
+
+    /// Severity below which vulnerabilities are reported, but don't fail the
+    /// audit (default: none, so every vulnerability fails it)
+    #[serde(rename = "fail-severity")]
+    pub fail_severity: Option<advisory::Severity>,
+}
+
+impl OutputConfig {
+    /// Color choice for the terminal streams (never colored in plain mode)
+    pub fn color_choice(&self) -> ColorChoice {
+        if self.plain {
+            ColorChoice::Never
+        } else {
+            self.color.choice()
+        }
+    }
+
+    /// Severity thresholds at which vulnerabilities fail the audit for each
+    /// kind of dependency: the `fail-on` rules, with those for kinds which
+    /// aren't in `dependency_kinds` (if given) set to `never`
+    pub fn fail_policy(&self) -> Option<FailOn> {
+        let kinds = match &self.dependency_kinds {
+            Some(kinds) => kinds,
+            None => return self.fail_on.clone(),
+        };
+
+        let mut fail_on = self.fail_on.clone().unwrap_or_default();
+
+        for scope in Scope::all() {
+            if !kinds.contains(&scope) {
+                *fail_on.rule_mut(scope) = Some(FailThreshold::Never);
+            }
+        }
+
+        Some(fail_on)
+    }
+
+    /// Is quiet mode enabled?
+    pub fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    /// Are reports meant to be consumed by other programs (because of the
+    /// output format, or because a reporter renders them)?
+    pub fn is_machine_readable(&self) -> bool {
+        self.format.is_machine_readable() || self.reporter.is_some()
+    }
+
+    /// Is the given optional check enabled (with `warn`, and not silenced
+    /// with `allow`)?
+    pub fn is_warned(&self, option: WarnOption) -> bool {
+        self.warn.contains(&option) && !self.allow.contains(&option)
+    }
+
+    /// Deny the given kind of warning (`warnings` denies all of them)
+    pub fn deny(&mut self, kind: DenyOption) {
+        let kinds = if kind == DenyOption::Warnings {
+            DenyOption::all()
+        } else {
+            vec![kind]
+        };
+
+        for kind in kinds {
+            if !self.deny.contains(&kind) {
+                self.deny.push(kind);
+            }
+        }
+    }
+
+    /// Is the given vulnerability's severity below the `fail-severity`
+    /// threshold, so it doesn't fail the audit? Vulnerabilities without a
+    /// (known or assumed) severity never are.
+    pub fn is_below_fail_severity(&self, vuln: &rustsec::Vulnerability) -> bool {
+        match (self.fail_severity, vuln.effective_severity) {
+            (Some(threshold), Some(severity)) => severity < threshold,
+            _ => false,
+        }
+    }
+}
+
+/// Severity thresholds at which vulnerabilities fail the audit, for each
+/// kind of dependency (resolved with `cargo metadata`). Vulnerabilities
+/// reachable through several kinds fail if any of their rules says so.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FailOn {
+    /// Threshold for regular dependencies (default: any severity)
+    pub normal: Option<FailThreshold>,
+
+    /// Threshold for build dependencies, proc-macros and their dependencies
+    /// (default: any severity)
+    pub build: Option<FailThreshold>,
+
+    /// Threshold for dev-dependencies (default: any severity)
+    pub dev: Option<FailThreshold>,
+}
+
+impl FailOn {
+    /// Threshold for the given kind of dependency
+    pub fn threshold(&self, scope: Scope) -> FailThreshold {
+        let threshold = match scope {
+            Scope::Normal => self.normal,
+            Scope::Build => self.build,
+            Scope::Dev => self.dev,
+        };
+
+        threshold.unwrap_or(FailThreshold::Severity(advisory::Severity::None))
+    }
+
+    /// Rule for the given kind of dependency (if any)
+    fn rule_mut(&mut self, scope: Scope) -> &mut Option<FailThreshold> {
+        match scope {
+            Scope::Normal => &mut self.normal,
+            Scope::Build => &mut self.build,
+            Scope::Dev => &mut self.dev,
+        }
+    }
+
+    /// Scope with the strictest rule a vulnerability with the given severity
+    /// (if known) fails, of those it's reachable through
+    pub fn failed(&self, scopes: &[Scope], severity: Option<advisory::Severity>) -> Option<Scope> {
+        scopes
+            .iter()
+            .copied()
+            .filter(|&scope| self.threshold(scope).fails(severity))
+            .min_by_key(|&scope| self.threshold(scope))
+    }
+}
+
+/// Severity at which vulnerabilities fail the audit
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum FailThreshold {
+    /// Vulnerabilities with this severity or above fail, as do those without
+    /// a CVSS score
+    Severity(advisory::Severity),
+
+    /// Vulnerabilities never fail
+    Never,
+}
+
+impl FailThreshold {
+    /// Does a vulnerability with the given severity (if known) fail?
+    pub fn fails(self, severity: Option<advisory::Severity>) -> bool {
+        match self {
+            FailThreshold::Severity(threshold) => match severity {
+                Some(severity) => severity >= threshold,
+                None => true,
+            },
+            FailThreshold::Never => false,
+        }
+    }
+}
+
+impl fmt::Display for FailThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailThreshold::Severity(severity) => write!(f, "{}", severity),
+            FailThreshold::Never => f.write_str("never"),
+        }
+    }
+}
+
+impl FromStr for FailThreshold {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "never" => Ok(FailThreshold::Never),
+            _ => s.parse().map(FailThreshold::Severity).map_err(|_| {
+                Error::new(
+                    ErrorKind::Parse,
+                    &format!(
+                        "invalid fail-on threshold: {} (expected a severity or `never`)",
+                        s
+                    ),
+                )
+            }),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FailThreshold {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for FailThreshold {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Badge settings
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BadgeConfig {
+    /// Label on the left of the badge (default: `cargo audit`)
+    pub label: Option<String>,
+
+    /// Show warnings which aren't denied on the badge (default: true).
+    /// Otherwise only vulnerabilities and denied warnings affect the badge.
+    pub warnings: Option<bool>,
+
+    /// Color when no vulnerabilities or warnings were found (default: `green`)
+    #[serde(rename = "clean-color")]
+    pub clean_color: Option<String>,
+
+    /// Color when only warnings which aren't denied were found (default:
+    /// `yellow`)
+    #[serde(rename = "warning-color")]
+    pub warning_color: Option<String>,
+
+    /// Color when vulnerabilities or denied warnings were found, i.e. the
+    /// audit fails (default: `red`)
+    #[serde(rename = "failure-color")]
+    pub failure_color: Option<String>,
+}
+
+/// Exit statuses for the outcomes of an audit, for those which aren't the
+/// default (see [`ExitCodes::default_code`])
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExitCodes {
+    /// No vulnerabilities or denied warnings were found
+    pub clean: Option<i32>,
+
+    /// Vulnerabilities were found
+    #[serde(rename = "vulnerabilities-found")]
+    pub vulnerabilities_found: Option<i32>,
+
+    /// Denied warnings (but no vulnerabilities) were found
+    #[serde(rename = "denied-warnings")]
+    pub denied_warnings: Option<i32>,
+
+    /// Delivering the report to a webhook failed (with `notify.required`)
+    #[serde(rename = "notify-failed")]
+    pub notify_failed: Option<i32>,
+
+    /// The advisory database is stale
+    #[serde(rename = "stale-db")]
+    pub stale_db: Option<i32>,
+
+    /// The advisory database couldn't be fetched or loaded
+    #[serde(rename = "database-error")]
+    pub database_error: Option<i32>,
+
+    /// Any other error
+    #[serde(rename = "operational-error")]
+    pub operational_error: Option<i32>,
+
+    /// The lockfile failed an integrity check (with `cargo audit verify`)
+    #[serde(rename = "integrity-failed")]
+    pub integrity_failed: Option<i32>,
+
+    /// Statuses to use instead of `vulnerabilities-found`, by the severity
+    /// of the most severe vulnerability which failed the audit
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub severity: Map<advisory::Severity, i32>,
+
+    /// Statuses to use instead of `denied-warnings`, by the kind of denied
+    /// warning found (the first configured one, if several kinds were found)
+    #[serde(
+        default,
+        skip_serializing_if = "Map::is_empty",
+        deserialize_with = "deserialize_kind_map",
+        serialize_with = "serialize_kind_map"
+    )]
+    pub warnings: Map<warning::Kind, i32>,
+}
+
+impl ExitCodes {
+    /// Exit status used for the given outcome unless configured otherwise
+    pub fn default_code(outcome: Outcome) -> i32 {
+        match outcome {
+            Outcome::Clean => 0,
+            Outcome::VulnerabilitiesFound
+            | Outcome::DeniedWarnings
+            | Outcome::StaleDb
+            | Outcome::DatabaseError => 1,
+            Outcome::OperationalError => 2,
+            Outcome::IntegrityFailed => 6,
+            Outcome::NotifyFailed => notify::NOTIFY_FAILED_EXIT_CODE,
+        }
+    }
+
+    /// Exit status configured for the given outcome
+    pub fn configured(&self, outcome: Outcome) -> Option<i32> {
+        match outcome {
+            Outcome::Clean => self.clean,
+            Outcome::VulnerabilitiesFound => self.vulnerabilities_found,
+            Outcome::DeniedWarnings => self.denied_warnings,
+            Outcome::NotifyFailed => self.notify_failed,
+            Outcome::StaleDb => self.stale_db,
+            Outcome::DatabaseError => self.database_error,
+            Outcome::OperationalError => self.operational_error,
+            Outcome::IntegrityFailed => self.integrity_failed,
+        }
+    }
+
+    /// Exit status to use for the given outcome
+    pub fn code(&self, outcome: Outcome) -> i32 {
+        self.configured(outcome)
+            .unwrap_or_else(|| Self::default_code(outcome))
+    }
+
+    /// Exit status to use for the given outcome of an audit, given the
+    /// severity of the most severe vulnerability which failed it (if known)
+    /// and the kinds of denied warnings it found
+    pub fn failure_code(
+        &self,
+        outcome: Outcome,
+        severity: Option<advisory::Severity>,
+        denied: &[warning::Kind],
+    ) -> i32 {
+        let code = match outcome {
+            Outcome::VulnerabilitiesFound => {
+                severity.and_then(|severity| self.severity.get(&severity).copied())
+            }
+            Outcome::DeniedWarnings => denied
+                .iter()
+                .find_map(|kind| self.warnings.get(kind).copied()),
+            _ => None,
+        };
+
+        code.unwrap_or_else(|| self.code(outcome))
+    }
+
+    /// Ensure configured exit statuses are valid (0-255), and can be told
+    /// apart: a configured status can't be used for any other outcome.
+    ///
+    /// Several outcomes share a status by default (e.g. vulnerabilities and
+    /// denied warnings both exit with 1), which is allowed for compatibility.
+    pub fn validate(&self) -> Result<(), Error> {
+        for outcome in Outcome::all() {
+            let code = match self.configured(outcome) {
+                Some(code) => code,
+                None => continue,
+            };
+
+            if !(0..=255).contains(&code) {
+                return Err(Error::new(
+                    ErrorKind::BadParam,
+                    &format!(
+                        "invalid exit code for {}: {} (must be 0-255)",
+                        outcome, code
+                    ),
+                ));
+            }
+
+            if let Some(other) = Outcome::all()
+                .into_iter()
+                .find(|&other| other != outcome && self.code(other) == code)
+            {
+                return Err(Error::new(
+                    ErrorKind::BadParam,
+                    &format!(
+                        "exit code {} for {} is also used for {}",
+                        code, outcome, other
+                    ),
+                ));
+            }
+        }
+
+        // Statuses for severities and kinds of warnings refine an outcome, so
+        // they can only be shared with that outcome (or each other)
+        let refinements = self
+            .severity
+            .iter()
+            .map(|(severity, &code)| {
+                (
+                    format!("{} vulnerabilities", severity),
+                    code,
+                    Outcome::VulnerabilitiesFound,
+                )
+            })
+            .chain(self.warnings.iter().map(|(kind, &code)| {
+                (
+                    format!("denied {} warnings", kind),
+                    code,
+                    Outcome::DeniedWarnings,
+                )
+            }));
+
+        for (what, code, refined) in refinements {
+            if !(0..=255).contains(&code) {
+                return Err(Error::new(
+                    ErrorKind::BadParam,
+                    &format!("invalid exit code for {}: {} (must be 0-255)", what, code),
+                ));
+            }
+
+            if let Some(other) = Outcome::all()
+                .into_iter()
+                .find(|&other| other != refined && self.code(other) == code)
+            {
+                return Err(Error::new(
+                    ErrorKind::BadParam,
+                    &format!("exit code {} for {} is also used for {}", code, what, other),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Warning kinds
+///
+/// Each kind of warning can also be spelled with a `warnings:` prefix, e.g.
+/// `warnings:unmaintained`.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Serialize, Ord)]
+pub enum DenyOption {
+    /// Deny all warnings
+    #[serde(rename = "warnings")]
+    Warnings,
+
+    /// Deny informational notice warnings
+    #[serde(rename = "notice", alias = "warnings:notice")]
+    Notice,
+
+    /// Deny unmaintained dependency warnings
+    #[serde(rename = "unmaintained", alias = "warnings:unmaintained")]
+    Unmaintained,
+
+    /// Deny unsound dependency warnings
+    #[serde(rename = "unsound", alias = "warnings:unsound")]
+    Unsound,
+
+    /// Deny yanked dependency warnings
+    #[serde(rename = "yanked", alias = "warnings:yanked")]
+    Yanked,
+
+    /// Deny warnings about vulnerable dependencies whose source was overridden
+    #[serde(rename = "overridden", alias = "warnings:overridden")]
+    Overridden,
+
+    /// Deny warnings about crates with several versions in the lockfile
+    /// (when checked for with `warn = ["duplicate-versions"]`)
+    #[serde(rename = "duplicate-versions", alias = "warnings:duplicate-versions")]
+    DuplicateVersions,
+
+    /// Deny warnings about vulnerable dependencies which aren't compiled with
+    /// the enabled features (when checked for with `--feature-aware`)
+    #[serde(rename = "optional", alias = "warnings:optional")]
+    Optional,
+
+    /// Deny warnings about vulnerable dependencies whose advisories only
+    /// affect other platforms than the target (which `warnings` doesn't deny)
+    #[serde(rename = "other-platform", alias = "warnings:other-platform")]
+    OtherPlatform,
+
+    /// Deny vulnerabilities in the CISA Known Exploited Vulnerabilities
+    /// catalog, even if they're below the severity threshold
+    #[serde(rename = "known-exploited")]
+    KnownExploited,
+}
+
+impl DenyOption {
+    /// Get all of the possible warnings to be denied (which excludes
+    /// `known-exploited`, as it applies to vulnerabilities, and
+    /// `other-platform`, as those warnings are informational)
+    pub fn all() -> Vec<Self> {
+        vec![
+            DenyOption::Warnings,
+            DenyOption::Notice,
+            DenyOption::Unmaintained,
+            DenyOption::Unsound,
+            DenyOption::Yanked,
+            DenyOption::Overridden,
+            DenyOption::DuplicateVersions,
+            DenyOption::Optional,
+        ]
+    }
+    /// Get the warning::Kind that corresponds to self, if applicable
+    pub fn get_warning_kind(self) -> Option<warning::Kind> {
+        match self {
+            DenyOption::Warnings => None,
+            DenyOption::Notice => Some(warning::Kind::Notice),
+            DenyOption::Unmaintained => Some(warning::Kind::Unmaintained),
+            DenyOption::Unsound => Some(warning::Kind::Unsound),
+            DenyOption::Yanked => Some(warning::Kind::Yanked),
+            DenyOption::Overridden => Some(warning::Kind::Overridden),
+            DenyOption::DuplicateVersions => Some(warning::Kind::DuplicateVersions),
+            DenyOption::Optional => Some(warning::Kind::Optional),
+            DenyOption::OtherPlatform => Some(warning::Kind::OtherPlatform),
+            DenyOption::KnownExploited => None,
+        }
+    }
+
+    /// Get the category of informational advisories which self denies
+    /// warnings about, if applicable
+    pub fn informational(self) -> Option<advisory::Informational> {
+        match self {
+            DenyOption::Notice => Some(advisory::Informational::Notice),
+            DenyOption::Unmaintained => Some(advisory::Informational::Unmaintained),
+            DenyOption::Unsound => Some(advisory::Informational::Unsound),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for DenyOption {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "warnings" => return Ok(DenyOption::Warnings),
+            "known-exploited" => return Ok(DenyOption::KnownExploited),
+            _ => (),
+        }
+
+        match s.strip_prefix("warnings:").unwrap_or(s) {
+            "notice" => Ok(DenyOption::Notice),
+            "unmaintained" => Ok(DenyOption::Unmaintained),
+            "unsound" => Ok(DenyOption::Unsound),
+            "yanked" => Ok(DenyOption::Yanked),
+            "overridden" => Ok(DenyOption::Overridden),
+            "duplicate-versions" => Ok(DenyOption::DuplicateVersions),
+            "optional" => Ok(DenyOption::Optional),
+            "other-platform" => Ok(DenyOption::OtherPlatform),
+            _ => Err(Error::new(
+                ErrorKind::Parse,
+                &format!("invalid deny option: {}", s),
+            )),
+        }
+    }
+}
+
+/// Optional checks which produce warnings, including the categories of
+/// informational advisories to warn about. Like [`DenyOption`], each can be
+/// spelled with a `warnings:` prefix.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Serialize, Ord)]
+pub enum WarnOption {
+    /// Warn about informational notices
+    #[serde(rename = "notice", alias = "warnings:notice")]
+    Notice,
+
+    /// Warn about unmaintained dependencies
+    #[serde(rename = "unmaintained", alias = "warnings:unmaintained")]
+    Unmaintained,
+
+    /// Warn about unsound dependencies
+    #[serde(rename = "unsound", alias = "warnings:unsound")]
+    Unsound,
+
+    /// Warn about older versions of crates which are also in the lockfile
+    /// with a newer version
+    #[serde(rename = "duplicate-versions", alias = "warnings:duplicate-versions")]
+    DuplicateVersions,
+}
+
+impl WarnOption {
+    /// Get the category of informational advisories which self warns about,
+    /// if applicable
+    pub fn informational(self) -> Option<advisory::Informational> {
+        match self {
+            WarnOption::Notice => Some(advisory::Informational::Notice),
+            WarnOption::Unmaintained => Some(advisory::Informational::Unmaintained),
+            WarnOption::Unsound => Some(advisory::Informational::Unsound),
+            WarnOption::DuplicateVersions => None,
+        }
+    }
+}
+
+impl FromStr for WarnOption {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.strip_prefix("warnings:").unwrap_or(s) {
+            "notice" => Ok(WarnOption::Notice),
+            "unmaintained" => Ok(WarnOption::Unmaintained),
+            "unsound" => Ok(WarnOption::Unsound),
+            "duplicate-versions" => Ok(WarnOption::DuplicateVersions),
+            _ => Err(Error::new(
+                ErrorKind::Parse,
+                &format!("invalid warn option: {}", s),
+            )),
+        }
+    }
+}
+
+/// Output format
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum OutputFormat {
+    /// Display JSON
+    #[serde(rename = "json")]
+    Json,
+
+    /// Display NDJSON (JSON Lines): one line per finding, then a summary
+    #[serde(rename = "ndjson")]
+    Ndjson,
+
+    /// Display a shields.io endpoint badge summarizing the report
+    #[serde(rename = "badge")]
+    Badge,
+
+    /// Display a SARIF 2.1.0 log, for CI code scanning
+    #[serde(rename = "sarif")]
+    Sarif,
+
+    /// Display Markdown tables, for pull request comments
+    #[serde(rename = "markdown")]
+    Markdown,
+
+    /// Display a JUnit XML test report, for CI test dashboards
+    #[serde(rename = "junit")]
+    Junit,
+
+    /// Display a standalone HTML page, for archiving or emailing
+    #[serde(rename = "html")]
+    Html,
+
+    /// Display the findings in the OSV format, as osv-scanner does
+    #[serde(rename = "osv")]
+    Osv,
+
+    /// Display an SPDX 2.3 document, for SPDX compliance pipelines
+    #[serde(rename = "spdx")]
+    Spdx,
+
+    /// Display a JSON array of the actions remediating the vulnerabilities,
+    /// for bots to apply
+    #[serde(rename = "fix-plan")]
+    FixPlan,
+
+    /// Display Prometheus metrics summarizing the report, for dashboards
+    /// and alerting
+    #[serde(rename = "metrics")]
+    Metrics,
+
+    /// Display human-readable output to the terminal
+    #[serde(rename = "terminal")]
+    Terminal,
+}
+
+impl OutputFormat {
+    /// Is this format meant to be consumed by other programs? If so, STDOUT
+    /// is reserved for the report, and status messages go to STDERR.
+    pub fn is_machine_readable(self) -> bool {
+        self != OutputFormat::Terminal
+    }
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Terminal
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "badge" => Ok(OutputFormat::Badge),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "markdown" => Ok(OutputFormat::Markdown),
+            "junit" => Ok(OutputFormat::Junit),
+            "osv" => Ok(OutputFormat::Osv),
+            "spdx" => Ok(OutputFormat::Spdx),
+            "fix-plan" => Ok(OutputFormat::FixPlan),
+            "metrics" => Ok(OutputFormat::Metrics),
+            "html" => Ok(OutputFormat::Html),
+            "terminal" => Ok(OutputFormat::Terminal),
+            other => Err(Error::new(
+                ErrorKind::Parse,
+                &format!("invalid output format: {}", other),
+            )),
+        }
+    }
+}
+
+/// Order of vulnerabilities and warnings in terminal reports
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SortOrder {
+    /// Sort by advisory ID, then by package
+    #[serde(rename = "id")]
+    Id,
+
+    /// Sort by package name, version and source, then by advisory ID
+    #[serde(rename = "package")]
+    Package,
+
+    /// Sort vulnerabilities by CVSS severity and base score (most severe
+    /// first), then by advisory ID. Warnings have no severity, and are sorted
+    /// by advisory ID.
+    #[serde(rename = "severity")]
+    Severity,
+}
+
+impl FromStr for SortOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "id" => Ok(SortOrder::Id),
+            "package" => Ok(SortOrder::Package),
+            "severity" => Ok(SortOrder::Severity),
+            other => Err(Error::new(
+                ErrorKind::Parse,
+                &format!("invalid sort order: {}", other),
+            )),
+        }
+    }
+}
+
+/// Whether terminal reports render advisory IDs and URLs as hyperlinks
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hyperlinks {
+    /// If STDOUT is a terminal known to support them
+    Auto,
+
+    /// Always, e.g. when piping into a pager which passes them through
+    Always,
+
+    /// Never
+    Never,
+}
+
+impl Hyperlinks {
+    /// Should hyperlinks be rendered?
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Hyperlinks::Auto => text::supports_hyperlinks(),
+            Hyperlinks::Always => true,
+            Hyperlinks::Never => false,
+        }
+    }
+}
+
+impl Default for Hyperlinks {
+    fn default() -> Self {
+        Hyperlinks::Auto
+    }
+}
+
+impl FromStr for Hyperlinks {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(Hyperlinks::Auto),
+            "always" => Ok(Hyperlinks::Always),
+            "never" => Ok(Hyperlinks::Never),
+            other => Err(Error::new(
+                ErrorKind::Parse,
+                &format!(
+                    "invalid hyperlinks setting (expected auto, always or never): {}",
+                    other
+                ),
+            )),
+        }
+    }
+}
+
+/// Whether terminal reports and messages are colored
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+    /// If STDOUT is a terminal (rather than a pipe or a file), `TERM` isn't
+    /// `dumb` and `NO_COLOR` isn't set
+    Auto,
+
+    /// Always, e.g. when piping into a pager which passes them through
+    Always,
+
+    /// Never
+    Never,
+}
+
+impl Color {
+    /// Color choice for the terminal streams
+    pub fn choice(self) -> ColorChoice {
+        match self {
+            Color::Auto if text::supports_color() => ColorChoice::Always,
+            Color::Auto => ColorChoice::Never,
+            Color::Always => ColorChoice::Always,
+            Color::Never => ColorChoice::Never,
+        }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color::Auto
+    }
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(Color::Auto),
+            "always" => Ok(Color::Always),
+            "never" => Ok(Color::Never),
+            other => Err(Error::new(
+                ErrorKind::Parse,
+                &format!(
+                    "invalid color setting (expected auto, always or never): {}",
+                    other
+                ),
+            )),
+        }
+    }
+}
+
+/// Target configuration
+///
+/// Vulnerabilities whose advisories only affect other CPU architectures or
+/// operating systems than the target are reported as `other-platform`
+/// warnings rather than failing the audit.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    /// Target triple (e.g. `x86_64-pc-windows-msvc`) to find vulnerabilities
+    /// for, setting the architecture and OS unless they're given too
+    pub triple: Option<String>,
+
+    /// Target architecture to find vulnerabilities for
+    pub arch: Option<Arch>,
+
+    /// Target OS to find vulnerabilities for
+    pub os: Option<OS>,
+}
+
+impl TargetConfig {
+    /// Platform of the target triple, if one is given (and known)
+    pub fn platform(&self) -> Option<&'static Platform> {
+        self.triple.as_deref().and_then(Platform::find)
+    }
+
+    /// Target architecture: the one given, or the target triple's
+    pub fn arch(&self) -> Option<Arch> {
+        self.arch
+            .or_else(|| self.platform().map(|platform| platform.target_arch))
+    }
+
+    /// Target OS: the one given, or the target triple's
+    pub fn os(&self) -> Option<OS> {
+        self.os
+            .or_else(|| self.platform().map(|platform| platform.target_os))
+    }
+}
+
+/// Packages configuration
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackageConfig {
+    /// Package scope which should be considered for querying for vulnerabilities.
+    pub source: Option<scope::Registry>,
+
+    /// Don't query for vulnerabilities in packages from git repositories
+    /// (e.g. forks of crates.io packages used via `[patch]`)
+    #[serde(default)]
+    pub skip_git: bool,
+
+    /// How to report vulnerabilities in git or path dependencies, which may
+    /// be forks containing a fix (default: warn)
+    #[serde(default)]
+    pub overridden: OverriddenAction,
+}
+
+/// Registry lookup configuration
+///
+/// When enabled, the crates.io sparse index is queried for each vulnerable
+/// crate to find out whether a release satisfying the advisory's patched
+/// versions has actually been published. Releases are cached in the report
+/// cache directory.
+///
+/// If cargo's configuration replaces crates.io (e.g. with a mirror), the
+/// replacement's index is queried instead, unless a `url` is configured.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistryConfig {
+    /// Look up patched releases of vulnerable crates (default: true)
+    #[serde(default = "default_true")]
+    pub lookup: bool,
+
+    /// URL of the sparse index (default: <https://index.crates.io/>)
+    pub url: Option<String>,
+
+    /// How long looked up releases are cached for, in hours (default: 1)
+    pub ttl_hours: Option<u64>,
+
+    /// How many crates' releases are fetched at once (default: 8)
+    pub concurrency: Option<usize>,
+
+    /// Log which index each registry lookup uses, e.g. to diagnose cargo's
+    /// source replacement configuration (default: false)
+    #[serde(default)]
+    pub debug: bool,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            lookup: true,
+            url: None,
+            ttl_hours: None,
+            concurrency: None,
+            debug: false,
+        }
+    }
+}
+
+/// Configuration for auditing for yanked crates
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct YankedConfig {
+    /// Is auditing for yanked crates enabled?
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Should the crates.io index be updated before checking for yanked crates?
+    #[serde(default = "default_true")]
+    pub update_index: bool,
+}
 
 impl Default for YankedConfig {
     fn default() -> Self {
@@ -275,6 +2467,190 @@ impl Default for YankedConfig {
     }
 }
 
+/// Network configuration
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetConfig {
+    /// Don't access the network: the advisory database isn't fetched, the
+    /// crates.io index isn't updated or queried for patched releases, EPSS
+    /// scores aren't fetched, and only a previously downloaded KEV catalog is
+    /// used (default: false)
+    #[serde(default)]
+    pub offline: bool,
+
+    /// Fail instead of skipping anything which would access the network,
+    /// e.g. fetching the advisory database or EPSS scores (implies
+    /// `offline`, default: false)
+    #[serde(default)]
+    pub strict_offline: bool,
+
+    /// How many times network operations are attempted, retrying with
+    /// exponential backoff after timeouts, connection failures and server
+    /// errors (default: 3)
+    pub attempts: Option<u32>,
+
+    /// How many seconds fetching the advisory database may go without making
+    /// progress before it's aborted (and retried), rather than hanging on a
+    /// connection which was silently dropped (default: no timeout)
+    pub timeout_secs: Option<u64>,
+
+    /// URL of the proxy to fetch the advisory database through, e.g.
+    /// `http://proxy.example.com:8080` (default: git's `http.proxy` setting,
+    /// or the `https_proxy` environment variable)
+    pub proxy: Option<String>,
+}
+
+impl NetConfig {
+    /// Options for fetching the advisory database with this configuration
+    pub fn fetch_options<'a>(&self) -> FetchOptions<'a> {
+        let mut options = FetchOptions::new();
+
+        if let Some(secs) = self.timeout_secs {
+            options = options.timeout(Duration::from_secs(secs));
+        }
+
+        if let Some(proxy) = &self.proxy {
+            options = options.proxy(proxy.as_str());
+        }
+
+        options
+    }
+}
+
+/// Webhook notification configuration
+///
+/// JSON reports are POSTed to each of the given URLs, retrying with
+/// exponential backoff after connection failures and server errors.
+///
+/// The URLs and headers are only read from the user's `~/.cargo/audit.toml`
+/// (or the command line and environment), never from a workspace's.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// URLs to POST reports to
+    #[serde(default)]
+    pub urls: Vec<String>,
+
+    /// URL to POST reports to, in addition to `urls`
+    pub webhook: Option<String>,
+
+    /// Payload to send (default: json)
+    #[serde(default)]
+    pub format: NotifyFormat,
+
+    /// Extra HTTP headers to send. Values may refer to environment variables
+    /// as `${NAME}` (e.g. to keep auth tokens out of the config file).
+    #[serde(default)]
+    pub headers: Map<String, String>,
+
+    /// When to send reports (default: findings)
+    #[serde(default)]
+    pub on: NotifyOn,
+
+    /// Exit with a distinct status if a report can't be delivered (default:
+    /// false, i.e. only print a warning)
+    #[serde(default)]
+    pub required: bool,
+
+    /// How many times to retry delivering a report (default: 3)
+    pub retries: Option<u32>,
+}
+
+/// When to send reports to webhooks
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum NotifyOn {
+    /// Only when vulnerabilities or warnings were found
+    #[serde(rename = "findings")]
+    Findings,
+
+    /// After every audit
+    #[serde(rename = "always")]
+    Always,
+
+    /// Only when findings are new since the run recorded in the state file
+    /// (or the baseline report), sending only the new findings
+    #[serde(rename = "new")]
+    New,
+}
+
+impl Default for NotifyOn {
+    fn default() -> Self {
+        NotifyOn::Findings
+    }
+}
+
+impl FromStr for NotifyOn {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "findings" => Ok(NotifyOn::Findings),
+            "always" => Ok(NotifyOn::Always),
+            "new" => Ok(NotifyOn::New),
+            other => Err(Error::new(
+                ErrorKind::Parse,
+                &format!("invalid --notify-on option: {}", other),
+            )),
+        }
+    }
+}
+
+/// Payload sent to webhooks
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum NotifyFormat {
+    /// The JSON report (as output by `cargo audit --json`)
+    #[serde(rename = "json")]
+    Json,
+
+    /// A message for Slack-compatible incoming webhooks (e.g. Slack,
+    /// Mattermost or Rocket.Chat), summarizing the findings
+    #[serde(rename = "slack")]
+    Slack,
+}
+
+impl Default for NotifyFormat {
+    fn default() -> Self {
+        NotifyFormat::Json
+    }
+}
+
+impl FromStr for NotifyFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "json" => Ok(NotifyFormat::Json),
+            "slack" => Ok(NotifyFormat::Slack),
+            other => Err(Error::new(
+                ErrorKind::Parse,
+                &format!("invalid --notify-format option: {}", other),
+            )),
+        }
+    }
+}
+
+/// Deserialize a table keyed by warning kind (TOML keys are strings, which
+/// the derived implementation for warning kinds can't be deserialized from)
+fn deserialize_kind_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Map<warning::Kind, i32>, D::Error> {
+    Map::<String, i32>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(kind, value)| Ok((kind.parse().map_err(de::Error::custom)?, value)))
+        .collect()
+}
+
+/// Serialize a table keyed by warning kind, with string keys
+fn serialize_kind_map<S: serde::Serializer>(
+    map: &Map<warning::Kind, i32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter()
+        .map(|(kind, &value)| (kind.as_str(), value))
+        .collect::<Map<&str, i32>>()
+        .serialize(serializer)
+}
+
 /// Helper function for returning a default of `true`
 fn default_true() -> bool {
     true
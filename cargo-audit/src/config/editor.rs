@@ -0,0 +1,302 @@
+//! Editing `audit.toml` files in-place
+
+use super::{Acknowledgment, IgnoredAdvisory};
+use rustsec::{
+    error::{Error, ErrorKind},
+    fs,
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Array, Document, InlineTable, Item, Table, Value};
+
+/// Editor for `audit.toml` files which preserves their existing formatting
+/// and comments
+#[derive(Debug)]
+pub struct ConfigEditor {
+    /// Path to the config file
+    path: PathBuf,
+
+    /// Parsed config file
+    document: Document,
+}
+
+impl ConfigEditor {
+    /// Open the config file at the given path, which will be created when
+    /// saved if it doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+
+        let document = if path.exists() {
+            fs::read_to_string(&path)?.parse().map_err(|e| {
+                Error::new(
+                    ErrorKind::Parse,
+                    &format!("couldn't parse {}: {}", path.display(), e),
+                )
+            })?
+        } else {
+            Document::new()
+        };
+
+        Ok(Self { path, document })
+    }
+
+    /// Path to the config file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add an entry for the given advisory to `advisories.ignore`.
+    ///
+    /// If the advisory is already ignored, its existing entry is replaced
+    /// (i.e. its reason and expiry are updated) and `true` is returned.
+    pub fn ignore(&mut self, advisory: &IgnoredAdvisory) -> Result<bool, Error> {
+        let path = &self.path;
+        let ignore = advisories_array(&mut self.document, path, "ignore")?;
+
+        // TOML 0.5 arrays must be homogeneous, so convert any bare advisory
+        // IDs into tables before adding structured entries alongside them
+        if ignore.iter().any(|entry| entry.is_str()) {
+            let mut entries = vec![];
+
+            while !ignore.is_empty() {
+                entries.push(ignore.remove(0));
+            }
+
+            for entry in entries {
+                let entry = match entry.as_str() {
+                    Some(id) => {
+                        let mut table = InlineTable::default();
+                        table.get_or_insert("id", id);
+                        table.fmt();
+
+                        let decor = entry.decor();
+                        toml_edit::decorated(
+                            Value::InlineTable(table),
+                            decor.prefix(),
+                            decor.suffix(),
+                        )
+                    }
+                    None => entry,
+                };
+
+                ignore
+                    .push_formatted(entry)
+                    .map_err(|_| invalid(path, "advisories.ignore", "an array of advisory IDs"))?;
+            }
+        }
+
+        upsert(ignore, advisory.id.as_str(), inline_table(advisory))
+            .map_err(|_| invalid(path, "advisories.ignore", "an array of advisory IDs"))
+    }
+
+    /// Add an entry for the given acknowledgment to `advisories.acknowledged`.
+    ///
+    /// If the advisory is already acknowledged, its existing entry is replaced
+    /// (i.e. its ticket and expiry are updated) and `true` is returned.
+    pub fn acknowledge(&mut self, acknowledgment: &Acknowledgment) -> Result<bool, Error> {
+        let path = &self.path;
+        let acknowledged = advisories_array(&mut self.document, path, "acknowledged")?;
+
+        upsert(
+            acknowledged,
+            acknowledgment.id.as_str(),
+            acknowledgment_table(acknowledgment),
+        )
+        .map_err(|_| {
+            invalid(
+                path,
+                "advisories.acknowledged",
+                "an array of acknowledgment tables",
+            )
+        })
+    }
+
+    /// Add a comment line before the entry for the given advisory in
+    /// `advisories.<key>` (e.g. `ignore`), after any comments it already has
+    pub fn comment(&mut self, key: &str, id: &str, comment: &str) -> Result<(), Error> {
+        let path = &self.path;
+        let array = advisories_array(&mut self.document, path, key)?;
+
+        let index = match position(array, id) {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+
+        let entry = array.get(index).cloned().expect("array entry");
+        let (prefix, suffix) = {
+            let decor = entry.decor();
+            (decor.prefix().to_owned(), decor.suffix().to_owned())
+        };
+
+        // Keep the lines before the entry, and its indentation
+        let (lines, indent) = match prefix.rfind('\n') {
+            Some(newline) => (&prefix[..=newline], &prefix[newline + 1..]),
+            None => ("\n", "    "),
+        };
+
+        let prefix = format!("{}{}# {}\n{}", lines, indent, comment, indent);
+        array
+            .replace_formatted(index, toml_edit::decorated(entry, &prefix, &suffix))
+            .map_err(|_| invalid(path, &format!("advisories.{}", key), "an array"))?;
+
+        Ok(())
+    }
+
+    /// Write the config file to disk, creating its parent directory if needed
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Tables added to an empty document are preceded by a blank line
+        fs::write(&self.path, self.document.to_string().trim_start())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConfigEditor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+impl IgnoredAdvisory {
+    /// Render this advisory as the `advisories.ignore` entry written by
+    /// [`ConfigEditor::ignore`]
+    pub fn to_toml(&self) -> String {
+        inline_table(self).to_string().trim().to_owned()
+    }
+}
+
+impl Acknowledgment {
+    /// Render this acknowledgment as the `advisories.acknowledged` entry
+    /// written by [`ConfigEditor::acknowledge`]
+    pub fn to_toml(&self) -> String {
+        acknowledgment_table(self).to_string().trim().to_owned()
+    }
+}
+
+/// Get the `advisories.<key>` array of the document, adding it if needed
+fn advisories_array<'a>(
+    document: &'a mut Document,
+    path: &Path,
+    key: &str,
+) -> Result<&'a mut Array, Error> {
+    let advisories = document
+        .as_table_mut()
+        .entry("advisories")
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| invalid(path, "advisories", "a table"))?;
+
+    advisories
+        .entry(key)
+        .or_insert(toml_edit::value(Array::default()))
+        .as_array_mut()
+        .ok_or_else(|| invalid(path, &format!("advisories.{}", key), "an array"))
+}
+
+/// Replace the entry of the array with the given `id`, or add it (laid out
+/// like the existing entries), returning whether an entry was replaced.
+///
+/// Fails if the entry's type doesn't match the array's existing entries.
+fn upsert(array: &mut Array, id: &str, entry: InlineTable) -> Result<bool, ()> {
+    let entry = Value::InlineTable(entry);
+
+    match position(array, id) {
+        Some(index) => {
+            array.replace(index, entry).expect("array of inline tables");
+            Ok(true)
+        }
+        None => {
+            // Lay out the new entry like the existing ones (e.g. one per line)
+            // (keeping only the indentation, not any comments, before it)
+            let entry = match array.iter().last() {
+                Some(last) => {
+                    let prefix = last.decor().prefix();
+                    let indent = match prefix.rfind('\n') {
+                        Some(newline) => &prefix[newline..],
+                        None => " ",
+                    };
+
+                    toml_edit::decorated(entry, indent, "")
+                }
+                None => entry,
+            };
+
+            array.push_formatted(entry).map_err(|_| ())?;
+            Ok(false)
+        }
+    }
+}
+
+/// Index of the entry of the array with the given `id` (if any)
+fn position(array: &Array, id: &str) -> Option<usize> {
+    (0..array.len()).find(|&index| {
+        array
+            .get(index)
+            .and_then(Value::as_inline_table)
+            .and_then(|entry| entry.get("id"))
+            .and_then(Value::as_str)
+            == Some(id)
+    })
+}
+
+/// Build the inline table representation of an ignored advisory
+fn inline_table(advisory: &IgnoredAdvisory) -> InlineTable {
+    let mut table = InlineTable::default();
+    table.get_or_insert("id", advisory.id.as_str());
+
+    if let Some(reason) = &advisory.reason {
+        table.get_or_insert("reason", reason.as_str());
+    }
+
+    if let Some(expires) = &advisory.expires {
+        table.get_or_insert("expires", expires.as_str());
+    }
+
+    if let Some(justification) = advisory.justification {
+        table.get_or_insert("justification", justification.as_str());
+    }
+
+    if let Some(package) = &advisory.package {
+        table.get_or_insert("package", package.as_str());
+    }
+
+    if let Some(version) = &advisory.version {
+        table.get_or_insert("version", version.to_string());
+    }
+
+    if !advisory.via.is_empty() {
+        let via: Value = advisory.via.iter().map(|name| name.as_str()).collect();
+        table.get_or_insert("via", via);
+    }
+
+    table.fmt();
+    table
+}
+
+/// Error for config files whose structure we don't know how to edit
+fn invalid(path: &Path, key: &str, expected: &str) -> Error {
+    Error::new(
+        ErrorKind::Parse,
+        &format!("{}: expected `{}` to be {}", path.display(), key, expected),
+    )
+}
+
+/// Build the inline table representation of an acknowledgment
+fn acknowledgment_table(acknowledgment: &Acknowledgment) -> InlineTable {
+    let mut table = InlineTable::default();
+    table.get_or_insert("id", acknowledgment.id.as_str());
+    table.get_or_insert("ticket", acknowledgment.ticket.as_str());
+
+    if let Some(date) = &acknowledgment.date {
+        table.get_or_insert("date", date.as_str());
+    }
+
+    table.get_or_insert("expires", acknowledgment.expires.as_str());
+    table.fmt();
+    table
+}
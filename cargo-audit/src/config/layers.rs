@@ -0,0 +1,161 @@
+//! Layering `audit.toml` files, so the user's `~/.cargo/audit.toml` can hold
+//! defaults (e.g. organization-wide ones) which each workspace's
+//! `.cargo/audit.toml` overrides
+
+use super::{
+    validate::{key_line, unreadable},
+    AuditConfig, ConfigProblem,
+};
+use crate::commands;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Keys which are only read from the user's `~/.cargo/audit.toml` (or the
+/// command line and `CARGO_AUDIT_*` variables): webhooks are sent the report
+/// along with headers which may expand environment variables (e.g. tokens),
+/// so a workspace being audited mustn't be able to choose where they go
+const USER_ONLY_KEYS: &[(&str, &str)] = &[
+    ("notify", "urls"),
+    ("notify", "webhook"),
+    ("notify", "headers"),
+];
+
+/// Configuration file which has been read
+#[derive(Clone, Debug)]
+pub(super) struct Layer {
+    /// Path to the file
+    pub path: PathBuf,
+
+    /// The file's source
+    pub source: String,
+}
+
+impl AuditConfig {
+    /// Load the configuration files at the given paths, in increasing order
+    /// of precedence, failing with the first problem with them (see
+    /// [`AuditConfig::load_file`])
+    pub fn load_layers(paths: &[PathBuf]) -> Result<Self, ConfigProblem> {
+        let (config, layers) = Self::merge_layers(paths)?;
+
+        match config.problems().into_iter().next() {
+            Some(problem) => Err(locate(problem, &layers)),
+            None => Ok(config),
+        }
+    }
+
+    /// Read and merge the configuration files at the given paths, in
+    /// increasing order of precedence.
+    ///
+    /// Each file must be a valid configuration on its own. Their tables are
+    /// merged key by key, arrays (e.g. `advisories.ignore`) are concatenated,
+    /// and other values of files with a higher precedence replace those of
+    /// files with a lower one. Keys which are only read from the user's
+    /// configuration are ignored (with a warning) in the other files.
+    pub(super) fn merge_layers(paths: &[PathBuf]) -> Result<(Self, Vec<Layer>), ConfigProblem> {
+        let mut merged = toml::Value::Table(Default::default());
+        let mut layers = vec![];
+
+        for path in paths {
+            let source = fs::read_to_string(path).map_err(|e| unreadable(path, &e))?;
+
+            // Parse errors are located in the file they're in, rather than
+            // in the merged configuration
+            Self::parse(&source).map_err(|problem| problem.locate(path, &source))?;
+
+            let mut value = source
+                .parse::<toml::Value>()
+                .map_err(|e| ConfigProblem::new(None, e.to_string()).locate(path, &source))?;
+
+            if !is_user_config(path) {
+                remove_user_only_keys(&mut value, path);
+            }
+
+            merge(&mut merged, value);
+            layers.push(Layer {
+                path: path.clone(),
+                source,
+            });
+        }
+
+        let config = merged.try_into().map_err(|e: toml::de::Error| {
+            let problem = ConfigProblem::new(None, e.to_string());
+            match layers.last() {
+                Some(layer) => problem.locate(&layer.path, &layer.source),
+                None => problem,
+            }
+        })?;
+
+        Ok((config, layers))
+    }
+}
+
+/// Is the given file the current user's `~/.cargo/audit.toml`?
+fn is_user_config(path: &Path) -> bool {
+    commands::home_config_path().as_deref() == Some(path)
+}
+
+/// Remove the keys which are only read from the user's configuration from
+/// another file's, warning about any it sets
+fn remove_user_only_keys(value: &mut toml::Value, path: &Path) {
+    for (table, key) in USER_ONLY_KEYS {
+        let removed = value
+            .get_mut(*table)
+            .and_then(toml::Value::as_table_mut)
+            .and_then(|table| table.remove(*key));
+
+        if removed.is_some() {
+            tracing::warn!(
+                "ignoring `{}.{}` in {}: it's only read from ~/.cargo/audit.toml",
+                table,
+                key,
+                path.display()
+            );
+        }
+    }
+}
+
+/// Merge a TOML value with a higher precedence into one with a lower one:
+/// tables are merged key by key, arrays are concatenated (skipping items
+/// which are already in them), and other values are replaced
+fn merge(base: &mut toml::Value, value: toml::Value) {
+    match (base, value) {
+        (toml::Value::Table(base), toml::Value::Table(table)) => {
+            for (key, value) in table {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (toml::Value::Array(base), toml::Value::Array(array)) => {
+            for item in array {
+                if !base.contains(&item) {
+                    base.push(item);
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+/// Locate a problem with the merged configuration in the file with the
+/// highest precedence which sets its key (or the last file, if none do)
+pub(super) fn locate(problem: ConfigProblem, layers: &[Layer]) -> ConfigProblem {
+    let layer = layers
+        .iter()
+        .rev()
+        .find(|layer| match &problem.key {
+            Some(key) => key_line(&layer.source, key).is_some(),
+            None => false,
+        })
+        .or_else(|| layers.last());
+
+    match layer {
+        Some(layer) => problem.locate(&layer.path, &layer.source),
+        None => problem,
+    }
+}
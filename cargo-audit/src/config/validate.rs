@@ -0,0 +1,477 @@
+//! Validating `audit.toml` files, with problems located by file, line and
+//! key so they can be fixed without guesswork
+
+use super::{layers, url_source_name, AuditConfig, DatabaseBackend};
+use crate::{acknowledgments, template::Template};
+use rustsec::{
+    advisory::{self, id::Kind},
+    error::{Error, ErrorKind},
+};
+use std::{
+    collections::BTreeSet as Set,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Problem with a configuration
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigProblem {
+    /// File the problem is in (if it's in a file)
+    pub file: Option<PathBuf>,
+
+    /// Line of the file the problem is on (1-based, if known)
+    pub line: Option<usize>,
+
+    /// Dotted path of the key the problem is with, e.g. `output.format`
+    pub key: Option<String>,
+
+    /// What's wrong
+    pub message: String,
+}
+
+impl ConfigProblem {
+    /// Create a problem with the given key which isn't located in a file
+    pub fn new(key: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            file: None,
+            line: None,
+            key: key.map(ToOwned::to_owned),
+            message: message.into(),
+        }
+    }
+
+    /// Locate the problem in the given file, finding the line of its key
+    /// (if it has one and it's in the file's source)
+    pub fn locate(mut self, file: &Path, source: &str) -> Self {
+        if self.line.is_none() {
+            self.line = self.key.as_ref().and_then(|key| key_line(source, key));
+        }
+
+        self.file = Some(file.to_owned());
+        self
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}", file.display())?;
+
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+
+            write!(f, ": ")?;
+        }
+
+        if let Some(key) = &self.key {
+            write!(f, "`{}`: ", key)?;
+        }
+
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ConfigProblem {}
+
+impl From<ConfigProblem> for Error {
+    fn from(problem: ConfigProblem) -> Error {
+        Error::new(ErrorKind::BadParam, &problem.to_string())
+    }
+}
+
+impl AuditConfig {
+    /// Load the configuration file at the given path, failing with the first
+    /// problem with it: syntax errors, unknown keys and values of the wrong
+    /// type, then problems with its values (see [`AuditConfig::problems`])
+    pub fn load_file(path: &Path) -> Result<Self, ConfigProblem> {
+        Self::load_layers(&[path.to_owned()])
+    }
+
+    /// Find every problem with the effective configuration: the files at the
+    /// given paths (layered in increasing order of precedence), overridden
+    /// by `CARGO_AUDIT_*` environment variables
+    pub fn check(paths: &[PathBuf]) -> Vec<ConfigProblem> {
+        let (mut config, layers) = match Self::merge_layers(paths) {
+            Ok(merged) => merged,
+            Err(problem) => return vec![problem],
+        };
+
+        let file_problems = config.problems();
+
+        let mut problems: Vec<_> = file_problems
+            .iter()
+            .cloned()
+            .map(|problem| layers::locate(problem, &layers))
+            .collect();
+
+        if let Err(e) = config.override_from_env() {
+            problems.push(ConfigProblem::new(None, e.msg()));
+            return problems;
+        }
+
+        // Problems introduced by the environment aren't in the files
+        problems.extend(
+            config
+                .problems()
+                .into_iter()
+                .filter(|problem| !file_problems.contains(problem)),
+        );
+
+        problems
+    }
+
+    /// Parse a configuration file's source, locating any errors in it
+    pub fn parse(source: &str) -> Result<Self, ConfigProblem> {
+        toml::from_str(source).map_err(|e| parse_problem(source, &e))
+    }
+
+    /// Problems with the configuration's values which are well-typed, but
+    /// wrong (e.g. malformed advisory IDs, or conflicting exit codes)
+    pub fn problems(&self) -> Vec<ConfigProblem> {
+        let mut problems = vec![];
+        let mut ignored = Set::new();
+
+        for advisory in &self.advisories.ignore {
+            let id = &advisory.id;
+
+            if let Some(message) = advisory_id_problem(id) {
+                problems.push(ConfigProblem::new(Some("advisories.ignore"), message));
+            }
+
+            let scope = (
+                id.as_str(),
+                &advisory.package,
+                &advisory.version,
+                &advisory.via,
+            );
+
+            if !ignored.insert(scope) {
+                problems.push(ConfigProblem::new(
+                    Some("advisories.ignore"),
+                    format!("{} is ignored more than once", id),
+                ));
+            }
+
+            if matches!(&advisory.reason, Some(reason) if reason.trim().is_empty()) {
+                problems.push(ConfigProblem::new(
+                    Some("advisories.ignore"),
+                    format!("the ignore of {} needs a reason", id),
+                ));
+            }
+
+            if let Some(expires) = &advisory.expires {
+                if !acknowledgments::is_calendar_date(expires) {
+                    problems.push(ConfigProblem::new(
+                        Some("advisories.ignore"),
+                        format!("{} isn't a date in the calendar", expires.as_str()),
+                    ));
+                }
+            }
+        }
+
+        let mut acknowledged = Set::new();
+
+        for acknowledgment in &self.advisories.acknowledged {
+            let id = &acknowledgment.id;
+
+            if let Some(message) = advisory_id_problem(id) {
+                problems.push(ConfigProblem::new(Some("advisories.acknowledged"), message));
+            }
+
+            if !acknowledged.insert(id.as_str()) {
+                problems.push(ConfigProblem::new(
+                    Some("advisories.acknowledged"),
+                    format!("{} is acknowledged more than once", id),
+                ));
+            }
+
+            if acknowledgment.ticket.trim().is_empty() {
+                problems.push(ConfigProblem::new(
+                    Some("advisories.acknowledged"),
+                    format!("the acknowledgment of {} needs a ticket", id),
+                ));
+            }
+
+            for date in acknowledgment
+                .date
+                .iter()
+                .chain(Some(&acknowledgment.expires))
+            {
+                if !acknowledgments::is_calendar_date(date) {
+                    problems.push(ConfigProblem::new(
+                        Some("advisories.acknowledged"),
+                        format!("{} isn't a date in the calendar", date.as_str()),
+                    ));
+                }
+            }
+        }
+
+        if let Some(triple) = &self.target.triple {
+            if self.target.platform().is_none() {
+                problems.push(ConfigProblem::new(
+                    Some("target.triple"),
+                    format!("unknown target triple: {}", triple),
+                ));
+            }
+        }
+
+        if let Some(threshold) = self.epss.threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                problems.push(ConfigProblem::new(
+                    Some("epss.threshold"),
+                    format!("invalid EPSS threshold {} (must be 0-1)", threshold),
+                ));
+            }
+        }
+
+        if let Err(e) = self.output.exit_codes.validate() {
+            problems.push(ConfigProblem::new(Some("output.exit-codes"), e.msg()));
+        }
+
+        if let Some(path) = &self.output.template {
+            if let Err(e) = Template::load(path) {
+                problems.push(ConfigProblem::new(Some("output.template"), e.msg()));
+            }
+        }
+
+        for (name, source) in &self.database.sources {
+            let fetched = self.database.fetch
+                && source.fetch.unwrap_or(true)
+                && source.backend != Some(DatabaseBackend::Local);
+
+            if fetched && source.url.is_none() {
+                problems.push(ConfigProblem::new(
+                    Some(&format!("database.sources.{}", name)),
+                    "a `url` is required to fetch the source (or set `fetch = false` \
+                     to use its local copy as-is)",
+                ));
+            }
+        }
+
+        for url in &self.database.urls {
+            if url_source_name(url).is_empty() {
+                problems.push(ConfigProblem::new(
+                    Some("database.urls"),
+                    format!("invalid advisory database URL {:?}", url),
+                ));
+            }
+        }
+
+        for url in &self.notify.urls {
+            if let Some(message) = url_problem(url) {
+                problems.push(ConfigProblem::new(Some("notify.urls"), message));
+            }
+        }
+
+        if let Some(message) = self.notify.webhook.as_deref().and_then(url_problem) {
+            problems.push(ConfigProblem::new(Some("notify.webhook"), message));
+        }
+
+        for name in self.notify.headers.keys() {
+            if name.parse::<reqwest::header::HeaderName>().is_err() {
+                problems.push(ConfigProblem::new(
+                    Some("notify.headers"),
+                    format!("invalid HTTP header name {:?}", name),
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// Check the configuration has no problems, failing with the first one
+    pub fn validate(&self) -> Result<(), Error> {
+        match self.problems().into_iter().next() {
+            Some(problem) => Err(problem.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Problem for a configuration file which can't be read
+pub(super) fn unreadable(path: &Path, error: &std::io::Error) -> ConfigProblem {
+    ConfigProblem {
+        file: Some(path.to_owned()),
+        line: None,
+        key: None,
+        message: format!("couldn't read the file: {}", error),
+    }
+}
+
+/// What's wrong with an advisory ID (if anything).
+///
+/// IDs of known kinds must be well-formed (e.g. `RUSTSEC-YYYY-NNNN`), and
+/// IDs of other kinds (e.g. of an internal database) mustn't look like
+/// misspelled IDs of known kinds.
+pub fn advisory_id_problem(id: &advisory::Id) -> Option<String> {
+    let string = id.as_str();
+
+    let well_formed = match id.kind() {
+        Kind::RUSTSEC => numbered(string, "RUSTSEC-", 4, 4),
+        Kind::CVE => numbered(string, "CVE-", 4, usize::MAX),
+        Kind::TALOS => numbered(string, "TALOS-", 4, 4),
+        Kind::GHSA => string
+            .trim_start_matches("GHSA-")
+            .split('-')
+            .map(|part| part.len() == 4 && part.chars().all(|c| c.is_ascii_alphanumeric()))
+            .eq(vec![true; 3]),
+        _ => {
+            let upper = string.to_ascii_uppercase();
+
+            return ["RUSTSEC-", "CVE-", "GHSA-", "TALOS-"]
+                .iter()
+                .find(|prefix| upper.starts_with(*prefix))
+                .map(|prefix| {
+                    format!(
+                        "malformed advisory ID {:?} (its `{}` prefix must be uppercase)",
+                        string, prefix
+                    )
+                });
+        }
+    };
+
+    if well_formed {
+        None
+    } else {
+        let format = match id.kind() {
+            Kind::RUSTSEC => "RUSTSEC-YYYY-NNNN",
+            Kind::CVE => "CVE-YYYY-NNNN",
+            Kind::TALOS => "TALOS-YYYY-NNNN",
+            _ => "GHSA-xxxx-xxxx-xxxx",
+        };
+
+        Some(format!(
+            "malformed advisory ID {:?} (expected {})",
+            string, format
+        ))
+    }
+}
+
+/// Does the string have the given prefix followed by a 4-digit year, a dash,
+/// and a number with between `min` and `max` digits?
+fn numbered(string: &str, prefix: &str, min: usize, max: usize) -> bool {
+    let rest = match string.strip_prefix(prefix) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let mut parts = rest.splitn(2, '-');
+    let year = parts.next().unwrap_or_default();
+    let number = parts.next().unwrap_or_default();
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+
+    year.len() == 4
+        && all_digits(year)
+        && number.len() >= min
+        && number.len() <= max
+        && all_digits(number)
+}
+
+/// What's wrong with a URL to send reports to (if anything)
+fn url_problem(url: &str) -> Option<String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if ["http", "https"].contains(&parsed.scheme()) => None,
+        Ok(parsed) => Some(format!(
+            "unsupported URL scheme `{}` in {:?}",
+            parsed.scheme(),
+            url
+        )),
+        Err(e) => Some(format!("invalid URL {:?}: {}", url, e)),
+    }
+}
+
+/// Convert a TOML parse error into a problem, with the line and key it's at.
+///
+/// TOML errors look like ``<message> for key `<key>` at line <n> column
+/// <m>``, or for unknown keys, ``unknown field `<name>`, expected ...``
+/// where the key is the table the unknown field is in.
+fn parse_problem(source: &str, error: &toml::de::Error) -> ConfigProblem {
+    let mut message = error.to_string();
+
+    if let Some(index) = message.rfind(" at line ") {
+        message.truncate(index);
+    }
+
+    let mut key = None;
+
+    if let Some(index) = message.rfind(" for key `") {
+        let path = message[index + " for key `".len()..].trim_end_matches('`');
+
+        if !path.is_empty() {
+            key = Some(path.to_owned());
+        }
+
+        message.truncate(index);
+    }
+
+    let mut line = error.line_col().map(|(line, _)| line + 1);
+
+    // Point unknown fields at the line they're on, rather than at the start
+    // of the table they're in
+    if let Some(field) = message
+        .strip_prefix("unknown field `")
+        .and_then(|rest| rest.split('`').next())
+    {
+        let field_key = match &key {
+            Some(table) => format!("{}.{}", table, field),
+            None => field.to_owned(),
+        };
+
+        if let Some(field_line) = key_line(source, &field_key) {
+            line = Some(field_line);
+        }
+
+        key = Some(field_key);
+    }
+
+    ConfigProblem {
+        file: None,
+        line,
+        key,
+        message,
+    }
+}
+
+/// Find the line (1-based) a dotted key is defined on in a TOML source,
+/// either as a key of its table, or as a table header.
+///
+/// This is a best-effort search of the source's lines which handles
+/// `[table]` headers and `key = value` lines, but not dotted or quoted keys.
+pub(super) fn key_line(source: &str, key: &str) -> Option<usize> {
+    let mut table = String::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            table = line
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim()
+                .to_owned();
+
+            if table == key {
+                return Some(index + 1);
+            }
+
+            continue;
+        }
+
+        let name = match line.find('=') {
+            Some(index) => line[..index].trim(),
+            None => continue,
+        };
+
+        let full_key = if table.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{}", table, name)
+        };
+
+        if full_key == key {
+            return Some(index + 1);
+        }
+    }
+
+    None
+}
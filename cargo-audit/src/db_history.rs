@@ -0,0 +1,147 @@
+//! When advisories were first added to the advisory database
+//!
+//! For audit trails, `--first-published` states when each matched advisory
+//! could first have been detected: the date of the commit of the advisory
+//! database's git history which first added its file. Only the matched
+//! advisories are looked up, with a single walk of each source's history.
+//! Databases without full git history (e.g. copied without their `.git`
+//! directory, or shallow clones) can't be looked up.
+
+use crate::config::ResolvedSource;
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+};
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+/// Note explaining why dates are unknown, for reports of databases without
+/// git history
+pub const NO_HISTORY_NOTE: &str = "the advisory database has no git history (e.g. it isn't a \
+                                   git checkout, or is a shallow clone), so when advisories were \
+                                   first added to it is unknown";
+
+/// Dates advisories were first added to the database's history
+#[derive(Clone, Debug, Default)]
+pub struct FirstPublished {
+    /// Date each advisory was first added (`YYYY-MM-DD`), by ID
+    pub dates: Map<advisory::Id, String>,
+
+    /// Paths of the database sources without git history to look
+    /// advisories up in
+    pub missing_history: Vec<PathBuf>,
+}
+
+/// Look up when each of the given advisories was first added to the
+/// histories of the database's sources (the earliest, if it was added to
+/// several)
+pub fn first_published(
+    sources: &[ResolvedSource],
+    ids: &Set<advisory::Id>,
+) -> Result<FirstPublished, Error> {
+    let mut result = FirstPublished::default();
+
+    for source in sources {
+        let repo = match git2::Repository::open(&source.path) {
+            Ok(repo) if !repo.is_shallow() => repo,
+            _ => {
+                result.missing_history.push(source.path.clone());
+                continue;
+            }
+        };
+
+        let dates = added_dates(&repo, ids).map_err(|e| {
+            Error::new(
+                ErrorKind::Repo,
+                &format!(
+                    "couldn't read the history of {}: {}",
+                    source.path.display(),
+                    e.message()
+                ),
+            )
+        })?;
+
+        for (id, date) in dates {
+            let earliest = result.dates.entry(id).or_insert_with(|| date.clone());
+
+            if date < *earliest {
+                *earliest = date;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Walk the first-parent history of the repository's `HEAD` from its first
+/// commit, finding the dates of the commits which first added the given
+/// advisories' files (`crates/<package>/<ID>.md` and the like, wherever they
+/// were moved to later)
+fn added_dates(
+    repo: &git2::Repository,
+    ids: &Set<advisory::Id>,
+) -> Result<Map<advisory::Id, String>, git2::Error> {
+    let mut dates = Map::new();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.simplify_first_parent()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    for oid in revwalk {
+        if dates.len() == ids.len() {
+            break;
+        }
+
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+        for delta in diff.deltas() {
+            if delta.status() != git2::Delta::Added {
+                continue;
+            }
+
+            let id = match delta.new_file().path().and_then(advisory_id) {
+                Some(id) => id,
+                None => continue,
+            };
+
+            if ids.contains(&id) && !dates.contains_key(&id) {
+                dates.insert(id, commit_date(&commit));
+            }
+        }
+    }
+
+    Ok(dates)
+}
+
+/// ID of the advisory whose file is at the given path of the database (if
+/// it's an advisory file)
+fn advisory_id(path: &Path) -> Option<advisory::Id> {
+    let in_collection = path.starts_with("crates") || path.starts_with("rust");
+
+    if !in_collection {
+        return None;
+    }
+
+    let name = path.file_name()?.to_str()?;
+    let stem = name
+        .strip_suffix(".md")
+        .or_else(|| name.strip_suffix(".toml"))?;
+
+    stem.parse().ok()
+}
+
+/// Date of a commit (in UTC), as `YYYY-MM-DD`
+pub fn commit_date(commit: &git2::Commit<'_>) -> String {
+    let time = UNIX_EPOCH + Duration::from_secs(commit.time().seconds().max(0) as u64);
+    humantime::format_rfc3339(time).to_string()[..10].to_owned()
+}
@@ -0,0 +1,118 @@
+//! Warnings about crates with several versions in the lockfile
+//!
+//! Duplicate versions aren't vulnerabilities, but they're how old vulnerable
+//! versions linger after a partial upgrade. With `--warn duplicate-versions`,
+//! each older version of a crate which is also locked at a newer version is
+//! reported, along with the direct dependencies pulling it in and whether any
+//! of the crate's versions has an advisory.
+
+use rustsec::{
+    advisory,
+    cargo_lock::dependency::{graph::EdgeDirection, Dependency, Tree},
+    lockfile::Lockfile,
+    package::{self, Package},
+    warning::{self, Duplicates},
+    Report, Version, Warning,
+};
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+
+/// Warn about the older versions of every crate in the lockfile which has
+/// more than one version, using the report's findings to note advisories
+/// against any of them and the dependency tree (if known) to find which
+/// direct dependencies pull them in
+pub fn warnings(lockfile: &Lockfile, report: &Report, tree: Option<&Tree>) -> Vec<Warning> {
+    let mut packages: Map<&package::Name, Vec<&Package>> = Map::new();
+
+    for package in &lockfile.packages {
+        packages.entry(&package.name).or_default().push(package);
+    }
+
+    let mut warnings = vec![];
+
+    for (name, packages) in packages {
+        let versions: Set<&Version> = packages.iter().map(|package| &package.version).collect();
+
+        if versions.len() < 2 {
+            continue;
+        }
+
+        let newest = *versions.iter().next_back().expect("versions aren't empty");
+        let advisories = advisories(report, name);
+
+        for package in packages {
+            if &package.version == newest {
+                continue;
+            }
+
+            let mut warning = Warning::new(warning::Kind::DuplicateVersions, package, None, None);
+            warning.duplicates = Some(Duplicates {
+                versions: versions.iter().map(|&version| version.clone()).collect(),
+                pinned_by: tree
+                    .map(|tree| pinned_by(tree, package))
+                    .unwrap_or_default(),
+                advisories: advisories.clone(),
+            });
+
+            warnings.push(warning);
+        }
+    }
+
+    warnings
+}
+
+/// Advisories found against any version of the given crate
+fn advisories(report: &Report, name: &package::Name) -> Vec<advisory::Id> {
+    let vulnerabilities = report
+        .vulnerabilities
+        .list
+        .iter()
+        .filter(|vuln| &vuln.package.name == name)
+        .map(|vuln| &vuln.advisory.id);
+
+    let warnings = report
+        .warnings
+        .values()
+        .flatten()
+        .filter(|warning| &warning.package.name == name)
+        .filter_map(|warning| warning.advisory.as_ref())
+        .map(|advisory| &advisory.id);
+
+    vulnerabilities
+        .chain(warnings)
+        .cloned()
+        .collect::<Set<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Direct dependencies of the workspace (i.e. of the tree's roots) which
+/// are, or transitively depend on, the given package
+fn pinned_by(tree: &Tree, package: &Package) -> Vec<String> {
+    let graph = tree.graph();
+    let node = match tree.nodes().get(&Dependency::from(package)) {
+        Some(&node) => node,
+        None => return vec![],
+    };
+
+    let mut dependents = Set::new();
+    let mut pending = vec![node];
+
+    while let Some(node) = pending.pop() {
+        if dependents.insert(node) {
+            pending.extend(graph.neighbors_directed(node, EdgeDirection::Incoming));
+        }
+    }
+
+    let direct: Set<String> = tree
+        .roots()
+        .into_iter()
+        .flat_map(|root| graph.neighbors_directed(root, EdgeDirection::Outgoing))
+        .filter(|node| dependents.contains(node))
+        .map(|node| {
+            let package = &graph[node];
+            format!("{} {}", package.name, package.version)
+        })
+        .collect();
+
+    direct.into_iter().collect()
+}
@@ -0,0 +1,287 @@
+//! Exploit Prediction Scoring System (EPSS) scores from the FIRST API
+//!
+//! Scores for all of a report's CVEs are fetched in a single request, and
+//! cached on disk (including CVEs without a score) until their TTL expires.
+
+use crate::{cache::Cache, config::EpssConfig, retry::Retry};
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    fs,
+    vulnerability::Epss,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap as Map,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// URL of the FIRST EPSS API
+pub const DEFAULT_URL: &str = "https://api.first.org/data/v1/epss";
+
+/// Name of the file scores are cached in (located in the report cache directory)
+pub const CACHE_FILE: &str = "epss.json";
+
+/// How long fetched scores are cached for by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long to wait for the EPSS API to respond
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client for the EPSS API
+#[derive(Clone, Debug)]
+pub struct Client {
+    /// URL of the EPSS API
+    url: String,
+
+    /// Path to the file scores are cached in
+    cache_path: PathBuf,
+
+    /// How long fetched scores are cached for
+    ttl: Duration,
+
+    /// Policy for retrying failed requests
+    retry: Retry,
+}
+
+impl Client {
+    /// Create a client for the EPSS API at the given URL
+    pub fn new(url: impl Into<String>, cache_path: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            url: url.into(),
+            cache_path: cache_path.into(),
+            ttl,
+            retry: Retry::default(),
+        }
+    }
+
+    /// Create the client configured in `audit.toml`, caching scores in the
+    /// given report cache
+    pub fn from_config(config: &EpssConfig, cache: &Cache) -> Self {
+        Self::new(
+            config.url.as_deref().unwrap_or(DEFAULT_URL),
+            cache.dir().join(CACHE_FILE),
+            config
+                .ttl_hours
+                .map(|hours| Duration::from_secs(hours * 60 * 60))
+                .unwrap_or(DEFAULT_TTL),
+        )
+    }
+
+    /// Set the policy for retrying failed requests
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Path to the file scores are cached in
+    pub fn cache_path(&self) -> &Path {
+        &self.cache_path
+    }
+
+    /// Get the scores for the given CVEs, omitting CVEs without a score.
+    ///
+    /// Scores are served from the cache where possible, and the rest are
+    /// fetched in a single request. If that fails, the error is returned
+    /// along with whatever scores are cached, regardless of their age.
+    pub fn scores(&self, cves: &[advisory::Id]) -> (Map<advisory::Id, Epss>, Option<Error>) {
+        let mut cache = self.load_cache();
+        let now = unix_time();
+
+        let missing: Vec<_> = cves
+            .iter()
+            .filter(|cve| match cache.get(cve.as_str()) {
+                Some(entry) => now.saturating_sub(entry.fetched) >= self.ttl.as_secs(),
+                None => true,
+            })
+            .collect();
+
+        let error = if missing.is_empty() {
+            None
+        } else {
+            match self.fetch(&missing) {
+                Ok(scores) => {
+                    for cve in missing {
+                        let entry = CacheEntry {
+                            fetched: now,
+                            score: scores.get(cve).cloned(),
+                        };
+                        cache.insert(cve.as_str().to_owned(), entry);
+                    }
+
+                    if let Err(e) = self.save_cache(&cache) {
+                        tracing::debug!("couldn't cache EPSS scores: {}", e);
+                    }
+
+                    None
+                }
+                Err(e) => Some(e),
+            }
+        };
+
+        let scores = cves
+            .iter()
+            .filter_map(|cve| {
+                let score = cache.get(cve.as_str())?.score.clone()?;
+                Some((cve.clone(), score))
+            })
+            .collect();
+
+        (scores, error)
+    }
+
+    /// Fetch the scores for the given CVEs from the API
+    fn fetch(&self, cves: &[&advisory::Id]) -> Result<Map<advisory::Id, Epss>, Error> {
+        let cve_list = cves
+            .iter()
+            .map(|cve| cve.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        tracing::debug!("fetching EPSS scores for {} CVEs", cves.len());
+
+        let response = self.retry.run("fetching EPSS scores", || {
+            let response = reqwest::blocking::Client::builder()
+                .user_agent(concat!("cargo-audit/", env!("CARGO_PKG_VERSION")))
+                .timeout(TIMEOUT)
+                .build()
+                .and_then(|client| {
+                    client
+                        .get(&self.url)
+                        .query(&[("cve", &cve_list), ("limit", &cves.len().to_string())])
+                        .send()
+                })
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text())?;
+
+            Ok(response)
+        })?;
+
+        let response: Response = serde_json::from_str(&response).map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("invalid EPSS API response: {}", e),
+            )
+        })?;
+
+        response
+            .data
+            .into_iter()
+            .map(|score| {
+                let epss = score.parse()?;
+                Ok((epss.cve.clone(), epss))
+            })
+            .collect()
+    }
+
+    /// Load the cached scores, ignoring an invalid or missing cache
+    fn load_cache(&self) -> Map<String, CacheEntry> {
+        let contents = match fs::read_to_string(&self.cache_path) {
+            Ok(contents) => contents,
+            Err(_) => return Map::new(),
+        };
+
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::debug!("ignoring invalid EPSS cache: {}", e);
+            Map::new()
+        })
+    }
+
+    /// Save the cached scores
+    fn save_cache(&self, cache: &Map<String, CacheEntry>) -> Result<(), Error> {
+        let json = serde_json::to_string(cache).map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't serialize EPSS cache: {}", e),
+            )
+        })?;
+
+        if let Some(dir) = self.cache_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&self.cache_path, json)?;
+        Ok(())
+    }
+}
+
+/// Cached score for a CVE
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CacheEntry {
+    /// When the score was fetched (seconds since the Unix epoch)
+    fetched: u64,
+
+    /// Score for the CVE (if it has one)
+    score: Option<Epss>,
+}
+
+/// Response from the EPSS API
+#[derive(Debug, Deserialize)]
+struct Response {
+    /// Scores for the requested CVEs which have one
+    data: Vec<Score>,
+}
+
+/// Score as returned by the EPSS API (which encodes numbers as strings)
+#[derive(Debug, Deserialize)]
+struct Score {
+    cve: String,
+    epss: String,
+    percentile: String,
+    date: String,
+}
+
+impl Score {
+    /// Parse the score's fields
+    fn parse(self) -> Result<Epss, Error> {
+        let parse_number = |value: &str| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Parse,
+                        &format!("invalid EPSS score for {}: {}", self.cve, value),
+                    )
+                })
+        };
+
+        Ok(Epss {
+            score: parse_number(&self.epss)?,
+            percentile: parse_number(&self.percentile)?,
+            cve: self.cve.parse()?,
+            date: self.date,
+        })
+    }
+}
+
+/// Format the given score for display, e.g. `0.87 (97th percentile)`
+pub fn display(epss: &Epss) -> String {
+    let percentile = (epss.percentile * 100.0).floor() as u32;
+
+    let suffix = match (percentile % 10, percentile % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    // Most CVEs have scores well below 1%, which would all display as 0.00
+    let precision = if epss.score < 0.01 { 4 } else { 2 };
+
+    format!(
+        "{:.*} ({}{} percentile)",
+        precision, epss.score, percentile, suffix
+    )
+}
+
+/// Current time in seconds since the Unix epoch
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,645 @@
+//! Feature-aware auditing
+//!
+//! `Cargo.lock` contains every package which could be compiled for any
+//! selection of features and target platform. When a feature selection is
+//! given (`--features`, `--all-features` or `--no-default-features`), the
+//! packages `cargo build` would actually compile with it on the host platform
+//! are resolved with `cargo metadata`, and the others are pruned from the
+//! lockfile before auditing it.
+//!
+//! As with `cargo build`, features are unified across the workspace's
+//! default members, and dev-dependencies aren't compiled.
+//!
+//! With `--feature-aware`, nothing is pruned: instead, vulnerabilities in the
+//! packages which are only compiled with features that aren't enabled are
+//! reported as `optional` warnings, which don't fail the audit.
+//!
+//! `cargo metadata` also tells which kind of dependency (regular, build or
+//! dev) each package is reached through, for `fail-on` policies, which
+//! packages are workspace members, for showing which of them findings affect,
+//! and which requirements packages declare for their dependencies, for
+//! telling whether upgrading a vulnerable package needs any of them bumped.
+//!
+//! Finally, `--from-metadata` audits the dependency graph in saved
+//! `cargo metadata --locked` output instead of a lockfile, e.g. for projects
+//! whose `Cargo.lock` is in a format this version can't parse.
+
+use crate::config::FeaturesConfig;
+use rustsec::{
+    cargo_lock::{
+        dependency::Dependency,
+        package::{SourceId, Version},
+        Lockfile, Package,
+    },
+    error::{Error, ErrorKind},
+    vulnerability::{Dependent, Scope},
+    VersionReq,
+};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    env, fs,
+    io::{self, Read},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Packages compiled with the configured feature selection, resolved with
+/// `cargo metadata` for the given `Cargo.toml`
+pub fn resolve(
+    manifest_path: &Path,
+    config: &FeaturesConfig,
+    offline: bool,
+) -> Result<Set<Dependency>, Error> {
+    let mut cmd = metadata_command(manifest_path, offline);
+    feature_args(&mut cmd, config);
+
+    if let Some(host) = host_triple() {
+        cmd.arg("--filter-platform").arg(host);
+    }
+
+    metadata(cmd, "resolve features")?.compiled_packages()
+}
+
+/// Packages which are only compiled with features the configured selection
+/// (or the default features, if none is given) doesn't enable: those
+/// compiled with all features enabled, less those compiled with the
+/// selection. Dev-dependencies and dependencies for other platforms aren't
+/// compiled either way, so they aren't included.
+pub fn optional(
+    manifest_path: &Path,
+    config: &FeaturesConfig,
+    offline: bool,
+) -> Result<Set<Dependency>, Error> {
+    let selected = resolve(manifest_path, config, offline)?;
+    let all_features = FeaturesConfig {
+        features: vec![],
+        all_features: true,
+        no_default_features: false,
+        ..config.clone()
+    };
+
+    Ok(resolve(manifest_path, &all_features, offline)?
+        .difference(&selected)
+        .cloned()
+        .collect())
+}
+
+/// Kinds of dependency through which each package is reachable from the
+/// workspace's members, resolved with `cargo metadata` for the given
+/// `Cargo.toml` (with the configured feature selection, or all features so
+/// that every package in the lockfile is classified)
+pub fn scopes(
+    manifest_path: &Path,
+    config: &FeaturesConfig,
+    offline: bool,
+) -> Result<Map<Dependency, Set<Scope>>, Error> {
+    let mut cmd = metadata_command(manifest_path, offline);
+
+    if config.is_selected() {
+        feature_args(&mut cmd, config);
+    } else {
+        cmd.arg("--all-features");
+    }
+
+    metadata(cmd, "resolve dependency kinds")?.scopes()
+}
+
+/// Members of the workspace of the given `Cargo.toml`, listed with
+/// `cargo metadata --no-deps` (which doesn't resolve any dependencies)
+pub fn workspace_members(manifest_path: &Path) -> Result<Set<Dependency>, Error> {
+    let mut cmd = metadata_command(manifest_path, true);
+    cmd.arg("--no-deps");
+
+    let metadata = metadata(cmd, "list workspace members")?;
+    metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .map(MetadataPackage::to_dependency)
+        .collect()
+}
+
+/// Dependents of each package in the dependency graph of the given
+/// `Cargo.toml` (with all features enabled), along with the requirements
+/// they declare for it, resolved with `cargo metadata` in offline mode (so
+/// this fails if the dependencies' sources haven't been downloaded)
+pub fn dependents(manifest_path: &Path) -> Result<Map<Dependency, Vec<Dependent>>, Error> {
+    let mut cmd = metadata_command(manifest_path, true);
+    cmd.arg("--all-features");
+
+    metadata(cmd, "resolve dependency requirements")?.dependents()
+}
+
+/// Read the output of `cargo metadata --format-version 1` at the given path
+/// (or `-` for STDIN), as a lockfile with the packages in its dependency graph
+pub fn read_lockfile(path: &Path) -> Result<Lockfile, Error> {
+    let result = if path == Path::new("-") {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source).map(|_| source)
+    } else {
+        fs::read_to_string(path)
+    };
+
+    let source = result.map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't read {}: {}", path.display(), e),
+        )
+    })?;
+
+    parse_lockfile(&source).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse {}: {}", path.display(), e.msg()),
+        )
+    })
+}
+
+/// Parse the output of `cargo metadata --format-version 1` as a lockfile
+/// with the packages in its dependency graph
+pub fn parse_lockfile(source: &str) -> Result<Lockfile, Error> {
+    let metadata: Metadata =
+        serde_json::from_str(source).map_err(|e| Error::new(ErrorKind::Parse, &e.to_string()))?;
+
+    metadata.lockfile()
+}
+
+/// `cargo metadata` command for the given `Cargo.toml`
+fn metadata_command(manifest_path: &Path, offline: bool) -> Command {
+    let mut cmd = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    cmd.args(&["metadata", "--format-version", "1", "--locked"])
+        .arg("--manifest-path")
+        .arg(manifest_path);
+
+    if offline {
+        cmd.arg("--offline");
+    }
+
+    cmd
+}
+
+/// Add the arguments selecting the configured features to a `cargo` command
+fn feature_args(cmd: &mut Command, config: &FeaturesConfig) {
+    if !config.features.is_empty() {
+        cmd.arg("--features").arg(config.features.join(","));
+    }
+
+    if config.all_features {
+        cmd.arg("--all-features");
+    }
+
+    if config.no_default_features {
+        cmd.arg("--no-default-features");
+    }
+}
+
+/// Run a `cargo metadata` command (in order to do `what`, for errors) and
+/// parse its output
+fn metadata(mut cmd: Command, what: &str) -> Result<Metadata, Error> {
+    let output = cmd.stdin(Stdio::null()).output().map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't run `cargo metadata`: {}", e),
+        )
+    })?;
+
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Io,
+            &format!(
+                "couldn't {} with `cargo metadata`:\n{}",
+                what,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ),
+        ));
+    }
+
+    serde_json::from_slice(&output.stdout).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse `cargo metadata` output: {}", e),
+        )
+    })
+}
+
+/// Remove the packages which aren't in `compiled` (along with dependencies
+/// on them) from the lockfile, returning how many were removed
+pub fn prune(lockfile: &mut Lockfile, compiled: &Set<Dependency>) -> usize {
+    let count = lockfile.packages.len();
+    lockfile
+        .packages
+        .retain(|package| compiled.contains(&Dependency::from(package)));
+
+    // Dependencies don't always record their source, so match them by name
+    // and version
+    let remaining: Set<_> = lockfile
+        .packages
+        .iter()
+        .map(|package| (package.name.clone(), package.version.clone()))
+        .collect();
+
+    for package in &mut lockfile.packages {
+        package
+            .dependencies
+            .retain(|dep| remaining.contains(&(dep.name.clone(), dep.version.clone())));
+    }
+
+    count - lockfile.packages.len()
+}
+
+/// Target triple of the host, which `cargo build` compiles for by default
+fn host_triple() -> Option<String> {
+    let output = Command::new(env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+        .arg("-vV")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_owned())
+}
+
+/// Relevant parts of the output of `cargo metadata --format-version 1`
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    /// Packages in the dependency graph
+    packages: Vec<MetadataPackage>,
+
+    /// Packages `cargo build` builds in the workspace (cargo 1.71+)
+    #[serde(default)]
+    workspace_default_members: Option<Vec<String>>,
+
+    /// Packages in the workspace
+    workspace_members: Vec<String>,
+
+    /// Dependency graph resolved with the selected features and platform
+    resolve: Option<Resolve>,
+}
+
+impl Metadata {
+    /// Packages reachable from the workspace's default members through
+    /// anything but dev-dependencies
+    fn compiled_packages(&self) -> Result<Set<Dependency>, Error> {
+        let nodes = self.nodes()?;
+
+        let mut reachable = Set::new();
+        let mut pending: Vec<&str> = self
+            .workspace_default_members
+            .as_ref()
+            .unwrap_or(&self.workspace_members)
+            .iter()
+            .map(String::as_str)
+            .collect();
+
+        while let Some(id) = pending.pop() {
+            if !reachable.insert(id) {
+                continue;
+            }
+
+            if let Some(node) = nodes.get(id) {
+                pending.extend(
+                    node.deps
+                        .iter()
+                        .filter(|dep| dep.is_compiled())
+                        .map(|dep| dep.pkg.as_str()),
+                );
+            }
+        }
+
+        self.packages
+            .iter()
+            .filter(|package| reachable.contains(package.id.as_str()))
+            .map(MetadataPackage::to_dependency)
+            .collect()
+    }
+
+    /// Kinds of dependency through which each package is reachable from the
+    /// workspace's members. Scopes only narrow from `normal`: dev- and build
+    /// dependencies (and proc-macros) of a build dependency are all `build`
+    /// ones, say, and everything a dev-dependency pulls in is `dev`.
+    fn scopes(&self) -> Result<Map<Dependency, Set<Scope>>, Error> {
+        let nodes = self.nodes()?;
+        let proc_macros: Set<&str> = self
+            .packages
+            .iter()
+            .filter(|package| package.is_proc_macro())
+            .map(|package| package.id.as_str())
+            .collect();
+
+        let mut reachable: Set<(&str, Scope)> = Set::new();
+        let mut pending: Vec<(&str, Scope)> = self
+            .workspace_members
+            .iter()
+            .map(|id| (id.as_str(), Scope::Normal))
+            .collect();
+
+        while let Some((id, scope)) = pending.pop() {
+            if !reachable.insert((id, scope)) {
+                continue;
+            }
+
+            let node = match nodes.get(id) {
+                Some(node) => node,
+                None => continue,
+            };
+
+            for dep in &node.deps {
+                let kinds: Vec<Option<&str>> = if dep.dep_kinds.is_empty() {
+                    vec![None]
+                } else {
+                    dep.dep_kinds
+                        .iter()
+                        .map(|kind| kind.kind.as_deref())
+                        .collect()
+                };
+
+                for kind in kinds {
+                    let dep_scope = match (scope, kind) {
+                        (Scope::Normal, Some("dev")) => Scope::Dev,
+                        (Scope::Normal, Some("build")) => Scope::Build,
+                        (Scope::Normal, _) if proc_macros.contains(dep.pkg.as_str()) => {
+                            Scope::Build
+                        }
+                        _ => scope,
+                    };
+
+                    pending.push((dep.pkg.as_str(), dep_scope));
+                }
+            }
+        }
+
+        let mut by_id: Map<&str, Set<Scope>> = Map::new();
+
+        for (id, scope) in reachable {
+            by_id.entry(id).or_default().insert(scope);
+        }
+
+        let mut scopes = Map::new();
+
+        for package in &self.packages {
+            if let Some(package_scopes) = by_id.remove(package.id.as_str()) {
+                scopes.insert(package.to_dependency()?, package_scopes);
+            }
+        }
+
+        Ok(scopes)
+    }
+
+    /// Dependents of each package, and the requirements they declare for it
+    /// (which match its version: a package may depend on several versions of
+    /// a crate under different names)
+    fn dependents(&self) -> Result<Map<Dependency, Vec<Dependent>>, Error> {
+        let nodes = self.nodes()?;
+        let packages: Map<&str, &MetadataPackage> = self
+            .packages
+            .iter()
+            .map(|package| (package.id.as_str(), package))
+            .collect();
+
+        let mut by_id: Map<&str, Vec<Dependent>> =
+            packages.keys().map(|id| (*id, vec![])).collect();
+
+        for node in nodes.values() {
+            let dependent = match packages.get(node.id.as_str()) {
+                Some(dependent) => dependent,
+                None => continue,
+            };
+
+            let member = self.workspace_members.contains(&dependent.id);
+            let name = dependent.to_dependency()?.name;
+
+            for dep in &node.deps {
+                let package = match packages.get(dep.pkg.as_str()) {
+                    Some(package) => package,
+                    None => continue,
+                };
+
+                // Dependencies may be declared more than once, e.g. as both
+                // regular and build dependencies
+                let mut requirements: Vec<VersionReq> = vec![];
+
+                for declared in &dependent.dependencies {
+                    if declared.name != package.name {
+                        continue;
+                    }
+
+                    if let Ok(requirement) = declared.req.parse::<VersionReq>() {
+                        if requirement.matches(&package.version)
+                            && !requirements.contains(&requirement)
+                        {
+                            requirements.push(requirement);
+                        }
+                    }
+                }
+
+                let dependents = by_id.entry(dep.pkg.as_str()).or_default();
+
+                for requirement in requirements {
+                    dependents.push(Dependent {
+                        name: name.clone(),
+                        version: dependent.version.clone(),
+                        requirement,
+                        member,
+                    });
+                }
+            }
+        }
+
+        let mut dependents = Map::new();
+
+        for (id, package_dependents) in by_id {
+            dependents.insert(packages[id].to_dependency()?, package_dependents);
+        }
+
+        Ok(dependents)
+    }
+
+    /// Lockfile with the packages in the resolved dependency graph, and the
+    /// dependencies between them
+    fn lockfile(&self) -> Result<Lockfile, Error> {
+        let nodes = self.nodes()?;
+        let mut dependencies: Map<&str, Dependency> = Map::new();
+
+        for package in &self.packages {
+            if nodes.contains_key(package.id.as_str()) {
+                dependencies.insert(package.id.as_str(), package.to_dependency()?);
+            }
+        }
+
+        let mut packages = vec![];
+
+        for (id, dependency) in &dependencies {
+            let mut package_dependencies: Vec<Dependency> = nodes[id]
+                .deps
+                .iter()
+                .filter_map(|dep| dependencies.get(dep.pkg.as_str()).cloned())
+                .collect();
+
+            package_dependencies.sort();
+            package_dependencies.dedup();
+
+            packages.push(Package {
+                name: dependency.name.clone(),
+                version: dependency.version.clone(),
+                source: dependency.source.clone(),
+                checksum: None,
+                dependencies: package_dependencies,
+                replace: None,
+            });
+        }
+
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        Ok(Lockfile {
+            version: Default::default(),
+            packages,
+            root: None,
+            metadata: Default::default(),
+            patch: Default::default(),
+        })
+    }
+
+    /// Packages in the resolved dependency graph, by ID
+    fn nodes(&self) -> Result<Map<&str, &Node>, Error> {
+        let resolve = self.resolve.as_ref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::Parse,
+                &"`cargo metadata` output has no dependency graph",
+            )
+        })?;
+
+        Ok(resolve
+            .nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node))
+            .collect())
+    }
+}
+
+/// Package in `cargo metadata` output
+#[derive(Debug, Deserialize)]
+struct MetadataPackage {
+    /// Opaque package ID
+    id: String,
+
+    /// Package name
+    name: String,
+
+    /// Package version
+    version: Version,
+
+    /// Source of the package (`None` for path dependencies)
+    source: Option<String>,
+
+    /// Targets of the package (its library, binaries, tests, etc.)
+    #[serde(default)]
+    targets: Vec<Target>,
+
+    /// Dependencies declared in the package's `Cargo.toml`
+    #[serde(default)]
+    dependencies: Vec<DeclaredDependency>,
+}
+
+impl MetadataPackage {
+    /// Is the package a proc-macro, i.e. does it run while compiling the
+    /// packages which depend on it?
+    fn is_proc_macro(&self) -> bool {
+        self.targets
+            .iter()
+            .any(|target| target.kind.iter().any(|kind| kind == "proc-macro"))
+    }
+
+    /// Get the lockfile dependency for this package
+    fn to_dependency(&self) -> Result<Dependency, Error> {
+        let source = match &self.source {
+            Some(source) => Some(source.parse::<SourceId>().map_err(|e| {
+                Error::new(
+                    ErrorKind::Parse,
+                    &format!("invalid source for {} {}: {}", self.name, self.version, e),
+                )
+            })?),
+            None => None,
+        };
+
+        Ok(Dependency {
+            name: self.name.parse().map_err(|e| {
+                Error::new(
+                    ErrorKind::Parse,
+                    &format!("invalid package name {:?}: {}", self.name, e),
+                )
+            })?,
+            version: self.version.clone(),
+            source,
+        })
+    }
+}
+
+/// Dependency declared in a package's `Cargo.toml`, in `cargo metadata`
+/// output
+#[derive(Debug, Deserialize)]
+struct DeclaredDependency {
+    /// Name of the package depended on (not the name it's renamed to)
+    name: String,
+
+    /// Version requirement for the package
+    req: String,
+}
+
+/// Target of a package in `cargo metadata` output
+#[derive(Debug, Deserialize)]
+struct Target {
+    /// Kinds of target, e.g. `lib` or `proc-macro`
+    #[serde(default)]
+    kind: Vec<String>,
+}
+
+/// Resolved dependency graph in `cargo metadata` output
+#[derive(Debug, Deserialize)]
+struct Resolve {
+    /// Packages in the graph
+    nodes: Vec<Node>,
+}
+
+/// Package in the resolved dependency graph
+#[derive(Debug, Deserialize)]
+struct Node {
+    /// ID of the package
+    id: String,
+
+    /// Dependencies of the package
+    #[serde(default)]
+    deps: Vec<NodeDep>,
+}
+
+/// Dependency of a package in the resolved dependency graph
+#[derive(Debug, Deserialize)]
+struct NodeDep {
+    /// ID of the dependency
+    pkg: String,
+
+    /// Kinds of dependency (cargo 1.41+)
+    #[serde(default)]
+    dep_kinds: Vec<DepKind>,
+}
+
+impl NodeDep {
+    /// Is the dependency compiled by `cargo build`, i.e. is it a regular or
+    /// build dependency?
+    fn is_compiled(&self) -> bool {
+        self.dep_kinds.is_empty()
+            || self
+                .dep_kinds
+                .iter()
+                .any(|kind| kind.kind.as_deref() != Some("dev"))
+    }
+}
+
+/// Kind of dependency (`None` for regular dependencies)
+#[derive(Debug, Deserialize)]
+struct DepKind {
+    /// `dev`, `build` or `None`
+    kind: Option<String>,
+}
@@ -0,0 +1,219 @@
+//! Auditing git dependencies by the commits which fix advisories
+//!
+//! The version of a git dependency says little about whether it includes a
+//! fix, so vulnerable git dependencies are reported as `overridden` warnings.
+//! When an advisory lists the commits which fix it (`patched_commits` in its
+//! `[versions]`), the locked revision is checked against them instead, in a
+//! bare clone of the dependency's repository:
+//!
+//! - revisions which include one of the commits are patched, whatever their
+//!   version
+//! - revisions which predate the commits (which are in the repository, but
+//!   not among the revision's ancestors) are vulnerable
+//! - otherwise (e.g. a fork which cherry-picked the fix, so the commits aren't
+//!   in its repository), the warning stays, to be verified manually
+
+use crate::{
+    retry::{self, Retry},
+    verify::{self, git_error},
+};
+use rustsec::{
+    cargo_lock::Package,
+    report::VulnerabilityInfo,
+    warning::{self, Warning},
+    Database, Error, Report, Vulnerability,
+};
+use std::{collections::BTreeMap as Map, path::PathBuf};
+
+/// Settings for checking locked revisions
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Directory the bare clones of git dependencies are kept in
+    pub git_dir: PathBuf,
+
+    /// How to retry fetching repositories
+    pub retry: Retry,
+}
+
+/// Whether a locked revision includes the fix for an advisory
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// The revision includes one of the commits which fix the advisory
+    Patched,
+
+    /// The revision predates the commits which fix the advisory
+    Vulnerable,
+
+    /// The commits which fix the advisory aren't in the repository
+    Unknown,
+}
+
+/// Check the locked revisions of the git dependencies which the report's
+/// `overridden` warnings (and vulnerabilities) are about, for advisories
+/// which list the commits fixing them. Patched ones are removed from the
+/// report, and `overridden` warnings about vulnerable ones become
+/// vulnerabilities.
+///
+/// Returns the errors for the repositories which couldn't be checked (whose
+/// findings are left as they are).
+pub fn apply(report: &mut Report, db: &Database, settings: &Settings) -> Vec<Error> {
+    let mut checker = Checker {
+        settings,
+        statuses: Map::new(),
+        errors: vec![],
+    };
+
+    let overridden = report
+        .warnings
+        .remove(&warning::Kind::Overridden)
+        .unwrap_or_default();
+
+    let mut warnings = vec![];
+    let mut found = vec![];
+
+    for warning in overridden {
+        match checker.warning_status(&warning) {
+            Some(Status::Patched) => (),
+            Some(Status::Vulnerable) => {
+                match warning
+                    .advisory
+                    .as_ref()
+                    .and_then(|advisory| db.get(&advisory.id))
+                {
+                    Some(advisory) => {
+                        let mut vuln = Vulnerability::new(advisory, &warning.package);
+
+                        if vuln.severity.is_none() {
+                            vuln.effective_severity = report.settings.unscored_severity;
+                        }
+
+                        vuln.source = warning.source;
+                        found.push(vuln);
+                    }
+                    None => warnings.push(warning),
+                }
+            }
+            Some(Status::Unknown) | None => warnings.push(warning),
+        }
+    }
+
+    if !warnings.is_empty() {
+        report.warnings.insert(warning::Kind::Overridden, warnings);
+    }
+
+    // Vulnerabilities of git dependencies are reported as they are with
+    // `--overridden fail`, unless the locked revision includes the fix
+    let count = report.vulnerabilities.list.len();
+    let mut list: Vec<Vulnerability> = report
+        .vulnerabilities
+        .list
+        .drain(..)
+        .filter(|vuln| {
+            checker.status(&vuln.package, vuln.versions.patched_commits()) != Some(Status::Patched)
+        })
+        .collect();
+
+    if !found.is_empty() || list.len() != count {
+        list.extend(found);
+
+        let ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+        let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+        let vex = std::mem::take(&mut report.vulnerabilities.vex);
+        report.vulnerabilities = VulnerabilityInfo::new(list);
+        report.vulnerabilities.ignored = ignored;
+        report.vulnerabilities.acknowledged = acknowledged;
+        report.vulnerabilities.vex = vex;
+        report.sort();
+    } else {
+        report.vulnerabilities.list = list;
+    }
+
+    checker.errors
+}
+
+/// Checks locked revisions, fetching each repository (and checking each
+/// revision against each set of commits) once
+struct Checker<'a> {
+    settings: &'a Settings,
+    statuses: Map<(String, String, Vec<String>), Option<Status>>,
+    errors: Vec<Error>,
+}
+
+impl Checker<'_> {
+    /// Status of the package an `overridden` warning is about
+    fn warning_status(&mut self, warning: &Warning) -> Option<Status> {
+        let commits = warning.versions.as_ref()?.patched_commits();
+        self.status(&warning.package, commits)
+    }
+
+    /// Status of the locked revision of the given package, if it's a git
+    /// dependency and there are commits to check it against
+    fn status(&mut self, package: &Package, commits: &[String]) -> Option<Status> {
+        let source = package.source.as_ref().filter(|source| source.is_git())?;
+        let rev = source.precise()?;
+
+        if commits.is_empty() {
+            return None;
+        }
+
+        let key = (source.url().to_string(), rev.to_owned(), commits.to_vec());
+
+        if let Some(status) = self.statuses.get(&key) {
+            return *status;
+        }
+
+        let (url, rev, commits) = &key;
+        let status = self
+            .settings
+            .retry
+            .run(&format!("fetching {}", url), || {
+                verify::fetch_clone(&self.settings.git_dir, url).map_err(retry::Failure::git)
+            })
+            .and_then(|repo| rev_status(&repo, rev, commits));
+
+        let status = match status {
+            Ok(status) => Some(status),
+            Err(e) => {
+                self.errors.push(e);
+                None
+            }
+        };
+
+        self.statuses.insert(key, status);
+        status
+    }
+}
+
+/// Does the given revision of the repository include one of the commits?
+fn rev_status(repo: &git2::Repository, rev: &str, commits: &[String]) -> Result<Status, Error> {
+    let rev = git2::Oid::from_str(rev).map_err(git_error)?;
+
+    // Revisions which aren't on any branch or tag aren't fetched
+    if repo.find_commit(rev).is_err() {
+        return Ok(Status::Unknown);
+    }
+
+    let mut found = false;
+
+    for commit in commits {
+        let fix = match repo
+            .revparse_single(commit)
+            .and_then(|object| object.peel_to_commit())
+        {
+            Ok(fix) => fix.id(),
+            Err(_) => continue,
+        };
+
+        if fix == rev || repo.graph_descendant_of(rev, fix).map_err(git_error)? {
+            return Ok(Status::Patched);
+        }
+
+        found = true;
+    }
+
+    Ok(if found {
+        Status::Vulnerable
+    } else {
+        Status::Unknown
+    })
+}
@@ -0,0 +1,456 @@
+//! Remediation plans
+//!
+//! The `fix-plan` format outputs a JSON array of the concrete actions which
+//! remediate the vulnerabilities found, so bots (e.g. Renovate, or in-house
+//! automation) can apply them without reimplementing how upgrades are
+//! worked out. Actions are derived from each vulnerability's recommended
+//! upgrade and how it can be applied given its dependents' requirements
+//! (see [`Resolution`]), falling back to the dependency graph when the
+//! requirements aren't known. Vulnerabilities in the same package version
+//! share an action when a single upgrade fixes them all.
+
+use rustsec::{
+    advisory,
+    cargo_lock::dependency::{graph::EdgeDirection, Dependency, Tree},
+    package::{self, Package},
+    vulnerability::{self, PatchedRelease, Resolution, Upgrade},
+    Report, Version, VersionReq, Vulnerability,
+};
+use serde::Serialize;
+use std::collections::BTreeMap as Map;
+
+/// Action remediating vulnerabilities in a package version
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "action")]
+pub enum Action {
+    /// Every dependent's requirement allows a fixed version, so updating the
+    /// lockfile suffices
+    Update {
+        /// Vulnerable package
+        package: package::Name,
+
+        /// Vulnerable version
+        from: Version,
+
+        /// Fixed version to update to
+        to: Version,
+
+        /// Command updating the lockfile
+        command: String,
+
+        /// Advisories the update fixes
+        advisories: Vec<advisory::Id>,
+
+        /// What to do, for humans
+        description: String,
+    },
+
+    /// Workspace members' requirements must be bumped to allow a fixed
+    /// version, then the lockfile updated
+    Bump {
+        /// Vulnerable package
+        package: package::Name,
+
+        /// Vulnerable version
+        from: Version,
+
+        /// Fixed version to upgrade to
+        to: Version,
+
+        /// Requirement to bump to, e.g. `^2`
+        requirement: String,
+
+        /// Workspace members whose requirements must be bumped
+        dependents: Vec<Dependent>,
+
+        /// Command updating the lockfile once the requirements are bumped
+        command: String,
+
+        /// Advisories the upgrade fixes
+        advisories: Vec<advisory::Id>,
+
+        /// What to do, for humans
+        description: String,
+    },
+
+    /// Dependents outside the workspace don't allow a fixed version, so they
+    /// must be upgraded (or the vulnerable package patched) first
+    UpgradeDependents {
+        /// Vulnerable package
+        package: package::Name,
+
+        /// Vulnerable version
+        from: Version,
+
+        /// Nearest fixed version
+        to: Version,
+
+        /// Dependents which must be upgraded
+        dependents: Vec<Dependent>,
+
+        /// Advisories the upgrade would fix
+        advisories: Vec<advisory::Id>,
+
+        /// What to do, for humans
+        description: String,
+    },
+
+    /// No fixed version is available (or known) to upgrade to
+    NoFix {
+        /// Vulnerable package
+        package: package::Name,
+
+        /// Vulnerable version
+        version: Version,
+
+        /// Advisories without a fix
+        advisories: Vec<advisory::Id>,
+
+        /// What to do, for humans
+        description: String,
+    },
+}
+
+/// Package depending on a vulnerable package
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Dependent {
+    /// Name of the dependent
+    pub name: package::Name,
+
+    /// Version of the dependent
+    pub version: Version,
+
+    /// Requirement the dependent declares for the vulnerable package (if
+    /// known)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<VersionReq>,
+}
+
+impl From<&vulnerability::Dependent> for Dependent {
+    fn from(dependent: &vulnerability::Dependent) -> Self {
+        Self {
+            name: dependent.name.clone(),
+            version: dependent.version.clone(),
+            requirement: Some(dependent.requirement.clone()),
+        }
+    }
+}
+
+/// How a single vulnerability can be fixed
+#[derive(Clone, Debug)]
+enum Fix {
+    /// Update the lockfile to the version
+    Update(Version),
+
+    /// Bump the given members' requirements to allow the version
+    Bump(Version, Vec<Dependent>),
+
+    /// Upgrade the given dependents to allow the version
+    UpgradeDependents(Version, Vec<Dependent>),
+}
+
+impl Fix {
+    /// Version the fix upgrades to
+    fn version(&self) -> &Version {
+        match self {
+            Fix::Update(version) | Fix::Bump(version, _) | Fix::UpgradeDependents(version, _) => {
+                version
+            }
+        }
+    }
+}
+
+/// Work out the actions remediating the report's vulnerabilities, ordered by
+/// package name and version
+pub fn plan(report: &Report, tree: Option<&Tree>) -> Vec<Action> {
+    let mut packages: Map<Dependency, Vec<&Vulnerability>> = Map::new();
+
+    for vulnerability in &report.vulnerabilities.list {
+        packages
+            .entry(Dependency::from(&vulnerability.package))
+            .or_default()
+            .push(vulnerability);
+    }
+
+    let mut actions = vec![];
+
+    for vulnerabilities in packages.values() {
+        let package = &vulnerabilities[0].package;
+        let spec = package_spec(package, tree);
+
+        let mut fixes = vec![];
+        let mut unfixed = vec![];
+
+        for vulnerability in vulnerabilities {
+            match fix(vulnerability, tree) {
+                Some(fix) => fixes.push((fix, *vulnerability)),
+                None => unfixed.push(vulnerability.advisory.id.clone()),
+            }
+        }
+
+        // Upgrade to the highest version first, as it's the likeliest to fix
+        // the other vulnerabilities too
+        fixes.sort_by(|(a, _), (b, _)| b.version().cmp(a.version()));
+
+        while !fixes.is_empty() {
+            let (fix, recommended) = fixes.remove(0);
+            let (fixed, rest): (Vec<_>, Vec<_>) =
+                fixes.into_iter().partition(|(_, vulnerability)| {
+                    !vulnerability.versions.is_vulnerable(fix.version())
+                });
+
+            let advisories = Some(recommended)
+                .into_iter()
+                .chain(fixed.into_iter().map(|(_, vulnerability)| vulnerability))
+                .map(|vulnerability| vulnerability.advisory.id.clone())
+                .collect();
+
+            actions.push(action(package, &spec, fix, advisories));
+            fixes = rest;
+        }
+
+        if !unfixed.is_empty() {
+            let description = format!(
+                "no fix available for {} {} ({}) - consider the advisories' workarounds, \
+                 or replacing the crate",
+                package.name,
+                package.version,
+                join(&unfixed)
+            );
+
+            actions.push(Action::NoFix {
+                package: package.name.clone(),
+                version: package.version.clone(),
+                advisories: unfixed,
+                description,
+            });
+        }
+    }
+
+    actions
+}
+
+/// How the vulnerability can be fixed (if a fixed version is known)
+fn fix(vulnerability: &Vulnerability, tree: Option<&Tree>) -> Option<Fix> {
+    if let Some(resolution) = &vulnerability.resolution {
+        let dependents = |dependents: &[vulnerability::Dependent]| {
+            dependents.iter().map(Dependent::from).collect()
+        };
+
+        return Some(match resolution {
+            Resolution::Update { version } => Fix::Update(version.clone()),
+            Resolution::Bump {
+                version,
+                dependents: bumped,
+            } => Fix::Bump(version.clone(), dependents(bumped)),
+            Resolution::Blocked {
+                version,
+                dependents: blocking,
+            } => Fix::UpgradeDependents(version.clone(), dependents(blocking)),
+        });
+    }
+
+    // Without a registry lookup, the upgrade is to the lowest versions of
+    // the advisory's ranges (unless the lookup found nothing to upgrade to)
+    let upgrade = match (&vulnerability.upgrade, &vulnerability.patched_release) {
+        (Some(upgrade), _) => upgrade.clone(),
+        (None, Some(PatchedRelease::Unreleased)) => return None,
+        (None, _) => Upgrade::from_ranges(&vulnerability.package.version, &vulnerability.versions)?,
+    };
+
+    if let Some(compatible) = upgrade.compatible {
+        return Some(Fix::Update(compatible));
+    }
+
+    // A major bump needs the dependents' requirements changed: the
+    // workspace's own ones if it depends on the package directly, otherwise
+    // those of the dependents which pull it in
+    let major = upgrade.major?;
+    let dependents = dependents(&vulnerability.package, tree);
+    let (members, others): (Vec<_>, Vec<_>) =
+        dependents.into_iter().partition(|(_, member)| *member);
+
+    Some(if others.is_empty() {
+        Fix::Bump(
+            major,
+            members
+                .into_iter()
+                .map(|(dependent, _)| dependent)
+                .collect(),
+        )
+    } else {
+        Fix::UpgradeDependents(
+            major,
+            others.into_iter().map(|(dependent, _)| dependent).collect(),
+        )
+    })
+}
+
+/// Packages which depend on the given one in the dependency graph (if
+/// known), and whether they're local (i.e. workspace members)
+fn dependents(package: &Package, tree: Option<&Tree>) -> Vec<(Dependent, bool)> {
+    let tree = match tree {
+        Some(tree) => tree,
+        None => return vec![],
+    };
+
+    let node = match tree.nodes().get(&Dependency::from(package)) {
+        Some(&node) => node,
+        None => return vec![],
+    };
+
+    let graph = tree.graph();
+    let mut dependents: Vec<_> = graph
+        .neighbors_directed(node, EdgeDirection::Incoming)
+        .map(|dependent| {
+            let dependent = &graph[dependent];
+            (
+                Dependent {
+                    name: dependent.name.clone(),
+                    version: dependent.version.clone(),
+                    requirement: None,
+                },
+                dependent.source.is_none(),
+            )
+        })
+        .collect();
+
+    dependents.sort_by(|(a, _), (b, _)| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    dependents
+}
+
+/// Action applying the fix to the package, fixing the given advisories
+fn action(package: &Package, spec: &str, fix: Fix, advisories: Vec<advisory::Id>) -> Action {
+    let name = &package.name;
+    let from = &package.version;
+
+    match fix {
+        Fix::Update(to) => {
+            let command = update_command(spec, &to);
+            let description = format!(
+                "update {} from {} to {} with `{}` ({})",
+                name,
+                from,
+                to,
+                command,
+                join(&advisories)
+            );
+
+            Action::Update {
+                package: name.clone(),
+                from: from.clone(),
+                to,
+                command,
+                advisories,
+                description,
+            }
+        }
+        Fix::Bump(to, dependents) => {
+            let requirement = caret_requirement(&to);
+            let command = update_command(spec, &to);
+            let members = if dependents.is_empty() {
+                "the workspace".to_owned()
+            } else {
+                dependents
+                    .iter()
+                    .map(|dependent| dependent.name.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let description = format!(
+                "bump the requirement on {} to {} in {}, then run `{}` ({})",
+                name,
+                requirement,
+                members,
+                command,
+                join(&advisories)
+            );
+
+            Action::Bump {
+                package: name.clone(),
+                from: from.clone(),
+                to,
+                requirement,
+                dependents,
+                command,
+                advisories,
+                description,
+            }
+        }
+        Fix::UpgradeDependents(to, dependents) => {
+            let blocking = dependents
+                .iter()
+                .map(|dependent| match &dependent.requirement {
+                    Some(requirement) => format!(
+                        "{} {} (requires {})",
+                        dependent.name, dependent.version, requirement
+                    ),
+                    None => format!("{} {}", dependent.name, dependent.version),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let description = format!(
+                "upgrade {} (or patch {}) to get {} {} ({})",
+                blocking,
+                name,
+                name,
+                to,
+                join(&advisories)
+            );
+
+            Action::UpgradeDependents {
+                package: name.clone(),
+                from: from.clone(),
+                to,
+                dependents,
+                advisories,
+                description,
+            }
+        }
+    }
+}
+
+/// Package ID specification for `cargo update -p`, which needs the version
+/// when the lockfile has several versions of the package
+fn package_spec(package: &Package, tree: Option<&Tree>) -> String {
+    let versions = tree
+        .map(|tree| {
+            tree.nodes()
+                .keys()
+                .filter(|dependency| dependency.name == package.name)
+                .count()
+        })
+        .unwrap_or(1);
+
+    if versions > 1 {
+        format!("{}@{}", package.name, package.version)
+    } else {
+        package.name.to_string()
+    }
+}
+
+/// Command updating the lockfile to the given version of a package
+fn update_command(spec: &str, to: &Version) -> String {
+    format!("cargo update -p {} --precise {}", spec, to)
+}
+
+/// Caret requirement allowing the version and its semver-compatible
+/// successors, e.g. `^2` for 2.0.0, `^0.3` for 0.3.0 or `^1.2.4` for 1.2.4
+fn caret_requirement(version: &Version) -> String {
+    if !version.pre.is_empty() || version.patch != 0 {
+        format!("^{}", version)
+    } else if version.major != 0 && version.minor == 0 {
+        format!("^{}", version.major)
+    } else {
+        format!("^{}.{}", version.major, version.minor)
+    }
+}
+
+/// Join advisory IDs for display
+fn join(advisories: &[advisory::Id]) -> String {
+    advisories
+        .iter()
+        .map(advisory::Id::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
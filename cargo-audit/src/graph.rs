@@ -0,0 +1,184 @@
+//! Graphviz (DOT) export of dependency paths to vulnerable packages
+//!
+//! By default the exported graph only contains vulnerable and warned
+//! packages, along with every package on a path from them up to the
+//! workspace's root packages. Vulnerable packages are filled red, warned
+//! ones yellow, and roots are drawn in bold.
+
+use rustsec::{
+    cargo_lock::dependency::{
+        graph::{EdgeDirection, NodeIndex},
+        Dependency, Tree,
+    },
+    error::{Error, ErrorKind},
+    fs, Report,
+};
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Advisories (or other findings) about a package in the graph
+#[derive(Debug, Default)]
+struct Findings {
+    /// Is the package vulnerable (as opposed to only warned about)?
+    vulnerable: bool,
+
+    /// Labels of the findings, e.g. advisory IDs
+    labels: Vec<String>,
+
+    /// Descriptions of the findings, e.g. advisory titles
+    descriptions: Vec<String>,
+}
+
+/// Write the dependency graph for the given report to a DOT file, including
+/// every package if `full` is set
+pub fn save(path: &Path, tree: &Tree, report: &Report, full: bool) -> Result<(), Error> {
+    let error = |e: io::Error| {
+        Error::new(
+            ErrorKind::Io,
+            &format!(
+                "couldn't write dependency graph to {}: {}",
+                path.display(),
+                e
+            ),
+        )
+    };
+
+    let mut file = BufWriter::new(fs::File::create(path).map_err(error)?);
+    write(&mut file, tree, report, full).map_err(error)?;
+    file.flush().map_err(error)
+}
+
+/// Write the dependency graph for the given report in DOT format, including
+/// every package if `full` is set
+pub fn write(w: &mut impl Write, tree: &Tree, report: &Report, full: bool) -> io::Result<()> {
+    let graph = tree.graph();
+    let findings = findings(tree, report);
+    let roots: Set<NodeIndex> = tree.roots().into_iter().collect();
+
+    let nodes: Set<NodeIndex> = if full {
+        graph.node_indices().collect()
+    } else {
+        // Every package depending on a vulnerable or warned one, directly or
+        // transitively
+        let mut nodes = Set::new();
+        let mut pending: Vec<NodeIndex> = findings.keys().copied().collect();
+
+        while let Some(node) = pending.pop() {
+            if nodes.insert(node) {
+                pending.extend(graph.neighbors_directed(node, EdgeDirection::Incoming));
+            }
+        }
+
+        nodes
+    };
+
+    writeln!(w, "digraph dependencies {{")?;
+    writeln!(w, "    node [shape=box];")?;
+
+    for &node in &nodes {
+        let package = &graph[node];
+        let mut label = vec![escape(&format!("{} {}", package.name, package.version))];
+        let mut attrs = vec![];
+        let mut styles = vec![];
+
+        if let Some(findings) = findings.get(&node) {
+            label.extend(findings.labels.iter().map(|label| escape(label)));
+
+            let tooltip: Vec<_> = findings
+                .descriptions
+                .iter()
+                .map(|description| escape(description))
+                .collect();
+
+            attrs.push(format!("tooltip=\"{}\"", tooltip.join("\\n")));
+            attrs.push(format!(
+                "fillcolor={}",
+                if findings.vulnerable { "red" } else { "yellow" }
+            ));
+            styles.push("filled");
+        }
+
+        if roots.contains(&node) {
+            styles.push("bold");
+        }
+
+        if !styles.is_empty() {
+            attrs.push(format!("style=\"{}\"", styles.join(",")));
+        }
+
+        attrs.insert(0, format!("label=\"{}\"", label.join("\\n")));
+        writeln!(w, "    n{} [{}];", node.index(), attrs.join(", "))?;
+    }
+
+    // Packages may depend on the same package more than once (e.g. as both a
+    // regular and a build dependency), which is drawn as a single edge
+    let edges: Set<(usize, usize)> = graph
+        .raw_edges()
+        .iter()
+        .filter(|edge| nodes.contains(&edge.source()) && nodes.contains(&edge.target()))
+        .map(|edge| (edge.source().index(), edge.target().index()))
+        .collect();
+
+    for (source, target) in edges {
+        writeln!(w, "    n{} -> n{};", source, target)?;
+    }
+
+    writeln!(w, "}}")
+}
+
+/// Findings in the report, by the graph node of the package they're about
+fn findings(tree: &Tree, report: &Report) -> Map<NodeIndex, Findings> {
+    let mut findings: Map<NodeIndex, Findings> = Map::new();
+
+    let vulnerabilities = report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vuln| (&vuln.package, true, Some(&vuln.advisory), None));
+
+    let warnings = report.warnings.values().flatten().map(|warning| {
+        (
+            &warning.package,
+            false,
+            warning.advisory.as_ref(),
+            Some(warning.kind.as_str()),
+        )
+    });
+
+    for (package, vulnerable, advisory, kind) in vulnerabilities.chain(warnings) {
+        let node = match tree.nodes().get(&Dependency::from(package)) {
+            Some(node) => *node,
+            None => continue,
+        };
+
+        let entry = findings.entry(node).or_default();
+        entry.vulnerable |= vulnerable;
+
+        match advisory {
+            Some(advisory) => {
+                entry.labels.push(advisory.id.to_string());
+                entry.descriptions.push(match kind {
+                    Some(kind) => format!("{} ({}): {}", advisory.id, kind, advisory.title),
+                    None => format!("{}: {}", advisory.id, advisory.title),
+                });
+            }
+            None => {
+                let kind = kind.unwrap_or("warning");
+                entry.labels.push(kind.to_owned());
+                entry.descriptions.push(kind.to_owned());
+            }
+        }
+    }
+
+    findings
+}
+
+/// Escape a string for use in a quoted DOT ID
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,315 @@
+//! Audits of a lockfile's states in its git history
+//!
+//! `cargo audit history` answers when a project was exposed to advisories:
+//! it reads the lockfile at each of a range of git revisions (or at each tag
+//! matching a pattern) straight from the repository, without touching the
+//! working tree, and audits every state against the current advisory
+//! database, which is loaded once for the whole walk. Comparing each state
+//! with the one before it shows where each advisory first appeared and
+//! where it was resolved.
+
+use crate::{auditor::Auditor, db_history, lockfile};
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    lockfile::Lockfile,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    path::Path,
+};
+
+/// Revisions of the history to audit
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Revisions {
+    /// Range of revisions, e.g. `v1.0..v1.8` (its start, then the commits of
+    /// its first-parent history which changed the lockfile), or a single
+    /// revision
+    Range(String),
+
+    /// Tags matching a glob pattern, e.g. `v*`, in the order they were
+    /// committed
+    Tags(String),
+}
+
+/// State of the lockfile at a revision, with the advisories it matched
+#[derive(Clone, Debug, Serialize)]
+pub struct Revision {
+    /// Name of the revision: the tag pointing at its commit (if any), or its
+    /// abbreviated commit ID
+    pub revision: String,
+
+    /// ID of the commit
+    pub commit: String,
+
+    /// Date of the commit (in UTC, `YYYY-MM-DD`)
+    pub date: String,
+
+    /// Why the revision wasn't audited (e.g. it has no lockfile), if it
+    /// wasn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+
+    /// Advisories matching the lockfile, with the packages they matched
+    /// (`name version`)
+    pub advisories: Map<advisory::Id, Vec<String>>,
+
+    /// Advisories which match, but didn't at the previous audited revision
+    pub introduced: Vec<advisory::Id>,
+
+    /// Advisories which matched at the previous audited revision, but don't
+    /// anymore
+    pub resolved: Vec<advisory::Id>,
+}
+
+impl Revision {
+    /// Was the revision audited?
+    pub fn is_audited(&self) -> bool {
+        self.skipped.is_none()
+    }
+}
+
+/// Period of the history in which an advisory matched the lockfile
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Exposure {
+    /// ID of the advisory
+    pub id: advisory::Id,
+
+    /// Revision the advisory first matched at
+    pub introduced: String,
+
+    /// Revision the advisory stopped matching at (if it did)
+    pub resolved: Option<String>,
+}
+
+/// Find the periods each advisory matched in, in the order they started
+pub fn exposures(revisions: &[Revision]) -> Vec<Exposure> {
+    let mut exposures: Vec<Exposure> = vec![];
+
+    for revision in revisions {
+        for id in &revision.resolved {
+            if let Some(exposure) = exposures
+                .iter_mut()
+                .find(|exposure| &exposure.id == id && exposure.resolved.is_none())
+            {
+                exposure.resolved = Some(revision.revision.clone());
+            }
+        }
+
+        for id in &revision.introduced {
+            exposures.push(Exposure {
+                id: id.clone(),
+                introduced: revision.revision.clone(),
+                resolved: None,
+            });
+        }
+    }
+
+    exposures
+}
+
+/// Audit the lockfile at the given path at each of the revisions of the
+/// git repository containing it
+pub fn audit(
+    auditor: &mut Auditor,
+    lockfile_path: &Path,
+    revisions: &Revisions,
+) -> Result<Vec<Revision>, Error> {
+    let (repo, path_in_repo) = lockfile::repository(lockfile_path)?;
+    let commits = match revisions {
+        Revisions::Range(range) => range_commits(&repo, range, &path_in_repo),
+        Revisions::Tags(pattern) => tag_commits(&repo, pattern),
+    }
+    .map_err(|e| repo_error(revisions, &e))?;
+
+    let tags = tag_names(&repo).map_err(|e| repo_error(revisions, &e))?;
+    let mut audited = vec![];
+    let mut previous: Option<Set<advisory::Id>> = None;
+
+    for (name, commit) in commits {
+        let commit_id = commit.id().to_string();
+
+        let revision_name = name
+            .or_else(|| tags.get(&commit.id()).cloned())
+            .unwrap_or_else(|| commit_id[..7].to_owned());
+
+        let mut revision = Revision {
+            revision: revision_name,
+            commit: commit_id,
+            date: db_history::commit_date(&commit),
+            skipped: None,
+            advisories: Map::new(),
+            introduced: vec![],
+            resolved: vec![],
+        };
+
+        let lockfile = commit
+            .tree()
+            .map_err(|e| e.message().to_owned())
+            .and_then(|tree| {
+                lockfile::read_tree(&repo, &tree, &path_in_repo, &revision.revision).map_err(|e| {
+                    match e.kind() {
+                        ErrorKind::NotFound => format!("no {}", path_in_repo.display()),
+                        _ => e.msg().to_owned(),
+                    }
+                })
+            })
+            .and_then(|toml| {
+                toml.parse::<Lockfile>()
+                    .map_err(|e| format!("couldn't parse {}: {}", path_in_repo.display(), e))
+            });
+
+        match lockfile {
+            Ok(lockfile) => {
+                let report = auditor.generate_report(&lockfile);
+
+                let vulnerabilities = report
+                    .vulnerabilities
+                    .list
+                    .iter()
+                    .map(|vuln| (&vuln.advisory, &vuln.package));
+
+                let warnings = report.warnings.values().flatten().filter_map(|warning| {
+                    warning
+                        .advisory
+                        .as_ref()
+                        .map(|advisory| (advisory, &warning.package))
+                });
+
+                for (advisory, package) in vulnerabilities.chain(warnings) {
+                    let packages = revision.advisories.entry(advisory.id.clone()).or_default();
+                    let package = format!("{} {}", package.name, package.version);
+
+                    if !packages.contains(&package) {
+                        packages.push(package);
+                    }
+                }
+
+                let matched: Set<_> = revision.advisories.keys().cloned().collect();
+                let before = previous.unwrap_or_default();
+                revision.introduced = matched.difference(&before).cloned().collect();
+                revision.resolved = before.difference(&matched).cloned().collect();
+                previous = Some(matched);
+            }
+            Err(reason) => revision.skipped = Some(reason),
+        }
+
+        audited.push(revision);
+    }
+
+    Ok(audited)
+}
+
+/// Commits of a range of revisions (`FROM..TO`), or a single revision: the
+/// start of the range (named as given), then the commits of the first-parent
+/// history up to its end which changed the lockfile, oldest first
+fn range_commits<'r>(
+    repo: &'r git2::Repository,
+    range: &str,
+    path_in_repo: &Path,
+) -> Result<Vec<(Option<String>, git2::Commit<'r>)>, git2::Error> {
+    let mut halves = range.splitn(2, "..");
+    let from = halves.next().unwrap_or_default();
+    let to = halves.next();
+
+    let start = repo.revparse_single(from)?.peel_to_commit()?;
+    let mut commits = vec![(Some(from.to_owned()), start.clone())];
+
+    let to = match to {
+        Some(to) => to,
+        None => return Ok(commits),
+    };
+
+    let end = repo.revparse_single(to)?.peel_to_commit()?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(end.id())?;
+    revwalk.hide(start.id())?;
+    revwalk.simplify_first_parent()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+
+    let mut last_lockfile = lockfile_id(&start, path_in_repo);
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let lockfile = lockfile_id(&commit, path_in_repo);
+
+        if lockfile != last_lockfile || commit.id() == end.id() {
+            let name = if commit.id() == end.id() {
+                Some(to.to_owned())
+            } else {
+                None
+            };
+
+            commits.push((name, commit));
+        }
+
+        last_lockfile = lockfile;
+    }
+
+    Ok(commits)
+}
+
+/// Commits of the tags matching a glob pattern, in the order they were
+/// committed (then by name)
+fn tag_commits<'r>(
+    repo: &'r git2::Repository,
+    pattern: &str,
+) -> Result<Vec<(Option<String>, git2::Commit<'r>)>, git2::Error> {
+    let mut commits = vec![];
+
+    for name in repo.tag_names(Some(pattern))?.iter().flatten() {
+        let commit = repo
+            .revparse_single(&format!("refs/tags/{}", name))?
+            .peel_to_commit()?;
+
+        commits.push((Some(name.to_owned()), commit));
+    }
+
+    commits.sort_by(|(a_name, a), (b_name, b)| {
+        (a.time().seconds(), a_name).cmp(&(b.time().seconds(), b_name))
+    });
+
+    Ok(commits)
+}
+
+/// Names of the tags pointing at each commit (the first, by name, if there
+/// are several)
+fn tag_names(repo: &git2::Repository) -> Result<Map<git2::Oid, String>, git2::Error> {
+    let mut tags = Map::new();
+
+    for name in repo.tag_names(None)?.iter().flatten() {
+        let commit = repo
+            .revparse_single(&format!("refs/tags/{}", name))
+            .and_then(|object| object.peel_to_commit());
+
+        if let Ok(commit) = commit {
+            tags.entry(commit.id()).or_insert_with(|| name.to_owned());
+        }
+    }
+
+    Ok(tags)
+}
+
+/// ID of the lockfile's blob at a commit (if it has the lockfile)
+fn lockfile_id(commit: &git2::Commit<'_>, path_in_repo: &Path) -> Option<git2::Oid> {
+    commit
+        .tree()
+        .ok()?
+        .get_path(path_in_repo)
+        .ok()
+        .map(|entry| entry.id())
+}
+
+/// Error for revisions which couldn't be resolved or walked
+fn repo_error(revisions: &Revisions, error: &git2::Error) -> Error {
+    let revisions = match revisions {
+        Revisions::Range(range) => format!("git revisions {:?}", range),
+        Revisions::Tags(pattern) => format!("git tags matching {:?}", pattern),
+    };
+
+    Error::new(
+        ErrorKind::Repo,
+        &format!("couldn't walk {}: {}", revisions, error.message()),
+    )
+}
@@ -0,0 +1,272 @@
+//! HTML reports
+//!
+//! The `html` format renders the findings as a standalone HTML page (with its
+//! styles inline, so it doesn't load anything), for archiving as a CI artifact
+//! or emailing: a summary of the audit, a table of the vulnerabilities
+//! color-coded by severity, another of the warnings, and the dependency tree
+//! of each affected package in an expandable `<details>` section.
+
+use crate::members;
+use rustsec::{
+    advisory,
+    cargo_lock::dependency::{graph::EdgeDirection, Dependency, Tree},
+    package::Package,
+    Report, VersionReq,
+};
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+/// Title of the report
+pub const TITLE: &str = "cargo audit report";
+
+/// Styles of the report
+const STYLE: &str = "\
+body { font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", Helvetica, Arial, sans-serif; margin: 2em; color: #24292f; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #d0d7de; padding: 0.4em 0.8em; text-align: left; vertical-align: top; }
+th { background: #f6f8fa; }
+code, pre { font-family: SFMono-Regular, Consolas, \"Liberation Mono\", Menlo, monospace; }
+pre { background: #f6f8fa; padding: 1em; overflow-x: auto; }
+details { margin-bottom: 0.5em; }
+.severity { font-weight: bold; }
+.critical { background: #ffebe9; } .critical .severity { color: #82071e; }
+.high { background: #fff1e5; } .high .severity { color: #bc4c00; }
+.medium { background: #fff8c5; } .medium .severity { color: #7d4e00; }
+.low, .none { background: #ddf4ff; } .low .severity, .none .severity { color: #0969da; }
+.clean { color: #1a7f37; }
+";
+
+/// Write the report as a standalone HTML page, with dependency trees if the
+/// dependency tree is known
+pub fn write(w: &mut impl Write, report: &Report, tree: Option<&Tree>) -> io::Result<()> {
+    let vulnerabilities = &report.vulnerabilities.list;
+    let warnings: Vec<_> = report.warnings.values().flatten().collect();
+
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html lang=\"en\">")?;
+    writeln!(w, "<head>")?;
+    writeln!(w, "<meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>{}</title>", TITLE)?;
+    writeln!(w, "<style>\n{}</style>", STYLE)?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(w, "<h1>{}</h1>", TITLE)?;
+
+    write_summary(w, report, warnings.len())?;
+
+    if vulnerabilities.is_empty() && warnings.is_empty() {
+        writeln!(
+            w,
+            "<p class=\"clean\">No known vulnerabilities or warnings found.</p>"
+        )?;
+    }
+
+    if !vulnerabilities.is_empty() {
+        writeln!(w, "<h2>Vulnerabilities</h2>")?;
+        writeln!(w, "<table>")?;
+        writeln!(
+            w,
+            "<tr><th>Advisory</th><th>Crate</th><th>Version</th><th>Severity</th>\
+             <th>Title</th><th>Patched</th><th>Dependency</th></tr>"
+        )?;
+
+        for vulnerability in vulnerabilities {
+            let (class, severity) = match (
+                &vulnerability.advisory.cvss,
+                vulnerability.effective_severity,
+            ) {
+                (Some(cvss), _) => (
+                    cvss.severity().to_string(),
+                    format!("{} ({:.1})", cvss.severity(), cvss.score().value()),
+                ),
+                (None, Some(severity)) => (severity.to_string(), format!("{} (assumed)", severity)),
+                (None, None) => ("unknown".to_owned(), "unknown".to_owned()),
+            };
+
+            writeln!(
+                w,
+                "<tr class=\"{}\"><td>{}</td><td><code>{}</code></td><td>{}</td>\
+                 <td class=\"severity\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                class,
+                link(&vulnerability.advisory),
+                escape(vulnerability.package.name.as_str()),
+                vulnerability.package.version,
+                severity,
+                escape(&vulnerability.advisory.title),
+                patched(vulnerability.versions.patched()),
+                members::kind(vulnerability.direct)
+            )?;
+        }
+
+        writeln!(w, "</table>")?;
+    }
+
+    if !warnings.is_empty() {
+        writeln!(w, "<h2>Warnings</h2>")?;
+        writeln!(w, "<table>")?;
+        writeln!(
+            w,
+            "<tr><th>Kind</th><th>Crate</th><th>Version</th><th>Advisory</th><th>Title</th></tr>"
+        )?;
+
+        for warning in &warnings {
+            let (advisory, title) = match &warning.advisory {
+                Some(advisory) => (link(advisory), escape(&advisory.title)),
+                None => ("-".to_owned(), "-".to_owned()),
+            };
+
+            writeln!(
+                w,
+                "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                warning.kind,
+                escape(warning.package.name.as_str()),
+                warning.package.version,
+                advisory,
+                title
+            )?;
+        }
+
+        writeln!(w, "</table>")?;
+    }
+
+    if let Some(tree) = tree {
+        let packages: Vec<&Package> = vulnerabilities
+            .iter()
+            .map(|vulnerability| &vulnerability.package)
+            .chain(warnings.iter().map(|warning| &warning.package))
+            .collect();
+
+        if !packages.is_empty() {
+            writeln!(w, "<h2>Dependency trees</h2>")?;
+        }
+
+        // Only show the tree once per package
+        let mut displayed = BTreeSet::new();
+
+        for package in packages {
+            if displayed.insert(Dependency::from(package)) {
+                write_tree(w, tree, package)?;
+            }
+        }
+    }
+
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")
+}
+
+/// Write a table summarizing the audit: what was audited, and what was found
+fn write_summary(w: &mut impl Write, report: &Report, warning_count: usize) -> io::Result<()> {
+    let mut rows = vec![];
+
+    if let Some(path) = &report.lockfile.path {
+        rows.push((
+            "Lockfile",
+            format!("<code>{}</code>", escape(&path.display().to_string())),
+        ));
+    }
+
+    rows.push((
+        "Dependencies",
+        report.lockfile.dependency_count().to_string(),
+    ));
+    rows.push(("Advisories", report.database.advisory_count.to_string()));
+
+    if let Some(commit) = &report.database.last_commit {
+        rows.push((
+            "Database commit",
+            format!("<code>{}</code>", escape(commit)),
+        ));
+    }
+
+    rows.push((
+        "Vulnerabilities",
+        report.vulnerabilities.list.len().to_string(),
+    ));
+    rows.push(("Warnings", warning_count.to_string()));
+
+    if let Some(outcome) = report.outcome {
+        rows.push(("Outcome", outcome.to_string()));
+    }
+
+    writeln!(w, "<table>")?;
+
+    for (name, value) in rows {
+        writeln!(w, "<tr><th>{}</th><td>{}</td></tr>", name, value)?;
+    }
+
+    writeln!(w, "</table>")
+}
+
+/// Write the inverse dependency tree of the given package, collapsed
+fn write_tree(w: &mut impl Write, tree: &Tree, package: &Package) -> io::Result<()> {
+    let node = match tree.nodes().get(&Dependency::from(package)) {
+        Some(&node) => node,
+        None => return Ok(()),
+    };
+
+    let mut rendered = vec![];
+    tree.render(&mut rendered, node, EdgeDirection::Incoming)?;
+
+    writeln!(w, "<details>")?;
+    writeln!(
+        w,
+        "<summary><code>{} {}</code></summary>",
+        escape(package.name.as_str()),
+        package.version
+    )?;
+    writeln!(
+        w,
+        "<pre>{}</pre>",
+        escape(&String::from_utf8_lossy(&rendered))
+    )?;
+    writeln!(w, "</details>")
+}
+
+/// Patched versions, as a table cell
+fn patched(reqs: &[VersionReq]) -> String {
+    if reqs.is_empty() {
+        return "no patched versions".to_owned();
+    }
+
+    reqs.iter()
+        .map(|req| format!("<code>{}</code>", escape(&req.to_string())))
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+/// Link to the advisory (or its URL, if its ID has no page of its own), as a
+/// table cell
+fn link(advisory: &advisory::Metadata) -> String {
+    match advisory
+        .id
+        .url()
+        .or_else(|| advisory.url.as_ref().map(ToString::to_string))
+    {
+        Some(url) => format!(
+            "<a href=\"{}\">{}</a>",
+            escape(&url),
+            escape(advisory.id.as_str())
+        ),
+        None => escape(advisory.id.as_str()),
+    }
+}
+
+/// Escape text for HTML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,754 @@
+//! Auditing the Rust binaries in OCI container images
+//!
+//! `cargo audit image` reads an image from a tarball written by `docker save`
+//! (or an OCI image layout archived with `tar`), or pulls it from a registry,
+//! and walks the filesystem its layers add up to for executables with audit
+//! data embedded by [cargo-auditable]. Each of them is audited like a
+//! lockfile. Files deleted by later layers (i.e. with whiteouts) aren't
+//! audited, and executables without audit data are counted as skipped.
+//!
+//! Images are pulled anonymously, using the bearer token a registry's token
+//! service hands out for public repositories when asked for one.
+//!
+//! [cargo-auditable]: https://github.com/rust-secure-code/cargo-auditable
+
+use crate::{
+    auditor::Auditor,
+    installed::{self, Binary},
+    retry::{Failure, Retry},
+};
+use reqwest::{blocking::Client, header, StatusCode};
+use rustsec::{
+    error::{Error, ErrorKind},
+    report::DatabaseInfo,
+    repository::tarball,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap as Map,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+/// Platform whose image is audited when a reference is to a multi-platform
+/// image
+pub const DEFAULT_PLATFORM: &str = "linux/amd64";
+
+/// Registry of images whose references don't name one (i.e. Docker Hub)
+pub const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Host of Docker Hub's registry API
+const DOCKER_HUB_API: &str = "registry-1.docker.io";
+
+/// Media types of image manifests accepted from registries
+const MANIFEST_TYPES: &[&str] = &[
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+/// Prefix of the names of whiteout files, which delete the file (or
+/// directory) of the same name from lower layers
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// Name of opaque whiteout files, which delete the contents of their
+/// directory from lower layers
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// How long to wait for the registry to respond
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Report of the audits of an image's binaries
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `image`, to tell these reports apart from lockfile audits
+    pub mode: &'static str,
+
+    /// Image which was audited: the path of its tarball, or its reference
+    pub image: String,
+
+    /// Digest of the image's manifest (if pulled from a registry)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+
+    /// Information about the advisory database
+    pub database: DatabaseInfo,
+
+    /// Audited binaries, by path
+    pub binaries: Vec<Binary>,
+
+    /// Number of executables without audit data, which were skipped
+    pub skipped: usize,
+}
+
+impl Report {
+    /// Number of vulnerabilities found in all of the binaries
+    pub fn vulnerability_count(&self) -> usize {
+        self.binaries
+            .iter()
+            .map(|binary| binary.report.vulnerabilities.count)
+            .sum()
+    }
+
+    /// Number of binaries with vulnerabilities
+    pub fn vulnerable_count(&self) -> usize {
+        self.binaries
+            .iter()
+            .filter(|binary| binary.report.vulnerabilities.found)
+            .count()
+    }
+}
+
+/// Reference to an image in a registry, e.g. `ghcr.io/owner/app:1.0` or
+/// `alpine@sha256:...`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reference {
+    /// Registry host (and port, if any), e.g. `ghcr.io`
+    pub registry: String,
+
+    /// Repository in the registry, e.g. `library/alpine`
+    pub repository: String,
+
+    /// Tag or digest of the image (`latest` if neither is given)
+    pub tag: String,
+}
+
+impl Reference {
+    /// Base URL of the registry's API for the repository
+    fn api_url(&self) -> String {
+        let host = if self.registry == DEFAULT_REGISTRY {
+            DOCKER_HUB_API
+        } else {
+            &self.registry
+        };
+
+        format!("https://{}/v2/{}", host, self.repository)
+    }
+}
+
+impl FromStr for Reference {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::new(ErrorKind::Parse, &format!("invalid image reference: {}", s));
+
+        let (name, tag) = match s.find('@') {
+            Some(at) => (&s[..at], s[at + 1..].to_owned()),
+            None => match s.rfind(':') {
+                // A colon after the last slash separates the tag (rather than
+                // a registry's port)
+                Some(colon) if !s[colon..].contains('/') => {
+                    (&s[..colon], s[colon + 1..].to_owned())
+                }
+                _ => (s, "latest".to_owned()),
+            },
+        };
+
+        // The first component is a registry if it looks like a host name
+        let (registry, repository) = match name.find('/') {
+            Some(slash)
+                if name[..slash].contains('.')
+                    || name[..slash].contains(':')
+                    || &name[..slash] == "localhost" =>
+            {
+                (name[..slash].to_owned(), name[slash + 1..].to_owned())
+            }
+            _ => (DEFAULT_REGISTRY.to_owned(), name.to_owned()),
+        };
+
+        let repository = if registry == DEFAULT_REGISTRY && !repository.contains('/') {
+            format!("library/{}", repository)
+        } else {
+            repository
+        };
+
+        if repository.is_empty()
+            || tag.is_empty()
+            || repository.split('/').any(|part| {
+                part.is_empty()
+                    || !part
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c))
+            })
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            registry,
+            repository,
+            tag,
+        })
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.tag.contains(':') { '@' } else { ':' };
+        write!(
+            f,
+            "{}/{}{}{}",
+            self.registry, self.repository, separator, self.tag
+        )
+    }
+}
+
+/// Image manifest (or index of the manifests of a multi-platform image)
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    /// Media type of the manifest (if given)
+    #[serde(default)]
+    media_type: Option<String>,
+
+    /// Image layers, from the bottom up
+    #[serde(default)]
+    layers: Vec<Descriptor>,
+
+    /// Manifests of each platform's image (in indexes)
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+}
+
+impl Manifest {
+    /// Is this an index of the manifests of a multi-platform image?
+    fn is_index(&self) -> bool {
+        !self.manifests.is_empty()
+            || matches!(&self.media_type, Some(media_type) if media_type.contains("index")
+                || media_type.contains("manifest.list"))
+    }
+
+    /// Descriptor of the manifest for the given platform (`os/arch`, or
+    /// `os/arch/variant`), in an index
+    fn platform_manifest(&self, platform: &str) -> Option<&Descriptor> {
+        self.manifests.iter().find(|descriptor| {
+            descriptor
+                .platform
+                .as_ref()
+                .map(|p| p.matches(platform))
+                .unwrap_or(false)
+        })
+    }
+}
+
+/// Reference to content (e.g. a manifest or layer) by digest
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    /// Media type of the content
+    #[serde(default)]
+    media_type: Option<String>,
+
+    /// Digest of the content, e.g. `sha256:...`
+    digest: String,
+
+    /// Platform of the image (in indexes)
+    #[serde(default)]
+    platform: Option<Platform>,
+}
+
+/// Platform of an image in an index
+#[derive(Debug, Deserialize)]
+struct Platform {
+    /// Operating system, e.g. `linux`
+    os: String,
+
+    /// CPU architecture, e.g. `amd64`
+    architecture: String,
+
+    /// Variant of the CPU architecture, e.g. `v8`
+    #[serde(default)]
+    variant: Option<String>,
+}
+
+impl Platform {
+    /// Is this the given platform (`os/arch`, or `os/arch/variant`)?
+    fn matches(&self, platform: &str) -> bool {
+        let mut parts = platform.split('/');
+
+        parts.next() == Some(&self.os)
+            && parts.next() == Some(&self.architecture)
+            && match parts.next() {
+                Some(variant) => self.variant.as_deref() == Some(variant),
+                None => true,
+            }
+    }
+}
+
+/// Entry in the `manifest.json` of a `docker save` tarball
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SavedImage {
+    /// Paths of the image's layers in the tarball, from the bottom up
+    layers: Vec<String>,
+}
+
+/// Audit the image at the given path (a `docker save` tarball or an archived
+/// OCI image layout) or, if there's no such file, pull the image with the
+/// given reference for the given platform (`os/arch`) and audit it
+pub fn audit(
+    auditor: &mut Auditor,
+    image: &str,
+    platform: &str,
+    offline: bool,
+    retry: Retry,
+) -> Result<Report, Error> {
+    let path = Path::new(image);
+
+    let (layers, digest) = if path.is_file() {
+        (read_archive(path, platform)?, None)
+    } else if offline {
+        return Err(Error::new(
+            ErrorKind::Io,
+            &format!(
+                "no image tarball at {}, and images can't be pulled offline",
+                image
+            ),
+        ));
+    } else {
+        pull(&image.parse()?, platform, retry)?
+    };
+
+    let mut filesystem = Filesystem::default();
+
+    for layer in &layers {
+        filesystem.apply(layer)?;
+    }
+
+    let mut report = Report {
+        mode: "image",
+        image: image.to_owned(),
+        digest,
+        database: DatabaseInfo::new(auditor.database()),
+        binaries: vec![],
+        skipped: 0,
+    };
+
+    for (path, audit_data) in filesystem.executables {
+        match audit_data {
+            Some(compressed) => {
+                let path = PathBuf::from(format!("/{}", path));
+                report
+                    .binaries
+                    .push(installed::audit_embedded(auditor, &path, &compressed)?);
+            }
+            None => report.skipped += 1,
+        }
+    }
+
+    Ok(report)
+}
+
+/// Executables in the filesystem an image's layers add up to
+#[derive(Debug, Default)]
+struct Filesystem {
+    /// Executables by path (relative to the root), with the (compressed)
+    /// audit data embedded in them, if they have any
+    executables: Map<String, Option<Vec<u8>>>,
+}
+
+impl Filesystem {
+    /// Apply a layer (a tar archive, optionally gzip-compressed) on top of
+    /// the filesystem
+    fn apply(&mut self, layer: &[u8]) -> Result<(), Error> {
+        if layer.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            return Err(Error::new(
+                ErrorKind::Parse,
+                &"zstd-compressed image layers aren't supported",
+            ));
+        }
+
+        let tar = tarball::decompress(layer)?;
+        let entries = tarball::entries(&tar)?;
+
+        // Whiteouts only delete files from lower layers, so they're applied
+        // before the layer's own files
+        for entry in &entries {
+            let path = normalize(&entry.path);
+            let (dir, name) = match path.rfind('/') {
+                Some(slash) => (&path[..slash + 1], &path[slash + 1..]),
+                None => ("", path.as_str()),
+            };
+
+            if name == OPAQUE_WHITEOUT {
+                self.remove_under(dir);
+            } else if let Some(deleted) = name.strip_prefix(WHITEOUT_PREFIX) {
+                let deleted = format!("{}{}", dir, deleted);
+                self.executables.remove(&deleted);
+                self.remove_under(&format!("{}/", deleted));
+            }
+        }
+
+        for entry in &entries {
+            let path = normalize(&entry.path);
+
+            if path
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .starts_with(WHITEOUT_PREFIX)
+            {
+                continue;
+            }
+
+            if entry.is_file() && entry.mode & 0o111 != 0 {
+                let audit_data = installed::embedded_audit_data(entry.data).map(<[u8]>::to_vec);
+                self.executables.insert(path, audit_data);
+            } else {
+                // Anything else replaces an executable at the same path
+                self.executables.remove(&path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove the executables under the given directory (ending with `/`, or
+    /// empty for the root)
+    fn remove_under(&mut self, dir: &str) {
+        self.executables.retain(|path, _| !path.starts_with(dir));
+    }
+}
+
+/// Path of a tar entry relative to the filesystem's root, without a leading
+/// `./` or `/` or a trailing `/`
+fn normalize(path: &str) -> String {
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+/// Read the layers of the image in a `docker save` tarball (listed in its
+/// `manifest.json`) or an archived OCI image layout (listed in the manifest
+/// its `index.json` points to, for the given platform if there are several)
+fn read_archive(path: &Path, platform: &str) -> Result<Vec<Vec<u8>>, Error> {
+    let archive = fs::read(path).map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't read {}: {}", path.display(), e),
+        )
+    })?;
+
+    let tar = tarball::decompress(&archive)?;
+    let files: Map<String, &[u8]> = tarball::entries(&tar)?
+        .into_iter()
+        .filter(|entry| entry.is_file())
+        .map(|entry| (normalize(&entry.path), entry.data))
+        .collect();
+
+    let file = |name: &str| -> Result<&[u8], Error> {
+        files.get(name).copied().ok_or_else(|| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!(
+                    "{} isn't an image tarball: it has no {}",
+                    path.display(),
+                    name
+                ),
+            )
+        })
+    };
+
+    let layer_paths: Vec<String> = if files.contains_key("manifest.json") {
+        let saved: Vec<SavedImage> = parse_json(file("manifest.json")?, "manifest.json")?;
+
+        match saved.into_iter().next() {
+            Some(image) => image.layers,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Parse,
+                    &format!("{} has no images", path.display()),
+                ))
+            }
+        }
+    } else {
+        let mut manifest: Manifest = parse_json(file("index.json")?, "index.json")?;
+
+        while manifest.is_index() {
+            let descriptor = manifest
+                .platform_manifest(platform)
+                .or_else(|| manifest.manifests.first())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Parse,
+                        &format!("{} has no images", path.display()),
+                    )
+                })?;
+
+            let blob = blob_path(&descriptor.digest);
+            manifest = parse_json(file(&blob)?, &blob)?;
+        }
+
+        manifest
+            .layers
+            .iter()
+            .map(|layer| blob_path(&layer.digest))
+            .collect()
+    };
+
+    layer_paths
+        .iter()
+        .map(|layer| file(&normalize(layer)).map(<[u8]>::to_vec))
+        .collect()
+}
+
+/// Path of a blob in an OCI image layout, e.g. `blobs/sha256/...`
+fn blob_path(digest: &str) -> String {
+    format!("blobs/{}", digest.replacen(':', "/", 1))
+}
+
+/// Pull the layers of the image with the given reference (for the given
+/// platform, if it's a multi-platform image) from its registry, returning
+/// them along with the digest of the image's manifest (if the registry says)
+fn pull(
+    reference: &Reference,
+    platform: &str,
+    retry: Retry,
+) -> Result<(Vec<Vec<u8>>, Option<String>), Error> {
+    let mut registry = Registry::new(reference, retry)?;
+    let (mut body, mut digest) = registry.get_manifest(&reference.tag)?;
+    let mut manifest: Manifest = parse_json(&body, "image manifest")?;
+
+    if manifest.is_index() {
+        let descriptor = manifest.platform_manifest(platform).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                &format!("{} has no image for {}", reference, platform),
+            )
+        })?;
+
+        tracing::debug!(
+            "pulling the {} image of {}: {} ({})",
+            platform,
+            reference,
+            descriptor.digest,
+            descriptor.media_type.as_deref().unwrap_or("unknown type")
+        );
+
+        let platform_digest = descriptor.digest.clone();
+        let (platform_body, _) = registry.get_manifest(&platform_digest)?;
+        body = platform_body;
+        digest = Some(platform_digest);
+        manifest = parse_json(&body, "image manifest")?;
+    }
+
+    let layers = manifest
+        .layers
+        .iter()
+        .map(|layer| registry.get(&format!("blobs/{}", layer.digest), None))
+        .map(|response| response.map(|(body, _)| body))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((layers, digest))
+}
+
+/// Client for a repository in a registry
+struct Registry {
+    /// HTTP client
+    client: Client,
+
+    /// Base URL of the repository's API
+    url: String,
+
+    /// Bearer token from the registry's token service (once it asked for one)
+    token: Option<String>,
+
+    /// Policy for retrying requests
+    retry: Retry,
+}
+
+impl Registry {
+    /// Create a client for the repository of the given reference
+    fn new(reference: &Reference, retry: Retry) -> Result<Self, Error> {
+        let client = Client::builder()
+            .user_agent(concat!("cargo-audit/", env!("CARGO_PKG_VERSION")))
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Io,
+                    &format!("couldn't create HTTP client: {}", e),
+                )
+            })?;
+
+        Ok(Self {
+            client,
+            url: reference.api_url(),
+            token: None,
+            retry,
+        })
+    }
+
+    /// Get the manifest with the given tag or digest, and its digest (if the
+    /// registry says)
+    fn get_manifest(&mut self, tag: &str) -> Result<(Vec<u8>, Option<String>), Error> {
+        let accept = MANIFEST_TYPES.join(", ");
+        self.get(&format!("manifests/{}", tag), Some(&accept))
+    }
+
+    /// Get the content at the given path under the repository's API,
+    /// authenticating with a bearer token if the registry asks for one.
+    /// Returns the content, and its digest (if the registry says).
+    fn get(
+        &mut self,
+        path: &str,
+        accept: Option<&str>,
+    ) -> Result<(Vec<u8>, Option<String>), Error> {
+        let url = format!("{}/{}", self.url, path);
+
+        loop {
+            let client = &self.client;
+            let token = self.token.as_deref();
+
+            let response = self.retry.run(&format!("downloading {}", url), || {
+                let mut request = client.get(&url);
+
+                if let Some(accept) = accept {
+                    request = request.header(header::ACCEPT, accept);
+                }
+
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+
+                let response = request.send().map_err(Failure::http)?;
+                let status = response.status();
+
+                if status.is_success() || status == StatusCode::UNAUTHORIZED {
+                    Ok(response)
+                } else {
+                    Err(Failure::status(status))
+                }
+            });
+
+            let response = response
+                .map_err(|e| Error::new(e.kind(), &format!("couldn't download {}: {}", url, e)))?;
+
+            if response.status() == StatusCode::UNAUTHORIZED {
+                if self.token.is_some() {
+                    return Err(Error::new(
+                        ErrorKind::Io,
+                        &format!("couldn't download {}: the registry refused access", url),
+                    ));
+                }
+
+                let challenge = response
+                    .headers()
+                    .get(header::WWW_AUTHENTICATE)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_owned();
+
+                self.token = Some(self.authenticate(&challenge)?);
+                continue;
+            }
+
+            let digest = response
+                .headers()
+                .get("docker-content-digest")
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned);
+
+            let body = response.bytes().map_err(|e| {
+                Error::new(ErrorKind::Io, &format!("couldn't download {}: {}", url, e))
+            })?;
+
+            return Ok((body.to_vec(), digest));
+        }
+    }
+
+    /// Get a bearer token from the token service named in the given
+    /// `WWW-Authenticate` challenge
+    fn authenticate(&self, challenge: &str) -> Result<String, Error> {
+        let params = match challenge.strip_prefix("Bearer ") {
+            Some(params) => parse_challenge(params),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Io,
+                    &format!(
+                        "the registry at {} requires credentials, which aren't supported",
+                        self.url
+                    ),
+                ))
+            }
+        };
+
+        let realm = params.get("realm").ok_or_else(|| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("the registry at {} didn't name a token service", self.url),
+            )
+        })?;
+
+        let query: Vec<_> = ["service", "scope"]
+            .iter()
+            .filter_map(|key| params.get(*key).map(|value| (*key, value.as_str())))
+            .collect();
+
+        #[derive(Deserialize)]
+        struct Token {
+            #[serde(default)]
+            token: Option<String>,
+            #[serde(default)]
+            access_token: Option<String>,
+        }
+
+        let client = &self.client;
+        let json = self.retry.run("getting a registry token", || {
+            Ok(client
+                .get(realm.as_str())
+                .query(&query)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.bytes())?)
+        })?;
+
+        let token: Token = parse_json(&json, "registry token")?;
+
+        token.token.or(token.access_token).ok_or_else(|| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("the token service at {} didn't hand out a token", realm),
+            )
+        })
+    }
+}
+
+/// Parse the parameters of a `WWW-Authenticate` challenge, e.g.
+/// `realm="https://auth.docker.io/token",service="registry.docker.io"`
+fn parse_challenge(params: &str) -> Map<String, String> {
+    let mut parsed = Map::new();
+    let mut rest = params.trim();
+
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim().to_lowercase();
+        rest = &rest[equals + 1..];
+
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let value = &quoted[..end];
+            rest = quoted.get(end + 1..).unwrap_or_default();
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+
+        parsed.insert(key, value.trim().to_owned());
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+
+    parsed
+}
+
+/// Parse a JSON document (named `what` in errors) from an image
+fn parse_json<T: serde::de::DeserializeOwned>(json: &[u8], what: &str) -> Result<T, Error> {
+    serde_json::from_slice(json)
+        .map_err(|e| Error::new(ErrorKind::Parse, &format!("couldn't parse {}: {}", what, e)))
+}
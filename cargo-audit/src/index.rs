@@ -0,0 +1,592 @@
+//! Published releases of crates, from the crates.io sparse index
+//!
+//! This is the registry client shared by every registry-dependent feature
+//! (yanked checks and patched release lookups). Each crate's releases are
+//! fetched with a single request, several crates at once, and cached on disk
+//! (including crates which aren't published) until their TTL expires, after
+//! which they're revalidated with the index's `ETag`. In offline mode only
+//! cached releases are served.
+
+use crate::{
+    cache::Cache,
+    cargo_config::{Replacement, ReplacementIndex, Token},
+    config::{AuditConfig, RegistryConfig},
+    retry::Retry,
+};
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    fs, package,
+    vulnerability::PatchedRelease,
+    Version,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap as Map,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// URL of the crates.io sparse index
+pub const DEFAULT_URL: &str = "https://index.crates.io/";
+
+/// Name of the file releases are cached in (located in the report cache directory)
+pub const CACHE_FILE: &str = "index.json";
+
+/// How long looked up releases are cached for by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How many crates' releases are fetched at once by default
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// User agent requests to the index are made with
+const USER_AGENT: &str = concat!("cargo-audit/", env!("CARGO_PKG_VERSION"));
+
+/// How long to wait for the index to respond
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Published release of a crate
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Release {
+    /// Version of the release
+    pub version: Version,
+
+    /// Has the release been yanked?
+    pub yanked: bool,
+
+    /// Date the release was published (`YYYY-MM-DD`, if the index records it)
+    pub date: Option<String>,
+
+    /// SHA-256 checksum of the release's `.crate` file (hex-encoded)
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+/// Counters of a client's lookups, logged in verbose mode
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Requests made to the index
+    pub requests: usize,
+
+    /// Crates whose releases were served from the cache without a request
+    pub cache_hits: usize,
+
+    /// Requests answered with `304 Not Modified`, which revalidated the
+    /// cached releases
+    pub revalidated: usize,
+}
+
+/// Client for the sparse index.
+///
+/// Clones share their cache and counters, so a single client can be handed
+/// to every feature which looks up releases.
+#[derive(Clone, Debug)]
+pub struct Client {
+    /// URL of the sparse index
+    url: String,
+
+    /// Path to the file releases are cached in
+    cache_path: PathBuf,
+
+    /// How long looked up releases are cached for
+    ttl: Duration,
+
+    /// Policy for retrying failed requests
+    retry: Retry,
+
+    /// Token to authenticate to the index with (if it requires one)
+    token: Option<Token>,
+
+    /// Log the URL of each lookup at the info level, rather than debug
+    debug: bool,
+
+    /// Only serve cached releases, without making any requests
+    offline: bool,
+
+    /// How many crates' releases are fetched at once
+    concurrency: usize,
+
+    /// Cached releases (loaded on first use), by crate name
+    cache: Arc<Mutex<Option<Map<String, CacheEntry>>>>,
+
+    /// Counters of the lookups made so far
+    stats: Arc<Mutex<Stats>>,
+}
+
+impl Client {
+    /// Create a client for the sparse index at the given URL
+    pub fn new(url: impl Into<String>, cache_path: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            url: url.into(),
+            cache_path: cache_path.into(),
+            ttl,
+            retry: Retry::default(),
+            token: None,
+            debug: false,
+            offline: false,
+            concurrency: DEFAULT_CONCURRENCY,
+            cache: Arc::default(),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Create the client configured in `audit.toml`, caching releases in
+    /// the given report cache
+    pub fn from_config(config: &RegistryConfig, cache: &Cache) -> Self {
+        Self::new(
+            config.url.as_deref().unwrap_or(DEFAULT_URL),
+            cache.dir().join(CACHE_FILE),
+            config
+                .ttl_hours
+                .map(|hours| Duration::from_secs(hours * 60 * 60))
+                .unwrap_or(DEFAULT_TTL),
+        )
+        .concurrency(config.concurrency.unwrap_or(DEFAULT_CONCURRENCY))
+    }
+
+    /// Create the client for looking up releases of crates.io crates, with
+    /// the configured retries and caching: in the sparse index configured in
+    /// `audit.toml`, or the one replacing crates.io in cargo's configuration,
+    /// or crates.io's. In offline mode, it only serves cached releases.
+    ///
+    /// Returns `None` if crates.io is replaced with a git index or a local
+    /// source (and no URL is configured), which have no sparse index to look
+    /// releases up in.
+    pub fn for_crates_io(config: &AuditConfig, replacement: Option<Replacement>) -> Option<Self> {
+        let cache = Cache::from_config(&config.cache);
+        let client = match replacement {
+            Some(_) if config.registry.url.is_some() => Self::from_config(&config.registry, &cache),
+            None => Self::from_config(&config.registry, &cache),
+            Some(Replacement {
+                index: ReplacementIndex::Sparse(url),
+                token,
+                ..
+            }) => {
+                let mut registry = config.registry.clone();
+                registry.url = Some(url);
+                Self::from_config(&registry, &cache).token(token)
+            }
+            Some(replacement) => {
+                tracing::debug!(
+                    "not looking up releases, as crates.io is replaced with {} (set `registry.url` to a sparse index to look them up)",
+                    replacement.index
+                );
+                return None;
+            }
+        };
+
+        Some(
+            client
+                .retry(Retry::from_config(&config.net))
+                .debug(config.registry.debug)
+                .offline(config.net.offline),
+        )
+    }
+
+    /// Set the policy for retrying failed requests
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the token to authenticate to the index with
+    pub fn token(mut self, token: Option<Token>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Log the URL of each lookup at the info level (e.g. for
+    /// `--registry-debug`), rather than the debug level
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Only serve cached releases (however old), without making any
+    /// requests, e.g. in `--offline` mode
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Set how many crates' releases are fetched at once (at least one)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// URL of the sparse index
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Path to the file releases are cached in
+    pub fn cache_path(&self) -> &Path {
+        &self.cache_path
+    }
+
+    /// Counters of the lookups made so far (by this client and its clones)
+    pub fn stats(&self) -> Stats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// Get the releases of the given crates (no releases for crates which
+    /// aren't published).
+    ///
+    /// Releases are served from the cache where possible, and the rest are
+    /// fetched (or revalidated, if they're cached but expired). Crates whose
+    /// releases couldn't be fetched are omitted, and the first error is
+    /// returned along with whatever releases are known.
+    pub fn releases(
+        &self,
+        names: &[&package::Name],
+    ) -> (Map<package::Name, Vec<Release>>, Option<Error>) {
+        let mut guard = self.cache.lock().unwrap();
+        let cache = guard.get_or_insert_with(|| self.load_cache());
+        let now = unix_time();
+        let mut stale = vec![];
+
+        for name in names {
+            // Releases cached before checksums were recorded are refetched
+            let fresh = match cache.get(name.as_str()) {
+                Some(entry) => {
+                    self.offline
+                        || (now.saturating_sub(entry.fetched) < self.ttl.as_secs()
+                            && entry.has_checksums())
+                }
+                None => false,
+            };
+
+            if fresh {
+                self.stats.lock().unwrap().cache_hits += 1;
+            } else if !self.offline && !stale.iter().any(|(stale, _)| stale == *name) {
+                let etag = cache
+                    .get(name.as_str())
+                    .and_then(|entry| entry.etag.clone());
+                stale.push(((*name).clone(), etag));
+            }
+        }
+
+        let fetched = !stale.is_empty();
+        let mut results = self.fetch_all(stale);
+        let mut error = None;
+
+        for name in names {
+            match results.remove(*name) {
+                Some(Ok(Fetched::Modified { releases, etag })) => {
+                    let entry = CacheEntry {
+                        fetched: now,
+                        etag,
+                        releases,
+                    };
+                    cache.insert(name.as_str().to_owned(), entry);
+                }
+                Some(Ok(Fetched::NotModified)) => {
+                    if let Some(entry) = cache.get_mut(name.as_str()) {
+                        entry.fetched = now;
+                    }
+                }
+                Some(Err(e)) => {
+                    // Don't fall back to stale cached releases: a patched
+                    // release may have been published since
+                    cache.remove(name.as_str());
+                    error.get_or_insert(e);
+                }
+                None => continue,
+            }
+        }
+
+        if fetched {
+            if let Err(e) = self.save_cache(cache) {
+                tracing::debug!("couldn't cache crate releases: {}", e);
+            }
+        }
+
+        let releases = names
+            .iter()
+            .filter_map(|name| {
+                let entry = cache.get(name.as_str())?;
+                Some(((*name).clone(), entry.releases.clone()))
+            })
+            .collect();
+
+        (releases, error)
+    }
+
+    /// Fetch the releases of the given crates (with the `ETag`s of their
+    /// cached releases, if any), up to `concurrency` at once
+    fn fetch_all(
+        &self,
+        names: Vec<(package::Name, Option<String>)>,
+    ) -> Map<package::Name, Result<Fetched, Error>> {
+        let mut results = Map::new();
+
+        if names.is_empty() {
+            return results;
+        }
+
+        let http = match reqwest::blocking::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(TIMEOUT)
+            .build()
+        {
+            Ok(http) => http,
+            Err(e) => {
+                let message = format!("couldn't create HTTP client: {}", e);
+
+                for (name, _) in names {
+                    results.insert(name, Err(Error::new(ErrorKind::Io, &message)));
+                }
+
+                return results;
+            }
+        };
+
+        let workers = self.concurrency.min(names.len());
+        let queue = Arc::new(Mutex::new(names.into_iter()));
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..workers {
+            let client = self.clone();
+            let http = http.clone();
+            let queue = Arc::clone(&queue);
+            let sender = sender.clone();
+
+            thread::spawn(move || loop {
+                let next = queue.lock().unwrap().next();
+
+                let (name, etag) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+
+                let result = client.fetch(&http, &name, etag.as_deref());
+
+                if sender.send((name, result)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        // Only the workers hold senders now, so this ends once they're done
+        drop(sender);
+        results.extend(receiver);
+        results
+    }
+
+    /// Fetch the releases of the given crate from the index, unless they
+    /// haven't been modified since the response with the given `ETag`
+    fn fetch(
+        &self,
+        http: &reqwest::blocking::Client,
+        name: &package::Name,
+        etag: Option<&str>,
+    ) -> Result<Fetched, Error> {
+        let url = format!(
+            "{}/{}",
+            self.url.trim_end_matches('/'),
+            index_path(name.as_str())
+        );
+
+        if self.debug {
+            tracing::info!("fetching releases of {} from {}", name, url);
+        } else {
+            tracing::debug!("fetching releases of {} from {}", name, url);
+        }
+
+        let response = self
+            .retry
+            .run(&format!("fetching releases of {}", name), || {
+                self.stats.lock().unwrap().requests += 1;
+                let mut request = http.get(&url);
+
+                // Cargo sends registry tokens as-is
+                if let Some(token) = &self.token {
+                    request = request.header(reqwest::header::AUTHORIZATION, token.as_str());
+                }
+
+                if let Some(etag) = etag {
+                    request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+                }
+
+                let response = request.send()?;
+
+                if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+                    return Ok(None);
+                }
+
+                // Crates which aren't published (or are private) have no
+                // index file
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(Some((None, String::new())));
+                }
+
+                let response = response.error_for_status()?;
+                let etag = response
+                    .headers()
+                    .get(reqwest::header::ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(ToOwned::to_owned);
+
+                Ok(Some((etag, response.text()?)))
+            })?;
+
+        let (etag, body) = match response {
+            Some(response) => response,
+            None => {
+                self.stats.lock().unwrap().revalidated += 1;
+                return Ok(Fetched::NotModified);
+            }
+        };
+
+        let releases = body
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let entry: IndexEntry = serde_json::from_str(line).map_err(|e| {
+                    Error::new(
+                        ErrorKind::Parse,
+                        &format!("invalid index entry for {}: {}", name, e),
+                    )
+                })?;
+
+                Ok(Release {
+                    version: entry.vers,
+                    yanked: entry.yanked,
+                    checksum: entry.cksum,
+                    date: entry
+                        .pubtime
+                        .map(|pubtime| pubtime.chars().take(10).collect()),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Fetched::Modified { releases, etag })
+    }
+
+    /// Load the cached releases, ignoring an invalid or missing cache
+    fn load_cache(&self) -> Map<String, CacheEntry> {
+        let contents = match fs::read_to_string(&self.cache_path) {
+            Ok(contents) => contents,
+            Err(_) => return Map::new(),
+        };
+
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::debug!("ignoring invalid crate release cache: {}", e);
+            Map::new()
+        })
+    }
+
+    /// Save the cached releases
+    fn save_cache(&self, cache: &Map<String, CacheEntry>) -> Result<(), Error> {
+        let json = serde_json::to_string(cache).map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't serialize crate release cache: {}", e),
+            )
+        })?;
+
+        if let Some(dir) = self.cache_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&self.cache_path, json)?;
+        Ok(())
+    }
+}
+
+/// Find the oldest release satisfying the patched versions of an advisory,
+/// ignoring yanked releases
+pub fn patched_release(releases: &[Release], versions: &advisory::Versions) -> PatchedRelease {
+    releases
+        .iter()
+        .filter(|release| {
+            !release.yanked
+                && versions
+                    .patched()
+                    .iter()
+                    .any(|req| req.matches(&release.version))
+        })
+        .min_by(|a, b| a.version.cmp(&b.version))
+        .map(|release| PatchedRelease::Published {
+            version: release.version.clone(),
+            date: release.date.clone(),
+        })
+        .unwrap_or(PatchedRelease::Unreleased)
+}
+
+/// Path of a crate's file in the index, e.g. `se/rd/serde`
+fn index_path(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Cached releases of a crate
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CacheEntry {
+    /// When the releases were fetched (seconds since the Unix epoch)
+    fetched: u64,
+
+    /// `ETag` of the index's response, to revalidate the releases with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+
+    /// Releases of the crate
+    releases: Vec<Release>,
+}
+
+impl CacheEntry {
+    /// Do all of the cached releases have checksums?
+    fn has_checksums(&self) -> bool {
+        self.releases
+            .iter()
+            .all(|release| release.checksum.is_some())
+    }
+}
+
+/// Result of fetching a crate's releases
+enum Fetched {
+    /// Releases, and the `ETag` of the response (if any)
+    Modified {
+        releases: Vec<Release>,
+        etag: Option<String>,
+    },
+
+    /// Cached releases are still current
+    NotModified,
+}
+
+/// Line of a crate's index file (one per release)
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    /// Version of the release
+    vers: Version,
+
+    /// Has the release been yanked?
+    #[serde(default)]
+    yanked: bool,
+
+    /// SHA-256 checksum of the `.crate` file
+    #[serde(default)]
+    cksum: Option<String>,
+
+    /// When the release was published (RFC 3339, only recorded for newer releases)
+    #[serde(default)]
+    pubtime: Option<String>,
+}
+
+/// Current time in seconds since the Unix epoch
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,724 @@
+//! Auditing the binaries installed with `cargo install`
+//!
+//! `cargo audit installed` audits each binary in the `bin` directory of the
+//! install root (`$CARGO_HOME` unless `--root` or `CARGO_INSTALL_ROOT` is
+//! given). Binaries built with [cargo-auditable] embed the packages they were
+//! built from in a `.dep-v0` section, which is audited like a lockfile. For
+//! other binaries, only the version of the installed crate can be checked,
+//! which is found in the `.crates2.json` file cargo keeps in the install root.
+//! Symlinks, and files which couldn't be identified as Rust binaries, are
+//! skipped.
+//!
+//! Binaries aren't read in full: only their headers, symbol tables and audit
+//! data section are, so large (e.g. debug) binaries can be audited without
+//! loading their code and debug info into memory.
+//!
+//! [cargo-auditable]: https://github.com/rust-secure-code/cargo-auditable
+
+use crate::auditor::Auditor;
+use rustsec::{
+    cargo_lock::{
+        package::{self, source::CRATES_IO_INDEX, SourceId},
+        Dependency, Lockfile, Package,
+    },
+    error::{Error, ErrorKind},
+    report::DatabaseInfo,
+    Version,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    env, fs,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Name of the section cargo-auditable embeds audit data in
+pub const AUDIT_DATA_SECTION: &str = ".dep-v0";
+
+/// Magic number WebAssembly binaries start with
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// Version of the WebAssembly core modules which can be read
+const WASM_MODULE_VERSION: &[u8] = &[1, 0, 0, 0];
+
+/// Layer of WebAssembly components, which follows their (two-byte) version
+/// where core modules have the rest of theirs
+const WASM_COMPONENT_LAYER: &[u8] = &[1, 0];
+
+/// ID of WebAssembly custom sections, which cargo-auditable embeds audit data
+/// in (named like the section it uses in other executables)
+const WASM_CUSTOM_SECTION: u8 = 0;
+
+/// Name of the file cargo records installed crates in
+pub const INSTALL_METADATA_FILE: &str = ".crates2.json";
+
+/// Report of the audits of the installed binaries
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `installed`, to tell these reports apart from lockfile audits
+    pub mode: &'static str,
+
+    /// Install root whose `bin` directory was audited
+    pub root: PathBuf,
+
+    /// Information about the advisory database
+    pub database: DatabaseInfo,
+
+    /// Audited binaries, by name
+    pub binaries: Vec<Binary>,
+
+    /// Number of files which were skipped, by why they were
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub skipped: Map<Skipped, usize>,
+
+    /// Crates to reinstall, as updates resolve their binaries' vulnerabilities
+    pub reinstall: Vec<Reinstall>,
+}
+
+impl Report {
+    /// Number of vulnerabilities found in all of the binaries
+    pub fn vulnerability_count(&self) -> usize {
+        self.binaries
+            .iter()
+            .map(|binary| binary.report.vulnerabilities.count)
+            .sum()
+    }
+
+    /// Number of binaries with vulnerabilities
+    pub fn vulnerable_count(&self) -> usize {
+        self.binaries
+            .iter()
+            .filter(|binary| binary.report.vulnerabilities.found)
+            .count()
+    }
+}
+
+/// Audited binary
+#[derive(Clone, Debug, Serialize)]
+pub struct Binary {
+    /// File name of the binary
+    pub name: String,
+
+    /// Path to the binary
+    pub path: PathBuf,
+
+    /// Installed crate the binary is from (if known)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<Installed>,
+
+    /// Where the audited packages were found
+    pub data: Data,
+
+    /// Report of the audit of the binary's packages
+    pub report: rustsec::Report,
+}
+
+/// Crate installed with `cargo install`
+#[derive(Clone, Debug, Serialize)]
+pub struct Installed {
+    /// Name of the crate
+    pub name: package::Name,
+
+    /// Installed version
+    pub version: Version,
+
+    /// Source of the crate (if known)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceId>,
+}
+
+/// Where the packages audited for a binary were found
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Data {
+    /// Audit data embedded by cargo-auditable, listing every package the
+    /// binary was built with
+    AuditData,
+
+    /// Cargo's install metadata, which only lists the installed crate
+    InstallMetadata,
+}
+
+impl Data {
+    /// Get a description of where the packages were found
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Data::AuditData => "audit data",
+            Data::InstallMetadata => "install metadata only, dependencies unchecked",
+        }
+    }
+}
+
+/// What a file was found to be when looking for audit data in it
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Found {
+    /// Executable with the given (zlib-compressed) audit data
+    AuditData(Vec<u8>),
+
+    /// Executable without audit data
+    Executable,
+
+    /// WebAssembly component, whose audit data can't be read yet
+    WasmComponent,
+
+    /// Anything else
+    Other,
+}
+
+/// Reason a file was skipped
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Skipped {
+    /// Symlink (e.g. to a binary which isn't managed by cargo)
+    Symlink,
+
+    /// File which isn't a Rust binary, or has neither audit data nor install
+    /// metadata
+    NotRust,
+}
+
+impl Skipped {
+    /// Get a description of why files were skipped
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Skipped::Symlink => "symlink",
+            Skipped::NotRust => "not Rust",
+        }
+    }
+}
+
+/// Crate whose binaries' vulnerabilities can be resolved by reinstalling it
+#[derive(Clone, Debug, Serialize)]
+pub struct Reinstall {
+    /// Name of the crate
+    pub package: package::Name,
+
+    /// Binaries it installed with vulnerabilities
+    pub binaries: Vec<String>,
+
+    /// Command to reinstall it
+    pub command: String,
+}
+
+/// Audit data embedded by cargo-auditable
+#[derive(Debug, Deserialize)]
+struct AuditData {
+    packages: Vec<AuditDataPackage>,
+}
+
+/// Package listed in audit data
+#[derive(Debug, Deserialize)]
+struct AuditDataPackage {
+    name: package::Name,
+    version: Version,
+    source: String,
+    #[serde(default)]
+    dependencies: Vec<usize>,
+    #[serde(default)]
+    root: bool,
+}
+
+/// Cargo's install metadata (i.e. `.crates2.json`)
+#[derive(Debug, Default, Deserialize)]
+struct InstallMetadata {
+    #[serde(default)]
+    installs: Map<String, InstallInfo>,
+}
+
+/// Information about an installed crate in cargo's install metadata
+#[derive(Debug, Deserialize)]
+struct InstallInfo {
+    #[serde(default)]
+    bins: Set<String>,
+}
+
+/// Get the install root: `CARGO_INSTALL_ROOT` if set, otherwise `$CARGO_HOME`
+pub fn default_root() -> Result<PathBuf, Error> {
+    if let Some(root) = env::var_os("CARGO_INSTALL_ROOT") {
+        return Ok(PathBuf::from(root));
+    }
+
+    home::cargo_home()
+        .map_err(|e| Error::new(ErrorKind::Io, &format!("couldn't locate CARGO_HOME: {}", e)))
+}
+
+/// Audit the binaries in the `bin` directory of the given install root
+pub fn audit(auditor: &mut Auditor, root: &Path) -> Result<Report, Error> {
+    let bin_dir = root.join("bin");
+    let installed = installed_crates(root)?;
+
+    let mut entries = fs::read_dir(&bin_dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't read {}: {}", bin_dir.display(), e),
+            )
+        })?;
+
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut report = Report {
+        mode: "installed",
+        root: root.to_owned(),
+        database: DatabaseInfo::new(auditor.database()),
+        binaries: vec![],
+        skipped: Map::new(),
+        reinstall: vec![],
+    };
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        let metadata = fs::symlink_metadata(&path).map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't read {}: {}", path.display(), e),
+            )
+        })?;
+
+        if metadata.file_type().is_symlink() {
+            *report.skipped.entry(Skipped::Symlink).or_default() += 1;
+            continue;
+        } else if !metadata.is_file() {
+            continue;
+        }
+
+        let (packages, data) = match binary_packages(scan(&path)?, installed.get(&name)) {
+            Some(found) => found,
+            None => {
+                *report.skipped.entry(Skipped::NotRust).or_default() += 1;
+                continue;
+            }
+        };
+
+        // The installed crate is the audit data's root package, unless the
+        // install metadata says otherwise
+        let package = installed
+            .get(&name)
+            .cloned()
+            .or_else(|| root_package(&packages));
+
+        report.binaries.push(Binary {
+            name,
+            path,
+            package,
+            data,
+            report: auditor.generate_report(&lockfile(&packages)),
+        });
+    }
+
+    report.reinstall = reinstall(&report.binaries);
+    Ok(report)
+}
+
+/// Read the audit data cargo-auditable embedded in the executable (ELF, PE
+/// or Mach-O) at the given path, as a lockfile listing the packages it was
+/// built from
+pub fn read_binary(path: &Path) -> Result<Lockfile, Error> {
+    let compressed = match scan(path)? {
+        Found::AuditData(compressed) => compressed,
+        Found::Executable => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                &format!(
+                    "{} has no audit data (build it with `cargo auditable build` to embed its \
+                     dependencies)",
+                    path.display()
+                ),
+            ))
+        }
+        Found::WasmComponent => {
+            return Err(Error::new(
+                ErrorKind::Parse,
+                &format!(
+                    "{} is a WebAssembly component, whose audit data isn't supported yet \
+                     (only core modules' is)",
+                    path.display()
+                ),
+            ))
+        }
+        Found::Other => {
+            return Err(Error::new(
+                ErrorKind::Parse,
+                &format!(
+                    "{} isn't an executable (ELF, PE, Mach-O or WebAssembly)",
+                    path.display()
+                ),
+            ))
+        }
+    };
+
+    let packages = decode_audit_data(&compressed).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("{}: {}", path.display(), e.msg()),
+        )
+    })?;
+
+    Ok(lockfile(&packages))
+}
+
+/// Look for the (zlib-compressed) audit data cargo-auditable embedded in the
+/// file at the given path, if it's an executable (ELF, PE, Mach-O or
+/// WebAssembly module).
+///
+/// The file is read through a cache of the ranges its headers point to, so
+/// only those and the audit data section are read rather than the whole file
+/// (WebAssembly modules' sections are skipped past after reading their
+/// headers).
+pub fn scan(path: &Path) -> Result<Found, Error> {
+    let file = fs::File::open(path).map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't read {}: {}", path.display(), e),
+        )
+    })?;
+
+    let cache = object::ReadCache::new(file);
+
+    if let Ok(file) = object::File::parse(&cache) {
+        return Ok(match audit_data_section(&file) {
+            Some(compressed) => Found::AuditData(compressed.to_vec()),
+            None => Found::Executable,
+        });
+    }
+
+    Ok(scan_wasm(cache.into_inner()).unwrap_or(Found::Other))
+}
+
+/// Look for the audit data in the custom sections of the WebAssembly binary
+/// read by the given reader, seeking past the contents of other sections
+fn scan_wasm<R: Read + Seek>(mut reader: R) -> io::Result<Found> {
+    let mut header = [0; 8];
+    reader.seek(SeekFrom::Start(0))?;
+    reader.read_exact(&mut header)?;
+
+    if &header[..4] != WASM_MAGIC {
+        return Ok(Found::Other);
+    } else if &header[6..] == WASM_COMPONENT_LAYER {
+        return Ok(Found::WasmComponent);
+    } else if &header[4..] != WASM_MODULE_VERSION {
+        return Ok(Found::Other);
+    }
+
+    loop {
+        let mut id = [0];
+
+        if reader.read(&mut id)? == 0 {
+            return Ok(Found::Executable);
+        }
+
+        let (size, _) = read_leb128(&mut reader)?;
+
+        if id[0] != WASM_CUSTOM_SECTION {
+            reader.seek(SeekFrom::Current(size as i64))?;
+            continue;
+        }
+
+        let (name_size, name_size_length) = read_leb128(&mut reader)?;
+        let data_size = size
+            .checked_sub(name_size_length + name_size)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid custom section"))?;
+
+        let name = read_exact(&mut reader, name_size)?;
+
+        if name == AUDIT_DATA_SECTION.as_bytes() {
+            return Ok(Found::AuditData(read_exact(&mut reader, data_size)?));
+        }
+
+        reader.seek(SeekFrom::Current(data_size as i64))?;
+    }
+}
+
+/// Read an unsigned (32-bit) LEB128 integer, as WebAssembly encodes sizes
+/// with, along with the number of bytes it takes up
+fn read_leb128<R: Read>(reader: &mut R) -> io::Result<(u64, u64)> {
+    let mut value = 0;
+
+    for length in 1..=5 {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << (7 * (length - 1));
+
+        if byte[0] & 0x80 == 0 {
+            return Ok((value, length));
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "invalid LEB128 integer",
+    ))
+}
+
+/// Read the given number of bytes, without allocating them up front (in case
+/// a size read from the file is bogus)
+fn read_exact<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    reader.take(size).read_to_end(&mut bytes)?;
+
+    if (bytes.len() as u64) < size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(bytes)
+}
+
+/// Find the (zlib-compressed) audit data cargo-auditable embedded in the
+/// executable (ELF, PE or Mach-O) with the given contents, if it's an
+/// executable with any
+pub fn embedded_audit_data(contents: &[u8]) -> Option<&[u8]> {
+    let file = object::File::parse(contents).ok()?;
+    audit_data_section(&file)
+}
+
+/// Audit the binary at the given path by the (zlib-compressed) audit data
+/// embedded in it, e.g. as found by [`embedded_audit_data`]
+pub fn audit_embedded(
+    auditor: &mut Auditor,
+    path: &Path,
+    compressed: &[u8],
+) -> Result<Binary, Error> {
+    let packages = decode_audit_data(compressed).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("{}: {}", path.display(), e.msg()),
+        )
+    })?;
+
+    Ok(Binary {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.to_owned(),
+        package: root_package(&packages),
+        data: Data::AuditData,
+        report: auditor.generate_report(&lockfile(&packages)),
+    })
+}
+
+/// Parse the (zlib-compressed) audit data embedded by cargo-auditable into
+/// the packages it lists, with their dependencies
+pub fn parse_audit_data(compressed: &[u8]) -> Result<Vec<Package>, Error> {
+    Ok(lockfile_packages(&decode_audit_data(compressed)?))
+}
+
+/// Decompress and deserialize audit data
+fn decode_audit_data(compressed: &[u8]) -> Result<Vec<AuditDataPackage>, Error> {
+    let json = miniz_oxide::inflate::decompress_to_vec_zlib(compressed).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't decompress audit data: {:?}", e),
+        )
+    })?;
+
+    let data: AuditData = serde_json::from_slice(&json).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse audit data: {}", e),
+        )
+    })?;
+
+    Ok(data.packages)
+}
+
+/// Packages to audit for a binary which was found to be what's given, from
+/// its audit data if it has any, otherwise from the install metadata (if
+/// any). Returns `None` if the file can't be identified as a Rust binary.
+fn binary_packages(
+    found: Found,
+    installed: Option<&Installed>,
+) -> Option<(Vec<AuditDataPackage>, Data)> {
+    let audit_data = match found {
+        Found::AuditData(compressed) => match decode_audit_data(&compressed) {
+            Ok(packages) => Some(packages),
+            Err(e) => {
+                tracing::debug!("ignoring invalid audit data: {}", e);
+                None
+            }
+        },
+        Found::Executable | Found::WasmComponent => None,
+        Found::Other => return None,
+    };
+
+    if let Some(packages) = audit_data {
+        return Some((packages, Data::AuditData));
+    }
+
+    installed.map(|installed| {
+        let package = AuditDataPackage {
+            name: installed.name.clone(),
+            version: installed.version.clone(),
+            source: match &installed.source {
+                Some(source) if source.is_default_registry() => "crates.io".to_owned(),
+                _ => "other".to_owned(),
+            },
+            dependencies: vec![],
+            root: true,
+        };
+
+        (vec![package], Data::InstallMetadata)
+    })
+}
+
+/// Crate the binary was built from: the audit data's root package (if any)
+fn root_package(packages: &[AuditDataPackage]) -> Option<Installed> {
+    packages
+        .iter()
+        .find(|package| package.root)
+        .map(|package| Installed {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            source: crates_io_source(&package.source),
+        })
+}
+
+/// Contents of the section audit data is embedded in (if the file has one)
+fn audit_data_section<'a, R: object::ReadRef<'a>>(file: &object::File<'a, R>) -> Option<&'a [u8]> {
+    use object::{Object, ObjectSection};
+
+    file.section_by_name(AUDIT_DATA_SECTION)
+        .and_then(|section| section.data().ok())
+}
+
+/// Lockfile listing the given audit data packages
+fn lockfile(packages: &[AuditDataPackage]) -> Lockfile {
+    Lockfile {
+        version: Default::default(),
+        packages: lockfile_packages(packages),
+        root: None,
+        metadata: Default::default(),
+        patch: Default::default(),
+    }
+}
+
+/// Convert audit data packages into lockfile packages, so they can be
+/// audited (and have a dependency tree) like a lockfile's
+fn lockfile_packages(packages: &[AuditDataPackage]) -> Vec<Package> {
+    packages
+        .iter()
+        .map(|package| Package {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            source: crates_io_source(&package.source),
+            checksum: None,
+            dependencies: package
+                .dependencies
+                .iter()
+                .filter_map(|&index| packages.get(index))
+                .map(|dependency| Dependency {
+                    name: dependency.name.clone(),
+                    version: dependency.version.clone(),
+                    source: crates_io_source(&dependency.source),
+                })
+                .collect(),
+            replace: None,
+        })
+        .collect()
+}
+
+/// Source ID of a package from audit data, which only records whether it's
+/// from crates.io (advisories only apply to crates.io packages anyway)
+fn crates_io_source(source: &str) -> Option<SourceId> {
+    if source == "crates.io" {
+        SourceId::from_url(&format!("registry+{}", CRATES_IO_INDEX)).ok()
+    } else {
+        None
+    }
+}
+
+/// Crates recorded in the install root's install metadata, by the names of
+/// the binaries they installed
+fn installed_crates(root: &Path) -> Result<Map<String, Installed>, Error> {
+    let path = root.join(INSTALL_METADATA_FILE);
+
+    let metadata: InstallMetadata = match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("couldn't parse {}: {}", path.display(), e),
+            )
+        })?,
+        Err(_) => InstallMetadata::default(),
+    };
+
+    let mut installed = Map::new();
+
+    for (id, info) in metadata.installs {
+        let package = match parse_package_id(&id) {
+            Some(package) => package,
+            None => {
+                tracing::debug!(
+                    "ignoring unrecognized package ID in {}: {}",
+                    path.display(),
+                    id
+                );
+                continue;
+            }
+        };
+
+        for bin in info.bins {
+            installed.insert(bin, package.clone());
+        }
+    }
+
+    Ok(installed)
+}
+
+/// Parse a package ID in the install metadata, e.g.
+/// `ripgrep 13.0.0 (registry+https://github.com/rust-lang/crates.io-index)`
+fn parse_package_id(id: &str) -> Option<Installed> {
+    let mut parts = id.splitn(3, ' ');
+    let name = parts.next()?.parse().ok()?;
+    let version = parts.next()?.parse().ok()?;
+    let source = parts
+        .next()
+        .map(|source| source.trim_start_matches('(').trim_end_matches(')'))
+        .and_then(|source| SourceId::from_url(source).ok());
+
+    Some(Installed {
+        name,
+        version,
+        source,
+    })
+}
+
+/// Crates to reinstall: those from crates.io whose binaries' vulnerabilities
+/// all have patched versions, which reinstalling (without `--locked`) picks up
+fn reinstall(binaries: &[Binary]) -> Vec<Reinstall> {
+    let mut crates: Map<package::Name, Vec<String>> = Map::new();
+
+    for binary in binaries {
+        let vulnerabilities = &binary.report.vulnerabilities.list;
+
+        if vulnerabilities.is_empty()
+            || vulnerabilities
+                .iter()
+                .any(|vuln| vuln.versions.patched().is_empty())
+        {
+            continue;
+        }
+
+        if let Some(package) = &binary.package {
+            if matches!(&package.source, Some(source) if source.is_default_registry()) {
+                crates
+                    .entry(package.name.clone())
+                    .or_default()
+                    .push(binary.name.clone());
+            }
+        }
+    }
+
+    crates
+        .into_iter()
+        .map(|(package, binaries)| Reinstall {
+            command: format!("cargo install --force {}", package),
+            package,
+            binaries,
+        })
+        .collect()
+}
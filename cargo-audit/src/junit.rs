@@ -0,0 +1,251 @@
+//! JUnit XML reports
+//!
+//! The `junit` format outputs the findings as a JUnit-style XML test report,
+//! which CI systems such as Jenkins, GitLab and Buildkite show in their test
+//! UIs. Each (crate, advisory) pair is a test case: vulnerabilities and
+//! denied warnings fail, while allowed warnings (and vulnerabilities below
+//! the `--severity` threshold) are skipped. A report without findings has
+//! a single passing test case, so it isn't mistaken for an empty one.
+
+use crate::config::OutputConfig;
+use rustsec::{package::Package, warning, Report};
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+/// Name of the test suite
+pub const SUITE_NAME: &str = "cargo-audit";
+
+/// Test case for a (crate, advisory) pair
+struct TestCase {
+    /// Name of the crate
+    crate_name: String,
+
+    /// Advisory ID, or kind of warning for warnings without an advisory
+    /// (e.g. `yanked`)
+    id: String,
+
+    /// Affected versions of the crate
+    versions: Vec<String>,
+
+    /// Kind of finding, e.g. `vulnerability` or `unmaintained`
+    kind: String,
+
+    /// Advisory title, or a description of the warning
+    title: String,
+
+    /// Details of the finding (patched versions, URL)
+    details: Vec<String>,
+
+    /// How the finding affects the audit
+    result: TestResult,
+}
+
+/// Result of a test case
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum TestResult {
+    /// The finding fails the audit
+    Failure,
+
+    /// The finding is reported, but doesn't fail the audit
+    Skipped,
+}
+
+/// Write the report as JUnit XML
+pub fn write(w: &mut impl Write, report: &Report, config: &OutputConfig) -> io::Result<()> {
+    let denied: BTreeSet<warning::Kind> = config
+        .deny
+        .iter()
+        .filter_map(|option| option.get_warning_kind())
+        .collect();
+
+    let mut cases: Vec<TestCase> = vec![];
+
+    for vulnerability in &report.vulnerabilities.list {
+        let mut details = vec![format!(
+            "Patched: {}",
+            patched(vulnerability.versions.patched())
+        )];
+        details.extend(vulnerability.advisory.id.url());
+
+        let result = if config.is_below_fail_severity(vulnerability) {
+            TestResult::Skipped
+        } else {
+            TestResult::Failure
+        };
+
+        add_case(
+            &mut cases,
+            &vulnerability.package,
+            TestCase {
+                crate_name: vulnerability.package.name.to_string(),
+                id: vulnerability.advisory.id.to_string(),
+                versions: vec![],
+                kind: "vulnerability".to_owned(),
+                title: vulnerability.advisory.title.clone(),
+                details,
+                result,
+            },
+        );
+    }
+
+    for warning in report.warnings.values().flatten() {
+        let (id, title, details) = match &warning.advisory {
+            Some(advisory) => (
+                advisory.id.to_string(),
+                advisory.title.clone(),
+                advisory.id.url().into_iter().collect(),
+            ),
+            None => (
+                warning.kind.to_string(),
+                format!("{} crate", warning.kind),
+                vec![],
+            ),
+        };
+
+        let result = if denied.contains(&warning.kind) {
+            TestResult::Failure
+        } else {
+            TestResult::Skipped
+        };
+
+        add_case(
+            &mut cases,
+            &warning.package,
+            TestCase {
+                crate_name: warning.package.name.to_string(),
+                id,
+                versions: vec![],
+                kind: warning.kind.to_string(),
+                title,
+                details,
+                result,
+            },
+        );
+    }
+
+    let failures = count(&cases, TestResult::Failure);
+    let skipped = count(&cases, TestResult::Skipped);
+    let tests = cases.len().max(1);
+
+    writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        w,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\">",
+        SUITE_NAME, tests, failures, skipped
+    )?;
+    writeln!(
+        w,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\">",
+        SUITE_NAME, tests, failures, skipped
+    )?;
+
+    if cases.is_empty() {
+        writeln!(
+            w,
+            "    <testcase classname=\"{}\" name=\"no known vulnerabilities\"/>",
+            SUITE_NAME
+        )?;
+    }
+
+    for case in &cases {
+        writeln!(
+            w,
+            "    <testcase classname=\"{}\" name=\"{}\">",
+            escape(&case.crate_name),
+            escape(&case.id)
+        )?;
+
+        let message = format!(
+            "{} {}: {}",
+            case.crate_name,
+            case.versions.join(", "),
+            case.title
+        );
+
+        match case.result {
+            TestResult::Failure => {
+                let mut text = vec![message.clone()];
+                text.extend(case.details.iter().cloned());
+
+                writeln!(
+                    w,
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>",
+                    escape(&case.kind),
+                    escape(&message),
+                    escape(&text.join("\n"))
+                )?;
+            }
+            TestResult::Skipped => {
+                writeln!(w, "      <skipped message=\"{}\"/>", escape(&message))?;
+            }
+        }
+
+        writeln!(w, "    </testcase>")?;
+    }
+
+    writeln!(w, "  </testsuite>")?;
+    writeln!(w, "</testsuites>")
+}
+
+/// Add the test case for a finding about the given package, merging it into
+/// the existing one for the same crate and advisory (if any)
+fn add_case(cases: &mut Vec<TestCase>, package: &Package, case: TestCase) {
+    let version = package.version.to_string();
+
+    match cases
+        .iter_mut()
+        .find(|existing| existing.crate_name == case.crate_name && existing.id == case.id)
+    {
+        Some(existing) => {
+            if !existing.versions.contains(&version) {
+                existing.versions.push(version);
+            }
+
+            // A version which fails the audit fails the whole case
+            if case.result == TestResult::Failure {
+                existing.result = TestResult::Failure;
+            }
+        }
+        None => cases.push(TestCase {
+            versions: vec![version],
+            ..case
+        }),
+    }
+}
+
+/// Number of test cases with the given result
+fn count(cases: &[TestCase], result: TestResult) -> usize {
+    cases.iter().filter(|case| case.result == result).count()
+}
+
+/// Patched versions of a vulnerable package
+fn patched(reqs: &[rustsec::VersionReq]) -> String {
+    if reqs.is_empty() {
+        return "no patched versions".to_owned();
+    }
+
+    reqs.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Escape text for XML attributes and content
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,212 @@
+//! CISA Known Exploited Vulnerabilities (KEV) catalog
+//!
+//! The catalog is downloaded to the report cache directory and reused until
+//! its TTL expires. If it can't be downloaded, a previously downloaded copy
+//! is used regardless of its age.
+
+use crate::{cache::Cache, config::KevConfig, retry::Retry};
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    fs,
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeSet as Set,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+/// URL of the catalog's JSON feed
+pub const DEFAULT_URL: &str =
+    "https://www.cisa.gov/sites/default/files/feeds/known_exploited_vulnerabilities.json";
+
+/// Name of the file the catalog is downloaded to (located in the report
+/// cache directory)
+pub const CACHE_FILE: &str = "kev.json";
+
+/// How long the downloaded catalog is used for by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long to wait for the catalog to download
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Known Exploited Vulnerabilities catalog
+#[derive(Clone, Debug, Default)]
+pub struct Catalog {
+    /// IDs of the CVEs in the catalog
+    cves: Set<String>,
+}
+
+impl Catalog {
+    /// Load the catalog from the given JSON file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Is the given CVE in the catalog?
+    pub fn contains(&self, cve: &advisory::Id) -> bool {
+        self.cves.contains(cve.as_str())
+    }
+
+    /// Number of CVEs in the catalog
+    pub fn len(&self) -> usize {
+        self.cves.len()
+    }
+
+    /// Is the catalog empty?
+    pub fn is_empty(&self) -> bool {
+        self.cves.is_empty()
+    }
+}
+
+impl FromStr for Catalog {
+    type Err = Error;
+
+    fn from_str(json: &str) -> Result<Self, Error> {
+        let feed: Feed = serde_json::from_str(json)
+            .map_err(|e| Error::new(ErrorKind::Parse, &format!("invalid KEV catalog: {}", e)))?;
+
+        Ok(Self {
+            cves: feed
+                .vulnerabilities
+                .into_iter()
+                .map(|vuln| vuln.cve_id)
+                .collect(),
+        })
+    }
+}
+
+/// Client for downloading the catalog
+#[derive(Clone, Debug)]
+pub struct Client {
+    /// URL of the catalog
+    url: String,
+
+    /// Path the catalog is downloaded to
+    cache_path: PathBuf,
+
+    /// How long the downloaded catalog is used for
+    ttl: Duration,
+
+    /// Policy for retrying failed downloads
+    retry: Retry,
+
+    /// Only use a previously downloaded catalog
+    offline: bool,
+}
+
+impl Client {
+    /// Create a client for the catalog at the given URL
+    pub fn new(url: impl Into<String>, cache_path: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            url: url.into(),
+            cache_path: cache_path.into(),
+            ttl,
+            retry: Retry::default(),
+            offline: false,
+        }
+    }
+
+    /// Create the client configured in `audit.toml`, downloading the catalog
+    /// to the given report cache
+    pub fn from_config(config: &KevConfig, cache: &Cache) -> Self {
+        Self::new(
+            config.url.as_deref().unwrap_or(DEFAULT_URL),
+            cache.dir().join(CACHE_FILE),
+            config
+                .ttl_hours
+                .map(|hours| Duration::from_secs(hours * 60 * 60))
+                .unwrap_or(DEFAULT_TTL),
+        )
+    }
+
+    /// Only use a previously downloaded catalog, regardless of its age
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Set the policy for retrying failed downloads
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Path the catalog is downloaded to
+    pub fn cache_path(&self) -> &Path {
+        &self.cache_path
+    }
+
+    /// Get the catalog, downloading it unless a fresh copy was downloaded
+    /// previously.
+    ///
+    /// If downloading fails, the error is returned along with the previously
+    /// downloaded catalog (if any).
+    pub fn catalog(&self) -> (Option<Catalog>, Option<Error>) {
+        if self.offline || self.is_fresh() {
+            match Catalog::load(&self.cache_path) {
+                Ok(catalog) => return (Some(catalog), None),
+                Err(e) if self.offline => return (None, Some(e)),
+                Err(e) => tracing::debug!("ignoring downloaded KEV catalog: {}", e),
+            }
+        }
+
+        match self.download() {
+            Ok(catalog) => (Some(catalog), None),
+            Err(e) => (Catalog::load(&self.cache_path).ok(), Some(e)),
+        }
+    }
+
+    /// Was the catalog downloaded less than a TTL ago?
+    fn is_fresh(&self) -> bool {
+        let age = fs::metadata(&self.cache_path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+        matches!(age, Some(age) if age < self.ttl)
+    }
+
+    /// Download the catalog, replacing any previously downloaded copy
+    fn download(&self) -> Result<Catalog, Error> {
+        tracing::debug!("downloading KEV catalog from {}", self.url);
+
+        let json = self.retry.run("downloading the KEV catalog", || {
+            let json = reqwest::blocking::Client::builder()
+                .user_agent(concat!("cargo-audit/", env!("CARGO_PKG_VERSION")))
+                .timeout(TIMEOUT)
+                .build()
+                .and_then(|client| client.get(&self.url).send())
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text())?;
+
+            Ok(json)
+        })?;
+
+        let catalog = json.parse()?;
+
+        if let Some(dir) = self.cache_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&self.cache_path, json)?;
+        Ok(catalog)
+    }
+}
+
+/// JSON feed of the catalog
+#[derive(Debug, Deserialize)]
+struct Feed {
+    /// Vulnerabilities in the catalog
+    vulnerabilities: Vec<FeedEntry>,
+}
+
+/// Vulnerability in the catalog's JSON feed
+#[derive(Debug, Deserialize)]
+struct FeedEntry {
+    /// ID of the CVE
+    #[serde(rename = "cveID")]
+    cve_id: String,
+}
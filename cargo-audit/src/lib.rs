@@ -12,6 +12,10 @@
 //! documentation for the `rustsec` crate:
 //!
 //! <https://docs.rs/rustsec/>
+//!
+//! To embed the complete `cargo audit` flow in another tool instead (e.g.
+//! including report caching, yanked crate warnings and EPSS scores), see
+//! [`auditor::Auditor`].
 
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/RustSec/logos/main/rustsec-logo-lg.png",
@@ -20,14 +24,63 @@
 #![forbid(unsafe_code)]
 #![warn(missing_docs, rust_2018_idioms, trivial_casts, unused_qualifications)]
 
+pub mod acknowledgments;
 pub mod application;
 pub mod auditor;
+pub mod backup;
+pub mod badge;
+pub mod binaries;
+pub mod cache;
+pub mod cargo_config;
 pub mod commands;
 pub mod config;
+pub mod db_history;
+pub mod duplicates;
+pub mod epss;
 pub mod error;
+pub mod features;
+pub mod fix_commits;
+pub mod fix_plan;
+pub mod graph;
+pub mod history;
+pub mod html;
+pub mod image;
+pub mod index;
+pub mod installed;
+pub mod junit;
+pub mod kev;
+pub mod lint;
 pub mod lockfile;
+pub mod logging;
+pub mod manifest;
+pub mod markdown;
+pub mod members;
+pub mod metrics;
+pub mod ndjson;
+pub mod notify;
+pub mod osv;
+pub mod output;
+pub mod patch;
 mod prelude;
 pub mod presenter;
+pub mod progress;
+pub mod reachability;
+pub mod reporter;
+pub mod retry;
+pub mod sarif;
+pub mod sbom;
+pub mod scoped_ignores;
+pub mod spdx;
+pub mod state;
+pub mod suppressions;
+pub mod template;
+pub mod text;
+pub mod triage;
+pub mod verify;
+pub mod vex;
+pub mod watch;
+pub mod whats_new;
+pub mod workspaces;
 
 /// Current version of the `cargo-audit` crate
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,254 @@
+//! Linting advisories before they're submitted to an advisory database
+//!
+//! `cargo audit lint` checks local advisory files the way the advisory
+//! database's CI does, so contributors can fix them before submitting:
+//!
+//! - each file parses as an advisory, with the required fields, a valid date
+//!   and consistent version ranges
+//! - [`rustsec::advisory::Linter`]'s checks pass (e.g. known categories,
+//!   `https://` URLs, and an ID matching the date's year)
+//! - no two advisories have the same ID (besides the `RUSTSEC-0000-0000`
+//!   placeholder new advisories use)
+//! - optionally, the advisories' URLs are reachable
+
+use rustsec::{
+    advisory::{self, Linter},
+    error::{Error, ErrorKind},
+    fs,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap as Map, HashMap},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Timeout for checking that a URL is reachable
+const URL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Lint settings
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    /// Check that the advisories' URLs are reachable
+    pub check_urls: bool,
+}
+
+/// Results of linting advisory files
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `lint`, to tell these reports apart from lockfile audits
+    pub mode: &'static str,
+
+    /// Results for each advisory file, in the order they were found
+    pub files: Vec<FileResult>,
+}
+
+impl Report {
+    /// Number of files with lint errors
+    pub fn invalid_count(&self) -> usize {
+        self.files.iter().filter(|file| !file.is_valid()).count()
+    }
+
+    /// Did any file have lint errors?
+    pub fn is_failure(&self) -> bool {
+        self.invalid_count() > 0
+    }
+}
+
+/// Lint results for an advisory file
+#[derive(Clone, Debug, Serialize)]
+pub struct FileResult {
+    /// Path to the file
+    pub path: PathBuf,
+
+    /// ID of the advisory (if the file could be parsed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<advisory::Id>,
+
+    /// Lint errors
+    pub errors: Vec<String>,
+}
+
+impl FileResult {
+    /// Did the file pass every lint?
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Lint the advisory files at the given paths. Directories are searched
+/// recursively for files named after an advisory ID (e.g.
+/// `RUSTSEC-2021-0001.md`), while files are linted whatever their name.
+pub fn lint(paths: &[PathBuf], settings: &Settings) -> Result<Report, Error> {
+    let mut files = vec![];
+
+    for path in paths {
+        if path.is_dir() {
+            find_advisories(path, &mut files)?;
+        } else if path.is_file() {
+            files.push(path.clone());
+        } else {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                &format!("no such file or directory: {}", path.display()),
+            ));
+        }
+    }
+
+    let mut results: Vec<FileResult> = files.into_iter().map(lint_file).collect();
+    check_duplicate_ids(&mut results);
+
+    if settings.check_urls {
+        check_urls(&mut results)?;
+    }
+
+    Ok(Report {
+        mode: "lint",
+        files: results,
+    })
+}
+
+/// Find the advisory files in a directory (and its subdirectories, besides
+/// hidden ones like `.git`), in order of their paths
+fn find_advisories(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    entries.sort();
+
+    for path in entries {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        if path.is_dir() {
+            if !name.starts_with('.') {
+                find_advisories(&path, files)?;
+            }
+        } else if is_advisory_file(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Is the file named after an advisory ID, with a `.md` extension?
+fn is_advisory_file(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("md")
+        && path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<advisory::Id>().ok())
+            .map_or(false, |id| !id.is_other())
+}
+
+/// Lint a single advisory file
+fn lint_file(path: PathBuf) -> FileResult {
+    match Linter::lint_file(&path) {
+        Ok(linter) => FileResult {
+            id: Some(linter.advisory().id().clone()),
+            errors: linter.errors().iter().map(ToString::to_string).collect(),
+            path,
+        },
+        Err(e) => FileResult {
+            id: None,
+            errors: vec![e.msg().to_owned()],
+            path,
+        },
+    }
+}
+
+/// Flag the advisories which have the same ID as another one
+fn check_duplicate_ids(results: &mut [FileResult]) {
+    let mut paths: Map<advisory::Id, Vec<PathBuf>> = Map::new();
+
+    for result in results.iter() {
+        if let Some(id) = result.id.as_ref().filter(|id| !id.is_placeholder()) {
+            paths
+                .entry(id.clone())
+                .or_default()
+                .push(result.path.clone());
+        }
+    }
+
+    for result in results.iter_mut() {
+        let others: Vec<String> = match &result.id {
+            Some(id) => paths
+                .get(id)
+                .into_iter()
+                .flatten()
+                .filter(|path| **path != result.path)
+                .map(|path| path.display().to_string())
+                .collect(),
+            None => continue,
+        };
+
+        if !others.is_empty() {
+            result.errors.push(format!(
+                "duplicate advisory ID `{}` (also used by {})",
+                result.id.as_ref().unwrap(),
+                others.join(", ")
+            ));
+        }
+    }
+}
+
+/// Flag the URLs of the advisories which aren't reachable (checking each URL
+/// once, however many advisories refer to it)
+fn check_urls(results: &mut [FileResult]) -> Result<(), Error> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(concat!("cargo-audit/", env!("CARGO_PKG_VERSION")))
+        .timeout(URL_TIMEOUT)
+        .build()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't create HTTP client: {}", e),
+            )
+        })?;
+
+    let mut checked: HashMap<String, Option<String>> = HashMap::new();
+
+    for result in results.iter_mut() {
+        // Advisories which don't parse have already failed
+        let advisory = match advisory::Advisory::load_file(&result.path) {
+            Ok(advisory) => advisory,
+            Err(_) => continue,
+        };
+
+        for url in advisory.metadata.reference_urls() {
+            let problem = checked
+                .entry(url.to_string())
+                .or_insert_with(|| url_problem(&client, url.as_str()));
+
+            if let Some(problem) = problem {
+                result
+                    .errors
+                    .push(format!("URL {} isn't reachable: {}", url, problem));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Why the URL isn't reachable, if it isn't. Servers which don't allow `HEAD`
+/// requests are sent a `GET` request instead.
+fn url_problem(client: &reqwest::blocking::Client, url: &str) -> Option<String> {
+    let response = client.head(url).send().and_then(|response| {
+        if response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+            client.get(url).send()
+        } else {
+            Ok(response)
+        }
+    });
+
+    match response {
+        Ok(response) if response.status().is_success() => None,
+        Ok(response) => Some(format!("server responded with {}", response.status())),
+        Err(e) => Some(e.to_string()),
+    }
+}
@@ -1,30 +1,504 @@
 //! Cargo.lock-related utilities
 
-use rustsec::error::{Error, ErrorKind};
-use std::process::Command;
+use rustsec::{
+    error::{Error, ErrorKind},
+    fs,
+    lockfile::Lockfile,
+    package,
+};
+use std::{
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
 
-/// Run `cargo generate-lockfile`
-pub fn generate() -> Result<(), Error> {
-    let status = Command::new("cargo").arg("generate-lockfile").status();
+/// Name of `Cargo.lock`
+pub const CARGO_LOCK_FILE: &str = "Cargo.lock";
 
-    if let Err(e) = status {
-        return Err(Error::new(
+/// Name of `Cargo.toml`
+pub const CARGO_TOML_FILE: &str = "Cargo.toml";
+
+/// Locate the `Cargo.lock` which corresponds to the given `Cargo.toml`.
+///
+/// Like cargo, this finds the root of the workspace the manifest belongs to
+/// and returns the path to the lockfile next to the workspace root manifest.
+pub fn locate(manifest_path: &Path) -> Result<PathBuf, Error> {
+    Ok(workspace_root(manifest_path)?.with_file_name(CARGO_LOCK_FILE))
+}
+
+/// Find the root manifest of the workspace the given manifest belongs to:
+///
+/// 1. The manifest itself, if it contains a `[workspace]` table
+/// 2. The manifest pointed to by `package.workspace`, if present
+/// 3. The nearest ancestor manifest containing a `[workspace]` table which
+///    the package is a member of: like cargo, one whose `workspace.members`
+///    (which may be glob patterns) include it, or whose root package depends
+///    on it by path, unless `workspace.exclude` excludes it
+/// 4. Otherwise the manifest itself (i.e. a standalone package)
+pub fn workspace_root(manifest_path: &Path) -> Result<PathBuf, Error> {
+    let manifest_path = fs::canonicalize(manifest_path).map_err(|e| {
+        Error::new(
+            ErrorKind::NotFound,
+            &format!("couldn't find {}: {}", manifest_path.display(), e),
+        )
+    })?;
+
+    let manifest = load_manifest(&manifest_path)?;
+
+    if manifest.get("workspace").is_some() {
+        return Ok(manifest_path);
+    }
+
+    let manifest_dir = manifest_path.parent().ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            &format!("{} isn't in a directory", manifest_path.display()),
+        )
+    })?;
+
+    if let Some(root) = manifest
+        .get("package")
+        .and_then(|package| package.get("workspace"))
+        .and_then(|workspace| workspace.as_str())
+    {
+        return Ok(manifest_dir.join(root).join(CARGO_TOML_FILE));
+    }
+
+    for dir in manifest_dir.ancestors().skip(1) {
+        let candidate = dir.join(CARGO_TOML_FILE);
+
+        if !candidate.is_file() {
+            continue;
+        }
+
+        let root = load_manifest(&candidate)?;
+
+        if let Some(workspace) = root.get("workspace") {
+            if is_member(dir, &root, workspace, manifest_dir)? {
+                return Ok(candidate);
+            }
+        }
+    }
+
+    Ok(manifest_path)
+}
+
+/// Is the package in the given (canonical) directory a member of the
+/// workspace with the given root manifest in `root_dir`?
+///
+/// Members listed as they are (rather than matching a glob pattern) are
+/// members even if they're excluded, and only direct path dependencies of the
+/// root package are considered.
+fn is_member(
+    root_dir: &Path,
+    root: &toml::Value,
+    workspace: &toml::Value,
+    package_dir: &Path,
+) -> Result<bool, Error> {
+    let paths = |key: &str| {
+        workspace
+            .get(key)
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+    };
+
+    if paths("members").any(|member| same_dir(&root_dir.join(member), package_dir)) {
+        return Ok(true);
+    }
+
+    if paths("exclude").any(|excluded| package_dir.starts_with(root_dir.join(excluded))) {
+        return Ok(false);
+    }
+
+    for pattern in paths("members") {
+        if expand_member(root_dir, pattern)?
+            .iter()
+            .any(|member| same_dir(member, package_dir))
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(["dependencies", "dev-dependencies", "build-dependencies"]
+        .iter()
+        .filter_map(|key| root.get(key)?.as_table())
+        .flat_map(|dependencies| dependencies.values())
+        .filter_map(|dependency| dependency.get("path")?.as_str())
+        .any(|path| same_dir(&root_dir.join(path), package_dir)))
+}
+
+/// Expand a `workspace.members` entry, which may be a glob pattern, into the
+/// paths under the workspace root matching it
+fn expand_member(root_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, Error> {
+    // Only the member is a pattern, not the path of the workspace root
+    let pattern = Path::new(&glob::Pattern::escape(&root_dir.to_string_lossy())).join(pattern);
+
+    let invalid = |e: &dyn std::fmt::Display| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("invalid workspace member {}: {}", pattern.display(), e),
+        )
+    };
+
+    glob::glob(&pattern.to_string_lossy())
+        .map_err(|e| invalid(&e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(&e))
+}
+
+/// Is the given path (which may not exist) the given canonical directory?
+fn same_dir(path: &Path, dir: &Path) -> bool {
+    fs::canonicalize(path).ok().as_deref() == Some(dir)
+}
+
+/// Parse a `Cargo.toml` file as untyped TOML
+fn load_manifest(manifest_path: &Path) -> Result<toml::Value, Error> {
+    let manifest = fs::read_to_string(manifest_path)?;
+
+    toml::from_str(&manifest).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse {}: {}", manifest_path.display(), e),
+        )
+    })
+}
+
+/// Run `cargo generate-lockfile` for the given `Cargo.toml`
+///
+/// If cargo fails, the error it printed is included verbatim in the returned
+/// [`Error`] so the underlying cause (e.g. offline, broken manifest) is visible.
+pub fn generate(manifest_path: &Path) -> Result<(), Error> {
+    let output = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .arg("generate-lockfile")
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't run `cargo generate-lockfile`: {}", e),
+            )
+        })?;
+
+    if !output.status.success() {
+        return Err(cargo_error("cargo generate-lockfile", &output));
+    }
+
+    Ok(())
+}
+
+/// Update a single locked package in the lockfile of the given
+/// `Cargo.toml`'s workspace with `cargo update`, to the given version (if
+/// any, otherwise to the newest one its requirements allow), leaving every
+/// other package as it's locked where possible.
+///
+/// Returns `false` if the package isn't locked (e.g. it has already been
+/// updated along with another package).
+pub fn update(
+    manifest_path: &Path,
+    package: &package::Name,
+    from: &package::Version,
+    precise: Option<&package::Version>,
+) -> Result<bool, Error> {
+    let mut cmd = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    cmd.arg("update")
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .arg("--package")
+        .arg(format!("{}:{}", package, from));
+
+    if let Some(version) = precise {
+        cmd.arg("--precise").arg(version.to_string());
+    }
+
+    let output = cmd.stdin(Stdio::null()).output().map_err(|e| {
+        Error::new(
             ErrorKind::Io,
-            &format!("couldn't run `cargo generate-lockfile`: {}", e),
-        ));
+            &format!("couldn't run `cargo update`: {}", e),
+        )
+    })?;
+
+    if output.status.success() {
+        return Ok(true);
     }
-    let status = status.unwrap();
 
-    if !status.success() {
-        let msg = match status.code() {
-            Some(code) => format!(
-                "non-zero exit status running `cargo generate-lockfile`: {}",
-                code
-            ),
-            _ => "no exit status running `cargo generate-lockfile`!".to_string(),
-        };
+    // "package ID specification `foo:1.0.0` did not match any packages"
+    if String::from_utf8_lossy(&output.stderr).contains("did not match any packages") {
+        return Ok(false);
+    }
+
+    Err(cargo_error("cargo update", &output))
+}
 
-        return Err(Error::new(ErrorKind::Io, &msg));
+/// Check that the workspace of the given `Cargo.toml` still resolves with
+/// its lockfile as it is (i.e. `cargo metadata --locked` succeeds), and that
+/// the lockfile parses
+pub fn verify(manifest_path: &Path, lockfile_path: &Path) -> Result<(), Error> {
+    resolve_workspace(manifest_path, true, false)?;
+
+    Lockfile::load(lockfile_path).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse {}: {}", lockfile_path.display(), e),
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Resolve the workspace of the given `Cargo.toml` with `cargo metadata`,
+/// which fails if the lockfile needs to be updated when `locked` (and
+/// otherwise updates it)
+pub fn resolve_workspace(manifest_path: &Path, locked: bool, offline: bool) -> Result<(), Error> {
+    let output = metadata(manifest_path, locked, offline)?;
+
+    if !output.status.success() {
+        return Err(cargo_error("cargo metadata", &output));
     }
+
     Ok(())
 }
+
+/// Check whether the lockfile of the given `Cargo.toml`'s workspace is up to
+/// date with its manifests, i.e. whether `cargo metadata --locked` succeeds.
+///
+/// Returns an error if cargo fails for any other reason (e.g. when offline,
+/// dependencies which haven't been downloaded can't be resolved).
+pub fn is_up_to_date(manifest_path: &Path, offline: bool) -> Result<bool, Error> {
+    let output = metadata(manifest_path, true, offline)?;
+
+    if output.status.success() {
+        return Ok(true);
+    }
+
+    // "the lock file /path/Cargo.lock needs to be updated but --locked was
+    // passed to prevent this", or "cannot update the lock file ..." in newer
+    // versions of cargo
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if stderr.contains("needs to be updated") || stderr.contains("cannot update the lock file") {
+        return Ok(false);
+    }
+
+    Err(cargo_error("cargo metadata", &output))
+}
+
+/// Run `cargo metadata` for the given `Cargo.toml`
+fn metadata(manifest_path: &Path, locked: bool, offline: bool) -> Result<Output, Error> {
+    let mut cmd = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    cmd.args(&["metadata", "--format-version", "1"])
+        .arg("--manifest-path")
+        .arg(manifest_path);
+
+    if locked {
+        cmd.arg("--locked");
+    }
+
+    if offline {
+        cmd.arg("--offline");
+    }
+
+    cmd.stdin(Stdio::null()).output().map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't run `cargo metadata`: {}", e),
+        )
+    })
+}
+
+/// Error for a cargo command which failed, including the error it printed
+/// verbatim
+fn cargo_error(command: &str, output: &Output) -> Error {
+    let mut msg = match output.status.code() {
+        Some(code) => format!("non-zero exit status running `{}`: {}", command, code),
+        _ => format!("no exit status running `{}`!", command),
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if !stderr.trim().is_empty() {
+        msg.push('\n');
+        msg.push_str(stderr.trim_end());
+    }
+
+    Error::new(ErrorKind::Io, &msg)
+}
+
+/// Interactively ask whether `Cargo.lock` should be generated.
+///
+/// Returns `false` without prompting unless both STDIN and STDOUT are a TTY.
+pub fn confirm_generate() -> bool {
+    if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stdout) {
+        return false;
+    }
+
+    print!("Cargo.lock not found. Generate it with `cargo generate-lockfile`? [y/N] ");
+
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim(), "y" | "Y" | "yes" | "Yes")
+}
+
+/// Do the two paths refer to the same file?
+///
+/// Paths which don't exist (yet) are compared after making them absolute.
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    let normalize = |path: &Path| {
+        fs::canonicalize(path).unwrap_or_else(|_| {
+            env::current_dir()
+                .map(|cwd| cwd.join(path))
+                .unwrap_or_else(|_| path.to_owned())
+        })
+    };
+
+    normalize(a) == normalize(b)
+}
+
+/// Determine which `Cargo.lock` to audit from an explicitly specified
+/// lockfile and/or manifest path, erroring if the two are inconsistent.
+///
+/// Returns `None` if neither was specified (i.e. use the default location).
+pub fn resolve(
+    lockfile_path: Option<&Path>,
+    manifest_path: Option<&Path>,
+) -> Result<Option<PathBuf>, Error> {
+    let manifest_path = match manifest_path {
+        Some(path) => path,
+        None => return Ok(lockfile_path.map(ToOwned::to_owned)),
+    };
+
+    let derived_path = locate(manifest_path)?;
+
+    if let Some(lockfile_path) = lockfile_path {
+        if !same_file(lockfile_path, &derived_path) {
+            return Err(Error::new(
+                ErrorKind::BadParam,
+                &format!(
+                    "--file {} is inconsistent with --manifest-path {} \
+                     (whose lockfile is {})",
+                    lockfile_path.display(),
+                    manifest_path.display(),
+                    derived_path.display()
+                ),
+            ));
+        }
+    }
+
+    Ok(Some(derived_path))
+}
+
+/// Read the given lockfile as of a git revision (e.g. `HEAD~5`) of the
+/// repository containing it
+pub fn read_revision(lockfile_path: &Path, revision: &str) -> Result<String, Error> {
+    let (repo, path_in_repo) = repository(lockfile_path)?;
+
+    let tree = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Repo,
+                &format!("invalid git revision {:?}: {}", revision, e.message()),
+            )
+        })?;
+
+    read_tree(&repo, &tree, &path_in_repo, revision)
+}
+
+/// Open the git repository containing the given lockfile, along with the
+/// lockfile's path relative to the root of the repository
+pub fn repository(lockfile_path: &Path) -> Result<(git2::Repository, PathBuf), Error> {
+    let lockfile_path = env::current_dir()?.join(lockfile_path);
+    let (dir, file_name) = match (lockfile_path.parent(), lockfile_path.file_name()) {
+        (Some(dir), Some(file_name)) => (dir, file_name),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::BadParam,
+                &format!("invalid lockfile path: {}", lockfile_path.display()),
+            ))
+        }
+    };
+
+    let repo = git2::Repository::discover(dir).map_err(|e| {
+        Error::new(
+            ErrorKind::Repo,
+            &format!(
+                "couldn't find a git repository containing {}: {}",
+                dir.display(),
+                e.message()
+            ),
+        )
+    })?;
+
+    let workdir = repo.workdir().ok_or_else(|| {
+        Error::new(
+            ErrorKind::Repo,
+            &format!("{} is a bare repository", repo.path().display()),
+        )
+    })?;
+
+    // Paths in git trees are relative to the root of the repository
+    let path_in_repo = fs::canonicalize(dir)?
+        .strip_prefix(fs::canonicalize(workdir)?)
+        .map(|dir| dir.join(file_name))
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::BadParam,
+                &format!(
+                    "{} is outside of the repository at {}",
+                    lockfile_path.display(),
+                    workdir.display()
+                ),
+            )
+        })?;
+
+    Ok((repo, path_in_repo))
+}
+
+/// Read the lockfile at the given path (relative to the root of the
+/// repository) of a git tree, which is described as `revision` in errors
+pub fn read_tree(
+    repo: &git2::Repository,
+    tree: &git2::Tree<'_>,
+    path_in_repo: &Path,
+    revision: &str,
+) -> Result<String, Error> {
+    let blob = tree
+        .get_path(path_in_repo)
+        .and_then(|entry| entry.to_object(repo))
+        .and_then(|object| object.peel_to_blob())
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::NotFound,
+                &format!(
+                    "git revision {:?} has no {}",
+                    revision,
+                    path_in_repo.display()
+                ),
+            )
+        })?;
+
+    String::from_utf8(blob.content().to_owned()).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!(
+                "{} at git revision {:?} isn't valid UTF-8: {}",
+                path_in_repo.display(),
+                revision,
+                e
+            ),
+        )
+    })
+}
@@ -0,0 +1,43 @@
+//! Diagnostic logging via `tracing`
+//!
+//! Logs are written to STDERR so they never interfere with reports printed
+//! to STDOUT (e.g. `--json`).
+
+use abscissa_core::{terminal::ColorChoice, FrameworkError, FrameworkErrorKind};
+use std::{env, io};
+use tracing_log::LogTracer;
+use tracing_subscriber::FmtSubscriber;
+
+/// Environment variable which overrides the log filter (e.g. `RUST_LOG=debug`)
+pub const FILTER_ENV_VAR: &str = "RUST_LOG";
+
+/// Get the log filter for the given number of `-v` flags, unless one has
+/// been explicitly configured via `RUST_LOG`
+pub fn filter(verbosity: u32) -> String {
+    if let Ok(filter) = env::var(FILTER_ENV_VAR) {
+        return filter;
+    }
+
+    match verbosity {
+        0 => "info",
+        1 => "debug",
+        _ => "trace",
+    }
+    .to_owned()
+}
+
+/// Install a global `tracing` subscriber which logs to STDERR
+pub fn init(filter: String, color_choice: ColorChoice) -> Result<(), FrameworkError> {
+    LogTracer::init().map_err(|e| FrameworkErrorKind::ComponentError.context(e))?;
+
+    let subscriber = FmtSubscriber::builder()
+        .with_ansi(color_choice != ColorChoice::Never)
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .finish();
+
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|e| FrameworkErrorKind::ComponentError.context(e))?;
+
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Auditing a `Cargo.toml`'s dependency requirements without a lockfile
+//!
+//! For triaging third-party crates when only their `Cargo.toml` is at hand,
+//! `cargo audit manifest` checks whether the version requirements declared
+//! for each regular and build dependency intersect the versions affected by
+//! any vulnerability (informational advisories aren't checked). This is only a rough answer: which versions are actually
+//! used depends on how the requirements are resolved, so these reports are
+//! kept distinct from lockfile audits (with a `mode` of `manifest`). Path and
+//! git dependencies, and those from other registries, are skipped.
+
+use rustsec::{
+    advisory,
+    database::Query,
+    error::{Error, ErrorKind},
+    fs, package,
+    report::DatabaseInfo,
+    vulnerability::Scope,
+    Database, VersionReq,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap as Map,
+    path::{Path, PathBuf},
+};
+
+/// Caveat stated in every report, since requirements aren't resolved
+pub const CAVEAT: &str = "requirement-level match, no lockfile - actual resolution may differ";
+
+/// Report of the advisories matching a manifest's dependency requirements
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `manifest`, to tell these reports apart from lockfile audits
+    pub mode: &'static str,
+
+    /// How the matches should be read (see [`CAVEAT`])
+    pub caveat: &'static str,
+
+    /// Information about the advisory database
+    pub database: DatabaseInfo,
+
+    /// Information about the audited manifest
+    pub manifest: ManifestInfo,
+
+    /// Number of dependency requirements checked
+    pub dependencies: usize,
+
+    /// Number of dependencies which were skipped, by why they were
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub skipped: Map<Skipped, usize>,
+
+    /// Advisories matching the requirements, by dependency table (regular,
+    /// then build dependencies), then dependency name
+    pub matches: Vec<Match>,
+}
+
+/// Information about an audited manifest
+#[derive(Clone, Debug, Serialize)]
+pub struct ManifestInfo {
+    /// Path to the manifest
+    pub path: PathBuf,
+
+    /// Name of the manifest's package (if it has one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+}
+
+/// Dependency requirement matching an advisory
+#[derive(Clone, Debug, Serialize)]
+pub struct Match {
+    /// Name of the dependency in the manifest
+    pub dependency: String,
+
+    /// Name of the package it's a requirement on (which differs from the
+    /// dependency's name if it was renamed)
+    pub package: package::Name,
+
+    /// Version requirement of the dependency
+    pub requirement: VersionReq,
+
+    /// Kind of dependency
+    pub kind: Scope,
+
+    /// Advisory the requirement matches
+    pub advisory: advisory::Metadata,
+
+    /// Versions affected by the advisory
+    pub versions: advisory::Versions,
+}
+
+/// Reason a dependency was skipped
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Skipped {
+    /// Path dependency
+    Path,
+
+    /// Git dependency
+    Git,
+
+    /// Dependency from a registry other than crates.io
+    Registry,
+
+    /// Dependency inherited from the workspace, whose requirement is in
+    /// another manifest
+    Workspace,
+
+    /// Dependency whose requirement couldn't be checked, e.g. `1.*`
+    Unsupported,
+}
+
+impl Skipped {
+    /// Get a description of why dependencies were skipped
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Skipped::Path => "path",
+            Skipped::Git => "git",
+            Skipped::Registry => "other registry",
+            Skipped::Workspace => "inherited from the workspace",
+            Skipped::Unsupported => "unsupported requirement",
+        }
+    }
+}
+
+/// Dependency declared in a manifest
+#[derive(Clone, Debug)]
+struct Dependency {
+    /// Name of the dependency in the manifest
+    name: String,
+
+    /// Name of the package it's a requirement on
+    package: String,
+
+    /// Kind of dependency
+    kind: Scope,
+
+    /// Version requirement, or why the dependency is skipped
+    requirement: Result<VersionReq, Skipped>,
+}
+
+/// Audit the dependency requirements of the manifest at the given path,
+/// ignoring the given advisories
+pub fn audit(database: &Database, path: &Path, ignore: &[advisory::Id]) -> Result<Report, Error> {
+    let toml = fs::read_to_string(path).map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't read {}: {}", path.display(), e),
+        )
+    })?;
+    let manifest: toml::Value = toml.parse().map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse {}: {}", path.display(), e),
+        )
+    })?;
+
+    let mut report = Report {
+        mode: "manifest",
+        caveat: CAVEAT,
+        database: DatabaseInfo::new(database),
+        manifest: ManifestInfo {
+            path: path.to_owned(),
+            package: manifest
+                .get("package")
+                .and_then(|package| package.get("name"))
+                .and_then(toml::Value::as_str)
+                .map(ToOwned::to_owned),
+        },
+        dependencies: 0,
+        skipped: Map::new(),
+        matches: vec![],
+    };
+
+    for dependency in dependencies(&manifest) {
+        let requirement = match dependency.requirement {
+            Ok(requirement) => requirement,
+            Err(skipped) => {
+                *report.skipped.entry(skipped).or_default() += 1;
+                continue;
+            }
+        };
+
+        let package: package::Name = match dependency.package.parse() {
+            Ok(package) => package,
+            Err(_) => {
+                *report.skipped.entry(Skipped::Unsupported).or_default() += 1;
+                continue;
+            }
+        };
+
+        let query = Query::crate_scope().package(package.clone());
+
+        // Requirements which can't be checked against every advisory for
+        // the package are skipped, rather than reported as partially checked
+        let checked: Result<Vec<_>, _> = database
+            .query(&query)
+            .into_iter()
+            .filter(|advisory| !ignore.contains(&advisory.metadata.id))
+            .map(|advisory| {
+                advisory
+                    .versions
+                    .intersects(&requirement)
+                    .map(|intersects| (advisory, intersects))
+            })
+            .collect();
+
+        let checked = match checked {
+            Ok(checked) => checked,
+            Err(_) => {
+                *report.skipped.entry(Skipped::Unsupported).or_default() += 1;
+                continue;
+            }
+        };
+
+        report.dependencies += 1;
+
+        for (advisory, intersects) in checked {
+            if intersects {
+                report.matches.push(Match {
+                    dependency: dependency.name.clone(),
+                    package: package.clone(),
+                    requirement: requirement.clone(),
+                    kind: dependency.kind,
+                    advisory: advisory.metadata.clone(),
+                    versions: advisory.versions.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Regular and build dependencies declared in a manifest, including
+/// platform-specific ones
+fn dependencies(manifest: &toml::Value) -> Vec<Dependency> {
+    let mut tables = vec![manifest];
+
+    if let Some(targets) = manifest.get("target").and_then(toml::Value::as_table) {
+        tables.extend(targets.values());
+    }
+
+    let mut dependencies = vec![];
+
+    for table in tables {
+        for (key, kind) in &[
+            ("dependencies", Scope::Normal),
+            ("build-dependencies", Scope::Build),
+            ("build_dependencies", Scope::Build),
+        ] {
+            let declared = match table.get(key).and_then(toml::Value::as_table) {
+                Some(declared) => declared,
+                None => continue,
+            };
+
+            for (name, value) in declared {
+                dependencies.push(dependency(name, value, *kind));
+            }
+        }
+    }
+
+    dependencies
+}
+
+/// Dependency declared with the given name and value (either a version
+/// requirement or a table)
+fn dependency(name: &str, value: &toml::Value, kind: Scope) -> Dependency {
+    let table = value.as_table();
+    let field = |key: &str| table.and_then(|table| table.get(key));
+
+    let requirement = if field("path").is_some() {
+        Err(Skipped::Path)
+    } else if field("git").is_some() {
+        Err(Skipped::Git)
+    } else if field("registry").is_some() || field("registry-index").is_some() {
+        Err(Skipped::Registry)
+    } else if field("workspace").and_then(toml::Value::as_bool) == Some(true) {
+        Err(Skipped::Workspace)
+    } else {
+        // Cargo treats a missing requirement as `*`
+        let requirement = match value {
+            toml::Value::String(requirement) => Some(requirement.as_str()),
+            _ => field("version").and_then(toml::Value::as_str),
+        };
+
+        VersionReq::parse(requirement.unwrap_or("*")).map_err(|_| Skipped::Unsupported)
+    };
+
+    Dependency {
+        name: name.to_owned(),
+        package: field("package")
+            .and_then(toml::Value::as_str)
+            .unwrap_or(name)
+            .to_owned(),
+        kind,
+        requirement,
+    }
+}
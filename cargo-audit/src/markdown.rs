@@ -0,0 +1,171 @@
+//! Markdown reports
+//!
+//! The `markdown` format renders the findings as GitHub Flavored Markdown,
+//! for bots to post as pull (or merge) request comments on GitHub or GitLab:
+//! a table of the vulnerabilities (advisory ID, crate, version, severity,
+//! patched versions, a link to the advisory and whether it's a direct or
+//! transitive dependency), another of the warnings, and the dependency tree
+//! of each affected package in a collapsible `<details>` section.
+
+use crate::members;
+use rustsec::{
+    advisory,
+    cargo_lock::dependency::{graph::EdgeDirection, Dependency, Tree},
+    package::Package,
+    Report, VersionReq,
+};
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+/// Heading of the report
+pub const HEADING: &str = "## cargo audit";
+
+/// Write the report as Markdown, with dependency trees if the dependency
+/// tree is known
+pub fn write(w: &mut impl Write, report: &Report, tree: Option<&Tree>) -> io::Result<()> {
+    let vulnerabilities = &report.vulnerabilities.list;
+    let warnings: Vec<_> = report.warnings.values().flatten().collect();
+
+    writeln!(w, "{}", HEADING)?;
+    writeln!(w)?;
+
+    if vulnerabilities.is_empty() && warnings.is_empty() {
+        writeln!(w, "No known vulnerabilities or warnings found.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        w,
+        "**{} {} found**, {} {}.",
+        vulnerabilities.len(),
+        plural(vulnerabilities.len(), "vulnerability", "vulnerabilities"),
+        warnings.len(),
+        plural(warnings.len(), "warning", "warnings")
+    )?;
+
+    if !vulnerabilities.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "### Vulnerabilities")?;
+        writeln!(w)?;
+        writeln!(
+            w,
+            "| Advisory | Crate | Version | Severity | Patched | Link | Dependency |"
+        )?;
+        writeln!(w, "|---|---|---|---|---|---|---|")?;
+
+        for vulnerability in vulnerabilities {
+            let severity = match &vulnerability.advisory.cvss {
+                Some(cvss) => format!("{} ({:.1})", cvss.severity(), cvss.score().value()),
+                None => "-".to_owned(),
+            };
+
+            writeln!(
+                w,
+                "| {} | `{}` | {} | {} | {} | {} | {} |",
+                vulnerability.advisory.id,
+                vulnerability.package.name,
+                vulnerability.package.version,
+                severity,
+                patched(vulnerability.versions.patched()),
+                link(&vulnerability.advisory),
+                members::kind(vulnerability.direct)
+            )?;
+        }
+    }
+
+    if !warnings.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "### Warnings")?;
+        writeln!(w)?;
+        writeln!(w, "| Kind | Crate | Version | Advisory | Link |")?;
+        writeln!(w, "|---|---|---|---|---|")?;
+
+        for warning in &warnings {
+            let (id, url) = match &warning.advisory {
+                Some(advisory) => (advisory.id.to_string(), link(advisory)),
+                None => ("-".to_owned(), "-".to_owned()),
+            };
+
+            writeln!(
+                w,
+                "| {} | `{}` | {} | {} | {} |",
+                warning.kind, warning.package.name, warning.package.version, id, url
+            )?;
+        }
+    }
+
+    if let Some(tree) = tree {
+        // Only show the tree once per package
+        let mut displayed = BTreeSet::new();
+        let packages = vulnerabilities
+            .iter()
+            .map(|vulnerability| &vulnerability.package)
+            .chain(warnings.iter().map(|warning| &warning.package));
+
+        for package in packages {
+            if displayed.insert(Dependency::from(package)) {
+                write_tree(w, tree, package)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the inverse dependency tree of the given package, collapsed
+fn write_tree(w: &mut impl Write, tree: &Tree, package: &Package) -> io::Result<()> {
+    let node = match tree.nodes().get(&Dependency::from(package)) {
+        Some(&node) => node,
+        None => return Ok(()),
+    };
+
+    writeln!(w)?;
+    writeln!(w, "<details>")?;
+    writeln!(
+        w,
+        "<summary>Dependency tree of <code>{} {}</code></summary>",
+        package.name, package.version
+    )?;
+    writeln!(w)?;
+    writeln!(w, "```")?;
+    tree.render(w, node, EdgeDirection::Incoming)?;
+    writeln!(w, "```")?;
+    writeln!(w)?;
+    writeln!(w, "</details>")
+}
+
+/// Patched versions, as a table cell
+fn patched(reqs: &[VersionReq]) -> String {
+    if reqs.is_empty() {
+        return "no patched versions".to_owned();
+    }
+
+    reqs.iter()
+        .map(|req| format!("`{}`", req))
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+/// Link to the advisory (or its URL, if its ID has no page of its own), as a
+/// table cell
+fn link(advisory: &advisory::Metadata) -> String {
+    match advisory
+        .id
+        .url()
+        .or_else(|| advisory.url.as_ref().map(ToString::to_string))
+    {
+        Some(url) => format!("[{}]({})", advisory.id, url),
+        None => "-".to_owned(),
+    }
+}
+
+/// Singular or plural noun for the given count
+fn plural(count: usize, singular: &'static str, plural: &'static str) -> &'static str {
+    if count == 1 {
+        singular
+    } else {
+        plural
+    }
+}
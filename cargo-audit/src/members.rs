@@ -0,0 +1,126 @@
+//! Workspace members affected by each finding
+//!
+//! In a large workspace, knowing a vulnerable package is somewhere in the
+//! lockfile isn't enough to route the finding to whoever owns the affected
+//! code. Each vulnerability and warning is annotated with the workspace
+//! members from which the package is reachable in the dependency graph, and
+//! whether it's a direct dependency of one of them or only a transitive one
+//! (policies often treat vulnerabilities in direct dependencies, which the
+//! workspace can upgrade itself, more severely).
+//! Members are listed by `cargo metadata` when the workspace's `Cargo.toml`
+//! can be loaded, falling back to the local roots of the lockfile's
+//! dependency graph (i.e. packages without a source which nothing else
+//! depends on).
+
+use rustsec::{
+    cargo_lock::dependency::{
+        graph::{EdgeDirection, NodeIndex},
+        Dependency, Tree,
+    },
+    package::{self, Package},
+    Report,
+};
+use std::collections::BTreeSet as Set;
+
+/// Annotate the report's findings with whether their packages are direct
+/// dependencies of the workspace, and the workspace members affected by
+/// them, given the members listed by `cargo metadata` (if known). Findings
+/// in single-package workspaces aren't annotated with members, as they all
+/// affect it.
+pub fn annotate(report: &mut Report, tree: &Tree, members: Option<&Set<Dependency>>) {
+    let members = member_nodes(tree, members);
+
+    for vulnerability in &mut report.vulnerabilities.list {
+        vulnerability.direct = is_direct(tree, &members, &vulnerability.package);
+    }
+
+    for warning in report.warnings.values_mut().flatten() {
+        warning.direct = is_direct(tree, &members, &warning.package);
+    }
+
+    if members.len() < 2 {
+        return;
+    }
+
+    for vulnerability in &mut report.vulnerabilities.list {
+        vulnerability.members = affected(tree, &members, &vulnerability.package);
+    }
+
+    for warning in report.warnings.values_mut().flatten() {
+        warning.members = affected(tree, &members, &warning.package);
+    }
+}
+
+/// Nodes of the workspace members in the dependency graph, which are the
+/// graph's local roots unless members listed by `cargo metadata` are in it
+fn member_nodes(tree: &Tree, members: Option<&Set<Dependency>>) -> Set<NodeIndex> {
+    let listed: Set<NodeIndex> = members
+        .into_iter()
+        .flatten()
+        .filter_map(|member| tree.nodes().get(member).copied())
+        .collect();
+
+    if listed.is_empty() {
+        tree.roots()
+            .into_iter()
+            .filter(|&root| tree.graph()[root].source.is_none())
+            .collect()
+    } else {
+        listed
+    }
+}
+
+/// Is the given package a workspace member, or a direct dependency of one?
+/// (`None` if the package isn't in the graph, or no members are known)
+fn is_direct(tree: &Tree, members: &Set<NodeIndex>, package: &Package) -> Option<bool> {
+    if members.is_empty() {
+        return None;
+    }
+
+    let node = *tree.nodes().get(&Dependency::from(package))?;
+
+    let direct = members.contains(&node)
+        || tree
+            .graph()
+            .neighbors_directed(node, EdgeDirection::Incoming)
+            .any(|dependent| members.contains(&dependent));
+
+    Some(direct)
+}
+
+/// Describe a finding's dependency kind, given whether its package is a
+/// direct dependency of the workspace (if known)
+pub fn kind(direct: Option<bool>) -> &'static str {
+    match direct {
+        Some(true) => "direct",
+        Some(false) => "transitive",
+        None => "-",
+    }
+}
+
+/// Names of the workspace members which are, or transitively depend on, the
+/// given package (`None` if the package isn't in the graph)
+fn affected(
+    tree: &Tree,
+    members: &Set<NodeIndex>,
+    package: &Package,
+) -> Option<Vec<package::Name>> {
+    let graph = tree.graph();
+    let node = *tree.nodes().get(&Dependency::from(package))?;
+
+    let mut dependents = Set::new();
+    let mut pending = vec![node];
+
+    while let Some(node) = pending.pop() {
+        if dependents.insert(node) {
+            pending.extend(graph.neighbors_directed(node, EdgeDirection::Incoming));
+        }
+    }
+
+    let names: Set<package::Name> = members
+        .intersection(&dependents)
+        .map(|&node| graph[node].name.clone())
+        .collect();
+
+    Some(names.into_iter().collect())
+}
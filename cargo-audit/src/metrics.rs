@@ -0,0 +1,182 @@
+//! Prometheus metrics
+//!
+//! The `metrics` format outputs gauges summarizing the report in the
+//! Prometheus text exposition format (which is also valid [OpenMetrics]),
+//! e.g. for node_exporter's textfile collector to pick up after a scheduled
+//! audit, so dashboards and alerts can be driven from it. Every severity and
+//! kind of warning is listed, with zeros for those which weren't found, so
+//! series don't disappear when findings are resolved. Each sample has a
+//! `lockfile` label with the path of the audited lockfile (if known), so the
+//! metrics of several projects can be collected side by side.
+//!
+//! [OpenMetrics]: https://openmetrics.io/
+
+use rustsec::{advisory::Severity, report::Outcome, warning, Report};
+use std::{
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Prefix of the names of the metrics
+pub const PREFIX: &str = "cargo_audit";
+
+/// Severities vulnerabilities are counted by, in increasing order (those
+/// without a CVSS score are counted as `unknown`)
+const SEVERITIES: &[Severity] = &[
+    Severity::None,
+    Severity::Low,
+    Severity::Medium,
+    Severity::High,
+    Severity::Critical,
+];
+
+/// Kinds of warnings counted
+const WARNING_KINDS: &[warning::Kind] = &[
+    warning::Kind::Notice,
+    warning::Kind::Unmaintained,
+    warning::Kind::Unsound,
+    warning::Kind::Yanked,
+    warning::Kind::Overridden,
+    warning::Kind::DuplicateVersions,
+    warning::Kind::Optional,
+    warning::Kind::OtherPlatform,
+];
+
+/// Writer of metrics, each with a `HELP` and `TYPE` line before its samples
+struct Metrics<'a, W: Write> {
+    /// Where the metrics are written
+    w: &'a mut W,
+
+    /// Labels every sample has, e.g. `lockfile="Cargo.lock"`
+    common_labels: Vec<(&'static str, String)>,
+}
+
+impl<'a, W: Write> Metrics<'a, W> {
+    /// Write the `HELP` and `TYPE` lines of a gauge
+    fn gauge(&mut self, name: &str, help: &str) -> io::Result<()> {
+        writeln!(self.w, "# HELP {}_{} {}", PREFIX, name, help)?;
+        writeln!(self.w, "# TYPE {}_{} gauge", PREFIX, name)
+    }
+
+    /// Write a sample of a metric, with the common labels and the given ones
+    fn sample(
+        &mut self,
+        name: &str,
+        labels: &[(&str, &str)],
+        value: impl std::fmt::Display,
+    ) -> io::Result<()> {
+        let labels: Vec<String> = self
+            .common_labels
+            .iter()
+            .map(|(label, value)| (*label, value.as_str()))
+            .chain(labels.iter().copied())
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+            .collect();
+
+        if labels.is_empty() {
+            writeln!(self.w, "{}_{} {}", PREFIX, name, value)
+        } else {
+            writeln!(
+                self.w,
+                "{}_{}{{{}}} {}",
+                PREFIX,
+                name,
+                labels.join(","),
+                value
+            )
+        }
+    }
+}
+
+/// Write the report's metrics
+pub fn write(w: &mut impl Write, report: &Report) -> io::Result<()> {
+    let common_labels = match &report.lockfile.path {
+        Some(path) => vec![("lockfile", path.display().to_string())],
+        None => vec![],
+    };
+
+    let mut metrics = Metrics { w, common_labels };
+
+    metrics.gauge(
+        "vulnerabilities",
+        "Number of vulnerabilities found, by the severity of their advisory",
+    )?;
+
+    for severity in SEVERITIES.iter().copied().map(Some).chain(Some(None)) {
+        let count = report
+            .vulnerabilities
+            .list
+            .iter()
+            .filter(|vulnerability| vulnerability.severity == severity)
+            .count();
+
+        let label = severity.map(Severity::as_str).unwrap_or("unknown");
+        metrics.sample("vulnerabilities", &[("severity", label)], count)?;
+    }
+
+    metrics.gauge("warnings", "Number of warnings found, by kind")?;
+
+    for kind in WARNING_KINDS {
+        let count = report.warnings.get(kind).map(Vec::len).unwrap_or(0);
+        metrics.sample("warnings", &[("kind", kind.as_str())], count)?;
+    }
+
+    metrics.gauge(
+        "vulnerable_crates",
+        "Number of distinct crates with vulnerabilities",
+    )?;
+    metrics.sample("vulnerable_crates", &[], report.summary.crates)?;
+
+    metrics.gauge("dependencies", "Number of dependencies in the lockfile")?;
+    metrics.sample("dependencies", &[], report.lockfile.dependency_count())?;
+
+    if let Some(outcome) = report.outcome {
+        metrics.gauge(
+            "outcome",
+            "Outcome of the audit (1 for the audit's outcome, 0 for the others)",
+        )?;
+
+        for other in Outcome::all() {
+            let value = if other == outcome { 1 } else { 0 };
+            metrics.sample("outcome", &[("outcome", other.as_str())], value)?;
+        }
+    }
+
+    metrics.gauge(
+        "database_advisories",
+        "Number of advisories in the advisory database",
+    )?;
+    metrics.sample("database_advisories", &[], report.database.advisory_count)?;
+
+    let now = SystemTime::now();
+
+    if let Some(last_updated) = report.database.last_updated {
+        let age = now.duration_since(last_updated).unwrap_or_default();
+
+        metrics.gauge(
+            "database_age_seconds",
+            "Time since the advisory database was last updated",
+        )?;
+        metrics.sample("database_age_seconds", &[], age.as_secs())?;
+    }
+
+    metrics.gauge(
+        "last_run_timestamp_seconds",
+        "Time the audit was run, as a Unix timestamp",
+    )?;
+    metrics.sample(
+        "last_run_timestamp_seconds",
+        &[],
+        now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+    )?;
+
+    writeln!(metrics.w, "# EOF")
+}
+
+/// Escape a label value: backslashes, double quotes and line feeds
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,243 @@
+//! NDJSON (JSON Lines) reports
+//!
+//! Each finding is written as a self-contained JSON object on a line of its
+//! own (with a `kind` of `vulnerability` or `warning`), followed by a final
+//! `summary` line, which is the only line when nothing was found. Lines are
+//! flushed as they're written, so log pipelines see findings promptly. This
+//! is also what commands given with `--reporter` read (see
+//! [`crate::reporter`]).
+
+use crate::reporter::{self, ReportRenderer};
+use rustsec::{
+    advisory::{self, Severity},
+    cargo_lock::dependency::{
+        graph::{EdgeDirection, NodeIndex},
+        Dependency, Tree,
+    },
+    package::{self, Package},
+    report::{LockfileInfo, Outcome, Summary},
+    warning::{self, Duplicates},
+    Report, Vulnerability, Warning,
+};
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// Maximum number of dependency paths listed for each finding
+pub const MAX_PATHS: usize = 16;
+
+/// Line for a vulnerability
+#[derive(Serialize)]
+struct VulnerabilityLine<'a> {
+    /// Always `vulnerability`
+    kind: &'static str,
+
+    /// The vulnerability, as in JSON reports (including its severity)
+    #[serde(flatten)]
+    vulnerability: &'a Vulnerability,
+
+    /// Dependency paths from the vulnerable package up to the workspace's
+    /// root packages (see [`paths`])
+    paths: Vec<Vec<String>>,
+}
+
+/// Line for a warning
+#[derive(Serialize)]
+struct WarningLine<'a> {
+    /// Always `warning`
+    kind: &'static str,
+
+    /// Kind of warning, e.g. `unmaintained`
+    warning: warning::Kind,
+
+    /// Package warned about
+    package: &'a Package,
+
+    /// Advisory the warning is for (if any)
+    advisory: Option<&'a advisory::Metadata>,
+
+    /// Versions affected by the advisory
+    versions: Option<&'a advisory::Versions>,
+
+    /// Severity of the advisory (if it has a CVSS score)
+    severity: Option<Severity>,
+
+    /// Other versions of the package (for `duplicate-versions` warnings)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicates: Option<&'a Duplicates>,
+
+    /// Workspace members affected by the warning (if known)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    members: Option<&'a Vec<package::Name>>,
+
+    /// Is the package a direct dependency of the workspace (if known)?
+    #[serde(skip_serializing_if = "Option::is_none")]
+    direct: Option<bool>,
+
+    /// Dependency paths from the package up to the workspace's root packages
+    paths: Vec<Vec<String>>,
+}
+
+/// Final line, summarizing the report
+#[derive(Serialize)]
+struct SummaryLine<'a> {
+    /// Always `summary`
+    kind: &'static str,
+
+    /// Number of vulnerabilities found
+    vulnerabilities: usize,
+
+    /// Number of warnings found
+    warnings: usize,
+
+    /// Advisories, package versions, crates and direct dependencies affected
+    #[serde(flatten)]
+    summary: &'a Summary,
+
+    /// Outcome of the audit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<Outcome>,
+
+    /// Information about the audited lockfile
+    lockfile: &'a LockfileInfo,
+}
+
+/// Write the report as NDJSON, with dependency paths if the dependency tree
+/// is known
+pub fn write(w: &mut impl Write, report: &Report, tree: Option<&Tree>) -> io::Result<()> {
+    reporter::render(&mut Renderer, w, report, tree)
+}
+
+/// Renderer for NDJSON reports, which writes a line for each finding as it's
+/// rendered
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Renderer;
+
+impl ReportRenderer for Renderer {
+    fn render_vulnerability(
+        &mut self,
+        w: &mut dyn Write,
+        vulnerability: &Vulnerability,
+        tree: Option<&Tree>,
+    ) -> io::Result<()> {
+        write_line(
+            w,
+            &VulnerabilityLine {
+                kind: "vulnerability",
+                vulnerability,
+                paths: paths(tree, &vulnerability.package),
+            },
+        )
+    }
+
+    fn render_warning(
+        &mut self,
+        w: &mut dyn Write,
+        warning: &Warning,
+        tree: Option<&Tree>,
+    ) -> io::Result<()> {
+        write_line(
+            w,
+            &WarningLine {
+                kind: "warning",
+                warning: warning.kind,
+                package: &warning.package,
+                advisory: warning.advisory.as_ref(),
+                versions: warning.versions.as_ref(),
+                severity: warning
+                    .advisory
+                    .as_ref()
+                    .and_then(|advisory| advisory.cvss.as_ref())
+                    .map(|cvss| cvss.severity()),
+                duplicates: warning.duplicates.as_ref(),
+                members: warning.members.as_ref(),
+                direct: warning.direct,
+                paths: paths(tree, &warning.package),
+            },
+        )
+    }
+
+    fn end_report(&mut self, w: &mut dyn Write, report: &Report) -> io::Result<()> {
+        write_line(
+            w,
+            &SummaryLine {
+                kind: "summary",
+                vulnerabilities: report.vulnerabilities.list.len(),
+                warnings: report.warnings.values().map(Vec::len).sum(),
+                summary: &report.summary,
+                outcome: report.outcome,
+                lockfile: &report.lockfile,
+            },
+        )
+    }
+}
+
+/// Write a single line of JSON, flushing it
+fn write_line(w: &mut dyn Write, line: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *w, line)?;
+    writeln!(w)?;
+    w.flush()
+}
+
+/// Dependency paths from the given package up to the workspace's root
+/// packages, each listing packages as `name version` starting with the
+/// package itself (at most [`MAX_PATHS`], in a stable order)
+pub fn paths(tree: Option<&Tree>, package: &Package) -> Vec<Vec<String>> {
+    let tree = match tree {
+        Some(tree) => tree,
+        None => return vec![],
+    };
+
+    let node = match tree.nodes().get(&Dependency::from(package)) {
+        Some(&node) => node,
+        None => return vec![],
+    };
+
+    let mut paths = vec![];
+    let mut path = vec![node];
+    collect_paths(tree, &mut path, &mut paths);
+
+    paths
+        .into_iter()
+        .map(|path| {
+            path.into_iter()
+                .map(|node| {
+                    let package = &tree.graph()[node];
+                    format!("{} {}", package.name, package.version)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Extend the given path through each package depending on its last one,
+/// collecting the paths which reach a root
+fn collect_paths(tree: &Tree, path: &mut Vec<NodeIndex>, paths: &mut Vec<Vec<NodeIndex>>) {
+    let node = *path.last().expect("paths are never empty");
+    let mut dependents: Vec<NodeIndex> = tree
+        .graph()
+        .neighbors_directed(node, EdgeDirection::Incoming)
+        .collect();
+
+    if dependents.is_empty() {
+        paths.push(path.clone());
+        return;
+    }
+
+    dependents.sort();
+    dependents.dedup();
+
+    for dependent in dependents {
+        if paths.len() >= MAX_PATHS {
+            return;
+        }
+
+        // Skip dependency cycles (e.g. through dev-dependencies)
+        if path.contains(&dependent) {
+            continue;
+        }
+
+        path.push(dependent);
+        collect_paths(tree, path, paths);
+        path.pop();
+    }
+}
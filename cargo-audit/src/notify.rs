@@ -0,0 +1,371 @@
+//! Webhook notifications
+//!
+//! Reports are POSTed as JSON to each configured URL, or summarized in a
+//! message for Slack-compatible incoming webhooks. Deliveries which fail
+//! due to connection errors, server errors or rate limiting are retried with
+//! exponential backoff (see [`crate::retry`]), while other client errors
+//! (e.g. `401 Unauthorized`) fail immediately.
+
+use crate::{
+    config::{NotifyConfig, NotifyFormat, NotifyOn},
+    retry::{self, Failure, Retry},
+    state::Changes,
+};
+use reqwest::{
+    blocking::Client,
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+};
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    report::VulnerabilityInfo,
+    Report,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap as Map, env, time::Duration};
+
+/// Exit status used when a report couldn't be delivered to a webhook and
+/// notifications are required (`--notify-required`)
+pub const NOTIFY_FAILED_EXIT_CODE: i32 = 3;
+
+/// How many times delivering a report is retried by default
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// How long to wait for a webhook to respond
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends reports to webhooks
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    /// URLs to POST reports to
+    urls: Vec<String>,
+
+    /// Extra HTTP headers (before substituting environment variables)
+    headers: Map<String, String>,
+
+    /// When to send reports
+    on: NotifyOn,
+
+    /// Payload to send
+    format: NotifyFormat,
+
+    /// How many times to retry delivering a report
+    retries: u32,
+
+    /// How long to wait before the first retry
+    backoff: Duration,
+}
+
+impl Notifier {
+    /// Create a notifier which sends reports with findings to the given URLs
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            urls,
+            headers: Map::new(),
+            on: NotifyOn::default(),
+            format: NotifyFormat::default(),
+            retries: DEFAULT_RETRIES,
+            backoff: retry::INITIAL_BACKOFF,
+        }
+    }
+
+    /// Create the notifier configured in `audit.toml`
+    pub fn from_config(config: &NotifyConfig) -> Self {
+        let urls = config.urls.iter().chain(&config.webhook).cloned().collect();
+        let mut notifier = Self::new(urls)
+            .on(config.on)
+            .format(config.format)
+            .retries(config.retries.unwrap_or(DEFAULT_RETRIES));
+
+        notifier.headers = config.headers.clone();
+        notifier
+    }
+
+    /// Send the given HTTP header, whose value may refer to environment
+    /// variables as `${NAME}`
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set when to send reports
+    pub fn on(mut self, on: NotifyOn) -> Self {
+        self.on = on;
+        self
+    }
+
+    /// Set the payload to send
+    pub fn format(mut self, format: NotifyFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set how many times to retry delivering a report
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set how long to wait before the first retry (doubling after each one)
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// URLs reports are sent to
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Should the given report be sent? With `--notify-on new`, the report
+    /// is expected to only have the new findings (see [`new_findings`]).
+    pub fn should_notify(&self, report: &Report) -> bool {
+        if self.urls.is_empty() {
+            return false;
+        }
+
+        match self.on {
+            NotifyOn::Always => true,
+            NotifyOn::Findings | NotifyOn::New => {
+                report.vulnerabilities.found
+                    || report
+                        .warnings
+                        .values()
+                        .any(|warnings| !warnings.is_empty())
+            }
+        }
+    }
+
+    /// Send the given report to every URL, returning an error for each one it
+    /// couldn't be delivered to
+    pub fn notify(&self, report: &Report) -> Vec<Error> {
+        let (client, body, headers) = match self.prepare(report) {
+            Ok(request) => request,
+            Err(e) => return vec![e],
+        };
+
+        self.urls
+            .iter()
+            .filter_map(|url| self.deliver(&client, url, &body, &headers).err())
+            .collect()
+    }
+
+    /// Build the HTTP client, request body and headers for sending the report
+    fn prepare(&self, report: &Report) -> Result<(Client, Vec<u8>, HeaderMap), Error> {
+        let body = match self.format {
+            NotifyFormat::Json => serde_json::to_vec(report),
+            NotifyFormat::Slack => serde_json::to_vec(&SlackMessage::new(report)),
+        }
+        .map_err(|e| Error::new(ErrorKind::Io, &format!("couldn't serialize report: {}", e)))?;
+
+        let client = Client::builder()
+            .user_agent(concat!("cargo-audit/", env!("CARGO_PKG_VERSION")))
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|e| Error::new(ErrorKind::Io, &e.to_string()))?;
+
+        Ok((client, body, self.header_map()?))
+    }
+
+    /// Deliver the report to the given URL, retrying with backoff
+    fn deliver(
+        &self,
+        client: &Client,
+        url: &str,
+        body: &[u8],
+        headers: &HeaderMap,
+    ) -> Result<(), Error> {
+        Retry::new(self.retries + 1)
+            .backoff(self.backoff)
+            .run(&format!("delivering report to {}", url), || {
+                let response = client
+                    .post(url)
+                    .headers(headers.clone())
+                    .body(body.to_owned())
+                    .send()?;
+
+                if !response.status().is_success() {
+                    return Err(Failure::status(response.status()));
+                }
+
+                tracing::debug!("delivered report to {}", url);
+                Ok(())
+            })
+            .map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    &format!("couldn't deliver report to {}: {}", url, e.msg()),
+                )
+            })
+    }
+
+    /// HTTP headers to send, with environment variables substituted
+    fn header_map(&self) -> Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        for (name, value) in &self.headers {
+            let invalid = |e: &dyn std::fmt::Display| {
+                Error::new(
+                    ErrorKind::BadParam,
+                    &format!("invalid notification header {}: {}", name, e),
+                )
+            };
+
+            headers.insert(
+                name.parse::<HeaderName>().map_err(|e| invalid(&e))?,
+                expand_env(value)?
+                    .parse::<HeaderValue>()
+                    .map_err(|e| invalid(&e))?,
+            );
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Copy of the report with only the findings which are new since the run
+/// recorded in the state file (or the baseline report), for `--notify-on new`
+pub fn new_findings(report: &Report, changes: &Changes) -> Report {
+    let mut report = report.clone();
+
+    let list = report
+        .vulnerabilities
+        .list
+        .drain(..)
+        .filter(|vuln| changes.is_new_vulnerability(vuln))
+        .collect();
+
+    let ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+    let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+    let vex = std::mem::take(&mut report.vulnerabilities.vex);
+    report.vulnerabilities = VulnerabilityInfo::new(list);
+    report.vulnerabilities.ignored = ignored;
+    report.vulnerabilities.acknowledged = acknowledged;
+    report.vulnerabilities.vex = vex;
+
+    for warnings in report.warnings.values_mut() {
+        warnings.retain(|warning| changes.is_new_warning(warning));
+    }
+
+    report.warnings.retain(|_, warnings| !warnings.is_empty());
+    report
+}
+
+/// Message for Slack-compatible incoming webhooks
+#[derive(Debug, Serialize)]
+pub struct SlackMessage {
+    /// Text of the message (in Slack's `mrkdwn` format)
+    pub text: String,
+}
+
+impl SlackMessage {
+    /// Summarize the findings of the given report: how many there are, then
+    /// a line for each one
+    pub fn new(report: &Report) -> Self {
+        let vulnerabilities = &report.vulnerabilities.list;
+        let warnings: Vec<_> = report.warnings.values().flatten().collect();
+
+        let mut text = match (vulnerabilities.len(), warnings.len()) {
+            (0, 0) => "*cargo audit* found no vulnerabilities or warnings".to_owned(),
+            (vulnerabilities, warnings) => format!(
+                "*cargo audit* found {} {} and {} {}",
+                vulnerabilities,
+                if vulnerabilities == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                },
+                warnings,
+                if warnings == 1 { "warning" } else { "warnings" }
+            ),
+        };
+
+        if let Some(path) = &report.lockfile.path {
+            text.push_str(&format!(" in `{}`", escape(&path.display().to_string())));
+        }
+
+        for vuln in vulnerabilities {
+            let severity = vuln
+                .advisory
+                .cvss
+                .as_ref()
+                .map(|cvss| format!(" ({})", cvss.severity()))
+                .unwrap_or_default();
+
+            text.push_str(&format!(
+                "\n\u{2022} {} `{} {}`: {}{}",
+                link(&vuln.advisory.id),
+                escape(vuln.package.name.as_str()),
+                vuln.package.version,
+                escape(&vuln.advisory.title),
+                severity
+            ));
+        }
+
+        for warning in warnings {
+            text.push_str(&format!(
+                "\n\u{2022} {}: `{} {}`",
+                warning.kind,
+                escape(warning.package.name.as_str()),
+                warning.package.version
+            ));
+
+            if let Some(advisory) = &warning.advisory {
+                text.push_str(&format!(
+                    " {} {}",
+                    link(&advisory.id),
+                    escape(&advisory.title)
+                ));
+            }
+        }
+
+        Self { text }
+    }
+}
+
+/// Link to the advisory with the given ID (if it has a page of its own)
+fn link(id: &advisory::Id) -> String {
+    match id.url() {
+        Some(url) => format!("<{}|{}>", url, escape(id.as_str())),
+        None => escape(id.as_str()),
+    }
+}
+
+/// Escape the characters Slack treats as control characters in messages
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Substitute the values of environment variables referred to as `${NAME}`
+/// in the given string, erroring if any of them aren't set
+pub fn expand_env(value: &str) -> Result<String, Error> {
+    let mut expanded = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}').ok_or_else(|| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("unterminated ${{...}} in {:?}", value),
+            )
+        })?;
+
+        let name = &rest[start + 2..start + end];
+        let var = env::var(name).map_err(|e| {
+            Error::new(
+                ErrorKind::NotFound,
+                &format!("environment variable {}: {}", name, e),
+            )
+        })?;
+
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&var);
+        rest = &rest[start + end + 1..];
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
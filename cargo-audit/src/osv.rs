@@ -0,0 +1,162 @@
+//! OSV reports
+//!
+//! The `osv` format outputs the findings in the JSON layout of
+//! [osv-scanner]'s reports, so tools which consume those can read them: each
+//! affected package lists the advisories about it in the [OSV format], along
+//! with groups of the IDs (and aliases) which refer to the same
+//! vulnerability. Warnings without an advisory (e.g. yanked crates) aren't
+//! included, as OSV has no way to express them.
+//!
+//! [osv-scanner]: https://github.com/google/osv-scanner
+//! [OSV format]: https://ossf.github.io/osv-schema/
+
+use rustsec::{osv::OsvAdvisory, package::Package, Advisory, Report};
+use serde::Serialize;
+
+/// Ecosystem of crates in OSV
+pub const ECOSYSTEM: &str = "crates.io";
+
+/// Results of scanning lockfiles (just the one)
+#[derive(Clone, Debug, Serialize)]
+pub struct Results {
+    /// Results for each scanned lockfile
+    pub results: Vec<SourceResult>,
+}
+
+/// Results for a lockfile
+#[derive(Clone, Debug, Serialize)]
+pub struct SourceResult {
+    /// Scanned lockfile
+    pub source: Source,
+
+    /// Packages which advisories were found for
+    pub packages: Vec<PackageResult>,
+}
+
+/// Scanned lockfile
+#[derive(Clone, Debug, Serialize)]
+pub struct Source {
+    /// Path to the lockfile
+    pub path: String,
+
+    /// Kind of source (always `lockfile`)
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+/// Advisories found for a package
+#[derive(Clone, Debug, Serialize)]
+pub struct PackageResult {
+    /// Affected package
+    pub package: PackageInfo,
+
+    /// Advisories about the package, in the OSV format
+    pub vulnerabilities: Vec<OsvAdvisory>,
+
+    /// IDs which refer to the same vulnerability: one group per advisory,
+    /// with its ID followed by its aliases
+    pub groups: Vec<Group>,
+}
+
+/// Affected package
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct PackageInfo {
+    /// Name of the crate
+    pub name: String,
+
+    /// Version of the crate
+    pub version: String,
+
+    /// Ecosystem of the crate (always `crates.io`)
+    pub ecosystem: &'static str,
+}
+
+/// IDs which refer to the same vulnerability
+#[derive(Clone, Debug, Serialize)]
+pub struct Group {
+    /// Advisory ID, followed by its aliases
+    pub ids: Vec<String>,
+}
+
+impl Results {
+    /// Convert the findings of an audit
+    pub fn new(report: &Report) -> Self {
+        let mut packages: Vec<PackageResult> = vec![];
+
+        let mut add = |package: &Package, advisory: Advisory| {
+            let info = PackageInfo {
+                name: package.name.to_string(),
+                version: package.version.to_string(),
+                ecosystem: ECOSYSTEM,
+            };
+
+            let index = match packages.iter().position(|result| result.package == info) {
+                Some(index) => index,
+                None => {
+                    packages.push(PackageResult {
+                        package: info,
+                        vulnerabilities: vec![],
+                        groups: vec![],
+                    });
+                    packages.len() - 1
+                }
+            };
+
+            let result = &mut packages[index];
+
+            if result
+                .vulnerabilities
+                .iter()
+                .any(|existing| existing.id() == advisory.id())
+            {
+                return;
+            }
+
+            let mut ids = vec![advisory.id().to_string()];
+            ids.extend(advisory.metadata.aliases.iter().map(ToString::to_string));
+            result.groups.push(Group { ids });
+            result.vulnerabilities.push(advisory.to_osv());
+        };
+
+        for vulnerability in &report.vulnerabilities.list {
+            add(
+                &vulnerability.package,
+                Advisory {
+                    metadata: vulnerability.advisory.clone(),
+                    affected: vulnerability.affected.clone(),
+                    versions: vulnerability.versions.clone(),
+                },
+            );
+        }
+
+        for warning in report.warnings.values().flatten() {
+            if let (Some(metadata), Some(versions)) = (&warning.advisory, &warning.versions) {
+                add(
+                    &warning.package,
+                    Advisory {
+                        metadata: metadata.clone(),
+                        affected: None,
+                        versions: versions.clone(),
+                    },
+                );
+            }
+        }
+
+        let path = report
+            .lockfile
+            .path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "Cargo.lock".to_owned());
+
+        Self {
+            results: vec![SourceResult {
+                source: Source {
+                    path,
+                    kind: "lockfile",
+                },
+                packages,
+            }],
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Writing status messages and reports to the terminal.
+//!
+//! The [`status_ok!`] and [`status_warn!`] macros re-exported by the prelude
+//! replace Abscissa's, which panic if writing to STDOUT fails. If STDOUT was
+//! closed early (e.g. when piped into `head`), these exit quietly with
+//! [`BROKEN_PIPE_EXIT_CODE`] instead, and report any other error.
+//!
+//! When a machine-readable report format is selected, status messages are
+//! written to STDERR instead (see [`status_to_stderr`]), so STDOUT contains
+//! nothing but the report.
+
+use abscissa_core::{terminal::status::Status, FrameworkError};
+use rustsec::{Error, ErrorKind};
+use std::{
+    error::Error as _,
+    io::{self, Write},
+    process::exit,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Exit status used when STDOUT is closed early: the one shells report for
+/// processes killed by `SIGPIPE` (128 + 13)
+pub const BROKEN_PIPE_EXIT_CODE: i32 = 141;
+
+/// Are status messages written to STDERR rather than STDOUT?
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Print a Cargo-like justified status (in green if colors are enabled)
+#[macro_export]
+macro_rules! status_ok {
+    ($status:expr, $msg:expr) => {
+        $crate::output::print_status(
+            abscissa_core::terminal::status::Status::new()
+                .justified()
+                .bold()
+                .color(abscissa_core::terminal::Color::Green)
+                .status($status),
+            $msg,
+        )
+    };
+    ($status:expr, $fmt:expr, $($arg:tt)+) => {
+        $crate::status_ok!($status, format!($fmt, $($arg)+))
+    };
+}
+
+/// Print a warning status message (in yellow if colors are enabled)
+#[macro_export]
+macro_rules! status_warn {
+    ($msg:expr) => {
+        $crate::output::print_status(
+            abscissa_core::terminal::status::Status::new()
+                .bold()
+                .color(abscissa_core::terminal::Color::Yellow)
+                .status("warning:"),
+            $msg,
+        )
+    };
+    ($fmt:expr, $($arg:tt)+) => {
+        $crate::status_warn!(format!($fmt, $($arg)+))
+    };
+}
+
+/// Write status messages to STDERR rather than STDOUT (e.g. because STDOUT
+/// is reserved for a JSON report)
+pub fn status_to_stderr(enabled: bool) {
+    STATUS_TO_STDERR.store(enabled, Ordering::Relaxed);
+}
+
+/// Print the given status message, exiting if that fails
+pub fn print_status(status: Status, msg: impl AsRef<str>) {
+    let result = if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+        status.print_stderr(msg)
+    } else {
+        status.print_stdout(msg)
+    };
+
+    if let Err(e) = result {
+        exit_on_write_error(io_error(e));
+    }
+}
+
+/// Print a warning to STDERR, even if status messages go to STDOUT (e.g. for
+/// failures which shouldn't be mixed up with the report)
+pub fn warn_stderr(msg: impl AsRef<str>) {
+    let result = Status::new()
+        .bold()
+        .color(abscissa_core::terminal::Color::Yellow)
+        .status("warning:")
+        .print_stderr(msg);
+
+    if let Err(e) = result {
+        exit_on_write_error(io_error(e));
+    }
+}
+
+/// Exit quietly if the given error means STDOUT was closed early
+pub fn exit_if_broken_pipe(error: &io::Error) {
+    if error.kind() == io::ErrorKind::BrokenPipe {
+        exit(BROKEN_PIPE_EXIT_CODE);
+    }
+}
+
+/// Turn the result of writing a report (or the like, as described by
+/// `what`) to STDOUT into an error, exiting quietly if STDOUT was closed early
+/// (e.g. piped into `head`)
+pub fn report_result(result: io::Result<()>, what: &str) -> Result<(), Error> {
+    result.map_err(|e| {
+        exit_if_broken_pipe(&e);
+        Error::new(ErrorKind::Io, &format!("couldn't write {}: {}", what, e))
+    })
+}
+
+/// Exit after failing to write output, reporting the error unless STDOUT
+/// was closed early
+pub fn exit_on_write_error(error: io::Error) -> ! {
+    exit_if_broken_pipe(&error);
+
+    // Don't panic if STDERR is unwritable too
+    let _ = writeln!(io::stderr(), "error: couldn't write output: {}", error);
+    exit(2);
+}
+
+/// Get the I/O error underlying a failure to print a status message
+pub fn io_error(error: FrameworkError) -> io::Error {
+    let kind = error
+        .source()
+        .and_then(|e| e.downcast_ref::<io::Error>())
+        .map(io::Error::kind)
+        .unwrap_or(io::ErrorKind::Other);
+
+    io::Error::new(kind, error.to_string())
+}
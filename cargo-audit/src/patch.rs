@@ -0,0 +1,282 @@
+//! Temporary `[patch.crates-io]` entries for unreleased fixes
+//!
+//! Some advisories are fixed upstream before a patched version is released.
+//! When an advisory references the fixing commit (or branch) in a git
+//! repository, `cargo audit fix --allow-git-patches` can point the vulnerable
+//! crate at it with a `[patch.crates-io]` entry in the workspace manifest,
+//! until a fixed release is published.
+
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    fs, package,
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Document, InlineTable, Item, Table, Value};
+
+/// Git revision which fixes a vulnerable crate
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GitPatch {
+    /// Name of the patched crate
+    pub package: package::Name,
+
+    /// URL of the git repository containing the fix
+    pub git: String,
+
+    /// Fixing commit (preferred, as it can't change)
+    pub rev: Option<String>,
+
+    /// Branch containing the fix (if no commit is referenced)
+    pub branch: Option<String>,
+}
+
+impl GitPatch {
+    /// Find the fix for the given advisory in the commits or branches its
+    /// URL and references link to (on GitHub or GitLab), preferring commits
+    pub fn from_advisory(metadata: &advisory::Metadata) -> Option<Self> {
+        let links: Vec<(String, GitRef)> = metadata
+            .url
+            .iter()
+            .chain(metadata.references.iter())
+            .filter_map(|url| parse_git_link(url.as_str()))
+            .collect();
+
+        let (git, git_ref) = links
+            .iter()
+            .find(|(_, git_ref)| matches!(git_ref, GitRef::Rev(_)))
+            .or_else(|| links.first())?
+            .clone();
+
+        let (rev, branch) = match git_ref {
+            GitRef::Rev(rev) => (Some(rev), None),
+            GitRef::Branch(branch) => (None, Some(branch)),
+        };
+
+        Some(Self {
+            package: metadata.package.clone(),
+            git,
+            rev,
+            branch,
+        })
+    }
+
+    /// Render the `[patch.crates-io]` entry, e.g.
+    /// `foo = { git = "https://github.com/example/foo", rev = "0123abc" }`
+    pub fn to_toml(&self) -> String {
+        format!(
+            "{} = {}",
+            self.package,
+            self.inline_table().to_string().trim()
+        )
+    }
+
+    /// Build the inline table of the `[patch.crates-io]` entry
+    fn inline_table(&self) -> InlineTable {
+        let mut table = InlineTable::default();
+        table.get_or_insert("git", self.git.as_str());
+
+        if let Some(rev) = &self.rev {
+            table.get_or_insert("rev", rev.as_str());
+        }
+
+        if let Some(branch) = &self.branch {
+            table.get_or_insert("branch", branch.as_str());
+        }
+
+        table.fmt();
+        table
+    }
+}
+
+impl fmt::Display for GitPatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.rev, &self.branch) {
+            (Some(rev), _) => write!(f, "{} (commit {})", self.git, rev),
+            (None, Some(branch)) => write!(f, "{} (branch {})", self.git, branch),
+            (None, None) => write!(f, "{}", self.git),
+        }
+    }
+}
+
+/// Commit or branch a link points to
+#[derive(Clone, Debug)]
+enum GitRef {
+    /// Commit hash
+    Rev(String),
+
+    /// Branch name
+    Branch(String),
+}
+
+/// Parse a link to a commit or branch on GitHub or GitLab, e.g.
+/// `https://github.com/example/foo/commit/0123abc`, into the URL of the
+/// repository and the commit or branch
+fn parse_git_link(url: &str) -> Option<(String, GitRef)> {
+    let url = url.split(&['#', '?'][..]).next()?;
+    let path = url
+        .strip_prefix("https://github.com/")
+        .map(|path| ("https://github.com", path))
+        .or_else(|| {
+            url.strip_prefix("https://gitlab.com/")
+                .map(|path| ("https://gitlab.com", path))
+        });
+
+    let (host, path) = path?;
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    // Repository paths end at GitLab's `-` separator, or after `owner/repo`
+    // on GitHub (e.g. `owner/repo/pull/1/commits/0123abc`)
+    let repo_len = segments
+        .iter()
+        .position(|&segment| segment == "-")
+        .unwrap_or(2);
+
+    if repo_len < 2 || segments.len() < repo_len + 2 {
+        return None;
+    }
+
+    let repo = format!("{}/{}", host, segments[..repo_len].join("/"));
+    let rest = &segments[repo_len..];
+    let rest = if rest[0] == "-" { &rest[1..] } else { rest };
+
+    let git_ref = match rest {
+        ["commit", rev] | ["pull", _, "commits", rev] | ["merge_requests", _, "diffs", rev] => {
+            let rev = rev.trim_end_matches(".patch").trim_end_matches(".diff");
+
+            if rev.len() < 7 || !rev.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+
+            GitRef::Rev(rev.to_owned())
+        }
+        ["tree", branch @ ..] if !branch.is_empty() => GitRef::Branch(branch.join("/")),
+        _ => return None,
+    };
+
+    Some((repo, git_ref))
+}
+
+/// Editor for the `[patch.crates-io]` table of a manifest, which preserves
+/// its existing formatting and comments
+#[derive(Debug)]
+pub struct PatchEditor {
+    /// Path to the manifest
+    path: PathBuf,
+
+    /// Parsed manifest
+    document: Document,
+}
+
+impl PatchEditor {
+    /// Open the manifest at the given path
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let document = fs::read_to_string(&path)?.parse().map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("couldn't parse {}: {}", path.display(), e),
+            )
+        })?;
+
+        Ok(Self { path, document })
+    }
+
+    /// Path to the manifest
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Add the given patch, refusing to replace an existing entry for the
+    /// same crate (which may have been added deliberately)
+    pub fn add(&mut self, patch: &GitPatch) -> Result<(), Error> {
+        let path = &self.path;
+
+        // `patch` only holds the `[patch.<registry>]` tables, so it gets no
+        // header of its own
+        let mut patch_table = Table::new();
+        patch_table.set_implicit(true);
+
+        let patches = self
+            .document
+            .as_table_mut()
+            .entry("patch")
+            .or_insert(Item::Table(patch_table))
+            .as_table_mut()
+            .ok_or_else(|| invalid(path, "patch", "a table"))?
+            .entry("crates-io")
+            .or_insert(Item::Table(Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| invalid(path, "patch.crates-io", "a table"))?;
+
+        if patches.contains_key(patch.package.as_str()) {
+            return Err(Error::new(
+                ErrorKind::BadParam,
+                &format!(
+                    "{} already has a [patch.crates-io] entry for {}",
+                    path.display(),
+                    patch.package
+                ),
+            ));
+        }
+
+        *patches.entry(patch.package.as_str()) =
+            toml_edit::value(Value::InlineTable(patch.inline_table()));
+
+        Ok(())
+    }
+
+    /// Write the manifest to disk
+    pub fn save(&self) -> Result<(), Error> {
+        fs::write(&self.path, self.document.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for PatchEditor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.document)
+    }
+}
+
+/// Find the workspace manifest for the given `Cargo.toml`, where cargo reads
+/// `[patch]` entries from: the closest manifest (starting with the given
+/// one) with a `[workspace]` table, or the given manifest if there is none
+pub fn workspace_manifest(manifest_path: &Path) -> PathBuf {
+    let absolute_path = manifest_path
+        .canonicalize()
+        .unwrap_or_else(|_| manifest_path.to_owned());
+
+    if is_workspace(&absolute_path) {
+        return manifest_path.to_owned();
+    }
+
+    absolute_path
+        .parent()
+        .and_then(Path::parent)
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|candidate| is_workspace(candidate))
+        .unwrap_or_else(|| manifest_path.to_owned())
+}
+
+/// Does the manifest at the given path have a `[workspace]` table?
+fn is_workspace(manifest_path: &Path) -> bool {
+    fs::read_to_string(manifest_path)
+        .ok()
+        .and_then(|toml| toml.parse::<Document>().ok())
+        .map_or(false, |document| {
+            document.as_table().contains_table("workspace")
+        })
+}
+
+/// Error for manifests whose structure we don't know how to edit
+fn invalid(path: &Path, key: &str, expected: &str) -> Error {
+    Error::new(
+        ErrorKind::Parse,
+        &format!("{}: expected `{}` to be {}", path.display(), key, expected),
+    )
+}
@@ -6,3 +6,6 @@ pub use abscissa_core::prelude::*;
 
 /// Application state accessors
 pub use crate::application::{app_config, app_reader, app_writer};
+
+/// Status macros which handle write errors (shadowing Abscissa's)
+pub use crate::{status_ok, status_warn};
@@ -1,62 +1,202 @@
 //! Presenter for `rustsec::Report` information.
 
 use crate::{
-    config::{DenyOption, OutputConfig, OutputFormat},
+    badge::Badge,
+    binaries,
+    config::{DenyOption, FailThreshold, OutputConfig, OutputFormat, SortOrder},
+    epss, fix_plan, history, html, image, installed, junit, lint, manifest, markdown, metrics,
+    ndjson, osv, output,
     prelude::*,
+    reporter::{self, ReportRenderer},
+    sarif, spdx, state,
+    suppressions::Suppression,
+    template::Template,
+    text, verify, whats_new, workspaces,
 };
 use abscissa_core::terminal::{
     self,
-    Color::{self, Red, Yellow},
+    Color::{self, Green, Magenta, Red, Yellow},
 };
-use rustsec::cargo_lock::{
-    dependency::{self, graph::EdgeDirection, Dependency},
-    Lockfile, Package,
+use rustsec::{
+    advisory,
+    cargo_lock::{
+        dependency::{self, graph::EdgeDirection, Dependency},
+        package, Lockfile, Package,
+    },
+    report::{self, DatabaseInfo, Outcome},
+    vulnerability::{
+        Dependent, Explanation, PatchedRelease, Reachability, RequirementMatch, Resolution, Scope,
+        Scopes, Upgrade,
+    },
+    warning, Error, Version,
+};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    fs, io,
+    path::Path,
 };
-use std::{collections::BTreeSet as Set, io, path::Path};
 
 use std::io::Write as _;
 use std::string::ToString as _;
 
 /// Vulnerability information presenter
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Presenter {
     /// Keep track packages we've displayed once so we don't show the same dep tree
-    // TODO(tarcieri): group advisories about the same package?
     displayed_packages: Set<Dependency>,
 
+    /// Names and versions shared by packages from more than one source, which
+    /// are displayed along with their source to tell them apart
+    ambiguous_packages: Set<(package::Name, Version)>,
+
+    /// Advisories reported on, mapped to the other reported advisories they're
+    /// related to (in either direction)
+    related_findings: Map<advisory::Id, Set<advisory::Id>>,
+
     /// Keep track of the warning kinds that correspond to deny-warnings options
-    deny_warning_kinds: Set<rustsec::warning::Kind>,
+    deny_warning_kinds: Set<warning::Kind>,
+
+    /// Differences between the findings and those recorded in the state
+    /// file (if one is configured)
+    changes: Option<state::Changes>,
+
+    /// Ignored advisories which match the audited packages
+    suppressions: Vec<Suppression>,
+
+    /// Exit status of the audit being reported (if the auditor chose one),
+    /// for reports of denied warnings, which exit by themselves
+    exit_code: Option<i32>,
 
     /// Output configuration
     config: OutputConfig,
+
+    /// Width to wrap the report to
+    width: usize,
+
+    /// Width to truncate the lines of dependency trees to (if it's the
+    /// terminal's or was given, outside of plain mode)
+    tree_width: Option<usize>,
+
+    /// Render advisory IDs and URLs as hyperlinks?
+    hyperlinks: bool,
+
+    /// Template to render vulnerabilities with (if one is configured)
+    template: Option<Template>,
+
+    /// Renderer for reports, overriding the output format (if any)
+    renderer: Option<Box<dyn ReportRenderer>>,
 }
 
 impl Presenter {
     /// Create a new vulnerability information presenter
     pub fn new(config: &OutputConfig) -> Self {
+        // Plain reports don't depend on the terminal, so they're never
+        // wrapped (or truncated)
+        let known_width = if config.plain {
+            None
+        } else {
+            config.width.or_else(text::terminal_width)
+        };
+
+        let width = match known_width {
+            Some(width) => width,
+            None if config.plain => usize::MAX,
+            None => text::DEFAULT_WIDTH,
+        };
+
         Self {
             displayed_packages: Set::new(),
+            ambiguous_packages: Set::new(),
+            related_findings: Map::new(),
+            changes: None,
+            suppressions: vec![],
+            exit_code: None,
             deny_warning_kinds: config
                 .deny
                 .iter()
                 .filter_map(|k| k.get_warning_kind())
                 .collect(),
             config: config.clone(),
+            width,
+            tree_width: known_width,
+            hyperlinks: !config.plain && config.hyperlinks.is_enabled(),
+            // Templates are checked when the configuration is validated
+            template: config
+                .template
+                .as_ref()
+                .and_then(|path| Template::load(path).ok()),
+            renderer: config
+                .reporter
+                .as_ref()
+                .map(|program| -> Box<dyn ReportRenderer> {
+                    Box::new(reporter::Command::new(
+                        program.as_str(),
+                        config.reporter_args.clone(),
+                    ))
+                }),
         }
     }
 
-    /// Information to display before a report is generated
-    pub fn before_report(&mut self, lockfile_path: &Path, lockfile: &Lockfile) {
+    /// Render reports with the given renderer rather than the configured
+    /// output format (or reporter command)
+    pub fn set_renderer(&mut self, renderer: Box<dyn ReportRenderer>) {
+        self.renderer = Some(renderer);
+    }
+
+    /// Information to display before a report is generated (or replayed
+    /// from the cache)
+    pub fn before_report(
+        &mut self,
+        lockfile_path: &Path,
+        lockfile: &Lockfile,
+        database: &DatabaseInfo,
+        cached: bool,
+    ) {
+        let mut seen = Set::new();
+
+        for package in &lockfile.packages {
+            if !seen.insert((&package.name, &package.version)) {
+                self.ambiguous_packages
+                    .insert((package.name.clone(), package.version.clone()));
+            }
+        }
+
         if !self.config.is_quiet() {
             status_ok!(
                 "Scanning",
-                "{} for vulnerabilities ({} crate dependencies)",
+                "{} for vulnerabilities ({} crate dependencies){}",
                 lockfile_path.display(),
                 lockfile.packages.len(),
+                if cached { " (cached)" } else { "" }
             );
+
+            // Machine-readable reports include this in their `database` field
+            if !self.config.is_machine_readable() {
+                status_ok!("Using", "advisory database: {}", database_summary(database));
+            }
         }
     }
 
+    /// Mark findings which are new since the run recorded in the state
+    /// file (or the baseline report), and list the recorded ones which were
+    /// resolved
+    pub fn show_changes(&mut self, changes: Option<state::Changes>) {
+        self.changes = changes;
+    }
+
+    /// Summarize the given ignored advisories, which match the audited
+    /// packages, at the end of the report
+    pub fn show_suppressions(&mut self, suppressions: Vec<Suppression>) {
+        self.suppressions = suppressions;
+    }
+
+    /// Exit with the given status when the report fails the audit by itself
+    /// (rather than the status configured for its outcome)
+    pub fn set_exit_code(&mut self, code: i32) {
+        self.exit_code = Some(code);
+    }
+
     fn warning_word(&self, count: u64) -> &str {
         if count != 1 {
             "warnings"
@@ -65,223 +205,3338 @@ impl Presenter {
         }
     }
 
-    /// Print the vulnerability report generated by an audit
+    /// Describe a number of warnings, with the number of each kind, e.g.
+    /// `3 allowed warnings found (1 unsound, 2 yanked), affecting 4 package
+    /// versions`
+    fn warning_summary(
+        &self,
+        adjective: &str,
+        kinds: &[(warning::Kind, u64)],
+        versions: u64,
+    ) -> String {
+        let count = kinds.iter().map(|(_, count)| count).sum();
+        let mut summary = format!("{} {} {} found", count, adjective, self.warning_word(count));
+
+        if kinds.len() > 1 {
+            let kinds = kinds
+                .iter()
+                .map(|(kind, count)| format!("{} {}", count, kind))
+                .collect::<Vec<_>>();
+
+            summary.push_str(&format!(" ({})", kinds.join(", ")));
+        }
+
+        if versions != count {
+            summary.push_str(&format!(", affecting {} package versions", versions));
+        }
+
+        summary
+    }
+
+    /// Print the vulnerability report generated by an audit.
+    ///
+    /// Dependency trees are omitted if `tree` is `None` (i.e. the lockfile's
+    /// dependency graph is invalid).
     pub fn print_report(
         &mut self,
         report: &rustsec::Report,
         self_advisories: &[rustsec::Advisory],
-        lockfile: &Lockfile,
-    ) {
-        if self.config.format == OutputFormat::Json {
-            serde_json::to_writer(io::stdout(), &report).unwrap();
-            io::stdout().flush().unwrap();
-            return;
-        }
+        tree: Option<&dependency::Tree>,
+    ) -> Result<(), Error> {
+        let result = if let Some(mut renderer) = self.renderer.take() {
+            let result = self
+                .machine_output()
+                .and_then(|mut w| reporter::render(renderer.as_mut(), &mut w, report, tree));
+            self.renderer = Some(renderer);
+            result
+        } else {
+            match self.config.format {
+                OutputFormat::Json => self
+                    .machine_output()
+                    .and_then(|mut w| write_json_report(&mut w, report)),
+                OutputFormat::Ndjson => self
+                    .machine_output()
+                    .and_then(|mut w| ndjson::write(&mut w, report, tree)),
+                OutputFormat::Badge => self.machine_output().and_then(|mut w| {
+                    write_json_report(&mut w, &Badge::new(report, &self.config.badge))
+                }),
+                OutputFormat::Sarif => self
+                    .machine_output()
+                    .and_then(|mut w| write_json_report(&mut w, &sarif::Log::new(report))),
+                OutputFormat::Markdown => self
+                    .machine_output()
+                    .and_then(|mut w| markdown::write(&mut w, report, tree)),
+                OutputFormat::Junit => self
+                    .machine_output()
+                    .and_then(|mut w| junit::write(&mut w, report, &self.config)),
+                OutputFormat::Osv => self
+                    .machine_output()
+                    .and_then(|mut w| write_json_report(&mut w, &osv::Results::new(report))),
+                OutputFormat::Spdx => self.machine_output().and_then(|mut w| {
+                    write_json_report(&mut w, &spdx::Document::new(report, tree))
+                }),
+                OutputFormat::FixPlan => self
+                    .machine_output()
+                    .and_then(|mut w| write_json_report(&mut w, &fix_plan::plan(report, tree))),
+                OutputFormat::Metrics => self
+                    .machine_output()
+                    .and_then(|mut w| metrics::write(&mut w, report)),
+                OutputFormat::Html => self
+                    .machine_output()
+                    .and_then(|mut w| html::write(&mut w, report, tree)),
+                OutputFormat::Terminal => self
+                    .write_report(report, self_advisories, tree)
+                    .and_then(|()| self.write_json_file(report)),
+            }
+        };
 
-        // We'll set this to true if (e.g.) we see a warning and have deny-warnings enabled.
-        // Once we've printed the whole report, we'll bail out of the whole program.
-        let mut exit_with_failure = false;
+        output::report_result(result, "report")
+    }
 
-        let tree = lockfile
-            .dependency_tree()
-            .expect("invalid Cargo.lock dependency tree");
+    /// Print the differences between the findings of two audits
+    pub fn print_diff(&mut self, diff: &report::Diff) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, diff))
+        } else {
+            self.write_diff(diff, true)
+        };
 
-        // Print out vulnerabilities and warnings
-        for vulnerability in &report.vulnerabilities.list {
-            self.print_vulnerability(vulnerability, &tree);
-        }
+        output::report_result(result, "diff")
+    }
+
+    /// Print the findings introduced and resolved between two audits (e.g.
+    /// in watch mode), leaving out the unchanged ones
+    pub fn print_delta(&mut self, diff: &report::Diff) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            let delta = report::Diff {
+                introduced: diff.introduced.clone(),
+                resolved: diff.resolved.clone(),
+                unchanged: Default::default(),
+            };
+
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, &delta))
+        } else {
+            self.write_diff(diff, false)
+        };
+
+        output::report_result(result, "changes")
+    }
+
+    /// Print the advisories matching a manifest's dependency requirements
+    pub fn print_manifest_report(&mut self, report: &manifest::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
+        } else {
+            self.write_manifest_report(report)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write the human-readable manifest report to STDOUT, stating that it
+    /// isn't based on a lockfile both before and after the matches
+    fn write_manifest_report(&mut self, report: &manifest::Report) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            let mut skipped = String::new();
 
-        for warnings in report.warnings.values() {
-            for warning in warnings.iter() {
-                self.print_warning(warning, &tree)
+            if !report.skipped.is_empty() {
+                let reasons = report
+                    .skipped
+                    .iter()
+                    .map(|(reason, count)| format!("{} {}", count, reason.as_str()))
+                    .collect::<Vec<_>>();
+
+                skipped = format!(", skipped {}", reasons.join(", "));
             }
+
+            status_ok!(
+                "Scanning",
+                "{} dependency requirements ({} checked{})",
+                report.manifest.path.display(),
+                report.dependencies,
+                skipped
+            );
+            status_ok!(
+                "Using",
+                "advisory database: {}",
+                database_summary(&report.database)
+            );
+            status_warn!("{}", report.caveat);
         }
 
-        // Print out any self-advisories
-        if !self_advisories.is_empty() {
-            let msg = "This copy of cargo-audit has known advisories!";
+        for found in &report.matches {
+            self.print_attr(Red, "Crate:        ", &found.package)?;
 
-            if self.config.deny.contains(&DenyOption::Warnings) {
-                status_err!(msg);
+            let kind = if found.kind == Scope::Build {
+                " (build dependency)"
             } else {
-                status_warn!(msg);
-            }
+                ""
+            };
 
-            for advisory in self_advisories {
-                self.print_metadata(
-                    &advisory.metadata,
-                    self.warning_color(self.config.deny.contains(&DenyOption::Warnings)),
-                );
+            if found.dependency == found.package.as_str() {
+                self.print_attr(
+                    Red,
+                    "Requirement:  ",
+                    format!("{}{}", found.requirement, kind),
+                )?;
+            } else {
+                self.print_attr(
+                    Red,
+                    "Requirement:  ",
+                    format!("{}{} (as `{}`)", found.requirement, kind, found.dependency),
+                )?;
             }
-            println!();
-        }
 
-        if report.vulnerabilities.found {
-            if report.vulnerabilities.count == 1 {
-                status_err!("1 vulnerability found!");
+            self.print_metadata(&found.advisory, Red)?;
+
+            if found.versions.patched().is_empty() {
+                self.print_attr(Red, "Solution:     ", "No safe upgrade is available!")?;
             } else {
-                status_err!("{} vulnerabilities found!", report.vulnerabilities.count);
+                self.print_attr(
+                    Red,
+                    "Solution:     ",
+                    format!(
+                        "Require {}",
+                        found
+                            .versions
+                            .patched()
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(" OR ")
+                    ),
+                )?;
             }
+
+            writeln!(io::stdout())?;
         }
 
-        // Count up the warnings, sorting into denied and allowed
-        let mut num_denied: u64 = 0;
-        let mut num_not_denied: u64 = 0;
+        match report.matches.len() {
+            0 => status_ok!(
+                "Success",
+                "no dependency requirements match known vulnerabilities ({})",
+                report.caveat
+            ),
+            1 => status_err!(
+                "1 vulnerability may affect these requirements ({})",
+                report.caveat
+            ),
+            count => status_err!(
+                "{} vulnerabilities may affect these requirements ({})",
+                count,
+                report.caveat
+            ),
+        }
 
-        for (kind, warnings) in report.warnings.iter() {
-            if self.deny_warning_kinds.contains(kind) {
-                num_denied += warnings.len() as u64;
-            } else {
-                num_not_denied += warnings.len() as u64;
+        Ok(())
+    }
+
+    /// Print the report of an audit of the installed binaries
+    pub fn print_installed(&mut self, report: &installed::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
+        } else {
+            self.write_installed(report)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write the human-readable report for each installed binary to STDOUT,
+    /// followed by a summary of them all
+    fn write_installed(&mut self, report: &installed::Report) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            let mut skipped = String::new();
+
+            if !report.skipped.is_empty() {
+                let reasons = report
+                    .skipped
+                    .iter()
+                    .map(|(reason, count)| format!("{} {}", count, reason.as_str()))
+                    .collect::<Vec<_>>();
+
+                skipped = format!(", skipped {}", reasons.join(", "));
             }
+
+            status_ok!(
+                "Scanning",
+                "{} for installed binaries ({} audited{})",
+                report.root.join("bin").display(),
+                report.binaries.len(),
+                skipped
+            );
+            status_ok!(
+                "Using",
+                "advisory database: {}",
+                database_summary(&report.database)
+            );
         }
 
-        if num_denied > 0 || num_not_denied > 0 {
-            if num_denied > 0 {
-                status_err!(
-                    "{} denied {} found!",
-                    num_denied,
-                    self.warning_word(num_denied)
-                );
-                exit_with_failure = true;
-            }
-            if num_not_denied > 0 {
-                status_warn!(
-                    "{} allowed {} found",
-                    num_not_denied,
-                    self.warning_word(num_not_denied)
-                );
-            }
+        for binary in &report.binaries {
+            let package = match &binary.package {
+                Some(package) => format!("{} {}, ", package.name, package.version),
+                None => String::new(),
+            };
+
+            status_ok!(
+                "Binary",
+                "{} ({}{})",
+                binary.name,
+                package,
+                binary.data.as_str()
+            );
+
+            self.write_binary_findings(binary)?;
         }
 
-        if !self_advisories.is_empty() {
-            let upgrade_msg = "upgrade cargo-audit to the latest version: \
-                               cargo install --force cargo-audit";
+        let vulnerable = report.vulnerable_count();
 
-            if self.config.deny.contains(&DenyOption::Warnings) {
-                status_err!(upgrade_msg);
-                exit_with_failure = true;
+        if vulnerable == 0 {
+            status_ok!(
+                "Success",
+                "no vulnerabilities found in {} installed {}",
+                report.binaries.len(),
+                if report.binaries.len() == 1 {
+                    "binary"
+                } else {
+                    "binaries"
+                }
+            );
+            return Ok(());
+        }
+
+        let count = report.vulnerability_count();
+        status_err!(
+            "{} {} found in {} of {} installed binaries!",
+            count,
+            if count == 1 {
+                "vulnerability"
             } else {
-                status_warn!(upgrade_msg);
-            }
+                "vulnerabilities"
+            },
+            vulnerable,
+            report.binaries.len()
+        );
+
+        for reinstall in &report.reinstall {
+            status_warn!(
+                "updates resolve the vulnerabilities in {}: {}",
+                reinstall.binaries.join(", "),
+                reinstall.command
+            );
         }
 
-        // TODO(tarcieri): better unify this with vulnerabilities handling
-        if exit_with_failure {
-            std::process::exit(1);
+        Ok(())
+    }
+
+    /// Write the vulnerabilities and warnings found in an audited binary
+    fn write_binary_findings(&mut self, binary: &installed::Binary) -> io::Result<()> {
+        self.related_findings = related_findings(&binary.report);
+
+        let mut vulnerabilities: Vec<_> = binary.report.vulnerabilities.list.iter().collect();
+        sort_vulnerabilities(&mut vulnerabilities, self.config.sort);
+
+        self.print_vulnerabilities(&vulnerabilities, None)?;
+
+        for warnings in binary.report.warnings.values() {
+            let mut warnings: Vec<_> = warnings.iter().collect();
+            sort_warnings(&mut warnings, self.config.sort);
+
+            for group in group_warnings(&warnings) {
+                self.print_warning(&group, None)?;
+            }
         }
+
+        Ok(())
     }
 
-    /// Print information about the given vulnerability
-    fn print_vulnerability(
-        &mut self,
-        vulnerability: &rustsec::Vulnerability,
-        tree: &dependency::Tree,
-    ) {
-        self.print_attr(Red, "Crate:        ", &vulnerability.package.name);
-        self.print_attr(
-            Red,
-            "Version:      ",
-            &vulnerability.package.version.to_string(),
-        );
-        self.print_metadata(&vulnerability.advisory, Red);
+    /// Print the report of the audits of the binaries in a container image
+    pub fn print_image(&mut self, report: &image::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
+        } else {
+            self.write_image(report)
+        };
 
-        if vulnerability.versions.patched().is_empty() {
-            self.print_attr(Red, "Solution:     ", "No safe upgrade is available!");
+        output::report_result(result, "report")
+    }
+
+    /// Write the human-readable report for each binary in an image to
+    /// STDOUT, followed by a summary of them all
+    fn write_image(&mut self, report: &image::Report) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            let digest = match &report.digest {
+                Some(digest) => format!(" ({})", digest),
+                None => String::new(),
+            };
+
+            status_ok!(
+                "Scanning",
+                "image {}{} for Rust binaries ({} audited, skipped {} without audit data)",
+                report.image,
+                digest,
+                report.binaries.len(),
+                report.skipped
+            );
+            status_ok!(
+                "Using",
+                "advisory database: {}",
+                database_summary(&report.database)
+            );
+        }
+
+        self.write_binaries(&report.binaries, " in the image")
+    }
+
+    /// Print the combined report of the audits of the binaries in a set of
+    /// files and directories
+    pub fn print_binaries(&mut self, report: &binaries::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
         } else {
-            self.print_attr(
-                Red,
-                "Solution:     ",
-                format!(
-                    "Upgrade to {}",
-                    vulnerability
-                        .versions
-                        .patched()
-                        .iter()
-                        .map(ToString::to_string)
-                        .collect::<Vec<_>>()
-                        .as_slice()
-                        .join(" OR ")
-                ),
+            self.write_binaries_report(report)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write the human-readable report for each binary found in a set of
+    /// files and directories to STDOUT, followed by a summary of them all
+    fn write_binaries_report(&mut self, report: &binaries::Report) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            let paths = report
+                .paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>();
+
+            status_ok!(
+                "Scanning",
+                "{} for Rust binaries ({} audited, skipped {} without audit data)",
+                paths.join(", "),
+                report.binaries.len(),
+                report.skipped
+            );
+            status_ok!(
+                "Using",
+                "advisory database: {}",
+                database_summary(&report.database)
             );
         }
 
-        self.print_tree(Red, &vulnerability.package, tree);
-        println!();
+        self.write_binaries(&report.binaries, "")
     }
 
-    /// Print information about a given warning
-    fn print_warning(&mut self, warning: &rustsec::Warning, tree: &dependency::Tree) {
-        let color = self.warning_color(self.deny_warning_kinds.contains(&warning.kind));
+    /// Write the findings for each of the given binaries, by path, followed
+    /// by a summary of them all (which ends with the given location, e.g.
+    /// ` in the image`)
+    fn write_binaries(&mut self, binaries: &[installed::Binary], location: &str) -> io::Result<()> {
+        for binary in binaries {
+            let package = match &binary.package {
+                Some(package) => format!("{} {}, ", package.name, package.version),
+                None => String::new(),
+            };
 
-        self.print_attr(color, "Crate:        ", &warning.package.name);
-        self.print_attr(
-            color,
-            "Version:      ",
-            &warning.package.version.to_string(),
-        );
-        self.print_attr(color, "Warning:      ", warning.kind.as_str());
+            status_ok!(
+                "Binary",
+                "{} ({}{})",
+                binary.path.display(),
+                package,
+                binary.data.as_str()
+            );
 
-        if let Some(metadata) = &warning.advisory {
-            self.print_metadata(metadata, color)
+            self.write_binary_findings(binary)?;
+        }
+
+        let vulnerable = binaries
+            .iter()
+            .filter(|binary| binary.report.vulnerabilities.found)
+            .count();
+
+        if vulnerable == 0 {
+            status_ok!(
+                "Success",
+                "no vulnerabilities found in {} {}{}",
+                binaries.len(),
+                if binaries.len() == 1 {
+                    "binary"
+                } else {
+                    "binaries"
+                },
+                location
+            );
+            return Ok(());
         }
 
-        self.print_tree(color, &warning.package, tree);
-        println!();
+        let count: usize = binaries
+            .iter()
+            .map(|binary| binary.report.vulnerabilities.count)
+            .sum();
+        status_err!(
+            "{} {} found in {} of {} binaries{}!",
+            count,
+            if count == 1 {
+                "vulnerability"
+            } else {
+                "vulnerabilities"
+            },
+            vulnerable,
+            binaries.len(),
+            location
+        );
+
+        Ok(())
     }
 
-    /// Get the color to use when displaying warnings
-    fn warning_color(&self, deny_warning: bool) -> Color {
-        if deny_warning {
-            Red
+    /// Print the combined report of the audits of the lockfiles under a
+    /// directory
+    pub fn print_workspaces(&mut self, report: &workspaces::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
         } else {
-            Yellow
-        }
+            self.write_workspaces(report)
+                .and_then(|()| self.write_json_file(report))
+        };
+
+        output::report_result(result, "report")
     }
 
-    /// Print a warning about a particular advisory
-    fn print_metadata(&self, metadata: &rustsec::advisory::Metadata, color: Color) {
-        self.print_attr(color, "Title:        ", &metadata.title);
-        self.print_attr(color, "Date:         ", &metadata.date);
-        self.print_attr(color, "ID:           ", &metadata.id);
+    /// Write a section with the human-readable report for each lockfile to
+    /// STDOUT, followed by a summary of them all
+    fn write_workspaces(&mut self, report: &workspaces::Report) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            status_ok!(
+                "Scanning",
+                "{} for lockfiles ({} found)",
+                report.root.display(),
+                report.lockfiles.len()
+            );
+            status_ok!(
+                "Using",
+                "advisory database: {}",
+                database_summary(&report.database)
+            );
+        }
+
+        for audited in &report.lockfiles {
+            let lockfile_report = match (&audited.report, &audited.error) {
+                (Some(lockfile_report), _) => lockfile_report,
+                (None, error) => {
+                    status_err!(
+                        "couldn't audit {}: {}",
+                        audited.path.display(),
+                        error.as_deref().unwrap_or_default()
+                    );
+                    continue;
+                }
+            };
+
+            let count = lockfile_report.vulnerabilities.count;
+            let status = if count == 0 {
+                "no vulnerabilities".to_owned()
+            } else if count == 1 {
+                "1 vulnerability".to_owned()
+            } else {
+                format!("{} vulnerabilities", count)
+            };
 
-        if let Some(url) = metadata.id.url() {
-            self.print_attr(color, "URL:          ", &url);
-        } else if let Some(url) = &metadata.url {
-            self.print_attr(color, "URL:          ", url);
+            status_ok!("Lockfile", "{} ({})", audited.path.display(), status);
+
+            self.related_findings = related_findings(lockfile_report);
+
+            let mut vulnerabilities: Vec<_> = lockfile_report.vulnerabilities.list.iter().collect();
+            sort_vulnerabilities(&mut vulnerabilities, self.config.sort);
+
+            self.print_vulnerabilities(&vulnerabilities, None)?;
+
+            for warnings in lockfile_report.warnings.values() {
+                let mut warnings: Vec<_> = warnings.iter().collect();
+                sort_warnings(&mut warnings, self.config.sort);
+
+                for group in group_warnings(&warnings) {
+                    self.print_warning(&group, None)?;
+                }
+            }
+        }
+
+        let lockfiles = report.lockfiles.len();
+        let plural = if lockfiles == 1 { "" } else { "s" };
+        let vulnerable = report.vulnerable_count();
+        let errors = report.error_count();
+
+        if errors > 0 {
+            status_err!(
+                "{} of {} lockfile{} couldn't be audited",
+                errors,
+                lockfiles,
+                plural
+            );
+        }
+
+        if vulnerable == 0 {
+            if errors == 0 {
+                status_ok!(
+                    "Success",
+                    "no vulnerabilities found in {} lockfile{}",
+                    lockfiles,
+                    plural
+                );
+            }
+
+            return Ok(());
         }
+
+        let count = report.vulnerability_count();
+        status_err!(
+            "{} {} found in {} of {} lockfile{}!",
+            count,
+            if count == 1 {
+                "vulnerability"
+            } else {
+                "vulnerabilities"
+            },
+            vulnerable,
+            lockfiles,
+            plural
+        );
+
+        Ok(())
     }
 
-    /// Display an attribute of a particular vulnerability
-    fn print_attr(&self, color: Color, attr: &str, content: impl AsRef<str>) {
-        terminal::status::Status::new()
-            .bold()
-            .color(color)
-            .status(attr)
-            .print_stdout(content.as_ref())
-            .unwrap();
+    /// Print the advisories added since a reference point
+    pub fn print_whats_new(&mut self, report: &whats_new::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
+        } else {
+            self.write_whats_new(report)
+        };
+
+        output::report_result(result, "report")
     }
 
-    /// Print the inverse dependency tree to standard output
-    fn print_tree(&mut self, color: Color, package: &Package, tree: &dependency::Tree) {
-        // Only show the tree once per package
-        if !self.displayed_packages.insert(Dependency::from(package)) {
-            return;
+    /// Write the human-readable list of new advisories to STDOUT, with the
+    /// ones affecting the lockfiles first
+    fn write_whats_new(&mut self, report: &whats_new::Report) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            status_ok!(
+                "Comparing",
+                "advisory database ({}) against {}",
+                database_summary(&report.database),
+                report.since.description()
+            );
         }
 
-        if !self.config.show_tree.unwrap_or(true) {
-            return;
+        let mut advisories: Vec<_> = report.advisories.iter().collect();
+        advisories.sort_by_key(|new| new.matches.is_empty());
+
+        for new in advisories {
+            let color = if new.matches.is_empty() { Yellow } else { Red };
+
+            self.print_attr(color, "Crate:        ", &new.advisory.package)?;
+            self.print_metadata(&new.advisory, color)?;
+
+            if !new.matches.is_empty() {
+                let matches = new
+                    .matches
+                    .iter()
+                    .map(|found| {
+                        format!(
+                            "{} {} ({})",
+                            found.package,
+                            found.version,
+                            found.lockfile.display()
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                self.print_lines(color, "Affects:      ", &matches)?;
+            }
+
+            writeln!(io::stdout())?;
         }
 
-        terminal::status::Status::new()
-            .bold()
-            .color(color)
-            .status("Dependency tree:")
-            .print_stdout("")
-            .unwrap();
+        let count = report.advisories.len();
+        let matching = report.matching_count();
+        let summary = format!(
+            "{} new {} since {}",
+            count,
+            if count == 1 { "advisory" } else { "advisories" },
+            report.since.description()
+        );
+
+        if report.lockfiles.is_empty() {
+            status_ok!("Found", summary);
+        } else if matching == 0 {
+            status_ok!("Success", "{}, none affecting the given lockfiles", summary);
+        } else {
+            status_err!("{}, {} affecting the given lockfiles!", summary, matching);
+        }
 
-        let package_node = tree.nodes()[&Dependency::from(package)];
-        tree.render(&mut io::stdout(), package_node, EdgeDirection::Incoming)
-            .unwrap();
+        Ok(())
     }
+
+    /// Print the advisories found by `cargo audit search`
+    pub fn print_search(&mut self, advisories: &[&rustsec::Advisory]) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, &advisories))
+        } else {
+            self.write_search(advisories)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write the human-readable list of advisories found by a search
+    fn write_search(&mut self, advisories: &[&rustsec::Advisory]) -> io::Result<()> {
+        for advisory in advisories {
+            let color = match advisory.severity() {
+                Some(advisory::Severity::None) | Some(advisory::Severity::Low) | None => Yellow,
+                Some(_) => Red,
+            };
+
+            self.print_attr(color, "Crate:        ", &advisory.metadata.package)?;
+            self.print_metadata(&advisory.metadata, color)?;
+
+            if let Some(cvss) = &advisory.metadata.cvss {
+                self.print_attr(
+                    color,
+                    "Severity:     ",
+                    format!("{} ({:.1})", cvss.severity(), cvss.score().value()),
+                )?;
+            }
+
+            writeln!(io::stdout())?;
+        }
+
+        let count = advisories.len();
+        status_ok!(
+            "Found",
+            "{} matching {}",
+            count,
+            if count == 1 { "advisory" } else { "advisories" }
+        );
+
+        Ok(())
+    }
+
+    /// Print the timeline of the audits of a lockfile at git revisions
+    pub fn print_history(
+        &mut self,
+        lockfile_path: &Path,
+        database: &DatabaseInfo,
+        revisions: &[history::Revision],
+    ) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, &revisions))
+        } else {
+            self.write_history(lockfile_path, database, revisions)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write the timeline of a lockfile's audits to STDOUT: a row for each
+    /// revision with the advisories it matched (marking those introduced
+    /// there, and any resolved), then the period each advisory matched in
+    fn write_history(
+        &mut self,
+        lockfile_path: &Path,
+        database: &DatabaseInfo,
+        revisions: &[history::Revision],
+    ) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            status_ok!(
+                "Auditing",
+                "{} at {} git {}",
+                lockfile_path.display(),
+                revisions.len(),
+                if revisions.len() == 1 {
+                    "revision"
+                } else {
+                    "revisions"
+                }
+            );
+            status_ok!("Using", "advisory database: {}", database_summary(database));
+        }
+
+        let width = revisions
+            .iter()
+            .map(|revision| revision.revision.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(12);
+
+        for revision in revisions {
+            let label = format!("{:<width$}", revision.revision, width = width + 2);
+
+            let (color, findings) = match &revision.skipped {
+                Some(reason) => (Yellow, format!("skipped: {}", reason)),
+                None => {
+                    let mut findings = revision
+                        .advisories
+                        .keys()
+                        .map(|id| {
+                            if revision.introduced.contains(id) {
+                                format!("{} (new)", id)
+                            } else {
+                                id.to_string()
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    if findings.is_empty() {
+                        findings.push_str("no advisories");
+                    }
+
+                    if !revision.resolved.is_empty() {
+                        let resolved = revision
+                            .resolved
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>();
+
+                        findings.push_str(&format!("; resolved {}", resolved.join(", ")));
+                    }
+
+                    if !revision.introduced.is_empty() {
+                        (Red, findings)
+                    } else if !revision.resolved.is_empty() || revision.advisories.is_empty() {
+                        (Green, findings)
+                    } else {
+                        (Yellow, findings)
+                    }
+                }
+            };
+
+            self.print_attr(
+                color,
+                &label,
+                format!("{} {}  {}", &revision.commit[..7], revision.date, findings),
+            )?;
+        }
+
+        let exposures = history::exposures(revisions);
+
+        if !exposures.is_empty() {
+            writeln!(io::stdout())?;
+
+            terminal::status::Status::new()
+                .bold()
+                .color(Red)
+                .status("Exposures:")
+                .print_stdout(format!(
+                    "{} {}",
+                    exposures.len(),
+                    if exposures.len() == 1 {
+                        "period"
+                    } else {
+                        "periods"
+                    }
+                ))
+                .map_err(output::io_error)?;
+
+            writeln!(io::stdout())?;
+
+            for exposure in &exposures {
+                let period = match &exposure.resolved {
+                    Some(resolved) => format!(
+                        "introduced in {}, resolved in {}",
+                        exposure.introduced, resolved
+                    ),
+                    None => format!("introduced in {}, still present", exposure.introduced),
+                };
+
+                self.print_attr(
+                    if exposure.resolved.is_some() {
+                        Yellow
+                    } else {
+                        Red
+                    },
+                    &format!("{:<21}", exposure.id.as_str()),
+                    period,
+                )?;
+            }
+        }
+
+        let skipped = revisions
+            .iter()
+            .filter(|revision| !revision.is_audited())
+            .count();
+
+        let advisories: Set<_> = revisions
+            .iter()
+            .flat_map(|revision| revision.advisories.keys())
+            .collect();
+
+        let summary = format!(
+            "{} {} audited ({} skipped), {} {} matched",
+            revisions.len() - skipped,
+            if revisions.len() - skipped == 1 {
+                "revision"
+            } else {
+                "revisions"
+            },
+            skipped,
+            advisories.len(),
+            if advisories.len() == 1 {
+                "advisory"
+            } else {
+                "advisories"
+            }
+        );
+
+        if advisories.is_empty() {
+            status_ok!("Success", summary);
+        } else {
+            status_warn!(summary);
+        }
+
+        Ok(())
+    }
+
+    /// Print the results of the integrity checks of a lockfile
+    pub fn print_verify(&mut self, report: &verify::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
+        } else {
+            self.write_verify(report)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write a human-readable table of the results of the integrity checks
+    /// to STDOUT, with each check's failures under it
+    fn write_verify(&mut self, report: &verify::Report) -> io::Result<()> {
+        if !self.config.is_quiet() {
+            status_ok!("Verifying", "{}", report.lockfile.display());
+        }
+
+        for check in &report.checks {
+            let (color, status) = match check.status {
+                verify::Status::Passed => (Green, format!("passed ({} checked)", check.checked)),
+                verify::Status::Failed => (
+                    Red,
+                    format!(
+                        "FAILED ({} checked, {} {})",
+                        check.checked,
+                        check.failures.len(),
+                        if check.failures.len() == 1 {
+                            "failure"
+                        } else {
+                            "failures"
+                        }
+                    ),
+                ),
+                verify::Status::Skipped => (
+                    Yellow,
+                    format!("skipped ({})", check.reason.as_deref().unwrap_or_default()),
+                ),
+            };
+
+            let mut lines = vec![status];
+            lines.extend(check.failures.iter().map(|failure| {
+                match (&failure.package, &failure.version) {
+                    (Some(package), Some(version)) => {
+                        format!("{} {}: {}", package, version, failure.message)
+                    }
+                    _ => failure.message.clone(),
+                }
+            }));
+
+            self.print_lines(
+                color,
+                &format!("{:<14}", format!("{}:", check.check)),
+                &lines,
+            )?;
+        }
+
+        let failures = report.failure_count();
+
+        if report.is_failure() {
+            status_err!(
+                "{} integrity {} found in {}!",
+                failures,
+                if failures == 1 { "failure" } else { "failures" },
+                report.lockfile.display()
+            );
+        } else {
+            status_ok!("Success", "no integrity failures found");
+        }
+
+        Ok(())
+    }
+
+    /// Print the results of linting advisory files
+    pub fn print_lint(&mut self, report: &lint::Report) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, report))
+        } else {
+            self.write_lint(report)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write the results of linting advisory files to STDOUT, with each
+    /// file's errors under it
+    fn write_lint(&mut self, report: &lint::Report) -> io::Result<()> {
+        for file in &report.files {
+            let (color, status) = if file.is_valid() {
+                (Green, "ok".to_owned())
+            } else {
+                (
+                    Red,
+                    format!(
+                        "{} {}",
+                        file.errors.len(),
+                        if file.errors.len() == 1 {
+                            "error"
+                        } else {
+                            "errors"
+                        }
+                    ),
+                )
+            };
+
+            let mut lines = vec![format!("{} ({})", file.path.display(), status)];
+            lines.extend(file.errors.iter().map(|error| format!("- {}", error)));
+
+            let attr = match &file.id {
+                Some(id) => format!("{}:", id),
+                None => "Unparsed:".to_owned(),
+            };
+
+            self.print_lines(color, &format!("{:<19}", attr), &lines)?;
+        }
+
+        let invalid = report.invalid_count();
+
+        if report.is_failure() {
+            status_err!(
+                "{} of {} advisory {} failed lint",
+                invalid,
+                report.files.len(),
+                if report.files.len() == 1 {
+                    "file"
+                } else {
+                    "files"
+                }
+            );
+        } else if report.files.is_empty() {
+            status_warn!("no advisory files found");
+        } else {
+            status_ok!(
+                "Success",
+                "{} advisory {} passed lint",
+                report.files.len(),
+                if report.files.len() == 1 {
+                    "file"
+                } else {
+                    "files"
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Print explanations of how a lockfile's packages were matched against
+    /// an advisory
+    pub fn print_explanations(
+        &mut self,
+        advisory: &advisory::Advisory,
+        lockfile_path: &Path,
+        explanations: &[(Package, Explanation)],
+    ) -> Result<(), Error> {
+        let result = if self.config.format.is_machine_readable() {
+            let report = Explanations {
+                advisory: &advisory.metadata.id,
+                package: &advisory.metadata.package,
+                lockfile: lockfile_path,
+                explanations: explanations
+                    .iter()
+                    .map(|(_, explanation)| explanation)
+                    .collect(),
+            };
+
+            self.machine_output()
+                .and_then(|mut w| write_json_report(&mut w, &report))
+        } else {
+            self.write_explanations(advisory, lockfile_path, explanations)
+        };
+
+        output::report_result(result, "report")
+    }
+
+    /// Write how each package was matched against the advisory to STDOUT:
+    /// every requirement and affected range, the pre-release rules applied,
+    /// and the verdict
+    fn write_explanations(
+        &self,
+        advisory: &advisory::Advisory,
+        lockfile_path: &Path,
+        explanations: &[(Package, Explanation)],
+    ) -> io::Result<()> {
+        let metadata = &advisory.metadata;
+
+        if !self.config.is_quiet() {
+            status_ok!(
+                "Explaining",
+                "{} against {}",
+                metadata.id,
+                lockfile_path.display()
+            );
+        }
+
+        for (package, explanation) in explanations {
+            let color = if explanation.vulnerable { Red } else { Green };
+            let version = &explanation.version;
+
+            self.print_attr(color, "Crate:        ", package.name.as_str())?;
+            self.print_attr(color, "Version:      ", version.to_string())?;
+            self.print_lines(
+                color,
+                "Patched:      ",
+                &requirement_lines(&explanation.patched),
+            )?;
+            self.print_lines(
+                color,
+                "Unaffected:   ",
+                &requirement_lines(&explanation.unaffected),
+            )?;
+
+            let mut affected = explanation
+                .affected
+                .iter()
+                .map(|range| {
+                    format!(
+                        "{}: {} {}",
+                        range,
+                        if range.contains {
+                            "contains"
+                        } else {
+                            "doesn't contain"
+                        },
+                        version
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            if affected.is_empty() {
+                affected.push("none".to_owned());
+            }
+
+            self.print_lines(color, "Affected:     ", &affected)?;
+
+            let attr = "Pre-releases: ";
+
+            if explanation.prerelease_rules.is_empty() {
+                self.print_attr(
+                    color,
+                    attr,
+                    format!("none applied ({} isn't a pre-release)", version),
+                )?;
+            } else {
+                let lines = explanation
+                    .prerelease_rules
+                    .iter()
+                    .flat_map(|rule| text::wrap(rule, self.content_width(attr)))
+                    .collect::<Vec<_>>();
+
+                self.print_lines(color, attr, &lines)?;
+            }
+
+            let mut verdict = if explanation.vulnerable {
+                format!("vulnerable ({} is in an affected range)", version)
+            } else {
+                format!("not vulnerable ({} isn't in an affected range)", version)
+            };
+
+            if metadata.withdrawn.is_some() {
+                verdict.push_str(", but the advisory is withdrawn, so isn't reported");
+            } else if explanation.vulnerable && metadata.informational.is_some() {
+                verdict.push_str(", reported as a warning as the advisory is informational");
+            }
+
+            self.print_attr(color, "Verdict:      ", verdict)?;
+            writeln!(io::stdout())?;
+        }
+
+        if explanations.is_empty() {
+            status_warn!(
+                "no {} packages in {}",
+                metadata.package,
+                lockfile_path.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Output for machine-readable reports: the configured output file, or
+    /// STDOUT
+    fn machine_output(&self) -> io::Result<Box<dyn io::Write>> {
+        let path = match &self.config.file {
+            Some(path) => path,
+            None => return Ok(Box::new(io::stdout())),
+        };
+
+        let file = fs::File::create(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        Ok(Box::new(file))
+    }
+
+    /// Write the JSON report to the output file (if any) alongside a
+    /// terminal report
+    fn write_json_file(&self, report: &impl serde::Serialize) -> io::Result<()> {
+        if self.config.file.is_none() {
+            return Ok(());
+        }
+
+        self.machine_output()
+            .and_then(|mut w| write_json_report(&mut w, report))
+    }
+
+    /// Write the human-readable diff to STDOUT, in sections for introduced,
+    /// resolved and (if `unchanged` is true) unchanged findings
+    fn write_diff(&mut self, diff: &report::Diff, unchanged: bool) -> io::Result<()> {
+        let sections = [
+            ("Introduced:", Red, &diff.introduced),
+            ("Resolved:", Green, &diff.resolved),
+            ("Still present:", Yellow, &diff.unchanged),
+        ];
+
+        let count = if unchanged { 3 } else { 2 };
+
+        for (title, color, findings) in sections.iter().take(count) {
+            terminal::status::Status::new()
+                .bold()
+                .color(*color)
+                .status(*title)
+                .print_stdout(format!(
+                    "{} {}",
+                    findings.len(),
+                    if findings.len() == 1 {
+                        "finding"
+                    } else {
+                        "findings"
+                    }
+                ))
+                .map_err(output::io_error)?;
+
+            writeln!(io::stdout())?;
+
+            let mut vulnerabilities: Vec<_> = findings.vulnerabilities.iter().collect();
+            sort_vulnerabilities(&mut vulnerabilities, self.config.sort);
+
+            self.print_vulnerabilities(&vulnerabilities, None)?;
+
+            let warnings: Vec<_> = findings.warnings.iter().collect();
+
+            for group in group_warnings(&warnings) {
+                self.print_warning(&group, None)?;
+            }
+        }
+
+        let summary = format!(
+            "{} introduced, {} resolved, {} still present",
+            diff.introduced.len(),
+            diff.resolved.len(),
+            diff.unchanged.len()
+        );
+
+        if diff.introduced.is_empty() {
+            status_ok!("Compared", summary);
+        } else {
+            status_err!(summary);
+        }
+
+        Ok(())
+    }
+
+    /// Write the human-readable report to STDOUT
+    fn write_report(
+        &mut self,
+        report: &rustsec::Report,
+        self_advisories: &[rustsec::Advisory],
+        tree: Option<&dependency::Tree>,
+    ) -> io::Result<()> {
+        // We'll set this to true if (e.g.) we see a warning and have deny-warnings enabled.
+        // Once we've printed the whole report, we'll bail out of the whole program.
+        let mut exit_with_failure = false;
+
+        self.related_findings = related_findings(report);
+
+        // Print out vulnerabilities and warnings
+        let mut vulnerabilities: Vec<_> = report.vulnerabilities.list.iter().collect();
+        sort_vulnerabilities(&mut vulnerabilities, self.config.sort);
+
+        self.print_vulnerabilities(&vulnerabilities, tree)?;
+
+        for (kind, warnings) in warning_sections(report) {
+            let mut warnings: Vec<_> = warnings.iter().collect();
+            sort_warnings(&mut warnings, self.config.sort);
+            self.print_section(kind, &warnings)?;
+
+            for group in group_warnings(&warnings) {
+                self.print_warning(&group, tree)?;
+            }
+        }
+
+        self.print_ignored(report)?;
+        self.print_acknowledged(report)?;
+        self.print_vex_suppressed(report)?;
+        self.print_suppressions()?;
+        self.print_resolved()?;
+
+        // Print out any self-advisories
+        if !self_advisories.is_empty() {
+            let msg = "This copy of cargo-audit has known advisories!";
+
+            if self.config.deny.contains(&DenyOption::Warnings) {
+                status_err!(msg);
+            } else {
+                status_warn!(msg);
+            }
+
+            for advisory in self_advisories {
+                self.print_metadata(
+                    &advisory.metadata,
+                    self.warning_color(self.config.deny.contains(&DenyOption::Warnings)),
+                )?;
+            }
+            writeln!(io::stdout())?;
+        }
+
+        if report.vulnerabilities.found {
+            let advisory_count = report.vulnerabilities.advisory_count();
+
+            if advisory_count == report.vulnerabilities.count {
+                if advisory_count == 1 {
+                    status_err!("1 vulnerability found!");
+                } else {
+                    status_err!("{} vulnerabilities found!", advisory_count);
+                }
+            } else {
+                status_err!(
+                    "{} {} found, affecting {} package versions!",
+                    advisory_count,
+                    if advisory_count == 1 {
+                        "vulnerability"
+                    } else {
+                        "vulnerabilities"
+                    },
+                    report.vulnerabilities.count
+                );
+            }
+
+            let summary = &report.summary;
+            let mut affected = format!(
+                "{} package {} of {} {}",
+                summary.package_versions,
+                if summary.package_versions == 1 {
+                    "version"
+                } else {
+                    "versions"
+                },
+                summary.crates,
+                if summary.crates == 1 {
+                    "crate"
+                } else {
+                    "crates"
+                }
+            );
+
+            if let Some(direct) = summary.direct_dependencies {
+                affected.push_str(&format!(
+                    ", pulled in by {} direct {}",
+                    direct,
+                    if direct == 1 {
+                        "dependency"
+                    } else {
+                        "dependencies"
+                    }
+                ));
+            }
+
+            status_ok!("Affected", affected);
+
+            if self.config.fail_policy().is_some() {
+                let failed = report
+                    .vulnerabilities
+                    .list
+                    .iter()
+                    .filter(|vuln| !matches!(&vuln.scopes, Some(scopes) if scopes.failed.is_none()))
+                    .count();
+
+                if failed > 0 {
+                    status_err!(
+                        "{} of {} vulnerable package versions fail the fail-on policy",
+                        failed,
+                        report.vulnerabilities.list.len()
+                    );
+                } else {
+                    status_warn!("none of these fail the fail-on policy");
+                }
+            }
+
+            if let Some(threshold) = self.config.fail_severity {
+                let below = report
+                    .vulnerabilities
+                    .list
+                    .iter()
+                    .filter(|vuln| self.config.is_below_fail_severity(vuln))
+                    .count();
+
+                if below > 0 {
+                    status_warn!(
+                        "{} of {} vulnerable package versions are below the {} severity \
+                         threshold, and don't fail the audit",
+                        below,
+                        report.vulnerabilities.list.len(),
+                        threshold
+                    );
+                }
+            }
+
+            // Related advisories may describe the same underlying issue
+            let related = report
+                .vulnerabilities
+                .list
+                .iter()
+                .filter(|vuln| self.related_findings.contains_key(&vuln.advisory.id))
+                .count();
+
+            if related > 0 {
+                status_warn!(
+                    "{} of these are for related advisories (see `Related:`), which may be the same issue",
+                    related
+                );
+            }
+        }
+
+        let ignored = report.vulnerabilities.ignored.len();
+
+        if ignored > 0 {
+            status_warn!(
+                "{} {} ignored by category policy",
+                ignored,
+                if ignored == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                }
+            );
+        }
+
+        let acknowledged = report.vulnerabilities.acknowledged.len();
+
+        if acknowledged > 0 {
+            status_warn!(
+                "{} {} acknowledged (not failing until their acknowledgments expire)",
+                acknowledged,
+                if acknowledged == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                }
+            );
+        }
+
+        let vex = report.vulnerabilities.vex.len();
+
+        if vex > 0 {
+            status_warn!(
+                "{} {} suppressed by VEX",
+                vex,
+                if vex == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                }
+            );
+        }
+
+        // Count up the warnings of each kind, sorting into denied and
+        // allowed. Warnings for the same advisory count once, however many
+        // package versions they're about.
+        let mut denied = vec![];
+        let mut not_denied = vec![];
+        let mut denied_versions: u64 = 0;
+        let mut not_denied_versions: u64 = 0;
+
+        for (kind, warnings) in warning_sections(report) {
+            let findings = warning::finding_count(warnings) as u64;
+
+            if self.deny_warning_kinds.contains(&kind) {
+                denied.push((kind, findings));
+                denied_versions += warnings.len() as u64;
+            } else {
+                not_denied.push((kind, findings));
+                not_denied_versions += warnings.len() as u64;
+            }
+        }
+
+        if !denied.is_empty() {
+            status_err!(
+                "{}!",
+                self.warning_summary("denied", &denied, denied_versions)
+            );
+            exit_with_failure = true;
+        }
+
+        if !not_denied.is_empty() {
+            status_warn!(
+                "{}",
+                self.warning_summary("allowed", &not_denied, not_denied_versions)
+            );
+        }
+
+        if let Some(overridden) = report.warnings.get(&warning::Kind::Overridden) {
+            status_warn!(
+                "{} of these {} for vulnerable git or path dependencies (e.g. [patch] forks), \
+                 which need to be verified manually",
+                overridden.len(),
+                if overridden.len() == 1 { "is" } else { "are" }
+            );
+        }
+
+        if let Some(optional) = report.warnings.get(&warning::Kind::Optional) {
+            status_warn!(
+                "{} of these {} for vulnerable crates only compiled with features which aren't \
+                 enabled",
+                optional.len(),
+                if optional.len() == 1 { "is" } else { "are" }
+            );
+        }
+
+        if let Some(platform) = report.warnings.get(&warning::Kind::OtherPlatform) {
+            status_warn!(
+                "{} of these {} for vulnerable crates whose advisories only affect other \
+                 platforms than the target",
+                platform.len(),
+                if platform.len() == 1 { "is" } else { "are" }
+            );
+        }
+
+        if let Some(inventory) = &report.inventory {
+            self.print_inventory(inventory);
+        }
+
+        if !self_advisories.is_empty() {
+            let upgrade_msg = "upgrade cargo-audit to the latest version: \
+                               cargo install --force cargo-audit";
+
+            if self.config.deny.contains(&DenyOption::Warnings) {
+                status_err!(upgrade_msg);
+                exit_with_failure = true;
+            } else {
+                status_warn!(upgrade_msg);
+            }
+        }
+
+        self.print_summary_table(&report.summary)?;
+
+        // TODO(tarcieri): better unify this with vulnerabilities handling
+        // (without a dependency tree the caller exits with an error instead)
+        if exit_with_failure && tree.is_some() {
+            let outcome = report.outcome.unwrap_or(Outcome::DeniedWarnings);
+            std::process::exit(
+                self.exit_code
+                    .unwrap_or_else(|| self.config.exit_codes.code(outcome)),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Print a table of the report's findings: vulnerabilities by severity
+    /// and by kind of dependency, warnings by kind, and how many
+    /// vulnerabilities were ignored (unless nothing was found)
+    fn print_summary_table(&self, summary: &report::Summary) -> io::Result<()> {
+        let table = summary_table(summary);
+
+        if self.config.is_quiet() || table.is_empty() {
+            return Ok(());
+        }
+
+        terminal::status::Status::new()
+            .bold()
+            .status("Summary:")
+            .print_stdout("")
+            .map_err(output::io_error)?;
+
+        writeln!(io::stdout())?;
+
+        for (label, value) in table {
+            writeln!(io::stdout(), "  {:<22} {}", label, value)?;
+        }
+
+        writeln!(io::stdout())
+    }
+
+    /// Print the numbers of packages which were audited and skipped
+    fn print_inventory(&self, inventory: &report::Inventory) {
+        let skipped = &inventory.skipped;
+
+        status_ok!(
+            "Inventory",
+            "{} packages audited; skipped {} not compiled with the selected features, \
+             {} outside the package source scope and {} git packages",
+            inventory.packages.len(),
+            skipped.features,
+            skipped.source_scope,
+            skipped.git
+        );
+
+        match skipped.yanked_check {
+            Some(0) => (),
+            Some(count) => status_ok!(
+                "Inventory",
+                "{} packages not from crates.io weren't checked for yanked releases",
+                count
+            ),
+            None => status_ok!("Inventory", "yanked releases weren't checked for"),
+        }
+    }
+
+    /// Print the given (sorted) vulnerabilities grouped by advisory, and the
+    /// advisories of a single package version which only affect that version
+    /// together in one block (unless in verbose mode, which shows each
+    /// advisory in full)
+    fn print_vulnerabilities(
+        &mut self,
+        vulnerabilities: &[&rustsec::Vulnerability],
+        tree: Option<&dependency::Tree>,
+    ) -> io::Result<()> {
+        let groups = group_vulnerabilities(vulnerabilities);
+
+        // Templates show each advisory in its own block
+        if let Some(template) = self.template.clone() {
+            for group in &groups {
+                self.print_templated(&template, group, tree)?;
+            }
+
+            return Ok(());
+        }
+
+        let mut printed: Vec<&Package> = vec![];
+
+        for group in &groups {
+            let package = &group[0].package;
+
+            let same_package = groups
+                .iter()
+                .filter(|other| other.len() == 1 && other[0].package == *package)
+                .map(|other| other[0])
+                .collect::<Vec<_>>();
+
+            if group.len() > 1 || same_package.len() == 1 || self.config.verbose {
+                self.print_vulnerability(group, tree)?;
+            } else if !printed.contains(&package) {
+                printed.push(package);
+                self.print_package_vulnerabilities(&same_package, tree)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print the advisories of a single package version: the package and
+    /// its dependency tree once, followed by the advisories' IDs and titles
+    fn print_package_vulnerabilities(
+        &mut self,
+        vulnerabilities: &[&rustsec::Vulnerability],
+        tree: Option<&dependency::Tree>,
+    ) -> io::Result<()> {
+        let package = &vulnerabilities[0].package;
+
+        if let Some(changes) = &self.changes {
+            if vulnerabilities
+                .iter()
+                .any(|vuln| changes.is_new_vulnerability(vuln))
+            {
+                self.print_new(Red, changes)?;
+            }
+        }
+
+        self.print_attr(Red, "Crate:        ", &package.name)?;
+        self.print_attr(Red, "Version:      ", &package.version.to_string())?;
+        self.print_source(Red, package)?;
+
+        let attr = "Advisories:   ";
+        let mut lines = vec![];
+
+        for vuln in vulnerabilities {
+            let mut advisory = format!("{} {}", vuln.advisory.id, vuln.advisory.title);
+
+            if let Some(severity) = vuln.effective_severity {
+                if vuln.is_severity_assumed() {
+                    advisory.push_str(&format!(" ({}, assumed)", severity));
+                } else if let Some(score) = cvss_score(vuln) {
+                    advisory.push_str(&format!(" ({} {:.1})", severity, score));
+                } else {
+                    advisory.push_str(&format!(" ({})", severity));
+                }
+            }
+
+            if vuln.known_exploited == Some(true) {
+                advisory.push_str(" [known exploited]");
+            }
+
+            if let Some(changes) = &self.changes {
+                if changes.since.is_some() && changes.is_new_vulnerability(vuln) {
+                    advisory.push_str(" [new]");
+                }
+            }
+
+            lines.extend(text::wrap(&advisory, self.content_width(attr)));
+        }
+
+        self.print_lines(Red, attr, &lines)?;
+        self.print_scopes(vulnerabilities)?;
+        self.print_dependency_kind(Red, vulnerabilities.iter().map(|vuln| vuln.direct))?;
+        self.print_members(
+            Red,
+            vulnerabilities
+                .iter()
+                .filter_map(|vuln| vuln.members.as_ref())
+                .flatten(),
+        )?;
+
+        let solutions = vulnerabilities
+            .iter()
+            .map(|vuln| {
+                if vuln.versions.patched().is_empty() {
+                    "No safe upgrade is available!".to_owned()
+                } else {
+                    self.solution(&[vuln])
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let attr = "Solution:     ";
+
+        if solutions.iter().all(|solution| *solution == solutions[0]) {
+            self.print_attr(Red, attr, &solutions[0])?;
+        } else {
+            let mut lines = vec![];
+
+            for (vuln, solution) in vulnerabilities.iter().zip(&solutions) {
+                let solution = format!("{}: {}", vuln.advisory.id, solution);
+                lines.extend(text::wrap(&solution, self.content_width(attr)));
+            }
+
+            self.print_lines(Red, attr, &lines)?;
+        }
+
+        self.print_resolutions(vulnerabilities)?;
+        self.print_attr(
+            Red,
+            "Details:      ",
+            "use --verbose to show each advisory in full",
+        )?;
+
+        self.print_tree(Red, package, tree)?;
+        writeln!(io::stdout())
+    }
+
+    /// Print information about the given vulnerabilities, which are for the
+    /// same advisory (i.e. different versions of the same package)
+    fn print_vulnerability(
+        &mut self,
+        vulnerabilities: &[&rustsec::Vulnerability],
+        tree: Option<&dependency::Tree>,
+    ) -> io::Result<()> {
+        let vulnerability = vulnerabilities[0];
+
+        if let Some(changes) = &self.changes {
+            if vulnerabilities
+                .iter()
+                .any(|vuln| changes.is_new_vulnerability(vuln))
+            {
+                self.print_new(Red, changes)?;
+            }
+        }
+
+        if vulnerability.known_exploited == Some(true) {
+            self.print_attr(
+                Red,
+                "KNOWN EXPLOITED",
+                "listed in CISA's Known Exploited Vulnerabilities catalog",
+            )?;
+        }
+
+        self.print_attr(Red, "Crate:        ", &vulnerability.package.name)?;
+
+        if vulnerabilities.len() == 1 {
+            self.print_attr(
+                Red,
+                "Version:      ",
+                &vulnerability.package.version.to_string(),
+            )?;
+            self.print_source(Red, &vulnerability.package)?;
+        } else {
+            let versions = vulnerabilities
+                .iter()
+                .map(|vuln| self.version_name(&vuln.package))
+                .collect::<Vec<_>>();
+
+            self.print_attr(Red, "Versions:     ", versions.join(", "))?;
+        }
+
+        self.print_metadata(&vulnerability.advisory, Red)?;
+        self.print_severity(vulnerability)?;
+        self.print_first_published(Red, vulnerability.first_published_in_db.as_ref())?;
+        self.print_database_source(Red, vulnerability.source.as_deref())?;
+
+        if let Some(score) = &vulnerability.epss {
+            self.print_attr(Red, "EPSS:         ", epss::display(score))?;
+        }
+
+        self.print_scopes(vulnerabilities)?;
+        self.print_dependency_kind(Red, vulnerabilities.iter().map(|vuln| vuln.direct))?;
+        self.print_members(
+            Red,
+            vulnerabilities
+                .iter()
+                .filter_map(|vuln| vuln.members.as_ref())
+                .flatten(),
+        )?;
+        self.print_reachability(vulnerability.reachability.as_ref())?;
+
+        if vulnerability.versions.patched().is_empty() {
+            self.print_attr(Red, "Solution:     ", "No safe upgrade is available!")?;
+        } else {
+            self.print_attr(Red, "Solution:     ", self.solution(vulnerabilities))?;
+            self.print_resolutions(vulnerabilities)?;
+
+            // The recommendation replaces the advisory's ranges, which are
+            // still shown in verbose mode for reference
+            if self.config.verbose {
+                self.print_attr(
+                    Red,
+                    "Patched:      ",
+                    join_reqs(vulnerability.versions.patched()),
+                )?;
+
+                if !vulnerability.versions.unaffected().is_empty() {
+                    self.print_attr(
+                        Red,
+                        "Unaffected:   ",
+                        join_reqs(vulnerability.versions.unaffected()),
+                    )?;
+                }
+            }
+        }
+
+        self.print_description(&vulnerability.advisory, Red)?;
+
+        // Showing a tree for every affected version gets noisy, so they're
+        // only shown in verbose mode
+        if vulnerabilities.len() == 1 || self.config.verbose {
+            for vuln in vulnerabilities {
+                self.print_tree(Red, &vuln.package, tree)?;
+            }
+        } else if tree.is_some() && self.config.show_tree.unwrap_or(true) {
+            self.print_attr(
+                Red,
+                "Dependency tree:",
+                format!(
+                    "omitted for {} versions (use --verbose to show them)",
+                    vulnerabilities.len()
+                ),
+            )?;
+        }
+
+        writeln!(io::stdout())
+    }
+
+    /// Print the given vulnerabilities (for the same advisory) with the
+    /// configured template, followed by their dependency trees
+    fn print_templated(
+        &mut self,
+        template: &Template,
+        vulnerabilities: &[&rustsec::Vulnerability],
+        tree: Option<&dependency::Tree>,
+    ) -> io::Result<()> {
+        let vulnerability = vulnerabilities[0];
+        let advisory = &vulnerability.advisory;
+
+        let mut values = Map::new();
+        values.insert("crate", vulnerability.package.name.to_string());
+        values.insert(
+            "version",
+            join(vulnerabilities.iter().map(|vuln| &vuln.package.version)),
+        );
+        values.insert("id", advisory.id.to_string());
+        values.insert("title", advisory.title.clone());
+        values.insert("date", advisory.date.as_str().to_owned());
+
+        let url = advisory
+            .id
+            .url()
+            .or_else(|| advisory.url.as_ref().map(ToString::to_string));
+        values.insert("url", url.unwrap_or_default());
+
+        values.insert("aliases", join(&advisory.aliases));
+        values.insert("categories", join(&advisory.categories));
+
+        if let Some(severity) = vulnerability.effective_severity {
+            values.insert("severity", severity.to_string());
+        }
+
+        if let Some(score) = cvss_score(vulnerability) {
+            values.insert("score", format!("{:.1}", score));
+        }
+
+        if let Some(cvss) = &advisory.cvss {
+            values.insert("cvss", cvss.to_string());
+        }
+
+        let patched = vulnerability.versions.patched();
+        values.insert("patched", join_reqs(patched));
+
+        if !patched.is_empty() {
+            values.insert("solution", self.solution(vulnerabilities));
+        }
+
+        let direct: Set<bool> = vulnerabilities
+            .iter()
+            .filter_map(|vuln| vuln.direct)
+            .collect();
+
+        let dependency = match (direct.contains(&true), direct.contains(&false)) {
+            (true, true) => "direct and transitive",
+            (true, false) => "direct",
+            (false, true) => "transitive",
+            (false, false) => "",
+        };
+        values.insert("dependency", dependency.to_owned());
+
+        let members: Set<_> = vulnerabilities
+            .iter()
+            .filter_map(|vuln| vuln.members.as_ref())
+            .flatten()
+            .collect();
+        values.insert("members", join(members));
+
+        if let Some(score) = &vulnerability.epss {
+            values.insert("epss", epss::display(score));
+        }
+
+        if vulnerability.known_exploited == Some(true) {
+            values.insert("exploited", "true".to_owned());
+        }
+
+        if let Some(changes) = &self.changes {
+            if changes.since.is_some()
+                && vulnerabilities
+                    .iter()
+                    .any(|vuln| changes.is_new_vulnerability(vuln))
+            {
+                values.insert("new", "true".to_owned());
+            }
+        }
+
+        values.insert("description", advisory.description.clone());
+
+        let rendered = template.render(&values);
+        write!(io::stdout(), "{}", rendered)?;
+
+        if !rendered.is_empty() && !rendered.ends_with('\n') {
+            writeln!(io::stdout())?;
+        }
+
+        for vuln in vulnerabilities {
+            self.print_tree(Red, &vuln.package, tree)?;
+        }
+
+        writeln!(io::stdout())
+    }
+
+    /// Recommend an upgrade for the given vulnerabilities (of the same
+    /// advisory): the nearest published releases which aren't vulnerable if
+    /// they were looked up, otherwise the lowest versions of the advisory's
+    /// ranges, falling back to the ranges themselves.
+    ///
+    /// With several vulnerable versions, recommendations are only concrete
+    /// for a single one, so the ranges are shown instead.
+    fn solution(&self, vulnerabilities: &[&rustsec::Vulnerability]) -> String {
+        let vulnerability = vulnerabilities[0];
+        let patched = format!("Upgrade to {}", join_reqs(vulnerability.versions.patched()));
+
+        if vulnerabilities.len() > 1 {
+            return patched;
+        }
+
+        if let Some(upgrade) = &vulnerability.upgrade {
+            return upgrade.to_string();
+        }
+
+        let solution =
+            Upgrade::from_ranges(&vulnerability.package.version, &vulnerability.versions)
+                .map(|upgrade| upgrade.to_string())
+                .unwrap_or(patched);
+
+        match &vulnerability.patched_release {
+            Some(PatchedRelease::Unreleased) => format!(
+                "{}, but no published release satisfies the patched range yet - \
+                 consider the advisory's workaround",
+                solution
+            ),
+            _ => solution,
+        }
+    }
+
+    /// Print how the recommended upgrades can be applied, given the
+    /// requirements of the vulnerable packages' dependents (if they're known):
+    /// once if they're all the same, otherwise for each advisory (or version,
+    /// if they're all for the same advisory)
+    fn print_resolutions(&self, vulnerabilities: &[&rustsec::Vulnerability]) -> io::Result<()> {
+        let resolutions = vulnerabilities
+            .iter()
+            .filter_map(|vuln| Some((*vuln, resolution(vuln)?)))
+            .collect::<Vec<_>>();
+
+        let attr = "Resolution:   ";
+
+        let first = match resolutions.first() {
+            Some((_, first)) => first,
+            None => return Ok(()),
+        };
+
+        let all_same = resolutions
+            .iter()
+            .all(|(_, resolution)| resolution == first);
+
+        if all_same && resolutions.len() == vulnerabilities.len() {
+            return self.print_attr(Red, attr, first);
+        }
+
+        let same_advisory = resolutions
+            .iter()
+            .all(|(vuln, _)| vuln.advisory.id == resolutions[0].0.advisory.id);
+
+        let mut lines = vec![];
+
+        for (vuln, resolution) in &resolutions {
+            let resolution = if same_advisory {
+                format!("{}: {}", vuln.package.version, resolution)
+            } else {
+                format!("{}: {}", vuln.advisory.id, resolution)
+            };
+
+            lines.extend(text::wrap(&resolution, self.content_width(attr)));
+        }
+
+        self.print_lines(Red, attr, &lines)
+    }
+
+    /// Mark a finding as new since the run recorded in the state file or the
+    /// baseline (unless there is no recorded run, in which case every finding
+    /// is new)
+    fn print_new(&self, color: Color, changes: &state::Changes) -> io::Result<()> {
+        match &changes.since {
+            Some(since) => self.print_attr(color, "NEW", format!("since {}", since)),
+            None => Ok(()),
+        }
+    }
+
+    /// Print the vulnerabilities ignored by the category policy, which don't
+    /// fail the audit
+    fn print_ignored(&self, report: &rustsec::Report) -> io::Result<()> {
+        let ignored = &report.vulnerabilities.ignored;
+
+        if ignored.is_empty() {
+            return Ok(());
+        }
+
+        terminal::status::Status::new()
+            .bold()
+            .color(Yellow)
+            .status("Ignored by category policy:")
+            .print_stdout(format!(
+                "{} {}",
+                ignored.len(),
+                if ignored.len() == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                }
+            ))
+            .map_err(output::io_error)?;
+
+        writeln!(io::stdout())?;
+
+        for vuln in ignored {
+            self.print_attr(Yellow, "Crate:        ", &vuln.package.name)?;
+            self.print_attr(Yellow, "Version:      ", vuln.package.version.to_string())?;
+            self.print_attr(Yellow, "Title:        ", &vuln.advisory.title)?;
+            self.print_attr(Yellow, "ID:           ", &vuln.advisory.id)?;
+            self.print_attr(
+                Yellow,
+                "Categories:   ",
+                vuln.advisory
+                    .categories
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )?;
+            writeln!(io::stdout())?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the vulnerabilities of acknowledged advisories, which don't fail
+    /// the audit until their acknowledgments expire
+    fn print_acknowledged(&self, report: &rustsec::Report) -> io::Result<()> {
+        let acknowledged = &report.vulnerabilities.acknowledged;
+
+        if acknowledged.is_empty() {
+            return Ok(());
+        }
+
+        terminal::status::Status::new()
+            .bold()
+            .color(Yellow)
+            .status("Acknowledged:")
+            .print_stdout(format!(
+                "{} {}",
+                acknowledged.len(),
+                if acknowledged.len() == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                }
+            ))
+            .map_err(output::io_error)?;
+
+        writeln!(io::stdout())?;
+
+        for ack in acknowledged {
+            let vuln = &ack.vulnerability;
+            self.print_attr(Yellow, "Crate:        ", &vuln.package.name)?;
+            self.print_attr(Yellow, "Version:      ", vuln.package.version.to_string())?;
+            self.print_attr(Yellow, "Title:        ", &vuln.advisory.title)?;
+            self.print_attr(Yellow, "ID:           ", &vuln.advisory.id)?;
+            self.print_attr(Yellow, "Ticket:       ", &ack.ticket)?;
+            self.print_attr(
+                Yellow,
+                "Expires:      ",
+                format!(
+                    "{} ({} {} remaining)",
+                    ack.expires.as_str(),
+                    ack.days_remaining,
+                    if ack.days_remaining == 1 {
+                        "day"
+                    } else {
+                        "days"
+                    }
+                ),
+            )?;
+            writeln!(io::stdout())?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the vulnerabilities which VEX statements say the packages aren't
+    /// affected by, which don't fail the audit
+    fn print_vex_suppressed(&self, report: &rustsec::Report) -> io::Result<()> {
+        let suppressed = &report.vulnerabilities.vex;
+
+        if suppressed.is_empty() {
+            return Ok(());
+        }
+
+        terminal::status::Status::new()
+            .bold()
+            .color(Yellow)
+            .status("Suppressed by VEX:")
+            .print_stdout(format!(
+                "{} {}",
+                suppressed.len(),
+                if suppressed.len() == 1 {
+                    "vulnerability"
+                } else {
+                    "vulnerabilities"
+                }
+            ))
+            .map_err(output::io_error)?;
+
+        writeln!(io::stdout())?;
+
+        for entry in suppressed {
+            let vuln = &entry.vulnerability;
+            self.print_attr(Yellow, "Crate:        ", &vuln.package.name)?;
+            self.print_attr(Yellow, "Version:      ", vuln.package.version.to_string())?;
+            self.print_attr(Yellow, "Title:        ", &vuln.advisory.title)?;
+            self.print_attr(Yellow, "ID:           ", &vuln.advisory.id)?;
+
+            let status = match &entry.justification {
+                Some(justification) => format!("{} ({})", entry.status, justification),
+                None => entry.status.clone(),
+            };
+
+            self.print_attr(Yellow, "Status:       ", status)?;
+
+            if let Some(impact) = &entry.impact_statement {
+                self.print_attr(Yellow, "Impact:       ", impact)?;
+            }
+
+            self.print_attr(Yellow, "Document:     ", &entry.document)?;
+            writeln!(io::stdout())?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the ignored advisories which match the audited packages, with
+    /// why they're ignored and until when
+    fn print_suppressions(&self) -> io::Result<()> {
+        if self.suppressions.is_empty() {
+            return Ok(());
+        }
+
+        terminal::status::Status::new()
+            .bold()
+            .color(Yellow)
+            .status("Suppressed:")
+            .print_stdout(format!(
+                "{} ignored {}",
+                self.suppressions.len(),
+                if self.suppressions.len() == 1 {
+                    "advisory"
+                } else {
+                    "advisories"
+                }
+            ))
+            .map_err(output::io_error)?;
+
+        writeln!(io::stdout())?;
+
+        for suppression in &self.suppressions {
+            self.print_attr(Yellow, "ID:           ", &suppression.id)?;
+            self.print_attr(Yellow, "Title:        ", &suppression.title)?;
+            self.print_attr(Yellow, "Packages:     ", suppression.packages.join(", "))?;
+            self.print_attr(
+                Yellow,
+                "Reason:       ",
+                suppression.reason.as_deref().unwrap_or("(none given)"),
+            )?;
+
+            let expires = match (&suppression.expires, suppression.days_remaining) {
+                (Some(expires), Some(days)) => format!(
+                    "{} ({} {} remaining)",
+                    expires.as_str(),
+                    days,
+                    if days == 1 { "day" } else { "days" }
+                ),
+                _ => "never".to_owned(),
+            };
+
+            self.print_attr(Yellow, "Expires:      ", expires)?;
+            writeln!(io::stdout())?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the findings recorded in the state file (or the baseline report)
+    /// which were resolved since then
+    fn print_resolved(&self) -> io::Result<()> {
+        let changes = match &self.changes {
+            Some(changes) if !changes.resolved.is_empty() => changes,
+            _ => return Ok(()),
+        };
+
+        terminal::status::Status::new()
+            .bold()
+            .color(Green)
+            .status(match changes.since {
+                Some(state::Since::Baseline(_)) => "Resolved since baseline:",
+                _ => "Resolved since last run:",
+            })
+            .print_stdout(format!(
+                "{} {}",
+                changes.resolved.len(),
+                if changes.resolved.len() == 1 {
+                    "finding"
+                } else {
+                    "findings"
+                }
+            ))
+            .map_err(output::io_error)?;
+
+        writeln!(io::stdout())?;
+
+        for finding in &changes.resolved {
+            self.print_attr(Green, "Crate:        ", &finding.package)?;
+            self.print_attr(Green, "Version:      ", finding.version.to_string())?;
+
+            if let Some(kind) = finding.warning {
+                self.print_attr(Green, "Warning:      ", kind.as_str())?;
+            }
+
+            if let Some(id) = &finding.advisory {
+                self.print_attr(Green, "ID:           ", id)?;
+            }
+
+            // Baseline reports don't record when findings were first seen
+            if let Some(state::Since::Run(_)) = changes.since {
+                self.print_attr(Green, "First seen:   ", finding.first_seen.as_str())?;
+            }
+
+            writeln!(io::stdout())?;
+        }
+
+        if let Some(state::Since::Baseline(path)) = &changes.since {
+            writeln!(
+                io::stdout(),
+                "These can be pruned from the baseline with `--write-baseline {}`.",
+                path.display()
+            )?;
+            writeln!(io::stdout())?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the kinds of dependency the given vulnerabilities (for the same
+    /// advisory) are reachable through, and which `fail-on` rule makes them
+    /// fail the audit (if any)
+    fn print_scopes(&self, vulnerabilities: &[&rustsec::Vulnerability]) -> io::Result<()> {
+        let fail_on = match self.config.fail_policy() {
+            Some(fail_on) => fail_on,
+            None => return Ok(()),
+        };
+
+        let scopes: Vec<&Scopes> = vulnerabilities
+            .iter()
+            .filter_map(|vuln| vuln.scopes.as_ref())
+            .collect();
+
+        if scopes.is_empty() {
+            return Ok(());
+        }
+
+        let reachable: Set<Scope> = scopes
+            .iter()
+            .flat_map(|scopes| scopes.reachable.iter().copied())
+            .collect();
+        let failed: Set<Scope> = scopes.iter().filter_map(|scopes| scopes.failed).collect();
+
+        self.print_attr(
+            Red,
+            "Scope:        ",
+            reachable
+                .iter()
+                .map(Scope::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        )?;
+
+        let policy = if failed.is_empty() {
+            "allowed by the fail-on policy".to_owned()
+        } else {
+            let rules = failed
+                .iter()
+                .map(|&scope| match fail_on.threshold(scope) {
+                    FailThreshold::Severity(advisory::Severity::None) => {
+                        format!("`{}` (any severity)", scope)
+                    }
+                    threshold => format!("`{}` ({} or above)", scope, threshold),
+                })
+                .collect::<Vec<_>>();
+
+            format!(
+                "fails the {} {}",
+                rules.join(" and "),
+                if rules.len() == 1 { "rule" } else { "rules" }
+            )
+        };
+
+        self.print_attr(Red, "Policy:       ", policy)
+    }
+
+    /// Print information about the given warnings (of the same kind, for
+    /// the same advisory), listing each package version they're about
+    fn print_warning(
+        &mut self,
+        warnings: &[&rustsec::Warning],
+        tree: Option<&dependency::Tree>,
+    ) -> io::Result<()> {
+        let warning = warnings[0];
+        let color = self.kind_color(warning.kind);
+
+        if let Some(changes) = &self.changes {
+            if warnings
+                .iter()
+                .any(|warning| changes.is_new_warning(warning))
+            {
+                self.print_new(color, changes)?;
+            }
+        }
+
+        self.print_attr(color, "Crate:        ", &warning.package.name)?;
+
+        if warnings.len() > 1 {
+            let versions = warnings
+                .iter()
+                .map(|warning| {
+                    if warning.is_overridden() {
+                        format!(
+                            "{} ({})",
+                            warning.package.version,
+                            source_name(&warning.package)
+                        )
+                    } else {
+                        self.version_name(&warning.package)
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            self.print_attr(color, "Versions:     ", versions.join(", "))?;
+        } else {
+            self.print_attr(
+                color,
+                "Version:      ",
+                &warning.package.version.to_string(),
+            )?;
+        }
+
+        if warning.is_overridden() {
+            // Always show the source, which is what needs to be checked for the fix
+            if warnings.len() == 1 {
+                self.print_attr(color, "Source:       ", source_name(&warning.package))?;
+            }
+
+            self.print_attr(
+                color,
+                "Warning:      ",
+                "overridden dependency, verify manually that it contains the fix",
+            )?;
+        } else {
+            if warnings.len() == 1 {
+                self.print_source(color, &warning.package)?;
+            }
+
+            if warning.is_optional() {
+                self.print_attr(
+                    color,
+                    "Warning:      ",
+                    "optional, only compiled with features which aren't enabled",
+                )?;
+            } else if warning.is_other_platform() {
+                self.print_attr(
+                    color,
+                    "Warning:      ",
+                    "other platform, the advisory doesn't affect the target",
+                )?;
+            } else {
+                self.print_attr(color, "Warning:      ", warning.kind.as_str())?;
+            }
+        }
+
+        if let Some(duplicates) = &warning.duplicates {
+            self.print_duplicates(color, duplicates)?;
+        }
+
+        if let Some(metadata) = &warning.advisory {
+            self.print_metadata(metadata, color)?;
+            self.print_first_published(color, warning.first_published_in_db.as_ref())?;
+            self.print_database_source(color, warning.source.as_deref())?;
+        }
+
+        self.print_dependency_kind(color, warnings.iter().map(|warning| warning.direct))?;
+        self.print_members(
+            color,
+            warnings
+                .iter()
+                .filter_map(|warning| warning.members.as_ref())
+                .flatten(),
+        )?;
+
+        if let Some(metadata) = &warning.advisory {
+            self.print_description(metadata, color)?;
+        }
+
+        // As for vulnerabilities, a tree for every version is only shown in
+        // verbose mode
+        if warnings.len() == 1 || self.config.verbose {
+            for warning in warnings {
+                self.print_tree(color, &warning.package, tree)?;
+            }
+        } else if tree.is_some() && self.config.show_tree.unwrap_or(true) {
+            self.print_attr(
+                color,
+                "Dependency tree:",
+                format!(
+                    "omitted for {} versions (use --verbose to show them)",
+                    warnings.len()
+                ),
+            )?;
+        }
+
+        writeln!(io::stdout())
+    }
+
+    /// Print whether a finding's package versions are direct or transitive
+    /// dependencies of the workspace (if known)
+    fn print_dependency_kind(
+        &self,
+        color: Color,
+        direct: impl Iterator<Item = Option<bool>>,
+    ) -> io::Result<()> {
+        let direct: Set<bool> = direct.flatten().collect();
+
+        let kind = match (direct.contains(&true), direct.contains(&false)) {
+            (true, true) => "direct and transitive",
+            (true, false) => "direct",
+            (false, true) => "transitive",
+            (false, false) => return Ok(()),
+        };
+
+        self.print_attr(color, "Dependency:   ", kind)
+    }
+
+    /// Print the workspace members affected by a finding (if known)
+    fn print_members<'a>(
+        &self,
+        color: Color,
+        members: impl Iterator<Item = &'a package::Name>,
+    ) -> io::Result<()> {
+        let members: Set<&package::Name> = members.collect();
+
+        if members.is_empty() {
+            return Ok(());
+        }
+
+        self.print_attr(
+            color,
+            "Affects members:",
+            members
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    /// Print whether a vulnerability's affected functions are referenced in
+    /// the workspace's source code (if checked), and where
+    fn print_reachability(&self, reachability: Option<&Reachability>) -> io::Result<()> {
+        let reachability = match reachability {
+            Some(reachability) => reachability,
+            None => return Ok(()),
+        };
+
+        if !reachability.referenced {
+            return self.print_attr(
+                Red,
+                "Reachability: ",
+                "no direct reference to the affected functions found in your code",
+            );
+        }
+
+        self.print_attr(
+            Red,
+            "Reachability: ",
+            "affected functions are referenced in your code",
+        )?;
+
+        for reference in &reachability.references {
+            self.print_attr(
+                Red,
+                "Referenced:   ",
+                format!(
+                    "{} at {}:{}",
+                    reference.function,
+                    reference.path.display(),
+                    reference.line
+                ),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the other versions of a crate with several versions in the
+    /// lockfile, what pulls in the warned one, and any advisories
+    fn print_duplicates(&self, color: Color, duplicates: &warning::Duplicates) -> io::Result<()> {
+        let versions = duplicates
+            .versions
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        self.print_attr(color, "Versions:     ", versions.join(", "))?;
+
+        if !duplicates.pinned_by.is_empty() {
+            self.print_attr(color, "Pinned by:    ", duplicates.pinned_by.join(", "))?;
+        }
+
+        if !duplicates.advisories.is_empty() {
+            let advisories = duplicates
+                .advisories
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            self.print_attr(
+                color,
+                "Advisories:   ",
+                format!("{} (against some of these versions)", advisories.join(", ")),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Print the source of the given package if another package in the
+    /// lockfile has the same name and version
+    fn print_source(&self, color: Color, package: &Package) -> io::Result<()> {
+        if !self
+            .ambiguous_packages
+            .contains(&(package.name.clone(), package.version.clone()))
+        {
+            return Ok(());
+        }
+
+        self.print_attr(color, "Source:       ", source_name(package))
+    }
+
+    /// Version of the given package, along with its source if another
+    /// package in the lockfile has the same name and version
+    fn version_name(&self, package: &Package) -> String {
+        if self
+            .ambiguous_packages
+            .contains(&(package.name.clone(), package.version.clone()))
+        {
+            format!("{} ({})", package.version, source_name(package))
+        } else {
+            package.version.to_string()
+        }
+    }
+
+    /// Get the color to use when displaying warnings
+    fn warning_color(&self, deny_warning: bool) -> Color {
+        if deny_warning {
+            Red
+        } else {
+            Yellow
+        }
+    }
+
+    /// Get the color to use when displaying warnings of the given kind:
+    /// unsound crates are emphasized even when they aren't denied, since
+    /// they may well be exploitable
+    fn kind_color(&self, kind: warning::Kind) -> Color {
+        if self.deny_warning_kinds.contains(&kind) {
+            Red
+        } else if kind == warning::Kind::Unsound {
+            Magenta
+        } else {
+            Yellow
+        }
+    }
+
+    /// Print the effective severity of a vulnerability (if known) with its
+    /// CVSS base score, in a color for how severe it is (medium ones stand
+    /// out, low ones are like the other attributes), whether it's assumed as
+    /// the advisory has no CVSS score, and whether it's too low to fail the
+    /// audit
+    fn print_severity(&self, vulnerability: &rustsec::Vulnerability) -> io::Result<()> {
+        let severity = match vulnerability.effective_severity {
+            Some(severity) => severity,
+            None => return Ok(()),
+        };
+
+        let color = match severity {
+            advisory::Severity::Medium => Yellow,
+            _ => Red,
+        };
+
+        let mut text = if vulnerability.is_severity_assumed() {
+            format!("{} (assumed \u{2014} no CVSS published)", severity)
+        } else if let Some(score) = cvss_score(vulnerability) {
+            format!("{} ({:.1})", severity, score)
+        } else {
+            severity.to_string()
+        };
+
+        if self.config.is_below_fail_severity(vulnerability) {
+            text.push_str(", below the severity threshold (doesn't fail the audit)");
+        }
+
+        self.print_attr(color, "Severity:     ", text)
+    }
+
+    /// Print the heading of the section of the report for a kind of warning,
+    /// with the number of warnings in it
+    fn print_section(&self, kind: warning::Kind, warnings: &[&rustsec::Warning]) -> io::Result<()> {
+        let count = warning::finding_count(warnings.iter().copied()) as u64;
+        let mut summary = format!("{} {}", count, self.warning_word(count));
+
+        if warnings.len() as u64 != count {
+            summary.push_str(&format!(", affecting {} package versions", warnings.len()));
+        }
+
+        terminal::status::Status::new()
+            .bold()
+            .color(self.kind_color(kind))
+            .status(format!("{}:", section_title(kind)))
+            .print_stdout(summary)
+            .map_err(output::io_error)?;
+
+        writeln!(io::stdout())
+    }
+
+    /// Print a warning about a particular advisory
+    fn print_metadata(&self, metadata: &advisory::Metadata, color: Color) -> io::Result<()> {
+        self.print_attr(color, "Title:        ", &metadata.title)?;
+        self.print_attr(color, "Date:         ", &metadata.date)?;
+        match metadata.id.url() {
+            Some(url) => {
+                self.print_link(color, "ID:           ", metadata.id.as_str(), &url)?;
+                self.print_link(color, "URL:          ", &url, &url)?;
+            }
+            None => {
+                self.print_attr(color, "ID:           ", &metadata.id)?;
+
+                if let Some(url) = &metadata.url {
+                    self.print_link(color, "URL:          ", url.as_str(), url.as_str())?;
+                }
+            }
+        }
+
+        if !metadata.aliases.is_empty() {
+            let aliases = metadata
+                .aliases
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            self.print_attr(color, "Aliases:      ", aliases)?;
+        }
+
+        // Listing every reference gets noisy, so they're only shown in
+        // verbose mode
+        let references = metadata.reference_urls();
+
+        if self.config.verbose && !references.is_empty() {
+            let references = references
+                .iter()
+                .map(|url| url.to_string())
+                .collect::<Vec<_>>();
+
+            self.print_lines(color, "References:   ", &references)?;
+        }
+
+        if let Some(cvss) = &metadata.cvss {
+            self.print_attr(color, "CVSS:         ", cvss.to_string())?;
+        }
+
+        if !metadata.categories.is_empty() {
+            let categories = metadata
+                .categories
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            self.print_attr(color, "Categories:   ", categories.join(", "))?;
+        }
+
+        if !metadata.keywords.is_empty() {
+            let keywords = metadata
+                .keywords
+                .iter()
+                .map(|keyword| keyword.as_str())
+                .collect::<Vec<_>>();
+
+            self.print_attr(color, "Keywords:     ", keywords.join(", "))?;
+        }
+
+        // Only matched with `--include-withdrawn`
+        if let Some(withdrawn) = &metadata.withdrawn {
+            self.print_attr(color, "Withdrawn:    ", withdrawn)?;
+        }
+
+        if let Some(id) = &metadata.superseded_by {
+            match id.url() {
+                Some(url) => self.print_link(color, "Superseded:   ", id.as_str(), &url)?,
+                None => self.print_attr(color, "Superseded:   ", id)?,
+            }
+        }
+
+        let found = self
+            .related_findings
+            .get(&metadata.id)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut related: Set<_> = metadata.related.iter().collect();
+        related.extend(&found);
+
+        if !related.is_empty() {
+            let related = related
+                .into_iter()
+                .map(|id| {
+                    if found.contains(id) {
+                        format!("{} (also found)", id)
+                    } else {
+                        id.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            self.print_attr(color, "Related:      ", related)?;
+        }
+
+        Ok(())
+    }
+
+    /// Display when the advisory was first added to the advisory database's
+    /// git history (if looked up with `--first-published`)
+    fn print_first_published(
+        &self,
+        color: Color,
+        first_published: Option<&Option<String>>,
+    ) -> io::Result<()> {
+        match first_published {
+            Some(Some(date)) => self.print_attr(color, "First in DB:  ", date),
+            Some(None) => self.print_attr(
+                color,
+                "First in DB:  ",
+                "unknown (the advisory database has no git history)",
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Display which advisory database source the advisory came from (if
+    /// several were merged)
+    fn print_database_source(&self, color: Color, source: Option<&str>) -> io::Result<()> {
+        match source {
+            Some(source) => self.print_attr(color, "Database:     ", source),
+            None => Ok(()),
+        }
+    }
+
+    /// Display the advisory's description (if enabled with `--show-description`)
+    fn print_description(&self, metadata: &advisory::Metadata, color: Color) -> io::Result<()> {
+        if !self.config.show_description || metadata.description.trim().is_empty() {
+            return Ok(());
+        }
+
+        let attr = "Description:  ";
+        let lines = text::render_markdown(&metadata.description, self.content_width(attr));
+        self.print_lines(color, attr, &lines)
+    }
+
+    /// Display an attribute of a particular vulnerability, wrapping its
+    /// content to the report's width with a hanging indent
+    fn print_attr(&self, color: Color, attr: &str, content: impl AsRef<str>) -> io::Result<()> {
+        let lines = text::wrap(content.as_ref(), self.content_width(attr));
+        self.print_lines(color, attr, &lines)
+    }
+
+    /// Display an attribute whose content links to the given URL: as a
+    /// hyperlink if they're enabled, otherwise as plain text
+    fn print_link(&self, color: Color, attr: &str, content: &str, url: &str) -> io::Result<()> {
+        if self.hyperlinks {
+            // Links are a single word, so they're never wrapped
+            self.print_lines(color, attr, &[text::hyperlink(content, url)])
+        } else {
+            self.print_attr(color, attr, content)
+        }
+    }
+
+    /// Display lines of an attribute's content, indenting all but the first
+    /// to line up under it
+    fn print_lines(&self, color: Color, attr: &str, lines: &[String]) -> io::Result<()> {
+        let indent = " ".repeat(attr.chars().count());
+
+        for (i, line) in lines.iter().enumerate() {
+            terminal::status::Status::new()
+                .bold()
+                .color(color)
+                .status(if i == 0 { attr } else { &indent })
+                .print_stdout(line)
+                .map_err(output::io_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Width available for the content of the given attribute
+    fn content_width(&self, attr: &str) -> usize {
+        // Rather than squeezing content into a sliver of a narrow terminal,
+        // let it overflow
+        self.width.saturating_sub(attr.chars().count() + 1).max(20)
+    }
+
+    /// Print the inverse dependency tree to standard output
+    fn print_tree(
+        &mut self,
+        color: Color,
+        package: &Package,
+        tree: Option<&dependency::Tree>,
+    ) -> io::Result<()> {
+        let tree = match tree {
+            Some(tree) => tree,
+            None => return Ok(()),
+        };
+
+        // Only show the tree once per package
+        if !self.displayed_packages.insert(Dependency::from(package)) {
+            return Ok(());
+        }
+
+        if !self.config.show_tree.unwrap_or(true) {
+            return Ok(());
+        }
+
+        // Findings for packages which aren't in the tree (e.g. from a report
+        // rendered against another lockfile's tree) are shown without one
+        let package_node = match tree.nodes().get(&Dependency::from(package)) {
+            Some(node) => *node,
+            None => return Ok(()),
+        };
+
+        terminal::status::Status::new()
+            .bold()
+            .color(color)
+            .status("Dependency tree:")
+            .print_stdout("")
+            .map_err(output::io_error)?;
+
+        let mut rendered = vec![];
+
+        if self.config.shortest_path {
+            tree.render_shortest_path(&mut rendered, package_node)?;
+        } else {
+            match self.config.tree_depth {
+                Some(depth) => tree.render_to_depth(
+                    &mut rendered,
+                    package_node,
+                    EdgeDirection::Incoming,
+                    depth,
+                )?,
+                None => tree.render(&mut rendered, package_node, EdgeDirection::Incoming)?,
+            }
+        }
+
+        // Deeply nested lines are truncated rather than wrapped, which would
+        // break up the tree
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+
+        for line in String::from_utf8_lossy(&rendered).lines() {
+            match self.tree_width {
+                Some(width) => writeln!(stdout, "{}", text::truncate(line, width))?,
+                None => writeln!(stdout, "{}", line)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Sort vulnerabilities in the given order (by severity if none is given).
+/// Reports are already sorted by advisory ID, then by package, and sorting
+/// is stable, so that remains the order of vulnerabilities which are
+/// otherwise equal.
+fn sort_vulnerabilities(vulnerabilities: &mut [&rustsec::Vulnerability], order: Option<SortOrder>) {
+    match order {
+        Some(SortOrder::Id) => (),
+        Some(SortOrder::Package) => vulnerabilities.sort_by(|a, b| {
+            (&a.package.name, &a.package.version, &a.package.source).cmp(&(
+                &b.package.name,
+                &b.package.version,
+                &b.package.source,
+            ))
+        }),
+        None | Some(SortOrder::Severity) => vulnerabilities.sort_by(|a, b| {
+            // Most severe first, then highest score first (scores are never NaN)
+            (b.effective_severity, cvss_score(b))
+                .partial_cmp(&(a.effective_severity, cvss_score(a)))
+                .unwrap_or(Ordering::Equal)
+        }),
+    }
+}
+
+/// CVSS base score of the advisory for a vulnerability (if it has one)
+fn cvss_score(vulnerability: &rustsec::Vulnerability) -> Option<f64> {
+    vulnerability
+        .advisory
+        .cvss
+        .as_ref()
+        .map(|cvss| cvss.score().value())
+}
+
+/// Group vulnerabilities by advisory, in the order each advisory first
+/// appears in
+fn group_vulnerabilities<'a>(
+    vulnerabilities: &[&'a rustsec::Vulnerability],
+) -> Vec<Vec<&'a rustsec::Vulnerability>> {
+    let mut groups: Vec<Vec<&rustsec::Vulnerability>> = vec![];
+
+    for &vulnerability in vulnerabilities {
+        match groups
+            .iter_mut()
+            .find(|group| group[0].advisory.id == vulnerability.advisory.id)
+        {
+            Some(group) => group.push(vulnerability),
+            None => groups.push(vec![vulnerability]),
+        }
+    }
+
+    groups
+}
+
+/// Group warnings of the same kind for the same advisory (e.g. about several
+/// versions of an unmaintained crate), keeping their order. Warnings without
+/// an advisory (e.g. about yanked crates) aren't grouped.
+fn group_warnings<'a>(warnings: &[&'a rustsec::Warning]) -> Vec<Vec<&'a rustsec::Warning>> {
+    let mut groups: Vec<Vec<&rustsec::Warning>> = vec![];
+
+    for &warning in warnings {
+        let id = warning.advisory.as_ref().map(|advisory| &advisory.id);
+
+        match groups.iter_mut().find(|group| {
+            id.is_some()
+                && group[0].kind == warning.kind
+                && group[0].advisory.as_ref().map(|advisory| &advisory.id) == id
+        }) {
+            Some(group) => group.push(warning),
+            None => groups.push(vec![warning]),
+        }
+    }
+
+    groups
+}
+
+/// Sort warnings of the same kind in the given order (if any). Reports are
+/// already sorted by package, then by advisory ID.
+/// Kinds of warnings in the order their sections are shown in reports, from
+/// the most to the least serious implications: vulnerable code which can't
+/// be checked automatically, then vulnerable code which may not be compiled,
+/// then unsoundness, then crates which won't get fixes, then ones which
+/// shouldn't be used, then everything else (including vulnerable code which
+/// isn't compiled for the target)
+const WARNING_SECTIONS: &[warning::Kind] = &[
+    warning::Kind::Overridden,
+    warning::Kind::Optional,
+    warning::Kind::Unsound,
+    warning::Kind::Unmaintained,
+    warning::Kind::Yanked,
+    warning::Kind::Notice,
+    warning::Kind::DuplicateVersions,
+    warning::Kind::OtherPlatform,
+];
+
+/// The report's warnings of each kind, in section order (omitting kinds
+/// without warnings)
+fn warning_sections(report: &rustsec::Report) -> Vec<(warning::Kind, &[rustsec::Warning])> {
+    let mut sections: Vec<_> = report
+        .warnings
+        .iter()
+        .filter(|(_, warnings)| !warnings.is_empty())
+        .map(|(kind, warnings)| (*kind, warnings.as_slice()))
+        .collect();
+
+    sections.sort_by_key(|(kind, _)| {
+        WARNING_SECTIONS
+            .iter()
+            .position(|section| section == kind)
+            .unwrap_or(WARNING_SECTIONS.len())
+    });
+
+    sections
+}
+
+/// Title of the report's section for a kind of warning
+fn section_title(kind: warning::Kind) -> &'static str {
+    match kind {
+        warning::Kind::Overridden => "Overridden dependencies",
+        warning::Kind::Optional => "Vulnerable crates behind disabled features",
+        warning::Kind::Unsound => "Unsound crates",
+        warning::Kind::Unmaintained => "Unmaintained crates",
+        warning::Kind::Yanked => "Yanked crates",
+        warning::Kind::Notice => "Notices",
+        warning::Kind::DuplicateVersions => "Duplicate versions",
+        warning::Kind::OtherPlatform => "Vulnerable crates for other platforms",
+        _ => "Other warnings",
+    }
+}
+
+fn sort_warnings(warnings: &mut [&rustsec::Warning], order: Option<SortOrder>) {
+    match order {
+        None | Some(SortOrder::Package) => (),
+        Some(SortOrder::Id) | Some(SortOrder::Severity) => warnings.sort_by(|a, b| {
+            a.advisory
+                .as_ref()
+                .map(|advisory| &advisory.id)
+                .cmp(&b.advisory.as_ref().map(|advisory| &advisory.id))
+        }),
+    }
+}
+
+/// Rows of the summary table, with severities, kinds of dependency and kinds
+/// of warnings indented under the totals they break down (empty if nothing
+/// was found)
+fn summary_table(summary: &report::Summary) -> Vec<(String, usize)> {
+    let vulnerabilities = summary.severities.values().sum::<usize>() + summary.unscored;
+    let warnings = summary
+        .warning_kinds
+        .values()
+        .map(|kind| kind.findings)
+        .sum::<usize>();
+
+    if vulnerabilities == 0 && warnings == 0 && summary.ignored == 0 {
+        return vec![];
+    }
+
+    let mut rows = vec![("Vulnerabilities".to_owned(), vulnerabilities)];
+
+    // Most severe first
+    for (severity, count) in summary.severities.iter().rev() {
+        rows.push((format!("  {}", severity), *count));
+    }
+
+    if summary.unscored > 0 {
+        rows.push(("  unscored".to_owned(), summary.unscored));
+    }
+
+    if let (Some(direct), Some(transitive)) = (
+        summary.direct_vulnerabilities,
+        summary.transitive_vulnerabilities,
+    ) {
+        rows.push(("  in direct deps".to_owned(), direct));
+        rows.push(("  in transitive deps".to_owned(), transitive));
+    }
+
+    rows.push(("Ignored".to_owned(), summary.ignored));
+    rows.push(("Warnings".to_owned(), warnings));
+
+    for (kind, warnings) in &summary.warning_kinds {
+        rows.push((format!("  {}", kind), warnings.findings));
+    }
+
+    rows
+}
+
+/// Find the advisories in the report which are related to others in the
+/// report (whichever of them lists the other as `related`)
+fn related_findings(report: &rustsec::Report) -> Map<advisory::Id, Set<advisory::Id>> {
+    let advisories: Vec<&advisory::Metadata> = report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vuln| &vuln.advisory)
+        .chain(
+            report
+                .warnings
+                .values()
+                .flatten()
+                .filter_map(|warning| warning.advisory.as_ref()),
+        )
+        .collect();
+
+    let mut related_findings = Map::<_, Set<_>>::new();
+
+    for advisory in &advisories {
+        for other in &advisories {
+            if advisory.related.contains(&other.id) && advisory.id != other.id {
+                related_findings
+                    .entry(advisory.id.clone())
+                    .or_default()
+                    .insert(other.id.clone());
+                related_findings
+                    .entry(other.id.clone())
+                    .or_default()
+                    .insert(advisory.id.clone());
+            }
+        }
+    }
+
+    related_findings
+}
+
+/// Display name of the given package's source
+fn source_name(package: &Package) -> String {
+    match &package.source {
+        Some(source) => source.to_string(),
+        None => "local".to_owned(),
+    }
+}
+
+/// Describe how a vulnerability's recommended upgrade can be applied (if
+/// its package's dependents are known)
+fn resolution(vuln: &rustsec::Vulnerability) -> Option<String> {
+    let name = &vuln.package.name;
+
+    let requirements = |dependents: &[Dependent], versions: bool| {
+        dependents
+            .iter()
+            .map(|dependent| {
+                if versions {
+                    format!(
+                        "{} {} (requires {})",
+                        dependent.name, dependent.version, dependent.requirement
+                    )
+                } else {
+                    format!("{} (requires {})", dependent.name, dependent.requirement)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    Some(match vuln.resolution.as_ref()? {
+        Resolution::Update { version } => format!(
+            "`cargo update -p {}` suffices: every dependent's requirement allows {}",
+            name, version
+        ),
+        Resolution::Bump {
+            version,
+            dependents,
+        } => format!(
+            "bump the requirement on {} to allow {} in {}, then run `cargo update -p {}`",
+            name,
+            version,
+            requirements(dependents, false),
+            name
+        ),
+        Resolution::Blocked {
+            version,
+            dependents,
+        } => format!(
+            "no fixed version is compatible with the requirements of {} - they must be \
+             upgraded (or {} patched) to get {}",
+            requirements(dependents, true),
+            name,
+            version
+        ),
+    })
+}
+
+/// Join version requirements for display, e.g. `>=1.0.5, <2.0.0 OR >=2.1.3`
+fn join_reqs(reqs: &[rustsec::VersionReq]) -> String {
+    reqs.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Join values into a comma-separated list
+fn join<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Write the report (or diff) to STDOUT as a single line of JSON
+fn write_json_report(w: &mut impl io::Write, report: &impl serde::Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *w, report)?;
+    writeln!(w)?;
+    w.flush()
+}
+
+/// Explanations of how a lockfile's packages were matched against an
+/// advisory, as output in JSON
+#[derive(serde::Serialize)]
+struct Explanations<'a> {
+    /// Advisory the packages were matched against
+    advisory: &'a advisory::Id,
+
+    /// Package the advisory is about
+    package: &'a package::Name,
+
+    /// Lockfile the packages are from
+    lockfile: &'a Path,
+
+    /// Explanation for each package
+    explanations: Vec<&'a Explanation>,
+}
+
+/// Describe whether the version matched each of the requirements, e.g.
+/// `>=0.7.25: matched (range [0.7.25, ∞))`
+fn requirement_lines(requirements: &[RequirementMatch]) -> Vec<String> {
+    if requirements.is_empty() {
+        return vec!["none".to_owned()];
+    }
+
+    requirements
+        .iter()
+        .map(|req| {
+            let mut line = format!(
+                "{}: {} (range {})",
+                req.requirement,
+                if req.matched {
+                    "matched"
+                } else {
+                    "not matched"
+                },
+                req.range
+            );
+
+            if req.cargo_matched != req.matched {
+                line.push_str(if req.cargo_matched {
+                    ", though cargo's requirement matching would match it"
+                } else {
+                    ", though cargo's requirement matching wouldn't match it"
+                });
+            }
+
+            line
+        })
+        .collect()
+}
+
+/// Summarize which advisory data a report uses, e.g. `612 advisories, last
+/// updated 2024-01-15 (commit ab12cd3)`, dating it by its newest advisory
+/// rather than when it was last fetched
+fn database_summary(database: &DatabaseInfo) -> String {
+    let mut summary = format!(
+        "{} {}",
+        database.advisory_count,
+        if database.advisory_count == 1 {
+            "advisory"
+        } else {
+            "advisories"
+        }
+    );
+
+    if let Some(date) = &database.newest_advisory {
+        summary.push_str(&format!(", last updated {}", date.as_str()));
+    }
+
+    if let Some(commit) = &database.last_commit {
+        summary.push_str(&format!(" (commit {})", commit.get(..7).unwrap_or(commit)));
+    }
+
+    summary
 }
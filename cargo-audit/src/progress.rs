@@ -0,0 +1,138 @@
+//! Progress bar for fetching the advisory database
+//!
+//! Fetches on slow or filtered networks can take minutes, so while one is in
+//! progress a single line on STDERR shows how far it's got (objects received
+//! and deltas resolved for git repositories, bytes downloaded for tarball
+//! snapshots). It's only drawn when STDERR is a terminal, and is cleared once
+//! the fetch finishes.
+
+use rustsec::repository::Progress;
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+/// How often the progress bar is redrawn at most
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Width of the bar itself, between the brackets
+const BAR_WIDTH: usize = 30;
+
+/// Progress bar drawn on STDERR
+#[derive(Debug)]
+pub struct ProgressBar {
+    /// Is the progress bar drawn at all?
+    enabled: bool,
+
+    /// When the progress bar was last drawn (if it has been)
+    drawn: Option<Instant>,
+}
+
+impl ProgressBar {
+    /// Create a progress bar, which is drawn unless `quiet` or STDERR isn't
+    /// a terminal
+    pub fn new(quiet: bool) -> Self {
+        Self {
+            enabled: !quiet && atty::is(atty::Stream::Stderr),
+            drawn: None,
+        }
+    }
+
+    /// Redraw the progress bar (unless it was redrawn very recently)
+    pub fn update(&mut self, progress: &Progress) {
+        if !self.enabled {
+            return;
+        }
+
+        let now = Instant::now();
+
+        if let Some(drawn) = self.drawn {
+            if now.duration_since(drawn) < REDRAW_INTERVAL {
+                return;
+            }
+        }
+
+        self.drawn = Some(now);
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\r{}\x1b[K", render(progress));
+        let _ = stderr.flush();
+    }
+
+    /// Clear the progress bar (if it was drawn)
+    pub fn finish(&mut self) {
+        if self.drawn.take().is_some() {
+            let mut stderr = io::stderr();
+            let _ = write!(stderr, "\r\x1b[K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+impl Drop for ProgressBar {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Render a line describing the given progress, e.g.
+/// `Receiving objects [=========>      ] 1200/3800`
+pub fn render(progress: &Progress) -> String {
+    if progress.is_resolving_deltas() {
+        return format!(
+            "{:>17} {} {}/{}",
+            "Resolving deltas",
+            bar(progress.indexed_deltas, progress.total_deltas),
+            progress.indexed_deltas,
+            progress.total_deltas
+        );
+    }
+
+    if progress.total_objects > 0 {
+        return format!(
+            "{:>17} {} {}/{} ({})",
+            "Receiving objects",
+            bar(progress.received_objects, progress.total_objects),
+            progress.received_objects,
+            progress.total_objects,
+            mebibytes(progress.received_bytes)
+        );
+    }
+
+    match progress.total_bytes {
+        Some(total) if total > 0 => format!(
+            "{:>17} {} {}/{}",
+            "Downloading",
+            bar(progress.received_bytes, total),
+            mebibytes(progress.received_bytes),
+            mebibytes(total)
+        ),
+        _ => format!(
+            "{:>17} {}",
+            "Downloading",
+            mebibytes(progress.received_bytes)
+        ),
+    }
+}
+
+/// Bar which is `done / total` full
+fn bar(done: usize, total: usize) -> String {
+    let filled = if total == 0 {
+        0
+    } else {
+        (done.min(total) * BAR_WIDTH) / total
+    };
+
+    let mut bar = "=".repeat(filled);
+
+    if filled < BAR_WIDTH {
+        bar.push('>');
+        bar.push_str(&" ".repeat(BAR_WIDTH - filled - 1));
+    }
+
+    format!("[{}]", bar)
+}
+
+/// Format a number of bytes in mebibytes
+fn mebibytes(bytes: usize) -> String {
+    format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
@@ -0,0 +1,171 @@
+//! Hints of whether vulnerable functions are called by the audited workspace
+//!
+//! With `--reachability`, the functions an advisory lists as affected are
+//! looked for in the Rust source files of the workspace being audited (every
+//! `.rs` file under the lockfile's directory, skipping hidden and `target`
+//! directories), and each vulnerability is annotated with whether any of
+//! them are referenced.
+//!
+//! This is a heuristic, not an analysis of the call graph: a line
+//! references a function if it mentions the function's name as an
+//! identifier, outside of a `//` comment, in a file which also mentions the
+//! function's crate (e.g. in a `use` declaration). Renamed dependencies,
+//! glob imports of re-exports, and calls made by other dependencies aren't
+//! found, so "no direct reference" doesn't prove a function is unreachable,
+//! but it helps to triage transitive findings.
+
+use rustsec::{
+    advisory::affected::FunctionPath,
+    error::{Error, ErrorKind},
+    vulnerability::{FunctionReference, Reachability},
+    Report,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Maximum number of references listed for each vulnerability
+pub const MAX_REFERENCES: usize = 10;
+
+/// Name of the directories cargo builds in, which are never searched
+const TARGET_DIR: &str = "target";
+
+/// Rust source files of a workspace
+#[derive(Clone, Debug, Default)]
+pub struct Sources {
+    /// Source files, by their path relative to the workspace's root
+    files: Vec<(PathBuf, String)>,
+}
+
+impl Sources {
+    /// Read the Rust source files under the given directory
+    pub fn load(root: &Path) -> Result<Self, Error> {
+        let mut sources = Self::default();
+        sources.search(root, Path::new(""))?;
+        Ok(sources)
+    }
+
+    /// Number of source files
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Are there no source files?
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Find the references to the given functions
+    pub fn reachability(&self, functions: &[FunctionPath]) -> Reachability {
+        let mut references = vec![];
+        let mut referenced = false;
+
+        for (path, source) in &self.files {
+            for function in functions {
+                let (crate_name, name) = match function.segments() {
+                    [crate_name, .., name] => (crate_name.as_str().replace('-', "_"), name),
+                    _ => continue,
+                };
+
+                if !contains_identifier(source, &crate_name) {
+                    continue;
+                }
+
+                for (index, line) in source.lines().enumerate() {
+                    let code = match line.find("//") {
+                        Some(comment) => &line[..comment],
+                        None => line,
+                    };
+
+                    if !contains_identifier(code, name.as_str()) {
+                        continue;
+                    }
+
+                    referenced = true;
+
+                    if references.len() < MAX_REFERENCES {
+                        references.push(FunctionReference {
+                            function: function.clone(),
+                            path: path.clone(),
+                            line: index + 1,
+                        });
+                    }
+                }
+            }
+        }
+
+        Reachability {
+            referenced,
+            references,
+        }
+    }
+
+    /// Add the source files in the given directory (at the given path
+    /// relative to the root) and its subdirectories
+    fn search(&mut self, root: &Path, relative: &Path) -> Result<(), Error> {
+        let dir = root.join(relative);
+
+        let mut entries = fs::read_dir(&dir)
+            .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Io,
+                    &format!("couldn't read {}: {}", dir.display(), e),
+                )
+            })?;
+
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let path = relative.join(entry.file_name());
+
+            // Symlinks aren't followed, so each file is only read once
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+
+            if file_type.is_dir() {
+                if !name.starts_with('.') && name != TARGET_DIR {
+                    self.search(root, &path)?;
+                }
+            } else if file_type.is_file() && name.ends_with(".rs") {
+                // Files which aren't UTF-8 can't be Rust source
+                if let Ok(source) = fs::read_to_string(entry.path()) {
+                    self.files.push((path, source));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Annotate each vulnerability whose advisory lists affected functions (for
+/// the vulnerable version) with whether they're referenced in the sources
+pub fn annotate(report: &mut Report, sources: &Sources) {
+    for vulnerability in &mut report.vulnerabilities.list {
+        let functions = match vulnerability.affected_functions() {
+            Some(functions) if !functions.is_empty() => functions,
+            _ => continue,
+        };
+
+        vulnerability.reachability = Some(sources.reachability(&functions));
+    }
+}
+
+/// Does the text contain the given identifier, i.e. not as part of a longer
+/// one?
+fn contains_identifier(text: &str, identifier: &str) -> bool {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    text.match_indices(identifier).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + identifier.len()..].chars().next();
+
+        !before.map_or(false, is_ident_char) && !after.map_or(false, is_ident_char)
+    })
+}
@@ -0,0 +1,237 @@
+//! Pluggable report renderers
+//!
+//! Reports can be rendered by a [`ReportRenderer`] rather than one of the
+//! built-in formats, so downstream tools needn't re-parse JSON reports just
+//! to reformat them. Renderers are called once at the start of the report,
+//! once for each vulnerability and warning (in the order of JSON reports),
+//! and once at the end.
+//!
+//! Tools embedding `cargo audit` can install their own renderer with
+//! [`Auditor::set_renderer`](crate::auditor::Auditor::set_renderer) (or
+//! [`Presenter::set_renderer`](crate::presenter::Presenter::set_renderer)).
+//! From the command line, `--reporter <program>` (with any `--reporter-arg`s)
+//! runs a program which is given the findings as NDJSON on STDIN (see
+//! [`crate::ndjson`]), and whose output is the report.
+
+use crate::ndjson;
+use rustsec::{cargo_lock::dependency::Tree, Report, Vulnerability, Warning};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    process::{self, Child, Stdio},
+    thread::{self, JoinHandle},
+};
+
+/// Renders the findings of an audit, one at a time
+pub trait ReportRenderer {
+    /// Start rendering the given report
+    fn begin_report(&mut self, _w: &mut dyn Write, _report: &Report) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Render a vulnerability, whose dependency paths can be found in the
+    /// dependency tree (if known)
+    fn render_vulnerability(
+        &mut self,
+        w: &mut dyn Write,
+        vulnerability: &Vulnerability,
+        tree: Option<&Tree>,
+    ) -> io::Result<()>;
+
+    /// Render a warning, whose dependency paths can be found in the
+    /// dependency tree (if known)
+    fn render_warning(
+        &mut self,
+        w: &mut dyn Write,
+        warning: &Warning,
+        tree: Option<&Tree>,
+    ) -> io::Result<()>;
+
+    /// Finish rendering the given report (e.g. with a summary)
+    fn end_report(&mut self, _w: &mut dyn Write, _report: &Report) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for dyn ReportRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ReportRenderer")
+    }
+}
+
+/// Render the report with the given renderer
+pub fn render(
+    renderer: &mut dyn ReportRenderer,
+    w: &mut dyn Write,
+    report: &Report,
+    tree: Option<&Tree>,
+) -> io::Result<()> {
+    renderer.begin_report(w, report)?;
+
+    for vulnerability in &report.vulnerabilities.list {
+        renderer.render_vulnerability(w, vulnerability, tree)?;
+    }
+
+    for warning in report.warnings.values().flatten() {
+        renderer.render_warning(w, warning, tree)?;
+    }
+
+    renderer.end_report(w, report)
+}
+
+/// Renderer which runs a command (`--reporter`), writing the findings to its
+/// STDIN as NDJSON lines, and its STDOUT to the output
+#[derive(Debug)]
+pub struct Command {
+    /// Program to run
+    program: String,
+
+    /// Arguments to run it with
+    args: Vec<String>,
+
+    /// Command rendering the current report (if one is being rendered)
+    child: Option<Child>,
+
+    /// Thread collecting the command's output, which is read as it's
+    /// written, so that a command writing as it reads doesn't block
+    output: Option<JoinHandle<io::Result<Vec<u8>>>>,
+}
+
+impl Command {
+    /// Renderer running the given program with the given arguments
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+            child: None,
+            output: None,
+        }
+    }
+
+    /// Write to the running command's STDIN (unless it stopped reading)
+    fn forward(&mut self, f: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+        let child = self
+            .child
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "reporter isn't running"))?;
+
+        let stdin = match child.stdin.as_mut() {
+            Some(stdin) => stdin,
+            None => return Ok(()),
+        };
+
+        match f(stdin) {
+            // The command stopped reading (e.g. `head`): whether it failed
+            // is up to its exit status
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                child.stdin = None;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Error for the command failing in the given way
+    fn error(&self, e: impl fmt::Display) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("reporter `{}` {}", self.program, e),
+        )
+    }
+}
+
+impl ReportRenderer for Command {
+    fn begin_report(&mut self, _w: &mut dyn Write, _report: &Report) -> io::Result<()> {
+        // Stop the command rendering a previous report which failed
+        if let Some(mut child) = self.child.take() {
+            stop(&mut child);
+        }
+
+        let mut child = process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| self.error(format_args!("couldn't be run: {}", e)))?;
+
+        let mut stdout = child.stdout.take().expect("STDOUT is piped");
+        self.output = Some(thread::spawn(move || {
+            let mut output = vec![];
+            stdout.read_to_end(&mut output)?;
+            Ok(output)
+        }));
+        self.child = Some(child);
+        Ok(())
+    }
+
+    fn render_vulnerability(
+        &mut self,
+        _w: &mut dyn Write,
+        vulnerability: &Vulnerability,
+        tree: Option<&Tree>,
+    ) -> io::Result<()> {
+        self.forward(|w| ndjson::Renderer.render_vulnerability(w, vulnerability, tree))
+    }
+
+    fn render_warning(
+        &mut self,
+        _w: &mut dyn Write,
+        warning: &Warning,
+        tree: Option<&Tree>,
+    ) -> io::Result<()> {
+        self.forward(|w| ndjson::Renderer.render_warning(w, warning, tree))
+    }
+
+    fn end_report(&mut self, w: &mut dyn Write, report: &Report) -> io::Result<()> {
+        self.forward(|w| ndjson::Renderer.end_report(w, report))?;
+
+        let mut child = self.child.take().expect("reporter is running");
+
+        // Close STDIN, so the command knows the report is complete
+        drop(child.stdin.take());
+
+        let output = match self
+            .output
+            .take()
+            .expect("reporter's output is collected")
+            .join()
+        {
+            Ok(Ok(output)) => output,
+            result => {
+                // Don't leave the command running (or unreaped) if its output
+                // couldn't be read
+                stop(&mut child);
+
+                return Err(match result {
+                    Ok(Err(e)) => e,
+                    _ => self.error("output couldn't be read"),
+                });
+            }
+        };
+
+        let status = child.wait()?;
+
+        if !status.success() {
+            return Err(self.error(format_args!("failed ({})", status)));
+        }
+
+        w.write_all(&output)?;
+        w.flush()
+    }
+}
+
+impl Drop for Command {
+    /// Don't leave the command running (or unreaped) if rendering the report
+    /// failed before it was finished
+    fn drop(&mut self) {
+        if let Some(child) = self.child.as_mut() {
+            stop(child);
+        }
+    }
+}
+
+/// Kill a command and wait for it to exit
+fn stop(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
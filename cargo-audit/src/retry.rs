@@ -0,0 +1,224 @@
+//! Retrying network operations after transient failures
+//!
+//! Fetching the advisory database and the crates.io index, and requests to
+//! the sparse index, EPSS API and KEV catalog, are attempted up to
+//! `net.attempts` times with exponential backoff (plus jitter, so concurrent
+//! audits don't retry in lockstep). Only transient failures are retried:
+//! timeouts, connection failures, server errors and rate limiting. Other
+//! failures, e.g. `404 Not Found` or authentication errors, fail immediately.
+
+use crate::config::NetConfig;
+use reqwest::StatusCode;
+use rustsec::error::{Error, ErrorKind};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    thread,
+    time::Duration,
+};
+
+/// How many times network operations are attempted by default
+pub const DEFAULT_ATTEMPTS: u32 = 3;
+
+/// How long to wait before the first retry (doubling after each one)
+pub const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Words in error messages from git (and the crates.io index, which is a git
+/// repository) which indicate a transient failure
+const TRANSIENT_GIT_ERRORS: &[&str] = &[
+    "timed out",
+    "timeout",
+    "connect",
+    "resolve",
+    "reset",
+    "network",
+    "ssl",
+    "tls",
+    "eof",
+    "status code: 5",
+    "status code: 429",
+];
+
+/// Policy for retrying network operations
+#[derive(Copy, Clone, Debug)]
+pub struct Retry {
+    /// How many times an operation is attempted
+    attempts: u32,
+
+    /// How long to wait before the first retry
+    backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::new(DEFAULT_ATTEMPTS)
+    }
+}
+
+impl Retry {
+    /// Attempt operations up to the given number of times (at least once)
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            backoff: INITIAL_BACKOFF,
+        }
+    }
+
+    /// Create the policy configured in `audit.toml`
+    pub fn from_config(config: &NetConfig) -> Self {
+        Self::new(config.attempts.unwrap_or(DEFAULT_ATTEMPTS))
+    }
+
+    /// Set how long to wait before the first retry (doubling after each one)
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// How many times operations are attempted
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Run the given operation (described by `what`, e.g.
+    /// `fetching EPSS scores`), retrying it after transient failures.
+    ///
+    /// Errors state how many attempts were made if the operation was retried
+    /// or failed transiently.
+    pub fn run<T, F>(&self, what: &str, mut operation: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Result<T, Failure>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+
+        loop {
+            let failure = match operation() {
+                Ok(value) => return Ok(value),
+                Err(failure) => failure,
+            };
+
+            if !failure.transient || attempt >= self.attempts {
+                if !failure.transient && attempt == 1 {
+                    return Err(failure.error);
+                }
+
+                return Err(Error::new(
+                    failure.error.kind(),
+                    &format!(
+                        "{} (after {} {})",
+                        failure.error.msg(),
+                        attempt,
+                        if attempt == 1 { "attempt" } else { "attempts" }
+                    ),
+                ));
+            }
+
+            let delay = jitter(backoff);
+            tracing::info!(
+                "{} failed ({}), retrying in {}ms (attempt {} of {})",
+                what,
+                failure.error.msg(),
+                delay.as_millis(),
+                attempt + 1,
+                self.attempts
+            );
+
+            thread::sleep(delay);
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+/// Failure of a single attempt at a network operation
+#[derive(Debug)]
+pub struct Failure {
+    /// What went wrong
+    pub error: Error,
+
+    /// Is the failure worth retrying?
+    pub transient: bool,
+}
+
+impl Failure {
+    /// Failure which is worth retrying
+    pub fn transient(error: Error) -> Self {
+        Self {
+            error,
+            transient: true,
+        }
+    }
+
+    /// Failure which fails the operation immediately
+    pub fn permanent(error: Error) -> Self {
+        Self {
+            error,
+            transient: false,
+        }
+    }
+
+    /// Failure of an HTTP request, which is transient if it timed out,
+    /// couldn't connect, was interrupted or got a server error or rate
+    /// limiting response
+    pub fn http(error: reqwest::Error) -> Self {
+        let transient = match error.status() {
+            Some(status) => is_transient_status(status),
+            None => {
+                error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+            }
+        };
+
+        Self {
+            error: Error::new(ErrorKind::Io, &error.to_string()),
+            transient,
+        }
+    }
+
+    /// Failure of an HTTP request which got the given (unsuccessful) response
+    /// status
+    pub fn status(status: StatusCode) -> Self {
+        Self {
+            error: Error::new(ErrorKind::Io, &format!("server responded with {}", status)),
+            transient: is_transient_status(status),
+        }
+    }
+
+    /// Failure of a git operation (including updating the crates.io index),
+    /// which is transient if its error describes a network problem or server
+    /// error
+    pub fn git(error: Error) -> Self {
+        let transient = match error.kind() {
+            ErrorKind::Io | ErrorKind::Repo | ErrorKind::Registry => {
+                let msg = error.msg().to_lowercase();
+
+                !msg.contains("auth")
+                    && !msg.contains("credential")
+                    && TRANSIENT_GIT_ERRORS.iter().any(|word| msg.contains(word))
+            }
+            _ => false,
+        };
+
+        Self { error, transient }
+    }
+}
+
+impl From<reqwest::Error> for Failure {
+    fn from(error: reqwest::Error) -> Self {
+        Self::http(error)
+    }
+}
+
+/// Is a response with the given status worth retrying?
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Randomize the given backoff to between half and all of it
+fn jitter(backoff: Duration) -> Duration {
+    let half = backoff / 2;
+    let range = half.as_millis() as u64 + 1;
+    let random = RandomState::new().build_hasher().finish();
+
+    half + Duration::from_millis(random % range)
+}
@@ -0,0 +1,349 @@
+//! SARIF reports
+//!
+//! The `sarif` format outputs the findings as a [SARIF 2.1.0] log, which CI
+//! code scanning services (e.g. GitHub Code Scanning, Azure DevOps and
+//! GitLab) can ingest directly. Each advisory becomes a rule, with its ID,
+//! title, CVSS score and URL, and each vulnerable (or warned about) package
+//! a result, located at the package's entry in `Cargo.lock`.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use rustsec::{advisory, package::Package, Report};
+use serde::Serialize;
+use std::{env, fs, path::Path};
+
+/// URL of the SARIF 2.1.0 JSON schema
+pub const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// SARIF log with a single run of `cargo audit`
+#[derive(Clone, Debug, Serialize)]
+pub struct Log {
+    /// Schema of the log
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+
+    /// Version of SARIF (always `2.1.0`)
+    pub version: &'static str,
+
+    /// Runs of analysis tools (just the one)
+    pub runs: Vec<Run>,
+}
+
+/// Run of `cargo audit`
+#[derive(Clone, Debug, Serialize)]
+pub struct Run {
+    /// Tool which performed the run
+    pub tool: Tool,
+
+    /// Findings of the run
+    pub results: Vec<SarifResult>,
+}
+
+/// Analysis tool
+#[derive(Clone, Debug, Serialize)]
+pub struct Tool {
+    /// The tool's main component
+    pub driver: Driver,
+}
+
+/// Component of an analysis tool, with the rules it checks
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Driver {
+    /// Name of the tool
+    pub name: &'static str,
+
+    /// Version of the tool
+    pub version: &'static str,
+
+    /// URL of the tool's documentation
+    pub information_uri: &'static str,
+
+    /// Rules results can be for: one for each advisory (or kind of warning
+    /// without an advisory) found
+    pub rules: Vec<Rule>,
+}
+
+/// Rule a result is for
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    /// Advisory ID, or kind of warning (e.g. `yanked`)
+    pub id: String,
+
+    /// Advisory title
+    pub short_description: Message,
+
+    /// Advisory description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_description: Option<Message>,
+
+    /// URL of the advisory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help_uri: Option<String>,
+
+    /// Properties of the rule (e.g. its severity)
+    pub properties: RuleProperties,
+}
+
+/// Properties of a rule
+#[derive(Clone, Debug, Serialize)]
+pub struct RuleProperties {
+    /// CVSS base score (e.g. `7.5`), as GitHub Code Scanning expects
+    #[serde(rename = "security-severity", skip_serializing_if = "Option::is_none")]
+    pub security_severity: Option<String>,
+
+    /// CVSS vector
+    #[serde(rename = "cvss", skip_serializing_if = "Option::is_none")]
+    pub cvss: Option<String>,
+
+    /// Tags, e.g. `security` and the advisory's categories
+    pub tags: Vec<String>,
+}
+
+/// Result of the run: a package a rule was broken for
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    /// ID of the rule
+    pub rule_id: String,
+
+    /// Index of the rule in the driver's rules
+    pub rule_index: usize,
+
+    /// `error` for vulnerabilities, `warning` for warnings
+    pub level: &'static str,
+
+    /// Description of the finding
+    pub message: Message,
+
+    /// Location of the package in the lockfile
+    pub locations: Vec<Location>,
+}
+
+/// Text of a message
+#[derive(Clone, Debug, Serialize)]
+pub struct Message {
+    /// The text
+    pub text: String,
+}
+
+/// Location of a result
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    /// Location in a file
+    pub physical_location: PhysicalLocation,
+}
+
+/// Location in a file
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    /// The file
+    pub artifact_location: ArtifactLocation,
+
+    /// Lines within the file (if the package's entry was found)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+}
+
+/// File a result is in
+#[derive(Clone, Debug, Serialize)]
+pub struct ArtifactLocation {
+    /// Path to the file, relative to the current directory if it's in it
+    pub uri: String,
+}
+
+/// Lines of a file
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    /// First line (1-based)
+    pub start_line: usize,
+}
+
+impl Log {
+    /// Convert the given report: vulnerabilities are errors, and warnings
+    /// are warnings
+    pub fn new(report: &Report) -> Self {
+        // Results are still reported, without lines, for lockfiles which
+        // can't be read again (e.g. they were read from STDIN)
+        let lockfile_source = report
+            .lockfile
+            .path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        let uri = artifact_uri(
+            report
+                .lockfile
+                .path
+                .as_deref()
+                .unwrap_or_else(|| Path::new("Cargo.lock")),
+        );
+
+        let mut rules: Vec<Rule> = vec![];
+        let mut results = vec![];
+
+        let mut add = |rule: Rule, level, package: &Package, message: String| {
+            let rule_index = match rules.iter().position(|existing| existing.id == rule.id) {
+                Some(index) => index,
+                None => {
+                    rules.push(rule);
+                    rules.len() - 1
+                }
+            };
+
+            results.push(SarifResult {
+                rule_id: rules[rule_index].id.clone(),
+                rule_index,
+                level,
+                message: Message { text: message },
+                locations: vec![Location {
+                    physical_location: PhysicalLocation {
+                        artifact_location: ArtifactLocation { uri: uri.clone() },
+                        region: package_line(&lockfile_source, package)
+                            .map(|start_line| Region { start_line }),
+                    },
+                }],
+            });
+        };
+
+        for vuln in &report.vulnerabilities.list {
+            let message = format!(
+                "{} {} is vulnerable: {} ({})",
+                vuln.package.name, vuln.package.version, vuln.advisory.title, vuln.advisory.id
+            );
+
+            add(
+                advisory_rule(&vuln.advisory),
+                "error",
+                &vuln.package,
+                message,
+            );
+        }
+
+        for warning in report.warnings.values().flatten() {
+            let kind = warning.kind.as_str();
+
+            let (rule, message) = match &warning.advisory {
+                Some(advisory) => (
+                    advisory_rule(advisory),
+                    format!(
+                        "{} {} is {}: {} ({})",
+                        warning.package.name,
+                        warning.package.version,
+                        kind,
+                        advisory.title,
+                        advisory.id
+                    ),
+                ),
+                None => (
+                    Rule {
+                        id: kind.to_owned(),
+                        short_description: Message {
+                            text: format!("{} package", kind),
+                        },
+                        full_description: None,
+                        help_uri: None,
+                        properties: RuleProperties {
+                            security_severity: None,
+                            cvss: None,
+                            tags: vec![kind.to_owned()],
+                        },
+                    },
+                    format!(
+                        "{} {} is {}",
+                        warning.package.name, warning.package.version, kind
+                    ),
+                ),
+            };
+
+            add(rule, "warning", &warning.package, message);
+        }
+
+        Self {
+            schema: SCHEMA,
+            version: "2.1.0",
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "cargo-audit",
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: "https://rustsec.org",
+                        rules,
+                    },
+                },
+                results,
+            }],
+        }
+    }
+}
+
+/// Rule for an advisory
+fn advisory_rule(advisory: &advisory::Metadata) -> Rule {
+    let mut tags = vec!["security".to_owned()];
+    tags.extend(advisory.categories.iter().map(ToString::to_string));
+
+    let full_description = if advisory.description.trim().is_empty() {
+        None
+    } else {
+        Some(Message {
+            text: advisory.description.trim().to_owned(),
+        })
+    };
+
+    Rule {
+        id: advisory.id.to_string(),
+        short_description: Message {
+            text: advisory.title.clone(),
+        },
+        full_description,
+        help_uri: advisory
+            .id
+            .url()
+            .or_else(|| advisory.url.as_ref().map(ToString::to_string)),
+        properties: RuleProperties {
+            security_severity: advisory
+                .cvss
+                .as_ref()
+                .map(|cvss| format!("{:.1}", cvss.score().value())),
+            cvss: advisory.cvss.as_ref().map(ToString::to_string),
+            tags,
+        },
+    }
+}
+
+/// URI of the lockfile: its path relative to the current directory (where
+/// code scanning runs from the root of the repository), if it's in it
+fn artifact_uri(lockfile_path: &Path) -> String {
+    let relative = env::current_dir()
+        .ok()
+        .and_then(|dir| lockfile_path.strip_prefix(dir).ok().map(Path::to_owned))
+        .unwrap_or_else(|| lockfile_path.to_owned());
+
+    let uri = relative.to_string_lossy().replace('\\', "/");
+    uri.strip_prefix("./").unwrap_or(&uri).to_owned()
+}
+
+/// Line (1-based) of the package's `[[package]]` entry in the lockfile's
+/// source, if it's found
+fn package_line(source: &str, package: &Package) -> Option<usize> {
+    let name = format!("name = \"{}\"", package.name);
+    let version = format!("version = \"{}\"", package.version);
+    let lines: Vec<&str> = source.lines().collect();
+
+    lines.windows(2).enumerate().find_map(|(index, pair)| {
+        if pair[0].trim() == name && pair[1].trim() == version {
+            // Point at the `[[package]]` header above the name, if it's there
+            match index.checked_sub(1).map(|header| lines[header].trim()) {
+                Some("[[package]]") => Some(index),
+                _ => Some(index + 1),
+            }
+        } else {
+            None
+        }
+    })
+}
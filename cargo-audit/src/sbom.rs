@@ -0,0 +1,411 @@
+//! Auditing CycloneDX SBOMs
+//!
+//! `cargo audit --sbom bom.json` audits a [CycloneDX] software bill of
+//! materials (in JSON or XML) instead of a lockfile, e.g. one generated in
+//! CI with `cargo cyclonedx` and kept with a release. The components with
+//! `pkg:cargo/...` package URLs are audited like a lockfile's packages, and
+//! the SBOM's dependency graph (if it has one) is used for the dependency
+//! tree. Other components (e.g. npm packages) are ignored.
+//!
+//! [CycloneDX]: https://cyclonedx.org
+
+use rustsec::{
+    cargo_lock::{
+        package::{self, source::CRATES_IO_INDEX, SourceId},
+        Dependency, Lockfile, Package,
+    },
+    error::{Error, ErrorKind},
+    Version,
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap as Map, fs, path::Path};
+
+/// Prefix of the package URLs of crates
+pub const CARGO_PURL_PREFIX: &str = "pkg:cargo/";
+
+/// CycloneDX BOM, as JSON
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bom {
+    /// Always `CycloneDX`
+    bom_format: Option<String>,
+
+    /// Metadata, with the component the BOM describes
+    metadata: Option<Metadata>,
+
+    /// Components of the described component
+    #[serde(default)]
+    components: Vec<Component>,
+
+    /// Dependency graph of the components
+    #[serde(default)]
+    dependencies: Vec<DependencyEntry>,
+}
+
+/// Metadata of a BOM
+#[derive(Debug, Default, Deserialize)]
+struct Metadata {
+    /// Component the BOM describes
+    component: Option<Component>,
+}
+
+/// Component listed in a BOM
+#[derive(Debug, Default, Deserialize)]
+struct Component {
+    /// Reference dependencies refer to the component by
+    #[serde(rename = "bom-ref")]
+    bom_ref: Option<String>,
+
+    /// Package URL of the component
+    purl: Option<String>,
+
+    /// Components the component is made of
+    #[serde(default)]
+    components: Vec<Component>,
+}
+
+/// Dependencies of a component
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DependencyEntry {
+    /// Reference of the dependent component
+    #[serde(rename = "ref")]
+    reference: String,
+
+    /// References of its dependencies
+    #[serde(default)]
+    depends_on: Vec<String>,
+}
+
+/// Crate identified by a package URL
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Crate {
+    /// Name of the crate
+    pub name: package::Name,
+
+    /// Version of the crate
+    pub version: Version,
+
+    /// Is the crate from crates.io (the default when the package URL has
+    /// no `repository_url` qualifier)?
+    pub crates_io: bool,
+}
+
+/// Read the CycloneDX SBOM (JSON or XML) at the given path, as a lockfile
+/// listing the crates it has components for
+pub fn read(path: &Path) -> Result<Lockfile, Error> {
+    let source = fs::read_to_string(path).map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't read {}: {}", path.display(), e),
+        )
+    })?;
+
+    parse(&source).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse {}: {}", path.display(), e.msg()),
+        )
+    })
+}
+
+/// Parse a CycloneDX SBOM (JSON or XML, told apart by its first character)
+/// into a lockfile listing the crates it has components for
+pub fn parse(source: &str) -> Result<Lockfile, Error> {
+    let bom = if source.trim_start().starts_with('<') {
+        parse_xml(source)?
+    } else {
+        let bom: Bom = serde_json::from_str(source)
+            .map_err(|e| Error::new(ErrorKind::Parse, &e.to_string()))?;
+
+        match &bom.bom_format {
+            Some(format) if format != "CycloneDX" => {
+                return Err(Error::new(
+                    ErrorKind::Parse,
+                    &format!("unsupported BOM format {:?} (expected CycloneDX)", format),
+                ));
+            }
+            _ => bom,
+        }
+    };
+
+    Ok(lockfile(bom))
+}
+
+/// Parse the crate a package URL identifies (e.g.
+/// `pkg:cargo/rand@0.7.2`), if it's a valid `cargo` one
+pub fn parse_purl(purl: &str) -> Option<Crate> {
+    // Subpaths don't matter for identifying the crate
+    let purl = purl.split('#').next()?;
+    let mut parts = purl.splitn(2, '?');
+    let path = parts.next()?;
+    let qualifiers = parts.next().unwrap_or("");
+
+    if !path.to_ascii_lowercase().starts_with(CARGO_PURL_PREFIX) {
+        return None;
+    }
+
+    let mut name_version = path[CARGO_PURL_PREFIX.len()..].rsplitn(2, '@');
+    let version = percent_decode(name_version.next()?);
+    let name = percent_decode(name_version.next()?);
+
+    let repository_url = qualifiers
+        .split('&')
+        .filter_map(|qualifier| {
+            let mut key_value = qualifier.splitn(2, '=');
+            match (key_value.next(), key_value.next()) {
+                (Some("repository_url"), Some(url)) => Some(percent_decode(url)),
+                _ => None,
+            }
+        })
+        .next();
+
+    let crates_io = match repository_url {
+        Some(url) => {
+            let url = url.trim_end_matches('/');
+            url == CRATES_IO_INDEX || url == "https://crates.io"
+        }
+        None => true,
+    };
+
+    Some(Crate {
+        name: name.parse().ok()?,
+        version: version.parse().ok()?,
+        crates_io,
+    })
+}
+
+/// Lockfile listing the crates of the BOM's components, with dependencies
+/// between them from its dependency graph
+fn lockfile(bom: Bom) -> Lockfile {
+    let crates_io_source = SourceId::from_url(&format!("registry+{}", CRATES_IO_INDEX)).ok();
+
+    let mut components = vec![];
+    let mut pending: Vec<Component> = bom.components;
+    pending.extend(bom.metadata.and_then(|metadata| metadata.component));
+
+    while let Some(mut component) = pending.pop() {
+        pending.append(&mut component.components);
+        components.push(component);
+    }
+
+    // Crates by the references of their components
+    let mut crates: Map<String, Crate> = Map::new();
+    let mut packages: Vec<Package> = vec![];
+
+    for component in &components {
+        let krate = match component.purl.as_deref().and_then(parse_purl) {
+            Some(krate) => krate,
+            None => continue,
+        };
+
+        if let Some(reference) = &component.bom_ref {
+            crates.insert(reference.clone(), krate.clone());
+        }
+
+        let listed = packages
+            .iter()
+            .any(|package| package.name == krate.name && package.version == krate.version);
+
+        if !listed {
+            packages.push(Package {
+                name: krate.name,
+                version: krate.version,
+                source: if krate.crates_io {
+                    crates_io_source.clone()
+                } else {
+                    None
+                },
+                checksum: None,
+                dependencies: vec![],
+                replace: None,
+            });
+        }
+    }
+
+    for entry in &bom.dependencies {
+        let dependent = match crates.get(&entry.reference) {
+            Some(krate) => krate,
+            None => continue,
+        };
+
+        let package = match packages.iter().position(|package| {
+            package.name == dependent.name && package.version == dependent.version
+        }) {
+            Some(index) => index,
+            None => continue,
+        };
+
+        for reference in &entry.depends_on {
+            if let Some(dependency) = crates.get(reference) {
+                let dependency = Dependency {
+                    name: dependency.name.clone(),
+                    version: dependency.version.clone(),
+                    source: if dependency.crates_io {
+                        crates_io_source.clone()
+                    } else {
+                        None
+                    },
+                };
+
+                if !packages[package].dependencies.contains(&dependency) {
+                    packages[package].dependencies.push(dependency);
+                }
+            }
+        }
+    }
+
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+    Lockfile {
+        version: Default::default(),
+        packages,
+        root: None,
+        metadata: Default::default(),
+        patch: Default::default(),
+    }
+}
+
+/// Parse a BOM in CycloneDX's XML format
+fn parse_xml(source: &str) -> Result<Bom, Error> {
+    use quick_xml::events::{BytesStart, Event};
+
+    /// Value of the attribute with the given name (if the element has it)
+    fn attribute(
+        element: &BytesStart<'_>,
+        name: &[u8],
+        reader: &quick_xml::Reader<&[u8]>,
+    ) -> Option<String> {
+        element
+            .attributes()
+            .filter_map(Result::ok)
+            .find(|attribute| attribute.key == name)
+            .and_then(|attribute| attribute.unescape_and_decode_value(reader).ok())
+    }
+
+    let mut reader = quick_xml::Reader::from_str(source);
+    reader.trim_text(true);
+
+    let mut bom = Bom::default();
+    let mut buf = vec![];
+
+    // Names of the open elements, components being read, and the
+    // dependency whose dependencies are being read
+    let mut elements: Vec<Vec<u8>> = vec![];
+    let mut open_components: Vec<Component> = vec![];
+    let mut dependency: Option<DependencyEntry> = None;
+    let mut dependency_depth = 0;
+
+    loop {
+        let event = reader
+            .read_event(&mut buf)
+            .map_err(|e| Error::new(ErrorKind::Parse, &e.to_string()))?;
+
+        match event {
+            Event::Start(ref element) | Event::Empty(ref element) => {
+                let is_start = matches!(event, Event::Start(_));
+                let name = element.local_name().to_vec();
+
+                if elements.is_empty() && name != b"bom" {
+                    return Err(Error::new(
+                        ErrorKind::Parse,
+                        &"unsupported BOM format (expected a CycloneDX <bom>)",
+                    ));
+                }
+
+                match name.as_slice() {
+                    b"component" => {
+                        let component = Component {
+                            bom_ref: attribute(element, b"bom-ref", &reader),
+                            ..Component::default()
+                        };
+
+                        if is_start {
+                            open_components.push(component);
+                        }
+                    }
+                    b"dependency" => {
+                        let reference = attribute(element, b"ref", &reader).unwrap_or_default();
+
+                        match &mut dependency {
+                            Some(outer) => outer.depends_on.push(reference),
+                            None if is_start => {
+                                dependency = Some(DependencyEntry {
+                                    reference,
+                                    depends_on: vec![],
+                                });
+                                dependency_depth = elements.len();
+                            }
+                            None => bom.dependencies.push(DependencyEntry {
+                                reference,
+                                depends_on: vec![],
+                            }),
+                        }
+                    }
+                    _ => (),
+                }
+
+                if is_start {
+                    elements.push(name);
+                }
+            }
+            Event::Text(text) => {
+                let in_component_purl = elements.last().map(Vec::as_slice) == Some(b"purl")
+                    && elements.len() >= 2
+                    && elements[elements.len() - 2] == b"component";
+
+                if in_component_purl {
+                    if let Some(component) = open_components.last_mut() {
+                        component.purl = text.unescape_and_decode(&reader).ok();
+                    }
+                }
+            }
+            Event::End(_) => {
+                let name = elements.pop().unwrap_or_default();
+
+                match name.as_slice() {
+                    b"component" => {
+                        if let Some(component) = open_components.pop() {
+                            bom.components.push(component);
+                        }
+                    }
+                    b"dependency" if elements.len() == dependency_depth => {
+                        bom.dependencies.extend(dependency.take());
+                    }
+                    _ => (),
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+
+        buf.clear();
+    }
+
+    Ok(bom)
+}
+
+/// Decode the percent-encoded characters (e.g. `%2B` for `+`) of part of a
+/// package URL
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,138 @@
+//! Ignores scoped to packages and dependency paths
+//!
+//! Ignoring an advisory is too blunt in a monorepo, where it may be harmless
+//! where one member pulls in the vulnerable crate (e.g. only through
+//! `criterion`, for benchmarks) but not where another does. Ignores in
+//! `advisories.ignore` with a `package` (and `version`) only apply to the
+//! vulnerabilities in that package, and those with `via` packages only to
+//! the vulnerable packages which the workspace can't reach in the dependency
+//! graph without going through one of them. The advisory's other
+//! vulnerabilities are reported as usual.
+//!
+//! Unlike other ignores, which are applied while matching advisories, these
+//! are applied to the report afterwards, once the dependency graph is known.
+
+use crate::config::IgnoredAdvisory;
+use rustsec::{
+    advisory,
+    cargo_lock::dependency::{
+        graph::{EdgeDirection, NodeIndex},
+        Dependency, Tree,
+    },
+    package::{self, Package},
+    report::VulnerabilityInfo,
+    Report,
+};
+use std::collections::BTreeSet as Set;
+
+/// Leave the vulnerabilities and warnings which active scoped ignores apply
+/// to out of the report, returning how many vulnerabilities were left out
+pub fn apply(
+    report: &mut Report,
+    ignore: &[IgnoredAdvisory],
+    tree: Option<&Tree>,
+    today: &advisory::Date,
+) -> usize {
+    let scoped: Vec<_> = ignore
+        .iter()
+        .filter(|ignored| ignored.is_scoped() && ignored.is_active(today))
+        .collect();
+
+    if scoped.is_empty() {
+        return 0;
+    }
+
+    let is_ignored = |id: &advisory::Id, package: &Package| {
+        scoped
+            .iter()
+            .any(|ignored| ignored.id == *id && applies(ignored, package, tree))
+    };
+
+    let (ignored, list): (Vec<_>, Vec<_>) = report
+        .vulnerabilities
+        .list
+        .drain(..)
+        .partition(|vuln| is_ignored(&vuln.advisory.id, &vuln.package));
+
+    for warnings in report.warnings.values_mut() {
+        warnings.retain(|warning| match &warning.advisory {
+            Some(advisory) => !is_ignored(&advisory.id, &warning.package),
+            None => true,
+        });
+    }
+
+    report.warnings.retain(|_, warnings| !warnings.is_empty());
+
+    if !ignored.is_empty() {
+        let category_ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+        let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+        let vex = std::mem::take(&mut report.vulnerabilities.vex);
+        report.vulnerabilities = VulnerabilityInfo::new(list);
+        report.vulnerabilities.ignored = category_ignored;
+        report.vulnerabilities.acknowledged = acknowledged;
+        report.vulnerabilities.vex = vex;
+        report.sort();
+    } else {
+        report.vulnerabilities.list = list;
+    }
+
+    ignored.len()
+}
+
+/// Does the (scoped) ignore apply to the given package?
+///
+/// Ignores with `via` packages never apply without a dependency graph to
+/// check the paths to the package against.
+pub fn applies(ignored: &IgnoredAdvisory, package: &Package, tree: Option<&Tree>) -> bool {
+    if matches!(&ignored.package, Some(name) if *name != package.name) {
+        return false;
+    }
+
+    if matches!(&ignored.version, Some(version) if *version != package.version) {
+        return false;
+    }
+
+    if ignored.via.is_empty() {
+        return true;
+    }
+
+    let tree = match tree {
+        Some(tree) => tree,
+        None => return false,
+    };
+
+    match tree.nodes().get(&Dependency::from(package)) {
+        Some(&node) => !reachable_avoiding(tree, node, &ignored.via),
+        None => false,
+    }
+}
+
+/// Can the given node be reached from a root of the dependency graph
+/// without going through any of the packages with the given names?
+fn reachable_avoiding(tree: &Tree, target: NodeIndex, via: &[package::Name]) -> bool {
+    let graph = tree.graph();
+    let avoided = |node: NodeIndex| via.contains(&graph[node].name);
+
+    let mut visited = Set::new();
+    let mut pending: Vec<_> = tree
+        .roots()
+        .into_iter()
+        .filter(|&root| !avoided(root))
+        .collect();
+
+    while let Some(node) = pending.pop() {
+        if node == target {
+            return true;
+        }
+
+        if visited.insert(node) {
+            pending.extend(
+                graph
+                    .neighbors_directed(node, EdgeDirection::Outgoing)
+                    .filter(|&dependency| !avoided(dependency)),
+            );
+        }
+    }
+
+    false
+}
@@ -0,0 +1,475 @@
+//! SPDX reports
+//!
+//! The `spdx` format outputs an [SPDX 2.3] JSON document, for compliance
+//! pipelines which only accept SPDX. Each package of the lockfile is an SPDX
+//! package, with its package URL as an external reference, and the document
+//! records which packages depend on which (when the dependency graph is
+//! known). Each finding is attached to its package, as an annotation and as
+//! a security reference to the advisory.
+//!
+//! [SPDX 2.3]: https://spdx.github.io/spdx-spec/v2.3/
+
+use rustsec::{
+    advisory,
+    cargo_lock::{
+        dependency::{graph::EdgeDirection, Tree},
+        Dependency,
+    },
+    package::Package,
+    report::InventoryPackage,
+    Report,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    time::SystemTime,
+};
+
+/// Version of SPDX
+pub const SPDX_VERSION: &str = "SPDX-2.3";
+
+/// ID of the document itself
+pub const DOCUMENT_ID: &str = "SPDXRef-DOCUMENT";
+
+/// Value of fields which weren't determined
+pub const NOASSERTION: &str = "NOASSERTION";
+
+/// SPDX document
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    /// Version of SPDX (always `SPDX-2.3`)
+    pub spdx_version: &'static str,
+
+    /// License of the document's data (always `CC0-1.0`)
+    pub data_license: &'static str,
+
+    /// ID of the document (always `SPDXRef-DOCUMENT`)
+    #[serde(rename = "SPDXID")]
+    pub spdx_id: &'static str,
+
+    /// Name of the document
+    pub name: String,
+
+    /// URI uniquely identifying the document
+    pub document_namespace: String,
+
+    /// When and by what the document was created
+    pub creation_info: CreationInfo,
+
+    /// Packages of the lockfile
+    pub packages: Vec<SpdxPackage>,
+
+    /// Relationships between the document and packages
+    pub relationships: Vec<Relationship>,
+}
+
+/// When and by what a document was created
+#[derive(Clone, Debug, Serialize)]
+pub struct CreationInfo {
+    /// When the document was created (RFC 3339)
+    pub created: String,
+
+    /// Tools which created the document
+    pub creators: Vec<String>,
+}
+
+/// Package in a document
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpdxPackage {
+    /// ID of the package in the document, e.g. `SPDXRef-Package-foo-0.1.0`
+    #[serde(rename = "SPDXID")]
+    pub spdx_id: String,
+
+    /// Name of the package
+    pub name: String,
+
+    /// Version of the package
+    pub version_info: String,
+
+    /// Where the package can be downloaded from (or `NOASSERTION`)
+    pub download_location: String,
+
+    /// Are the package's files listed? (never)
+    pub files_analyzed: bool,
+
+    /// Checksums of the package (if the lockfile has one)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub checksums: Vec<Checksum>,
+
+    /// Package URL of the package, and the advisories found for it
+    pub external_refs: Vec<ExternalRef>,
+
+    /// Findings for the package
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+/// Checksum of a package
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checksum {
+    /// Algorithm (always `SHA256`)
+    pub algorithm: &'static str,
+
+    /// Hex digest
+    pub checksum_value: String,
+}
+
+/// Reference from a package to something outside the document
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalRef {
+    /// `PACKAGE-MANAGER` (for package URLs) or `SECURITY` (for advisories)
+    pub reference_category: &'static str,
+
+    /// `purl` or `advisory`
+    pub reference_type: &'static str,
+
+    /// Package URL, or URL of the advisory
+    pub reference_locator: String,
+
+    /// Description of the reference (for advisories)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// Annotation of a package with a finding
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    /// When the finding was made (RFC 3339)
+    pub annotation_date: String,
+
+    /// Always `REVIEW`
+    pub annotation_type: &'static str,
+
+    /// Tool which made the finding
+    pub annotator: String,
+
+    /// Description of the finding, e.g. `RUSTSEC-2020-0001 (vulnerability):
+    /// ...`
+    pub comment: String,
+}
+
+/// Relationship between two elements of a document
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Relationship {
+    /// ID of the element the relationship is from
+    pub spdx_element_id: String,
+
+    /// Kind of relationship: `DESCRIBES` or `DEPENDS_ON`
+    pub relationship_type: &'static str,
+
+    /// ID of the element the relationship is to
+    pub related_spdx_element: String,
+}
+
+impl Document {
+    /// Convert the given report, listing the packages in the dependency tree
+    /// (if known), or otherwise the report's inventory (if it has one), or
+    /// otherwise just the packages with findings
+    pub fn new(report: &Report, tree: Option<&Tree>) -> Self {
+        let created = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+        let tool = format!("Tool: cargo-audit-{}", env!("CARGO_PKG_VERSION"));
+
+        let mut packages: Vec<InventoryPackage> = match (tree, &report.inventory) {
+            (Some(tree), _) => tree
+                .graph()
+                .node_indices()
+                .map(|node| InventoryPackage::from(&tree.graph()[node]))
+                .collect(),
+            (None, Some(inventory)) => inventory.packages.clone(),
+            (None, None) => report
+                .vulnerabilities
+                .list
+                .iter()
+                .map(|vuln| &vuln.package)
+                .chain(report.warnings.values().flatten().map(|w| &w.package))
+                .map(InventoryPackage::from)
+                .collect(),
+        };
+
+        packages.sort_by_key(dependency);
+        packages.dedup_by_key(|package| dependency(package));
+
+        let indices: Map<Dependency, usize> = packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| (dependency(package), index))
+            .collect();
+
+        let mut ids = Set::new();
+        let mut spdx_packages: Vec<SpdxPackage> = packages
+            .iter()
+            .map(|package| spdx_package(package, &mut ids))
+            .collect();
+
+        let mut findings: Vec<(&Package, Option<&advisory::Metadata>, String)> = vec![];
+
+        for vuln in &report.vulnerabilities.list {
+            let mut details = vec![];
+
+            if let Some(cvss) = &vuln.advisory.cvss {
+                details.push(format!(
+                    "severity: {} ({:.1})",
+                    cvss.severity(),
+                    cvss.score().value()
+                ));
+            }
+
+            details.push(match vuln.versions.patched() {
+                [] => "no patched versions".to_owned(),
+                patched => format!(
+                    "patched: {}",
+                    patched
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                ),
+            });
+
+            let comment = format!(
+                "{} (vulnerability): {} ({})",
+                vuln.advisory.id,
+                vuln.advisory.title,
+                details.join(", ")
+            );
+
+            findings.push((&vuln.package, Some(&vuln.advisory), comment));
+        }
+
+        for warning in report.warnings.values().flatten() {
+            let comment = match &warning.advisory {
+                Some(advisory) => format!("{} ({}): {}", advisory.id, warning.kind, advisory.title),
+                None => format!("{} package", warning.kind),
+            };
+
+            findings.push((&warning.package, warning.advisory.as_ref(), comment));
+        }
+
+        for (package, advisory, comment) in findings {
+            let spdx_package = match indices.get(&Dependency::from(package)) {
+                Some(&index) => &mut spdx_packages[index],
+                None => continue,
+            };
+
+            if let Some(url) = advisory.and_then(advisory_url) {
+                spdx_package.external_refs.push(ExternalRef {
+                    reference_category: "SECURITY",
+                    reference_type: "advisory",
+                    reference_locator: url,
+                    comment: advisory
+                        .map(|advisory| format!("{}: {}", advisory.id, advisory.title)),
+                });
+            }
+
+            spdx_package.annotations.push(Annotation {
+                annotation_date: created.clone(),
+                annotation_type: "REVIEW",
+                annotator: tool.clone(),
+                comment,
+            });
+        }
+
+        let relationships = relationships(&indices, &spdx_packages, tree);
+
+        let name = match &report.lockfile.path {
+            Some(path) => format!("cargo-audit report for {}", path.display()),
+            None => "cargo-audit report".to_owned(),
+        };
+
+        Self {
+            spdx_version: SPDX_VERSION,
+            data_license: "CC0-1.0",
+            spdx_id: DOCUMENT_ID,
+            document_namespace: namespace(&name, &created, &spdx_packages),
+            name,
+            creation_info: CreationInfo {
+                created,
+                creators: vec![tool],
+            },
+            packages: spdx_packages,
+            relationships,
+        }
+    }
+}
+
+/// Convert a package, giving it an ID which isn't in `ids` (and adding it)
+fn spdx_package(package: &InventoryPackage, ids: &mut Set<String>) -> SpdxPackage {
+    let base = format!(
+        "SPDXRef-Package-{}-{}",
+        sanitize(package.name.as_str()),
+        sanitize(&package.version.to_string())
+    );
+
+    let mut spdx_id = base.clone();
+    let mut n = 1;
+
+    while !ids.insert(spdx_id.clone()) {
+        n += 1;
+        spdx_id = format!("{}-{}", base, n);
+    }
+
+    let download_location = match &package.source {
+        Some(source) if source.is_default_registry() => format!(
+            "https://crates.io/api/v1/crates/{}/{}/download",
+            package.name, package.version
+        ),
+        Some(source) if source.is_git() => format!("git+{}", source.url()),
+        _ => NOASSERTION.to_owned(),
+    };
+
+    SpdxPackage {
+        spdx_id,
+        name: package.name.to_string(),
+        version_info: package.version.to_string(),
+        download_location,
+        files_analyzed: false,
+        checksums: package
+            .checksum
+            .iter()
+            .map(|checksum| Checksum {
+                algorithm: "SHA256",
+                checksum_value: checksum.to_string(),
+            })
+            .collect(),
+        external_refs: vec![ExternalRef {
+            reference_category: "PACKAGE-MANAGER",
+            reference_type: "purl",
+            reference_locator: format!("pkg:cargo/{}@{}", package.name, package.version),
+            comment: None,
+        }],
+        annotations: vec![],
+    }
+}
+
+/// The document describes the workspace's packages (the local roots of the
+/// dependency tree, or every package if it isn't known), and each package
+/// depends on its dependencies
+fn relationships(
+    indices: &Map<Dependency, usize>,
+    spdx_packages: &[SpdxPackage],
+    tree: Option<&Tree>,
+) -> Vec<Relationship> {
+    let tree = match tree {
+        Some(tree) => tree,
+        None => {
+            return spdx_packages
+                .iter()
+                .map(|package| describes(&package.spdx_id))
+                .collect()
+        }
+    };
+
+    let graph = tree.graph();
+    let id = |node| {
+        spdx_packages[indices[&Dependency::from(&graph[node])]]
+            .spdx_id
+            .as_str()
+    };
+
+    let mut roots: Vec<_> = tree
+        .roots()
+        .into_iter()
+        .filter(|&root| graph[root].source.is_none())
+        .collect();
+
+    if roots.is_empty() {
+        roots = tree.roots();
+    }
+
+    let mut relationships: Vec<Relationship> =
+        roots.into_iter().map(|root| describes(id(root))).collect();
+    relationships.sort_by(|a, b| a.related_spdx_element.cmp(&b.related_spdx_element));
+
+    let mut dependencies: Vec<Relationship> = graph
+        .node_indices()
+        .flat_map(|node| {
+            graph
+                .neighbors_directed(node, EdgeDirection::Outgoing)
+                .map(move |dependency| (node, dependency))
+        })
+        .map(|(node, dependency)| Relationship {
+            spdx_element_id: id(node).to_owned(),
+            relationship_type: "DEPENDS_ON",
+            related_spdx_element: id(dependency).to_owned(),
+        })
+        .collect();
+
+    dependencies.sort_by(|a, b| {
+        (&a.spdx_element_id, &a.related_spdx_element)
+            .cmp(&(&b.spdx_element_id, &b.related_spdx_element))
+    });
+    dependencies.dedup_by(|a, b| {
+        a.spdx_element_id == b.spdx_element_id && a.related_spdx_element == b.related_spdx_element
+    });
+
+    relationships.extend(dependencies);
+    relationships
+}
+
+/// URL of the advisory (if it has one)
+fn advisory_url(advisory: &advisory::Metadata) -> Option<String> {
+    advisory
+        .id
+        .url()
+        .or_else(|| advisory.url.as_ref().map(ToString::to_string))
+}
+
+/// Package as a dependency, which identifies it in the lockfile
+fn dependency(package: &InventoryPackage) -> Dependency {
+    Dependency {
+        name: package.name.clone(),
+        version: package.version.clone(),
+        source: package.source.clone(),
+    }
+}
+
+/// The document describes the package with the given ID
+fn describes(spdx_id: &str) -> Relationship {
+    Relationship {
+        spdx_element_id: DOCUMENT_ID.to_owned(),
+        relationship_type: "DESCRIBES",
+        related_spdx_element: spdx_id.to_owned(),
+    }
+}
+
+/// Unique namespace of the document, from a digest of its name, creation
+/// time and packages
+fn namespace(name: &str, created: &str, packages: &[SpdxPackage]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(name.as_bytes());
+    hasher.update(created.as_bytes());
+
+    for package in packages {
+        hasher.update(package.spdx_id.as_bytes());
+        hasher.update(&[package.annotations.len() as u8]);
+    }
+
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    format!("https://rustsec.org/spdxdocs/cargo-audit-{}", digest)
+}
+
+/// Replace characters which can't be in SPDX IDs (anything but letters,
+/// numbers, `.` and `-`) with `-`
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,318 @@
+//! Tracking findings across runs
+//!
+//! With `--state-file`, the findings of each audit are recorded in a JSON
+//! file along with the date each was first seen. The next audit compares its
+//! findings to the recorded ones, marking those which are new since the last
+//! run and listing those which were resolved, then updates the file (unless
+//! `--no-update-state` is given). Findings are sorted, so the file diffs well
+//! when it's checked in.
+//!
+//! With `--baseline`, findings are compared to those of a baseline instead,
+//! which isn't updated: either a file written by `--write-baseline` (in the
+//! format of state files), or a JSON report (e.g. from `cargo audit --json`
+//! on the main branch). Only findings which aren't in the baseline fail the
+//! audit, and those in the baseline which were resolved are listed, so they
+//! can be pruned by writing the baseline again.
+
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    fs,
+    package::{self, Package},
+    warning, Report, Version, Vulnerability, Warning,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet as Set,
+    fmt, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Findings recorded by the last audit
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct State {
+    /// Date the state was last updated
+    pub updated: Option<advisory::Date>,
+
+    /// Recorded findings, sorted by advisory, then package
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+/// Finding recorded in the state file
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Finding {
+    /// Advisory the finding is for (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advisory: Option<advisory::Id>,
+
+    /// Kind of warning (`None` for vulnerabilities)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<warning::Kind>,
+
+    /// Name of the affected package
+    pub package: package::Name,
+
+    /// Version of the affected package
+    pub version: Version,
+
+    /// Date the finding was first seen
+    #[serde(rename = "first-seen")]
+    pub first_seen: advisory::Date,
+}
+
+impl Finding {
+    /// Key findings are matched across runs by
+    fn key(&self) -> Key {
+        (
+            self.advisory.clone(),
+            self.warning,
+            self.package.clone(),
+            self.version.clone(),
+        )
+    }
+}
+
+/// Advisory, kind of warning, package name and version of a finding
+type Key = (
+    Option<advisory::Id>,
+    Option<warning::Kind>,
+    package::Name,
+    Version,
+);
+
+/// What the findings of a report are compared to
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Since {
+    /// The run recorded in the state file on the given date
+    Run(advisory::Date),
+
+    /// The baseline report in the given file
+    Baseline(PathBuf),
+}
+
+impl fmt::Display for Since {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Since::Run(date) => f.write_str(date.as_str()),
+            Since::Baseline(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Differences between the findings of a report and the recorded ones
+#[derive(Clone, Debug, Default)]
+pub struct Changes {
+    /// What the findings are compared to (`None` if there's no recorded
+    /// state yet)
+    pub since: Option<Since>,
+
+    /// Keys of the findings in the report which weren't recorded
+    new: Set<Key>,
+
+    /// Recorded findings which aren't in the report any more (compared to a
+    /// baseline, their first-seen date is that of the comparison, as reports
+    /// don't record it)
+    pub resolved: Vec<Finding>,
+}
+
+impl Changes {
+    /// Is the given vulnerability new since the last run?
+    pub fn is_new_vulnerability(&self, vulnerability: &Vulnerability) -> bool {
+        self.new.contains(&vulnerability_key(vulnerability))
+    }
+
+    /// Is the given warning new since the last run?
+    pub fn is_new_warning(&self, warning: &Warning) -> bool {
+        self.new.contains(&warning_key(warning))
+    }
+}
+
+impl State {
+    /// Load the state from the given file, if it exists
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        serde_json::from_str(&json).map(Some).map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("couldn't parse {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Write the state to the given file
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut json =
+            serde_json::to_string_pretty(self).map_err(|e| Error::new(ErrorKind::Parse, &e))?;
+        json.push('\n');
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Compare the findings of the given report to the recorded ones
+    pub fn changes(state: Option<&Self>, report: &Report) -> Changes {
+        let recorded: Set<Key> = state
+            .map(|state| state.findings.iter().map(Finding::key).collect())
+            .unwrap_or_default();
+        let found = keys(report);
+
+        Changes {
+            since: state
+                .and_then(|state| state.updated.clone())
+                .map(Since::Run),
+            new: found.difference(&recorded).cloned().collect(),
+            resolved: state
+                .map(|state| {
+                    state
+                        .findings
+                        .iter()
+                        .filter(|finding| !found.contains(&finding.key()))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Compare the findings of the given report to those of the baseline
+    /// loaded from the given file
+    pub fn baseline_changes(path: &Path, baseline: &Self, report: &Report) -> Changes {
+        Changes {
+            since: Some(Since::Baseline(path.to_owned())),
+            ..Self::changes(Some(baseline), report)
+        }
+    }
+
+    /// Baseline recording the findings of the given report (for
+    /// `--write-baseline`)
+    pub fn baseline(report: &Report) -> Self {
+        Self::update(None, report, &today())
+    }
+
+    /// State recording the findings of the given report on the given date,
+    /// keeping the date findings which were already recorded were first seen
+    pub fn update(state: Option<&Self>, report: &Report, today: &advisory::Date) -> Self {
+        let findings = keys(report)
+            .into_iter()
+            .map(|key| {
+                let first_seen = state
+                    .and_then(|state| state.findings.iter().find(|finding| finding.key() == key))
+                    .map(|finding| finding.first_seen.clone())
+                    .unwrap_or_else(|| today.clone());
+                let (advisory, warning, package, version) = key;
+
+                Finding {
+                    advisory,
+                    warning,
+                    package,
+                    version,
+                    first_seen,
+                }
+            })
+            .collect();
+
+        Self {
+            updated: Some(today.clone()),
+            findings,
+        }
+    }
+}
+
+/// Load a baseline: either one written by `--write-baseline`, or a report
+/// written by `cargo audit --json`
+pub fn load_baseline(path: &Path) -> Result<State, Error> {
+    let json = read_report(path)?;
+
+    // Reports have fields which states don't
+    match serde_json::from_str(&json) {
+        Ok(state) => Ok(state),
+        Err(_) => parse_report(path, &json).map(|report| State::baseline(&report)),
+    }
+}
+
+/// Load a report written by `cargo audit --json`
+pub fn load_report(path: &Path) -> Result<Report, Error> {
+    parse_report(path, &read_report(path)?)
+}
+
+/// Read the JSON of the report (or baseline) in the given file
+fn read_report(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|e| {
+        Error::new(
+            ErrorKind::Io,
+            &format!("couldn't read report {}: {}", path.display(), e),
+        )
+    })
+}
+
+/// Parse a report written by `cargo audit --json`, read from the given file
+fn parse_report(path: &Path, json: &str) -> Result<Report, Error> {
+    serde_json::from_str(json).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't parse report {}: {}", path.display(), e),
+        )
+    })
+}
+
+/// Today's date (in UTC)
+pub fn today() -> advisory::Date {
+    let now = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    now[..10]
+        .parse()
+        .expect("RFC 3339 timestamps start with a date")
+}
+
+/// Keys of the findings in a report, in order
+fn keys(report: &Report) -> Set<Key> {
+    report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(vulnerability_key)
+        .chain(report.warnings.values().flatten().map(warning_key))
+        .collect()
+}
+
+/// Key of a vulnerability
+fn vulnerability_key(vulnerability: &Vulnerability) -> Key {
+    package_key(
+        Some(vulnerability.advisory.id.clone()),
+        None,
+        &vulnerability.package,
+    )
+}
+
+/// Key of a warning
+fn warning_key(warning: &Warning) -> Key {
+    package_key(
+        warning
+            .advisory
+            .as_ref()
+            .map(|advisory| advisory.id.clone()),
+        Some(warning.kind),
+        &warning.package,
+    )
+}
+
+/// Key of a finding about the given package
+fn package_key(
+    advisory: Option<advisory::Id>,
+    warning: Option<warning::Kind>,
+    package: &Package,
+) -> Key {
+    (
+        advisory,
+        warning,
+        package.name.clone(),
+        package.version.clone(),
+    )
+}
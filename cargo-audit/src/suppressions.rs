@@ -0,0 +1,128 @@
+//! Advisories suppressed by ignores
+//!
+//! Advisories in `advisories.ignore` are left out of reports altogether, so
+//! they can't fail the audit. To keep what's being suppressed (and why)
+//! visible, terminal reports end with a summary of the ignored advisories
+//! which match the audited packages, with the reason for each ignore and
+//! when it expires. Expired ignores no longer apply: their advisories are
+//! reported (and fail the audit) as usual again.
+
+use crate::{acknowledgments, config::IgnoredAdvisory, scoped_ignores, vex::Justification};
+use rustsec::{advisory, cargo_lock::dependency::Tree, lockfile::Lockfile, Database};
+
+/// Ignored advisory which matches some of the audited packages
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suppression {
+    /// ID of the advisory
+    pub id: advisory::Id,
+
+    /// Title of the advisory
+    pub title: String,
+
+    /// Other IDs of the advisory (e.g. CVEs)
+    pub aliases: Vec<advisory::Id>,
+
+    /// Packages the advisory matches (`name version`)
+    pub packages: Vec<String>,
+
+    /// Why the advisory is ignored (if recorded)
+    pub reason: Option<String>,
+
+    /// Date the ignore expires on (if it does)
+    pub expires: Option<advisory::Date>,
+
+    /// Days until the ignore expires (if it does)
+    pub days_remaining: Option<u64>,
+
+    /// Why the workspace isn't affected (if recorded), for OpenVEX documents
+    pub justification: Option<Justification>,
+}
+
+/// Find the active ignores whose advisories match packages of the lockfile
+pub fn suppressions(
+    database: &Database,
+    lockfile: &Lockfile,
+    ignore: &[IgnoredAdvisory],
+    today: &advisory::Date,
+) -> Vec<Suppression> {
+    let tree = scoped_tree(lockfile, ignore);
+
+    ignore
+        .iter()
+        .filter(|ignored| ignored.is_active(today))
+        .filter_map(|ignored| {
+            let advisory = database.get(&ignored.id)?;
+            let packages = matching_packages(advisory, ignored, lockfile, tree.as_ref());
+
+            if packages.is_empty() {
+                return None;
+            }
+
+            Some(Suppression {
+                id: ignored.id.clone(),
+                title: advisory.metadata.title.clone(),
+                aliases: advisory.metadata.aliases.clone(),
+                packages,
+                reason: ignored.reason.clone(),
+                expires: ignored.expires.clone(),
+                days_remaining: ignored
+                    .expires
+                    .as_ref()
+                    .map(|expires| acknowledgments::days_between(today, expires)),
+                justification: ignored.justification,
+            })
+        })
+        .collect()
+}
+
+/// Find the expired ignores whose advisories match packages of the lockfile,
+/// which are failing again
+pub fn expired<'a>(
+    database: &Database,
+    lockfile: &Lockfile,
+    ignore: &'a [IgnoredAdvisory],
+    today: &advisory::Date,
+) -> Vec<&'a IgnoredAdvisory> {
+    let tree = scoped_tree(lockfile, ignore);
+
+    ignore
+        .iter()
+        .filter(|ignored| !ignored.is_active(today))
+        .filter(|ignored| match database.get(&ignored.id) {
+            Some(advisory) => {
+                !matching_packages(advisory, ignored, lockfile, tree.as_ref()).is_empty()
+            }
+            None => false,
+        })
+        .collect()
+}
+
+/// Dependency graph of the lockfile, if any ignores are scoped to dependency
+/// paths
+fn scoped_tree(lockfile: &Lockfile, ignore: &[IgnoredAdvisory]) -> Option<Tree> {
+    if ignore.iter().any(|ignored| !ignored.via.is_empty()) {
+        lockfile.dependency_tree().ok()
+    } else {
+        None
+    }
+}
+
+/// Packages of the lockfile with versions the advisory applies to, which are
+/// in the ignore's scope (if it's scoped)
+fn matching_packages(
+    advisory: &rustsec::Advisory,
+    ignored: &IgnoredAdvisory,
+    lockfile: &Lockfile,
+    tree: Option<&Tree>,
+) -> Vec<String> {
+    lockfile
+        .packages
+        .iter()
+        .filter(|package| {
+            package.name == advisory.metadata.package
+                && advisory.versions.is_vulnerable(&package.version)
+                && scoped_ignores::applies(ignored, package, tree)
+        })
+        .map(|package| format!("{} {}", package.name, package.version))
+        .collect()
+}
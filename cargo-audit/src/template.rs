@@ -0,0 +1,308 @@
+//! Templates for the vulnerabilities in terminal reports
+//!
+//! With `output.template` (or `--template`), each vulnerability in terminal
+//! reports is rendered with the template in the given file rather than the
+//! built-in attributes, e.g. to translate their labels or add links to
+//! internal runbooks and ticket trackers. Dependency trees are still shown
+//! after each one.
+//!
+//! Templates use a small subset of [Handlebars] syntax:
+//!
+//! - `{{name}}` is replaced by the value of a variable (see [`VARIABLES`]),
+//!   which is empty if the vulnerability has none (lists of values are
+//!   comma-separated)
+//! - `{{#if name}}...{{else}}...{{/if}}` only keeps its contents if the
+//!   variable isn't empty (otherwise the optional `{{else}}` part)
+//! - `{{! comment }}` is left out
+//!
+//! As in Handlebars, lines which only contain a block tag (`{{#if}}`,
+//! `{{else}}`, `{{/if}}` or a comment) are left out altogether. Values aren't
+//! escaped, since they're written to a terminal rather than HTML.
+//!
+//! [Handlebars]: https://handlebarsjs.com/guide/
+
+use rustsec::{Error, ErrorKind};
+use std::{collections::BTreeMap as Map, fs, path::Path};
+
+/// Variables templates can use, with their descriptions
+pub const VARIABLES: &[(&str, &str)] = &[
+    ("crate", "name of the vulnerable crate"),
+    ("version", "vulnerable versions of the crate"),
+    ("id", "ID of the advisory"),
+    ("title", "title of the advisory"),
+    ("date", "date the advisory was reported"),
+    ("url", "URL of the advisory"),
+    ("aliases", "other IDs of the advisory (e.g. CVEs)"),
+    ("categories", "categories of the advisory"),
+    ("severity", "severity of the vulnerability"),
+    ("score", "CVSS base score of the advisory"),
+    ("cvss", "CVSS vector of the advisory"),
+    ("patched", "patched versions of the crate"),
+    ("solution", "recommended upgrade"),
+    ("dependency", "`direct` or `transitive` (if known)"),
+    ("members", "workspace members which depend on the crate"),
+    ("epss", "EPSS score of the advisory's CVE (with `--epss`)"),
+    ("exploited", "`true` if the CVE is known to be exploited"),
+    ("new", "`true` if it's new since the last audit"),
+    ("description", "description of the advisory (in Markdown)"),
+];
+
+/// Template for the vulnerabilities in terminal reports
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Template {
+    /// Parsed contents of the template
+    nodes: Vec<Node>,
+}
+
+/// Part of a template
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Node {
+    /// Text which is output as-is
+    Text(String),
+
+    /// Variable which is replaced by its value
+    Variable(String),
+
+    /// Conditional block: the first nodes if the variable isn't empty,
+    /// otherwise the second
+    If(String, Vec<Node>, Vec<Node>),
+}
+
+/// Piece of a template's source: text, or a tag (without its braces)
+#[derive(Debug)]
+enum Token {
+    /// Text outside of tags
+    Text(String),
+
+    /// Tag, with the line it's on
+    Tag(String, usize),
+}
+
+impl Template {
+    /// Load a template from the given file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let source = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't read template {}: {}", path.display(), e),
+            )
+        })?;
+
+        source.parse().map_err(|e: Error| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("invalid template {}: {}", path.display(), e.msg()),
+            )
+        })
+    }
+
+    /// Render the template with the given values of its variables (those
+    /// without one are empty)
+    pub fn render(&self, values: &Map<&str, String>) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, values, &mut output);
+        output
+    }
+}
+
+impl std::str::FromStr for Template {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self, Error> {
+        let mut nodes = vec![];
+        let mut blocks: Vec<Block> = vec![];
+
+        for token in strip_standalone_tags(tokenize(source)?) {
+            let (tag, line) = match token {
+                Token::Text(text) if text.is_empty() => continue,
+                Token::Text(text) => {
+                    current(&mut nodes, &mut blocks).push(Node::Text(text));
+                    continue;
+                }
+                Token::Tag(tag, line) => (tag, line),
+            };
+
+            if tag.starts_with('!') {
+                continue;
+            }
+
+            if let Some(variable) = tag.strip_prefix("#if ") {
+                blocks.push(Block {
+                    variable: check_variable(variable.trim(), line)?,
+                    line,
+                    nodes: vec![],
+                    then: None,
+                });
+            } else if tag == "else" {
+                match blocks.last_mut() {
+                    Some(block) if block.then.is_none() => {
+                        block.then = Some(std::mem::take(&mut block.nodes));
+                    }
+                    _ => return Err(syntax_error("unexpected {{else}}", line)),
+                }
+            } else if tag == "/if" {
+                let block = blocks
+                    .pop()
+                    .ok_or_else(|| syntax_error("unexpected {{/if}}", line))?;
+
+                let node = match block.then {
+                    Some(then) => Node::If(block.variable, then, block.nodes),
+                    None => Node::If(block.variable, block.nodes, vec![]),
+                };
+
+                current(&mut nodes, &mut blocks).push(node);
+            } else if tag.starts_with('#') || tag.starts_with('/') {
+                return Err(syntax_error(
+                    &format!("unsupported block tag {:?} (only #if is supported)", tag),
+                    line,
+                ));
+            } else {
+                let variable = check_variable(&tag, line)?;
+                current(&mut nodes, &mut blocks).push(Node::Variable(variable));
+            }
+        }
+
+        match blocks.pop() {
+            None => Ok(Self { nodes }),
+            Some(block) => Err(syntax_error("unclosed {{#if}}", block.line)),
+        }
+    }
+}
+
+/// `{{#if}}` block being parsed
+struct Block {
+    /// Variable the block depends on
+    variable: String,
+
+    /// Line the block starts on
+    line: usize,
+
+    /// Nodes parsed so far (of the `{{else}}` part, once it's reached)
+    nodes: Vec<Node>,
+
+    /// Nodes before the `{{else}}` (once it's reached)
+    then: Option<Vec<Node>>,
+}
+
+/// Nodes of the innermost block being parsed (or of the template itself)
+fn current<'a>(nodes: &'a mut Vec<Node>, blocks: &'a mut [Block]) -> &'a mut Vec<Node> {
+    match blocks.last_mut() {
+        Some(block) => &mut block.nodes,
+        None => nodes,
+    }
+}
+
+/// Split a template's source into text and tags
+fn tokenize(source: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut rest = source;
+    let mut line = 1;
+
+    while let Some(start) = rest.find("{{") {
+        let text = &rest[..start];
+        line += text.matches('\n').count();
+        tokens.push(Token::Text(text.to_owned()));
+
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| syntax_error("unclosed {{", line))?;
+
+        let tag = &after[..end];
+        tokens.push(Token::Tag(tag.trim().to_owned(), line));
+        line += tag.matches('\n').count();
+        rest = &after[end + 2..];
+    }
+
+    tokens.push(Token::Text(rest.to_owned()));
+    Ok(tokens)
+}
+
+/// Leave out the lines which only contain a block tag or a comment, so they
+/// don't leave blank lines behind
+fn strip_standalone_tags(mut tokens: Vec<Token>) -> Vec<Token> {
+    // Tokens alternate between text and tags, starting and ending with text
+    let mut starts_line = true;
+
+    for i in (1..tokens.len()).step_by(2) {
+        let is_block = match &tokens[i] {
+            Token::Tag(tag, _) => {
+                tag.starts_with(|c: char| c == '#' || c == '/' || c == '!') || tag == "else"
+            }
+            Token::Text(_) => false,
+        };
+
+        // Whether the text before this tag starts a line (at the start of the
+        // template, or after a stripped line)
+        let before_starts_line = std::mem::replace(&mut starts_line, false);
+
+        if !is_block {
+            continue;
+        }
+
+        let (before, after) = match (&tokens[i - 1], &tokens[i + 1]) {
+            (Token::Text(before), Token::Text(after)) => (before, after),
+            _ => continue,
+        };
+
+        // The text on the tag's line before it, and after it (including the
+        // line feed), if it's only whitespace
+        let line_start = match before.rfind('\n') {
+            Some(index) => Some(index + 1),
+            None if before_starts_line => Some(0),
+            None => None,
+        };
+        let line_end = match after.find('\n') {
+            Some(index) => Some(index + 1),
+            None if i + 2 == tokens.len() => Some(after.len()),
+            None => None,
+        };
+
+        if let (Some(start), Some(end)) = (line_start, line_end) {
+            if before[start..].trim().is_empty() && after[..end].trim().is_empty() {
+                let before = before[..start].to_owned();
+                let after = after[end..].to_owned();
+                tokens[i - 1] = Token::Text(before);
+                tokens[i + 1] = Token::Text(after);
+                starts_line = true;
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Check the given variable is one templates can use
+fn check_variable(name: &str, line: usize) -> Result<String, Error> {
+    if VARIABLES.iter().any(|(variable, _)| *variable == name) {
+        Ok(name.to_owned())
+    } else {
+        Err(syntax_error(&format!("unknown variable {:?}", name), line))
+    }
+}
+
+/// Error for a template which can't be parsed
+fn syntax_error(message: &str, line: usize) -> Error {
+    Error::new(ErrorKind::Parse, &format!("line {}: {}", line, message))
+}
+
+/// Render the given nodes with the given values
+fn render_nodes(nodes: &[Node], values: &Map<&str, String>, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable(variable) => {
+                if let Some(value) = values.get(variable.as_str()) {
+                    output.push_str(value);
+                }
+            }
+            Node::If(variable, then, otherwise) => {
+                let is_set = values
+                    .get(variable.as_str())
+                    .map_or(false, |value| !value.is_empty());
+
+                render_nodes(if is_set { then } else { otherwise }, values, output);
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! Plain-text formatting for terminal reports: word wrapping and
+//! truncation, rendering advisory descriptions (which are Markdown) without
+//! any markup, and colors and hyperlinks for terminals which support them.
+
+use std::env;
+#[cfg(unix)]
+use std::{
+    fs,
+    process::{Command, Stdio},
+};
+
+/// Width to wrap reports to when STDOUT isn't a terminal
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Width of the terminal STDOUT is connected to (if any), from `$COLUMNS`
+/// or by asking `stty` (this crate forbids the `unsafe` code an `ioctl` needs)
+pub fn terminal_width() -> Option<usize> {
+    if !atty::is(atty::Stream::Stdout) {
+        return None;
+    }
+
+    let columns = env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok());
+    columns.or_else(stty_width).filter(|&width| width > 0)
+}
+
+/// Width of the controlling terminal according to `stty size`
+#[cfg(unix)]
+fn stty_width() -> Option<usize> {
+    let tty = fs::File::open("/dev/tty").ok()?;
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    // Output is `<rows> <columns>`
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// Width of the controlling terminal (unknown on this platform)
+#[cfg(not(unix))]
+fn stty_width() -> Option<usize> {
+    None
+}
+
+/// Should terminal output be colored automatically? Only if STDOUT is a
+/// terminal (rather than a pipe, a file or a CI log), `TERM` isn't `dumb`,
+/// and `NO_COLOR` isn't set to a non-empty value (see <https://no-color.org>)
+pub fn supports_color() -> bool {
+    if env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty()) {
+        return false;
+    }
+
+    atty::is(atty::Stream::Stdout) && env::var("TERM").map_or(true, |term| term != "dumb")
+}
+
+/// Does the terminal STDOUT is connected to (if any) support OSC 8
+/// hyperlinks?
+///
+/// There's no way to ask a terminal, so this goes by the environment
+/// variables set by terminals known to support them. `FORCE_HYPERLINK=1` (or
+/// `0`) overrides the detection, as it does for other tools.
+pub fn supports_hyperlinks() -> bool {
+    if let Ok(force) = env::var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+
+    if !atty::is(atty::Stream::Stdout) {
+        return false;
+    }
+
+    let var = |name: &str| env::var(name).unwrap_or_default();
+
+    if var("TERM") == "dumb" {
+        return false;
+    }
+
+    // VTE-based terminals (e.g. GNOME Terminal) since VTE 0.50
+    let vte_version: u32 = var("VTE_VERSION").parse().unwrap_or(0);
+
+    ["iTerm.app", "WezTerm", "vscode", "Hyper", "ghostty"].contains(&var("TERM_PROGRAM").as_str())
+        || vte_version >= 5000
+        || ["WT_SESSION", "KONSOLE_VERSION", "KITTY_WINDOW_ID"]
+            .iter()
+            .any(|name| env::var_os(name).is_some())
+        || var("TERM").contains("kitty")
+        || var("TERM").contains("alacritty")
+}
+
+/// Render text as an OSC 8 hyperlink to the given URL, which terminals that
+/// don't support them display as just the text
+pub fn hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Wrap the given text to lines of at most `width` characters, breaking
+/// only between words (so words longer than `width`, e.g. URLs, overflow)
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+
+    for input_line in text.lines() {
+        let mut line = String::new();
+
+        for word in input_line.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(line);
+                line = String::new();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+
+            line.push_str(word);
+        }
+
+        lines.push(line);
+    }
+
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+
+    lines
+}
+
+/// Truncate the given line to at most `width` characters, ending it with an
+/// ellipsis if anything was cut off
+pub fn truncate(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        return line.to_owned();
+    }
+
+    let mut truncated: String = line.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Render Markdown as plain text wrapped to `width` characters: headings and
+/// paragraphs are wrapped, list items are wrapped with a hanging indent, and
+/// code blocks are indented but otherwise kept verbatim
+pub fn render_markdown(markdown: &str, width: usize) -> Vec<String> {
+    let mut renderer = Renderer {
+        width,
+        lines: vec![],
+        block: None,
+    };
+
+    let mut fence: Option<&str> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            } else {
+                renderer.lines.push(format!("    {}", line));
+            }
+
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            renderer.end_block();
+            fence = Some(&trimmed[..3]);
+        } else if trimmed.is_empty() {
+            renderer.end_block();
+            renderer.blank_line();
+        } else if let Some(heading) = heading(trimmed) {
+            renderer.end_block();
+            renderer.start_block("", heading);
+            renderer.end_block();
+        } else if let Some((marker, item)) = list_item(trimmed) {
+            renderer.end_block();
+            let indent = " ".repeat((line.len() - trimmed.len()) / 2 * 2);
+            renderer.start_block(&format!("{}{} ", indent, marker), item);
+        } else if line.starts_with("    ") && renderer.block.is_none() {
+            // Indented code block
+            renderer.lines.push(line.to_owned());
+        } else {
+            let text = trimmed.trim_start_matches('>').trim_start();
+
+            match &mut renderer.block {
+                Some(block) => {
+                    block.text.push(' ');
+                    block.text.push_str(text);
+                }
+                None => renderer.start_block("", text),
+            }
+        }
+    }
+
+    renderer.end_block();
+
+    while renderer.lines.last().map_or(false, String::is_empty) {
+        renderer.lines.pop();
+    }
+
+    renderer.lines
+}
+
+/// State of rendering Markdown
+struct Renderer {
+    /// Width to wrap lines to
+    width: usize,
+
+    /// Rendered lines
+    lines: Vec<String>,
+
+    /// Paragraph, heading or list item being rendered
+    block: Option<Block>,
+}
+
+/// Text which is wrapped together
+struct Block {
+    /// Prefix of the first line, e.g. a list item's bullet (subsequent lines
+    /// are indented by its width)
+    prefix: String,
+
+    /// Text of the block
+    text: String,
+}
+
+impl Renderer {
+    /// Start a new block of wrapped text
+    fn start_block(&mut self, prefix: &str, text: &str) {
+        self.block = Some(Block {
+            prefix: prefix.to_owned(),
+            text: text.to_owned(),
+        });
+    }
+
+    /// Wrap and render the current block (if any)
+    fn end_block(&mut self) {
+        let block = match self.block.take() {
+            Some(block) => block,
+            None => return,
+        };
+
+        let indent = block.prefix.chars().count();
+        let width = self.width.saturating_sub(indent).max(20);
+
+        for (i, line) in wrap(&inline(&block.text), width).into_iter().enumerate() {
+            if i == 0 {
+                self.lines.push(format!("{}{}", block.prefix, line));
+            } else {
+                self.lines.push(format!("{}{}", " ".repeat(indent), line));
+            }
+        }
+    }
+
+    /// Render a blank line (but never more than one in a row)
+    fn blank_line(&mut self) {
+        if self.lines.last().map_or(false, |line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+    }
+}
+
+/// Text of a heading line (if it is one)
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+
+    if text.len() < line.len() && (text.is_empty() || text.starts_with(' ')) {
+        Some(text.trim().trim_end_matches('#').trim_end())
+    } else {
+        None
+    }
+}
+
+/// Marker and text of a list item line (if it is one)
+fn list_item(line: &str) -> Option<(String, &str)> {
+    for bullet in &["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some(("-".to_owned(), item.trim_start()));
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+
+    if digits > 0 {
+        let rest = &line[digits..];
+
+        for delimiter in &[". ", ") "] {
+            if let Some(item) = rest.strip_prefix(delimiter) {
+                return Some((format!("{}.", &line[..digits]), item.trim_start()));
+            }
+        }
+    }
+
+    None
+}
+
+/// Remove inline markup: code spans and strong emphasis are shown as their
+/// text, and links as their text followed by the URL
+fn inline(text: &str) -> String {
+    let text = text.replace('`', "").replace("**", "");
+    let mut rendered = String::new();
+    let mut rest = text.as_str();
+
+    while let Some(start) = rest.find('[') {
+        let link = rest[start..].find("](").and_then(|middle| {
+            let end = rest[start + middle..].find(')')?;
+            Some((start + middle, start + middle + end))
+        });
+
+        let (middle, end) = match link {
+            Some(link) => link,
+            None => break,
+        };
+
+        let label = &rest[start + 1..middle];
+        let url = &rest[middle + 2..end];
+
+        // Not a link, e.g. `[1] see [this](url)`
+        if label.contains(']') {
+            rendered.push_str(&rest[..=start]);
+            rest = &rest[start + 1..];
+            continue;
+        }
+
+        // Images are rendered the same way as links
+        let prefix = rest[..start].strip_suffix('!').unwrap_or(&rest[..start]);
+        rendered.push_str(prefix);
+
+        if label.is_empty() || label == url {
+            rendered.push_str(url);
+        } else {
+            rendered.push_str(&format!("{} ({})", label, url));
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
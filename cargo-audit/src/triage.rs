@@ -0,0 +1,355 @@
+//! Interactive triage (`cargo audit --interactive`)
+//!
+//! After the report, each finding with an advisory is shown in turn, with the
+//! advisory's details and the inverse dependency tree of the affected
+//! package, and the user decides what to do about it: ignore it (with a
+//! reason, and optionally until a date), fix it later (acknowledging the
+//! vulnerability with a ticket until a deadline), or let it fail the audit.
+//! The ignores and acknowledgments are written to `audit.toml`, each with a
+//! comment recording the finding it was triaged from.
+
+use crate::{
+    acknowledgments,
+    config::{Acknowledgment, ConfigEditor, IgnoredAdvisory},
+};
+use rustsec::{
+    advisory,
+    cargo_lock::dependency::{graph::EdgeDirection, Dependency, Tree},
+    package::Package,
+    Error, Report, VersionReq,
+};
+use std::{
+    collections::BTreeSet as Set,
+    io::{self, BufRead, Write},
+};
+
+/// Number of days vulnerabilities are acknowledged for when they're to be
+/// fixed later, unless another number is given
+pub const DEFAULT_FIX_LATER_DAYS: u64 = 30;
+
+/// Finding to triage: an advisory which matched a package
+#[derive(Clone, Debug)]
+pub struct Finding<'a> {
+    /// Advisory which matched the package
+    pub advisory: &'a advisory::Metadata,
+
+    /// Affected package
+    pub package: &'a Package,
+
+    /// Patched versions of the package (if known)
+    pub patched: &'a [VersionReq],
+
+    /// Is this a vulnerability (rather than a warning)?
+    pub vulnerability: bool,
+}
+
+/// What to do about a finding
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Decision {
+    /// Ignore the advisory
+    Ignore(IgnoredAdvisory),
+
+    /// Acknowledge the vulnerability until it's fixed
+    FixLater(Acknowledgment),
+
+    /// Let the finding fail the audit
+    Fail,
+}
+
+/// Findings of the report to triage: its vulnerabilities, then its warnings
+/// with advisories (each advisory only once)
+pub fn findings(report: &Report) -> Vec<Finding<'_>> {
+    let vulnerabilities = report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vulnerability| Finding {
+            advisory: &vulnerability.advisory,
+            package: &vulnerability.package,
+            patched: vulnerability.versions.patched(),
+            vulnerability: true,
+        });
+
+    let warnings = report.warnings.values().flatten().filter_map(|warning| {
+        Some(Finding {
+            advisory: warning.advisory.as_ref()?,
+            package: &warning.package,
+            patched: warning
+                .versions
+                .as_ref()
+                .map(|versions| versions.patched())
+                .unwrap_or_default(),
+            vulnerability: false,
+        })
+    });
+
+    let mut seen = Set::new();
+
+    vulnerabilities
+        .chain(warnings)
+        .filter(|finding| seen.insert(&finding.advisory.id))
+        .collect()
+}
+
+/// Interactive triage of findings, prompting on the given output and reading
+/// the answers from the given input
+pub struct Triage<R, W> {
+    /// Where answers are read from
+    input: R,
+
+    /// Where findings and prompts are written to
+    output: W,
+
+    /// Today's date, which deadlines are counted from
+    today: advisory::Date,
+}
+
+impl<R: BufRead, W: Write> Triage<R, W> {
+    /// Triage findings with the given input and output, as of the given date
+    pub fn new(input: R, output: W, today: advisory::Date) -> Self {
+        Self {
+            input,
+            output,
+            today,
+        }
+    }
+
+    /// Show each finding (with its inverse dependency tree, if the tree is
+    /// known) and ask what to do about it. Findings which are left when the
+    /// user quits (or the input ends) fail the audit.
+    pub fn run(
+        &mut self,
+        findings: &[Finding<'_>],
+        tree: Option<&Tree>,
+    ) -> io::Result<Vec<Decision>> {
+        let mut decisions = vec![];
+
+        for (i, finding) in findings.iter().enumerate() {
+            writeln!(self.output)?;
+            write!(self.output, "({}/{}) ", i + 1, findings.len())?;
+            self.show(finding, tree)?;
+
+            match self.decide(finding)? {
+                Some(decision) => decisions.push(decision),
+                None => break,
+            }
+        }
+
+        decisions.resize(findings.len(), Decision::Fail);
+        Ok(decisions)
+    }
+
+    /// Show the advisory of a finding, and the inverse dependency tree of
+    /// its package
+    fn show(&mut self, finding: &Finding<'_>, tree: Option<&Tree>) -> io::Result<()> {
+        let advisory = finding.advisory;
+
+        writeln!(self.output, "{}: {}", advisory.id, advisory.title)?;
+        writeln!(
+            self.output,
+            "Crate:    {} {}",
+            finding.package.name, finding.package.version
+        )?;
+        writeln!(self.output, "Date:     {}", advisory.date.as_str())?;
+
+        if let Some(url) = advisory.id.url() {
+            writeln!(self.output, "URL:      {}", url)?;
+        }
+
+        if finding.patched.is_empty() {
+            writeln!(self.output, "Patched:  no patched versions")?;
+        } else {
+            let patched: Vec<_> = finding.patched.iter().map(ToString::to_string).collect();
+            writeln!(self.output, "Patched:  {}", patched.join(" or "))?;
+        }
+
+        if !advisory.description.trim().is_empty() {
+            writeln!(self.output)?;
+            writeln!(self.output, "{}", advisory.description.trim())?;
+        }
+
+        let node = tree.and_then(|tree| {
+            tree.nodes()
+                .get(&Dependency::from(finding.package))
+                .map(|&node| (tree, node))
+        });
+
+        if let Some((tree, node)) = node {
+            writeln!(self.output)?;
+            writeln!(self.output, "Dependency tree:")?;
+            tree.render(&mut self.output, node, EdgeDirection::Incoming)?;
+        }
+
+        Ok(())
+    }
+
+    /// Ask what to do about a finding, returning `None` if the user quits
+    fn decide(&mut self, finding: &Finding<'_>) -> io::Result<Option<Decision>> {
+        let question = if finding.vulnerability {
+            "Ignore it, fix it later or fail [i,l,f,q]? "
+        } else {
+            "Ignore it or fail [i,f,q]? "
+        };
+
+        loop {
+            let answer = match self.ask(question)? {
+                Some(answer) => answer,
+                None => return Ok(None),
+            };
+
+            match answer.as_str() {
+                "i" | "I" => return self.ignore(finding),
+                "l" | "L" if finding.vulnerability => return self.fix_later(finding),
+                "f" | "F" => return Ok(Some(Decision::Fail)),
+                "q" | "Q" => return Ok(None),
+                _ => {
+                    writeln!(self.output, "i - ignore the advisory in audit.toml")?;
+
+                    if finding.vulnerability {
+                        writeln!(
+                            self.output,
+                            "l - fix it later: acknowledge it in audit.toml until a deadline"
+                        )?;
+                    }
+
+                    writeln!(self.output, "f - let it fail the audit")?;
+                    writeln!(self.output, "q - let this and all remaining findings fail")?;
+                }
+            }
+        }
+    }
+
+    /// Ask why (and until when) the advisory of a finding is to be ignored
+    fn ignore(&mut self, finding: &Finding<'_>) -> io::Result<Option<Decision>> {
+        let reason = match self.ask_required("Why is it being ignored? ")? {
+            Some(reason) => reason,
+            None => return Ok(None),
+        };
+
+        let expires = loop {
+            let answer = match self.ask("Ignore it until (YYYY-MM-DD, empty for good)? ")? {
+                Some(answer) => answer,
+                None => return Ok(None),
+            };
+
+            if answer.is_empty() {
+                break None;
+            }
+
+            match answer.parse::<advisory::Date>() {
+                Ok(date) => break Some(date),
+                Err(e) => writeln!(self.output, "invalid date {:?}: {}", answer, e)?,
+            }
+        };
+
+        Ok(Some(Decision::Ignore(IgnoredAdvisory {
+            id: finding.advisory.id.clone(),
+            reason: Some(reason),
+            expires,
+            justification: None,
+            package: None,
+            version: None,
+            via: vec![],
+        })))
+    }
+
+    /// Ask which ticket tracks fixing a vulnerability, and by when
+    fn fix_later(&mut self, finding: &Finding<'_>) -> io::Result<Option<Decision>> {
+        let ticket = match self.ask_required("Which ticket tracks the fix? ")? {
+            Some(ticket) => ticket,
+            None => return Ok(None),
+        };
+
+        let question = format!("Days until it's fixed [{}]? ", DEFAULT_FIX_LATER_DAYS);
+
+        let days = loop {
+            let answer = match self.ask(&question)? {
+                Some(answer) => answer,
+                None => return Ok(None),
+            };
+
+            if answer.is_empty() {
+                break DEFAULT_FIX_LATER_DAYS;
+            }
+
+            match answer.parse::<u64>() {
+                Ok(days) if days > 0 => break days,
+                _ => writeln!(self.output, "expected a number of days, e.g. 30")?,
+            }
+        };
+
+        Ok(Some(Decision::FixLater(Acknowledgment {
+            id: finding.advisory.id.clone(),
+            ticket,
+            expires: acknowledgments::days_after(&self.today, days),
+            date: Some(self.today.clone()),
+        })))
+    }
+
+    /// Ask a question until it's answered, returning `None` at the end of the
+    /// input
+    fn ask_required(&mut self, question: &str) -> io::Result<Option<String>> {
+        loop {
+            match self.ask(question)? {
+                Some(answer) if answer.is_empty() => {
+                    writeln!(self.output, "an answer is required")?
+                }
+                answer => return Ok(answer),
+            }
+        }
+    }
+
+    /// Ask a question, returning the trimmed answer, or `None` at the end of
+    /// the input
+    fn ask(&mut self, question: &str) -> io::Result<Option<String>> {
+        write!(self.output, "{}", question)?;
+        self.output.flush()?;
+
+        let mut answer = String::new();
+
+        if self.input.read_line(&mut answer)? == 0 {
+            writeln!(self.output)?;
+            return Ok(None);
+        }
+
+        Ok(Some(answer.trim().to_owned()))
+    }
+}
+
+/// Write the ignores and acknowledgments among the decisions about the given
+/// findings to the config file, each with a comment recording the finding,
+/// returning how many were written
+pub fn save(
+    editor: &mut ConfigEditor,
+    findings: &[Finding<'_>],
+    decisions: &[Decision],
+    today: &advisory::Date,
+) -> Result<usize, Error> {
+    let mut saved = 0;
+
+    for (finding, decision) in findings.iter().zip(decisions) {
+        let comment = format!(
+            "{} {}: {} (triaged {})",
+            finding.package.name,
+            finding.package.version,
+            finding.advisory.title,
+            today.as_str()
+        );
+
+        match decision {
+            Decision::Ignore(ignored) => {
+                editor.ignore(ignored)?;
+                editor.comment("ignore", ignored.id.as_str(), &comment)?;
+            }
+            Decision::FixLater(acknowledgment) => {
+                editor.acknowledge(acknowledgment)?;
+                editor.comment("acknowledged", acknowledgment.id.as_str(), &comment)?;
+            }
+            Decision::Fail => continue,
+        }
+
+        saved += 1;
+    }
+
+    Ok(saved)
+}
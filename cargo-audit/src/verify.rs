@@ -0,0 +1,680 @@
+//! Integrity checks of a lockfile
+//!
+//! Rather than looking for advisories, `cargo audit verify` checks that a
+//! lockfile can be trusted as it is (e.g. as a release gate):
+//!
+//! - `checksums`: the checksum of each crates.io package matches the index's
+//! - `yanked`: none of the locked crates.io releases have been yanked
+//! - `git-revs`: the locked revision of each git dependency still exists
+//!   upstream, i.e. is reachable from one of its branches or tags
+//! - `resolution`: the lockfile is consistent with the workspace's manifests,
+//!   i.e. `cargo metadata --locked` succeeds. If it doesn't, the workspace is
+//!   resolved afresh (restoring the lockfile afterwards) to find the packages
+//!   missing from the lockfile and the extra ones in it.
+//!
+//! Each check can be skipped. In offline mode, git revisions aren't checked,
+//! and crates.io packages are only checked against cached releases.
+
+use crate::{
+    index,
+    lockfile::{self, CARGO_TOML_FILE},
+    prelude::*,
+    retry::{self, Retry},
+};
+use rustsec::{
+    cargo_lock::{Lockfile, Package},
+    error::{Error, ErrorKind},
+    fs, package, Version,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap as Map, BTreeSet as Set},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Name of the directory bare clones of git dependencies are kept in
+/// (located in the report cache directory)
+pub const GIT_DIR: &str = "git";
+
+/// Integrity check
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    /// Checksums of crates.io packages match the index's
+    Checksums,
+
+    /// None of the locked crates.io releases have been yanked
+    Yanked,
+
+    /// Locked revisions of git dependencies still exist upstream
+    GitRevs,
+
+    /// The lockfile is consistent with the workspace's manifests
+    Resolution,
+}
+
+impl Check {
+    /// All checks, in the order they're run
+    pub fn all() -> Vec<Self> {
+        vec![
+            Check::Checksums,
+            Check::Yanked,
+            Check::GitRevs,
+            Check::Resolution,
+        ]
+    }
+
+    /// Get the name of this check
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Check::Checksums => "checksums",
+            Check::Yanked => "yanked",
+            Check::GitRevs => "git-revs",
+            Check::Resolution => "resolution",
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Status of a check
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    /// Nothing failed the check
+    Passed,
+
+    /// Something failed the check
+    Failed,
+
+    /// The check wasn't run
+    Skipped,
+}
+
+impl Status {
+    /// Get the name of this status
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::Failed => "failed",
+            Status::Skipped => "skipped",
+        }
+    }
+}
+
+/// Result of a check
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckResult {
+    /// Check which was run
+    pub check: Check,
+
+    /// Did the check pass?
+    pub status: Status,
+
+    /// Number of packages (or git revisions) checked
+    pub checked: usize,
+
+    /// Why the check was skipped (if it was)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// Failures found by the check
+    pub failures: Vec<Failure>,
+}
+
+impl CheckResult {
+    /// Result of a check which checked the given number of packages, finding
+    /// the given failures
+    fn new(check: Check, checked: usize, failures: Vec<Failure>) -> Self {
+        Self {
+            check,
+            status: if failures.is_empty() {
+                Status::Passed
+            } else {
+                Status::Failed
+            },
+            checked,
+            reason: None,
+            failures,
+        }
+    }
+
+    /// Result of a check which was skipped for the given reason
+    fn skipped(check: Check, reason: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Skipped,
+            checked: 0,
+            reason: Some(reason.into()),
+            failures: vec![],
+        }
+    }
+}
+
+/// Integrity failure
+#[derive(Clone, Debug, Serialize)]
+pub struct Failure {
+    /// Kind of failure
+    pub kind: FailureKind,
+
+    /// Name of the package which failed (if the failure is about one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<package::Name>,
+
+    /// Version of the package which failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+
+    /// Description of the failure
+    pub message: String,
+}
+
+impl Failure {
+    /// Failure of the given package
+    fn package(kind: FailureKind, package: &Package, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            package: Some(package.name.clone()),
+            version: Some(package.version.clone()),
+            message: message.into(),
+        }
+    }
+}
+
+/// Kind of integrity failure
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureKind {
+    /// The lockfile's checksum of a package differs from the index's
+    ChecksumMismatch,
+
+    /// The lockfile has no checksum for a crates.io package
+    MissingChecksum,
+
+    /// The locked version of a crates.io package isn't in the index
+    NotPublished,
+
+    /// The index (or a git dependency's repository) couldn't be checked
+    LookupFailed,
+
+    /// The locked release has been yanked
+    Yanked,
+
+    /// The locked revision of a git dependency no longer exists upstream
+    MissingRevision,
+
+    /// The workspace resolves to a package which isn't in the lockfile
+    MissingPackage,
+
+    /// The lockfile has a package which the workspace doesn't resolve to
+    ExtraPackage,
+
+    /// The workspace couldn't be resolved with the lockfile
+    ResolutionFailed,
+}
+
+/// Settings for verifying a lockfile
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    /// Checks to skip
+    pub skip: Set<Check>,
+
+    /// Don't access the network: skip checking git revisions
+    pub offline: bool,
+
+    /// Directory bare clones of git dependencies are kept in, so they only
+    /// need to be fetched incrementally
+    pub git_dir: PathBuf,
+
+    /// Policy for retrying failed fetches of git dependencies
+    pub retry: Retry,
+}
+
+/// Report of the integrity checks of a lockfile
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `verify`, to tell these reports apart from lockfile audits
+    pub mode: &'static str,
+
+    /// Path to the lockfile
+    pub lockfile: PathBuf,
+
+    /// Results of each check, in the order they were run
+    pub checks: Vec<CheckResult>,
+}
+
+impl Report {
+    /// Number of failures found by all of the checks
+    pub fn failure_count(&self) -> usize {
+        self.checks.iter().map(|check| check.failures.len()).sum()
+    }
+
+    /// Did any check fail?
+    pub fn is_failure(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.status == Status::Failed)
+    }
+}
+
+/// Run the integrity checks of the lockfile at the given path, looking up
+/// crates.io packages with the given client (`None` if crates.io is replaced
+/// with a source which has no sparse index)
+pub fn verify(
+    lockfile_path: &Path,
+    client: Option<&index::Client>,
+    settings: &Settings,
+) -> Result<Report, Error> {
+    let lockfile = Lockfile::load(lockfile_path).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!("couldn't load {}: {}", lockfile_path.display(), e),
+        )
+    })?;
+
+    let mut checks = check_registry(&lockfile, client, &settings.skip);
+
+    checks.push(if settings.skip.contains(&Check::GitRevs) {
+        CheckResult::skipped(Check::GitRevs, "skipped with --skip-git-revs")
+    } else if settings.offline {
+        CheckResult::skipped(Check::GitRevs, "offline")
+    } else {
+        check_git_revs(&lockfile, settings)
+    });
+
+    checks.push(if settings.skip.contains(&Check::Resolution) {
+        CheckResult::skipped(Check::Resolution, "skipped with --skip-resolution")
+    } else {
+        check_resolution(lockfile_path, &lockfile, settings.offline)
+    });
+
+    Ok(Report {
+        mode: "verify",
+        lockfile: lockfile_path.to_owned(),
+        checks,
+    })
+}
+
+/// Check the checksums and yanked status of the crates.io packages (unless
+/// skipped), with a single lookup of their releases
+fn check_registry(
+    lockfile: &Lockfile,
+    client: Option<&index::Client>,
+    skip: &Set<Check>,
+) -> Vec<CheckResult> {
+    let checks = [Check::Checksums, Check::Yanked];
+    let skipped = |check: Check, reason: &str| CheckResult::skipped(check, reason);
+
+    let client = match client {
+        Some(client) if !checks.iter().all(|check| skip.contains(check)) => client,
+        _ => {
+            return checks
+                .iter()
+                .map(|&check| {
+                    if skip.contains(&check) {
+                        skipped(check, &format!("skipped with --skip-{}", check))
+                    } else {
+                        skipped(
+                            check,
+                            "crates.io is replaced with a source which has no sparse index",
+                        )
+                    }
+                })
+                .collect();
+        }
+    };
+
+    let packages: Vec<&Package> = lockfile
+        .packages
+        .iter()
+        .filter(|package| {
+            package
+                .source
+                .iter()
+                .any(|source| source.is_default_registry())
+        })
+        .collect();
+
+    let mut names: Vec<_> = packages.iter().map(|package| &package.name).collect();
+    names.sort();
+    names.dedup();
+
+    let (releases, error) = client.releases(&names);
+    let mut checksum_failures = vec![];
+    let mut yanked_failures = vec![];
+
+    for package in &packages {
+        let release = match releases.get(&package.name) {
+            Some(releases) => releases
+                .iter()
+                .find(|release| release.version == package.version),
+            None => {
+                let message = match &error {
+                    Some(e) => format!("couldn't look up releases: {}", e),
+                    None => "releases aren't cached, and can't be looked up offline".to_owned(),
+                };
+
+                let failure = Failure::package(FailureKind::LookupFailed, package, message);
+                checksum_failures.push(failure.clone());
+                yanked_failures.push(failure);
+                continue;
+            }
+        };
+
+        let release = match release {
+            Some(release) => release,
+            None => {
+                let failure = Failure::package(
+                    FailureKind::NotPublished,
+                    package,
+                    format!("{} isn't published in {}", package.version, client.url()),
+                );
+                checksum_failures.push(failure.clone());
+                yanked_failures.push(failure);
+                continue;
+            }
+        };
+
+        match (&package.checksum, &release.checksum) {
+            (None, _) => checksum_failures.push(Failure::package(
+                FailureKind::MissingChecksum,
+                package,
+                "the lockfile has no checksum",
+            )),
+            (Some(_), None) => checksum_failures.push(Failure::package(
+                FailureKind::LookupFailed,
+                package,
+                "the index has no checksum",
+            )),
+            (Some(locked), Some(published)) => {
+                if &locked.to_string() != published {
+                    checksum_failures.push(Failure::package(
+                        FailureKind::ChecksumMismatch,
+                        package,
+                        format!(
+                            "locked checksum {} doesn't match the index's {}",
+                            locked, published
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if release.yanked {
+            yanked_failures.push(Failure::package(
+                FailureKind::Yanked,
+                package,
+                "yanked from the index",
+            ));
+        }
+    }
+
+    vec![
+        if skip.contains(&Check::Checksums) {
+            skipped(Check::Checksums, "skipped with --skip-checksums")
+        } else {
+            CheckResult::new(Check::Checksums, packages.len(), checksum_failures)
+        },
+        if skip.contains(&Check::Yanked) {
+            skipped(Check::Yanked, "skipped with --skip-yanked")
+        } else {
+            CheckResult::new(Check::Yanked, packages.len(), yanked_failures)
+        },
+    ]
+}
+
+/// Check that the locked revision of each git dependency is reachable from
+/// one of the branches or tags of its repository
+fn check_git_revs(lockfile: &Lockfile, settings: &Settings) -> CheckResult {
+    let mut revs: Map<(String, String), Vec<&Package>> = Map::new();
+
+    for package in &lockfile.packages {
+        let source = match &package.source {
+            Some(source) if source.is_git() => source,
+            _ => continue,
+        };
+
+        if let Some(rev) = source.precise() {
+            revs.entry((source.url().to_string(), rev.to_owned()))
+                .or_default()
+                .push(package);
+        }
+    }
+
+    let mut failures = vec![];
+
+    for ((url, rev), packages) in &revs {
+        let exists = settings
+            .retry
+            .run(&format!("checking revision {} of {}", rev, url), || {
+                rev_exists(&settings.git_dir, url, rev).map_err(retry::Failure::git)
+            });
+
+        let (kind, message) = match exists {
+            Ok(true) => continue,
+            Ok(false) => (
+                FailureKind::MissingRevision,
+                format!("revision {} isn't on any branch or tag of {}", rev, url),
+            ),
+            Err(e) => (FailureKind::LookupFailed, e.to_string()),
+        };
+
+        for package in packages {
+            failures.push(Failure::package(kind, package, message.clone()));
+        }
+    }
+
+    CheckResult::new(Check::GitRevs, revs.len(), failures)
+}
+
+/// Is the given revision reachable from one of the branches or tags of the
+/// git repository at the given URL?
+///
+/// Revisions which are the tip of a branch or tag are found without fetching
+/// anything. Otherwise, the branches and tags are fetched into a bare clone
+/// (in `git_dir`, which is reused by later checks).
+fn rev_exists(git_dir: &Path, url: &str, rev: &str) -> Result<bool, Error> {
+    let oid = git2::Oid::from_str(rev).map_err(git_error)?;
+    let mut remote = git2::Remote::create_detached(url).map_err(git_error)?;
+    remote.connect(git2::Direction::Fetch).map_err(git_error)?;
+
+    if remote
+        .list()
+        .map_err(git_error)?
+        .iter()
+        .any(|head| head.oid() == oid)
+    {
+        return Ok(true);
+    }
+
+    let repo = fetch_clone(git_dir, url)?;
+
+    for reference in repo.references().map_err(git_error)? {
+        let tip = match reference.map_err(git_error)?.peel_to_commit() {
+            Ok(commit) => commit.id(),
+            Err(_) => continue,
+        };
+
+        if tip == oid || repo.graph_descendant_of(tip, oid).map_err(git_error)? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Fetch the branches and tags of the git repository at the given URL into
+/// its bare clone in `git_dir` (creating it if need be)
+pub fn fetch_clone(git_dir: &Path, url: &str) -> Result<git2::Repository, Error> {
+    let path = git_dir.join(clone_name(url));
+    let repo = match git2::Repository::open_bare(&path) {
+        Ok(repo) => repo,
+        Err(_) => {
+            fs::create_dir_all(&path)?;
+            git2::Repository::init_bare(&path).map_err(git_error)?
+        }
+    };
+
+    // Pruning removes deleted branches and tags, so commits which are only
+    // reachable from them aren't found
+    let mut options = git2::FetchOptions::new();
+    options.prune(git2::FetchPrune::On);
+    repo.remote_anonymous(url)
+        .and_then(|mut remote| {
+            remote.fetch(
+                &[
+                    "+refs/heads/*:refs/remotes/origin/*",
+                    "+refs/tags/*:refs/tags/*",
+                ],
+                Some(&mut options),
+                None,
+            )
+        })
+        .map_err(git_error)?;
+
+    Ok(repo)
+}
+
+/// Name of the bare clone of the repository at the given URL
+fn clone_name(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Error for a failed git operation
+pub fn git_error(e: git2::Error) -> Error {
+    Error::new(ErrorKind::Repo, &e.message().to_owned())
+}
+
+/// Check that the workspace resolves with the lockfile as it is, finding the
+/// packages which differ if it doesn't
+fn check_resolution(lockfile_path: &Path, lockfile: &Lockfile, offline: bool) -> CheckResult {
+    let manifest_path = lockfile_path.with_file_name(CARGO_TOML_FILE);
+
+    if !manifest_path.is_file() {
+        return CheckResult::skipped(
+            Check::Resolution,
+            format!("no {} next to the lockfile", CARGO_TOML_FILE),
+        );
+    }
+
+    let checked = lockfile.packages.len();
+    let locked_error = match lockfile::resolve_workspace(&manifest_path, true, offline) {
+        Ok(()) => return CheckResult::new(Check::Resolution, checked, vec![]),
+        Err(e) => e,
+    };
+
+    let resolution_failed = |message: String| {
+        let failure = Failure {
+            kind: FailureKind::ResolutionFailed,
+            package: None,
+            version: None,
+            message,
+        };
+
+        CheckResult::new(Check::Resolution, checked, vec![failure])
+    };
+
+    let resolved = match resolve_afresh(&manifest_path, lockfile_path, offline) {
+        Ok(resolved) => resolved,
+        Err(e) => return resolution_failed(format!("{} ({})", locked_error, e)),
+    };
+
+    let key = |package: &Package| {
+        (
+            package.name.clone(),
+            package.version.clone(),
+            package.source.as_ref().map(ToString::to_string),
+        )
+    };
+
+    let locked: Set<_> = lockfile.packages.iter().map(key).collect();
+    let fresh: Set<_> = resolved.packages.iter().map(key).collect();
+    let mut failures = vec![];
+
+    for package in &resolved.packages {
+        if !locked.contains(&key(package)) {
+            failures.push(Failure::package(
+                FailureKind::MissingPackage,
+                package,
+                "the workspace resolves to it, but it isn't in the lockfile",
+            ));
+        }
+    }
+
+    for package in &lockfile.packages {
+        if !fresh.contains(&key(package)) {
+            failures.push(Failure::package(
+                FailureKind::ExtraPackage,
+                package,
+                "it's in the lockfile, but the workspace doesn't resolve to it",
+            ));
+        }
+    }
+
+    // The lockfile may differ in other ways, e.g. in the dependencies of a
+    // package
+    if failures.is_empty() {
+        return resolution_failed(locked_error.to_string());
+    }
+
+    CheckResult::new(Check::Resolution, checked, failures)
+}
+
+/// Resolve the workspace of the given manifest afresh, restoring its
+/// lockfile afterwards, and return the resolved lockfile
+fn resolve_afresh(
+    manifest_path: &Path,
+    lockfile_path: &Path,
+    offline: bool,
+) -> Result<Lockfile, Error> {
+    let _restore = Restore::new(lockfile_path)?;
+    lockfile::resolve_workspace(manifest_path, false, offline)?;
+
+    Lockfile::load(lockfile_path).map_err(|e| {
+        Error::new(
+            ErrorKind::Parse,
+            &format!(
+                "couldn't load the resolved {}: {}",
+                lockfile_path.display(),
+                e
+            ),
+        )
+    })
+}
+
+/// Original contents of a file, which are written back when dropped
+struct Restore {
+    /// Path to the file
+    path: PathBuf,
+
+    /// Original contents of the file
+    contents: Vec<u8>,
+}
+
+impl Restore {
+    /// Save the original contents of the file at the given path
+    fn new(path: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            path: path.to_owned(),
+            contents: fs::read(path)?,
+        })
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Err(e) = fs::write(&self.path, &self.contents) {
+            status_err!("couldn't restore {}: {}", self.path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,409 @@
+//! OpenVEX documents
+//!
+//! `cargo audit vex` exports the advisories ignored in `audit.toml` which
+//! match the audited packages as an [OpenVEX] document, so compliance tools
+//! can consume the suppressions instead of an ad-hoc ignore list. Each
+//! suppression is a `not_affected` statement about the workspace's packages,
+//! with the vulnerable crates as subcomponents, and the ignore's
+//! `justification` (if any) and `reason` as its justification and impact
+//! statement.
+//!
+//! Conversely, `cargo audit --vex vex.json` reads VEX documents (e.g. ones
+//! published by a vendor) and moves the vulnerabilities which a statement
+//! marks `not_affected` or `fixed` out of the report's findings, so they
+//! don't fail the audit. A statement applies to a package if it's one of the
+//! statement's products or their subcomponents, or if the statement is about
+//! a workspace member as a whole. The report notes each of these as
+//! "suppressed by VEX", along with the document and statement.
+//!
+//! [OpenVEX]: https://github.com/openvex/spec
+
+use crate::suppressions::Suppression;
+use rustsec::{
+    error::{Error, ErrorKind},
+    lockfile::Lockfile,
+    package::Package,
+    report::{VexSuppressedVulnerability, VulnerabilityInfo},
+    Report,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, fs, path::Path, str::FromStr, time::SystemTime};
+
+/// Context of OpenVEX documents
+pub const CONTEXT: &str = "https://openvex.dev/ns/v0.2.0";
+
+/// Author of documents which aren't given one
+pub const DEFAULT_AUTHOR: &str = "cargo-audit";
+
+/// Impact statement of suppressions without a reason (OpenVEX requires
+/// `not_affected` statements to have a justification or an impact statement)
+const DEFAULT_IMPACT: &str = "Ignored in the cargo-audit configuration";
+
+/// Why a product isn't affected by a vulnerability, from the justifications
+/// defined by OpenVEX
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Justification {
+    /// The vulnerable component isn't included in the product
+    ComponentNotPresent,
+
+    /// The vulnerable code isn't included in the component
+    VulnerableCodeNotPresent,
+
+    /// The vulnerable code can't be executed by the product
+    VulnerableCodeNotInExecutePath,
+
+    /// The vulnerable code can't be controlled by an attacker
+    VulnerableCodeCannotBeControlledByAdversary,
+
+    /// The product has built-in protections against the vulnerability
+    InlineMitigationsAlreadyExist,
+}
+
+impl Justification {
+    /// Get all justifications
+    pub fn all() -> Vec<Self> {
+        vec![
+            Justification::ComponentNotPresent,
+            Justification::VulnerableCodeNotPresent,
+            Justification::VulnerableCodeNotInExecutePath,
+            Justification::VulnerableCodeCannotBeControlledByAdversary,
+            Justification::InlineMitigationsAlreadyExist,
+        ]
+    }
+
+    /// Get the name of the justification in OpenVEX documents
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Justification::ComponentNotPresent => "component_not_present",
+            Justification::VulnerableCodeNotPresent => "vulnerable_code_not_present",
+            Justification::VulnerableCodeNotInExecutePath => "vulnerable_code_not_in_execute_path",
+            Justification::VulnerableCodeCannotBeControlledByAdversary => {
+                "vulnerable_code_cannot_be_controlled_by_adversary"
+            }
+            Justification::InlineMitigationsAlreadyExist => "inline_mitigations_already_exist",
+        }
+    }
+}
+
+impl fmt::Display for Justification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Justification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Self::all()
+            .into_iter()
+            .find(|justification| justification.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::all().iter().map(|j| j.as_str()).collect();
+                format!(
+                    "invalid VEX justification {:?} (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Statuses of statements which suppress findings
+const SUPPRESSING_STATUSES: &[&str] = &["not_affected", "fixed"];
+
+/// OpenVEX document
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Document {
+    /// OpenVEX context (and so version) of the document: [`CONTEXT`] for
+    /// generated documents
+    #[serde(rename = "@context")]
+    pub context: String,
+
+    /// IRI identifying the document, derived from its statements
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    /// Person or organization which authored the document
+    #[serde(default)]
+    pub author: String,
+
+    /// When the document was issued (RFC 3339)
+    #[serde(default)]
+    pub timestamp: String,
+
+    /// Version of the document (always 1 for generated documents, as they're
+    /// regenerated rather than updated)
+    #[serde(default)]
+    pub version: u32,
+
+    /// Tool which generated the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tooling: Option<String>,
+
+    /// Statements about vulnerabilities
+    #[serde(default)]
+    pub statements: Vec<Statement>,
+}
+
+/// Statement about how a vulnerability affects products (always that they
+/// aren't, for generated documents)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Statement {
+    /// Vulnerability the statement is about
+    pub vulnerability: Vulnerability,
+
+    /// Products the statement applies to
+    #[serde(default)]
+    pub products: Vec<Product>,
+
+    /// Status of the products, e.g. `not_affected` or `fixed`
+    pub status: String,
+
+    /// Why the products aren't affected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub justification: Option<Justification>,
+
+    /// Explanation of why the products aren't affected (the reason the
+    /// advisory is ignored, for generated documents)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impact_statement: Option<String>,
+}
+
+/// Vulnerability a statement is about
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Vulnerability {
+    /// ID of the vulnerability, e.g. an advisory ID
+    pub name: String,
+
+    /// Other IDs of the vulnerability (e.g. CVEs)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// Product (or subcomponent of one), identified by its package URL
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Product {
+    /// Package URL, e.g. `pkg:cargo/foo@1.0.0`
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    /// Vulnerable components of the product
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subcomponents: Vec<Product>,
+}
+
+impl Document {
+    /// Build the document for the suppressions of a lockfile's advisories
+    pub fn new(lockfile: &Lockfile, suppressions: &[Suppression], author: &str) -> Self {
+        let members = members(lockfile);
+
+        let statements: Vec<Statement> = suppressions
+            .iter()
+            .map(|suppression| {
+                let vulnerable: Vec<Product> = suppression
+                    .packages
+                    .iter()
+                    .map(|package| {
+                        let mut parts = package.splitn(2, ' ');
+                        let name = parts.next().unwrap_or_default();
+                        let version = parts.next().unwrap_or_default();
+
+                        Product {
+                            id: purl(name, version),
+                            subcomponents: vec![],
+                        }
+                    })
+                    .collect();
+
+                // Without workspace members (e.g. a lockfile of a published
+                // crate), the vulnerable crates are the products
+                let products = if members.is_empty() {
+                    vulnerable
+                } else {
+                    members
+                        .iter()
+                        .map(|member| Product {
+                            id: member.clone(),
+                            subcomponents: vulnerable.clone(),
+                        })
+                        .collect()
+                };
+
+                let impact_statement = match (&suppression.reason, suppression.justification) {
+                    (Some(reason), _) => Some(reason.clone()),
+                    (None, Some(_)) => None,
+                    (None, None) => Some(DEFAULT_IMPACT.to_owned()),
+                };
+
+                Statement {
+                    vulnerability: Vulnerability {
+                        name: suppression.id.to_string(),
+                        aliases: suppression
+                            .aliases
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect(),
+                    },
+                    products,
+                    status: "not_affected".to_owned(),
+                    justification: suppression.justification,
+                    impact_statement,
+                }
+            })
+            .collect();
+
+        let digest = Sha256::digest(
+            serde_json::to_string(&statements)
+                .expect("statements serialize")
+                .as_bytes(),
+        );
+        let id: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        Self {
+            context: CONTEXT.to_owned(),
+            id: format!("urn:cargo-audit:vex:{}", id),
+            author: author.to_owned(),
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            version: 1,
+            tooling: Some(concat!("cargo-audit/", env!("CARGO_PKG_VERSION")).to_owned()),
+            statements,
+        }
+    }
+
+    /// Load a VEX document from a JSON file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let json = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't read VEX document {}: {}", path.display(), e),
+            )
+        })?;
+
+        serde_json::from_str(&json).map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("couldn't parse VEX document {}: {}", path.display(), e),
+            )
+        })
+    }
+}
+
+impl Statement {
+    /// Does the statement say the products aren't affected?
+    pub fn is_suppressing(&self) -> bool {
+        SUPPRESSING_STATUSES.contains(&self.status.as_str())
+    }
+
+    /// Is the statement about the given advisory (by its ID or an alias)?
+    pub fn is_about(&self, advisory: &rustsec::advisory::Metadata) -> bool {
+        let ids: Vec<&str> = Some(advisory.id.as_str())
+            .into_iter()
+            .chain(advisory.aliases.iter().map(|alias| alias.as_str()))
+            .collect();
+
+        Some(&self.vulnerability.name)
+            .into_iter()
+            .chain(self.vulnerability.aliases.iter())
+            .any(|name| ids.iter().any(|id| id.eq_ignore_ascii_case(name)))
+    }
+
+    /// Does the statement apply to the given package of a lockfile with the
+    /// given workspace members (as package URLs)?
+    pub fn applies_to(&self, package: &Package, members: &[String]) -> bool {
+        let package = purl(package.name.as_str(), &package.version.to_string());
+
+        self.products.iter().any(|product| {
+            purl_matches(&product.id, &package)
+                || product
+                    .subcomponents
+                    .iter()
+                    .any(|subcomponent| purl_matches(&subcomponent.id, &package))
+                || (product.subcomponents.is_empty()
+                    && members
+                        .iter()
+                        .any(|member| purl_matches(&product.id, member)))
+        })
+    }
+}
+
+/// Move the vulnerabilities which a statement of the documents says the
+/// lockfile's packages aren't affected by out of the report's findings
+pub fn apply(report: &mut Report, lockfile: &Lockfile, documents: &[Document]) {
+    let members = members(lockfile);
+    let mut list = vec![];
+
+    for vulnerability in report.vulnerabilities.list.drain(..) {
+        let found = documents.iter().find_map(|document| {
+            document
+                .statements
+                .iter()
+                .find(|statement| {
+                    statement.is_suppressing()
+                        && statement.is_about(&vulnerability.advisory)
+                        && statement.applies_to(&vulnerability.package, &members)
+                })
+                .map(|statement| (document, statement))
+        });
+
+        match found {
+            Some((document, statement)) => {
+                report.vulnerabilities.vex.push(VexSuppressedVulnerability {
+                    vulnerability,
+                    document: document.id.clone(),
+                    status: statement.status.clone(),
+                    justification: statement.justification.map(|j| j.to_string()),
+                    impact_statement: statement.impact_statement.clone(),
+                })
+            }
+            None => list.push(vulnerability),
+        }
+    }
+
+    if !report.vulnerabilities.vex.is_empty() {
+        let ignored = std::mem::take(&mut report.vulnerabilities.ignored);
+        let acknowledged = std::mem::take(&mut report.vulnerabilities.acknowledged);
+        let vex = std::mem::take(&mut report.vulnerabilities.vex);
+        report.vulnerabilities = VulnerabilityInfo::new(list);
+        report.vulnerabilities.ignored = ignored;
+        report.vulnerabilities.acknowledged = acknowledged;
+        report.vulnerabilities.vex = vex;
+        report.sort();
+    } else {
+        report.vulnerabilities.list = list;
+    }
+}
+
+/// Package URLs of the workspace members of a lockfile (the packages without
+/// a source)
+fn members(lockfile: &Lockfile) -> Vec<String> {
+    lockfile
+        .packages
+        .iter()
+        .filter(|package| package.source.is_none())
+        .map(|package| purl(package.name.as_str(), &package.version.to_string()))
+        .collect()
+}
+
+/// Package URL of a crate
+fn purl(name: &str, version: &str) -> String {
+    format!("pkg:cargo/{}@{}", name, version)
+}
+
+/// Does the package URL of a statement match that of a package? Qualifiers
+/// and subpaths are ignored, and package URLs without a version match every
+/// version.
+fn purl_matches(pattern: &str, purl: &str) -> bool {
+    let pattern = pattern
+        .split(|c| c == '?' || c == '#')
+        .next()
+        .unwrap_or_default();
+
+    if pattern.contains('@') {
+        pattern == purl
+    } else {
+        purl.starts_with(pattern) && purl[pattern.len()..].starts_with('@')
+    }
+}
@@ -0,0 +1,172 @@
+//! Watch mode (`cargo audit --watch`)
+//!
+//! After the first audit (whose report is printed in full), the lockfile is
+//! polled for changes and the advisory database is fetched again
+//! periodically. The lockfile is audited again whenever either changes, and
+//! only the findings which were introduced or resolved since the previous
+//! audit are printed.
+
+use crate::{auditor::Auditor, config::WatchConfig, prelude::*, presenter::Presenter};
+use rustsec::{report::Diff, Error, Report};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Default interval between fetches of the advisory database, in minutes
+pub const DEFAULT_FETCH_INTERVAL_MINUTES: u64 = 60;
+
+/// How often the lockfile is checked for changes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What changed since the previous audit
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// The lockfile was modified
+    Lockfile,
+
+    /// The advisory database was updated
+    Database,
+}
+
+/// Watches a lockfile and the advisory database, auditing the lockfile again
+/// when either changes
+pub struct Watcher {
+    /// Auditor the lockfile is audited with
+    auditor: Auditor,
+
+    /// Presenter the changes are printed with (taken from the auditor after
+    /// the first audit)
+    presenter: Option<Presenter>,
+
+    /// Path to the lockfile
+    lockfile_path: PathBuf,
+
+    /// Modification time and size of the lockfile when it was last audited
+    stamp: Option<(SystemTime, u64)>,
+
+    /// Report of the last audit
+    report: Option<Report>,
+
+    /// Interval between fetches of the advisory database
+    fetch_interval: Duration,
+
+    /// When the advisory database was last fetched
+    fetched: Instant,
+}
+
+impl Watcher {
+    /// Watch the lockfile at the given path, auditing it with the given
+    /// auditor
+    pub fn new(auditor: Auditor, lockfile_path: &Path, config: &WatchConfig) -> Self {
+        Self {
+            auditor,
+            presenter: None,
+            lockfile_path: lockfile_path.to_owned(),
+            stamp: None,
+            report: None,
+            fetch_interval: config
+                .fetch_interval_minutes
+                .map(|minutes| Duration::from_secs(minutes * 60))
+                .unwrap_or_else(|| Duration::from_secs(DEFAULT_FETCH_INTERVAL_MINUTES * 60)),
+            fetched: Instant::now(),
+        }
+    }
+
+    /// Audit the lockfile for the first time, printing the full report with
+    /// the auditor's presenter (which later changes are printed with)
+    pub fn start(&mut self) -> Result<&Report, Error> {
+        // Changes made while auditing are picked up by the next check
+        self.stamp = stamp(&self.lockfile_path);
+
+        let report = self.auditor.audit(Some(&self.lockfile_path))?;
+        self.presenter = self.auditor.take_presenter();
+        Ok(self.report.get_or_insert(report))
+    }
+
+    /// Watch until the process is interrupted
+    pub fn run(mut self) -> ! {
+        status_ok!(
+            "Watching",
+            "{} for changes (press Ctrl-C to stop)",
+            self.lockfile_path.display()
+        );
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            if let Some(change) = self.check() {
+                self.audit(change);
+            }
+        }
+    }
+
+    /// Check whether the lockfile or (if it's time to fetch it again) the
+    /// advisory database changed since the last audit
+    pub fn check(&mut self) -> Option<Change> {
+        let stamp = stamp(&self.lockfile_path);
+
+        if stamp != self.stamp {
+            self.stamp = stamp;
+            return Some(Change::Lockfile);
+        }
+
+        if self.fetched.elapsed() < self.fetch_interval {
+            return None;
+        }
+
+        self.fetched = Instant::now();
+
+        match self.auditor.reload_database() {
+            Ok(true) => Some(Change::Database),
+            Ok(false) => None,
+            Err(e) => {
+                status_warn!("couldn't fetch the advisory database: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Audit the lockfile again, printing the findings introduced and
+    /// resolved since the last audit. Failed audits (e.g. of a lockfile
+    /// which is being written) are reported, and the watch goes on.
+    pub fn audit(&mut self, change: Change) -> Option<Diff> {
+        match change {
+            Change::Lockfile => status_ok!("Changed", "{}", self.lockfile_path.display()),
+            Change::Database => status_ok!("Updated", "advisory database"),
+        }
+
+        let report = match self.auditor.audit(Some(&self.lockfile_path)) {
+            Ok(report) => report,
+            Err(e) => {
+                status_err!("{}", e);
+                return None;
+            }
+        };
+
+        let diff = match &self.report {
+            Some(previous) => Diff::new(previous, &report),
+            None => Diff::default(),
+        };
+
+        self.report = Some(report);
+
+        if diff.introduced.is_empty() && diff.resolved.is_empty() {
+            status_ok!("Unchanged", "no findings were introduced or resolved");
+        } else if let Some(presenter) = &mut self.presenter {
+            if let Err(e) = presenter.print_delta(&diff) {
+                status_err!("{}", e);
+            }
+        }
+
+        Some(diff)
+    }
+}
+
+/// Modification time and size of the file at the given path (if it exists)
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
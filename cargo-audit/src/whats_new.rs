@@ -0,0 +1,370 @@
+//! Advisories added to the advisory database since a reference point
+//!
+//! `cargo audit whats-new` lists the advisories which are in the database
+//! now but weren't at a reference point: a commit of a database source's git
+//! history, the last commit before a date, or a snapshot of the advisory IDs
+//! saved by an earlier run (with `--save-snapshot`). The new advisories can be
+//! cross-referenced against lockfiles, to flag the ones affecting them.
+
+use crate::config::ResolvedSource;
+use rustsec::{
+    advisory,
+    error::{Error, ErrorKind},
+    fs, package,
+    report::DatabaseInfo,
+    Database, Version,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet as Set,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Point of the advisory database's history to compare against
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Since {
+    /// Commit of a database source's git repository
+    Commit(String),
+
+    /// Start of a date (in UTC): the last commit before it, in each source
+    Date(advisory::Date),
+
+    /// Snapshot file of the advisory IDs, saved by an earlier run
+    Snapshot(PathBuf),
+}
+
+impl Since {
+    /// Get a description of the reference point
+    pub fn description(&self) -> String {
+        match self {
+            Since::Commit(commit) => format!("commit {}", commit),
+            Since::Date(date) => date.as_str().to_owned(),
+            Since::Snapshot(path) => format!("snapshot {}", path.display()),
+        }
+    }
+}
+
+/// Snapshot of the IDs of the advisories in the database
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// IDs of the advisories
+    pub advisories: Set<advisory::Id>,
+}
+
+impl Snapshot {
+    /// Take a snapshot of the advisory IDs in the given database
+    pub fn new(database: &Database) -> Self {
+        Self {
+            advisories: database
+                .iter()
+                .map(|advisory| advisory.metadata.id.clone())
+                .collect(),
+        }
+    }
+
+    /// Load a snapshot from the given file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let json = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't read {}: {}", path.display(), e),
+            )
+        })?;
+
+        serde_json::from_str(&json).map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("couldn't parse {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Save the snapshot to the given file
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            Error::new(
+                ErrorKind::Parse,
+                &format!("couldn't serialize snapshot: {}", e),
+            )
+        })?;
+
+        fs::write(path, json + "\n").map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't write {}: {}", path.display(), e),
+            )
+        })
+    }
+}
+
+/// Report of the advisories added since a reference point
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `whats-new`, to tell these reports apart from lockfile audits
+    pub mode: &'static str,
+
+    /// Information about the advisory database
+    pub database: DatabaseInfo,
+
+    /// Reference point the database was compared against
+    pub since: Since,
+
+    /// Lockfiles the new advisories were cross-referenced against
+    pub lockfiles: Vec<PathBuf>,
+
+    /// New advisories, by ID
+    pub advisories: Vec<NewAdvisory>,
+}
+
+impl Report {
+    /// Number of new advisories which affect any of the lockfiles
+    pub fn matching_count(&self) -> usize {
+        self.advisories
+            .iter()
+            .filter(|advisory| !advisory.matches.is_empty())
+            .count()
+    }
+}
+
+/// Advisory added since the reference point
+#[derive(Clone, Debug, Serialize)]
+pub struct NewAdvisory {
+    /// Advisory metadata
+    pub advisory: advisory::Metadata,
+
+    /// Versions affected by the advisory
+    pub versions: advisory::Versions,
+
+    /// Packages in the lockfiles the advisory affects
+    pub matches: Vec<Match>,
+}
+
+/// Package in a lockfile affected by a new advisory
+#[derive(Clone, Debug, Serialize)]
+pub struct Match {
+    /// Path to the lockfile
+    pub lockfile: PathBuf,
+
+    /// Name of the package
+    pub package: package::Name,
+
+    /// Version of the package
+    pub version: Version,
+}
+
+/// Start a report of the advisories in the database which weren't at the
+/// reference point, without any lockfile matches
+pub fn compare(
+    database: &Database,
+    sources: &[ResolvedSource],
+    since: Since,
+) -> Result<Report, Error> {
+    let new_ids: Set<advisory::Id> = match &since {
+        Since::Snapshot(path) => {
+            let snapshot = Snapshot::load(path)?;
+            Snapshot::new(database)
+                .advisories
+                .difference(&snapshot.advisories)
+                .cloned()
+                .collect()
+        }
+        Since::Commit(commit) => {
+            let mut found = false;
+            let mut ids = Set::new();
+
+            for source in sources {
+                let repo = open_repo(source)?;
+
+                // Commits can only be found in the source they're from
+                let reference = match repo.revparse_single(commit) {
+                    Ok(object) => object.peel_to_commit().map_err(|e| repo_error(source, e))?,
+                    Err(_) => continue,
+                };
+
+                found = true;
+                ids.extend(added_ids(&repo, Some(&reference)).map_err(|e| repo_error(source, e))?);
+            }
+
+            if !found {
+                return Err(Error::new(
+                    ErrorKind::Repo,
+                    &format!("commit {} isn't in the advisory database's history", commit),
+                ));
+            }
+
+            ids
+        }
+        Since::Date(date) => {
+            let start = start_of(date)?;
+            let mut ids = Set::new();
+
+            for source in sources {
+                let repo = open_repo(source)?;
+                let reference =
+                    last_commit_before(&repo, start).map_err(|e| repo_error(source, e))?;
+                ids.extend(
+                    added_ids(&repo, reference.as_ref()).map_err(|e| repo_error(source, e))?,
+                );
+            }
+
+            ids
+        }
+    };
+
+    // Advisories are only listed if they're in the loaded database (e.g. not
+    // of a format this version can't parse)
+    let advisories = new_ids
+        .iter()
+        .filter_map(|id| database.get(id))
+        .map(|advisory| NewAdvisory {
+            advisory: advisory.metadata.clone(),
+            versions: advisory.versions.clone(),
+            matches: vec![],
+        })
+        .collect();
+
+    Ok(Report {
+        mode: "whats-new",
+        database: DatabaseInfo::new(database),
+        since,
+        lockfiles: vec![],
+        advisories,
+    })
+}
+
+/// Flag the new advisories affecting the lockfile at the given path, given
+/// the report of an audit of it
+pub fn add_matches(report: &mut Report, lockfile_path: &Path, audit: &rustsec::Report) {
+    let findings = audit
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vuln| (&vuln.advisory.id, &vuln.package))
+        .chain(audit.warnings.values().flatten().filter_map(|warning| {
+            warning
+                .advisory
+                .as_ref()
+                .map(|advisory| (&advisory.id, &warning.package))
+        }));
+
+    for (id, package) in findings {
+        if let Some(new) = report
+            .advisories
+            .iter_mut()
+            .find(|new| &new.advisory.id == id)
+        {
+            new.matches.push(Match {
+                lockfile: lockfile_path.to_owned(),
+                package: package.name.clone(),
+                version: package.version.clone(),
+            });
+        }
+    }
+
+    report.lockfiles.push(lockfile_path.to_owned());
+}
+
+/// Open the git repository of a database source
+fn open_repo(source: &ResolvedSource) -> Result<git2::Repository, Error> {
+    git2::Repository::open(&source.path).map_err(|e| {
+        Error::new(
+            ErrorKind::Repo,
+            &format!(
+                "{} isn't a git repository ({}), compare against a snapshot with --since-snapshot instead",
+                source.path.display(),
+                e.message()
+            ),
+        )
+    })
+}
+
+/// Error for a failed git operation on a database source
+fn repo_error(source: &ResolvedSource, e: git2::Error) -> Error {
+    Error::new(
+        ErrorKind::Repo,
+        &format!(
+            "couldn't read the history of {}: {}",
+            source.path.display(),
+            e.message()
+        ),
+    )
+}
+
+/// IDs of the advisories at `HEAD` which weren't at the given commit (or
+/// all of them if there's no commit, i.e. the repository didn't exist yet)
+fn added_ids(
+    repo: &git2::Repository,
+    reference: Option<&git2::Commit<'_>>,
+) -> Result<Set<advisory::Id>, git2::Error> {
+    let head = repo.head()?.peel_to_commit()?;
+    let mut ids = advisory_ids(&head.tree()?)?;
+
+    if let Some(reference) = reference {
+        for id in advisory_ids(&reference.tree()?)? {
+            ids.remove(&id);
+        }
+    }
+
+    Ok(ids)
+}
+
+/// IDs of the advisories in a tree of the database, which are named after
+/// their advisory files (e.g. `crates/foo/RUSTSEC-2020-0001.md`)
+fn advisory_ids(tree: &git2::Tree<'_>) -> Result<Set<advisory::Id>, git2::Error> {
+    let mut ids = Set::new();
+
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        let in_collection = dir.starts_with("crates/") || dir.starts_with("rust/");
+
+        if let (true, Some(name)) = (in_collection, entry.name()) {
+            let stem = name
+                .strip_suffix(".md")
+                .or_else(|| name.strip_suffix(".toml"));
+
+            if let Some(id) = stem.and_then(|stem| stem.parse().ok()) {
+                ids.insert(id);
+            }
+        }
+
+        git2::TreeWalkResult::Ok
+    })?;
+
+    Ok(ids)
+}
+
+/// Last commit of `HEAD`'s history before the given time (in seconds since
+/// the Unix epoch), if any
+fn last_commit_before(
+    repo: &git2::Repository,
+    time: i64,
+) -> Result<Option<git2::Commit<'_>>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+
+        if commit.time().seconds() < time {
+            return Ok(Some(commit));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Start of the given date (in UTC), in seconds since the Unix epoch
+fn start_of(date: &advisory::Date) -> Result<i64, Error> {
+    humantime::parse_rfc3339(&format!("{}T00:00:00Z", date.as_str()))
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since_epoch| since_epoch.as_secs() as i64)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::BadParam,
+                &format!("invalid date: {}", date.as_str()),
+            )
+        })
+}
@@ -0,0 +1,197 @@
+//! Audits of every lockfile under a directory tree
+//!
+//! `cargo audit --recursive` (or `--workspace`) is for monorepos with several
+//! independent workspaces: it finds every `Cargo.lock` under the current
+//! directory, skipping hidden directories (e.g. `.git`) and `target`
+//! directories, and audits each of them against the advisory database, which
+//! is loaded once for them all. The reports are combined into one, with a
+//! section for each lockfile.
+
+use crate::{auditor::Auditor, config::ExitCodes, lockfile::CARGO_LOCK_FILE};
+use rustsec::{
+    error::{Error, ErrorKind},
+    report::{DatabaseInfo, Outcome},
+};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the directories cargo builds in, which are never searched
+const TARGET_DIR: &str = "target";
+
+/// Combined report of the audits of the lockfiles under a directory
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Always `workspaces`, to tell these reports apart from single lockfile
+    /// audits
+    pub mode: &'static str,
+
+    /// Directory which was searched for lockfiles
+    pub root: PathBuf,
+
+    /// Information about the advisory database
+    pub database: DatabaseInfo,
+
+    /// Audited lockfiles, in the order they were found
+    pub lockfiles: Vec<Audited>,
+
+    /// Most severe outcome of the audits
+    pub outcome: Outcome,
+
+    /// Exit status for the most severe outcome
+    #[serde(skip)]
+    pub exit_code: i32,
+}
+
+impl Report {
+    /// Number of vulnerabilities found in all of the lockfiles
+    pub fn vulnerability_count(&self) -> usize {
+        self.lockfiles
+            .iter()
+            .filter_map(|audited| audited.report.as_ref())
+            .map(|report| report.vulnerabilities.count)
+            .sum()
+    }
+
+    /// Number of lockfiles with vulnerabilities
+    pub fn vulnerable_count(&self) -> usize {
+        self.lockfiles
+            .iter()
+            .filter_map(|audited| audited.report.as_ref())
+            .filter(|report| report.vulnerabilities.found)
+            .count()
+    }
+
+    /// Number of lockfiles which couldn't be audited
+    pub fn error_count(&self) -> usize {
+        self.lockfiles
+            .iter()
+            .filter(|audited| audited.error.is_some())
+            .count()
+    }
+}
+
+/// Audited lockfile
+#[derive(Clone, Debug, Serialize)]
+pub struct Audited {
+    /// Path to the lockfile, relative to the searched directory
+    pub path: PathBuf,
+
+    /// Report of the audit, if the lockfile could be audited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<rustsec::Report>,
+
+    /// Why the lockfile couldn't be audited, if it couldn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Find the lockfiles under the given directory, as paths relative to it,
+/// with each directory's own lockfile before those of its subdirectories
+pub fn discover(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut lockfiles = vec![];
+    search(root, Path::new(""), &mut lockfiles)?;
+    Ok(lockfiles)
+}
+
+/// Audit each of the lockfiles under the given directory. Lockfiles which
+/// can't be audited (e.g. they can't be parsed) are reported as such,
+/// without stopping the others from being audited.
+pub fn audit(auditor: &mut Auditor, root: &Path, exit_codes: &ExitCodes) -> Result<Report, Error> {
+    let paths = discover(root)?;
+
+    if paths.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            &format!("no {} found under {}", CARGO_LOCK_FILE, root.display()),
+        ));
+    }
+
+    let mut report = Report {
+        mode: "workspaces",
+        root: root.to_owned(),
+        database: DatabaseInfo::new(auditor.database()),
+        lockfiles: vec![],
+        outcome: Outcome::Clean,
+        exit_code: 0,
+    };
+
+    for path in paths {
+        let (outcome, exit_code, audited) = match auditor.audit_lockfile(&root.join(&path)) {
+            Ok(lockfile_report) => {
+                let outcome = lockfile_report.outcome.unwrap_or(Outcome::Clean);
+                let exit_code = auditor.exit_code(&lockfile_report);
+
+                let audited = Audited {
+                    path,
+                    report: Some(lockfile_report),
+                    error: None,
+                };
+
+                (outcome, exit_code, audited)
+            }
+            Err(e) => {
+                let audited = Audited {
+                    path,
+                    report: None,
+                    error: Some(e.to_string()),
+                };
+
+                let outcome = Outcome::OperationalError;
+                (outcome, exit_codes.code(outcome), audited)
+            }
+        };
+
+        if report.lockfiles.is_empty() || outcome > report.outcome {
+            report.outcome = outcome;
+            report.exit_code = exit_code;
+        }
+
+        report.lockfiles.push(audited);
+    }
+
+    Ok(report)
+}
+
+/// Add the lockfiles in the given directory (at the given path relative to
+/// the searched one) and its subdirectories
+fn search(root: &Path, relative: &Path, lockfiles: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let dir = root.join(relative);
+
+    let mut entries = fs::read_dir(&dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Io,
+                &format!("couldn't read {}: {}", dir.display(), e),
+            )
+        })?;
+
+    entries.sort_by_key(|entry| entry.file_name());
+
+    if entries
+        .iter()
+        .any(|entry| entry.file_name() == CARGO_LOCK_FILE)
+    {
+        lockfiles.push(relative.join(CARGO_LOCK_FILE));
+    }
+
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        // Symlinks aren't followed, so each lockfile is only found once
+        let is_dir = entry
+            .file_type()
+            .map(|file_type| file_type.is_dir())
+            .unwrap_or(false);
+
+        if is_dir && !name.starts_with('.') && name != TARGET_DIR {
+            search(root, &relative.join(entry.file_name()), lockfiles)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Tests for acknowledging advisories until a deadline
+
+mod support;
+
+use cargo_audit::acknowledgments;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Run `cargo audit` in the given project directory against the database
+fn cargo_audit(project: &Path, db: &Path, args: &[&str]) -> Output {
+    let lockfile: PathBuf = fs::canonicalize("tests/support/base64_vuln/Cargo.lock").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db)
+        .arg("--file")
+        .arg(lockfile)
+        .args(args)
+        .env("CARGO_HOME", project)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn acknowledged_until_expiry() {
+    let db_dir = support::database(&[Advisory::base64()]);
+    let project_dir = tempfile::tempdir().unwrap();
+    let project = project_dir.path();
+
+    let output = cargo_audit(
+        project,
+        db_dir.path(),
+        &[
+            "ack",
+            "RUSTSEC-2017-0004",
+            "--ticket",
+            "JIRA-123",
+            "--days",
+            "30",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    let config_path = project.join(".cargo").join("audit.toml");
+    let config = fs::read_to_string(&config_path).unwrap();
+    assert!(config.contains("id = \"RUSTSEC-2017-0004\", ticket = \"JIRA-123\""));
+
+    let output = cargo_audit(project, db_dir.path(), &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(
+        stdout.contains("Acknowledged: 1 vulnerability"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Ticket:        JIRA-123"));
+    assert!(stdout.contains("(30 days remaining)"));
+
+    let output = cargo_audit(project, db_dir.path(), &["ack", "--list-acks"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("RUSTSEC-2017-0004  ticket JIRA-123"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("(30 days remaining)"));
+
+    // Once expired, the vulnerability fails the audit again
+    fs::write(
+        &config_path,
+        "[advisories]\n\
+         acknowledged = [{ id = \"RUSTSEC-2017-0004\", ticket = \"JIRA-123\", expires = \"2020-01-31\" }]\n",
+    )
+    .unwrap();
+
+    let output = cargo_audit(project, db_dir.path(), &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout
+        .contains("acknowledgment of RUSTSEC-2017-0004 (ticket JIRA-123) expired on 2020-01-31"));
+    assert!(!stdout.contains("Acknowledged:"));
+
+    let output = cargo_audit(project, db_dir.path(), &["ack", "--list-acks"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("expires 2020-01-31 (expired)"));
+}
+
+#[test]
+fn ack_requires_ticket_and_days() {
+    let db_dir = support::database(&[Advisory::base64()]);
+    let project_dir = tempfile::tempdir().unwrap();
+
+    for args in &[
+        &["ack", "RUSTSEC-2017-0004", "--days", "30"][..],
+        &["ack", "RUSTSEC-2017-0004", "--ticket", "JIRA-123"][..],
+        &[
+            "ack",
+            "RUSTSEC-2017-0004",
+            "--ticket",
+            "JIRA-123",
+            "--days",
+            "0",
+        ][..],
+    ] {
+        let output = cargo_audit(project_dir.path(), db_dir.path(), args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    }
+
+    assert!(!project_dir.path().join(".cargo").exists());
+}
+
+#[test]
+fn acknowledgment_dates() {
+    let date = |s: &str| s.parse().unwrap();
+
+    assert_eq!(
+        acknowledgments::days_after(&date("2024-02-28"), 2),
+        date("2024-03-01")
+    );
+    assert_eq!(
+        acknowledgments::days_between(&date("2023-12-31"), &date("2024-01-30")),
+        30
+    );
+    assert_eq!(
+        acknowledgments::days_between(&date("2024-01-30"), &date("2023-12-31")),
+        0
+    );
+    assert!(!acknowledgments::is_calendar_date(&date("2023-02-29")));
+}
@@ -0,0 +1,647 @@
+//! Auditor tests
+
+mod support;
+
+use cargo_audit::{
+    auditor::Auditor,
+    config::{AuditConfig, DenyOption, OutputFormat, WarnOption},
+};
+use rustsec::{
+    advisory,
+    package::Package,
+    report::{Outcome, OverriddenAction},
+    warning,
+};
+use std::{fs, path::Path};
+use support::Advisory;
+
+/// Advisory for versions of `package` prior to 0.2.0
+fn advisory(package: &str) -> Advisory {
+    Advisory::new("RUSTSEC-2020-0001", package)
+}
+
+/// Get an auditor for the advisory database in the given directory (which
+/// prints JSON reports, since the terminal isn't initialized in tests)
+fn auditor(db_path: &Path, config: &mut AuditConfig) -> Auditor {
+    config.database.path = Some(db_path.to_owned());
+    config.database.fetch = false;
+    config.output.quiet = true;
+    config.output.format = OutputFormat::Json;
+    config.yanked.enabled = false;
+    config.registry.lookup = false;
+    Auditor::new(config)
+}
+
+/// Named database sources are merged (advisories from sources which come
+/// first take precedence, and findings say which source they came from),
+/// and can be restricted to some of them
+#[test]
+fn merged_database_sources() {
+    let internal_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(internal_dir.path());
+
+    // `RUSTSEC-2020-0001` is already in the internal database, so only the
+    // advisory for `bar` is merged
+    let rustsec_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(rustsec_dir.path());
+    advisory("bar").write(rustsec_dir.path());
+    let bar_dir = rustsec_dir.path().join("crates").join("bar");
+    let advisory = fs::read_to_string(bar_dir.join("RUSTSEC-2020-0001.md")).unwrap();
+    fs::remove_file(bar_dir.join("RUSTSEC-2020-0001.md")).unwrap();
+    fs::write(
+        bar_dir.join("RUSTSEC-2020-0002.md"),
+        advisory.replace("RUSTSEC-2020-0001", "RUSTSEC-2020-0002"),
+    )
+    .unwrap();
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"bar\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let audit = |only: &[&str]| {
+        let mut config: AuditConfig = toml::from_str(&format!(
+            "[database]\nfetch = false\n\n\
+             [database.sources.internal]\npath = {:?}\n\n\
+             [database.sources.rustsec]\npath = {:?}\n",
+            internal_dir.path(),
+            rustsec_dir.path()
+        ))
+        .unwrap();
+        config.database.only = only.iter().map(|name| name.to_string()).collect();
+        config.output.quiet = true;
+        config.output.format = OutputFormat::Json;
+        config.yanked.enabled = false;
+        config.registry.lookup = false;
+
+        let mut ids: Vec<String> = Auditor::new(&config)
+            .audit(Some(&lockfile_path))
+            .unwrap()
+            .vulnerabilities
+            .list
+            .iter()
+            .map(|vuln| {
+                format!(
+                    "{} {} ({})",
+                    vuln.advisory.id,
+                    vuln.package.name,
+                    vuln.source.as_deref().unwrap_or("-")
+                )
+            })
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(
+        audit(&[]),
+        [
+            "RUSTSEC-2020-0001 foo (internal)",
+            "RUSTSEC-2020-0002 bar (rustsec)"
+        ]
+    );
+    assert_eq!(audit(&["internal"]), ["RUSTSEC-2020-0001 foo (-)"]);
+}
+
+/// Audits of lockfiles with an invalid dependency graph still report
+/// vulnerabilities, but return an error
+#[test]
+fn invalid_dependency_tree() {
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(db_dir.path());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"bar 0.2.0\"]\n",
+    )
+    .unwrap();
+
+    let mut auditor = auditor(db_dir.path(), &mut AuditConfig::default());
+    let error = auditor.audit(Some(&lockfile_path)).unwrap_err();
+
+    assert!(auditor.dependency_tree().is_none());
+    assert!(error
+        .to_string()
+        .contains("package foo 0.1.0 depends on bar 0.2.0, which has no package entry"));
+}
+
+/// The summary counts the distinct advisories, package versions and crates
+/// found, and the direct dependencies whose subtrees contain them
+#[test]
+fn summary_counts() {
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(db_dir.path());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"a\", \"b\", \"c\"]\n\n\
+         [[package]]\n\
+         name = \"a\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"foo 0.1.0\"]\n\n\
+         [[package]]\n\
+         name = \"b\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"foo 0.1.1\"]\n\n\
+         [[package]]\n\
+         name = \"c\"\n\
+         version = \"0.1.0\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.1\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let report = auditor(db_dir.path(), &mut AuditConfig::default())
+        .audit(Some(&lockfile_path))
+        .unwrap();
+
+    assert_eq!(report.summary.advisories, 1);
+    assert_eq!(report.summary.package_versions, 2);
+    assert_eq!(report.summary.crates, 1);
+    assert_eq!(report.summary.direct_dependencies, Some(2));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["summary"]["package-versions"], 2);
+    assert_eq!(json["summary"]["direct-dependencies"], 2);
+}
+
+/// With `--warn duplicate-versions`, older versions of crates which are also
+/// locked at a newer version are warned about, noting what pulls them in and
+/// any advisories against the crate
+#[test]
+fn duplicate_versions() {
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"a\", \"b\", \"c\", \"baz 2.0.0\"]\n\n\
+         [[package]]\n\
+         name = \"a\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"foo 0.1.0\"]\n\n\
+         [[package]]\n\
+         name = \"b\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"foo 0.2.0\"]\n\n\
+         [[package]]\n\
+         name = \"c\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"baz 1.0.0\"]\n\n\
+         [[package]]\n\
+         name = \"baz\"\n\
+         version = \"1.0.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"baz\"\n\
+         version = \"2.0.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.2.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(db_dir.path());
+
+    // The check is opt-in
+    let report = auditor(db_dir.path(), &mut AuditConfig::default())
+        .audit(Some(&lockfile_path))
+        .unwrap();
+    assert!(!report
+        .warnings
+        .contains_key(&warning::Kind::DuplicateVersions));
+
+    let mut config = AuditConfig::default();
+    config.output.warn.push(WarnOption::DuplicateVersions);
+
+    let report = auditor(db_dir.path(), &mut config)
+        .audit(Some(&lockfile_path))
+        .unwrap();
+
+    let warnings = &report.warnings[&warning::Kind::DuplicateVersions];
+    assert_eq!(warnings.len(), 2);
+
+    let baz = warnings[0].duplicates.as_ref().unwrap();
+    assert_eq!(warnings[0].package.name.as_str(), "baz");
+    assert_eq!(warnings[0].package.version.to_string(), "1.0.0");
+    assert_eq!(baz.pinned_by, ["c 0.1.0"]);
+    assert!(baz.advisories.is_empty());
+
+    let foo = warnings[1].duplicates.as_ref().unwrap();
+    assert_eq!(warnings[1].package.version.to_string(), "0.1.0");
+    assert_eq!(foo.pinned_by, ["a 0.1.0"]);
+    assert_eq!(foo.advisories[0].as_str(), "RUSTSEC-2020-0001");
+
+    let json = serde_json::to_value(&report).unwrap();
+    let json = &json["warnings"]["duplicate-versions"][1];
+    assert_eq!(json["kind"], "duplicate-versions");
+    assert_eq!(json["duplicates"]["versions"][1], "0.2.0");
+
+    // Duplicate versions are denied like other warnings
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("unrelated").write(db_dir.path());
+    config.output.deny(DenyOption::DuplicateVersions);
+
+    let report = auditor(db_dir.path(), &mut config)
+        .audit(Some(&lockfile_path))
+        .unwrap();
+    assert_eq!(report.outcome, Some(Outcome::DeniedWarnings));
+}
+
+/// Packages with the same name and version from different sources (e.g. a
+/// crates.io release and a git fork used via `[patch]`) are audited separately
+#[test]
+fn duplicate_packages_from_different_sources() {
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(db_dir.path());
+    let lockfile_path = Path::new("tests/support/patched_fork/Cargo.lock");
+
+    // Report the git fork as a vulnerability rather than an overridden dependency
+    let mut config = AuditConfig::default();
+    config.packages.overridden = OverriddenAction::Fail;
+
+    let report = auditor(db_dir.path(), &mut config)
+        .audit(Some(lockfile_path))
+        .unwrap();
+
+    let mut sources: Vec<_> = report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vuln| vuln.package.source.clone().unwrap())
+        .collect();
+    sources.sort();
+    sources.dedup();
+    assert_eq!(sources.len(), 2);
+
+    // Skipping git sources only leaves the crates.io release
+    let mut config = AuditConfig::default();
+    config.packages.skip_git = true;
+
+    let report = auditor(db_dir.path(), &mut config)
+        .audit(Some(lockfile_path))
+        .unwrap();
+
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert!(report.vulnerabilities.list[0]
+        .package
+        .source
+        .as_ref()
+        .unwrap()
+        .is_default_registry());
+}
+
+/// Configuration for an embedded auditor using the advisory database in the
+/// given directory (which is otherwise the default configuration, so e.g.
+/// nothing is printed without the output options used by `auditor`)
+fn embedded_config(db_path: &Path) -> AuditConfig {
+    let mut config = AuditConfig::default();
+    config.database.path = Some(db_path.to_owned());
+    config.database.fetch = false;
+    config.yanked.enabled = false;
+    config.registry.lookup = false;
+    config
+}
+
+/// Auditors created with `try_new` audit lockfiles without a presenter,
+/// honoring ignored advisories
+#[test]
+fn embedded_audit_lockfile() {
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(db_dir.path());
+    let lockfile_path = Path::new("tests/support/patched_fork/Cargo.lock");
+
+    let mut config = embedded_config(db_dir.path());
+    let report = Auditor::try_new(&config)
+        .unwrap()
+        .audit_lockfile(lockfile_path)
+        .unwrap();
+
+    // The git fork is reported as an overridden dependency
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert_eq!(report.warnings[&warning::Kind::Overridden].len(), 1);
+    assert_eq!(report.lockfile.path.as_deref(), Some(lockfile_path));
+
+    config
+        .advisories
+        .ignore
+        .push("RUSTSEC-2020-0001".parse::<advisory::Id>().unwrap().into());
+
+    let report = Auditor::try_new(&config)
+        .unwrap()
+        .audit_lockfile(lockfile_path)
+        .unwrap();
+
+    assert!(!report.vulnerabilities.found);
+    assert!(report.warnings.is_empty());
+}
+
+/// Packages can be audited without a lockfile, using an already loaded
+/// advisory database
+#[test]
+fn embedded_audit_packages() {
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(db_dir.path());
+
+    let database = rustsec::Database::open(db_dir.path()).unwrap();
+    let mut auditor = Auditor::with_database(database, &embedded_config(db_dir.path()));
+    assert_eq!(auditor.database().iter().count(), 1);
+
+    let packages = ["0.1.0", "0.2.0"]
+        .iter()
+        .map(|version| Package {
+            name: "foo".parse().unwrap(),
+            version: version.parse().unwrap(),
+            source: Some(
+                "registry+https://github.com/rust-lang/crates.io-index"
+                    .parse()
+                    .unwrap(),
+            ),
+            checksum: None,
+            dependencies: vec![],
+            replace: None,
+        })
+        .collect::<Vec<_>>();
+
+    let report = auditor.audit_packages(&packages);
+
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert_eq!(
+        report.vulnerabilities.list[0].package.version.to_string(),
+        "0.1.0"
+    );
+}
+
+/// Reports of packages audited without a lockfile are processed like those
+/// of lockfiles, e.g. applying the fail-on policy (to normal dependencies)
+#[test]
+fn embedded_audit_packages_fail_on() {
+    let db_dir = tempfile::tempdir().unwrap();
+    advisory("foo").write(db_dir.path());
+
+    let packages = [Package {
+        name: "foo".parse().unwrap(),
+        version: "0.1.0".parse().unwrap(),
+        source: Some(
+            "registry+https://github.com/rust-lang/crates.io-index"
+                .parse()
+                .unwrap(),
+        ),
+        checksum: None,
+        dependencies: vec![],
+        replace: None,
+    }];
+
+    let outcome = |fail_on: &str| {
+        let mut config = embedded_config(db_dir.path());
+        config.output.fail_on = Some(toml::from_str(fail_on).unwrap());
+        let report = Auditor::try_new(&config).unwrap().audit_packages(&packages);
+
+        assert_eq!(report.vulnerabilities.count, 1);
+        assert!(report.vulnerabilities.list[0].scopes.is_some());
+        report.outcome
+    };
+
+    assert_eq!(outcome("normal = \"never\""), Some(Outcome::Clean));
+    assert_eq!(
+        outcome("normal = \"low\""),
+        Some(Outcome::VulnerabilitiesFound)
+    );
+}
+
+/// Errors loading the advisory database are returned rather than exiting
+#[test]
+fn embedded_missing_database() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let advisory_dir = db_dir.path().join("crates").join("foo");
+    fs::create_dir_all(&advisory_dir).unwrap();
+    fs::write(advisory_dir.join("RUSTSEC-2020-0001.md"), "invalid").unwrap();
+
+    let error = Auditor::try_new(&embedded_config(db_dir.path()))
+        .err()
+        .unwrap();
+
+    assert!(error
+        .to_string()
+        .contains("error loading advisory database"));
+}
+
+/// Advisories for `foo` (`denial-of-service`) and `bar` (`denial-of-service`
+/// and `memory-corruption`)
+fn categorized_advisories() -> Vec<Advisory> {
+    vec![
+        Advisory::new("RUSTSEC-2020-0001", "foo")
+            .attributes("categories = [\"denial-of-service\"]"),
+        Advisory::new("RUSTSEC-2020-0002", "bar")
+            .attributes("categories = [\"denial-of-service\", \"memory-corruption\"]"),
+    ]
+}
+
+/// Vulnerabilities are ignored by category only if all of their advisory's
+/// categories are ignored, and are then listed separately instead of failing
+/// the audit
+#[test]
+fn ignore_categories() {
+    let db_dir = support::database(&categorized_advisories());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    let lockfile = |packages: &[&str]| {
+        packages
+            .iter()
+            .map(|package| {
+                format!(
+                    "[[package]]\n\
+                     name = \"{}\"\n\
+                     version = \"0.1.0\"\n\
+                     source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+                    package
+                )
+            })
+            .collect::<String>()
+    };
+
+    let mut config = AuditConfig::default();
+    config.advisories.ignore_categories = vec![advisory::Category::DenialOfService];
+
+    fs::write(&lockfile_path, lockfile(&["bar", "foo"])).unwrap();
+    let report = auditor(db_dir.path(), &mut config.clone())
+        .audit(Some(&lockfile_path))
+        .unwrap();
+
+    let ids = |list: &[rustsec::Vulnerability]| {
+        list.iter()
+            .map(|vuln| vuln.advisory.id.as_str().to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&report.vulnerabilities.list), ["RUSTSEC-2020-0002"]);
+    assert_eq!(ids(&report.vulnerabilities.ignored), ["RUSTSEC-2020-0001"]);
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert_eq!(report.outcome, Some(Outcome::VulnerabilitiesFound));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json["vulnerabilities"]["ignored"][0]["advisory"]["id"],
+        "RUSTSEC-2020-0001"
+    );
+
+    fs::write(&lockfile_path, lockfile(&["foo"])).unwrap();
+    let report = auditor(db_dir.path(), &mut config)
+        .audit(Some(&lockfile_path))
+        .unwrap();
+    assert!(!report.vulnerabilities.found);
+    assert_eq!(report.vulnerabilities.ignored.len(), 1);
+    assert_eq!(report.outcome, Some(Outcome::Clean));
+}
+
+/// Only vulnerabilities with a selected category, and without an excluded
+/// one, are reported
+#[test]
+fn select_categories() {
+    let db_dir = support::database(&categorized_advisories());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"bar\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let audit = |config: &mut AuditConfig| {
+        let report = auditor(db_dir.path(), config)
+            .audit(Some(&lockfile_path))
+            .unwrap();
+
+        let ids = |list: &[rustsec::Vulnerability]| {
+            list.iter()
+                .map(|vuln| vuln.advisory.id.as_str().to_owned())
+                .collect::<Vec<_>>()
+        };
+        (
+            ids(&report.vulnerabilities.list),
+            ids(&report.vulnerabilities.ignored),
+        )
+    };
+
+    let mut config = AuditConfig::default();
+    config.advisories.categories = vec![advisory::Category::MemoryCorruption];
+    let (found, ignored) = audit(&mut config);
+    assert_eq!(found, ["RUSTSEC-2020-0002"]);
+    assert_eq!(ignored, ["RUSTSEC-2020-0001"]);
+
+    let mut config = AuditConfig::default();
+    config.advisories.exclude_categories = vec![advisory::Category::MemoryCorruption];
+    let (found, ignored) = audit(&mut config);
+    assert_eq!(found, ["RUSTSEC-2020-0001"]);
+    assert_eq!(ignored, ["RUSTSEC-2020-0002"]);
+}
+
+/// Advisories without a CVSS score are over any severity threshold, unless
+/// a severity is assumed for them, which is marked as an assumption
+#[test]
+fn unscored_severity() {
+    let db_dir = support::database(&[
+        advisory("foo"),
+        Advisory::new("RUSTSEC-2020-0002", "bar")
+            .attributes("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:L/I:N/A:N\""),
+    ]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"bar\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let audit = |unscored: &str| {
+        let mut config: AuditConfig = toml::from_str(&format!(
+            "[advisories]\nseverity_threshold = \"medium\"\nunscored-severity = \"{}\"\n",
+            unscored
+        ))
+        .unwrap();
+
+        auditor(db_dir.path(), &mut config)
+            .audit(Some(&lockfile_path))
+            .unwrap()
+    };
+
+    let report = audit("unknown");
+    assert_eq!(report.vulnerabilities.count, 2);
+    let foo = &report.vulnerabilities.list[0];
+    assert_eq!(foo.package.name.as_str(), "foo");
+    assert_eq!(foo.effective_severity, None);
+    assert!(!foo.is_severity_assumed());
+
+    let bar = &report.vulnerabilities.list[1];
+    assert_eq!(bar.severity, Some(advisory::Severity::Medium));
+    assert_eq!(bar.effective_severity, Some(advisory::Severity::Medium));
+
+    let report = audit("high");
+    assert_eq!(report.vulnerabilities.count, 2);
+    let foo = &report.vulnerabilities.list[0];
+    assert_eq!(foo.effective_severity, Some(advisory::Severity::High));
+    assert!(foo.is_severity_assumed());
+
+    let json = serde_json::to_value(foo).unwrap();
+    assert!(json["severity"].is_null());
+    assert_eq!(json["effective_severity"], "high");
+
+    let report = audit("low");
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert_eq!(report.vulnerabilities.list[0].package.name.as_str(), "bar");
+
+    assert!(
+        toml::from_str::<AuditConfig>("[advisories]\nunscored-severity = \"severe\"\n").is_err()
+    );
+}
@@ -0,0 +1,86 @@
+//! `cargo audit fix` backup tests
+
+use cargo_audit::{
+    backup::{self, Backup},
+    lockfile,
+};
+use std::{fs, path::Path};
+
+/// Write a package without dependencies
+fn write_package(dir: &Path) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src").join("lib.rs"), "").unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[workspace]\n",
+    )
+    .unwrap();
+}
+
+/// Backups restore modified files, remove created ones, and the latest one
+/// is found by its timestamp
+#[test]
+fn create_and_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path());
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    let lockfile_path = dir.path().join("Cargo.lock");
+    let backups_dir = backup::backups_dir(&manifest_path).unwrap();
+    assert!(backups_dir.ends_with("target/audit-fix-backup"));
+    assert!(Backup::latest(&backups_dir).unwrap().is_none());
+
+    let older = Backup::create(&backups_dir, std::slice::from_ref(&manifest_path)).unwrap();
+    let backup = Backup::create(
+        &backups_dir,
+        &[
+            manifest_path.clone(),
+            lockfile_path.clone(),
+            manifest_path.clone(),
+        ],
+    )
+    .unwrap();
+    assert_eq!(backup.files().len(), 2);
+    assert!(backup.files()[0]
+        .copy
+        .as_ref()
+        .unwrap()
+        .ends_with(".audit-bak"));
+    assert!(backup.files()[1].copy.is_none());
+    assert_ne!(older.dir(), backup.dir());
+    assert_eq!(
+        backup::find_backups_dir(&manifest_path).unwrap(),
+        Some(backups_dir.clone())
+    );
+
+    let original = fs::read_to_string(&manifest_path).unwrap();
+    fs::write(&manifest_path, "[package]\n").unwrap();
+    fs::write(&lockfile_path, "garbage").unwrap();
+
+    let latest = Backup::latest(&backups_dir).unwrap().unwrap();
+    assert_eq!(latest.dir(), backup.dir());
+    latest.restore().unwrap();
+    assert_eq!(fs::read_to_string(&manifest_path).unwrap(), original);
+    assert!(!lockfile_path.exists());
+
+    backup.remove().unwrap();
+    assert_eq!(
+        Backup::latest(&backups_dir).unwrap().unwrap().dir(),
+        older.dir()
+    );
+}
+
+/// Verification fails if the lockfile doesn't parse
+#[test]
+fn verify_workspace() {
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path());
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    let lockfile_path = dir.path().join("Cargo.lock");
+    lockfile::generate(&manifest_path).unwrap();
+    lockfile::verify(&manifest_path, &lockfile_path).unwrap();
+
+    fs::write(&lockfile_path, "[[package]]\nname = 1\n").unwrap();
+    assert!(lockfile::verify(&manifest_path, &lockfile_path).is_err());
+}
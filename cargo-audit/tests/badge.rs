@@ -0,0 +1,138 @@
+//! Badge output tests
+
+mod support;
+
+use cargo_audit::badge::Badge;
+use std::{fs, path::Path, process::Command};
+use support::Advisory;
+
+/// Project depending on the given packages, with a vulnerability advisory
+/// for `foo` and an unmaintained advisory for `bar`
+struct Project {
+    db_dir: tempfile::TempDir,
+    project_dir: tempfile::TempDir,
+}
+
+impl Project {
+    fn new(dependencies: &[&str]) -> Self {
+        let db_dir = support::database(&[
+            Advisory::new("RUSTSEC-2020-0001", "foo"),
+            Advisory::new("RUSTSEC-2020-0002", "bar")
+                .attributes("informational = \"unmaintained\""),
+        ]);
+
+        let project_dir = tempfile::tempdir().unwrap();
+        let mut lockfile = String::new();
+
+        for package in dependencies {
+            lockfile.push_str(&format!(
+                "[[package]]\n\
+                 name = \"{}\"\n\
+                 version = \"0.1.0\"\n\
+                 source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+                package
+            ));
+        }
+
+        fs::write(project_dir.path().join("Cargo.lock"), lockfile).unwrap();
+
+        Self {
+            db_dir,
+            project_dir,
+        }
+    }
+
+    /// Path to the project
+    fn path(&self) -> &Path {
+        self.project_dir.path()
+    }
+
+    /// Run `cargo audit` with the given arguments
+    fn audit(&self, args: &[&str]) -> std::process::Output {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .current_dir(self.path())
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(self.db_dir.path())
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    }
+
+    /// Badge printed for the project with the given arguments
+    fn badge(&self, args: &[&str]) -> Badge {
+        let output = self.audit(&[&["--format", "badge"], args].concat());
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+}
+
+/// Vulnerabilities and denied warnings turn the badge red, other warnings
+/// yellow, and clean audits green
+#[test]
+fn badge_colors() {
+    let badge = Project::new(&["bar", "foo"]).badge(&[]);
+    assert_eq!(badge.schema_version, 1);
+    assert_eq!(badge.label, "cargo audit");
+    assert_eq!(badge.message, "1 vulnerability");
+    assert_eq!(badge.color, "red");
+
+    let project = Project::new(&["bar"]);
+    let badge = project.badge(&[]);
+    assert_eq!(badge.message, "1 warning");
+    assert_eq!(badge.color, "yellow");
+
+    let badge = project.badge(&["--deny", "warnings"]);
+    assert_eq!(badge.message, "1 warning");
+    assert_eq!(badge.color, "red");
+
+    let badge = Project::new(&[]).badge(&[]);
+    assert_eq!(badge.message, "no known issues");
+    assert_eq!(badge.color, "green");
+}
+
+/// Labels and colors can be configured, and warnings ignored
+#[test]
+fn configured_badge() {
+    let project = Project::new(&["bar"]);
+    let config_dir = project.path().join(".cargo");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("audit.toml"),
+        "[output.badge]\n\
+         label = \"security\"\n\
+         warnings = false\n\
+         clean-color = \"brightgreen\"\n",
+    )
+    .unwrap();
+
+    let badge = project.badge(&[]);
+    assert_eq!(badge.label, "security");
+    assert_eq!(badge.message, "no known issues");
+    assert_eq!(badge.color, "brightgreen");
+}
+
+/// `--output-file` writes the badge to a file, or with terminal reports, the
+/// JSON report alongside them
+#[test]
+fn output_file() {
+    let project = Project::new(&["foo"]);
+    let badge_path = project.path().join("badge.json");
+
+    let output = project.audit(&["--format", "badge", "--output-file", "badge.json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+
+    let badge: Badge = serde_json::from_str(&fs::read_to_string(&badge_path).unwrap()).unwrap();
+    assert_eq!(badge.message, "1 vulnerability");
+
+    let output = project.audit(&["--output-file", "report.json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Crate:"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(project.path().join("report.json")).unwrap())
+            .unwrap();
+    assert_eq!(report["schema_version"], rustsec::report::SCHEMA_VERSION);
+    assert_eq!(report["vulnerabilities"]["count"], 1);
+}
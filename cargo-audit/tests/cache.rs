@@ -0,0 +1,69 @@
+//! Report cache tests
+
+use cargo_audit::{
+    cache::{Cache, Entry, Key},
+    config::AuditConfig,
+};
+use rustsec::{lockfile::Lockfile, report, Database, Report};
+use std::fs;
+
+const LOCKFILE_PATH: &str = "tests/support/base64_vuln/Cargo.lock";
+
+/// Ensure cached reports are only replayed while their key matches
+#[test]
+fn cache_get_insert_clear() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let database = Database::open(db_dir.path()).unwrap();
+
+    let lockfile_toml = fs::read_to_string(LOCKFILE_PATH).unwrap();
+    let lockfile: Lockfile = lockfile_toml.parse().unwrap();
+    let report = Report::generate(&database, &lockfile, &report::Settings::default());
+
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(cache_dir.path().join("audit-cache"));
+    let lockfile_path = LOCKFILE_PATH.as_ref();
+
+    let mut config = AuditConfig::default();
+    let key = Key::new(&lockfile_toml, "abc123", &config);
+    assert!(cache.get(lockfile_path, &key).is_none());
+
+    let entry = Entry {
+        key: key.clone(),
+        report,
+        self_advisories: vec![],
+    };
+    cache.insert(lockfile_path, &entry).unwrap();
+    let cached = cache.get(lockfile_path, &key).unwrap();
+    assert_eq!(cached.key, key);
+
+    // Changing the lockfile, database commit, or report settings invalidates the entry
+    let modified_lockfile = Key::new(&format!("{}\n", lockfile_toml), "abc123", &config);
+    assert!(cache.get(lockfile_path, &modified_lockfile).is_none());
+    assert!(cache
+        .get(lockfile_path, &Key::new(&lockfile_toml, "def456", &config))
+        .is_none());
+
+    config.advisories.ignore.push(
+        "RUSTSEC-2017-0004"
+            .parse::<rustsec::advisory::Id>()
+            .unwrap()
+            .into(),
+    );
+    assert!(cache
+        .get(lockfile_path, &Key::new(&lockfile_toml, "abc123", &config))
+        .is_none());
+
+    // Options which don't affect the report (e.g. the output format) don't
+    let mut output_config = AuditConfig::default();
+    output_config.output.quiet = true;
+    assert!(cache
+        .get(
+            lockfile_path,
+            &Key::new(&lockfile_toml, "abc123", &output_config)
+        )
+        .is_some());
+
+    assert_eq!(cache.clear().unwrap(), 1);
+    assert!(cache.get(lockfile_path, &key).is_none());
+    assert_eq!(cache.clear().unwrap(), 0);
+}
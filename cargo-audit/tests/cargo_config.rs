@@ -0,0 +1,151 @@
+//! Tests for resolving cargo's source replacement of crates.io
+
+use cargo_audit::cargo_config::{CargoConfig, ReplacementIndex};
+use std::{fs, path::Path};
+
+/// Write a cargo configuration file to the `.cargo` directory of `dir`
+fn write_config(dir: &Path, name: &str, contents: &str) {
+    let cargo_dir = dir.join(".cargo");
+    fs::create_dir_all(&cargo_dir).unwrap();
+    fs::write(cargo_dir.join(name), contents).unwrap();
+}
+
+#[test]
+fn no_replacement() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = CargoConfig::load_from(dir.path(), None).unwrap();
+    assert!(config.files().is_empty());
+    assert_eq!(config.crates_io_replacement().unwrap(), None);
+
+    write_config(
+        dir.path(),
+        "config.toml",
+        "[source.crates-io]\nreplace-with = \"crates-io\"\n",
+    );
+    let config = CargoConfig::load_from(dir.path(), None).unwrap();
+    assert_eq!(config.files().len(), 1);
+    assert!(config.crates_io_replacement().is_err());
+}
+
+#[test]
+fn replacement_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+
+    // The nearest configuration takes precedence over its parents'
+    write_config(
+        dir.path(),
+        "config.toml",
+        "[source.crates-io]\n\
+         replace-with = \"vendored\"\n\n\
+         [source.vendored]\n\
+         directory = \"vendor\"\n\n\
+         [source.mirror]\n\
+         registry = \"https://git.example.com/crates.io-index\"\n",
+    );
+    write_config(
+        &project,
+        "config",
+        "[source.crates-io]\nreplace-with = \"internal\"\n\n\
+         [source.internal]\nreplace-with = \"mirror\"\n",
+    );
+
+    let config = CargoConfig::load_from(&project, None).unwrap();
+    assert_eq!(config.files().len(), 2);
+    assert!(config.files()[0].starts_with(&project));
+
+    let replacement = config.crates_io_replacement().unwrap().unwrap();
+    assert_eq!(replacement.source, "mirror");
+    assert_eq!(
+        replacement.index,
+        ReplacementIndex::Git("https://git.example.com/crates.io-index".to_owned())
+    );
+    assert_eq!(replacement.token, None);
+
+    let config = CargoConfig::load_from(dir.path(), None).unwrap();
+    let replacement = config.crates_io_replacement().unwrap().unwrap();
+    assert_eq!(replacement.index, ReplacementIndex::Local("vendor".into()));
+}
+
+#[test]
+fn sparse_replacement_token() {
+    let dir = tempfile::tempdir().unwrap();
+    let cargo_home = dir.path().join("cargo-home");
+    fs::create_dir_all(&cargo_home).unwrap();
+
+    write_config(
+        dir.path(),
+        "config.toml",
+        "[source.crates-io]\n\
+         replace-with = \"audit-test-internal\"\n\n\
+         [registries.audit-test-internal]\n\
+         index = \"sparse+https://crates.example.com/index/\"\n",
+    );
+    fs::write(
+        cargo_home.join("credentials.toml"),
+        "[registries.audit-test-internal]\ntoken = \"secret\"\n",
+    )
+    .unwrap();
+
+    let config = CargoConfig::load_from(dir.path(), Some(&cargo_home)).unwrap();
+    let replacement = config.crates_io_replacement().unwrap().unwrap();
+    assert_eq!(
+        replacement.index,
+        ReplacementIndex::Sparse("https://crates.example.com/index/".to_owned())
+    );
+    assert_eq!(replacement.token.as_ref().unwrap().as_str(), "secret");
+    assert!(!format!("{:?}", replacement).contains("secret"));
+}
+
+#[test]
+fn circular_replacement() {
+    let dir = tempfile::tempdir().unwrap();
+    write_config(
+        dir.path(),
+        "config.toml",
+        "[source.crates-io]\nreplace-with = \"a\"\n\n\
+         [source.a]\nreplace-with = \"b\"\n\n\
+         [source.b]\nreplace-with = \"a\"\n",
+    );
+
+    let config = CargoConfig::load_from(dir.path(), None).unwrap();
+    let err = config.crates_io_replacement().unwrap_err();
+    assert!(err.to_string().contains("circular"));
+}
+
+#[test]
+fn target_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let subdir = dir.path().join("crates").join("app");
+    let cargo_home = dir.path().join("home").join(".cargo");
+    fs::create_dir_all(&subdir).unwrap();
+    fs::create_dir_all(&cargo_home).unwrap();
+
+    let config = CargoConfig::load_from(&subdir, Some(&cargo_home)).unwrap();
+    assert_eq!(config.target_dir(), None);
+
+    // Relative to the directory containing the `.cargo` directory
+    fs::write(
+        cargo_home.join("config.toml"),
+        "[build]\ntarget-dir = \"shared\"\n",
+    )
+    .unwrap();
+    let config = CargoConfig::load_from(&subdir, Some(&cargo_home)).unwrap();
+    assert_eq!(
+        config.target_dir(),
+        Some(dir.path().join("home").join("shared").as_path())
+    );
+
+    // The nearest file takes precedence
+    write_config(
+        dir.path(),
+        "config.toml",
+        "[build]\ntarget-dir = \"build/target\"\n",
+    );
+    let config = CargoConfig::load_from(&subdir, Some(&cargo_home)).unwrap();
+    assert_eq!(
+        config.target_dir(),
+        Some(dir.path().join("build/target").as_path())
+    );
+}
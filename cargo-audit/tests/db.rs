@@ -0,0 +1,122 @@
+//! `cargo audit db` tests
+
+mod support;
+
+use std::{path::Path, process::Command};
+use support::Advisory;
+
+/// Advisories in the test database
+fn advisories() -> Vec<Advisory> {
+    vec![
+        Advisory::new("RUSTSEC-2019-0001", "foo")
+            .date("2019-06-01")
+            .attributes(
+                "categories = [\"memory-corruption\"]\n\
+                 cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H\"",
+            ),
+        Advisory::new("RUSTSEC-2020-0001", "bar")
+            .date("2020-06-01")
+            .attributes("informational = \"unmaintained\""),
+        Advisory::new("RUSTSEC-2020-0002", "baz")
+            .date("2020-06-01")
+            .attributes("withdrawn = \"2020-07-01\""),
+        Advisory::new("CVE-2021-0001", "std")
+            .collection("rust")
+            .date("2021-06-01"),
+    ]
+}
+
+/// Run `cargo audit db stats` against the given database
+fn stats(db_path: &Path, args: &[&str]) -> String {
+    run_stats(&["db", "stats"], db_path, args)
+}
+
+/// Run the given stats subcommand against the given database
+fn run_stats(subcommand: &[&str], db_path: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--color", "never"])
+        .args(subcommand)
+        .arg("--db-path")
+        .arg(db_path)
+        .args(args)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Statistics are computed from the database at `--db-path`, without a
+/// lockfile
+#[test]
+fn json_stats() {
+    let db_dir = support::database(&advisories());
+
+    let stats: serde_json::Value =
+        serde_json::from_str(&stats(db_dir.path(), &["--format", "json"])).unwrap();
+
+    assert_eq!(stats["advisories"], 4);
+    assert_eq!(
+        stats["collections"],
+        serde_json::json!({"crates": 3, "rust": 1})
+    );
+    assert_eq!(
+        stats["years"],
+        serde_json::json!({"2019": 1, "2020": 2, "2021": 1})
+    );
+    assert_eq!(
+        stats["informational"],
+        serde_json::json!({"unmaintained": 1})
+    );
+    assert_eq!(
+        stats["categories"],
+        serde_json::json!({"memory-corruption": 1})
+    );
+    assert_eq!(stats["with_cvss"], 1);
+    assert_eq!(stats["withdrawn"], 1);
+    assert_eq!(stats["newest"], "2021-06-01");
+    assert!(stats["commit"].is_null());
+    assert!(stats["last_updated"].is_null());
+}
+
+/// Terminal statistics are shown as tables
+#[test]
+fn terminal_stats() {
+    let db_dir = support::database(&advisories());
+
+    let stdout = stats(db_dir.path(), &[]);
+    assert!(
+        stdout.contains("Advisories               4\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Year                     Advisories\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("2020                     2\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("unmaintained             1\n"),
+        "{}",
+        stdout
+    );
+}
+
+/// `cargo audit stats` is short for `cargo audit db stats`
+#[test]
+fn stats_subcommand() {
+    let db_dir = support::database(&advisories());
+
+    let stdout = run_stats(&["stats"], db_dir.path(), &[]);
+    assert_eq!(stdout, stats(db_dir.path(), &[]));
+    assert!(
+        stdout.contains("Last updated             unknown\n"),
+        "{}",
+        stdout
+    );
+}
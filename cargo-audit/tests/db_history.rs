@@ -0,0 +1,259 @@
+//! Tests for looking up when advisories were first added to the database
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Lockfile depending on `foo` and `bar`
+const LOCKFILE: &str = "\
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Advisory with the given ID for versions of `package` prior to 0.2.0
+fn advisory(id: &str, package: &str) -> String {
+    Advisory::new(id, package).date("2021-01-01").markdown()
+}
+
+/// Commit the given files to the repository at the given time (in seconds
+/// since the Unix epoch), removing the given paths
+fn commit(repo: &git2::Repository, time: i64, files: &[(&str, String)], removed: &[&str]) {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+
+    for (name, contents) in files {
+        let path = workdir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        index.add_path(Path::new(name)).unwrap();
+    }
+
+    for name in removed {
+        fs::remove_file(workdir.join(name)).unwrap();
+        index.remove_path(Path::new(name)).unwrap();
+    }
+
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature =
+        git2::Signature::new("Test", "test@example.com", &git2::Time::new(time, 0)).unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test commit",
+        &tree,
+        parent.iter().collect::<Vec<_>>().as_slice(),
+    )
+    .unwrap();
+}
+
+/// Audit the lockfile with the database at the given path, with the given
+/// arguments (e.g. to look up when the advisories found were first published)
+fn audit(db_path: &Path, args: &[&str]) -> Output {
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .arg("--json")
+        .args(args)
+        .current_dir(project_dir.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Dates the advisories of the vulnerabilities were first published, by
+/// package
+fn first_published(report: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+    report["vulnerabilities"]["list"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|vuln| {
+            (
+                vuln["package"]["name"].as_str().unwrap().to_owned(),
+                vuln["first_published_in_db"].clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn first_published_in_history() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(db_dir.path()).unwrap();
+
+    // 2021-01-05, then the advisory is moved on 2021-06-01 (which doesn't
+    // change when it was first published), and another added on 2022-03-01
+    commit(
+        &repo,
+        1_609_848_000,
+        &[(
+            "crates/foo/RUSTSEC-2021-0001.toml",
+            advisory("RUSTSEC-2021-0001", "foo"),
+        )],
+        &[],
+    );
+    commit(
+        &repo,
+        1_622_548_800,
+        &[(
+            "crates/foo/RUSTSEC-2021-0001.md",
+            advisory("RUSTSEC-2021-0001", "foo"),
+        )],
+        &["crates/foo/RUSTSEC-2021-0001.toml"],
+    );
+    commit(
+        &repo,
+        1_646_136_000,
+        &[(
+            "crates/bar/RUSTSEC-2022-0001.md",
+            advisory("RUSTSEC-2022-0001", "bar"),
+        )],
+        &[],
+    );
+
+    let output = audit(db_dir.path(), &["--first-published"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        first_published(&report),
+        [
+            ("foo".to_owned(), serde_json::json!("2021-01-05")),
+            ("bar".to_owned(), serde_json::json!("2022-03-01")),
+        ]
+    );
+    assert!(report["database"]["first-published-note"].is_null());
+}
+
+#[test]
+fn no_history() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let advisory_dir = db_dir.path().join("crates").join("foo");
+    fs::create_dir_all(&advisory_dir).unwrap();
+    fs::write(
+        advisory_dir.join("RUSTSEC-2021-0001.md"),
+        advisory("RUSTSEC-2021-0001", "foo"),
+    )
+    .unwrap();
+
+    let output = audit(db_dir.path(), &["--first-published"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        first_published(&report),
+        [("foo".to_owned(), serde_json::Value::Null)]
+    );
+    assert!(report["database"]["first-published-note"]
+        .as_str()
+        .unwrap()
+        .contains("no git history"));
+}
+
+/// Packages with vulnerabilities in the report
+fn vulnerable_packages(output: &Output) -> Vec<String> {
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    report["vulnerabilities"]["list"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|vuln| vuln["package"]["name"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn as_of_commit() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(db_dir.path()).unwrap();
+
+    // `foo` on 2021-01-05 and `bar` on 2022-03-01, then the advisory for
+    // `foo` is changed on 2022-06-01 so 0.1.0 is no longer affected
+    let foo = (
+        "crates/foo/RUSTSEC-2021-0001.md",
+        advisory("RUSTSEC-2021-0001", "foo"),
+    );
+    commit(&repo, 1_609_848_000, std::slice::from_ref(&foo), &[]);
+    commit(
+        &repo,
+        1_646_136_000,
+        &[(
+            "crates/bar/RUSTSEC-2022-0001.md",
+            advisory("RUSTSEC-2022-0001", "bar"),
+        )],
+        &[],
+    );
+    commit(
+        &repo,
+        1_654_084_800,
+        &[(foo.0, foo.1.replace(">= 0.2.0", ">= 0.1.0"))],
+        &[],
+    );
+
+    let output = audit(db_dir.path(), &["--as-of", "2021-12-31"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(vulnerable_packages(&output), ["foo"]);
+
+    // Commits made on the day itself count
+    let output = audit(db_dir.path(), &["--as-of", "2022-03-01"]);
+    assert_eq!(vulnerable_packages(&output), ["foo", "bar"]);
+
+    let output = audit(db_dir.path(), &["--as-of", "2022-12-31"]);
+    assert_eq!(vulnerable_packages(&output), ["bar"]);
+
+    // The database can't be loaded as of a date before its history
+    let output = audit(db_dir.path(), &["--as-of", "2020-12-31"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("no commits on or before 2020-12-31"),
+        "{:?}",
+        output
+    );
+
+    let output = audit(db_dir.path(), &["--as-of", "yesterday"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
+
+#[test]
+fn as_of_without_history() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let advisory_dir = db_dir.path().join("crates").join("foo");
+    fs::create_dir_all(&advisory_dir).unwrap();
+    fs::write(
+        advisory_dir.join("RUSTSEC-2021-0001.md"),
+        advisory("RUSTSEC-2021-0001", "foo"),
+    )
+    .unwrap();
+
+    // The advisory was issued on 2021-01-01
+    let output = audit(db_dir.path(), &["--as-of", "2020-12-31"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("has no git history"),
+        "{:?}",
+        output
+    );
+
+    let output = audit(db_dir.path(), &["--as-of", "2021-01-01"]);
+    assert_eq!(vulnerable_packages(&output), ["foo"]);
+}
@@ -0,0 +1,244 @@
+//! `cargo audit diff` tests
+
+mod support;
+
+use cargo_audit::lockfile;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Lockfile depending on vulnerable `foo`
+const LOCKFILE_V1: &str = "\
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Lockfile depending on vulnerable `bar` and `foo`
+const LOCKFILE_V2: &str = "\
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"foo\"
+version = \"0.1.1\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Advisory database with advisories for `foo` and `bar`
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2021-0001", "foo").date("2021-01-01"),
+        Advisory::new("RUSTSEC-2021-0002", "bar").date("2021-01-01"),
+    ])
+}
+
+/// Commit the given files to the repository
+fn commit(repo: &git2::Repository, files: &[(&str, &str)]) {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+
+    for (name, contents) in files {
+        fs::write(workdir.join(name), contents).unwrap();
+        index.add_path(Path::new(name)).unwrap();
+    }
+
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test commit",
+        &tree,
+        parent.iter().collect::<Vec<_>>().as_slice(),
+    )
+    .unwrap();
+}
+
+/// Create a repository whose first commit has no `Cargo.lock`, followed by
+/// commits adding the two versions of it
+fn repository(path: &Path) -> git2::Repository {
+    let repo = git2::Repository::init(path).unwrap();
+    commit(&repo, &[("README.md", "test\n")]);
+    commit(&repo, &[("Cargo.lock", LOCKFILE_V1)]);
+    commit(&repo, &[("Cargo.lock", LOCKFILE_V2)]);
+    repo
+}
+
+/// Run `cargo audit diff` in the given directory
+fn diff(dir: &Path, db_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--db"])
+        .arg(db_path)
+        .arg("diff")
+        .args(args)
+        .current_dir(dir)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// IDs of the vulnerabilities in the given section of a JSON diff
+fn ids(diff: &serde_json::Value, section: &str) -> Vec<String> {
+    diff[section]["vulnerabilities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|vuln| vuln["advisory"]["id"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn read_revision() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    repository(repo_dir.path());
+    let lockfile_path = repo_dir.path().join("Cargo.lock");
+
+    assert_eq!(
+        lockfile::read_revision(&lockfile_path, "HEAD~1").unwrap(),
+        LOCKFILE_V1
+    );
+    assert_eq!(
+        lockfile::read_revision(&lockfile_path, "HEAD").unwrap(),
+        LOCKFILE_V2
+    );
+
+    let error = lockfile::read_revision(&lockfile_path, "HEAD~2").unwrap_err();
+    assert!(error.to_string().contains("has no Cargo.lock"), "{}", error);
+
+    assert!(lockfile::read_revision(&lockfile_path, "nonexistent").is_err());
+}
+
+/// Findings are compared between git revisions, failing only if any were
+/// introduced
+#[test]
+fn diff_revisions() {
+    let db_dir = database();
+    let repo_dir = tempfile::tempdir().unwrap();
+    repository(repo_dir.path());
+
+    let output = diff(
+        repo_dir.path(),
+        db_dir.path(),
+        &["--from", "HEAD~1", "--to", "HEAD", "--json"],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(ids(&json, "introduced"), ["RUSTSEC-2021-0002"]);
+    assert!(ids(&json, "resolved").is_empty());
+    assert_eq!(ids(&json, "unchanged"), ["RUSTSEC-2021-0001"]);
+
+    let output = diff(
+        repo_dir.path(),
+        db_dir.path(),
+        &["--from", "HEAD", "--to", "HEAD~1", "--json"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(ids(&json, "introduced").is_empty());
+    assert_eq!(ids(&json, "resolved"), ["RUSTSEC-2021-0002"]);
+
+    let output = diff(
+        repo_dir.path(),
+        db_dir.path(),
+        &["--from", "HEAD", "--to", "HEAD~1", "--fail-on", "any"],
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("0 introduced, 1 resolved, 1 still present"));
+}
+
+/// Revisions without a lockfile are an error
+#[test]
+fn diff_revision_without_lockfile() {
+    let db_dir = database();
+    let repo_dir = tempfile::tempdir().unwrap();
+    repository(repo_dir.path());
+
+    let output = diff(repo_dir.path(), db_dir.path(), &["--from", "HEAD~2"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("has no Cargo.lock"), "{}", stderr);
+}
+
+/// Lockfiles can be compared directly, defaulting to the current `Cargo.lock`
+#[test]
+fn diff_files() {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("old.lock"), LOCKFILE_V2).unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE_V1).unwrap();
+
+    let output = diff(
+        project_dir.path(),
+        db_dir.path(),
+        &["--from", "old.lock", "--json"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(ids(&json, "resolved"), ["RUSTSEC-2021-0002"]);
+    assert_eq!(ids(&json, "unchanged"), ["RUSTSEC-2021-0001"]);
+}
+
+#[test]
+fn diff_reports() {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+
+    for (name, lockfile) in &[("old", LOCKFILE_V1), ("new", LOCKFILE_V2)] {
+        let lockfile_path = project_dir.path().join(format!("{}.lock", name));
+        fs::write(&lockfile_path, lockfile).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--json", "--db"])
+            .arg(db_dir.path())
+            .arg("--file")
+            .arg(&lockfile_path)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+        fs::write(
+            project_dir.path().join(format!("{}.json", name)),
+            output.stdout,
+        )
+        .unwrap();
+    }
+
+    // The advisory database isn't needed to compare reports
+    let output = diff(
+        project_dir.path(),
+        &db_dir.path().join("missing"),
+        &["old.json", "new.json", "--json"],
+    );
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(ids(&json, "introduced"), ["RUSTSEC-2021-0002"]);
+    assert_eq!(ids(&json, "unchanged"), ["RUSTSEC-2021-0001"]);
+    assert!(ids(&json, "resolved").is_empty());
+
+    let output = diff(
+        project_dir.path(),
+        db_dir.path(),
+        &["old.json", "new.json", "--from", "old.lock"],
+    );
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
@@ -0,0 +1,208 @@
+//! EPSS score enrichment tests
+
+mod support;
+
+use cargo_audit::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    epss::{self, Client},
+};
+use rustsec::{advisory, vulnerability::Epss, Report};
+use std::{
+    fs,
+    io::{Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use support::Advisory;
+
+/// Response from the EPSS API for `CVE-2021-0001` (`CVE-2021-0002` has no score)
+const RESPONSE: &str = r#"{"status":"OK","status-code":200,"total":1,"data":[{"cve":"CVE-2021-0001","epss":"0.873210000","percentile":"0.975300000","date":"2021-06-01"}]}"#;
+
+/// Serve the given response to a single request on a local port, returning
+/// the URL to request and the request line received
+fn serve_once(body: &'static str) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/epss", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 4096];
+        let len = stream.read(&mut request).unwrap();
+        let request = String::from_utf8_lossy(&request[..len]).into_owned();
+        sender
+            .send(request.lines().next().unwrap_or_default().to_owned())
+            .unwrap();
+
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+    });
+
+    (url, receiver)
+}
+
+fn cves() -> Vec<advisory::Id> {
+    vec![
+        "CVE-2021-0001".parse().unwrap(),
+        "CVE-2021-0002".parse().unwrap(),
+    ]
+}
+
+/// Scores are fetched in a single request, then served from the cache
+/// (including CVEs without a score)
+#[test]
+fn fetch_and_cache_scores() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (url, requests) = serve_once(RESPONSE);
+    let client = Client::new(
+        url,
+        cache_dir.path().join(epss::CACHE_FILE),
+        Duration::from_secs(3600),
+    );
+
+    let (scores, error) = client.scores(&cves());
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(scores.len(), 1);
+
+    let score = &scores[&cves()[0]];
+    assert_eq!(score.score, 0.87321);
+    assert_eq!(score.percentile, 0.9753);
+    assert_eq!(score.date, "2021-06-01");
+
+    let request = requests.recv().unwrap();
+    assert!(
+        request.contains("cve=CVE-2021-0001%2CCVE-2021-0002"),
+        "{}",
+        request
+    );
+
+    // The server only handles one request, so these must come from the cache
+    let (cached_scores, error) = client.scores(&cves());
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(cached_scores, scores);
+}
+
+/// Failing to reach the API returns an error along with any cached scores
+#[test]
+fn unreachable_api() {
+    let cache_dir = tempfile::tempdir().unwrap();
+
+    // Nothing listens on a port once its listener is dropped
+    let url = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/epss", listener.local_addr().unwrap())
+    };
+
+    let client = Client::new(
+        url,
+        cache_dir.path().join(epss::CACHE_FILE),
+        Duration::from_secs(3600),
+    );
+
+    let (scores, error) = client.scores(&cves());
+    assert!(scores.is_empty());
+    assert!(error.is_some());
+}
+
+#[test]
+fn display_score() {
+    let score = |score, percentile| Epss {
+        cve: "CVE-2021-0001".parse().unwrap(),
+        score,
+        percentile,
+        date: "2021-06-01".to_owned(),
+    };
+
+    assert_eq!(
+        epss::display(&score(0.87321, 0.9753)),
+        "0.87 (97th percentile)"
+    );
+    assert_eq!(
+        epss::display(&score(0.00043, 0.1123)),
+        "0.0004 (11th percentile)"
+    );
+    assert_eq!(epss::display(&score(0.5, 0.92)), "0.50 (92nd percentile)");
+}
+
+/// Audit a lockfile with a vulnerable dependency whose advisory is an alias
+/// of `CVE-2021-0001`, with EPSS scores fetched from the given URL (and only
+/// vulnerabilities scored at least the given threshold reported)
+fn audit_with_epss(url: String, offline: bool, threshold: Option<f64>) -> Report {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2021-0001", "foo")
+        .date("2021-01-01")
+        .attributes("aliases = [\"CVE-2021-0001\"]")]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let mut config = AuditConfig::default();
+    config.database.path = Some(db_dir.path().to_owned());
+    config.database.fetch = false;
+    config.cache.path = Some(project_dir.path().join("cache"));
+    config.epss.enabled = true;
+    config.epss.url = Some(url);
+    config.epss.threshold = threshold;
+    config.net.offline = offline;
+    config.output.quiet = true;
+    config.output.format = OutputFormat::Json;
+    config.yanked.enabled = false;
+    config.registry.lookup = false;
+
+    Auditor::new(&config).audit(Some(&lockfile_path)).unwrap()
+}
+
+/// Vulnerabilities are annotated with the scores of their CVE aliases
+#[test]
+fn audit_adds_scores() {
+    let (url, _requests) = serve_once(RESPONSE);
+    let report = audit_with_epss(url, false, None);
+
+    let score = report.vulnerabilities.list[0].epss.as_ref().unwrap();
+    assert_eq!(score.cve.as_str(), "CVE-2021-0001");
+}
+
+/// Audits succeed without scores if the API can't be reached, or in offline mode
+#[test]
+fn audit_without_scores() {
+    let (url, requests) = serve_once(RESPONSE);
+    let report = audit_with_epss(url, true, None);
+
+    assert!(report.vulnerabilities.list[0].epss.is_none());
+    assert!(requests.try_recv().is_err());
+
+    let report = audit_with_epss("http://127.0.0.1:1/epss".to_owned(), false, None);
+    assert!(report.vulnerabilities.list[0].epss.is_none());
+}
+
+/// Vulnerabilities scored below the threshold aren't reported, unless their
+/// scores can't be fetched
+#[test]
+fn audit_with_threshold() {
+    let (url, _requests) = serve_once(RESPONSE);
+    let report = audit_with_epss(url, false, Some(0.5));
+    assert_eq!(report.vulnerabilities.count, 1);
+
+    let (url, _requests) = serve_once(RESPONSE);
+    let report = audit_with_epss(url, false, Some(0.9));
+    assert_eq!(report.vulnerabilities.count, 0);
+    assert!(!report.vulnerabilities.found);
+
+    let report = audit_with_epss("http://127.0.0.1:1/epss".to_owned(), false, Some(0.9));
+    assert_eq!(report.vulnerabilities.count, 1);
+}
@@ -0,0 +1,214 @@
+//! Exit status tests
+
+mod support;
+
+use std::{fs, path::Path, process::Command};
+use support::Advisory;
+
+/// Vulnerability advisory for `foo` and unmaintained advisory for `bar`, for
+/// versions prior to 0.2.0
+fn advisories() -> Vec<Advisory> {
+    vec![
+        Advisory::new("RUSTSEC-2020-0001", "foo"),
+        Advisory::new("RUSTSEC-2020-0002", "bar").attributes("informational = \"unmaintained\""),
+    ]
+}
+
+/// Project with a lockfile depending on the given packages (from crates.io),
+/// whose `audit.toml` contains the given exit codes
+fn write_project(dir: &Path, packages: &[&str], exit_codes: &str) {
+    let mut lockfile = String::new();
+
+    for package in packages {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            package
+        ));
+    }
+
+    fs::write(dir.join("Cargo.lock"), lockfile).unwrap();
+    fs::create_dir_all(dir.join(".cargo")).unwrap();
+    fs::write(
+        dir.join(".cargo").join("audit.toml"),
+        format!("[output.exit-codes]\n{}", exit_codes),
+    )
+    .unwrap();
+}
+
+/// Run `cargo audit` in the given project with the given arguments
+fn audit(project: &Path, db: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&[
+            "audit",
+            "--no-fetch",
+            "--no-cache",
+            "--color",
+            "never",
+            "--db",
+        ])
+        .arg(db)
+        .args(args)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Outcomes exit with their remapped codes, and JSON reports include the
+/// symbolic outcome
+#[test]
+fn remapped_exit_codes() {
+    let db_dir = support::database(&advisories());
+    let exit_codes = "vulnerabilities-found = 10\ndenied-warnings = 11\n";
+
+    let project = tempfile::tempdir().unwrap();
+    write_project(project.path(), &["bar", "foo"], exit_codes);
+    let output = audit(project.path(), db_dir.path(), &["--json"]);
+    assert_eq!(output.status.code(), Some(10));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["outcome"], "vulnerabilities-found");
+
+    let project = tempfile::tempdir().unwrap();
+    write_project(project.path(), &["bar"], exit_codes);
+    let output = audit(project.path(), db_dir.path(), &["--json"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["outcome"], "clean");
+
+    let output = audit(
+        project.path(),
+        db_dir.path(),
+        &["--json", "--deny", "unmaintained"],
+    );
+    assert_eq!(output.status.code(), Some(11));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["outcome"], "denied-warnings");
+
+    // Terminal reports exit with the same codes
+    let output = audit(project.path(), db_dir.path(), &["--deny", "unmaintained"]);
+    assert_eq!(output.status.code(), Some(11));
+}
+
+/// `--help` describes every option and lists the effective exit codes, and
+/// invalid mappings are rejected
+#[test]
+fn help_and_validation() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    write_project(project.path(), &[], "operational-error = 5\n");
+
+    let output = audit(project.path(), db_dir.path(), &["--help"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Descriptions of options too wide for the first column would be moved
+    // onto a line of their own, which is left out
+    for line in stdout.lines() {
+        assert_eq!(line, line.trim_end(), "option without a description");
+    }
+    assert!(stdout.contains("EXIT CODES:"), "{}", stdout);
+    assert!(
+        stdout.contains("    operational-error       5\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("    vulnerabilities-found   1\n"),
+        "{}",
+        stdout
+    );
+
+    write_project(project.path(), &[], "clean = 1\n");
+    let output = audit(project.path(), db_dir.path(), &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_ne!(output.status.code(), Some(0));
+    assert!(
+        stderr.contains("exit code 1 for clean is also used for vulnerabilities-found"),
+        "{}",
+        stderr
+    );
+}
+
+/// Advisories with CVSS scores for versions of `crit` (critical) and `med`
+/// (medium) prior to 0.2.0
+fn scored_advisories() -> Vec<Advisory> {
+    vec![
+        Advisory::new("RUSTSEC-2020-0003", "crit")
+            .attributes("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H\""),
+        Advisory::new("RUSTSEC-2020-0004", "med")
+            .attributes("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:L/I:N/A:N\""),
+    ]
+}
+
+/// Vulnerabilities below `--severity` are reported, but don't fail the audit
+#[test]
+fn severity_threshold() {
+    let db_dir = support::database(&scored_advisories());
+
+    let project = tempfile::tempdir().unwrap();
+    write_project(project.path(), &["med"], "");
+    let output = audit(project.path(), db_dir.path(), &["--severity", "high"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(stdout.contains("RUSTSEC-2020-0004"), "{}", stdout);
+    assert!(
+        stdout.contains("medium (5.3), below the severity threshold"),
+        "{}",
+        stdout
+    );
+
+    let output = audit(
+        project.path(),
+        db_dir.path(),
+        &["--json", "--severity", "medium"],
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["outcome"], "vulnerabilities-found");
+
+    write_project(project.path(), &["crit", "med"], "");
+    let output = audit(project.path(), db_dir.path(), &["--severity", "high"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+/// Failures exit with the status configured for the severity of the most
+/// severe failing vulnerability, or the kind of denied warning
+#[test]
+fn severity_and_warning_exit_codes() {
+    let db_dir = support::database(&[advisories(), scored_advisories()].concat());
+    let exit_codes =
+        "severity = { critical = 20, medium = 21 }\nwarnings = { unmaintained = 12 }\n";
+
+    let project = tempfile::tempdir().unwrap();
+    write_project(project.path(), &["crit", "med"], exit_codes);
+    let output = audit(project.path(), db_dir.path(), &[]);
+    assert_eq!(output.status.code(), Some(20));
+
+    // Only failing vulnerabilities choose the status: below the threshold,
+    // `med` doesn't, so the unscored `foo` exits with the usual status
+    write_project(project.path(), &["foo", "med"], exit_codes);
+    let output = audit(project.path(), db_dir.path(), &["--severity", "high"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    write_project(project.path(), &["med"], exit_codes);
+    let output = audit(project.path(), db_dir.path(), &[]);
+    assert_eq!(output.status.code(), Some(21));
+
+    write_project(project.path(), &["bar"], exit_codes);
+    let output = audit(project.path(), db_dir.path(), &["--deny", "unmaintained"]);
+    assert_eq!(output.status.code(), Some(12));
+
+    write_project(project.path(), &[], "severity = { high = 2 }\n");
+    let output = audit(project.path(), db_dir.path(), &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_ne!(output.status.code(), Some(0));
+    assert!(
+        stderr.contains("exit code 2 for high vulnerabilities is also used for operational-error"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,65 @@
+//! Tests for explaining how packages were matched against an advisory
+
+mod support;
+
+use cargo_audit::{auditor::Auditor, config::AuditConfig};
+use std::{path::Path, process::Command};
+use support::Advisory;
+
+#[test]
+fn explain_terminal() {
+    let db_dir = support::database(&[Advisory::base64()]);
+
+    let explain = |id: &str| {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(&["--explain", id])
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    };
+
+    let output = explain("RUSTSEC-2017-0004");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(stdout.contains("Version:       0.5.1"), "{}", stdout);
+    assert!(stdout.contains("Patched:       >=0.5.2: not matched (range [0.5.2, ∞))"));
+    assert!(stdout.contains("Unaffected:    <0.5.0: not matched (range [0, 0.5.0))"));
+    assert!(stdout.contains("Affected:      [0.5.0, 0.5.2): contains 0.5.1"));
+    assert!(stdout.contains("Pre-releases:  none applied (0.5.1 isn't a pre-release)"));
+    assert!(stdout.contains("Verdict:       vulnerable (0.5.1 is in an affected range)"));
+
+    let output = explain("RUSTSEC-2017-0005");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("no advisory RUSTSEC-2017-0005 in the advisory database"));
+}
+
+#[test]
+fn explain_trace() {
+    let db_dir = support::database(&[Advisory::base64()]);
+
+    let mut config = AuditConfig::default();
+    config.database.path = Some(db_dir.path().to_owned());
+    config.database.fetch = false;
+
+    let mut auditor = Auditor::try_new(&config).unwrap();
+    let explanations = auditor
+        .explain(
+            &"RUSTSEC-2017-0004".parse().unwrap(),
+            Some(Path::new("tests/support/base64_vuln/Cargo.lock")),
+        )
+        .unwrap();
+
+    assert_eq!(explanations.len(), 1);
+    let (package, explanation) = &explanations[0];
+    assert_eq!(package.version, explanation.version);
+    assert!(explanation.vulnerable);
+    assert!(!explanation.patched[0].matched);
+    assert!(!explanation.unaffected[0].matched);
+    assert_eq!(explanation.affected.len(), 1);
+    assert!(explanation.affected[0].contains);
+}
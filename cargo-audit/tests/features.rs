@@ -0,0 +1,353 @@
+//! Feature-aware auditing tests
+
+mod support;
+
+use cargo_audit::{
+    auditor::Auditor,
+    config::{AuditConfig, DenyOption, FeaturesConfig, OutputFormat},
+    features,
+};
+use rustsec::{
+    database::scope,
+    lockfile::Lockfile,
+    report::{Outcome, OverriddenAction},
+    vulnerability::Scope,
+    warning,
+};
+use std::{fs, path::Path};
+use support::Advisory;
+
+/// Lockfile for `app`, which depends on `opt` (optional), `win` (on Windows
+/// only) and `dev` (as a dev-dependency), each of which depend on `leaf`
+const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["dev", "opt", "win"]
+
+[[package]]
+name = "dev"
+version = "0.1.0"
+
+[[package]]
+name = "leaf"
+version = "0.1.0"
+
+[[package]]
+name = "opt"
+version = "0.1.0"
+dependencies = ["leaf"]
+
+[[package]]
+name = "win"
+version = "0.1.0"
+"#;
+
+/// Write a library crate with the given dependencies
+fn write_crate(dir: &Path, name: &str, extra: &str) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src").join("lib.rs"), "").unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n{}",
+            name, extra
+        ),
+    )
+    .unwrap();
+}
+
+/// Write the `app` workspace, returning the path to its `Cargo.toml`.
+///
+/// The dependencies are kept outside the workspace directory, which would
+/// otherwise make them workspace members.
+fn write_workspace(dir: &Path) -> std::path::PathBuf {
+    write_crate(&dir.join("leaf"), "leaf", "");
+    write_crate(
+        &dir.join("opt"),
+        "opt",
+        "[dependencies]\nleaf = { path = \"../leaf\" }\n",
+    );
+    write_crate(&dir.join("win"), "win", "");
+    write_crate(&dir.join("dev"), "dev", "");
+
+    let app_dir = dir.join("app");
+    write_crate(
+        &app_dir,
+        "app",
+        "[workspace]\n\n\
+         [dependencies]\n\
+         opt = { path = \"../opt\", optional = true }\n\n\
+         [target.'cfg(windows)'.dependencies]\n\
+         win = { path = \"../win\" }\n\n\
+         [dev-dependencies]\n\
+         dev = { path = \"../dev\" }\n",
+    );
+    fs::write(app_dir.join("Cargo.lock"), LOCKFILE).unwrap();
+    app_dir.join("Cargo.toml")
+}
+
+/// Prune the lockfile for the given feature selection, returning the names
+/// of the remaining packages and the number pruned
+fn prune(manifest_path: &Path, config: &FeaturesConfig) -> (Vec<String>, usize) {
+    let mut lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let compiled = features::resolve(manifest_path, config, true).unwrap();
+    let pruned = features::prune(&mut lockfile, &compiled);
+
+    let names = lockfile
+        .packages
+        .iter()
+        .map(|package| package.name.as_str().to_owned())
+        .collect();
+
+    (names, pruned)
+}
+
+/// Optional dependencies are only audited if their feature is enabled, while
+/// dependencies for other platforms and dev-dependencies never are
+#[test]
+fn prune_uncompiled_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = write_workspace(dir.path());
+
+    let config = FeaturesConfig {
+        no_default_features: true,
+        ..Default::default()
+    };
+    let (names, pruned) = prune(&manifest_path, &config);
+    assert_eq!(names, ["app"]);
+    assert_eq!(pruned, 4);
+
+    let config = FeaturesConfig {
+        features: vec!["opt".to_owned()],
+        ..Default::default()
+    };
+    let (names, pruned) = prune(&manifest_path, &config);
+    assert_eq!(names, ["app", "leaf", "opt"]);
+    assert_eq!(pruned, 2);
+
+    // Dependencies on pruned packages are removed too, keeping the
+    // dependency graph valid
+    let mut lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let compiled = features::resolve(&manifest_path, &config, true).unwrap();
+    features::prune(&mut lockfile, &compiled);
+    assert_eq!(lockfile.packages[0].dependencies.len(), 1);
+    assert!(lockfile.dependency_tree().is_ok());
+}
+
+/// Packages are optional if they're only compiled with features which
+/// aren't enabled (by default, or with the selection)
+#[test]
+fn optional_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = write_workspace(dir.path());
+
+    let optional = features::optional(&manifest_path, &FeaturesConfig::default(), true).unwrap();
+    let names: Vec<&str> = optional.iter().map(|dep| dep.name.as_str()).collect();
+    assert_eq!(names, ["leaf", "opt"]);
+
+    let config = FeaturesConfig {
+        features: vec!["opt".to_owned()],
+        ..Default::default()
+    };
+    assert!(features::optional(&manifest_path, &config, true)
+        .unwrap()
+        .is_empty());
+}
+
+/// With `feature_aware`, vulnerabilities in optional packages are reported
+/// as `optional` warnings, which only fail the audit if they're denied
+#[test]
+fn optional_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = write_workspace(dir.path());
+
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "leaf")]);
+
+    let mut config = AuditConfig::default();
+    config.database.path = Some(db_dir.path().to_owned());
+    config.database.fetch = false;
+    config.output.quiet = true;
+    config.output.format = OutputFormat::Json;
+    config.yanked.enabled = false;
+    config.registry.lookup = false;
+    config.net.offline = true;
+    config.features.feature_aware = true;
+    // The dependencies are path dependencies, which would otherwise be
+    // skipped, or `overridden`
+    config.packages.source = Some(scope::Registry::All);
+    config.packages.overridden = OverriddenAction::Fail;
+
+    let lockfile_path = manifest_path.with_file_name("Cargo.lock");
+    let report = Auditor::new(&config).audit(Some(&lockfile_path)).unwrap();
+    assert!(!report.vulnerabilities.found);
+    assert_eq!(report.outcome, Some(Outcome::Clean));
+
+    let warnings = &report.warnings[&warning::Kind::Optional];
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].package.name.as_str(), "leaf");
+
+    config.output.deny.push(DenyOption::Optional);
+    let report = Auditor::new(&config).audit(Some(&lockfile_path)).unwrap();
+    assert_eq!(report.outcome, Some(Outcome::DeniedWarnings));
+
+    // Compiled with the selected features, it's a vulnerability again
+    config.features.features.push("opt".to_owned());
+    let report = Auditor::new(&config).audit(Some(&lockfile_path)).unwrap();
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert!(!report.warnings.contains_key(&warning::Kind::Optional));
+}
+
+/// Errors from `cargo metadata` are reported
+#[test]
+fn resolve_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = FeaturesConfig {
+        features: vec!["opt".to_owned()],
+        ..Default::default()
+    };
+
+    let err = features::resolve(&dir.path().join("Cargo.toml"), &config, true).unwrap_err();
+    assert!(err.to_string().contains("cargo metadata"), "{}", err);
+}
+
+/// Packages are classified by the kinds of dependency they're reachable
+/// through, with proc-macros (and everything build dependencies pull in)
+/// counting as build dependencies
+#[test]
+fn dependency_scopes() {
+    let dir = tempfile::tempdir().unwrap();
+    write_crate(&dir.path().join("leaf"), "leaf", "");
+    write_crate(
+        &dir.path().join("opt"),
+        "opt",
+        "[dependencies]\nleaf = { path = \"../leaf\" }\n",
+    );
+    write_crate(
+        &dir.path().join("bld"),
+        "bld",
+        "[dependencies]\nleaf = { path = \"../leaf\" }\n",
+    );
+    write_crate(&dir.path().join("dev"), "dev", "");
+    write_crate(&dir.path().join("pm"), "pm", "[lib]\nproc-macro = true\n");
+
+    let app_dir = dir.path().join("app");
+    write_crate(
+        &app_dir,
+        "app",
+        "[workspace]\n\n\
+         [dependencies]\n\
+         opt = { path = \"../opt\" }\n\
+         pm = { path = \"../pm\" }\n\n\
+         [build-dependencies]\n\
+         bld = { path = \"../bld\" }\n\n\
+         [dev-dependencies]\n\
+         dev = { path = \"../dev\" }\n",
+    );
+    fs::write(
+        app_dir.join("Cargo.lock"),
+        r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["bld", "dev", "opt", "pm"]
+
+[[package]]
+name = "bld"
+version = "0.1.0"
+dependencies = ["leaf"]
+
+[[package]]
+name = "dev"
+version = "0.1.0"
+
+[[package]]
+name = "leaf"
+version = "0.1.0"
+
+[[package]]
+name = "opt"
+version = "0.1.0"
+dependencies = ["leaf"]
+
+[[package]]
+name = "pm"
+version = "0.1.0"
+"#,
+    )
+    .unwrap();
+
+    let scopes = features::scopes(
+        &app_dir.join("Cargo.toml"),
+        &FeaturesConfig::default(),
+        true,
+    )
+    .unwrap();
+
+    let scopes: Vec<(String, Vec<Scope>)> = scopes
+        .into_iter()
+        .map(|(dep, scopes)| (dep.name.as_str().to_owned(), scopes.into_iter().collect()))
+        .collect();
+
+    assert_eq!(
+        scopes,
+        [
+            ("app".to_owned(), vec![Scope::Normal]),
+            ("bld".to_owned(), vec![Scope::Build]),
+            ("dev".to_owned(), vec![Scope::Dev]),
+            ("leaf".to_owned(), vec![Scope::Normal, Scope::Build]),
+            ("opt".to_owned(), vec![Scope::Normal]),
+            ("pm".to_owned(), vec![Scope::Build]),
+        ]
+    );
+}
+
+/// Only workspace members are listed as such, not their path dependencies
+#[test]
+fn workspace_members() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = write_workspace(dir.path());
+
+    let members = features::workspace_members(&manifest_path).unwrap();
+    let names: Vec<&str> = members.iter().map(|member| member.name.as_str()).collect();
+    assert_eq!(names, ["app"]);
+}
+
+/// Dependents are listed with the requirements they declare, and whether
+/// they're workspace members
+#[test]
+fn dependents() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = write_workspace(dir.path());
+
+    let dependents = features::dependents(&manifest_path).unwrap();
+    let dependents_of = |name: &str| {
+        let (_, dependents) = dependents
+            .iter()
+            .find(|(package, _)| package.name.as_str() == name)
+            .unwrap();
+
+        dependents
+            .iter()
+            .map(|dependent| {
+                (
+                    dependent.name.as_str().to_owned(),
+                    dependent.requirement.to_string(),
+                    dependent.member,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert!(dependents_of("app").is_empty());
+    assert_eq!(
+        dependents_of("opt"),
+        [("app".to_owned(), "*".to_owned(), true)]
+    );
+    assert_eq!(
+        dependents_of("leaf"),
+        [("opt".to_owned(), "*".to_owned(), false)]
+    );
+}
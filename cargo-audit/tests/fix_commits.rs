@@ -0,0 +1,92 @@
+//! Tests for auditing git dependencies by the commits which fix advisories
+
+mod support;
+
+use std::{fs, path::Path, process::Command};
+use support::Advisory;
+
+/// Commit a file to the repository, returning the commit's hash
+fn commit(repo: &git2::Repository, contents: &str) -> String {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+    fs::write(workdir.join("lib.rs"), contents).unwrap();
+    index.add_path(Path::new("lib.rs")).unwrap();
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test commit",
+        &tree,
+        parent.iter().collect::<Vec<_>>().as_slice(),
+    )
+    .unwrap()
+    .to_string()
+}
+
+/// Audit a lockfile with `base64` 0.5.0 locked at the given revision of the
+/// repository, returning the JSON report
+fn audit(db_path: &Path, repo_path: &Path, rev: &str) -> serde_json::Value {
+    let project_dir = tempfile::tempdir().unwrap();
+    let project = project_dir.path();
+
+    fs::write(
+        project.join("Cargo.lock"),
+        format!(
+            "version = 3\n\n\
+             [[package]]\n\
+             name = \"base64\"\n\
+             version = \"0.5.0\"\n\
+             source = \"git+file://{}#{}\"\n",
+            repo_path.display(),
+            rev
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--json", "--db"])
+        .arg(db_path)
+        .env("CARGO_HOME", project)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .output()
+        .unwrap();
+
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+/// Git dependencies locked at a revision which predates the fix are
+/// vulnerable, and ones which include it are patched (whatever their version)
+#[test]
+fn audit_git_dependency_by_commit() {
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(repo_dir.path()).unwrap();
+    let vulnerable = commit(&repo, "// vulnerable\n");
+    let fix = commit(&repo, "// fixed\n");
+    let patched = commit(&repo, "// fixed, and more\n");
+
+    let db_dir = support::database(&[Advisory::base64().versions(&format!(
+        "patched = [\">= 0.5.2\"]\npatched_commits = [\"{}\"]",
+        &fix[..12]
+    ))]);
+
+    let report = audit(db_dir.path(), repo_dir.path(), &vulnerable);
+    assert_eq!(report["vulnerabilities"]["count"], 1);
+    assert_eq!(
+        report["vulnerabilities"]["list"][0]["advisory"]["id"],
+        "RUSTSEC-2017-0004"
+    );
+    assert!(report["warnings"].get("overridden").is_none());
+
+    for rev in &[fix, patched] {
+        let report = audit(db_dir.path(), repo_dir.path(), rev);
+        assert_eq!(report["vulnerabilities"]["count"], 0);
+        assert!(report["warnings"].get("overridden").is_none());
+    }
+}
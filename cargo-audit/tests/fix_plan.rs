@@ -0,0 +1,131 @@
+//! Remediation plan tests
+
+mod support;
+
+use cargo_audit::fix_plan;
+use rustsec::{lockfile::Lockfile, report, Database, Report};
+use std::process::Command;
+use support::Advisory;
+
+/// Lockfile for `app`, which depends on `foo`, `qux` and `baz` directly, and
+/// on `bar` via `mid`
+const LOCKFILE: &str = r#"[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["baz", "foo", "mid", "qux"]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "baz"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "mid"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["bar"]
+
+[[package]]
+name = "qux"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+/// Advisory for the package, with the given patched versions
+fn advisory(id: &str, package: &str, patched: &str) -> Advisory {
+    Advisory::new(id, package).versions(&format!("patched = [{}]", patched))
+}
+
+/// Actions are derived from the patched ranges and the dependency graph
+#[test]
+fn plan_actions() {
+    let advisories = [
+        advisory("RUSTSEC-2020-0001", "foo", "\">= 1.0.0\""),
+        advisory("RUSTSEC-2020-0002", "bar", "\">= 2.0.0\""),
+        advisory("RUSTSEC-2020-0003", "baz", ""),
+        advisory("RUSTSEC-2020-0004", "qux", "\">= 0.1.2\""),
+        advisory("RUSTSEC-2020-0005", "qux", "\">= 0.1.1\""),
+    ];
+    let advisories: Vec<String> = advisories.iter().map(Advisory::markdown).collect();
+    let advisories: Vec<&str> = advisories.iter().map(String::as_str).collect();
+    let database = Database::from_markdown(&advisories).unwrap();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let tree = lockfile.dependency_tree().unwrap();
+    let report = Report::generate(&database, &lockfile, &report::Settings::default());
+
+    let actions = serde_json::to_value(fix_plan::plan(&report, Some(&tree))).unwrap();
+    let actions = actions.as_array().unwrap();
+    assert_eq!(actions.len(), 4, "{:#?}", actions);
+
+    assert_eq!(actions[0]["action"], "upgrade-dependents");
+    assert_eq!(actions[0]["package"], "bar");
+    assert_eq!(actions[0]["to"], "2.0.0");
+    assert_eq!(actions[0]["dependents"][0]["name"], "mid");
+
+    assert_eq!(actions[1]["action"], "no-fix");
+    assert_eq!(actions[1]["package"], "baz");
+    assert_eq!(
+        actions[1]["advisories"],
+        serde_json::json!(["RUSTSEC-2020-0003"])
+    );
+
+    assert_eq!(actions[2]["action"], "bump");
+    assert_eq!(actions[2]["package"], "foo");
+    assert_eq!(actions[2]["requirement"], "^1");
+    assert_eq!(actions[2]["dependents"][0]["name"], "app");
+    assert_eq!(actions[2]["command"], "cargo update -p foo --precise 1.0.0");
+
+    // A single update fixes both advisories for `qux`
+    assert_eq!(actions[3]["action"], "update");
+    assert_eq!(actions[3]["package"], "qux");
+    assert_eq!(actions[3]["from"], "0.1.0");
+    assert_eq!(actions[3]["to"], "0.1.2");
+    assert_eq!(actions[3]["command"], "cargo update -p qux --precise 0.1.2");
+    assert_eq!(
+        actions[3]["advisories"],
+        serde_json::json!(["RUSTSEC-2020-0004", "RUSTSEC-2020-0005"])
+    );
+}
+/// `--format fix-plan` prints the actions as a JSON array
+#[test]
+fn fix_plan_format() {
+    let db_dir = support::database(&[advisory("RUSTSEC-2017-0004", "base64", "\">= 0.5.2\"")]);
+    let home = tempfile::tempdir().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+        .args(&["--format", "fix-plan"])
+        .env("CARGO_HOME", home.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let actions: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        actions,
+        serde_json::json!([{
+            "action": "update",
+            "package": "base64",
+            "from": "0.5.1",
+            "to": "0.5.2",
+            "command": "cargo update -p base64 --precise 0.5.2",
+            "advisories": ["RUSTSEC-2017-0004"],
+            "description": "update base64 from 0.5.1 to 0.5.2 with \
+                            `cargo update -p base64 --precise 0.5.2` (RUSTSEC-2017-0004)"
+        }])
+    );
+}
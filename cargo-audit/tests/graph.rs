@@ -0,0 +1,184 @@
+//! Graphviz (DOT) export tests
+
+mod support;
+
+use cargo_audit::graph;
+use rustsec::{advisory, lockfile::Lockfile, report, Database, Report};
+use std::{fs, process::Command};
+use support::Advisory;
+
+/// Workspace where `app` depends on vulnerable `foo` via `mid`, and on
+/// unmaintained `bar`, while `other` only depends on `unrelated`
+const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["bar", "mid"]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "mid"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["foo"]
+
+[[package]]
+name = "other"
+version = "0.1.0"
+dependencies = ["unrelated"]
+
+[[package]]
+name = "unrelated"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+/// Advisory database with a vulnerability advisory for `foo` and an
+/// unmaintained one for `bar`
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2021-0001", "foo")
+            .date("2021-01-01")
+            .title("Test \"foo\" advisory"),
+        Advisory::new("RUSTSEC-2021-0002", "bar")
+            .date("2021-01-01")
+            .attributes("informational = \"unmaintained\"")
+            .title("Test \"bar\" advisory"),
+    ])
+}
+
+/// Render the DOT graph for the lockfile
+fn render(full: bool) -> String {
+    let db_dir = database();
+
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let settings = report::Settings {
+        informational_warnings: vec![advisory::Informational::Unmaintained],
+        ..Default::default()
+    };
+    let report = Report::generate(
+        &Database::open(db_dir.path()).unwrap(),
+        &lockfile,
+        &settings,
+    );
+
+    let mut dot = vec![];
+    graph::write(
+        &mut dot,
+        &lockfile.dependency_tree().unwrap(),
+        &report,
+        full,
+    )
+    .unwrap();
+    String::from_utf8(dot).unwrap()
+}
+
+/// Node statement for the given package
+fn node<'a>(dot: &'a str, label: &str) -> Option<&'a str> {
+    dot.lines()
+        .find(|line| line.contains(&format!("label=\"{}", label)))
+}
+
+/// Only the paths from vulnerable and warned packages to the roots are
+/// exported by default
+#[test]
+fn vulnerable_paths() {
+    let dot = render(false);
+
+    assert!(dot.starts_with("digraph dependencies {\n"), "{}", dot);
+    assert!(dot.ends_with("}\n"), "{}", dot);
+
+    let foo = node(&dot, "foo 0.1.0").unwrap();
+    assert!(
+        foo.contains("label=\"foo 0.1.0\\nRUSTSEC-2021-0001\""),
+        "{}",
+        foo
+    );
+    assert!(foo.contains("fillcolor=red"), "{}", foo);
+    assert!(
+        foo.contains("tooltip=\"RUSTSEC-2021-0001: Test \\\"foo\\\" advisory\""),
+        "{}",
+        foo
+    );
+
+    let bar = node(&dot, "bar 0.1.0").unwrap();
+    assert!(bar.contains("fillcolor=yellow"), "{}", bar);
+    assert!(bar.contains("RUSTSEC-2021-0002 (unmaintained)"), "{}", bar);
+
+    let app = node(&dot, "app 0.1.0").unwrap();
+    assert!(app.contains("style=\"bold\""), "{}", app);
+    assert!(!node(&dot, "mid 0.1.0").unwrap().contains("style"));
+
+    assert!(node(&dot, "other 0.1.0").is_none(), "{}", dot);
+    assert!(node(&dot, "unrelated 0.1.0").is_none(), "{}", dot);
+    assert_eq!(dot.matches(" -> ").count(), 3, "{}", dot);
+}
+
+/// The whole graph is exported with `full`
+#[test]
+fn full_graph() {
+    let dot = render(true);
+
+    assert!(node(&dot, "other 0.1.0")
+        .unwrap()
+        .contains("style=\"bold\""));
+    assert!(node(&dot, "unrelated 0.1.0").is_some());
+    assert_eq!(dot.matches(" -> ").count(), 4, "{}", dot);
+}
+
+/// `--emit-graph` writes the graph alongside the regular report
+#[test]
+fn emit_graph() {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+    let graph_path = project_dir.path().join("paths.dot");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project_dir.path())
+        .args(&["audit", "--no-fetch", "--json", "--db"])
+        .arg(db_dir.path())
+        .arg("--emit-graph")
+        .arg(&graph_path)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(serde_json::from_slice::<serde_json::Value>(&output.stdout).is_ok());
+
+    let dot = fs::read_to_string(&graph_path).unwrap();
+    assert!(node(&dot, "foo 0.1.0").is_some(), "{}", dot);
+    assert!(node(&dot, "unrelated 0.1.0").is_none(), "{}", dot);
+
+    // Failing to write the graph is an error
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project_dir.path())
+        .args(&["audit", "--no-fetch", "--json", "--db"])
+        .arg(db_dir.path())
+        .args(&["--emit-graph", "nonexistent/paths.dot"])
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(
+        stderr.contains("couldn't write dependency graph"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,190 @@
+//! `cargo audit history` tests
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Lockfile depending on vulnerable `foo`
+const LOCKFILE_V1: &str = "\
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Lockfile depending on vulnerable `bar` and `foo`
+const LOCKFILE_V2: &str = "\
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"foo\"
+version = \"0.1.1\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Lockfile depending on vulnerable `bar` and patched `foo`
+const LOCKFILE_V3: &str = "\
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"foo\"
+version = \"0.2.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Advisory database with advisories for `foo` and `bar`
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2021-0001", "foo").date("2021-01-01"),
+        Advisory::new("RUSTSEC-2021-0002", "bar").date("2021-01-01"),
+    ])
+}
+
+/// Commit the given files to the repository, tagging the commit (if a tag
+/// is given)
+fn commit(repo: &git2::Repository, files: &[(&str, &str)], tag: Option<&str>) {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+
+    for (name, contents) in files {
+        fs::write(workdir.join(name), contents).unwrap();
+        index.add_path(Path::new(name)).unwrap();
+    }
+
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+
+    let oid = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "test commit",
+            &tree,
+            parent.iter().collect::<Vec<_>>().as_slice(),
+        )
+        .unwrap();
+
+    if let Some(tag) = tag {
+        let object = repo.find_object(oid, None).unwrap();
+        repo.tag_lightweight(tag, &object, false).unwrap();
+    }
+}
+
+/// Create a repository whose first commit has no `Cargo.lock`, followed by
+/// commits adding it, changing something else, then upgrading it twice
+fn repository(path: &Path) -> git2::Repository {
+    let repo = git2::Repository::init(path).unwrap();
+    commit(&repo, &[("README.md", "test\n")], Some("v0.1"));
+    commit(&repo, &[("Cargo.lock", LOCKFILE_V1)], Some("v1.0"));
+    commit(&repo, &[("README.md", "changed\n")], None);
+    commit(&repo, &[("Cargo.lock", LOCKFILE_V2)], None);
+    commit(&repo, &[("Cargo.lock", LOCKFILE_V3)], Some("v2.0"));
+    repo
+}
+
+/// Run `cargo audit history` in the given directory
+fn history(dir: &Path, db_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .args(&["--width", "200", "history"])
+        .args(args)
+        .current_dir(dir)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Values of the given field of each revision of a JSON timeline
+fn field(timeline: &serde_json::Value, name: &str) -> Vec<serde_json::Value> {
+    timeline
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|revision| revision[name].clone())
+        .collect()
+}
+
+/// The range's start and the commits changing the lockfile are audited,
+/// skipping those without a lockfile
+#[test]
+fn history_range() {
+    let db_dir = database();
+    let repo_dir = tempfile::tempdir().unwrap();
+    let repo = repository(repo_dir.path());
+
+    let output = history(
+        repo_dir.path(),
+        db_dir.path(),
+        &["--rev", "v0.1..v2.0", "--json"],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+
+    let timeline: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let upgrade = repo.revparse_single("v2.0~1").unwrap().id().to_string();
+    assert_eq!(
+        field(&timeline, "revision"),
+        ["v0.1", "v1.0", &upgrade[..7], "v2.0"]
+    );
+    assert_eq!(timeline[0]["skipped"], "no Cargo.lock");
+    assert_eq!(timeline[2]["commit"], upgrade);
+    assert_eq!(
+        field(&timeline, "introduced"),
+        [
+            serde_json::json!([]),
+            serde_json::json!(["RUSTSEC-2021-0001"]),
+            serde_json::json!(["RUSTSEC-2021-0002"]),
+            serde_json::json!([]),
+        ]
+    );
+    assert_eq!(
+        timeline[3]["resolved"],
+        serde_json::json!(["RUSTSEC-2021-0001"])
+    );
+    assert_eq!(
+        timeline[2]["advisories"]["RUSTSEC-2021-0001"],
+        serde_json::json!(["foo 0.1.1"])
+    );
+}
+
+/// Tags matching a pattern are audited in the order they were committed,
+/// and the timeline shows when each advisory was introduced and resolved
+#[test]
+fn history_tags() {
+    let db_dir = database();
+    let repo_dir = tempfile::tempdir().unwrap();
+    repository(repo_dir.path());
+
+    let output = history(repo_dir.path(), db_dir.path(), &["--tags", "v*"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+
+    assert!(stdout.contains("skipped: no Cargo.lock"), "{}", stdout);
+    assert!(stdout.contains("RUSTSEC-2021-0001 (new)"));
+    assert!(stdout.contains("RUSTSEC-2021-0002 (new); resolved RUSTSEC-2021-0001"));
+    assert!(stdout.contains("RUSTSEC-2021-0001     introduced in v1.0, resolved in v2.0"));
+    assert!(stdout.contains("RUSTSEC-2021-0002     introduced in v2.0, still present"));
+    assert!(stdout.contains("2 revisions audited (1 skipped), 2 advisories matched"));
+
+    let output = history(repo_dir.path(), db_dir.path(), &[]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = history(repo_dir.path(), db_dir.path(), &["--rev", "nonexistent"]);
+    assert_eq!(output.status.code(), Some(2));
+}
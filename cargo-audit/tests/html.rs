@@ -0,0 +1,117 @@
+//! HTML output tests
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Advisory database with an advisory for `foo` (with a CVSS score) and an
+/// unmaintained advisory for `bar`
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo")
+            .attributes("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:N/A:N\"")
+            .title("Test advisory for foo"),
+        Advisory::new("RUSTSEC-2020-0002", "bar")
+            .attributes("informational = \"unmaintained\"")
+            .title("Test advisory for bar"),
+    ])
+}
+
+/// Write the lockfile of `app`, which depends on `foo` and `bar` 0.1.0
+fn write_lockfile(project: &Path) {
+    let mut lockfile = String::from(
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"bar\", \"foo\"]\n\n",
+    );
+
+    for package in &["bar", "foo"] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            package
+        ));
+    }
+
+    fs::write(project.join("Cargo.lock"), lockfile).unwrap();
+}
+
+/// Run `cargo audit --format html` in the given project directory
+fn cargo_audit(project: &Path, db: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db)
+        .args(&["--format", "html"])
+        .env("CARGO_HOME", project)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// The report is a standalone page, with the vulnerabilities color-coded by
+/// severity, the warnings, and the dependency trees of the affected packages
+#[test]
+fn html_report() {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+    write_lockfile(project_dir.path());
+
+    let output = cargo_audit(project_dir.path(), db_dir.path());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(stdout.starts_with("<!DOCTYPE html>\n"), "{}", stdout);
+    assert!(stdout.trim_end().ends_with("</html>"), "{}", stdout);
+    assert!(
+        stdout.contains(
+            "<tr class=\"high\"><td>\
+             <a href=\"https://rustsec.org/advisories/RUSTSEC-2020-0001\">RUSTSEC-2020-0001</a>\
+             </td><td><code>foo</code></td><td>0.1.0</td>\
+             <td class=\"severity\">high (7.5)</td><td>Test advisory for foo</td>\
+             <td><code>&gt;=0.2.0</code></td><td>direct</td></tr>"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("<tr><td>unmaintained</td><td><code>bar</code></td>"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("<summary><code>foo 0.1.0</code></summary>"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("└── app 0.1.0"), "{}", stdout);
+
+    // Nothing is loaded from elsewhere
+    assert!(!stdout.contains("<link"), "{}", stdout);
+    assert!(!stdout.contains("<script"), "{}", stdout);
+}
+
+#[test]
+fn html_report_clean() {
+    let db_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(db_dir.path().join("crates")).unwrap();
+    let project_dir = tempfile::tempdir().unwrap();
+    write_lockfile(project_dir.path());
+
+    let output = cargo_audit(project_dir.path(), db_dir.path());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert!(
+        stdout.contains("No known vulnerabilities or warnings found."),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,93 @@
+//! Tests for ignoring advisories with reasons and expiry dates
+
+mod support;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Write the project's `.cargo/audit.toml` with the given ignore entry
+fn write_ignore(project: &Path, entry: &str) {
+    let config_dir = project.join(".cargo");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("audit.toml"),
+        format!("[advisories]\nignore = [{}]\n", entry),
+    )
+    .unwrap();
+}
+
+/// Run `cargo audit` in the given project directory against the database
+fn cargo_audit(project: &Path, db: &Path) -> Output {
+    let lockfile: PathBuf = fs::canonicalize("tests/support/base64_vuln/Cargo.lock").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db)
+        .arg("--file")
+        .arg(lockfile)
+        .env("CARGO_HOME", project)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn suppressed_until_expiry() {
+    let db_dir = support::database(&[Advisory::base64()]);
+    let project_dir = tempfile::tempdir().unwrap();
+    let project = project_dir.path();
+
+    write_ignore(
+        project,
+        "{ id = \"RUSTSEC-2017-0004\", reason = \"only decodes trusted input\", \
+         expires = \"2100-12-31\" }",
+    );
+
+    let output = cargo_audit(project, db_dir.path());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(
+        stdout.contains("Suppressed: 1 ignored advisory"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("only decodes trusted input"), "{}", stdout);
+    assert!(stdout.contains("base64 0.5.1"), "{}", stdout);
+
+    write_ignore(
+        project,
+        "{ id = \"RUSTSEC-2017-0004\", reason = \"only decodes trusted input\", \
+         expires = \"2017-06-01\" }",
+    );
+
+    let output = cargo_audit(project, db_dir.path());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("expired on 2017-06-01"), "{}", stdout);
+}
+
+#[test]
+fn ignore_table_needs_reason() {
+    let db_dir = support::database(&[Advisory::base64()]);
+    let project_dir = tempfile::tempdir().unwrap();
+    let project = project_dir.path();
+
+    write_ignore(
+        project,
+        "{ id = \"RUSTSEC-2017-0004\", expires = \"2100-12-31\" }",
+    );
+
+    let output = cargo_audit(project, db_dir.path());
+    assert!(!output.status.success(), "{:?}", output);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("so it needs a `reason`"),
+        "{:?}",
+        output
+    );
+}
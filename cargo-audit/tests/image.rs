@@ -0,0 +1,166 @@
+//! Tests for auditing the binaries in container images
+
+mod support;
+
+use cargo_audit::image::Reference;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Advisory database with an advisory for versions of `foo` prior to 0.2.0
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")])
+}
+
+/// Add a file with the given mode to a tar archive
+fn add_file(archive: &mut Vec<u8>, path: &str, mode: u32, data: &[u8]) {
+    let mut header = vec![0u8; 512];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[100..108].copy_from_slice(format!("{:07o}\0", mode).as_bytes());
+    header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+
+    archive.extend(header);
+    archive.extend(data);
+    archive.extend(vec![0; (512 - data.len() % 512) % 512]);
+}
+
+/// Run `cargo audit image` on the given image
+fn audit_image(db_path: &Path, image: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .arg("image")
+        .arg(image)
+        .args(args)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Image references default to Docker Hub's `library` and the `latest` tag
+#[test]
+fn parse_reference() {
+    let reference: Reference = "alpine".parse().unwrap();
+    assert_eq!(reference.registry, "docker.io");
+    assert_eq!(reference.repository, "library/alpine");
+    assert_eq!(reference.tag, "latest");
+
+    let reference: Reference = "localhost:5000/team/app:1.0".parse().unwrap();
+    assert_eq!(reference.registry, "localhost:5000");
+    assert_eq!(reference.repository, "team/app");
+    assert_eq!(reference.tag, "1.0");
+
+    let reference: Reference = "ghcr.io/owner/app@sha256:abc".parse().unwrap();
+    assert_eq!(reference.registry, "ghcr.io");
+    assert_eq!(reference.repository, "owner/app");
+    assert_eq!(reference.tag, "sha256:abc");
+    assert_eq!(reference.to_string(), "ghcr.io/owner/app@sha256:abc");
+
+    assert!("Upper/Case".parse::<Reference>().is_err());
+    assert!("app:".parse::<Reference>().is_err());
+}
+
+/// The binaries with audit data in a `docker save` tarball's layers are
+/// audited, after applying the whiteouts of upper layers (the audit data is
+/// added to a copy of `cargo-audit` with `objcopy`, if it's installed)
+#[cfg(target_os = "linux")]
+#[test]
+fn audit_saved_image() {
+    let db_dir = database();
+
+    let dir = tempfile::tempdir().unwrap();
+    let json = br#"{"packages":[
+        {"name":"bar","version":"0.1.0","source":"crates.io","dependencies":[1],"root":true},
+        {"name":"foo","version":"0.1.0","source":"crates.io"}
+    ]}"#;
+    let data = dir.path().join("audit-data");
+    fs::write(&data, miniz_oxide::deflate::compress_to_vec_zlib(json, 6)).unwrap();
+
+    let binary = dir.path().join("bar");
+    let added = Command::new("objcopy")
+        .arg("--add-section")
+        .arg(format!(".dep-v0={}", data.display()))
+        .arg(env!("CARGO_BIN_EXE_cargo-audit"))
+        .arg(&binary)
+        .status();
+
+    match added {
+        Ok(status) if status.success() => (),
+        _ => return,
+    }
+
+    let binary = fs::read(&binary).unwrap();
+
+    let mut lower = vec![];
+    add_file(&mut lower, "usr/local/bin/bar", 0o755, &binary);
+    add_file(&mut lower, "usr/local/bin/baz", 0o755, &binary);
+    add_file(&mut lower, "usr/bin/tool", 0o755, b"#!/bin/sh\n");
+    add_file(&mut lower, "etc/bar.toml", 0o644, &binary);
+    lower.extend(vec![0; 1024]);
+
+    let mut upper = vec![];
+    add_file(&mut upper, "usr/local/bin/.wh.baz", 0o644, b"");
+    upper.extend(vec![0; 1024]);
+
+    let mut tarball = vec![];
+    add_file(
+        &mut tarball,
+        "manifest.json",
+        0o644,
+        br#"[{"Config":"config.json","RepoTags":["app:latest"],"Layers":["lower/layer.tar","upper/layer.tar"]}]"#,
+    );
+    add_file(&mut tarball, "lower/layer.tar", 0o644, &lower);
+    add_file(&mut tarball, "upper/layer.tar", 0o644, &upper);
+    tarball.extend(vec![0; 1024]);
+
+    let image = dir.path().join("image.tar");
+    fs::write(&image, tarball).unwrap();
+
+    let output = audit_image(db_dir.path(), &image, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+    assert!(
+        stdout.contains("1 audited, skipped 1 without audit data")
+            || stderr.contains("1 audited, skipped 1 without audit data"),
+        "{}",
+        stderr
+    );
+
+    let output = audit_image(db_dir.path(), &image, &["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["mode"], "image");
+    assert_eq!(report["skipped"], 1);
+    assert_eq!(report["binaries"].as_array().unwrap().len(), 1);
+    assert_eq!(report["binaries"][0]["path"], "/usr/local/bin/bar");
+    assert_eq!(
+        report["binaries"][0]["report"]["vulnerabilities"]["count"],
+        1
+    );
+}
+
+/// Files which aren't image tarballs are operational errors
+#[test]
+fn audit_invalid_image() {
+    let db_dir = database();
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut tarball = vec![];
+    add_file(&mut tarball, "README", 0o644, b"hi\n");
+    tarball.extend(vec![0; 1024]);
+
+    let image = dir.path().join("image.tar");
+    fs::write(&image, tarball).unwrap();
+
+    let output = audit_image(db_dir.path(), &image, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("isn't an image tarball"), "{}", stderr);
+}
@@ -0,0 +1,385 @@
+//! Registry lookup tests
+
+mod support;
+
+use cargo_audit::{
+    auditor::Auditor,
+    config::{AuditConfig, OutputFormat},
+    index::{self, Client, Release, Stats},
+};
+use rustsec::{
+    advisory,
+    vulnerability::{PatchedRelease, Upgrade},
+    Report,
+};
+use std::{
+    fs,
+    io::{Read, Write},
+    net::TcpListener,
+    path::Path,
+    process::Command,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use support::Advisory;
+
+/// Index file for `foo`: 0.2.0 would be the first patched release, but it
+/// was yanked
+const FOO_RELEASES: &str = r#"{"name":"foo","vers":"0.1.0","deps":[],"cksum":"","features":{},"yanked":false}
+{"name":"foo","vers":"0.2.0","deps":[],"cksum":"","features":{},"yanked":true}
+{"name":"foo","vers":"0.2.1","deps":[],"cksum":"","features":{},"yanked":false,"pubtime":"2023-11-02T10:20:30Z"}
+{"name":"foo","vers":"0.3.0","deps":[],"cksum":"","features":{},"yanked":false}
+"#;
+
+/// `ETag` the index file for `foo` is served with
+const FOO_ETAG: &str = "\"foo-1\"";
+
+/// Respond to the given number of requests on a local port, serving the
+/// index file for `foo` (`304 Not Modified` if it's revalidated with its
+/// `ETag`, and 404 for any other crate), returning the URL of the index and
+/// the request lines received
+fn serve(requests: usize) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/index/", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for _ in 0..requests {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let len = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..len]).into_owned();
+            let request_line = request.lines().next().unwrap_or_default().to_owned();
+
+            let revalidated = request
+                .to_ascii_lowercase()
+                .contains(&format!("if-none-match: {}", FOO_ETAG));
+
+            let (status, body) = if !request_line.starts_with("GET /index/3/f/foo ") {
+                ("404 Not Found", "")
+            } else if revalidated {
+                ("304 Not Modified", "")
+            } else {
+                ("200 OK", FOO_RELEASES)
+            };
+
+            sender.send(request_line).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\netag: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                FOO_ETAG,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    (url, receiver)
+}
+
+/// Versions of an advisory patched in the given versions
+fn versions(patched: &str) -> advisory::Versions {
+    toml::from_str(&format!("patched = [\"{}\"]", patched)).unwrap()
+}
+
+/// Releases are fetched from the crate's index file, then served from the
+/// cache (including crates which aren't published)
+#[test]
+fn fetch_and_cache_releases() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (url, requests) = serve(2);
+    let client = Client::new(
+        url,
+        cache_dir.path().join(index::CACHE_FILE),
+        Duration::from_secs(3600),
+    );
+    let foo = "foo".parse().unwrap();
+    let private = "private-crate".parse().unwrap();
+
+    let (releases, error) = client.releases(&[&foo, &private]);
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(releases[&foo].len(), 4);
+    assert!(releases[&foo][1].yanked);
+    assert_eq!(releases[&foo][2].date.as_deref(), Some("2023-11-02"));
+    assert!(releases[&private].is_empty());
+
+    // Both crates are fetched at once, so the requests' order varies
+    let mut request_lines = [requests.recv().unwrap(), requests.recv().unwrap()];
+    request_lines.sort();
+    assert!(request_lines[0].starts_with("GET /index/3/f/foo "));
+    assert!(request_lines[1].starts_with("GET /index/pr/iv/private-crate "));
+
+    // The server only handles two requests, so these must come from the cache
+    let (cached_releases, error) = client.releases(&[&foo, &private]);
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(cached_releases, releases);
+    assert_eq!(
+        client.stats(),
+        Stats {
+            requests: 2,
+            cache_hits: 2,
+            revalidated: 0
+        }
+    );
+}
+
+/// Expired releases are revalidated with the `ETag` they were fetched with
+#[test]
+fn revalidate_expired_releases() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (url, requests) = serve(2);
+    let client = Client::new(
+        url,
+        cache_dir.path().join(index::CACHE_FILE),
+        Duration::from_secs(0),
+    );
+    let foo = "foo".parse().unwrap();
+
+    let (releases, error) = client.releases(&[&foo]);
+    assert!(error.is_none(), "{:?}", error);
+    let (revalidated_releases, error) = client.releases(&[&foo]);
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(revalidated_releases, releases);
+    assert_eq!(client.stats().revalidated, 1);
+
+    assert!(requests.recv().unwrap().starts_with("GET /index/3/f/foo "));
+    assert!(requests.recv().unwrap().starts_with("GET /index/3/f/foo "));
+}
+
+/// Offline clients serve cached releases however old they are, and omit
+/// crates which aren't cached
+#[test]
+fn offline_releases() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache_path = cache_dir.path().join(index::CACHE_FILE);
+    let (url, requests) = serve(1);
+    let foo = "foo".parse().unwrap();
+    let bar = "bar".parse().unwrap();
+
+    let (releases, _) =
+        Client::new(url.clone(), &cache_path, Duration::from_secs(0)).releases(&[&foo]);
+    assert!(requests.recv().is_ok());
+
+    let client = Client::new(url, &cache_path, Duration::from_secs(0)).offline(true);
+    let (offline_releases, error) = client.releases(&[&foo, &bar]);
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(offline_releases, releases);
+    assert_eq!(client.stats().requests, 0);
+    assert!(requests.try_recv().is_err());
+}
+
+/// Failing to reach the index returns an error, omitting the crate
+#[test]
+fn unreachable_index() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let client = Client::new(
+        "http://127.0.0.1:1/index/",
+        cache_dir.path().join(index::CACHE_FILE),
+        Duration::from_secs(3600),
+    );
+
+    let (releases, error) = client.releases(&[&"foo".parse().unwrap()]);
+    assert!(releases.is_empty());
+    assert!(error.is_some());
+}
+
+/// The oldest non-yanked release satisfying the patched versions is found
+#[test]
+fn find_patched_release() {
+    let releases: Vec<Release> = FOO_RELEASES
+        .lines()
+        .map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+            Release {
+                version: entry["vers"].as_str().unwrap().parse().unwrap(),
+                yanked: entry["yanked"].as_bool().unwrap(),
+                date: entry["pubtime"].as_str().map(|date| date[..10].to_owned()),
+                checksum: None,
+            }
+        })
+        .collect();
+
+    assert_eq!(
+        index::patched_release(&releases, &versions(">= 0.2.0")),
+        PatchedRelease::Published {
+            version: "0.2.1".parse().unwrap(),
+            date: Some("2023-11-02".to_owned())
+        }
+    );
+    assert_eq!(
+        index::patched_release(&releases, &versions(">= 0.2.5")),
+        PatchedRelease::Published {
+            version: "0.3.0".parse().unwrap(),
+            date: None
+        }
+    );
+    assert_eq!(
+        index::patched_release(&releases, &versions(">= 1.0.0")),
+        PatchedRelease::Unreleased
+    );
+}
+
+/// Project with a lockfile depending on vulnerable `foo` (from crates.io)
+/// and `bar` (which isn't published), and a database with advisories for them
+struct Project {
+    db_dir: tempfile::TempDir,
+    project_dir: tempfile::TempDir,
+}
+
+impl Project {
+    fn new() -> Self {
+        let db_dir = support::database(&[
+            Advisory::new("RUSTSEC-2021-0001", "foo").date("2021-01-01"),
+            Advisory::new("RUSTSEC-2021-0002", "bar").date("2021-01-01"),
+        ]);
+
+        let project_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            project_dir.path().join("Cargo.lock"),
+            "[[package]]\n\
+             name = \"bar\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+             [[package]]\n\
+             name = \"foo\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+        )
+        .unwrap();
+
+        Self {
+            db_dir,
+            project_dir,
+        }
+    }
+
+    /// Audit the project, looking up releases in the index at the given URL
+    fn audit(&self, url: &str, offline: bool) -> Report {
+        let mut config = AuditConfig::default();
+        config.database.path = Some(self.db_dir.path().to_owned());
+        config.database.fetch = false;
+        config.cache.path = Some(self.project_dir.path().join("cache"));
+        config.registry.url = Some(url.to_owned());
+        config.net.offline = offline;
+        config.output.quiet = true;
+        config.output.format = OutputFormat::Json;
+        config.yanked.enabled = false;
+
+        Auditor::new(&config)
+            .audit(Some(&self.project_dir.path().join("Cargo.lock")))
+            .unwrap()
+    }
+
+    /// Path to the project's lockfile
+    fn path(&self) -> &Path {
+        self.project_dir.path()
+    }
+}
+
+/// Vulnerabilities are annotated with whether a patched release is published
+#[test]
+fn audit_adds_patched_releases() {
+    let project = Project::new();
+    let (url, _requests) = serve(2);
+    let report = project.audit(&url, false);
+
+    let vulns = &report.vulnerabilities.list;
+    assert_eq!(vulns[0].package.name.as_str(), "foo");
+    assert_eq!(
+        vulns[0].patched_release,
+        Some(PatchedRelease::Published {
+            version: "0.2.1".parse().unwrap(),
+            date: Some("2023-11-02".to_owned())
+        })
+    );
+    assert_eq!(
+        vulns[0].upgrade,
+        Some(Upgrade {
+            from: "0.1.0".parse().unwrap(),
+            compatible: None,
+            major: Some("0.2.1".parse().unwrap()),
+            published: true,
+        })
+    );
+    assert_eq!(vulns[1].package.name.as_str(), "bar");
+    assert_eq!(vulns[1].patched_release, Some(PatchedRelease::Unreleased));
+    assert_eq!(vulns[1].upgrade, None);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json["vulnerabilities"]["list"][0]["patched_release"]["status"],
+        "published"
+    );
+}
+
+/// Nothing is looked up in offline mode
+#[test]
+fn offline_audit() {
+    let project = Project::new();
+    let (url, requests) = serve(2);
+    let report = project.audit(&url, true);
+
+    assert!(report.vulnerabilities.list[0].patched_release.is_none());
+    assert!(requests.try_recv().is_err());
+}
+
+/// The solution recommends the nearest published release, or the lowest
+/// patched version if lookups are disabled with `--no-registry-lookup`
+#[test]
+fn solution_annotations() {
+    let project = Project::new();
+    let (url, _requests) = serve(2);
+    let config_dir = project.path().join(".cargo");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("audit.toml"),
+        format!(
+            "[cache]\npath = {:?}\n\n[registry]\nurl = {:?}\n",
+            project.path().join("cache"),
+            url
+        ),
+    )
+    .unwrap();
+
+    let audit = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .current_dir(project.path())
+            .args(&["audit", "--no-fetch", "--color", "never", "--width", "200"])
+            .arg("--db")
+            .arg(project.db_dir.path())
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = audit(&[]);
+    assert!(
+        stdout.contains("You are on 0.1.0 - upgrade to 0.2.1 (a major bump)\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "You are on 0.1.0 - upgrade to at least 0.2.0 (a major bump), \
+             but no published release satisfies the patched range yet"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("Patched:"), "{}", stdout);
+
+    let stdout = audit(&["--no-registry-lookup", "--verbose"]);
+    assert!(
+        stdout.contains("You are on 0.1.0 - upgrade to at least 0.2.0 (a major bump)\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Patched:       >=0.2.0\n"), "{}", stdout);
+    assert!(!stdout.contains("published"), "{}", stdout);
+}
@@ -0,0 +1,295 @@
+//! Tests for auditing the binaries installed with `cargo install`
+
+mod support;
+
+use cargo_audit::installed;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Advisory database with an advisory for versions of `foo` prior to 0.2.0
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")])
+}
+
+/// Run `cargo audit installed` on the given install root
+fn audit_installed(db_path: &Path, root: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .args(&["installed", "--root"])
+        .arg(root)
+        .args(args)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Audit data lists packages with their dependencies, by index
+#[test]
+fn parse_audit_data() {
+    let json = br#"{"packages":[
+        {"name":"bar","version":"0.1.0","source":"crates.io","kind":"runtime","dependencies":[1],"root":true},
+        {"name":"foo","version":"0.1.0","source":"git"}
+    ]}"#;
+
+    let packages =
+        installed::parse_audit_data(&miniz_oxide::deflate::compress_to_vec_zlib(json, 6)).unwrap();
+
+    assert_eq!(packages.len(), 2);
+    assert_eq!(packages[0].name.as_str(), "bar");
+    assert!(packages[0].source.as_ref().unwrap().is_default_registry());
+    assert_eq!(packages[0].dependencies.len(), 1);
+    assert_eq!(packages[0].dependencies[0].name.as_str(), "foo");
+    assert!(packages[1].source.is_none());
+
+    assert!(installed::parse_audit_data(json).is_err());
+}
+
+/// Binaries without audit data are audited using the install metadata, and
+/// symlinks and non-Rust files are skipped
+#[cfg(unix)]
+#[test]
+fn installed_binaries() {
+    let db_dir = database();
+
+    let root = tempfile::tempdir().unwrap();
+    let bin_dir = root.path().join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+
+    // A Rust binary without audit data, installed from a vulnerable crate
+    fs::copy(env!("CARGO_BIN_EXE_cargo-audit"), bin_dir.join("foo")).unwrap();
+    fs::write(bin_dir.join("script"), "#!/bin/sh\necho hi\n").unwrap();
+    std::os::unix::fs::symlink(bin_dir.join("foo"), bin_dir.join("link")).unwrap();
+    fs::write(
+        root.path().join(".crates2.json"),
+        r#"{"installs":{"foo 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)":{"bins":["foo"]}}}"#,
+    )
+    .unwrap();
+
+    let output = audit_installed(db_dir.path(), root.path(), &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(
+        stdout.contains("1 audited, skipped 1 symlink, 1 not Rust"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("foo (foo 0.1.0, install metadata only"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+    assert!(stdout.contains("cargo install --force foo"), "{}", stdout);
+
+    let output = audit_installed(db_dir.path(), root.path(), &["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["mode"], "installed");
+    assert_eq!(report["binaries"][0]["name"], "foo");
+    assert_eq!(report["binaries"][0]["data"], "install-metadata");
+    assert_eq!(report["skipped"]["symlink"], 1);
+    assert_eq!(report["skipped"]["not-rust"], 1);
+    assert_eq!(
+        report["reinstall"][0]["command"],
+        "cargo install --force foo"
+    );
+}
+
+/// Run `cargo audit bin` on the given file
+fn audit_bin(db_path: &Path, binary: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .arg("bin")
+        .arg(binary)
+        .args(args)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// A binary's embedded audit data is audited like a lockfile (the audit data
+/// is added to a copy of `cargo-audit` with `objcopy`, if it's installed)
+#[cfg(target_os = "linux")]
+#[test]
+fn audit_binary() {
+    let db_dir = database();
+
+    let dir = tempfile::tempdir().unwrap();
+    let json = br#"{"packages":[
+        {"name":"bar","version":"0.1.0","source":"crates.io","dependencies":[1],"root":true},
+        {"name":"foo","version":"0.1.0","source":"crates.io"}
+    ]}"#;
+    let data = dir.path().join("audit-data");
+    fs::write(&data, miniz_oxide::deflate::compress_to_vec_zlib(json, 6)).unwrap();
+
+    let binary = dir.path().join("bar");
+    let added = Command::new("objcopy")
+        .arg("--add-section")
+        .arg(format!(".dep-v0={}", data.display()))
+        .arg(env!("CARGO_BIN_EXE_cargo-audit"))
+        .arg(&binary)
+        .status();
+
+    match added {
+        Ok(status) if status.success() => (),
+        _ => return,
+    }
+
+    let lockfile = installed::read_binary(&binary).unwrap();
+    assert_eq!(lockfile.packages.len(), 2);
+
+    let output = audit_bin(db_dir.path(), &binary, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+    assert!(stdout.contains("foo 0.1.0"), "{}", stdout);
+
+    let output = audit_bin(db_dir.path(), &binary, &["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["vulnerabilities"]["count"], 1);
+}
+
+/// Binaries without audit data, and files which aren't executables, are
+/// operational errors
+#[test]
+fn audit_binary_without_audit_data() {
+    let db_dir = database();
+
+    let output = audit_bin(
+        db_dir.path(),
+        Path::new(env!("CARGO_BIN_EXE_cargo-audit")),
+        &[],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("has no audit data"), "{}", stderr);
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("script");
+    fs::write(&script, "#!/bin/sh\necho hi\n").unwrap();
+
+    let error = installed::read_binary(&script).unwrap_err();
+    assert!(error.msg().contains("isn't an executable"), "{}", error);
+}
+
+/// Audit data is read from the custom section of WebAssembly modules, and
+/// components are recognized but not supported yet
+#[test]
+fn audit_wasm() {
+    let db_dir = database();
+
+    let module = Path::new("tests/support/wasm/auditable.wasm");
+    let lockfile = installed::read_binary(module).unwrap();
+    assert_eq!(lockfile.packages.len(), 2);
+
+    let output = audit_bin(db_dir.path(), module, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+
+    let component = Path::new("tests/support/wasm/component.wasm");
+    assert_eq!(
+        installed::scan(component).unwrap(),
+        installed::Found::WasmComponent
+    );
+
+    let output = audit_bin(db_dir.path(), component, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("is a WebAssembly component"), "{}", stderr);
+}
+
+/// Binaries aren't read in full, so a (sparse) file much larger than memory
+/// which starts with an executable is scanned without running out of it
+#[cfg(unix)]
+#[test]
+fn scan_large_binary() {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("large");
+    fs::copy(env!("CARGO_BIN_EXE_cargo-audit"), &binary).unwrap();
+
+    fs::OpenOptions::new()
+        .write(true)
+        .open(&binary)
+        .unwrap()
+        .set_len(64 << 30)
+        .unwrap();
+
+    assert_eq!(
+        installed::scan(&binary).unwrap(),
+        installed::Found::Executable
+    );
+
+    let error = installed::read_binary(&binary).unwrap_err();
+    assert!(error.msg().contains("has no audit data"), "{}", error);
+}
+
+/// Directories (with `--recursive`) and glob patterns are searched for
+/// binaries, which are audited together
+#[cfg(target_os = "linux")]
+#[test]
+fn audit_binaries_recursively() {
+    let db_dir = database();
+
+    let dir = tempfile::tempdir().unwrap();
+    let json = br#"{"packages":[
+        {"name":"bar","version":"0.1.0","source":"crates.io","dependencies":[1],"root":true},
+        {"name":"foo","version":"0.1.0","source":"crates.io"}
+    ]}"#;
+    let data = dir.path().join("audit-data");
+    fs::write(&data, miniz_oxide::deflate::compress_to_vec_zlib(json, 6)).unwrap();
+
+    let host = dir.path().join("host");
+    fs::create_dir_all(host.join("bin")).unwrap();
+    fs::create_dir_all(host.join("opt/tool")).unwrap();
+
+    let binary = host.join("bin/bar");
+    let added = Command::new("objcopy")
+        .arg("--add-section")
+        .arg(format!(".dep-v0={}", data.display()))
+        .arg(env!("CARGO_BIN_EXE_cargo-audit"))
+        .arg(&binary)
+        .status();
+
+    match added {
+        Ok(status) if status.success() => (),
+        _ => return,
+    }
+
+    fs::copy(
+        env!("CARGO_BIN_EXE_cargo-audit"),
+        host.join("opt/tool/tool"),
+    )
+    .unwrap();
+    fs::write(host.join("opt/tool/README"), "not a binary\n").unwrap();
+    std::os::unix::fs::symlink(&binary, host.join("opt/tool/bar")).unwrap();
+
+    let output = audit_bin(db_dir.path(), &host, &["--recursive", "--json"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["mode"], "binaries");
+    assert_eq!(report["skipped"], 1);
+    assert_eq!(report["binaries"].as_array().unwrap().len(), 1);
+    assert_eq!(report["binaries"][0]["path"], binary.to_str().unwrap());
+    assert_eq!(
+        report["binaries"][0]["report"]["vulnerabilities"]["count"],
+        1
+    );
+
+    let output = audit_bin(db_dir.path(), &host.join("*/ba?"), &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+
+    // Directories are only searched with `--recursive`
+    let output = audit_bin(db_dir.path(), &host, &[]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
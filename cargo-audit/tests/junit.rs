@@ -0,0 +1,139 @@
+//! JUnit output tests
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Advisory database with an advisory for `foo` (with a CVSS score) and an
+/// unmaintained advisory for `bar`
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo")
+            .attributes("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:N/A:N\"")
+            .title("Test advisory for foo"),
+        Advisory::new("RUSTSEC-2020-0002", "bar")
+            .attributes("informational = \"unmaintained\"")
+            .title("Test advisory for bar"),
+    ])
+}
+
+/// Write the lockfile of `app`, which depends on `foo` and `bar` 0.1.0
+fn write_lockfile(project: &Path) {
+    let mut lockfile = String::from(
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"bar\", \"foo\"]\n\n",
+    );
+
+    for package in &["bar", "foo"] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            package
+        ));
+    }
+
+    fs::write(project.join("Cargo.lock"), lockfile).unwrap();
+}
+
+/// Run `cargo audit --format junit` in the given project directory
+fn cargo_audit(project: &Path, db: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db)
+        .args(&["--format", "junit"])
+        .args(args)
+        .env("CARGO_HOME", project)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Vulnerabilities are failing test cases, and allowed warnings skipped ones
+/// unless they're denied
+#[test]
+fn junit_report() {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+    write_lockfile(project_dir.path());
+
+    let output = cargo_audit(project_dir.path(), db_dir.path(), &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(
+        stdout.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "<testsuite name=\"cargo-audit\" tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"1\">"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "<testcase classname=\"foo\" name=\"RUSTSEC-2020-0001\">\n      \
+             <failure type=\"vulnerability\" message=\"foo 0.1.0: Test advisory for foo\">"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "<testcase classname=\"bar\" name=\"RUSTSEC-2020-0002\">\n      \
+             <skipped message=\"bar 0.1.0: Test advisory for bar\"/>"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(stdout.ends_with("</testsuites>\n"), "{}", stdout);
+
+    let output = cargo_audit(
+        project_dir.path(),
+        db_dir.path(),
+        &["--deny", "unmaintained"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "<failure type=\"unmaintained\" message=\"bar 0.1.0: Test advisory for bar\">"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+/// Reports without findings have a passing test case
+#[test]
+fn junit_report_clean() {
+    let db_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(db_dir.path().join("crates")).unwrap();
+    let project_dir = tempfile::tempdir().unwrap();
+    write_lockfile(project_dir.path());
+
+    let output = cargo_audit(project_dir.path(), db_dir.path(), &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert!(
+        stdout.contains("tests=\"1\" failures=\"0\" errors=\"0\" skipped=\"0\""),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("<testcase classname=\"cargo-audit\" name=\"no known vulnerabilities\"/>"),
+        "{}",
+        stdout
+    );
+}
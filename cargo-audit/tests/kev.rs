@@ -0,0 +1,142 @@
+//! Known Exploited Vulnerabilities catalog tests
+
+mod support;
+
+use cargo_audit::{
+    auditor::Auditor,
+    config::{AuditConfig, DenyOption, OutputFormat},
+    kev::Catalog,
+};
+use rustsec::{advisory, Report};
+use std::fs;
+use support::Advisory;
+
+/// Catalog containing `CVE-2021-0001`
+const CATALOG: &str = r#"{
+  "title": "CISA Catalog of Known Exploited Vulnerabilities",
+  "catalogVersion": "2021.11.03",
+  "dateReleased": "2021-11-03T00:00:00.0000Z",
+  "count": 1,
+  "vulnerabilities": [
+    {
+      "cveID": "CVE-2021-0001",
+      "vendorProject": "Example",
+      "product": "foo",
+      "vulnerabilityName": "Example vulnerability",
+      "dateAdded": "2021-11-03",
+      "shortDescription": "Example vulnerability.",
+      "requiredAction": "Apply updates per vendor instructions.",
+      "dueDate": "2021-11-17"
+    }
+  ]
+}"#;
+
+/// Low severity advisory for `package` which is an alias of `cve`
+fn advisory(id: &str, package: &str, cve: &str) -> Advisory {
+    Advisory::new(id, package)
+        .date("2021-01-01")
+        .attributes(&format!(
+            "aliases = [\"{}\"]\n\
+         cvss = \"CVSS:3.1/AV:L/AC:H/PR:H/UI:R/S:U/C:L/I:N/A:N\"",
+            cve
+        ))
+}
+
+/// Audit a lockfile depending on vulnerable `foo` (`CVE-2021-0001`, which is
+/// in the catalog) and `bar` (`CVE-2021-0002`, which isn't)
+fn audit(configure: impl FnOnce(&mut AuditConfig)) -> Report {
+    let db_dir = support::database(&[
+        advisory("RUSTSEC-2021-0001", "foo", "CVE-2021-0001"),
+        advisory("RUSTSEC-2021-0002", "bar", "CVE-2021-0002"),
+    ]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    let kev_path = project_dir.path().join("kev.json");
+    fs::write(&kev_path, CATALOG).unwrap();
+
+    let mut lockfile = String::new();
+
+    for package in &["bar", "foo"] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            package
+        ));
+    }
+
+    fs::write(&lockfile_path, lockfile).unwrap();
+
+    let mut config = AuditConfig::default();
+    config.database.path = Some(db_dir.path().to_owned());
+    config.database.fetch = false;
+    config.kev.file = Some(kev_path);
+    config.output.quiet = true;
+    config.output.format = OutputFormat::Json;
+    config.yanked.enabled = false;
+    config.registry.lookup = false;
+    configure(&mut config);
+
+    Auditor::new(&config).audit(Some(&lockfile_path)).unwrap()
+}
+
+#[test]
+fn parse_catalog() {
+    let catalog: Catalog = CATALOG.parse().unwrap();
+
+    assert_eq!(catalog.len(), 1);
+    assert!(catalog.contains(&"CVE-2021-0001".parse::<advisory::Id>().unwrap()));
+    assert!(!catalog.contains(&"CVE-2021-0002".parse::<advisory::Id>().unwrap()));
+}
+
+/// Vulnerabilities with a CVE alias in the catalog are marked as known exploited
+#[test]
+fn mark_known_exploited() {
+    let report = audit(|_| ());
+
+    let known_exploited: Vec<_> = report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vuln| (vuln.package.name.as_str(), vuln.known_exploited))
+        .collect();
+
+    assert_eq!(known_exploited, [("foo", Some(true)), ("bar", Some(false))]);
+}
+
+/// `--deny known-exploited` reports known exploited vulnerabilities below
+/// the severity threshold
+#[test]
+fn deny_known_exploited() {
+    let report = audit(|config| {
+        config.advisories.severity_threshold = Some(advisory::Severity::High);
+    });
+
+    assert!(!report.vulnerabilities.found);
+
+    let report = audit(|config| {
+        config.advisories.severity_threshold = Some(advisory::Severity::High);
+        config.output.deny(DenyOption::KnownExploited);
+    });
+
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert_eq!(report.vulnerabilities.list[0].package.name.as_str(), "foo");
+    assert_eq!(report.vulnerabilities.list[0].known_exploited, Some(true));
+}
+
+/// A missing catalog only results in a warning
+#[test]
+fn missing_catalog() {
+    let report = audit(|config| {
+        config.kev.file = Some("nonexistent.json".into());
+    });
+
+    assert_eq!(report.vulnerabilities.count, 2);
+    assert!(report
+        .vulnerabilities
+        .list
+        .iter()
+        .all(|vuln| vuln.known_exploited.is_none()));
+}
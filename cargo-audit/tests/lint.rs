@@ -0,0 +1,98 @@
+//! Advisory linting (`cargo audit lint`) tests
+
+mod support;
+
+use cargo_audit::lint::{self, Settings};
+use std::{fs, path::Path, process::Command};
+use support::Advisory;
+
+/// Write an advisory with the given ID and `[versions]` section for the
+/// `base64` crate to the given directory
+fn write_advisory(dir: &Path, file_name: &str, id: &str, versions: &str) {
+    let crate_dir = dir.join("crates").join("base64");
+    fs::create_dir_all(&crate_dir).unwrap();
+    fs::write(
+        crate_dir.join(file_name),
+        Advisory::new(id, "base64")
+            .date("2017-05-03")
+            .versions(versions)
+            .markdown(),
+    )
+    .unwrap();
+}
+
+/// Advisories are found by their file names, and each one's errors are
+/// reported, including IDs used by more than one advisory
+#[test]
+fn lint_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    write_advisory(
+        dir.path(),
+        "RUSTSEC-2017-0004.md",
+        "RUSTSEC-2017-0004",
+        "patched = [\">= 0.5.2\"]",
+    );
+    write_advisory(
+        dir.path(),
+        "RUSTSEC-2017-0005.md",
+        "RUSTSEC-2017-0004",
+        "patched = [\"*\"]",
+    );
+    write_advisory(
+        dir.path(),
+        "RUSTSEC-2017-0006.md",
+        "RUSTSEC-2017-0006",
+        "patched = [\">= 0.5.2\"]",
+    );
+    fs::write(dir.path().join("README.md"), "# Not an advisory\n").unwrap();
+
+    let report = lint::lint(&[dir.path().to_owned()], &Settings::default()).unwrap();
+    assert_eq!(report.files.len(), 3);
+    assert_eq!(report.invalid_count(), 2);
+
+    let duplicate = &report.files[1];
+    assert!(duplicate.errors[0].contains("requirement matches every version"));
+    assert!(duplicate.errors[1].starts_with("duplicate advisory ID `RUSTSEC-2017-0004`"));
+    assert!(report.files[2].is_valid());
+}
+
+/// Files which can't be parsed fail lint with the parse error
+#[test]
+fn lint_unparseable_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("new-advisory.md");
+    fs::write(
+        &path,
+        "```toml\n[advisory]\nid = \"RUSTSEC-2017-0004\"\n```\n",
+    )
+    .unwrap();
+
+    let report = lint::lint(&[path], &Settings::default()).unwrap();
+    assert_eq!(report.files.len(), 1);
+    assert!(report.files[0].id.is_none());
+    assert!(report.is_failure());
+}
+
+/// `cargo audit lint` exits with status 1 if any advisory fails lint
+#[test]
+fn lint_command() {
+    let dir = tempfile::tempdir().unwrap();
+    write_advisory(
+        dir.path(),
+        "RUSTSEC-2017-0004.md",
+        "RUSTSEC-2017-0004",
+        "patched = [\"*\"]",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "lint", "--json"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["mode"], "lint");
+    assert_eq!(report["files"][0]["id"], "RUSTSEC-2017-0004");
+}
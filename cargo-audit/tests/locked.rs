@@ -0,0 +1,82 @@
+//! Tests for checking that lockfiles are up to date with their manifests
+
+use cargo_audit::lockfile;
+use std::{fs, path::Path, process::Command};
+
+/// Write the `app` package at the given version, with a lockfile for
+/// version 0.1.0
+fn write_package(dir: &Path, version: &str) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src").join("lib.rs"), "").unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"app\"\nversion = \"{}\"\nedition = \"2018\"\n\n[workspace]\n",
+            version
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("Cargo.lock"),
+        "version = 3\n\n[[package]]\nname = \"app\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn up_to_date() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = dir.path().join("Cargo.toml");
+
+    write_package(dir.path(), "0.1.0");
+    assert!(lockfile::is_up_to_date(&manifest_path, true).unwrap());
+
+    write_package(dir.path(), "0.2.0");
+    assert!(!lockfile::is_up_to_date(&manifest_path, true).unwrap());
+
+    // Checking doesn't update the lockfile
+    assert!(fs::read_to_string(dir.path().join("Cargo.lock"))
+        .unwrap()
+        .contains("version = \"0.1.0\""));
+}
+
+#[test]
+fn stale_lockfile() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(db_dir.path().join("crates")).unwrap();
+    write_package(dir.path(), "0.2.0");
+
+    let audit = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .current_dir(dir.path())
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    };
+
+    // By default, a stale lockfile is audited with a warning
+    let output = audit(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(
+        stdout.contains("Cargo.lock is out of date with"),
+        "{}",
+        stdout
+    );
+
+    for flag in &["--locked", "--frozen"] {
+        let output = audit(&[flag]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(2), "{}", stderr);
+        assert!(
+            stderr.contains("run `cargo update` or `cargo generate-lockfile`"),
+            "{}",
+            stderr
+        );
+    }
+}
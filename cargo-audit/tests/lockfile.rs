@@ -0,0 +1,96 @@
+//! Lockfile location tests
+
+use cargo_audit::lockfile;
+use std::{fs, path::Path};
+
+/// Write a package named after its directory, with the given extra manifest
+/// contents
+fn write_package(dir: &Path, extra: &str) {
+    let name = dir.file_name().unwrap().to_str().unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src").join("lib.rs"), "").unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n{}",
+            name, extra
+        ),
+    )
+    .unwrap();
+}
+
+/// Workspace root whose manifest has the given contents, with packages in
+/// `crates/member`, `crates/excluded` and `other`
+fn write_workspace(dir: &Path, manifest: &str) {
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    write_package(&dir.join("crates").join("member"), "");
+    write_package(&dir.join("crates").join("excluded"), "");
+    write_package(&dir.join("other"), "");
+}
+
+/// Workspace root the given package's manifest is found to belong to,
+/// relative to `dir`
+fn root(dir: &Path, package: &str) -> String {
+    let manifest_path = dir.join(package).join("Cargo.toml");
+    let root = lockfile::workspace_root(&manifest_path).unwrap();
+    let dir = fs::canonicalize(dir).unwrap();
+
+    root.strip_prefix(&dir)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .replace('\\', "/")
+}
+
+/// Members may be glob patterns, and excluded packages (or packages which
+/// aren't members at all) are standalone
+#[test]
+fn glob_members_and_exclude() {
+    let dir = tempfile::tempdir().unwrap();
+    write_workspace(
+        dir.path(),
+        "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/excluded\"]\n",
+    );
+
+    assert_eq!(root(dir.path(), "crates/member"), "Cargo.toml");
+    assert_eq!(
+        root(dir.path(), "crates/excluded"),
+        "crates/excluded/Cargo.toml"
+    );
+    assert_eq!(root(dir.path(), "other"), "other/Cargo.toml");
+}
+
+/// Members listed as they are (rather than by a glob pattern) are members even
+/// if they're excluded
+#[test]
+fn explicit_members_override_exclude() {
+    let dir = tempfile::tempdir().unwrap();
+    write_workspace(
+        dir.path(),
+        "[workspace]\nmembers = [\"crates/excluded\"]\nexclude = [\"crates\"]\n",
+    );
+
+    assert_eq!(root(dir.path(), "crates/excluded"), "Cargo.toml");
+    assert_eq!(
+        root(dir.path(), "crates/member"),
+        "crates/member/Cargo.toml"
+    );
+}
+
+/// Path dependencies of the root package are members
+#[test]
+fn path_dependency_members() {
+    let dir = tempfile::tempdir().unwrap();
+    write_workspace(
+        dir.path(),
+        "[package]\nname = \"root\"\nversion = \"0.1.0\"\n\n\
+         [dependencies]\nother = { path = \"./other\" }\n\n\
+         [workspace]\n",
+    );
+
+    assert_eq!(root(dir.path(), "other"), "Cargo.toml");
+    assert_eq!(
+        root(dir.path(), "crates/member"),
+        "crates/member/Cargo.toml"
+    );
+}
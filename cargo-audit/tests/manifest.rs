@@ -0,0 +1,121 @@
+//! `cargo audit manifest` tests
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Manifest with vulnerable, patched, renamed, build, path and git
+/// dependencies
+const MANIFEST: &str = "\
+[package]
+name = \"triaged\"
+version = \"0.1.0\"
+
+[dependencies]
+foo = \"0.1\"
+bar = { version = \"0.2\" }
+baz = { package = \"foo\", version = \"=0.1.5\" }
+local = { path = \"../local\" }
+forked = { git = \"https://github.com/example/forked\" }
+
+[build-dependencies]
+foo = \"~0.1.2\"
+";
+
+/// Advisory database with an advisory against versions of `foo` prior to
+/// 0.2.0
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::new("RUSTSEC-2021-0001", "foo").date("2021-01-01")])
+}
+
+/// Run `cargo audit manifest` on the given manifest
+fn audit_manifest(db_path: &Path, manifest_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .arg("manifest")
+        .arg(manifest_path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Requirements intersecting an advisory's affected versions are reported,
+/// with the caveat that they weren't resolved, and path and git
+/// dependencies are counted as skipped
+#[test]
+fn requirement_matches() {
+    let db_dir = database();
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let manifest_path = project_dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, MANIFEST).unwrap();
+
+    let output = audit_manifest(db_dir.path(), &manifest_path, &["--json"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["mode"], "manifest");
+    assert!(report["vulnerabilities"].is_null());
+    assert!(report["caveat"]
+        .as_str()
+        .unwrap()
+        .contains("actual resolution may differ"));
+    assert_eq!(report["manifest"]["package"], "triaged");
+    assert_eq!(report["dependencies"], 4);
+    assert_eq!(report["skipped"], serde_json::json!({"path": 1, "git": 1}));
+
+    let matches: Vec<_> = report["matches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| {
+            (
+                m["dependency"].as_str().unwrap(),
+                m["requirement"].as_str().unwrap(),
+                m["kind"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        matches,
+        [
+            ("baz", "=0.1.5", "normal"),
+            ("foo", "^0.1", "normal"),
+            ("foo", "~0.1.2", "build")
+        ]
+    );
+
+    let output = audit_manifest(db_dir.path(), &manifest_path, &[]);
+    let all = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(all.contains("Requirement:   =0.1.5 (as `baz`)"), "{}", all);
+    assert!(all.contains("~0.1.2 (build dependency)"), "{}", all);
+    assert!(
+        all.contains("requirement-level match, no lockfile"),
+        "{}",
+        all
+    );
+    assert!(all.contains("skipped 1 path, 1 git"), "{}", all);
+}
+
+/// Requirements which can't be vulnerable pass
+#[test]
+fn no_matches() {
+    let db_dir = database();
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let manifest_path = project_dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, "[dependencies]\nfoo = \"0.2\"\n").unwrap();
+
+    let output = audit_manifest(db_dir.path(), &manifest_path, &[]);
+    assert_eq!(output.status.code(), Some(0));
+}
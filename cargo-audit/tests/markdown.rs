@@ -0,0 +1,115 @@
+//! Markdown output tests
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Advisory database with an advisory for `foo` (with a CVSS score) and an
+/// unmaintained advisory for `bar`
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo")
+            .attributes("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:N/A:N\"")
+            .title("Test advisory for foo"),
+        Advisory::new("RUSTSEC-2020-0002", "bar")
+            .attributes("informational = \"unmaintained\"")
+            .title("Test advisory for bar"),
+    ])
+}
+
+/// Write the lockfile of `app`, which depends on `foo` and `bar` 0.1.0
+fn write_lockfile(project: &Path) {
+    let mut lockfile = String::from(
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"bar\", \"foo\"]\n\n",
+    );
+
+    for package in &["bar", "foo"] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            package
+        ));
+    }
+
+    fs::write(project.join("Cargo.lock"), lockfile).unwrap();
+}
+
+/// Run `cargo audit --format markdown` in the given project directory
+fn cargo_audit(project: &Path, db: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db)
+        .args(&["--format", "markdown"])
+        .env("CARGO_HOME", project)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Vulnerabilities and warnings are listed in tables, followed by the
+/// collapsed dependency trees of the affected packages
+#[test]
+fn markdown_report() {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+    write_lockfile(project_dir.path());
+
+    let output = cargo_audit(project_dir.path(), db_dir.path());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(stdout.starts_with("## cargo audit\n"), "{}", stdout);
+    assert!(
+        stdout.contains("**1 vulnerability found**, 1 warning."),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "| RUSTSEC-2020-0001 | `foo` | 0.1.0 | high (7.5) | `>=0.2.0` | \
+             [RUSTSEC-2020-0001](https://rustsec.org/advisories/RUSTSEC-2020-0001) | direct |"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("| unmaintained | `bar` | 0.1.0 | RUSTSEC-2020-0002 |"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("<summary>Dependency tree of <code>foo 0.1.0</code></summary>"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("└── app 0.1.0"), "{}", stdout);
+
+    // Status messages stay out of the report
+    assert!(!stdout.contains("Loaded"), "{}", stdout);
+}
+
+#[test]
+fn markdown_report_clean() {
+    let db_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(db_dir.path().join("crates")).unwrap();
+    let project_dir = tempfile::tempdir().unwrap();
+    write_lockfile(project_dir.path());
+
+    let output = cargo_audit(project_dir.path(), db_dir.path());
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "## cargo audit\n\nNo known vulnerabilities or warnings found.\n"
+    );
+}
@@ -0,0 +1,82 @@
+//! Tests for the advisory database freshness policy (`database.max-age`)
+
+mod support;
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Time of the database's only commit: 2020-01-01T00:00:00Z
+const COMMIT_TIME: i64 = 1_577_836_800;
+
+/// Create a git repository with an advisory database, last committed to at
+/// [`COMMIT_TIME`]
+fn write_database(path: &Path) {
+    let repo = git2::Repository::init(path).unwrap();
+    let advisory_path = Path::new("crates/base64/RUSTSEC-2017-0004.md");
+    Advisory::base64().write(path);
+
+    let mut index = repo.index().unwrap();
+    index.add_path(advisory_path).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature =
+        git2::Signature::new("Test", "test@example.com", &git2::Time::new(COMMIT_TIME, 0)).unwrap();
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test commit",
+        &tree,
+        &[],
+    )
+    .unwrap();
+}
+
+/// Audit a lockfile without vulnerabilities against the database at the
+/// given path, with the given `on-stale` policy and a `max-age` of 7 days
+fn audit(db_path: &Path, on_stale: &str) -> Output {
+    let home = tempfile::tempdir().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .args(&["--file", "tests/support/no_vulns/Cargo.lock"])
+        .env("CARGO_HOME", home.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .env("CARGO_AUDIT_OFFLINE", "true")
+        .env("CARGO_AUDIT_DB_MAX_AGE", "7d")
+        .env("CARGO_AUDIT_DB_ON_STALE", on_stale)
+        .output()
+        .unwrap()
+}
+
+/// Databases older than `max-age` are warned about, or fail the audit
+#[test]
+fn stale_database_policy() {
+    let db_dir = tempfile::tempdir().unwrap();
+    write_database(db_dir.path());
+
+    let expected = "advisory database was last updated on 2020-01-01";
+
+    // The database can't be fetched again while offline
+    for on_stale in &["warn", "refetch"] {
+        let output = audit(db_dir.path(), on_stale);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(output.status.code(), Some(0), "{}", stdout);
+        assert!(stdout.contains(expected), "{}", stdout);
+        assert!(stdout.contains("longer ago than its max-age of 7days"));
+    }
+
+    let output = audit(db_dir.path(), "error");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains(expected), "{}", stderr);
+
+    let output = audit(db_dir.path(), "sometimes");
+    assert_ne!(output.status.code(), Some(0));
+}
@@ -0,0 +1,151 @@
+//! Tests for auditing `cargo metadata` output and newer lockfile formats
+
+mod support;
+
+use cargo_audit::features;
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+use support::Advisory;
+
+/// `cargo metadata --format-version 1` output for `bar`, which depends on
+/// `foo` 0.1.0 from crates.io's sparse index (with the package ID format of
+/// cargo 1.77+)
+const METADATA: &str = r#"{
+  "packages": [
+    {
+      "id": "path+file:///work/bar#1.0.0",
+      "name": "bar",
+      "version": "1.0.0",
+      "source": null,
+      "dependencies": [{"name": "foo", "req": "^0.1"}],
+      "targets": [{"kind": ["bin"]}]
+    },
+    {
+      "id": "sparse+https://index.crates.io/#foo@0.1.0",
+      "name": "foo",
+      "version": "0.1.0",
+      "source": "sparse+https://index.crates.io/",
+      "dependencies": [],
+      "targets": [{"kind": ["lib"]}]
+    }
+  ],
+  "workspace_members": ["path+file:///work/bar#1.0.0"],
+  "resolve": {
+    "nodes": [
+      {
+        "id": "path+file:///work/bar#1.0.0",
+        "deps": [{"pkg": "sparse+https://index.crates.io/#foo@0.1.0", "dep_kinds": [{"kind": null}]}]
+      },
+      {"id": "sparse+https://index.crates.io/#foo@0.1.0", "deps": []}
+    ],
+    "root": "path+file:///work/bar#1.0.0"
+  },
+  "version": 1
+}"#;
+
+/// Version 4 lockfile with the same packages
+const V4_LOCKFILE: &str = r#"version = 4
+
+[[package]]
+name = "bar"
+version = "1.0.0"
+dependencies = ["foo"]
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "sparse+https://index.crates.io/"
+"#;
+
+/// Advisory database with an advisory for versions of `foo` prior to 0.2.0
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")])
+}
+
+/// Run `cargo audit --json` with the given arguments, writing `stdin` to it
+fn audit(db_path: &Path, args: &[&str], stdin: &str) -> Output {
+    let home = tempfile::tempdir().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--json", "--db"])
+        .arg(db_path)
+        .args(args)
+        .env("CARGO_HOME", home.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+/// The packages in the dependency graph are listed, with the dependencies
+/// between them
+#[test]
+fn parse_metadata() {
+    let lockfile = features::parse_lockfile(METADATA).unwrap();
+    let names: Vec<_> = lockfile
+        .packages
+        .iter()
+        .map(|package| package.name.as_str())
+        .collect();
+
+    assert_eq!(names, ["bar", "foo"]);
+    assert_eq!(lockfile.packages[0].dependencies.len(), 1);
+    assert_eq!(lockfile.packages[0].dependencies[0].name.as_str(), "foo");
+
+    let source = lockfile.packages[1].source.as_ref().unwrap();
+    assert!(source.is_default_registry());
+    assert!(lockfile.dependency_tree().is_ok());
+
+    // `cargo metadata --no-deps` output has no dependency graph to audit
+    assert!(features::parse_lockfile(r#"{"packages": [], "workspace_members": []}"#).is_err());
+}
+
+/// `cargo metadata` output is audited like a lockfile
+#[test]
+fn audit_from_metadata() {
+    let db_dir = database();
+
+    let output = audit(db_dir.path(), &["--from-metadata", "-"], METADATA);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["vulnerabilities"]["count"], 1);
+    assert_eq!(report["lockfile"]["dependency-count"], 2);
+
+    let output = audit(
+        db_dir.path(),
+        &["--from-metadata", "-", "--file", "Cargo.lock"],
+        METADATA,
+    );
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Version 4 lockfiles with sparse registry sources are audited
+#[test]
+fn audit_v4_lockfile() {
+    let db_dir = database();
+
+    let output = audit(db_dir.path(), &["--file", "-"], V4_LOCKFILE);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["vulnerabilities"]["count"], 1);
+    assert_eq!(
+        report["vulnerabilities"]["list"][0]["package"]["source"],
+        "sparse+https://index.crates.io/"
+    );
+}
@@ -0,0 +1,119 @@
+//! Prometheus metrics tests
+
+mod support;
+
+use cargo_audit::metrics;
+use rustsec::{lockfile::Lockfile, report, Database, Report};
+use std::process::Command;
+use support::Advisory;
+
+/// Lockfile for `app`, which depends on `foo` and `bar`
+const LOCKFILE: &str = r#"[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["bar", "foo"]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+/// Advisory for versions of the package prior to 1.0.0, with the given CVSS
+/// vector (if any)
+fn advisory(id: &str, package: &str, cvss: Option<&str>) -> Advisory {
+    let cvss = cvss
+        .map(|cvss| format!("cvss = \"{}\"", cvss))
+        .unwrap_or_default();
+
+    Advisory::new(id, package)
+        .attributes(&cvss)
+        .versions("patched = [\">= 1.0.0\"]")
+}
+
+/// Vulnerabilities are counted by severity, and every severity and kind of
+/// warning is listed
+#[test]
+fn write_metrics() {
+    let advisories = [
+        advisory(
+            "RUSTSEC-2020-0001",
+            "foo",
+            Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:N/A:N"),
+        ),
+        advisory("RUSTSEC-2020-0002", "bar", None),
+    ];
+    let advisories: Vec<String> = advisories.iter().map(Advisory::markdown).collect();
+    let advisories: Vec<&str> = advisories.iter().map(String::as_str).collect();
+    let database = Database::from_markdown(&advisories).unwrap();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let mut report = Report::generate(&database, &lockfile, &report::Settings::default());
+    report.lockfile.path = Some("app/Cargo.lock".into());
+
+    let mut output = vec![];
+    metrics::write(&mut output, &report).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+
+    for line in &[
+        "# TYPE cargo_audit_vulnerabilities gauge",
+        "cargo_audit_vulnerabilities{lockfile=\"app/Cargo.lock\",severity=\"high\"} 1",
+        "cargo_audit_vulnerabilities{lockfile=\"app/Cargo.lock\",severity=\"critical\"} 0",
+        "cargo_audit_vulnerabilities{lockfile=\"app/Cargo.lock\",severity=\"unknown\"} 1",
+        "cargo_audit_warnings{lockfile=\"app/Cargo.lock\",kind=\"unmaintained\"} 0",
+        "cargo_audit_vulnerable_crates{lockfile=\"app/Cargo.lock\"} 2",
+        "cargo_audit_dependencies{lockfile=\"app/Cargo.lock\"} 3",
+        "cargo_audit_database_advisories{lockfile=\"app/Cargo.lock\"} 2",
+    ] {
+        assert!(lines.contains(line), "{} not in:\n{}", line, output);
+    }
+
+    assert_eq!(lines.last(), Some(&"# EOF"));
+}
+
+/// `--format metrics` prints the metrics of the audit, including its outcome
+#[test]
+fn metrics_format() {
+    let db_dir = support::database(&[Advisory::base64()]);
+    let home = tempfile::tempdir().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+        .args(&["--format", "metrics"])
+        .env("CARGO_HOME", home.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let sample = |prefix: &str, suffix: &str| {
+        stdout
+            .lines()
+            .any(|line| line.starts_with(prefix) && line.ends_with(suffix))
+    };
+
+    assert!(
+        sample("cargo_audit_vulnerabilities{", "severity=\"unknown\"} 1"),
+        "{}",
+        stdout
+    );
+    assert!(
+        sample(
+            "cargo_audit_outcome{",
+            "outcome=\"vulnerabilities-found\"} 1"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(sample("cargo_audit_outcome{", "outcome=\"clean\"} 0"));
+    assert!(stdout.contains("# TYPE cargo_audit_last_run_timestamp_seconds gauge"));
+}
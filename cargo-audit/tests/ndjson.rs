@@ -0,0 +1,102 @@
+//! NDJSON report tests
+
+mod support;
+
+use std::{fs, process::Command};
+use support::Advisory;
+
+/// Advisory database with a vulnerability advisory for `foo` and an
+/// unmaintained advisory for `bar`, for versions prior to 0.2.0
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo"),
+        Advisory::new("RUSTSEC-2020-0002", "bar").attributes("informational = \"unmaintained\""),
+    ])
+}
+
+/// Audit a lockfile for `app`, which depends on the given packages, in
+/// NDJSON format, returning the parsed lines
+fn audit(dependencies: &[&str]) -> Vec<serde_json::Value> {
+    let db_dir = database();
+
+    let mut lockfile = format!(
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = {:?}\n\n",
+        dependencies
+    );
+
+    for package in dependencies {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            package
+        ));
+    }
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(&lockfile_path, lockfile).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--format", "ndjson", "--db"])
+        .arg(db_dir.path())
+        .arg("--file")
+        .arg(&lockfile_path)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with('\n'), "{}", stdout);
+
+    stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// Each finding is a line of its own, with the dependency paths to it,
+/// followed by a summary line
+#[test]
+fn one_line_per_finding() {
+    let lines = audit(&["bar", "foo"]);
+    assert_eq!(lines.len(), 3);
+
+    let vulnerability = &lines[0];
+    assert_eq!(vulnerability["kind"], "vulnerability");
+    assert_eq!(vulnerability["advisory"]["id"], "RUSTSEC-2020-0001");
+    assert_eq!(vulnerability["package"]["name"], "foo");
+    assert!(vulnerability["severity"].is_null());
+    assert_eq!(
+        vulnerability["paths"],
+        serde_json::json!([["foo 0.1.0", "app 0.1.0"]])
+    );
+
+    let warning = &lines[1];
+    assert_eq!(warning["kind"], "warning");
+    assert_eq!(warning["warning"], "unmaintained");
+    assert_eq!(warning["advisory"]["id"], "RUSTSEC-2020-0002");
+    assert_eq!(warning["package"]["name"], "bar");
+
+    let summary = &lines[2];
+    assert_eq!(summary["kind"], "summary");
+    assert_eq!(summary["vulnerabilities"], 1);
+    assert_eq!(summary["warnings"], 1);
+    assert_eq!(summary["crates"], 1);
+    assert_eq!(summary["outcome"], "vulnerabilities-found");
+}
+
+/// Clean audits still produce the summary line
+#[test]
+fn summary_only_when_clean() {
+    let lines = audit(&[]);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["kind"], "summary");
+    assert_eq!(lines[0]["vulnerabilities"], 0);
+    assert_eq!(lines[0]["outcome"], "clean");
+}
@@ -0,0 +1,347 @@
+//! Webhook notification tests
+
+mod support;
+
+use cargo_audit::{
+    auditor::Auditor,
+    config::{AuditConfig, NotifyFormat, NotifyOn, OutputFormat},
+    notify::{self, Notifier, SlackMessage},
+};
+use rustsec::Report;
+use std::{
+    fs,
+    io::{Read, Write},
+    net::TcpListener,
+    process::Command,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use support::Advisory;
+
+/// Respond to one request per given status on a local port, returning the
+/// URL to request and the requests received
+fn serve(statuses: &'static [u16]) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for status in statuses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 4096];
+
+            // Read until the body (whose length is given by the headers) is complete
+            loop {
+                let len = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..len]);
+                let text = String::from_utf8_lossy(&request).into_owned();
+
+                if let Some(body_start) = text.find("\r\n\r\n") {
+                    let content_length = text
+                        .lines()
+                        .find_map(|line| {
+                            let line = line.to_ascii_lowercase();
+                            let value = line.strip_prefix("content-length:")?;
+                            value.trim().parse::<usize>().ok()
+                        })
+                        .unwrap_or(0);
+
+                    if request.len() >= body_start + 4 + content_length {
+                        break;
+                    }
+                }
+
+                if len == 0 {
+                    break;
+                }
+            }
+
+            sender
+                .send(String::from_utf8_lossy(&request).into_owned())
+                .unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 {} Test\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+        }
+    });
+
+    (url, receiver)
+}
+
+/// Project with a lockfile depending on vulnerable `foo`, and a database
+/// with an advisory for it
+struct Project {
+    db_dir: tempfile::TempDir,
+    project_dir: tempfile::TempDir,
+}
+
+impl Project {
+    fn new() -> Self {
+        let db_dir =
+            support::database(&[Advisory::new("RUSTSEC-2021-0001", "foo").date("2021-01-01")]);
+
+        let project_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            project_dir.path().join("Cargo.lock"),
+            "[[package]]\n\
+             name = \"foo\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+        )
+        .unwrap();
+
+        Self {
+            db_dir,
+            project_dir,
+        }
+    }
+
+    /// Audit the project, sending the report to the given webhook
+    fn audit(&self, url: &str, required: bool) -> (Report, bool) {
+        self.audit_with(url, required, |_| ())
+    }
+
+    /// Audit the project with the given changes to the configuration,
+    /// sending the report to the given webhook
+    fn audit_with(
+        &self,
+        url: &str,
+        required: bool,
+        configure: impl FnOnce(&mut AuditConfig),
+    ) -> (Report, bool) {
+        let mut config = AuditConfig::default();
+        config.database.path = Some(self.db_dir.path().to_owned());
+        config.database.fetch = false;
+        config.notify.urls = vec![url.to_owned()];
+        config.notify.required = required;
+        config.notify.retries = Some(0);
+        config.output.quiet = true;
+        config.output.format = OutputFormat::Json;
+        config.yanked.enabled = false;
+        config.registry.lookup = false;
+        configure(&mut config);
+
+        let mut auditor = Auditor::new(&config);
+        let report = auditor
+            .audit(Some(&self.project_dir.path().join("Cargo.lock")))
+            .unwrap();
+
+        (report, auditor.notification_failed())
+    }
+}
+
+/// Reports are POSTed as JSON, including the lockfile path and headers with
+/// environment variables substituted
+#[test]
+fn deliver_report() {
+    let project = Project::new();
+    let (url, requests) = serve(&[200]);
+    let (report, failed) = project.audit(&url, true);
+
+    assert!(!failed);
+    assert!(report.lockfile.path.unwrap().ends_with("Cargo.lock"));
+
+    let request = requests.recv().unwrap();
+    assert!(request.starts_with("POST /hook "), "{}", request);
+    assert!(
+        request.contains("content-type: application/json"),
+        "{}",
+        request
+    );
+
+    let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+    let json: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert!(json["lockfile"]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("Cargo.lock"));
+    assert_eq!(
+        json["vulnerabilities"]["list"][0]["advisory"]["id"],
+        "RUSTSEC-2021-0001"
+    );
+}
+
+#[test]
+fn substitute_header_variables() {
+    std::env::set_var("CARGO_AUDIT_TEST_WEBHOOK_TOKEN", "s3cret");
+
+    let (url, requests) = serve(&[204]);
+    let errors = Notifier::new(vec![url])
+        .header("Authorization", "Bearer ${CARGO_AUDIT_TEST_WEBHOOK_TOKEN}")
+        .notify(&report());
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let request = requests.recv().unwrap().to_ascii_lowercase();
+    assert!(
+        request.contains("authorization: bearer s3cret"),
+        "{}",
+        request
+    );
+
+    let errors = Notifier::new(vec!["http://127.0.0.1:1/hook".to_owned()])
+        .header("Authorization", "Bearer ${CARGO_AUDIT_TEST_UNSET_VARIABLE}")
+        .notify(&report());
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]
+        .to_string()
+        .contains("CARGO_AUDIT_TEST_UNSET_VARIABLE"));
+}
+
+/// Server errors are retried, client errors aren't
+#[test]
+fn retry_with_backoff() {
+    let (url, requests) = serve(&[503, 500, 200]);
+    let notifier = Notifier::new(vec![url])
+        .retries(2)
+        .backoff(Duration::from_millis(10));
+
+    assert!(notifier.notify(&report()).is_empty());
+    assert_eq!(requests.iter().count(), 3);
+
+    let (url, requests) = serve(&[401, 200]);
+    let notifier = Notifier::new(vec![url])
+        .retries(2)
+        .backoff(Duration::from_millis(10));
+
+    let errors = notifier.notify(&report());
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].to_string().contains("401"), "{}", errors[0]);
+    assert!(requests.recv_timeout(Duration::from_secs(5)).is_ok());
+    assert!(requests.recv_timeout(Duration::from_millis(100)).is_err());
+}
+
+/// Clean reports are only sent with `--notify-on always`
+#[test]
+fn notify_on() {
+    let notifier = Notifier::new(vec!["http://127.0.0.1:1/hook".to_owned()]);
+    assert!(!notifier.should_notify(&report()));
+    assert!(notifier.on(NotifyOn::Always).should_notify(&report()));
+    assert!(!Notifier::new(vec![])
+        .on(NotifyOn::Always)
+        .should_notify(&report()));
+}
+
+/// With `--notify-on new`, only findings which are new since the recorded
+/// run are sent, and nothing is sent if there are none
+#[test]
+fn notify_on_new() {
+    let project = Project::new();
+    let state_file = project.project_dir.path().join("audit-state.json");
+    let configure = |config: &mut AuditConfig| {
+        config.notify.on = NotifyOn::New;
+        config.state.file = Some(state_file.clone());
+    };
+
+    let (url, requests) = serve(&[200]);
+    let (_, failed) = project.audit_with(&url, true, configure);
+    assert!(!failed);
+
+    let request = requests.recv().unwrap();
+    let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+    let json: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(json["vulnerabilities"]["count"], 1);
+
+    // Undeliverable, so it would fail if it were sent
+    let (report, failed) = project.audit_with("http://127.0.0.1:1/hook", true, configure);
+    assert!(report.vulnerabilities.found);
+    assert!(!failed);
+}
+
+/// Slack-compatible payloads summarize the findings, linking to advisories
+#[test]
+fn slack_payload() {
+    let project = Project::new();
+    let (audited, _) = project.audit("http://127.0.0.1:1/hook", false);
+    let text = SlackMessage::new(&audited).text;
+
+    assert!(
+        text.starts_with("*cargo audit* found 1 vulnerability and 0 warnings in `"),
+        "{}",
+        text
+    );
+    assert!(
+        text.ends_with(
+            "\n\u{2022} <https://rustsec.org/advisories/RUSTSEC-2021-0001|RUSTSEC-2021-0001> \
+             `foo 0.1.0`: Test advisory"
+        ),
+        "{}",
+        text
+    );
+
+    let (url, requests) = serve(&[200]);
+    let errors = Notifier::new(vec![url])
+        .on(NotifyOn::Always)
+        .format(NotifyFormat::Slack)
+        .notify(&report());
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let request = requests.recv().unwrap();
+    let body = &request[request.find("\r\n\r\n").unwrap() + 4..];
+    assert_eq!(
+        body,
+        r#"{"text":"*cargo audit* found no vulnerabilities or warnings"}"#
+    );
+}
+
+/// Delivery failures only fail the audit (with a distinct exit status) if
+/// notifications are required
+#[test]
+fn delivery_failure() {
+    let project = Project::new();
+
+    let (report, failed) = project.audit("http://127.0.0.1:1/hook", false);
+    assert!(!failed);
+    assert!(report.vulnerabilities.found);
+
+    // Don't retry (with backoff) in the project's `audit.toml`
+    let config_dir = project.project_dir.path().join(".cargo");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("audit.toml"), "[notify]\nretries = 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project.project_dir.path())
+        .args(&["audit", "--no-fetch", "--db"])
+        .arg(project.db_dir.path())
+        .args(&[
+            "--notify-url",
+            "http://127.0.0.1:1/hook",
+            "--notify-required",
+        ])
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        output.status.code(),
+        Some(notify::NOTIFY_FAILED_EXIT_CODE),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("couldn't deliver report to http://127.0.0.1:1/hook"),
+        "{}",
+        stderr
+    );
+}
+
+/// Report without any findings
+fn report() -> Report {
+    serde_json::from_str(
+        r#"{
+            "database": {"advisory-count": 0, "last-commit": null, "last-updated": null},
+            "lockfile": {"dependency-count": 0},
+            "settings": {"target_arch": null, "target_os": null, "severity": null, "ignore": [], "informational_warnings": [], "package_scope": null},
+            "vulnerabilities": {"found": false, "count": 0, "list": []},
+            "warnings": {}
+        }"#,
+    )
+    .unwrap()
+}
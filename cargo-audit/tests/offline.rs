@@ -0,0 +1,102 @@
+//! Tests for vendored advisory databases and `--strict-offline`
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Add an entry to a tar archive
+fn add_entry(archive: &mut Vec<u8>, path: &str, typeflag: u8, data: &[u8]) {
+    let mut header = vec![0u8; 512];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+    header[156] = typeflag;
+    header[257..263].copy_from_slice(b"ustar\0");
+
+    archive.extend(header);
+    archive.extend(data);
+    archive.extend(vec![0; (512 - data.len() % 512) % 512]);
+}
+
+/// Write a `.tar.gz` bundle of an advisory database with an advisory for
+/// versions of `base64` prior to 0.5.2
+fn write_bundle(path: &Path) {
+    let advisory = Advisory::base64().markdown();
+
+    let mut tar = vec![];
+    add_entry(&mut tar, "advisory-db-main/", b'5', b"");
+    add_entry(&mut tar, "advisory-db-main/crates/base64/", b'5', b"");
+    add_entry(
+        &mut tar,
+        "advisory-db-main/crates/base64/RUSTSEC-2017-0004.md",
+        b'0',
+        advisory.as_bytes(),
+    );
+    tar.extend(vec![0; 1024]);
+
+    let mut gzip = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    gzip.extend(miniz_oxide::deflate::compress_to_vec(&tar, 6));
+    gzip.extend(&[0; 8]);
+    fs::write(path, gzip).unwrap();
+}
+
+/// Run `cargo audit` on the `base64_vuln` lockfile with the given arguments
+fn cargo_audit(cargo_home: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--color", "never", "--file"])
+        .arg(Path::new("tests/support/base64_vuln/Cargo.lock"))
+        .args(args)
+        .env("CARGO_HOME", cargo_home)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn audit_with_bundle() {
+    let dir = tempfile::tempdir().unwrap();
+    let bundle = dir.path().join("advisory-db.tar.gz");
+    write_bundle(&bundle);
+    let bundle = bundle.to_str().unwrap();
+
+    for _ in 0..2 {
+        let output = cargo_audit(
+            dir.path(),
+            &[
+                "--db-path",
+                bundle,
+                "--strict-offline",
+                "--no-registry-lookup",
+            ],
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(output.status.code(), Some(1), "{:?}", output);
+        assert!(stdout.contains("RUSTSEC-2017-0004"), "{}", stdout);
+    }
+
+    // The bundle is only unpacked once
+    let unpacked = fs::read_dir(dir.path().join("advisory-db-bundles"))
+        .unwrap()
+        .count();
+    assert_eq!(unpacked, 1);
+}
+
+#[test]
+fn strict_offline_refuses_network_access() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = cargo_audit(dir.path(), &["--strict-offline"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(
+        stderr.contains("fetching the advisory database"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--no-registry-lookup"), "{}", stderr);
+    assert!(!dir.path().join("advisory-db").exists());
+}
@@ -0,0 +1,54 @@
+//! OSV output tests
+
+mod support;
+
+use std::{path::Path, process::Command};
+use support::Advisory;
+
+/// Advisory database with an advisory for versions of `base64` prior to 0.5.2
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::base64().attributes("aliases = [\"CVE-2017-1000430\"]")])
+}
+
+#[test]
+fn osv_report() {
+    let db_dir = database();
+    let home = tempfile::tempdir().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+        .args(&["--format", "osv"])
+        .env("CARGO_HOME", home.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let result = &report["results"][0];
+    assert_eq!(result["source"]["type"], "lockfile");
+    assert_eq!(
+        result["source"]["path"],
+        Path::new("tests/support/base64_vuln/Cargo.lock")
+            .display()
+            .to_string()
+    );
+
+    let package = &result["packages"][0];
+    assert_eq!(package["package"]["name"], "base64");
+    assert_eq!(package["package"]["ecosystem"], "crates.io");
+    assert_eq!(
+        package["groups"][0]["ids"],
+        serde_json::json!(["RUSTSEC-2017-0004", "CVE-2017-1000430"])
+    );
+
+    let vulnerability = &package["vulnerabilities"][0];
+    assert_eq!(vulnerability["id"], "RUSTSEC-2017-0004");
+    assert_eq!(
+        vulnerability["affected"][0]["ranges"][0]["events"],
+        serde_json::json!([{ "introduced": "0.5.0" }, { "fixed": "0.5.2" }])
+    );
+}
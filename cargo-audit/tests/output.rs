@@ -0,0 +1,1091 @@
+//! Tests for writing output to STDOUT
+
+mod support;
+
+use cargo_audit::output::BROKEN_PIPE_EXIT_CODE;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+use support::Advisory;
+
+/// Run `cargo audit` with an empty advisory database and the given arguments,
+/// closing STDOUT while it's still running
+fn audit_with_closed_stdout(db_path: &Path, args: &[&str]) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--db"])
+        .arg(db_path)
+        .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+        .args(args)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Close the read end of the pipe before anything is written to it
+    drop(child.stdout.take());
+    child.wait_with_output().unwrap()
+}
+
+/// Exit quietly when the process reading STDOUT goes away
+#[test]
+fn broken_pipe() {
+    let db_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(db_dir.path().join("crates")).unwrap();
+
+    for args in &[&[][..], &["--json", "--quiet"][..]] {
+        let output = audit_with_closed_stdout(db_dir.path(), args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(
+            output.status.code(),
+            Some(BROKEN_PIPE_EXIT_CODE),
+            "{}",
+            stderr
+        );
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+}
+
+/// In JSON mode, STDOUT contains nothing but the report, even when status
+/// messages and warnings are printed
+#[test]
+fn json_report_on_stdout() {
+    let db_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(db_dir.path().join("crates")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--db"])
+        .arg(db_dir.path())
+        .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+        .args(&["--json", "--deny-warnings", "-v"])
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.ends_with("}\n"), "{:?}", stdout);
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert!(report["vulnerabilities"].is_object());
+
+    assert!(stderr.contains("the --deny-warnings flag is deprecated"));
+    assert!(stderr.contains("Scanning"));
+}
+
+/// Packages which share a name and version with a package from another source
+/// are displayed along with their source, as are overridden dependencies
+#[test]
+fn ambiguous_packages_show_source() {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .args(&["--file", "tests/support/patched_fork/Cargo.lock"])
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    let sources: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Source:"))
+        .map(str::trim)
+        .collect();
+    assert_eq!(sources.len(), 2, "{}", stdout);
+
+    // The fork is reported as an overridden dependency, after the vulnerability
+    assert_eq!(
+        sources[0],
+        "registry+https://github.com/rust-lang/crates.io-index"
+    );
+    assert!(sources[1].starts_with("git+https://github.com/example/foo?branch=fix#"));
+    assert!(stdout.contains("overridden dependency, verify manually"));
+    assert!(stdout.contains("└── app 0.1.0"));
+    assert!(stdout.contains("└── legacy 0.1.0"));
+}
+
+/// Related advisories are listed in full reports of advisories (which
+/// several advisories for the same package version are shown in verbose
+/// mode), noting those which were also found
+#[test]
+fn related_advisories() {
+    let db_dir = support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo").attributes("related = []"),
+        Advisory::new("RUSTSEC-2020-0002", "foo")
+            .attributes(r#"related = ["RUSTSEC-2020-0001", "RUSTSEC-2019-0001"]"#),
+    ]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .args(&[
+            "--file",
+            "tests/support/patched_fork/Cargo.lock",
+            "--no-git-crates",
+            "--verbose",
+        ])
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let related: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Related:"))
+        .map(str::trim)
+        .collect();
+
+    assert_eq!(
+        related,
+        [
+            "RUSTSEC-2020-0002 (also found)",
+            "RUSTSEC-2019-0001, RUSTSEC-2020-0001 (also found)"
+        ],
+        "{}",
+        stdout
+    );
+
+    assert!(
+        stdout.contains("2 of these are for related advisories"),
+        "{}",
+        stdout
+    );
+}
+
+/// Vulnerabilities in several versions of a package are shown once per
+/// advisory, with their dependency trees only shown in verbose mode
+#[test]
+fn group_affected_versions() {
+    let db_dir = support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo").versions("patched = [\">= 0.3.0\"]")
+    ]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    let mut lockfile = String::new();
+
+    for version in &["0.1.0", "0.2.0"] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"foo\"\n\
+             version = \"{}\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            version
+        ));
+    }
+
+    fs::write(&lockfile_path, lockfile).unwrap();
+
+    let audit = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .arg("--file")
+            .arg(&lockfile_path)
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    };
+
+    let output = audit(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+
+    let attrs = |name: &str| -> Vec<String> {
+        stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix(name))
+            .map(|value| value.trim().to_owned())
+            .collect()
+    };
+
+    assert_eq!(attrs("ID:"), ["RUSTSEC-2020-0001"], "{}", stdout);
+    assert_eq!(attrs("Versions:"), ["0.1.0, 0.2.0"]);
+    assert_eq!(
+        attrs("Dependency tree:"),
+        ["omitted for 2 versions (use --verbose to show them)"]
+    );
+    assert!(
+        format!("{}{}", stdout, stderr)
+            .contains("1 vulnerability found, affecting 2 package versions!"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stdout
+            .contains("Affected 2 package versions of 1 crate, pulled in by 2 direct dependencies"),
+        "{}",
+        stdout
+    );
+
+    let output = audit(&["--verbose"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("Dependency tree:").count(), 2, "{}", stdout);
+    assert!(stdout.contains("foo 0.1.0") && stdout.contains("foo 0.2.0"));
+
+    let output = audit(&["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["vulnerabilities"]["count"], 2);
+    assert_eq!(
+        report["vulnerabilities"]["groups"],
+        serde_json::json!([{
+            "advisory": "RUSTSEC-2020-0001",
+            "package": "foo",
+            "versions": ["0.1.0", "0.2.0"]
+        }])
+    );
+}
+
+/// Several advisories for a single package version are shown in one block,
+/// listing each advisory's ID and title under the package and its dependency
+/// tree, unless in verbose mode
+#[test]
+fn group_package_advisories() {
+    let db_dir = support::database(&[
+        Advisory::base64().title("Integer overflow"),
+        Advisory::base64()
+            .id("RUSTSEC-2017-0005")
+            .title("Out-of-bounds read")
+            .versions("patched = [\">= 0.6.0\"]"),
+    ]);
+
+    let audit = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(&["--width", "200"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = audit(&[]);
+    assert_eq!(stdout.matches("Crate:").count(), 1, "{}", stdout);
+    assert_eq!(stdout.matches("Dependency tree:").count(), 1, "{}", stdout);
+    assert!(
+        stdout.contains("Advisories:    RUSTSEC-2017-0004 Integer overflow\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("               RUSTSEC-2017-0005 Out-of-bounds read\n"),
+        "{}",
+        stdout
+    );
+
+    let solutions: Vec<_> = stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("Solution:"))
+        .take(2)
+        .map(|line| line.trim_start_matches("Solution:").trim())
+        .collect();
+
+    assert_eq!(
+        solutions,
+        [
+            "RUSTSEC-2017-0004: You are on 0.5.1 - upgrade to at least 0.5.2",
+            "RUSTSEC-2017-0005: You are on 0.5.1 - upgrade to at least 0.6.0 (a major bump)"
+        ],
+        "{}",
+        stdout
+    );
+
+    let stdout = audit(&["--verbose"]);
+    assert_eq!(stdout.matches("Crate:").count(), 2, "{}", stdout);
+    assert!(!stdout.contains("Advisories:"), "{}", stdout);
+}
+
+/// Long attributes are wrapped to `--width` with a hanging indent, and
+/// `--show-description` adds the advisory's description
+#[test]
+fn wrap_to_width() {
+    let db_dir = support::database(&[Advisory::base64()
+        .title("Integer overflow leads to heap-based buffer overflow in encode_config_buf")
+        .description(
+            "Affected versions of this crate suffered from an integer overflow bug.\n\n\
+             - `encode_config_buf`",
+        )]);
+
+    let audit = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = audit(&["--width", "50"]);
+    assert!(
+        stdout.contains(
+            "Title:         Integer overflow leads to\n\
+             \x20              heap-based buffer overflow in\n\
+             \x20              encode_config_buf\n"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("Description:"), "{}", stdout);
+
+    let stdout = audit(&["--width", "50", "--show-description"]);
+    assert!(
+        stdout.contains(
+            "Description:   Affected versions of this crate\n\
+             \x20              suffered from an integer overflow\n\
+             \x20              bug.\n\
+             \x20              \n\
+             \x20              - encode_config_buf\n"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+/// The CVSS vector of advisories which have one is shown in (verbose, as
+/// both are for the same package version) terminal reports, and included as
+/// a string in JSON reports (or `null`)
+#[test]
+fn cvss_vector() {
+    let vector = "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H";
+    let db_dir = support::database(&[
+        Advisory::base64().attributes(&format!("cvss = \"{}\"", vector)),
+        Advisory::base64().id("RUSTSEC-2017-0005"),
+    ]);
+
+    let audit = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = audit(&["--verbose"]);
+    let vectors: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("CVSS:"))
+        .map(str::trim)
+        .collect();
+    assert_eq!(vectors, [vector], "{}", stdout);
+
+    let report: serde_json::Value = serde_json::from_str(&audit(&["--json"])).unwrap();
+    let list = report["vulnerabilities"]["list"].as_array().unwrap();
+    assert_eq!(list[0]["advisory"]["cvss"], vector);
+    assert!(list[1]["advisory"]["cvss"].is_null());
+}
+
+/// Terminal reports show the severity and CVSS base score of each
+/// vulnerability, and list the most severe first (unless sorted otherwise)
+#[test]
+fn severity_order() {
+    let db_dir = tempfile::tempdir().unwrap();
+
+    for (id, impact) in &[
+        ("RUSTSEC-2017-0004", Some("C:L/I:N/A:N")),
+        ("RUSTSEC-2017-0005", None),
+        ("RUSTSEC-2017-0006", Some("C:H/I:H/A:H")),
+        ("RUSTSEC-2017-0007", Some("C:H/I:N/A:N")),
+    ] {
+        let cvss = impact
+            .map(|impact| format!("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/{}\"", impact))
+            .unwrap_or_default();
+
+        Advisory::new(id, "base64")
+            .date("2017-05-03")
+            .attributes(&cvss)
+            .versions("patched = [\">= 0.5.2\"]")
+            .write(db_dir.path());
+    }
+
+    let audit = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let lines = |stdout: &str, attr: &str| -> Vec<String> {
+        stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix(attr))
+            .map(|line| line.trim().to_owned())
+            .collect()
+    };
+
+    let stdout = audit(&[]);
+    assert!(
+        stdout.contains("RUSTSEC-2017-0006 Test advisory (critical 9.8)"),
+        "{}",
+        stdout
+    );
+
+    let stdout = audit(&["--verbose"]);
+    assert_eq!(
+        lines(&stdout, "Severity:"),
+        ["critical (9.8)", "high (7.5)", "medium (5.3)"],
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        lines(&stdout, "ID:"),
+        [
+            "RUSTSEC-2017-0006",
+            "RUSTSEC-2017-0007",
+            "RUSTSEC-2017-0004",
+            "RUSTSEC-2017-0005"
+        ],
+        "{}",
+        stdout
+    );
+
+    let stdout = audit(&["--verbose", "--sort", "id"]);
+    assert_eq!(
+        lines(&stdout, "ID:"),
+        [
+            "RUSTSEC-2017-0004",
+            "RUSTSEC-2017-0005",
+            "RUSTSEC-2017-0006",
+            "RUSTSEC-2017-0007"
+        ],
+        "{}",
+        stdout
+    );
+}
+
+/// All of an advisory's reference URLs are listed in verbose mode, starting
+/// with its announcement URL, and its aliases are always listed
+#[test]
+fn references() {
+    let db_dir = support::database(&[Advisory::base64().attributes(
+        "url = \"https://github.com/example/base64/issues/1\"\n\
+         aliases = [\"CVE-2017-1000001\", \"GHSA-jfh8-c2jp-5v3q\"]\n\
+         references = [\"https://github.com/example/base64/pull/2\", \"https://github.com/example/base64/issues/1\"]",
+    )]);
+    let audit = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = audit(&["--verbose"]);
+    assert!(
+        stdout.contains(
+            "References:    https://github.com/example/base64/issues/1\n\
+             \x20              https://github.com/example/base64/pull/2\n"
+        ),
+        "{}",
+        stdout
+    );
+
+    let stdout = audit(&[]);
+    assert!(!stdout.contains("References:"));
+    assert!(
+        stdout.contains("Aliases:       CVE-2017-1000001, GHSA-jfh8-c2jp-5v3q\n"),
+        "{}",
+        stdout
+    );
+
+    let report: serde_json::Value = serde_json::from_str(&audit(&["--json"])).unwrap();
+    let advisory = &report["vulnerabilities"]["list"][0]["advisory"];
+    assert_eq!(
+        advisory["url"],
+        "https://github.com/example/base64/issues/1"
+    );
+    assert_eq!(advisory["references"].as_array().unwrap().len(), 2);
+}
+
+/// Which advisory data was used is stated before the report, dated by its
+/// newest advisory, and included in JSON reports instead
+#[test]
+fn database_banner() {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")]);
+
+    let audit = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    };
+
+    let output = audit(&[]);
+    let all = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        all.contains("advisory database: 1 advisory, last updated 2020-01-01"),
+        "{}",
+        all
+    );
+
+    let output = audit(&["--quiet"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("advisory database"));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("advisory database"));
+
+    let output = audit(&["--json"]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("advisory database"));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["database"]["advisory-count"], 1);
+    assert_eq!(report["database"]["newest-advisory"], "2020-01-01");
+}
+
+/// Findings list the workspace members they affect, which are the local
+/// roots of the dependency graph when there's no `Cargo.toml` to list them,
+/// and whether they're in direct dependencies of any of them
+#[test]
+fn affected_members() {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"api-gateway\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"bar\"]\n\n\
+         [[package]]\n\
+         name = \"bar\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+         dependencies = [\"foo\"]\n\n\
+         [[package]]\n\
+         name = \"billing-service\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"foo\"]\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"reports\"\n\
+         version = \"0.1.0\"\n\n\
+         [[package]]\n\
+         name = \"unused\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+         dependencies = [\"foo\"]\n",
+    )
+    .unwrap();
+
+    let audit = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .arg("--file")
+            .arg(&lockfile_path)
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = audit(&[]);
+    assert!(
+        stdout.contains("Affects members: api-gateway, billing-service\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Dependency:    direct\n"), "{}", stdout);
+
+    let report: serde_json::Value = serde_json::from_str(&audit(&["--json"])).unwrap();
+    assert_eq!(
+        report["vulnerabilities"]["list"][0]["members"],
+        serde_json::json!(["api-gateway", "billing-service"])
+    );
+    assert_eq!(report["vulnerabilities"]["list"][0]["direct"], true);
+}
+
+/// Advisory IDs and URLs are rendered as hyperlinks when enabled, and as
+/// plain text otherwise (including by default, when STDOUT isn't a terminal)
+#[test]
+fn hyperlinks() {
+    let db_dir = support::database(&[Advisory::base64()]);
+
+    let audit = |args: &[&str], force: Option<&str>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_cargo-audit"));
+        command
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .env_remove("FORCE_HYPERLINK");
+
+        if let Some(force) = force {
+            command.env("FORCE_HYPERLINK", force);
+        }
+
+        String::from_utf8_lossy(&command.output().unwrap().stdout).into_owned()
+    };
+
+    let url = "https://rustsec.org/advisories/RUSTSEC-2017-0004";
+    let linked = format!(
+        "ID:            \x1b]8;;{}\x1b\\RUSTSEC-2017-0004\x1b]8;;\x1b\\\n\
+         URL:           \x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\\n",
+        url, url, url
+    );
+
+    for (args, force) in &[
+        (&["--hyperlinks", "always"][..], None),
+        (&[][..], Some("1")),
+    ] {
+        let stdout = audit(args, *force);
+        assert!(stdout.contains(&linked), "{}", stdout);
+    }
+
+    for (args, force) in &[(&[][..], None), (&["--hyperlinks", "never"][..], Some("1"))] {
+        let stdout = audit(args, *force);
+        assert!(!stdout.contains('\x1b'), "{}", stdout);
+        assert!(
+            stdout.contains(&format!("URL:           {}\n", url)),
+            "{}",
+            stdout
+        );
+    }
+}
+
+/// Colors are only used on terminals (or when asked for), and plain reports
+/// have neither colors nor wrapped attributes
+#[test]
+fn colors_and_plain_reports() {
+    let db_dir = support::database(&[Advisory::base64()
+        .title("Integer overflow leads to heap-based buffer overflow in encode_config_buf")]);
+
+    let audit = |args: &[&str], envs: &[(&str, &str)]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--width", "60", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("TERM", "xterm-256color")
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .env_remove("NO_COLOR")
+            .envs(envs.iter().copied())
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let title = "Integer overflow leads to heap-based buffer overflow in encode_config_buf";
+
+    // STDOUT isn't a terminal
+    let stdout = audit(&[], &[]);
+    assert!(!stdout.contains('\x1b'), "{}", stdout);
+    assert!(!stdout.contains(title), "{}", stdout);
+
+    for (args, envs) in &[
+        (&["--color", "always"][..], &[][..]),
+        (&["--color", "always"][..], &[("NO_COLOR", "1")][..]),
+        (&[][..], &[("CARGO_AUDIT_COLOR", "always")][..]),
+    ] {
+        let stdout = audit(args, envs);
+        assert!(stdout.contains("\x1b["), "{}", stdout);
+    }
+
+    for (args, envs) in &[
+        (&["--plain", "--color", "always"][..], &[][..]),
+        (
+            &["--color", "always"][..],
+            &[("CARGO_AUDIT_PLAIN", "true")][..],
+        ),
+    ] {
+        let stdout = audit(args, envs);
+        assert!(!stdout.contains('\x1b'), "{}", stdout);
+        assert!(stdout.contains(&format!("{}\n", title)), "{}", stdout);
+    }
+}
+
+/// Warnings for the same advisory about several versions of a package are
+/// shown once, and count once towards the warning summary and deny policy
+#[test]
+fn group_warned_versions() {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")
+        .attributes("informational = \"unmaintained\"")
+        .versions("patched = []")]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    let mut lockfile = String::new();
+
+    for version in &["0.1.0", "0.2.0", "1.0.0"] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"foo\"\n\
+             version = \"{}\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            version
+        ));
+    }
+
+    fs::write(&lockfile_path, lockfile).unwrap();
+
+    let audit = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .arg("--file")
+            .arg(&lockfile_path)
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    };
+
+    let output = audit(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert_eq!(stdout.matches("ID:").count(), 1, "{}", stdout);
+    assert!(
+        stdout.contains("Versions:      0.1.0, 0.2.0, 1.0.0"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("1 allowed warning found, affecting 3 package versions"),
+        "{}",
+        stdout
+    );
+
+    let output = audit(&["--deny", "unmaintained"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("1 denied warning found, affecting 3 package versions!"),
+        "{}",
+        stderr
+    );
+
+    let output = audit(&["--format", "badge"]);
+    let badge: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(badge["message"], "1 warning");
+}
+
+/// Warnings are shown in sections for each kind, ordered by how serious
+/// their implications are, and counted by kind
+#[test]
+fn warning_sections() {
+    let db_dir = support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo")
+            .attributes("informational = \"unmaintained\"")
+            .versions("patched = []"),
+        Advisory::new("RUSTSEC-2020-0002", "bar")
+            .attributes("informational = \"unsound\"")
+            .versions("patched = []"),
+    ]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    let mut lockfile = String::new();
+
+    for (package, version) in &[("bar", "0.1.0"), ("foo", "0.1.0"), ("foo", "0.2.0")] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"{}\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            package, version
+        ));
+    }
+
+    fs::write(&lockfile_path, lockfile).unwrap();
+
+    let audit = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .arg("--file")
+            .arg(&lockfile_path)
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .env("CARGO_AUDIT_INFORMATIONAL_WARNINGS", "unmaintained,unsound")
+            .output()
+            .unwrap()
+    };
+
+    let output = audit(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+
+    let unsound = stdout.find("Unsound crates: 1 warning").expect(&stdout);
+    let unmaintained = stdout
+        .find("Unmaintained crates: 1 warning, affecting 2 package versions")
+        .expect(&stdout);
+    assert!(unsound < unmaintained, "{}", stdout);
+    assert!(
+        stdout.contains(
+            "2 allowed warnings found (1 unsound, 1 unmaintained), affecting 3 package versions"
+        ),
+        "{}",
+        stdout
+    );
+
+    let output = audit(&["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let counts = &report["summary"]["warning-kinds"];
+    assert_eq!(counts["unsound"]["findings"], 1);
+    assert_eq!(counts["unmaintained"]["findings"], 1);
+    assert_eq!(counts["unmaintained"]["package-versions"], 2);
+}
+
+/// Warnings show the inverse dependency tree of the package warned about,
+/// like vulnerabilities do
+#[test]
+fn warning_dependency_tree() {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")
+        .attributes("informational = \"unmaintained\"")
+        .versions("patched = []")]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"foo\"]\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .arg("--file")
+        .arg(&lockfile_path)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert_eq!(stdout.matches("Dependency tree:").count(), 1, "{}", stdout);
+    assert!(stdout.contains("foo 0.1.0\n└── app 0.1.0"), "{}", stdout);
+}
+
+/// The inventory lists the audited packages and counts the skipped ones
+#[test]
+fn include_inventory() {
+    let db_dir = tempfile::tempdir().unwrap();
+
+    let audit = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    };
+
+    let output = audit(&["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report.get("inventory").is_none());
+
+    let output = audit(&["--json", "--include-inventory"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let inventory = &report["inventory"];
+    let names: Vec<_> = inventory["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|package| package["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["base64", "byteorder"]);
+    assert_eq!(inventory["packages"][0]["version"], "0.5.1");
+    assert!(inventory["packages"][0]["checksum"].is_string());
+    assert_eq!(inventory["skipped"]["source-scope"], 1);
+    assert_eq!(inventory["skipped"]["git"], 0);
+    assert!(inventory["skipped"]["yanked-check"].is_null());
+
+    let output = audit(&["--include-inventory"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("2 packages audited; skipped 0 not compiled with the selected features"),
+        "{}",
+        stdout
+    );
+}
+
+/// Dependency trees can be limited to a depth, or to the shortest path from
+/// the affected package to a workspace member
+#[test]
+fn tree_depth_and_shortest_path() {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")]);
+
+    // `app` depends on `foo` via `a` and `b`, and (more directly) via `c`
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    let mut lockfile = String::from(
+        "[[package]]\n\
+         name = \"app\"\n\
+         version = \"0.1.0\"\n\
+         dependencies = [\"a\", \"c\"]\n\n",
+    );
+
+    for (name, dependency) in &[("a", "b"), ("b", "foo"), ("c", "foo"), ("foo", "")] {
+        lockfile.push_str(&format!(
+            "[[package]]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+            name
+        ));
+
+        if !dependency.is_empty() {
+            lockfile.push_str(&format!("dependencies = [\"{}\"]\n", dependency));
+        }
+
+        lockfile.push('\n');
+    }
+
+    fs::write(&lockfile_path, lockfile).unwrap();
+
+    let tree = |args: &[&str]| -> Vec<String> {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .arg("--file")
+            .arg(&lockfile_path)
+            .args(args)
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip_while(|line| !line.starts_with("Dependency tree:"))
+            .skip(1)
+            .take_while(|line| line.ends_with(" 0.1.0") || line.ends_with("..."))
+            .map(ToOwned::to_owned)
+            .collect()
+    };
+
+    let full = tree(&[]);
+    assert!(
+        full.iter().any(|line| line.ends_with("a 0.1.0")),
+        "{:?}",
+        full
+    );
+
+    let shallow = tree(&["--tree-depth", "1"]);
+    assert_eq!(shallow.len(), 5, "{:?}", shallow);
+    assert!(
+        !shallow.iter().any(|line| line.contains("app")),
+        "{:?}",
+        shallow
+    );
+    assert_eq!(
+        shallow.iter().filter(|line| line.ends_with("...")).count(),
+        2
+    );
+
+    assert_eq!(
+        tree(&["--shortest-path"]),
+        ["foo 0.1.0", "└── c 0.1.0", "    └── app 0.1.0"]
+    );
+}
+
+/// Findings in a lockfile whose dependency graph is invalid are still
+/// reported (without dependency trees) before the error, rather than panicking
+#[test]
+fn invalid_dependency_tree_report() {
+    let db_dir = support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")]);
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(
+        &lockfile_path,
+        "[[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+         checksum = \"0000000000000000000000000000000000000000000000000000000000000000\"\n\
+         dependencies = [\"bar 0.2.0\"]\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .arg("--file")
+        .arg(&lockfile_path)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+    assert!(!stdout.contains("Dependency tree:"), "{}", stdout);
+    assert!(stderr.contains("which has no package entry"), "{}", stderr);
+}
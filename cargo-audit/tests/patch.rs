@@ -0,0 +1,116 @@
+//! Git patch tests
+
+mod support;
+
+use cargo_audit::patch::{self, GitPatch, PatchEditor};
+use rustsec::Advisory;
+use std::fs;
+
+/// Parse an advisory for `foo` with the given URL and references
+fn parse_advisory(url: &str, references: &[&str]) -> Advisory {
+    support::Advisory::new("RUSTSEC-2021-0001", "foo")
+        .date("2021-01-01")
+        .attributes(&format!("url = {:?}\nreferences = {:?}", url, references))
+        .versions("patched = []")
+        .markdown()
+        .parse()
+        .unwrap()
+}
+
+/// Fixing commits are found in the advisory's links, and preferred over
+/// branches
+#[test]
+fn find_fixing_commit() {
+    let advisory = parse_advisory(
+        "https://github.com/example/foo/issues/1",
+        &[
+            "https://github.com/example/foo/tree/fix/overflow",
+            "https://github.com/example/foo/pull/2/commits/0123abcd",
+        ],
+    );
+
+    let patch = GitPatch::from_advisory(&advisory.metadata).unwrap();
+    assert_eq!(patch.git, "https://github.com/example/foo");
+    assert_eq!(patch.rev.as_deref(), Some("0123abcd"));
+    assert_eq!(patch.branch, None);
+    assert_eq!(
+        patch.to_toml(),
+        r#"foo = { git = "https://github.com/example/foo", rev = "0123abcd" }"#
+    );
+
+    let advisory = parse_advisory(
+        "https://gitlab.com/group/foo/-/tree/fix/overflow",
+        &["https://example.com/blog"],
+    );
+    let patch = GitPatch::from_advisory(&advisory.metadata).unwrap();
+    assert_eq!(patch.git, "https://gitlab.com/group/foo");
+    assert_eq!(patch.branch.as_deref(), Some("fix/overflow"));
+
+    let advisory = parse_advisory("https://github.com/example/foo/issues/1", &[]);
+    assert!(GitPatch::from_advisory(&advisory.metadata).is_none());
+}
+
+/// Patches are added without disturbing the rest of the manifest, and
+/// existing patches for the same crate are never replaced
+#[test]
+fn add_patch_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = dir.path().join("Cargo.toml");
+    let manifest = "[package]\n\
+                    name = \"app\" # the app\n\
+                    version = \"0.1.0\"\n\n\
+                    [dependencies]\n\
+                    foo  =  \"0.1\"\n";
+    fs::write(&manifest_path, manifest).unwrap();
+
+    let advisory = parse_advisory(
+        "https://github.com/example/foo/commit/0123abcd",
+        &["https://github.com/example/bar/commit/4567cdef"],
+    );
+    let patch = GitPatch::from_advisory(&advisory.metadata).unwrap();
+
+    let mut editor = PatchEditor::open(&manifest_path).unwrap();
+    editor.add(&patch).unwrap();
+    editor.save().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&manifest_path).unwrap(),
+        format!("{}\n[patch.crates-io]\n{}\n", manifest, patch.to_toml())
+    );
+
+    let mut editor = PatchEditor::open(&manifest_path).unwrap();
+    let err = editor.add(&patch).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("already has a [patch.crates-io] entry for foo"),
+        "{}",
+        err
+    );
+}
+
+/// Patches go in the workspace's manifest, which cargo reads them from
+#[test]
+fn find_workspace_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let member_dir = dir.path().join("member");
+    fs::create_dir_all(&member_dir).unwrap();
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        "[workspace]\nmembers = [\"member\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        member_dir.join("Cargo.toml"),
+        "[package]\nname = \"member\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        patch::workspace_manifest(&member_dir.join("Cargo.toml")),
+        dir.path().canonicalize().unwrap().join("Cargo.toml")
+    );
+    assert_eq!(
+        patch::workspace_manifest(&dir.path().join("Cargo.toml")),
+        dir.path().join("Cargo.toml")
+    );
+}
@@ -0,0 +1,70 @@
+//! Tests for the progress bar and options for fetching the advisory database
+
+use cargo_audit::{config::AuditConfig, progress};
+use rustsec::repository::Progress;
+
+/// Git fetches show objects received, then deltas resolved
+#[test]
+fn render_git_progress() {
+    let mut progress = Progress {
+        received_objects: 50,
+        total_objects: 200,
+        received_bytes: 3 * 1024 * 1024,
+        ..Progress::default()
+    };
+
+    assert_eq!(
+        progress::render(&progress),
+        "Receiving objects [=======>                      ] 50/200 (3.00 MiB)"
+    );
+
+    progress.received_objects = 200;
+    progress.total_deltas = 10;
+    progress.indexed_deltas = 10;
+
+    assert_eq!(
+        progress::render(&progress),
+        " Resolving deltas [==============================] 10/10"
+    );
+}
+
+/// Tarball downloads show bytes received, out of the total if it's known
+#[test]
+fn render_download_progress() {
+    let mut progress = Progress {
+        received_bytes: 512 * 1024,
+        total_bytes: Some(1024 * 1024),
+        ..Progress::default()
+    };
+
+    assert_eq!(
+        progress::render(&progress),
+        "      Downloading [===============>              ] 0.50 MiB/1.00 MiB"
+    );
+
+    progress.total_bytes = None;
+    assert_eq!(progress::render(&progress), "      Downloading 0.50 MiB");
+}
+
+/// Timeouts and proxies can be configured in the environment
+#[test]
+fn configured_fetch_options() {
+    let mut config = AuditConfig::default();
+    assert_eq!(
+        format!("{:?}", config.net.fetch_options()),
+        "FetchOptions { timeout: None, proxy: None, progress: false }"
+    );
+
+    config
+        .override_from_vars(vec![
+            ("CARGO_AUDIT_NET_TIMEOUT_SECS", "30"),
+            ("CARGO_AUDIT_NET_PROXY", "http://proxy.example.com:8080"),
+        ])
+        .unwrap();
+
+    assert_eq!(config.net.timeout_secs, Some(30));
+    assert_eq!(
+        format!("{:?}", config.net.fetch_options()),
+        "FetchOptions { timeout: Some(30s), proxy: Some(\"http://proxy.example.com:8080\"), progress: false }"
+    );
+}
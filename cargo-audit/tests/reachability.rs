@@ -0,0 +1,134 @@
+//! Tests for hinting whether affected functions are referenced
+
+mod support;
+
+use cargo_audit::reachability::Sources;
+use rustsec::advisory::affected::FunctionPath;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Lockfile for `app`, which depends on `foo-bar` 0.1.0
+const LOCKFILE: &str = r#"[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["foo-bar"]
+
+[[package]]
+name = "foo-bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+/// Advisory database with an advisory for versions of `foo-bar` prior to
+/// 0.2.0, affecting `foo_bar::parse::parse_header`
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo-bar").tables(
+        "[affected]\n\
+         functions = { \"foo_bar::parse::parse_header\" = [\"< 0.2.0\"] }\n",
+    )])
+}
+
+/// Write a workspace with the given `src/main.rs`
+fn write_workspace(dir: &Path, main: &str) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("Cargo.lock"), LOCKFILE).unwrap();
+    fs::write(dir.join("src").join("main.rs"), main).unwrap();
+}
+
+/// Audit the workspace in the given directory with `--reachability`
+fn audit(db_path: &Path, dir: &Path) -> Output {
+    let home = tempfile::tempdir().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--json", "--reachability", "--db"])
+        .arg(db_path)
+        .arg("--file")
+        .arg(dir.join("Cargo.lock"))
+        .env("CARGO_HOME", home.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Functions are referenced by name, outside of comments, in files which
+/// mention their crate
+#[test]
+fn find_references() {
+    let dir = tempfile::tempdir().unwrap();
+    write_workspace(
+        dir.path(),
+        "use foo_bar::parse;\n\
+         \n\
+         fn main() {\n    \
+             // parse_header(\"commented out\");\n    \
+             parse::parse_header(\"Host\");\n    \
+             parse_header_fast();\n\
+         }\n",
+    );
+    fs::create_dir_all(dir.path().join("target")).unwrap();
+    fs::write(
+        dir.path().join("target").join("generated.rs"),
+        "use foo_bar::parse::parse_header;\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src").join("other.rs"),
+        "fn parse_header() {}\n",
+    )
+    .unwrap();
+
+    let sources = Sources::load(dir.path()).unwrap();
+    assert_eq!(sources.len(), 2);
+
+    let function: FunctionPath = "foo_bar::parse::parse_header".parse().unwrap();
+    let reachability = sources.reachability(&[function.clone()]);
+    assert!(reachability.referenced);
+    assert_eq!(reachability.references.len(), 1);
+    assert_eq!(reachability.references[0].function, function);
+    assert_eq!(
+        reachability.references[0].path,
+        Path::new("src").join("main.rs")
+    );
+    assert_eq!(reachability.references[0].line, 5);
+
+    let function: FunctionPath = "foo_bar::parse::parse_body".parse().unwrap();
+    assert!(!sources.reachability(&[function]).referenced);
+}
+
+/// Vulnerabilities are annotated with whether their affected functions are
+/// referenced
+#[test]
+fn reachability_hints() {
+    let db_dir = database();
+
+    let dir = tempfile::tempdir().unwrap();
+    write_workspace(
+        dir.path(),
+        "fn main() {\n    foo_bar::parse::parse_header(\"Host\");\n}\n",
+    );
+
+    let output = audit(db_dir.path(), dir.path());
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let reachability = &report["vulnerabilities"]["list"][0]["reachability"];
+    assert_eq!(reachability["referenced"], true, "{}", report);
+    assert_eq!(
+        reachability["references"][0]["function"],
+        "foo_bar::parse::parse_header"
+    );
+    assert_eq!(reachability["references"][0]["line"], 2);
+
+    write_workspace(dir.path(), "fn main() {}\n");
+
+    let output = audit(db_dir.path(), dir.path());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let reachability = &report["vulnerabilities"]["list"][0]["reachability"];
+    assert_eq!(reachability["referenced"], false, "{}", report);
+    assert!(reachability.get("references").is_none());
+}
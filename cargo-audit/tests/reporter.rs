@@ -0,0 +1,165 @@
+//! Tests for custom report renderers
+
+mod support;
+
+use cargo_audit::reporter::{self, ReportRenderer};
+use rustsec::{
+    cargo_lock::dependency::Tree, lockfile::Lockfile, report, Database, Report, Vulnerability,
+    Warning,
+};
+use std::{
+    fs,
+    io::{self, Write},
+    process::Command,
+};
+use support::Advisory;
+
+/// Lockfile for `app`, which depends on `foo` and `bar`
+const LOCKFILE: &str = r#"[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["bar", "foo"]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+/// A vulnerability advisory for `foo` and an unmaintained advisory for `bar`,
+/// for versions prior to 0.2.0
+fn advisories() -> Vec<Advisory> {
+    vec![
+        Advisory::new("RUSTSEC-2020-0001", "foo"),
+        Advisory::new("RUSTSEC-2020-0002", "bar").attributes("informational = \"unmaintained\""),
+    ]
+}
+
+/// Renderer listing each finding on a line of its own
+struct Lines;
+
+impl ReportRenderer for Lines {
+    fn begin_report(&mut self, w: &mut dyn Write, report: &Report) -> io::Result<()> {
+        writeln!(w, "{} vulnerabilities:", report.vulnerabilities.count)
+    }
+
+    fn render_vulnerability(
+        &mut self,
+        w: &mut dyn Write,
+        vulnerability: &Vulnerability,
+        _tree: Option<&Tree>,
+    ) -> io::Result<()> {
+        writeln!(
+            w,
+            "{} {}",
+            vulnerability.advisory.id, vulnerability.package.name
+        )
+    }
+
+    fn render_warning(
+        &mut self,
+        w: &mut dyn Write,
+        warning: &Warning,
+        _tree: Option<&Tree>,
+    ) -> io::Result<()> {
+        writeln!(w, "{} {}", warning.kind, warning.package.name)
+    }
+
+    fn end_report(&mut self, w: &mut dyn Write, _report: &Report) -> io::Result<()> {
+        writeln!(w, "done")
+    }
+}
+
+/// Renderers are called for the start of the report, each finding, and its
+/// end
+#[test]
+fn custom_renderer() {
+    let advisories: Vec<String> = advisories().iter().map(Advisory::markdown).collect();
+    let advisories: Vec<&str> = advisories.iter().map(String::as_str).collect();
+    let database = Database::from_markdown(&advisories).unwrap();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let settings = report::Settings {
+        informational_warnings: vec![rustsec::advisory::Informational::Unmaintained],
+        ..report::Settings::default()
+    };
+    let report = Report::generate(&database, &lockfile, &settings);
+
+    let mut output = vec![];
+    reporter::render(&mut Lines, &mut output, &report, None).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "1 vulnerabilities:\n\
+         RUSTSEC-2020-0001 foo\n\
+         unmaintained bar\n\
+         done\n"
+    );
+}
+
+/// Audit the lockfile with the given reporter program and arguments,
+/// returning its output, or STDERR if the audit couldn't be reported
+fn audit(reporter: &[&str]) -> Result<String, String> {
+    let db_dir = support::database(&advisories());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    fs::write(&lockfile_path, LOCKFILE).unwrap();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cargo-audit"));
+    command.args(&["audit", "--no-fetch", "--reporter", reporter[0]]);
+
+    for arg in &reporter[1..] {
+        command.arg(format!("--reporter-arg={}", arg));
+    }
+
+    let output = command
+        .arg("--db")
+        .arg(db_dir.path())
+        .arg("--file")
+        .arg(&lockfile_path)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+    if stderr.contains("couldn't write report") {
+        Err(stderr)
+    } else {
+        assert_eq!(output.status.code(), Some(1), "{}", stderr);
+        Ok(stdout)
+    }
+}
+
+/// Reporter commands read a line of NDJSON for each finding and the summary,
+/// and their output is the report
+#[cfg(unix)]
+#[test]
+fn reporter_command() {
+    assert_eq!(audit(&["grep", "-c", "kind"]).unwrap(), "3\n");
+
+    // Arguments are passed as they are, rather than split on whitespace
+    assert_eq!(audit(&["sh", "-c", "wc -l | tr -d ' '"]).unwrap(), "3\n");
+
+    let stdout = audit(&["head", "-n", "1"]).unwrap();
+    let line: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(line["kind"], "vulnerability");
+    assert_eq!(line["advisory"]["id"], "RUSTSEC-2020-0001");
+    assert_eq!(line["direct"], true);
+
+    let stderr = audit(&["false"]).unwrap_err();
+    assert!(stderr.contains("reporter `false` failed"), "{}", stderr);
+
+    let stderr = audit(&["no-such-reporter-command"]).unwrap_err();
+    assert!(
+        stderr.contains("reporter `no-such-reporter-command` couldn't be run"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,156 @@
+//! Retry policy tests
+
+use cargo_audit::{
+    config::AuditConfig,
+    index::{self, Client},
+    retry::{Failure, Retry},
+};
+use rustsec::error::{Error, ErrorKind};
+use std::{
+    cell::Cell,
+    io::{Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// Respond to requests on a local port with the given statuses in turn
+/// (serving an empty index file for successful ones), returning the URL of
+/// the index and the request lines received
+fn serve(statuses: &[&'static str]) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/index/", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    let statuses = statuses.to_vec();
+
+    thread::spawn(move || {
+        for status in statuses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let len = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..len]).into_owned();
+
+            sender
+                .send(request.lines().next().unwrap_or_default().to_owned())
+                .unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+        }
+    });
+
+    (url, receiver)
+}
+
+/// Index client which retries quickly
+fn client(url: String, cache_dir: &tempfile::TempDir) -> Client {
+    Client::new(
+        url,
+        cache_dir.path().join(index::CACHE_FILE),
+        Duration::from_secs(3600),
+    )
+    .retry(Retry::new(3).backoff(Duration::from_millis(1)))
+}
+
+/// Transient failures are retried until the operation succeeds
+#[test]
+fn retries_transient_failures() {
+    let attempts = Cell::new(0);
+    let result = Retry::new(3)
+        .backoff(Duration::from_millis(1))
+        .run("testing", || {
+            attempts.set(attempts.get() + 1);
+
+            if attempts.get() < 3 {
+                Err(Failure::transient(Error::new(ErrorKind::Io, &"timed out")))
+            } else {
+                Ok(attempts.get())
+            }
+        });
+
+    assert_eq!(result.unwrap(), 3);
+}
+
+/// Permanent failures aren't retried, and the final error of an operation
+/// which kept failing says how many attempts were made
+#[test]
+fn gives_up() {
+    let attempts = Cell::new(0);
+    let error = Retry::new(3)
+        .backoff(Duration::from_millis(1))
+        .run("testing", || -> Result<(), _> {
+            attempts.set(attempts.get() + 1);
+            Err(Failure::permanent(Error::new(ErrorKind::Io, &"forbidden")))
+        })
+        .unwrap_err();
+
+    assert_eq!(attempts.get(), 1);
+    assert_eq!(error.msg(), "forbidden");
+
+    let error = Retry::new(3)
+        .backoff(Duration::from_millis(1))
+        .run("testing", || -> Result<(), _> {
+            Err(Failure::transient(Error::new(ErrorKind::Io, &"timed out")))
+        })
+        .unwrap_err();
+
+    assert_eq!(error.msg(), "timed out (after 3 attempts)");
+}
+
+/// Server errors from the sparse index are retried
+#[test]
+fn retries_server_errors() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (url, requests) = serve(&["503 Service Unavailable", "200 OK"]);
+    let foo = "foo".parse().unwrap();
+
+    let (releases, error) = client(url, &cache_dir).releases(&[&foo]);
+    assert!(error.is_none(), "{:?}", error);
+    assert!(releases[&foo].is_empty());
+    assert_eq!(requests.iter().count(), 2);
+}
+
+/// Client errors from the sparse index aren't retried
+#[test]
+fn client_errors_fail_immediately() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let (url, requests) = serve(&["403 Forbidden", "200 OK"]);
+
+    let (_, error) = client(url, &cache_dir).releases(&[&"foo".parse().unwrap()]);
+    let error = error.unwrap();
+    assert!(error.to_string().contains("403"), "{}", error);
+    assert!(!error.to_string().contains("attempt"), "{}", error);
+    assert!(requests.recv().is_ok());
+    assert!(requests.recv_timeout(Duration::from_millis(100)).is_err());
+}
+
+/// Git errors are only retried if they describe network problems
+#[test]
+fn classify_git_errors() {
+    let failure = |msg: &str| Failure::git(Error::new(ErrorKind::Repo, &msg));
+
+    assert!(failure("failed to connect to github.com: Connection timed out").transient);
+    assert!(failure("unexpected http status code: 502").transient);
+    assert!(!failure("unexpected http status code: 404").transient);
+    assert!(!failure("remote authentication required but no callback set").transient);
+    assert!(!Failure::git(Error::new(ErrorKind::Stale, &"repository is stale")).transient);
+}
+
+/// The number of attempts is configurable
+#[test]
+fn configured_attempts() {
+    let mut config = AuditConfig::default();
+    assert_eq!(Retry::from_config(&config.net).attempts(), 3);
+
+    config
+        .override_from_vars(vec![("CARGO_AUDIT_NET_ATTEMPTS", "5")])
+        .unwrap();
+    assert_eq!(Retry::from_config(&config.net).attempts(), 5);
+
+    config.net.attempts = Some(0);
+    assert_eq!(Retry::from_config(&config.net).attempts(), 1);
+}
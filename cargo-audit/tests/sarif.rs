@@ -0,0 +1,112 @@
+//! SARIF output tests
+
+mod support;
+
+use serde_json::Value;
+use std::{fs, path::Path, process::Command};
+use support::Advisory;
+
+/// Project depending on `foo` and `bar` 0.1.0, with a vulnerability advisory
+/// (with a CVSS score) for `foo` and an unmaintained advisory for `bar`
+struct Project {
+    db_dir: tempfile::TempDir,
+    project_dir: tempfile::TempDir,
+}
+
+impl Project {
+    fn new() -> Self {
+        let db_dir = support::database(&[
+            Advisory::new("RUSTSEC-2020-0001", "foo")
+                .attributes("cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:N/A:N\"")
+                .title("Test advisory for foo"),
+            Advisory::new("RUSTSEC-2020-0002", "bar")
+                .attributes("informational = \"unmaintained\"")
+                .title("Test advisory for bar"),
+        ]);
+
+        let project_dir = tempfile::tempdir().unwrap();
+        let mut lockfile = String::new();
+
+        for package in &["bar", "foo"] {
+            lockfile.push_str(&format!(
+                "[[package]]\n\
+                 name = \"{}\"\n\
+                 version = \"0.1.0\"\n\
+                 source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+                package
+            ));
+        }
+
+        fs::write(project_dir.path().join("Cargo.lock"), lockfile).unwrap();
+
+        Self {
+            db_dir,
+            project_dir,
+        }
+    }
+
+    /// Path to the project
+    fn path(&self) -> &Path {
+        self.project_dir.path()
+    }
+
+    /// SARIF log printed for the project
+    fn sarif(&self) -> Value {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .current_dir(self.path())
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(self.db_dir.path())
+            .args(&["--format", "sarif"])
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(1));
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+}
+
+/// Advisories become rules, and vulnerabilities error results (warnings
+/// warning results) located at the packages' entries in `Cargo.lock`
+#[test]
+fn sarif_log() {
+    let log = Project::new().sarif();
+    assert_eq!(log["version"], "2.1.0");
+
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "cargo-audit");
+
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0]["id"], "RUSTSEC-2020-0001");
+    assert_eq!(
+        rules[0]["shortDescription"]["text"],
+        "Test advisory for foo"
+    );
+    assert_eq!(
+        rules[0]["helpUri"],
+        "https://rustsec.org/advisories/RUSTSEC-2020-0001"
+    );
+    assert_eq!(rules[0]["properties"]["security-severity"], "7.5");
+    assert_eq!(rules[1]["id"], "RUSTSEC-2020-0002");
+    assert!(rules[1]["properties"].get("security-severity").is_none());
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0]["ruleId"], "RUSTSEC-2020-0001");
+    assert_eq!(results[0]["ruleIndex"], 0);
+    assert_eq!(results[0]["level"], "error");
+
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "Cargo.lock");
+    assert_eq!(location["region"]["startLine"], 6);
+
+    assert_eq!(results[1]["ruleId"], "RUSTSEC-2020-0002");
+    assert_eq!(results[1]["ruleIndex"], 1);
+    assert_eq!(results[1]["level"], "warning");
+
+    let location = &results[1]["locations"][0]["physicalLocation"];
+    assert_eq!(location["region"]["startLine"], 1);
+}
@@ -0,0 +1,137 @@
+//! Tests for auditing CycloneDX SBOMs
+
+mod support;
+
+use cargo_audit::sbom;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// JSON SBOM of `bar`, which depends on `foo` 0.1.0
+const JSON_SBOM: &str = r#"{
+  "bomFormat": "CycloneDX",
+  "specVersion": "1.4",
+  "metadata": {
+    "component": {"type": "application", "bom-ref": "bar", "name": "bar", "purl": "pkg:cargo/bar@1.0.0"}
+  },
+  "components": [
+    {"type": "library", "bom-ref": "foo", "name": "foo", "purl": "pkg:cargo/foo@0.1.0"},
+    {"type": "library", "bom-ref": "left-pad", "name": "left-pad", "purl": "pkg:npm/left-pad@1.3.0"}
+  ],
+  "dependencies": [
+    {"ref": "bar", "dependsOn": ["foo"]},
+    {"ref": "foo"}
+  ]
+}"#;
+
+/// XML SBOM with the same components
+const XML_SBOM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<bom xmlns="http://cyclonedx.org/schema/bom/1.4" version="1">
+  <metadata>
+    <component type="application" bom-ref="bar">
+      <name>bar</name>
+      <purl>pkg:cargo/bar@1.0.0</purl>
+    </component>
+  </metadata>
+  <components>
+    <component type="library" bom-ref="foo">
+      <name>foo</name>
+      <purl>pkg:cargo/foo@0.1.0</purl>
+    </component>
+  </components>
+  <dependencies>
+    <dependency ref="bar">
+      <dependency ref="foo"/>
+    </dependency>
+    <dependency ref="foo"/>
+  </dependencies>
+</bom>
+"#;
+
+/// Advisory database with an advisory for versions of `foo` prior to 0.2.0
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")])
+}
+
+/// Run `cargo audit --sbom` on the given SBOM
+fn audit_sbom(db_path: &Path, sbom_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .arg("--sbom")
+        .arg(sbom_path)
+        .args(args)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Package URLs name crates, with percent-encoded characters, and are from
+/// crates.io unless another registry is given
+#[test]
+fn parse_purls() {
+    let krate = sbom::parse_purl("pkg:cargo/foo@1.0.0%2Bbuild.1?arch=x86_64#src").unwrap();
+    assert_eq!(krate.name.as_str(), "foo");
+    assert_eq!(krate.version.to_string(), "1.0.0+build.1");
+    assert!(krate.crates_io);
+
+    let krate =
+        sbom::parse_purl("pkg:cargo/foo@1.0.0?repository_url=https%3A%2F%2Fexample.com%2Findex")
+            .unwrap();
+    assert!(!krate.crates_io);
+
+    assert!(sbom::parse_purl("pkg:npm/left-pad@1.3.0").is_none());
+    assert!(sbom::parse_purl("pkg:cargo/foo").is_none());
+}
+
+/// JSON and XML SBOMs list the same crates, with the same dependencies
+#[test]
+fn parse_json_and_xml() {
+    for source in &[JSON_SBOM, XML_SBOM] {
+        let lockfile = sbom::parse(source).unwrap();
+        let names: Vec<_> = lockfile
+            .packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+
+        assert_eq!(names, ["bar", "foo"]);
+        assert_eq!(lockfile.packages[0].dependencies.len(), 1);
+        assert_eq!(lockfile.packages[0].dependencies[0].name.as_str(), "foo");
+        assert!(lockfile.packages[1].dependencies.is_empty());
+    }
+
+    assert!(sbom::parse(r#"{"bomFormat": "SPDX"}"#).is_err());
+    assert!(sbom::parse("<spdx/>").is_err());
+}
+
+/// SBOMs are audited like lockfiles
+#[test]
+fn audit_sbom_file() {
+    let db_dir = database();
+
+    let dir = tempfile::tempdir().unwrap();
+    let sbom_path = dir.path().join("bom.xml");
+    fs::write(&sbom_path, XML_SBOM).unwrap();
+
+    let output = audit_sbom(db_dir.path(), &sbom_path, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+    assert!(stdout.contains("bar 1.0.0"), "{}", stdout);
+
+    let sbom_path = dir.path().join("bom.json");
+    fs::write(&sbom_path, JSON_SBOM).unwrap();
+
+    let output = audit_sbom(db_dir.path(), &sbom_path, &["--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["vulnerabilities"]["count"], 1);
+    assert_eq!(report["lockfile"]["dependency-count"], 2);
+
+    let output = audit_sbom(db_dir.path(), &sbom_path, &["--file", "Cargo.lock"]);
+    assert_eq!(output.status.code(), Some(2));
+}
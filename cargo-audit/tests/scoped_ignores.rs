@@ -0,0 +1,124 @@
+//! Tests for ignores scoped to packages and dependency paths
+
+mod support;
+
+use cargo_audit::{config::IgnoredAdvisory, scoped_ignores};
+use rustsec::{advisory, lockfile::Lockfile, report, Database, Report};
+use support::Advisory;
+
+/// Workspace where `app` depends on vulnerable `foo` 0.1.0 only via
+/// `criterion`, and on vulnerable `foo` 0.1.1 via `mid`
+const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["criterion", "mid"]
+
+[[package]]
+name = "criterion"
+version = "0.3.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["foo 0.1.0"]
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.1.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "mid"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["foo 0.1.1"]
+"#;
+
+/// Audit the lockfile against an advisory for `foo`, applying the given
+/// ignores
+fn audit(ignore: &[IgnoredAdvisory]) -> (Report, usize) {
+    let advisory = Advisory::new("RUSTSEC-2021-0001", "foo")
+        .date("2021-01-01")
+        .markdown();
+
+    let database = Database::from_markdown(&[&advisory]).unwrap();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let tree = lockfile.dependency_tree().unwrap();
+    let mut report = Report::generate(&database, &lockfile, &report::Settings::default());
+    assert_eq!(report.vulnerabilities.count, 2);
+
+    let today: advisory::Date = "2021-06-01".parse().unwrap();
+    let ignored = scoped_ignores::apply(&mut report, ignore, Some(&tree), &today);
+    (report, ignored)
+}
+
+/// Ignore of the advisory with the given scope
+fn ignore(scope: &str) -> IgnoredAdvisory {
+    toml::from_str::<toml::Value>(&format!(
+        "ignore = {{ id = \"RUSTSEC-2021-0001\", {} }}",
+        scope
+    ))
+    .unwrap()["ignore"]
+        .clone()
+        .try_into()
+        .unwrap()
+}
+
+/// Remaining vulnerable versions of `foo`
+fn versions(report: &Report) -> Vec<String> {
+    report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vuln| vuln.package.version.to_string())
+        .collect()
+}
+
+/// Ignores scoped to a dependency path only apply to packages the workspace
+/// can't reach without going through it
+#[test]
+fn ignore_via() {
+    let (report, ignored) = audit(&[ignore("via = [\"criterion\"]")]);
+    assert_eq!(ignored, 1);
+    assert_eq!(versions(&report), ["0.1.1"]);
+    assert_eq!(report.vulnerabilities.count, 1);
+
+    let (report, ignored) = audit(&[ignore("via = [\"criterion\", \"mid\"]")]);
+    assert_eq!(ignored, 2);
+    assert!(!report.vulnerabilities.found);
+
+    let (report, ignored) = audit(&[ignore("via = [\"unrelated\"]")]);
+    assert_eq!(ignored, 0);
+    assert_eq!(versions(&report), ["0.1.0", "0.1.1"]);
+}
+
+/// Ignores scoped to a package version only apply to that version
+#[test]
+fn ignore_package_version() {
+    let (report, ignored) = audit(&[ignore("package = \"foo\", version = \"0.1.1\"")]);
+    assert_eq!(ignored, 1);
+    assert_eq!(versions(&report), ["0.1.0"]);
+
+    let (_, ignored) = audit(&[ignore("package = \"foo\"")]);
+    assert_eq!(ignored, 2);
+
+    let (_, ignored) = audit(&[ignore("package = \"bar\"")]);
+    assert_eq!(ignored, 0);
+}
+
+/// Scoped ignores which expired, and unscoped ignores (which are applied
+/// while matching), aren't applied
+#[test]
+fn inactive_ignores() {
+    let (_, ignored) = audit(&[ignore(
+        "via = [\"criterion\"], reason = \"benchmarks only\", expires = \"2021-01-31\"",
+    )]);
+    assert_eq!(ignored, 0);
+
+    let (_, ignored) = audit(&["RUSTSEC-2021-0001".parse::<advisory::Id>().unwrap().into()]);
+    assert_eq!(ignored, 0);
+}
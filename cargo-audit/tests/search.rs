@@ -0,0 +1,106 @@
+//! `cargo audit search` tests
+
+mod support;
+
+use std::{path::Path, process::Command};
+use support::Advisory;
+
+/// Advisory database with a few advisories
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2019-0001", "foo")
+            .date("2019-06-01")
+            .attributes(
+                "categories = [\"memory-corruption\"]\n\
+                 aliases = [\"CVE-2019-1234\"]\n\
+                 cvss = \"CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H\"",
+            )
+            .title("Use after free in Foo"),
+        Advisory::new("RUSTSEC-2020-0001", "bar")
+            .date("2020-06-01")
+            .attributes("informational = \"unmaintained\"")
+            .title("Bar is unmaintained"),
+        Advisory::new("RUSTSEC-2020-0002", "foo")
+            .date("2020-06-01")
+            .attributes(
+                "categories = [\"thread-safety\"]\n\
+                 cvss = \"CVSS:3.1/AV:N/AC:H/PR:L/UI:N/S:U/C:N/I:L/A:N\"",
+            )
+            .title("Data race in Foo"),
+        Advisory::new("RUSTSEC-2021-0001", "foo")
+            .date("2021-06-01")
+            .attributes("withdrawn = \"2021-07-01\"")
+            .title("Withdrawn advisory about Foo"),
+    ])
+}
+
+/// IDs of the advisories `cargo audit search` lists with the given arguments
+fn search(db_path: &Path, args: &[&str]) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--color", "never", "search", "--json", "--db-path"])
+        .arg(db_path)
+        .args(args)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    let advisories: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+
+    advisories
+        .iter()
+        .map(|advisory| advisory["advisory"]["id"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+/// Advisories are searched by text and filtered, newest first, without
+/// withdrawn ones
+#[test]
+fn search_advisories() {
+    let db_dir = database();
+    let db_path = db_dir.path();
+
+    assert_eq!(
+        search(db_path, &["foo"]),
+        ["RUSTSEC-2020-0002", "RUSTSEC-2019-0001"]
+    );
+    assert_eq!(search(db_path, &["data", "RACE"]), ["RUSTSEC-2020-0002"]);
+    assert_eq!(search(db_path, &["--crate", "bar"]), ["RUSTSEC-2020-0001"]);
+    assert_eq!(
+        search(db_path, &["--id", "cve-2019-1234"]),
+        ["RUSTSEC-2019-0001"]
+    );
+    assert_eq!(
+        search(db_path, &["--severity", "high"]),
+        ["RUSTSEC-2019-0001"]
+    );
+    assert_eq!(
+        search(db_path, &["--since", "2020-01-01", "--until", "2020-12-31"]),
+        ["RUSTSEC-2020-0002", "RUSTSEC-2020-0001"]
+    );
+    assert_eq!(
+        search(db_path, &["--category", "thread-safety", "foo"]),
+        ["RUSTSEC-2020-0002"]
+    );
+    assert!(search(db_path, &["--crate", "foo", "unmaintained"]).is_empty());
+}
+
+/// Terminal output lists each advisory, and how many were found
+#[test]
+fn terminal_search() {
+    let db_dir = database();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--color", "never", "search", "--db-path"])
+        .arg(db_dir.path())
+        .arg("race")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("RUSTSEC-2020-0002"), "{}", stdout);
+    assert!(stdout.contains("Data race in Foo"), "{}", stdout);
+    assert!(stdout.contains("low (3.1)"), "{}", stdout);
+    assert!(stdout.contains("thread-safety"), "{}", stdout);
+    assert!(stdout.contains("1 matching advisory"), "{:?}", output);
+}
@@ -0,0 +1,116 @@
+//! SPDX output tests
+
+mod support;
+
+use std::process::Command;
+use support::Advisory;
+
+/// Advisory database with an advisory for versions of `base64` prior to 0.5.2
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::base64()])
+}
+
+#[test]
+fn spdx_report() {
+    let db_dir = database();
+    let home = tempfile::tempdir().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+        .args(&["--format", "spdx"])
+        .env("CARGO_HOME", home.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["spdxVersion"], "SPDX-2.3");
+    assert_eq!(document["SPDXID"], "SPDXRef-DOCUMENT");
+    assert!(document["documentNamespace"]
+        .as_str()
+        .unwrap()
+        .starts_with("https://rustsec.org/spdxdocs/cargo-audit-"));
+
+    // Every locked package is listed, in order, with its package URL
+    let packages = document["packages"].as_array().unwrap();
+    let ids: Vec<&str> = packages
+        .iter()
+        .map(|package| package["SPDXID"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            "SPDXRef-Package-base64-0.5.1",
+            "SPDXRef-Package-base64-vuln-0.1.0",
+            "SPDXRef-Package-byteorder-1.3.1"
+        ]
+    );
+
+    let base64 = &packages[0];
+    assert_eq!(base64["name"], "base64");
+    assert_eq!(base64["versionInfo"], "0.5.1");
+    assert_eq!(
+        base64["downloadLocation"],
+        "https://crates.io/api/v1/crates/base64/0.5.1/download"
+    );
+    assert_eq!(
+        base64["externalRefs"],
+        serde_json::json!([
+            {
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": "pkg:cargo/base64@0.5.1"
+            },
+            {
+                "referenceCategory": "SECURITY",
+                "referenceType": "advisory",
+                "referenceLocator": "https://rustsec.org/advisories/RUSTSEC-2017-0004",
+                "comment": "RUSTSEC-2017-0004: Test advisory"
+            }
+        ])
+    );
+    assert_eq!(
+        base64["annotations"][0]["comment"],
+        "RUSTSEC-2017-0004 (vulnerability): Test advisory (patched: >=0.5.2)"
+    );
+    assert_eq!(packages[1]["downloadLocation"], "NOASSERTION");
+    assert!(packages[2].get("annotations").is_none());
+
+    // The document describes the workspace, whose packages depend on others
+    let relationships: Vec<(&str, &str, &str)> = document["relationships"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|relationship| {
+            (
+                relationship["spdxElementId"].as_str().unwrap(),
+                relationship["relationshipType"].as_str().unwrap(),
+                relationship["relatedSpdxElement"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        relationships,
+        [
+            (
+                "SPDXRef-DOCUMENT",
+                "DESCRIBES",
+                "SPDXRef-Package-base64-vuln-0.1.0"
+            ),
+            (
+                "SPDXRef-Package-base64-0.5.1",
+                "DEPENDS_ON",
+                "SPDXRef-Package-byteorder-1.3.1"
+            ),
+            (
+                "SPDXRef-Package-base64-vuln-0.1.0",
+                "DEPENDS_ON",
+                "SPDXRef-Package-base64-0.5.1"
+            ),
+        ]
+    );
+}
@@ -0,0 +1,275 @@
+//! Tests for tracking findings across runs with a state file
+
+mod support;
+
+use cargo_audit::state::{self, State};
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Lockfile with two crates.io packages
+const LOCKFILE: &str = "[[package]]\n\
+                        name = \"bar\"\n\
+                        version = \"0.1.0\"\n\
+                        source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+                        [[package]]\n\
+                        name = \"foo\"\n\
+                        version = \"0.1.0\"\n\
+                        source = \"registry+https://github.com/rust-lang/crates.io-index\"\n";
+
+/// Run `cargo audit` on the lockfile in the given directory
+fn audit(db_path: &Path, project_dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .arg("--file")
+        .arg(project_dir.join("Cargo.lock"))
+        .args(args)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// Findings which are new since the recorded run are marked, resolved ones
+/// are listed, and first-seen dates are kept across runs
+#[test]
+fn state_file() {
+    let db_dir = tempfile::tempdir().unwrap();
+    Advisory::new("RUSTSEC-2020-0001", "foo").write(db_dir.path());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+    let state_path = project_dir.path().join("audit-state.json");
+    let state_arg = state_path.to_str().unwrap();
+
+    // Every finding of the first run is new, so none are marked
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--state-file", state_arg],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(!stdout.contains("NEW"), "{}", stdout);
+
+    let recorded = State::load(&state_path).unwrap().unwrap();
+    assert_eq!(recorded.updated, Some(state::today()));
+    assert_eq!(recorded.findings.len(), 1);
+    assert_eq!(recorded.findings[0].package.as_str(), "foo");
+
+    // Pretend the first run was a while ago
+    let json = fs::read_to_string(&state_path)
+        .unwrap()
+        .replace(state::today().as_str(), "2020-06-01");
+    fs::write(&state_path, json).unwrap();
+
+    Advisory::new("RUSTSEC-2020-0002", "bar").write(db_dir.path());
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--state-file", state_arg],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.matches("NEW since 2020-06-01").count(),
+        1,
+        "{}",
+        stdout
+    );
+
+    let recorded = State::load(&state_path).unwrap().unwrap();
+    let first_seen: Vec<_> = recorded
+        .findings
+        .iter()
+        .map(|finding| (finding.package.as_str(), finding.first_seen.as_str()))
+        .collect();
+    assert_eq!(
+        first_seen,
+        [("foo", "2020-06-01"), ("bar", state::today().as_str())]
+    );
+
+    // Nothing is new, so nothing fails with `--fail-on new`
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--state-file", state_arg, "--fail-on", "new"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    // Resolved findings are listed, without updating the state
+    fs::remove_dir_all(db_dir.path().join("crates").join("foo")).unwrap();
+    let before = fs::read_to_string(&state_path).unwrap();
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--state-file", state_arg, "--no-update-state"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Resolved since last run: 1 finding"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("RUSTSEC-2020-0001"), "{}", stdout);
+    assert_eq!(fs::read_to_string(&state_path).unwrap(), before);
+}
+
+/// `--fail-on new` needs a state file to compare against
+#[test]
+fn fail_on_new_requires_state_file() {
+    let db_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(db_dir.path().join("crates")).unwrap();
+
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+
+    let output = audit(db_dir.path(), project_dir.path(), &["--fail-on", "new"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("requires a state file"), "{}", stderr);
+}
+
+/// With `--baseline`, findings are compared to those of an earlier report,
+/// which is left untouched
+#[test]
+fn baseline() {
+    let db_dir = tempfile::tempdir().unwrap();
+    Advisory::new("RUSTSEC-2020-0001", "foo").write(db_dir.path());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+    let baseline_path = project_dir.path().join("baseline.json");
+    let baseline_arg = baseline_path.to_str().unwrap();
+
+    let output = audit(db_dir.path(), project_dir.path(), &["--json"]);
+    assert_eq!(output.status.code(), Some(1));
+    fs::write(&baseline_path, &output.stdout).unwrap();
+
+    // Only the finding which isn't in the baseline is new, and fails the audit
+    Advisory::new("RUSTSEC-2020-0002", "bar").write(db_dir.path());
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout
+            .matches(&format!("NEW since {}", baseline_arg))
+            .count(),
+        1,
+        "{}",
+        stdout
+    );
+
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg, "--fail-on", "new"],
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    // Findings in the baseline don't fail the audit
+    fs::remove_dir_all(db_dir.path().join("crates").join("bar")).unwrap();
+    let before = fs::read_to_string(&baseline_path).unwrap();
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg, "--fail-on", "new"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&baseline_path).unwrap(), before);
+
+    // Findings in the baseline which were resolved are listed
+    fs::remove_dir_all(db_dir.path().join("crates").join("foo")).unwrap();
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Resolved since baseline: 1 finding"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("First seen"), "{}", stdout);
+}
+
+/// `--write-baseline` records the findings (without failing the audit), and
+/// later audits with `--baseline` only fail on findings which aren't in it
+#[test]
+fn write_baseline() {
+    let db_dir = tempfile::tempdir().unwrap();
+    Advisory::new("RUSTSEC-2020-0001", "foo").write(db_dir.path());
+
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+    let baseline_path = project_dir.path().join("baseline.json");
+    let baseline_arg = baseline_path.to_str().unwrap();
+
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--write-baseline", baseline_arg],
+    );
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+
+    let baseline = state::load_baseline(&baseline_path).unwrap();
+    assert_eq!(baseline.findings.len(), 1);
+    assert_eq!(baseline.findings[0].package.as_str(), "foo");
+
+    // Findings in the baseline don't fail the audit, but new ones do
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg],
+    );
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+
+    Advisory::new("RUSTSEC-2020-0002", "bar").write(db_dir.path());
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg],
+    );
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    // Resolved findings are listed, to be pruned by writing the baseline
+    // again
+    fs::remove_dir_all(db_dir.path().join("crates").join("foo")).unwrap();
+    fs::remove_dir_all(db_dir.path().join("crates").join("bar")).unwrap();
+    let output = audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Resolved since baseline: 1 finding"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!(
+            "These can be pruned from the baseline with `--write-baseline {}`.",
+            baseline_arg
+        )),
+        "{}",
+        stdout
+    );
+
+    audit(
+        db_dir.path(),
+        project_dir.path(),
+        &["--baseline", baseline_arg, "--write-baseline", baseline_arg],
+    );
+    assert!(state::load_baseline(&baseline_path)
+        .unwrap()
+        .findings
+        .is_empty());
+}
@@ -0,0 +1,174 @@
+//! Helpers shared by the integration tests, which include this module with
+//! `mod support;`
+
+// Each test only uses some of the helpers
+#![allow(dead_code)]
+
+use std::{fs, path::Path};
+use tempfile::TempDir;
+
+/// Advisory to write to a test advisory database: by default, one reported
+/// on 2020-01-01 which is patched in `>= 0.2.0` of its package
+#[derive(Clone, Debug)]
+pub struct Advisory {
+    /// Collection the advisory is in (`crates` or `rust`)
+    collection: String,
+
+    /// ID of the advisory
+    id: String,
+
+    /// Package the advisory is about
+    package: String,
+
+    /// Date the advisory was reported
+    date: String,
+
+    /// Other attributes of the `[advisory]` table (as TOML)
+    attributes: String,
+
+    /// Tables between the `[advisory]` and `[versions]` tables (as TOML)
+    tables: String,
+
+    /// Contents of the `[versions]` table (as TOML)
+    versions: String,
+
+    /// Title of the advisory
+    title: String,
+
+    /// Description of the advisory (in Markdown)
+    description: String,
+}
+
+impl Advisory {
+    /// Advisory with the given ID about the given package
+    pub fn new(id: &str, package: &str) -> Self {
+        Self {
+            collection: "crates".to_owned(),
+            id: id.to_owned(),
+            package: package.to_owned(),
+            date: "2020-01-01".to_owned(),
+            attributes: String::new(),
+            tables: String::new(),
+            versions: "patched = [\">= 0.2.0\"]".to_owned(),
+            title: "Test advisory".to_owned(),
+            description: "Test advisory.".to_owned(),
+        }
+    }
+
+    /// Advisory about the version of `base64` locked in
+    /// `tests/support/base64_vuln` (RUSTSEC-2017-0004), which is patched in
+    /// `>= 0.5.2` and doesn't affect `< 0.5.0`
+    pub fn base64() -> Self {
+        Self::new("RUSTSEC-2017-0004", "base64")
+            .date("2017-05-03")
+            .versions("patched = [\">= 0.5.2\"]\nunaffected = [\"< 0.5.0\"]")
+    }
+
+    /// Set the ID of the advisory
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = id.to_owned();
+        self
+    }
+
+    /// Set the collection the advisory is in
+    pub fn collection(mut self, collection: &str) -> Self {
+        self.collection = collection.to_owned();
+        self
+    }
+
+    /// Set the date the advisory was reported
+    pub fn date(mut self, date: &str) -> Self {
+        self.date = date.to_owned();
+        self
+    }
+
+    /// Set other attributes of the `[advisory]` table (as TOML)
+    pub fn attributes(mut self, attributes: &str) -> Self {
+        self.attributes = attributes.to_owned();
+        self
+    }
+
+    /// Set the tables between the `[advisory]` and `[versions]` tables (as
+    /// TOML), e.g. `[affected]`
+    pub fn tables(mut self, tables: &str) -> Self {
+        self.tables = tables.to_owned();
+        self
+    }
+
+    /// Set the contents of the `[versions]` table (as TOML)
+    pub fn versions(mut self, versions: &str) -> Self {
+        self.versions = versions.to_owned();
+        self
+    }
+
+    /// Set the title of the advisory
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_owned();
+        self
+    }
+
+    /// Set the description of the advisory (in Markdown)
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    /// Render the advisory as Markdown with TOML front matter, as advisories
+    /// are in the advisory database
+    pub fn markdown(&self) -> String {
+        format!(
+            "```toml\n\
+             [advisory]\n\
+             id = \"{}\"\n\
+             package = \"{}\"\n\
+             date = \"{}\"\n\
+             {}\n\
+             {}\
+             [versions]\n\
+             {}\n\
+             ```\n\n\
+             # {}\n\n\
+             {}\n",
+            self.id,
+            self.package,
+            self.date,
+            lines(&self.attributes),
+            lines(&self.tables),
+            self.versions.trim_end(),
+            self.title,
+            self.description.trim_end()
+        )
+    }
+
+    /// Write the advisory to the advisory database in the given directory
+    pub fn write(&self, db_path: &Path) {
+        let advisory_dir = db_path.join(&self.collection).join(&self.package);
+        fs::create_dir_all(&advisory_dir).unwrap();
+        fs::write(
+            advisory_dir.join(format!("{}.md", self.id)),
+            self.markdown(),
+        )
+        .unwrap();
+    }
+}
+
+/// Create an advisory database in a temporary directory, with the given
+/// advisories
+pub fn database(advisories: &[Advisory]) -> TempDir {
+    let db_dir = tempfile::tempdir().unwrap();
+
+    for advisory in advisories {
+        advisory.write(db_dir.path());
+    }
+
+    db_dir
+}
+
+/// The given TOML, ending with a line feed unless it's empty
+fn lines(toml: &str) -> String {
+    if toml.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", toml.trim_end())
+    }
+}
@@ -0,0 +1,117 @@
+//! Tests for reporting vulnerabilities for other platforms than the target
+
+mod support;
+
+use std::{fs, path::Path, process::Command};
+use support::Advisory;
+
+/// Advisory database with an advisory for `foo` versions before 0.2.0, only
+/// affecting Windows
+fn database() -> tempfile::TempDir {
+    support::database(&[
+        Advisory::new("RUSTSEC-2020-0001", "foo").tables("[affected]\nos = [\"windows\"]\n")
+    ])
+}
+
+/// Audit a lockfile with `foo` 0.1.0 with the given arguments, returning the
+/// exit status and JSON report (if any)
+fn audit(db_path: &Path, args: &[&str]) -> (Option<i32>, Option<serde_json::Value>) {
+    let project_dir = tempfile::tempdir().unwrap();
+    let project = project_dir.path();
+
+    fs::write(
+        project.join("Cargo.lock"),
+        "[[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--json", "--db"])
+        .arg(db_path)
+        .args(args)
+        .env("CARGO_HOME", project)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap();
+
+    (
+        output.status.code(),
+        serde_json::from_slice(&output.stdout).ok(),
+    )
+}
+
+/// Vulnerabilities for other platforms than the target are reported as
+/// `other-platform` warnings, which don't fail the audit unless denied
+#[test]
+fn other_platform_warnings() {
+    let db_dir = database();
+
+    let (status, report) = audit(db_dir.path(), &["--target", "x86_64-unknown-linux-gnu"]);
+    let report = report.unwrap();
+    assert_eq!(status, Some(0));
+    assert_eq!(report["vulnerabilities"]["count"], 0);
+
+    let warnings = report["warnings"]["other-platform"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["advisory"]["id"], "RUSTSEC-2020-0001");
+
+    // `--deny warnings` leaves them informational
+    let (status, _) = audit(
+        db_dir.path(),
+        &["--target", "x86_64-unknown-linux-gnu", "--deny", "warnings"],
+    );
+    assert_eq!(status, Some(0));
+
+    let (status, _) = audit(
+        db_dir.path(),
+        &[
+            "--target",
+            "x86_64-unknown-linux-gnu",
+            "--deny",
+            "other-platform",
+        ],
+    );
+    assert_eq!(status, Some(1));
+
+    // `--target-os` takes precedence over the triple's
+    let (status, report) = audit(
+        db_dir.path(),
+        &[
+            "--target",
+            "x86_64-unknown-linux-gnu",
+            "--target-os",
+            "windows",
+        ],
+    );
+    assert_eq!(status, Some(1));
+    assert_eq!(report.unwrap()["vulnerabilities"]["count"], 1);
+}
+
+/// Vulnerabilities for the target (or without a target) are reported as usual
+#[test]
+fn target_platform_vulnerabilities() {
+    let db_dir = database();
+
+    for args in &[&["--target", "x86_64-pc-windows-msvc"][..], &[]] {
+        let (status, report) = audit(db_dir.path(), args);
+        let report = report.unwrap();
+        assert_eq!(status, Some(1));
+        assert_eq!(report["vulnerabilities"]["count"], 1);
+        assert!(report["warnings"].get("other-platform").is_none());
+    }
+}
+
+/// Unknown target triples are rejected
+#[test]
+fn unknown_target() {
+    let db_dir = database();
+
+    let (status, report) = audit(db_dir.path(), &["--target", "x86_64-bogus-os"]);
+    assert_ne!(status, Some(0));
+    assert!(report.is_none());
+}
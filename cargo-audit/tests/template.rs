@@ -0,0 +1,113 @@
+//! Tests for rendering vulnerabilities with templates
+
+mod support;
+
+use cargo_audit::template::Template;
+use std::{collections::BTreeMap as Map, fs, process::Command};
+use support::Advisory;
+
+/// Variables are replaced by their values, conditional blocks are kept only
+/// for variables which aren't empty, and lines with only block tags or
+/// comments are left out
+#[test]
+fn render_template() {
+    let template: Template = "{{! Dutch labels }}\n\
+                              Titel: {{title}}\n\
+                              {{#if severity}}\n\
+                              Ernst: {{severity}}\n\
+                              {{/if}}\n\
+                              Oplossing: {{#if patched}}{{solution}}{{else}}geen{{/if}}\n"
+        .parse()
+        .unwrap();
+
+    let mut values = Map::new();
+    values.insert("title", "Test advisory".to_owned());
+    values.insert("patched", String::new());
+    assert_eq!(
+        template.render(&values),
+        "Titel: Test advisory\nOplossing: geen\n"
+    );
+
+    values.insert("severity", "high".to_owned());
+    values.insert("patched", ">= 1.0.0".to_owned());
+    values.insert("solution", "Upgrade to >= 1.0.0".to_owned());
+    assert_eq!(
+        template.render(&values),
+        "Titel: Test advisory\nErnst: high\nOplossing: Upgrade to >= 1.0.0\n"
+    );
+}
+
+/// Unknown variables and unbalanced blocks are errors, with their line
+#[test]
+fn invalid_templates() {
+    for (source, message) in &[
+        (
+            "ID: {{id}}\nTitle: {{titel}}\n",
+            "line 2: unknown variable \"titel\"",
+        ),
+        (
+            "{{#if severity}}\n{{severity}}\n",
+            "line 1: unclosed {{#if}}",
+        ),
+        ("{{id}}{{/if}}", "line 1: unexpected {{/if}}"),
+        ("{{#each aliases}}{{/each}}", "unsupported block tag"),
+        ("{{id", "line 1: unclosed {{"),
+    ] {
+        let error = source.parse::<Template>().unwrap_err();
+        assert!(error.msg().contains(message), "{}", error);
+    }
+}
+
+/// `--template` renders each vulnerability with the template, and invalid
+/// templates are configuration errors
+#[test]
+fn template_option() {
+    let db_dir = support::database(&[Advisory::base64()]);
+
+    let template_dir = tempfile::tempdir().unwrap();
+    let template_path = template_dir.path().join("audit.hbs");
+    let home = tempfile::tempdir().unwrap();
+
+    let audit = || {
+        Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+            .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+            .arg(db_dir.path())
+            .args(&["--file", "tests/support/base64_vuln/Cargo.lock"])
+            .arg("--template")
+            .arg(&template_path)
+            .env("CARGO_HOME", home.path())
+            .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+            .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+            .output()
+            .unwrap()
+    };
+
+    fs::write(
+        &template_path,
+        "Kwetsbaarheid: {{id}} in {{crate}} {{version}}\n\
+         Runbook: https://wiki.example.com/runbooks/{{id}}\n",
+    )
+    .unwrap();
+
+    let output = audit();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "Kwetsbaarheid: RUSTSEC-2017-0004 in base64 0.5.1\n\
+             Runbook: https://wiki.example.com/runbooks/RUSTSEC-2017-0004\n"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("Title:"), "{}", stdout);
+
+    fs::write(&template_path, "{{titel}}\n").unwrap();
+
+    let output = audit();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("unknown variable \"titel\""), "{}", stderr);
+    assert!(output.stdout.is_empty());
+}
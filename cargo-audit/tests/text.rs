@@ -0,0 +1,69 @@
+//! Plain-text formatting tests
+
+use cargo_audit::text;
+
+/// Text is wrapped between words, and long words overflow
+#[test]
+fn wrap_words() {
+    assert_eq!(
+        text::wrap("the quick brown fox jumps over the lazy dog", 15),
+        ["the quick brown", "fox jumps over", "the lazy dog"]
+    );
+    assert_eq!(
+        text::wrap("see https://example.com/a/very/long/url here", 10),
+        ["see", "https://example.com/a/very/long/url", "here"]
+    );
+    assert_eq!(text::wrap("", 10), [""]);
+}
+
+/// Markdown is rendered without markup: lists get a hanging indent and code
+/// blocks are kept verbatim
+#[test]
+fn render_markdown() {
+    let markdown = "## Impact\n\n\
+                    Calling `foo::bar` with **untrusted** input may\n\
+                    overflow a buffer, see [the issue](https://example.com/1).\n\n\
+                    - first affected function, which has a long description\n\
+                    - second\n\n\
+                    ```rust\n\
+                    let x = foo::bar(input);\n\
+                    ```\n";
+
+    assert_eq!(
+        text::render_markdown(markdown, 30),
+        [
+            "Impact",
+            "",
+            "Calling foo::bar with",
+            "untrusted input may overflow a",
+            "buffer, see the issue",
+            "(https://example.com/1).",
+            "",
+            "- first affected function,",
+            "  which has a long description",
+            "- second",
+            "",
+            "    let x = foo::bar(input);",
+        ]
+    );
+}
+
+/// Hyperlinks are OSC 8 escape sequences around the text
+#[test]
+fn hyperlink() {
+    assert_eq!(
+        text::hyperlink("RUSTSEC-2017-0004", "https://example.com/"),
+        "\x1b]8;;https://example.com/\x1b\\RUSTSEC-2017-0004\x1b]8;;\x1b\\"
+    );
+}
+
+/// Lines longer than the width are cut off with an ellipsis
+#[test]
+fn truncate_lines() {
+    assert_eq!(
+        text::truncate("│   └── base64 0.5.1", 20),
+        "│   └── base64 0.5.1"
+    );
+    assert_eq!(text::truncate("│   └── base64 0.5.1", 12), "│   └── bas…");
+    assert_eq!(text::truncate("base64", 0), "…");
+}
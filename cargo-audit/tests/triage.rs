@@ -0,0 +1,131 @@
+//! Interactive triage (`cargo audit --interactive`) tests
+
+mod support;
+
+use cargo_audit::{
+    auditor::Auditor,
+    config::{AuditConfig, ConfigEditor},
+    triage::{self, Decision, Triage},
+};
+use rustsec::report::Outcome;
+use std::{fs, io::Cursor, path::Path};
+use support::Advisory;
+
+/// Audit a lockfile with vulnerable versions of `foo` and `bar`
+fn audit(db_path: &Path, lockfile_path: &Path) -> (Auditor, rustsec::Report) {
+    Advisory::new("RUSTSEC-2020-0001", "foo").write(db_path);
+    Advisory::new("RUSTSEC-2020-0002", "bar").write(db_path);
+    fs::write(
+        lockfile_path,
+        "[[package]]\n\
+         name = \"bar\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\n\
+         name = \"foo\"\n\
+         version = \"0.1.0\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+    )
+    .unwrap();
+
+    let mut config = AuditConfig::default();
+    config.database.path = Some(db_path.to_owned());
+    config.database.fetch = false;
+    config.yanked.enabled = false;
+    config.registry.lookup = false;
+
+    let mut auditor = Auditor::try_new(&config).unwrap();
+    let report = auditor.audit_lockfile(lockfile_path).unwrap();
+    (auditor, report)
+}
+
+/// Ignores and acknowledgments are written to `audit.toml` with comments,
+/// and no longer fail the audit
+#[test]
+fn ignore_and_fix_later() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let project_dir = tempfile::tempdir().unwrap();
+    let (mut auditor, mut report) = audit(db_dir.path(), &project_dir.path().join("Cargo.lock"));
+    assert_eq!(report.outcome, Some(Outcome::VulnerabilitiesFound));
+
+    let today: rustsec::advisory::Date = "2021-01-01".parse().unwrap();
+    let findings = triage::findings(&report);
+    assert_eq!(findings.len(), 2);
+
+    let mut output = vec![];
+    let input = Cursor::new("x\ni\n\nonly decodes trusted input\n2021-13-01\n\nl\nJIRA-123\n10\n");
+    let decisions = Triage::new(input, &mut output, today.clone())
+        .run(&findings, None)
+        .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("(1/2) RUSTSEC-2020-000"), "{}", output);
+    assert!(output.contains("Patched:  >="), "{}", output);
+    assert!(output.contains("l - fix it later"), "{}", output);
+    assert!(output.contains("an answer is required"), "{}", output);
+    assert!(output.contains("invalid date"), "{}", output);
+
+    match &decisions[..] {
+        [Decision::Ignore(ignored), Decision::FixLater(acknowledgment)] => {
+            assert_eq!(ignored.id, findings[0].advisory.id);
+            assert_eq!(
+                ignored.reason.as_deref(),
+                Some("only decodes trusted input")
+            );
+            assert_eq!(ignored.expires, None);
+            assert_eq!(acknowledgment.id, findings[1].advisory.id);
+            assert_eq!(acknowledgment.ticket, "JIRA-123");
+            assert_eq!(acknowledgment.expires.as_str(), "2021-01-11");
+        }
+        other => panic!("unexpected decisions: {:?}", other),
+    }
+
+    let config_path = project_dir.path().join("audit.toml");
+    let mut editor = ConfigEditor::open(&config_path).unwrap();
+    assert_eq!(
+        triage::save(&mut editor, &findings, &decisions, &today).unwrap(),
+        2
+    );
+    editor.save().unwrap();
+
+    let written = fs::read_to_string(&config_path).unwrap();
+    let comment = format!(
+        "# {} 0.1.0: Test advisory (triaged 2021-01-01)",
+        findings[0].package.name
+    );
+    assert!(written.contains(&comment), "{}", written);
+    assert!(written.contains("ticket = \"JIRA-123\""), "{}", written);
+
+    let config: AuditConfig = toml::from_str(&written).unwrap();
+    assert_eq!(config.advisories.ignore.len(), 1);
+    assert_eq!(config.advisories.acknowledged.len(), 1);
+
+    auditor.apply_triage(&mut report, &decisions, &today);
+    assert_eq!(report.outcome, Some(Outcome::Clean));
+    assert_eq!(report.vulnerabilities.count, 0);
+    assert_eq!(report.vulnerabilities.ignored.len(), 1);
+}
+
+/// Findings left when the user quits fail the audit, and nothing is written
+#[test]
+fn quit() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let project_dir = tempfile::tempdir().unwrap();
+    let (mut auditor, mut report) = audit(db_dir.path(), &project_dir.path().join("Cargo.lock"));
+
+    let today: rustsec::advisory::Date = "2021-01-01".parse().unwrap();
+    let findings = triage::findings(&report);
+    let decisions = Triage::new(Cursor::new("f\nq\n"), vec![], today.clone())
+        .run(&findings, None)
+        .unwrap();
+    assert_eq!(decisions, [Decision::Fail, Decision::Fail]);
+
+    let mut editor = ConfigEditor::open(project_dir.path().join("audit.toml")).unwrap();
+    assert_eq!(
+        triage::save(&mut editor, &findings, &decisions, &today).unwrap(),
+        0
+    );
+
+    auditor.apply_triage(&mut report, &decisions, &today);
+    assert_eq!(report.outcome, Some(Outcome::VulnerabilitiesFound));
+}
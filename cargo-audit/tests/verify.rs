@@ -0,0 +1,146 @@
+//! Lockfile integrity check tests
+
+use cargo_audit::{
+    index::Client,
+    verify::{self, Check, FailureKind, Settings, Status},
+};
+use std::{
+    fs,
+    io::{Read, Write},
+    net::TcpListener,
+    path::Path,
+    process::Command,
+    thread,
+    time::Duration,
+};
+
+/// Checksum of `foo` 0.1.0
+const FOO_CHECKSUM: &str = "0b3b9ccc2d3edf1d52bdbde1f1ae3ea5da41d7cb12b4d536d2ff809eb3fd82c3";
+
+/// Index file for `foo`, whose 0.2.0 release was yanked
+const FOO_RELEASES: &str = r#"{"name":"foo","vers":"0.1.0","deps":[],"cksum":"0b3b9ccc2d3edf1d52bdbde1f1ae3ea5da41d7cb12b4d536d2ff809eb3fd82c3","features":{},"yanked":false}
+{"name":"foo","vers":"0.2.0","deps":[],"cksum":"2d3e1c3a2cdd2b5ba8d7f1c08bca9e4d59da42f1d0a166344a4d2a7ee6b2e441","features":{},"yanked":true}
+"#;
+
+/// Respond to a single request on a local port, serving the index file for
+/// `foo`, returning the URL of the index
+fn serve() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/index/", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 4096];
+        let _ = stream.read(&mut request).unwrap();
+
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            FOO_RELEASES.len(),
+            FOO_RELEASES
+        )
+        .unwrap();
+    });
+
+    url
+}
+
+/// Write a lockfile with a package for each of the given versions of `foo`
+/// and their checksums
+fn write_lockfile(dir: &Path, packages: &[(&str, &str)]) -> std::path::PathBuf {
+    let mut lockfile = String::from("version = 3\n");
+
+    for (version, checksum) in packages {
+        lockfile.push_str(&format!(
+            "\n[[package]]\nname = \"foo\"\nversion = \"{}\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+             checksum = \"{}\"\n",
+            version, checksum
+        ));
+    }
+
+    let path = dir.join("Cargo.lock");
+    fs::write(&path, lockfile).unwrap();
+    path
+}
+
+#[test]
+fn checksum_mismatch_and_yanked() {
+    let dir = tempfile::tempdir().unwrap();
+    let lockfile = write_lockfile(
+        dir.path(),
+        &[
+            ("0.1.0", FOO_CHECKSUM),
+            (
+                "0.2.0",
+                "1111111111111111111111111111111111111111111111111111111111111111",
+            ),
+        ],
+    );
+
+    let client = Client::new(
+        serve(),
+        dir.path().join("index.json"),
+        Duration::from_secs(3600),
+    );
+    let mut settings = Settings::default();
+    settings.skip.insert(Check::GitRevs);
+    settings.skip.insert(Check::Resolution);
+
+    let report = verify::verify(&lockfile, Some(&client), &settings).unwrap();
+    assert!(report.is_failure());
+    assert_eq!(report.failure_count(), 2);
+
+    let statuses: Vec<_> = report
+        .checks
+        .iter()
+        .map(|check| (check.check, check.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            (Check::Checksums, Status::Failed),
+            (Check::Yanked, Status::Failed),
+            (Check::GitRevs, Status::Skipped),
+            (Check::Resolution, Status::Skipped),
+        ]
+    );
+
+    for check in &report.checks[..2] {
+        assert_eq!(check.checked, 2);
+        assert_eq!(check.failures.len(), 1);
+        assert_eq!(
+            check.failures[0].version.as_ref().unwrap().to_string(),
+            "0.2.0"
+        );
+    }
+
+    assert_eq!(
+        report.checks[0].failures[0].kind,
+        FailureKind::ChecksumMismatch
+    );
+    assert_eq!(report.checks[1].failures[0].kind, FailureKind::Yanked);
+}
+
+#[test]
+fn skip_all_checks() {
+    let dir = tempfile::tempdir().unwrap();
+    let lockfile = write_lockfile(dir.path(), &[("0.1.0", FOO_CHECKSUM)]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--color", "never", "verify", "--json"])
+        .args(&["--skip-checksums", "--skip-yanked"])
+        .args(&["--skip-git-revs", "--skip-resolution", "--file"])
+        .arg(&lockfile)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["mode"], "verify");
+
+    let checks = json["checks"].as_array().unwrap();
+    assert_eq!(checks.len(), 4);
+    assert!(checks.iter().all(|check| check["status"] == "skipped"));
+}
@@ -0,0 +1,182 @@
+//! OpenVEX export (`cargo audit vex`) and ingestion (`--vex`) tests
+
+mod support;
+
+use std::{fs, process::Command};
+use support::Advisory;
+
+/// Advisory database with an advisory for versions of `base64` prior to 0.5.2
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::base64().attributes("aliases = [\"CVE-2017-1000430\"]")])
+}
+
+/// Export the ignores in the project's `audit.toml` as an OpenVEX document
+fn vex(ignore: &str) -> serde_json::Value {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+    let project = project_dir.path();
+
+    let config_dir = project.join(".cargo");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("audit.toml"),
+        format!("[advisories]\nignore = [{}]\n", ignore),
+    )
+    .unwrap();
+
+    let output_path = project.join("vex.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_dir.path())
+        .args(&["vex", "--author", "Security Team", "--file"])
+        .arg(fs::canonicalize("tests/support/base64_vuln/Cargo.lock").unwrap())
+        .arg("--output")
+        .arg(&output_path)
+        .env("CARGO_HOME", project)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap()
+}
+
+/// Each ignored advisory matching the lockfile is a `not_affected` statement
+/// about the workspace, with the vulnerable crate as a subcomponent
+#[test]
+fn export_ignores() {
+    let document = vex(
+        "{ id = \"RUSTSEC-2017-0004\", reason = \"only decodes trusted input\", \
+         justification = \"vulnerable_code_not_in_execute_path\" }",
+    );
+
+    assert_eq!(document["@context"], "https://openvex.dev/ns/v0.2.0");
+    assert!(document["@id"]
+        .as_str()
+        .unwrap()
+        .starts_with("urn:cargo-audit:vex:"));
+    assert_eq!(document["author"], "Security Team");
+    assert_eq!(document["version"], 1);
+
+    let statements = document["statements"].as_array().unwrap();
+    assert_eq!(statements.len(), 1);
+
+    let statement = &statements[0];
+    assert_eq!(statement["vulnerability"]["name"], "RUSTSEC-2017-0004");
+    assert_eq!(
+        statement["vulnerability"]["aliases"],
+        serde_json::json!(["CVE-2017-1000430"])
+    );
+    assert_eq!(statement["status"], "not_affected");
+    assert_eq!(
+        statement["justification"],
+        "vulnerable_code_not_in_execute_path"
+    );
+    assert_eq!(statement["impact_statement"], "only decodes trusted input");
+    assert_eq!(
+        statement["products"],
+        serde_json::json!([{
+            "@id": "pkg:cargo/base64_vuln@0.1.0",
+            "subcomponents": [{ "@id": "pkg:cargo/base64@0.5.1" }]
+        }])
+    );
+}
+
+/// Ignores without a reason or justification still get an impact statement,
+/// as OpenVEX requires one of them
+#[test]
+fn export_bare_ignore() {
+    let document = vex("\"RUSTSEC-2017-0004\"");
+    let statement = &document["statements"][0];
+
+    assert!(statement.get("justification").is_none());
+    assert_eq!(
+        statement["impact_statement"],
+        "Ignored in the cargo-audit configuration"
+    );
+}
+
+/// Audit the `base64_vuln` lockfile with the given VEX document, returning
+/// whether the audit succeeded and its JSON report
+fn audit_with_vex(document: serde_json::Value) -> (bool, serde_json::Value) {
+    let db_dir = database();
+    let project_dir = tempfile::tempdir().unwrap();
+    let project = project_dir.path();
+
+    let vex_path = project.join("vex.json");
+    fs::write(&vex_path, document.to_string()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project)
+        .args(&["audit", "--no-fetch", "--color", "never", "--json", "--db"])
+        .arg(db_dir.path())
+        .arg("--file")
+        .arg(fs::canonicalize("tests/support/base64_vuln/Cargo.lock").unwrap())
+        .arg("--vex")
+        .arg(&vex_path)
+        .env("CARGO_HOME", project)
+        .output()
+        .unwrap();
+
+    let report = serde_json::from_slice(&output.stdout).unwrap();
+    (output.status.success(), report)
+}
+
+/// VEX document with a single statement about the given product
+fn vex_document(status: &str, product: &str) -> serde_json::Value {
+    serde_json::json!({
+        "@context": "https://openvex.dev/ns/v0.2.0",
+        "@id": "https://example.com/vex/1",
+        "author": "Vendor",
+        "timestamp": "2023-01-01T00:00:00Z",
+        "version": 1,
+        "statements": [{
+            "vulnerability": { "name": "CVE-2017-1000430" },
+            "products": [{ "@id": product }],
+            "status": status,
+            "justification": "vulnerable_code_not_in_execute_path"
+        }]
+    })
+}
+
+/// Vulnerabilities a statement marks `not_affected` are suppressed, with the
+/// statement recorded in the report
+#[test]
+fn suppress_not_affected() {
+    let (success, report) = audit_with_vex(vex_document("not_affected", "pkg:cargo/base64"));
+
+    assert!(success, "{}", report);
+    assert_eq!(report["vulnerabilities"]["found"], false);
+
+    let suppressed = report["vulnerabilities"]["vex"].as_array().unwrap();
+    assert_eq!(suppressed.len(), 1);
+    assert_eq!(
+        suppressed[0]["vulnerability"]["advisory"]["id"],
+        "RUSTSEC-2017-0004"
+    );
+    assert_eq!(suppressed[0]["document"], "https://example.com/vex/1");
+    assert_eq!(suppressed[0]["status"], "not_affected");
+    assert_eq!(
+        suppressed[0]["justification"],
+        "vulnerable_code_not_in_execute_path"
+    );
+}
+
+/// Statements about a workspace member as a whole apply to its dependencies
+#[test]
+fn suppress_fixed_for_workspace_member() {
+    let (success, report) = audit_with_vex(vex_document("fixed", "pkg:cargo/base64_vuln@0.1.0"));
+
+    assert!(success, "{}", report);
+    assert_eq!(report["vulnerabilities"]["vex"][0]["status"], "fixed");
+}
+
+/// Statements saying the product is affected don't suppress anything
+#[test]
+fn keep_affected() {
+    let (success, report) = audit_with_vex(vex_document("affected", "pkg:cargo/base64"));
+
+    assert!(!success);
+    assert_eq!(report["vulnerabilities"]["found"], true);
+    assert!(report["vulnerabilities"].get("vex").is_none());
+}
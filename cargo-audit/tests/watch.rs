@@ -0,0 +1,105 @@
+//! Watch mode (`cargo audit --watch`) tests
+
+mod support;
+
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use support::Advisory;
+
+/// How long to wait for each report
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Advisory database with an advisory for `foo` versions before 0.2.0
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::new("RUSTSEC-2020-0001", "foo")])
+}
+
+/// Write a lockfile with the given packages (names and versions)
+fn write_lockfile(path: &Path, packages: &[(&str, &str)]) {
+    let lockfile: String = packages
+        .iter()
+        .map(|(name, version)| {
+            format!(
+                "[[package]]\n\
+                 name = \"{}\"\n\
+                 version = \"{}\"\n\
+                 source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+                name, version
+            )
+        })
+        .collect();
+
+    fs::write(path, lockfile).unwrap();
+}
+
+/// The first report is printed in full, then only the findings introduced
+/// or resolved when the lockfile changes
+#[test]
+fn watch_lockfile() {
+    let db_dir = database();
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let lockfile_path = project_dir.path().join("Cargo.lock");
+    write_lockfile(&lockfile_path, &[("foo", "0.2.0")]);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(project_dir.path())
+        .args(&["audit", "--no-fetch", "--watch", "--json", "--db"])
+        .arg(db_dir.path())
+        .env("CARGO_HOME", project_dir.path())
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in stdout.lines() {
+            let line = line.unwrap();
+
+            if sender
+                .send(serde_json::from_str::<serde_json::Value>(&line).unwrap())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let report = receiver.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(report["vulnerabilities"]["count"], 0);
+
+    write_lockfile(&lockfile_path, &[("bar", "1.0.0"), ("foo", "0.1.0")]);
+    let delta = receiver.recv_timeout(TIMEOUT).unwrap();
+
+    write_lockfile(&lockfile_path, &[("foo", "0.2.0")]);
+    let resolved = receiver.recv_timeout(TIMEOUT);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let introduced = &delta["introduced"]["vulnerabilities"];
+    assert_eq!(introduced.as_array().unwrap().len(), 1, "{}", delta);
+    assert_eq!(introduced[0]["advisory"]["id"], "RUSTSEC-2020-0001");
+    assert!(delta["unchanged"]["vulnerabilities"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    let resolved = resolved.unwrap();
+    assert_eq!(
+        resolved["resolved"]["vulnerabilities"][0]["advisory"]["id"],
+        "RUSTSEC-2020-0001"
+    );
+}
@@ -0,0 +1,172 @@
+//! `cargo audit whats-new` tests
+
+mod support;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Lockfile depending on `bar`
+const LOCKFILE: &str = "\
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Advisory with the given ID for versions of `package` prior to 0.2.0
+fn advisory(id: &str, package: &str) -> String {
+    Advisory::new(id, package).date("2021-01-01").markdown()
+}
+
+/// Commit the given files to the repository, returning the commit's ID
+fn commit(repo: &git2::Repository, files: &[(&str, String)]) -> String {
+    let workdir = repo.workdir().unwrap();
+    let mut index = repo.index().unwrap();
+
+    for (name, contents) in files {
+        let path = workdir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        index.add_path(Path::new(name)).unwrap();
+    }
+
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "test commit",
+        &tree,
+        parent.iter().collect::<Vec<_>>().as_slice(),
+    )
+    .unwrap()
+    .to_string()
+}
+
+/// Run `cargo audit whats-new` in the given directory
+fn whats_new(dir: &Path, db_path: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db_path)
+        .arg("whats-new")
+        .args(args)
+        .current_dir(dir)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+/// IDs of the new advisories in a JSON report
+fn ids(report: &serde_json::Value) -> Vec<String> {
+    report["advisories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|new| new["advisory"]["id"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn since_commit() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(db_dir.path()).unwrap();
+    let reference = commit(
+        &repo,
+        &[(
+            "crates/foo/RUSTSEC-2021-0001.md",
+            advisory("RUSTSEC-2021-0001", "foo"),
+        )],
+    );
+    commit(
+        &repo,
+        &[(
+            "crates/bar/RUSTSEC-2021-0002.md",
+            advisory("RUSTSEC-2021-0002", "bar"),
+        )],
+    );
+
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join("Cargo.lock"), LOCKFILE).unwrap();
+
+    let output = whats_new(
+        project_dir.path(),
+        db_dir.path(),
+        &["--since-commit", &reference, "--json"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["mode"], "whats-new");
+    assert_eq!(ids(&report), ["RUSTSEC-2021-0002"]);
+    assert!(report["advisories"][0]["matches"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    // The new advisory affects the lockfile
+    let output = whats_new(
+        project_dir.path(),
+        db_dir.path(),
+        &["--since-commit", &reference, "--file", "Cargo.lock"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("bar 0.1.0 (Cargo.lock)"), "{}", stdout);
+    assert!(stderr.contains("1 new advisory since commit"), "{}", stderr);
+
+    // Every advisory is new since before the first commit
+    let output = whats_new(
+        project_dir.path(),
+        db_dir.path(),
+        &["--since-date", "2000-01-01", "--json"],
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(ids(&report), ["RUSTSEC-2021-0001", "RUSTSEC-2021-0002"]);
+}
+
+#[test]
+fn since_snapshot() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let advisory_dir = db_dir.path().join("crates").join("foo");
+    fs::create_dir_all(&advisory_dir).unwrap();
+    fs::write(
+        advisory_dir.join("RUSTSEC-2021-0001.md"),
+        advisory("RUSTSEC-2021-0001", "foo"),
+    )
+    .unwrap();
+
+    let project_dir = tempfile::tempdir().unwrap();
+    let output = whats_new(
+        project_dir.path(),
+        db_dir.path(),
+        &["--save-snapshot", "snapshot.json"],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(project_dir.path().join("snapshot.json").is_file());
+
+    let advisory_dir = db_dir.path().join("crates").join("bar");
+    fs::create_dir_all(&advisory_dir).unwrap();
+    fs::write(
+        advisory_dir.join("RUSTSEC-2021-0002.md"),
+        advisory("RUSTSEC-2021-0002", "bar"),
+    )
+    .unwrap();
+
+    let output = whats_new(
+        project_dir.path(),
+        db_dir.path(),
+        &["--since-snapshot", "snapshot.json", "--json"],
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(ids(&report), ["RUSTSEC-2021-0002"]);
+    assert_eq!(report["since"]["snapshot"], "snapshot.json");
+}
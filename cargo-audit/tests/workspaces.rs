@@ -0,0 +1,119 @@
+//! Tests for auditing every lockfile under a directory
+
+mod support;
+
+use cargo_audit::workspaces;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+use support::Advisory;
+
+/// Advisory database with an advisory for versions of `base64` prior to 0.5.2
+fn database() -> tempfile::TempDir {
+    support::database(&[Advisory::base64()])
+}
+
+/// Copy the lockfile of the given test project into a directory of the tree
+fn copy_lockfile(project: &str, dir: &Path) {
+    fs::create_dir_all(dir).unwrap();
+    fs::copy(
+        Path::new("tests/support").join(project).join("Cargo.lock"),
+        dir.join("Cargo.lock"),
+    )
+    .unwrap();
+}
+
+/// Tree with a vulnerable and a clean workspace, along with lockfiles in
+/// directories which aren't searched
+fn write_tree(root: &Path) {
+    copy_lockfile("base64_vuln", &root.join("services").join("api"));
+    copy_lockfile("no_vulns", &root.join("tools"));
+    copy_lockfile("base64_vuln", &root.join("tools").join("target"));
+    copy_lockfile("base64_vuln", &root.join(".git"));
+}
+
+/// Run `cargo audit --recursive` in the given directory against the database
+fn cargo_audit(root: &Path, db: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cargo-audit"))
+        .current_dir(root)
+        .args(&["audit", "--no-fetch", "--color", "never", "--db"])
+        .arg(db)
+        .arg("--recursive")
+        .args(args)
+        .env("CARGO_HOME", root)
+        .env("CARGO_AUDIT_YANKED_ENABLED", "false")
+        .env("CARGO_AUDIT_REGISTRY_LOOKUP", "false")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn discover_lockfiles() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+    copy_lockfile("no_vulns", dir.path());
+
+    assert_eq!(
+        workspaces::discover(dir.path()).unwrap(),
+        vec![
+            PathBuf::from("Cargo.lock"),
+            Path::new("services").join("api").join("Cargo.lock"),
+            Path::new("tools").join("Cargo.lock"),
+        ]
+    );
+}
+
+#[test]
+fn combined_report() {
+    let db_dir = database();
+    let dir = tempfile::tempdir().unwrap();
+    write_tree(dir.path());
+
+    let output = cargo_audit(dir.path(), db_dir.path(), &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("(1 vulnerability)"), "{}", stdout);
+    assert!(stdout.contains("RUSTSEC-2017-0004"), "{}", stdout);
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("1 vulnerability found in 1 of 2 lockfiles"),
+        "{:?}",
+        output
+    );
+
+    let output = cargo_audit(dir.path(), db_dir.path(), &["--json"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["mode"], "workspaces");
+    assert_eq!(report["outcome"], "vulnerabilities-found");
+
+    let lockfiles = report["lockfiles"].as_array().unwrap();
+    assert_eq!(lockfiles.len(), 2);
+    assert_eq!(lockfiles[0]["report"]["vulnerabilities"]["count"], 1);
+    assert_eq!(lockfiles[1]["report"]["vulnerabilities"]["count"], 0);
+}
+
+#[test]
+fn unreadable_lockfile() {
+    let db_dir = database();
+    let dir = tempfile::tempdir().unwrap();
+    copy_lockfile("no_vulns", &dir.path().join("good"));
+    fs::create_dir_all(dir.path().join("bad")).unwrap();
+    fs::write(dir.path().join("bad").join("Cargo.lock"), "not a lockfile").unwrap();
+
+    let output = cargo_audit(dir.path(), db_dir.path(), &[]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("couldn't audit bad"),
+        "{:?}",
+        output
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("good"),
+        "{:?}",
+        output
+    );
+}
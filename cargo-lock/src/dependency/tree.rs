@@ -9,8 +9,15 @@ use super::{
     graph::{EdgeDirection, Graph, NodeIndex, Nodes},
     Dependency,
 };
-use crate::{error::Error, lockfile::Lockfile, Map};
-use std::{collections::BTreeSet as Set, io};
+use crate::{
+    error::{Error, ErrorKind},
+    lockfile::Lockfile,
+    Map,
+};
+use std::{
+    collections::{BTreeSet as Set, VecDeque},
+    io,
+};
 
 /// Dependency tree computed from a `Cargo.lock` file
 #[derive(Clone, Debug)]
@@ -24,6 +31,9 @@ pub struct Tree {
 
 impl Tree {
     /// Construct a new dependency tree for the given [`Lockfile`].
+    ///
+    /// Returns an error if the lockfile contains duplicate package entries,
+    /// or dependencies which don't match any package entry.
     pub fn new(lockfile: &Lockfile) -> Result<Self, Error> {
         let mut graph = Graph::new();
         let mut nodes = Map::new();
@@ -31,7 +41,18 @@ impl Tree {
         // Populate all graph nodes in the first pass
         for package in &lockfile.packages {
             let node_index = graph.add_node(package.clone());
-            nodes.insert(Dependency::from(package), node_index);
+
+            if nodes
+                .insert(Dependency::from(package), node_index)
+                .is_some()
+            {
+                fail!(
+                    ErrorKind::Parse,
+                    "duplicate entry for package {} {}",
+                    package.name,
+                    package.version
+                );
+            }
         }
 
         // Populate all graph edges in the second pass
@@ -39,8 +60,18 @@ impl Tree {
             let parent_index = nodes[&Dependency::from(package)];
 
             for dependency in &package.dependencies {
-                if let Some(node_index) = nodes.get(dependency) {
-                    graph.add_edge(parent_index, *node_index, dependency.clone());
+                match nodes.get(dependency) {
+                    Some(node_index) => {
+                        graph.add_edge(parent_index, *node_index, dependency.clone());
+                    }
+                    None => fail!(
+                        ErrorKind::Parse,
+                        "package {} {} depends on {} {}, which has no package entry",
+                        package.name,
+                        package.version,
+                        dependency.name,
+                        dependency.version
+                    ),
                 }
             }
         }
@@ -68,7 +99,98 @@ impl Tree {
         direction: EdgeDirection,
         symbols: &Symbols,
     ) -> io::Result<()> {
-        Presenter::new(&self.graph, symbols).print_node(w, node_index, direction)
+        Presenter::new(&self.graph, symbols, None).print_node(w, node_index, direction)
+    }
+
+    /// Render the dependency graph for the given [`NodeIndex`] using the
+    /// default set of [`Symbols`], down to the given depth.
+    ///
+    /// Nodes at that depth whose own dependencies (or dependents) are elided
+    /// are followed by a `...` line.
+    pub fn render_to_depth(
+        &self,
+        w: &mut impl io::Write,
+        node_index: NodeIndex,
+        direction: EdgeDirection,
+        max_depth: usize,
+    ) -> io::Result<()> {
+        Presenter::new(&self.graph, &Symbols::default(), Some(max_depth))
+            .print_node(w, node_index, direction)
+    }
+
+    /// Compute the shortest inverse path from the given [`NodeIndex`] to a
+    /// workspace member (i.e. a package without a source, or a root package),
+    /// via the packages which depend on it.
+    ///
+    /// The path starts with the given node, and ends with the workspace member.
+    pub fn shortest_inverse_path(&self, node_index: NodeIndex) -> Vec<NodeIndex> {
+        // Breadth-first search, recording the node each one was reached from
+        let mut reached_from = Map::new();
+        let mut queue = VecDeque::new();
+        let mut member = node_index;
+        queue.push_back(node_index);
+
+        while let Some(index) = queue.pop_front() {
+            let mut dependents = self
+                .graph
+                .neighbors_directed(index, EdgeDirection::Incoming)
+                .peekable();
+
+            let is_local = index != node_index && self.graph[index].source.is_none();
+
+            if is_local || dependents.peek().is_none() {
+                member = index;
+                break;
+            }
+
+            for dependent in dependents {
+                if dependent != node_index && !reached_from.contains_key(&dependent) {
+                    reached_from.insert(dependent, index);
+                    queue.push_back(dependent);
+                }
+            }
+        }
+
+        let mut path = vec![member];
+
+        while let Some(&index) = reached_from.get(&path[path.len() - 1]) {
+            path.push(index);
+        }
+
+        path.reverse();
+        path
+    }
+
+    /// Render the shortest inverse path from the given [`NodeIndex`] to a
+    /// workspace member (see [`Tree::shortest_inverse_path`]) using the
+    /// default set of [`Symbols`].
+    pub fn render_shortest_path(
+        &self,
+        w: &mut impl io::Write,
+        node_index: NodeIndex,
+    ) -> io::Result<()> {
+        let symbols = Symbols::default();
+
+        for (depth, index) in self
+            .shortest_inverse_path(node_index)
+            .into_iter()
+            .enumerate()
+        {
+            if depth > 0 {
+                write!(
+                    w,
+                    "{0}{1}{2}{2} ",
+                    "    ".repeat(depth - 1),
+                    symbols.ell,
+                    symbols.right
+                )?;
+            }
+
+            let package = &self.graph[index];
+            writeln!(w, "{} {}", &package.name, &package.version)?;
+        }
+
+        Ok(())
     }
 
     /// Get the indexes of the root packages in the workspace
@@ -120,16 +242,20 @@ struct Presenter<'g, 's> {
 
     /// Dependencies we've already visited
     visited: Set<NodeIndex>,
+
+    /// Depth below which dependencies are elided
+    max_depth: Option<usize>,
 }
 
 impl<'g, 's> Presenter<'g, 's> {
     /// Create a new dependency tree `Presenter`.
-    fn new(graph: &'g Graph, symbols: &'s Symbols) -> Self {
+    fn new(graph: &'g Graph, symbols: &'s Symbols, max_depth: Option<usize>) -> Self {
         Self {
             graph,
             symbols,
             levels_continue: vec![],
             visited: Set::new(),
+            max_depth,
         }
     }
 
@@ -143,24 +269,26 @@ impl<'g, 's> Presenter<'g, 's> {
         let package = &self.graph[node_index];
         let new = self.visited.insert(node_index);
 
-        if let Some((&last_continues, rest)) = self.levels_continue.split_last() {
-            for &continues in rest {
-                let c = if continues { self.symbols.down } else { " " };
-                write!(w, "{}   ", c)?;
-            }
-
-            let c = if last_continues {
-                self.symbols.tee
-            } else {
-                self.symbols.ell
-            };
+        self.print_prefix(w)?;
+        writeln!(w, "{} {}", &package.name, &package.version)?;
 
-            write!(w, "{0}{1}{1} ", c, self.symbols.right)?;
+        if !new {
+            return Ok(());
         }
 
-        writeln!(w, "{} {}", &package.name, &package.version)?;
+        if self.max_depth == Some(self.levels_continue.len()) {
+            if self
+                .graph
+                .edges_directed(node_index, direction)
+                .next()
+                .is_some()
+            {
+                self.levels_continue.push(false);
+                self.print_prefix(w)?;
+                writeln!(w, "...")?;
+                self.levels_continue.pop();
+            }
 
-        if !new {
             return Ok(());
         }
 
@@ -182,6 +310,26 @@ impl<'g, 's> Presenter<'g, 's> {
 
         Ok(())
     }
+
+    /// Print the lines leading up to a node at the current level.
+    fn print_prefix(&self, w: &mut impl io::Write) -> io::Result<()> {
+        if let Some((&last_continues, rest)) = self.levels_continue.split_last() {
+            for &continues in rest {
+                let c = if continues { self.symbols.down } else { " " };
+                write!(w, "{}   ", c)?;
+            }
+
+            let c = if last_continues {
+                self.symbols.tee
+            } else {
+                self.symbols.ell
+            };
+
+            write!(w, "{0}{1}{1} ", c, self.symbols.right)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -208,4 +356,41 @@ mod tests {
         let root_package = &tree.graph[roots[0]];
         assert_eq!(root_package.name.as_str(), "cargo-lock");
     }
+
+    #[test]
+    fn compute_shortest_inverse_path() {
+        let tree = Tree::new(&load_lockfile()).unwrap();
+        let node = tree
+            .nodes()
+            .keys()
+            .find(|dep| dep.name.as_str() == "serde_derive")
+            .unwrap();
+        let path = tree.shortest_inverse_path(tree.nodes()[node]);
+
+        assert_eq!(tree.graph[path[0]].name.as_str(), "serde_derive");
+        assert_eq!(
+            tree.graph[*path.last().unwrap()].name.as_str(),
+            "cargo-lock"
+        );
+    }
+
+    #[test]
+    fn render_to_depth() {
+        let tree = Tree::new(&load_lockfile()).unwrap();
+        let root = tree.roots()[0];
+        let mut rendered = vec![];
+        tree.render_to_depth(&mut rendered, root, EdgeDirection::Outgoing, 0)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(rendered)
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            [
+                &format!("cargo-lock {}", tree.graph[root].version),
+                "└── ..."
+            ]
+        );
+    }
 }
@@ -74,7 +74,13 @@ impl TryFrom<EncodableLockfile> for Lockfile {
     type Error = Error;
 
     fn try_from(raw_lockfile: EncodableLockfile) -> Result<Lockfile, Error> {
-        let version = ResolveVersion::detect(&raw_lockfile.package, &raw_lockfile.metadata)?;
+        let detected = ResolveVersion::detect(&raw_lockfile.package, &raw_lockfile.metadata)?;
+
+        // Versions 3 and newer are encoded explicitly, older ones detected
+        let version = match raw_lockfile.version {
+            Some(version) => ResolveVersion::try_from(version)?,
+            None => detected,
+        };
         let mut packages = Vec::with_capacity(raw_lockfile.package.len());
 
         for raw_package in &raw_lockfile.package {
@@ -129,6 +135,10 @@ impl From<&Lockfile> for EncodableLockfile {
                 metadata.remove(&checksum_key);
             }
 
+            if lockfile.version >= ResolveVersion::V4 {
+                raw_pkg.url_encode_sources();
+            }
+
             packages.push(raw_pkg);
         }
 
@@ -164,6 +174,12 @@ impl ToString for EncodableLockfile {
         out.push_str(extra_line);
         out.push('\n');
 
+        // Versions which can't be autodetected (V3 and newer) are written
+        // out explicitly, as Cargo does
+        if let Some(version) = self.version {
+            out.push_str(&format!("version = {}\n\n", version));
+        }
+
         let deps = toml["package"].as_array().unwrap();
         for dep in deps {
             let dep = dep.as_table().unwrap();
@@ -239,7 +255,7 @@ pub(crate) struct EncodablePackage {
     pub(super) version: Version,
 
     /// Source of a package
-    pub(super) source: Option<SourceId>,
+    pub(super) source: Option<EncodableSourceId>,
 
     /// Package checksum
     pub(super) checksum: Option<Checksum>,
@@ -265,7 +281,7 @@ impl EncodablePackage {
         Ok(Package {
             name: self.name.clone(),
             version: self.version.clone(),
-            source: self.source.clone(),
+            source: self.source_id(),
             checksum: self.checksum.clone(),
             dependencies,
             replace: self
@@ -282,6 +298,24 @@ impl EncodablePackage {
             dependency.v2(packages);
         }
     }
+
+    /// Percent-encode the git references of the sources of the package and
+    /// its dependencies, for `ResolveVersion::V4` and newer
+    fn url_encode_sources(&mut self) {
+        let dependencies = self.dependencies.iter_mut().chain(self.replace.as_mut());
+
+        for source in dependencies
+            .filter_map(|dep| dep.source.as_mut())
+            .chain(self.source.as_mut())
+        {
+            source.url_encoded = true;
+        }
+    }
+
+    /// Source of the package (if it isn't a path)
+    pub(super) fn source_id(&self) -> Option<SourceId> {
+        self.source.as_ref().map(|source| source.source_id.clone())
+    }
 }
 
 /// Note: this only works for `ResolveVersion::V1` dependencies.
@@ -298,7 +332,7 @@ impl From<&Package> for EncodablePackage {
         EncodablePackage {
             name: package.name.clone(),
             version: package.version.clone(),
-            source: package.source.clone(),
+            source: package.source.clone().map(EncodableSourceId::new),
             checksum: package.checksum.clone(),
             dependencies: package
                 .dependencies
@@ -320,7 +354,7 @@ pub(crate) struct EncodableDependency {
     pub(super) version: Option<Version>,
 
     /// Source for the dependency
-    pub(super) source: Option<SourceId>,
+    pub(super) source: Option<EncodableSourceId>,
 }
 
 impl EncodableDependency {
@@ -332,7 +366,18 @@ impl EncodableDependency {
 
         if let Some(v) = &self.version {
             version = Some(v.clone());
-            source = self.source.clone();
+            source = self.source.as_ref().map(|s| s.source_id.clone());
+
+            // The source is omitted if the name and version are unambiguous
+            if source.is_none() {
+                let mut matching = packages
+                    .iter()
+                    .filter(|pkg| pkg.name == self.name && pkg.version == *v);
+
+                if let (Some(pkg), None) = (matching.next(), matching.next()) {
+                    source = pkg.source_id();
+                }
+            }
         } else {
             for pkg in packages {
                 if pkg.name == self.name {
@@ -341,7 +386,7 @@ impl EncodableDependency {
                     }
 
                     version = Some(pkg.version.clone());
-                    source = pkg.source.clone();
+                    source = pkg.source_id();
                 }
             }
         };
@@ -375,6 +420,14 @@ impl EncodableDependency {
         if matching.len() == 1 {
             self.version = None;
             self.source = None;
+        } else if self.version.is_some()
+            && matching
+                .iter()
+                .filter(|package| Some(&package.version) == self.version.as_ref())
+                .count()
+                == 1
+        {
+            self.source = None;
         }
     }
 }
@@ -402,7 +455,7 @@ impl FromStr for EncodableDependency {
                         s
                     ))
                 } else {
-                    s[1..(s.len() - 1)].parse()
+                    s[1..(s.len() - 1)].parse().map(EncodableSourceId::new)
                 }
             })
             .transpose()?;
@@ -449,7 +502,7 @@ impl From<&Dependency> for EncodableDependency {
         EncodableDependency {
             name: package.name.clone(),
             version: Some(package.version.clone()),
-            source: package.source.clone(),
+            source: package.source.clone().map(EncodableSourceId::new),
         }
     }
 }
@@ -467,3 +520,45 @@ impl Serialize for EncodableDependency {
         self.to_string().serialize(serializer)
     }
 }
+
+/// Source of a package as it's encoded in a lockfile
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub(crate) struct EncodableSourceId {
+    /// The source
+    source_id: SourceId,
+
+    /// Are git references percent-encoded? (`ResolveVersion::V4` and newer)
+    url_encoded: bool,
+}
+
+impl EncodableSourceId {
+    /// Encode the given source without percent-encoding git references
+    fn new(source_id: SourceId) -> Self {
+        Self {
+            source_id,
+            url_encoded: false,
+        }
+    }
+}
+
+impl fmt::Display for EncodableSourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source_id.fmt_url(f, self.url_encoded)
+    }
+}
+
+impl<'de> Deserialize<'de> for EncodableSourceId {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SourceId::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl Serialize for EncodableSourceId {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.source_id.is_path() {
+            None::<String>.serialize(serializer)
+        } else {
+            serializer.collect_str(self)
+        }
+    }
+}
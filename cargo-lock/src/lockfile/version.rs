@@ -30,6 +30,13 @@ pub enum ResolveVersion {
     /// For more information, see:
     /// <https://internals.rust-lang.org/t/upcoming-changes-to-cargo-lock/14017>
     V3 = 3,
+
+    /// Percent-encodes Git references in source URLs (e.g. a branch named
+    /// `feat/x` is encoded as `?branch=feat%2Fx`).
+    ///
+    /// For more information, see:
+    /// <https://github.com/rust-lang/cargo/pull/12280>
+    V4 = 4,
 }
 
 impl ResolveVersion {
@@ -57,8 +64,11 @@ impl ResolveVersion {
     }
 
     /// Should this version be explicitly encoded?
+    ///
+    /// Versions 1 and 2 are autodetected, while cargo writes a `version`
+    /// field for version 3 and newer.
     pub(super) fn is_explicit(self) -> bool {
-        u32::from(self) > 3
+        u32::from(self) >= 3
     }
 }
 
@@ -101,6 +111,7 @@ impl TryFrom<u32> for ResolveVersion {
             1 => Ok(ResolveVersion::V1),
             2 => Ok(ResolveVersion::V2),
             3 => Ok(ResolveVersion::V3),
+            4 => Ok(ResolveVersion::V4),
             _ => fail!(
                 ErrorKind::Parse,
                 "invalid Cargo.lock format version: `{}`",
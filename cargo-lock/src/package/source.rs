@@ -18,6 +18,9 @@ use std::path::Path;
 /// Location of the crates.io index
 pub const CRATES_IO_INDEX: &str = "https://github.com/rust-lang/crates.io-index";
 
+/// Location of the crates.io sparse index
+pub const CRATES_IO_SPARSE_INDEX: &str = "https://index.crates.io/";
+
 /// Default branch name
 pub const DEFAULT_BRANCH: &str = "master";
 
@@ -50,6 +53,9 @@ enum SourceKind {
     /// A remote registry.
     Registry,
 
+    /// A remote registry accessed over HTTP using the sparse protocol.
+    SparseRegistry,
+
     /// A local filesystem-based registry.
     #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     LocalRegistry,
@@ -111,7 +117,15 @@ impl SourceId {
                 Ok(SourceId::new(SourceKind::Registry, url)?
                     .with_precise(Some("locked".to_string())))
             }
+            "sparse" => {
+                let url = url.into_url()?;
+                Ok(SourceId::new(SourceKind::SparseRegistry, url)?
+                    .with_precise(Some("locked".to_string())))
+            }
             "path" => Self::new(SourceKind::Path, url.into_url()?),
+            "local-registry" => Self::new(SourceKind::LocalRegistry, url.into_url()?),
+            #[cfg(any(unix, windows))]
+            "directory" => Self::new(SourceKind::Directory, url.into_url()?),
             kind => fail!(ErrorKind::Parse, "unsupported source protocol: {}", kind),
         }
     }
@@ -179,7 +193,7 @@ impl SourceId {
     /// Returns `true` if this source is from a registry (either local or not).
     pub fn is_registry(&self) -> bool {
         match self.kind {
-            SourceKind::Registry | SourceKind::LocalRegistry => true,
+            SourceKind::Registry | SourceKind::SparseRegistry | SourceKind::LocalRegistry => true,
             _ => false,
         }
     }
@@ -189,7 +203,16 @@ impl SourceId {
     /// "remote" may also mean a file URL to a git index, so it is not
     /// necessarily "remote". This just means it is not `local-registry`.
     pub fn is_remote_registry(&self) -> bool {
-        self.kind == SourceKind::Registry
+        match self.kind {
+            SourceKind::Registry | SourceKind::SparseRegistry => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if this source is a registry accessed using the sparse
+    /// protocol.
+    pub fn is_sparse(&self) -> bool {
+        self.kind == SourceKind::SparseRegistry
     }
 
     /// Returns `true` if this source from a Git repository.
@@ -222,22 +245,19 @@ impl SourceId {
         }
     }
 
-    /// Returns `true` if the remote registry is the standard <https://crates.io>.
+    /// Returns `true` if the remote registry is the standard <https://crates.io>,
+    /// accessed using either the git or the sparse protocol.
     pub fn is_default_registry(&self) -> bool {
-        self.kind == SourceKind::Registry && self.url.as_str() == CRATES_IO_INDEX
-    }
-}
-
-impl FromStr for SourceId {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Error> {
-        Self::from_url(s)
+        match self.kind {
+            SourceKind::Registry => self.url.as_str() == CRATES_IO_INDEX,
+            SourceKind::SparseRegistry => self.url.as_str() == CRATES_IO_SPARSE_INDEX,
+            _ => false,
+        }
     }
-}
 
-impl fmt::Display for SourceId {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    /// Write the URL of this source, percent-encoding git references if
+    /// `url_encoded` (as `ResolveVersion::V4` lockfiles and newer do)
+    pub(crate) fn fmt_url(&self, f: &mut fmt::Formatter<'_>, url_encoded: bool) -> fmt::Result {
         match self {
             SourceId {
                 kind: SourceKind::Path,
@@ -251,7 +271,7 @@ impl fmt::Display for SourceId {
                 ..
             } => {
                 write!(f, "git+{}", url)?;
-                if let Some(pretty) = reference.pretty_ref() {
+                if let Some(pretty) = reference.pretty_ref_with(url_encoded) {
                     write!(f, "?{}", pretty)?;
                 }
                 if let Some(precise) = precise.as_ref() {
@@ -264,6 +284,11 @@ impl fmt::Display for SourceId {
                 ref url,
                 ..
             } => write!(f, "registry+{}", url),
+            SourceId {
+                kind: SourceKind::SparseRegistry,
+                ref url,
+                ..
+            } => write!(f, "sparse+{}", url),
             SourceId {
                 kind: SourceKind::LocalRegistry,
                 ref url,
@@ -279,6 +304,20 @@ impl fmt::Display for SourceId {
     }
 }
 
+impl FromStr for SourceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::from_url(s)
+    }
+}
+
+impl fmt::Display for SourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_url(f, false)
+    }
+}
+
 impl Serialize for SourceId {
     fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         if self.is_path() {
@@ -313,9 +352,19 @@ impl GitReference {
     /// Returns a `Display`able view of this git reference, or None if using
     /// the head of the default branch
     pub fn pretty_ref(&self) -> Option<PrettyRef<'_>> {
+        self.pretty_ref_with(false)
+    }
+
+    /// Returns a `Display`able view of this git reference (percent-encoding
+    /// its value if `url_encoded`), or None if using the head of the default
+    /// branch
+    fn pretty_ref_with(&self, url_encoded: bool) -> Option<PrettyRef<'_>> {
         match *self {
             GitReference::Branch(ref s) if *s == DEFAULT_BRANCH => None,
-            _ => Some(PrettyRef { inner: self }),
+            _ => Some(PrettyRef {
+                inner: self,
+                url_encoded,
+            }),
         }
     }
 }
@@ -323,14 +372,34 @@ impl GitReference {
 /// A git reference that can be `Display`ed
 pub struct PrettyRef<'a> {
     inner: &'a GitReference,
+    url_encoded: bool,
 }
 
 impl<'a> fmt::Display for PrettyRef<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self.inner {
-            GitReference::Branch(ref b) => write!(f, "branch={}", b),
-            GitReference::Tag(ref s) => write!(f, "tag={}", s),
-            GitReference::Rev(ref s) => write!(f, "rev={}", s),
+        let value = match *self.inner {
+            GitReference::Branch(ref s) => {
+                f.write_str("branch=")?;
+                s
+            }
+            GitReference::Tag(ref s) => {
+                f.write_str("tag=")?;
+                s
+            }
+            GitReference::Rev(ref s) => {
+                f.write_str("rev=")?;
+                s
+            }
+        };
+
+        if self.url_encoded {
+            for part in url::form_urlencoded::byte_serialize(value.as_bytes()) {
+                f.write_str(part)?;
+            }
+
+            Ok(())
+        } else {
+            f.write_str(value)
         }
     }
 }
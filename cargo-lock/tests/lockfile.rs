@@ -2,7 +2,9 @@
 
 // TODO(tarcieri): add more example `Cargo.lock` files which cover more scenarios
 
-use cargo_lock::{metadata, Lockfile, ResolveVersion, Version};
+use cargo_lock::{
+    metadata, package::source::GitReference, Lockfile, ResolveVersion, SourceId, Version,
+};
 
 /// Load our own `Cargo.lock` file for use in tests
 fn load_our_lockfile() -> Lockfile {
@@ -51,6 +53,82 @@ fn load_example_v2_lockfile() {
     assert_eq!(lockfile.metadata.len(), 0);
 }
 
+/// Load example V4 `Cargo.lock` file, with sparse registry and git sources
+#[test]
+fn load_example_v4_lockfile() {
+    let lockfile = Lockfile::load("tests/support/Cargo.lock.v4-example").unwrap();
+    assert_eq!(lockfile.version, ResolveVersion::V4);
+    assert_eq!(lockfile.packages.len(), 4);
+
+    let itoa = lockfile.packages[1].source.as_ref().unwrap();
+    assert!(itoa.is_sparse());
+    assert!(itoa.is_default_registry());
+    assert_eq!(itoa.to_string(), "sparse+https://index.crates.io/");
+
+    // Git references are percent-encoded
+    let ryu = lockfile.packages[2].source.as_ref().unwrap();
+    assert_eq!(
+        ryu.git_reference(),
+        Some(&GitReference::Branch("feat/simd".to_owned()))
+    );
+
+    assert!(lockfile.packages[3]
+        .source
+        .as_ref()
+        .unwrap()
+        .is_default_registry());
+}
+
+/// Ensure the explicit version of V3 and newer lockfiles is retained
+#[test]
+fn serialize_v4_lockfile() {
+    let lockfile = Lockfile::load("tests/support/Cargo.lock.v4-example").unwrap();
+    let reserialized = lockfile.to_string();
+    assert!(reserialized.contains("version = 4\n"));
+
+    let lockfile2 = reserialized.parse::<Lockfile>().unwrap();
+    assert_eq!(lockfile, lockfile2);
+}
+
+/// Ensure git references are percent-encoded in V4 lockfiles, so those with
+/// characters which are special in URLs survive a round trip
+#[test]
+fn serialize_v4_git_references() {
+    let url = "https://github.com/dtolnay/ryu".parse().unwrap();
+    let branch = GitReference::Branch("feat/a#b&c d".to_owned());
+    let source = SourceId::for_git(&url, branch.clone())
+        .unwrap()
+        .with_precise(Some("8f1a7e8c2b0b6cf5b1a7b5d0b9d4f5e3c1d7a6b2".to_owned()));
+
+    let mut lockfile = Lockfile::load("tests/support/Cargo.lock.v4-example").unwrap();
+    lockfile.packages[0].dependencies[1].source = Some(source.clone());
+    lockfile.packages[2].source = Some(source);
+
+    let reserialized = lockfile.to_string();
+    assert!(
+        reserialized.contains("?branch=feat%2Fa%23b%26c+d#8f1a7e8c"),
+        "{}",
+        reserialized
+    );
+
+    let lockfile2 = reserialized.parse::<Lockfile>().unwrap();
+    assert_eq!(
+        lockfile2.packages[2]
+            .source
+            .as_ref()
+            .unwrap()
+            .git_reference(),
+        Some(&branch)
+    );
+    assert_eq!(lockfile, lockfile2);
+
+    // Older versions write references as they are
+    lockfile.version = ResolveVersion::V3;
+    assert!(lockfile
+        .to_string()
+        .contains("?branch=feat/a#b&c d#8f1a7e8c"));
+}
+
 /// Ensure we can reserialize this crate's own `Cargo.lock` file
 #[test]
 fn serialize_our_own_lockfile() {
@@ -121,4 +199,47 @@ mod tree {
 
         assert_eq!(tree.nodes().len(), 472);
     }
+
+    /// Duplicate package entries (e.g. from a botched merge) are an error
+    #[test]
+    fn reject_duplicate_packages() {
+        let lockfile: Lockfile = r#"
+[[package]]
+name = "foo"
+version = "0.1.0"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            lockfile.dependency_tree().unwrap_err().to_string(),
+            "parse error: duplicate entry for package foo 0.1.0"
+        );
+    }
+
+    /// Dependencies without a corresponding package entry are an error
+    #[test]
+    fn reject_dangling_dependencies() {
+        let lockfile: Lockfile = r#"
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = ["bar 0.2.0"]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            lockfile.dependency_tree().unwrap_err().to_string(),
+            "parse error: package foo 0.1.0 depends on bar 0.2.0, which has no package entry"
+        );
+    }
 }
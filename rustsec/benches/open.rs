@@ -0,0 +1,76 @@
+//! Benchmark for opening a large synthetic advisory database, comparing
+//! reading only each advisory's front matter (leaving its description until
+//! it's needed) against parsing whole advisories.
+//!
+//! Run with `cargo bench -p rustsec --bench open`.
+
+use rustsec::Database;
+use std::{fs, time::Instant};
+
+/// Number of advisories in the synthetic database
+const ADVISORIES: usize = 2000;
+
+/// Number of paragraphs in each advisory's description
+const PARAGRAPHS: usize = 40;
+
+/// Number of times to open the database for each configuration
+const ITERATIONS: u32 = 10;
+
+fn main() {
+    let db_dir = tempfile::tempdir().unwrap();
+    let description = "Synthetic advisory for benchmarking, with a description as long as \
+                       those of the advisories which explain their vulnerability in depth.\n\n"
+        .repeat(PARAGRAPHS);
+
+    for i in 0..ADVISORIES {
+        let package = format!("package-{}", i);
+        let id = format!("RUSTSEC-2020-{:04}", i);
+        let package_dir = db_dir.path().join("crates").join(&package);
+        fs::create_dir_all(&package_dir).unwrap();
+
+        let advisory = format!(
+            "```toml\n\
+             [advisory]\n\
+             id = \"{}\"\n\
+             package = \"{}\"\n\
+             date = \"2020-01-01\"\n\n\
+             [versions]\n\
+             patched = [\">= 1.0.0\"]\n\
+             ```\n\n\
+             # Synthetic advisory\n\n\
+             {}",
+            id, package, description
+        );
+
+        fs::write(package_dir.join(format!("{}.md", id)), advisory).unwrap();
+    }
+
+    let lazy = bench(|| {
+        let db = Database::open_lazy(db_dir.path()).unwrap();
+        assert_eq!(db.iter().count(), ADVISORIES);
+    });
+
+    let eager = bench(|| {
+        let db = Database::open(db_dir.path()).unwrap();
+        assert_eq!(db.iter().count(), ADVISORIES);
+    });
+
+    println!(
+        "{} advisories ({} byte descriptions): lazy descriptions {:?}, eager {:?} per open",
+        ADVISORIES,
+        description.trim().len(),
+        lazy,
+        eager
+    );
+}
+
+/// Run the given function repeatedly, returning the mean time taken
+fn bench(f: impl Fn()) -> std::time::Duration {
+    let started = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        f();
+    }
+
+    started.elapsed() / ITERATIONS
+}
@@ -0,0 +1,92 @@
+//! Benchmark for generating a report on a large synthetic lockfile, comparing
+//! matching packages sequentially (a single thread) against in parallel.
+//!
+//! Run with `cargo bench -p rustsec --bench report`.
+
+use rustsec::{lockfile::Lockfile, report, Database, Report};
+use std::{fmt::Write as _, fs, time::Instant};
+
+/// Number of packages in the synthetic lockfile
+const PACKAGES: usize = 1400;
+
+/// Number of advisories in the synthetic database
+const ADVISORIES: usize = 600;
+
+/// Number of times to generate the report for each configuration
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    let db_dir = tempfile::tempdir().unwrap();
+
+    for i in 0..ADVISORIES {
+        let package = format!("package-{}", i % PACKAGES);
+        let id = format!("RUSTSEC-2020-{:04}", i);
+        let package_dir = db_dir.path().join("crates").join(&package);
+        fs::create_dir_all(&package_dir).unwrap();
+
+        let advisory = format!(
+            "```toml\n\
+             [advisory]\n\
+             id = \"{}\"\n\
+             package = \"{}\"\n\
+             date = \"2020-01-01\"\n\n\
+             [versions]\n\
+             patched = [\">= 1.0.0\"]\n\
+             ```\n\n\
+             # Synthetic advisory\n\n\
+             Synthetic advisory for benchmarking.\n",
+            id, package
+        );
+
+        fs::write(package_dir.join(format!("{}.md", id)), advisory).unwrap();
+    }
+
+    let db = Database::open(db_dir.path()).unwrap();
+
+    let mut lockfile_toml = String::new();
+
+    for i in 0..PACKAGES {
+        write!(
+            lockfile_toml,
+            "[[package]]\n\
+             name = \"package-{}\"\n\
+             version = \"0.{}.0\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n",
+            i,
+            i % 10
+        )
+        .unwrap();
+    }
+
+    let lockfile: Lockfile = lockfile_toml.parse().unwrap();
+    let settings = report::Settings::default();
+
+    let sequential = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap()
+        .install(|| bench(&db, &lockfile, &settings));
+
+    let parallel = bench(&db, &lockfile, &settings);
+
+    println!(
+        "{} packages, {} advisories: sequential {:?}, parallel {:?} ({} threads) per report",
+        PACKAGES,
+        ADVISORIES,
+        sequential,
+        parallel,
+        rayon::current_num_threads()
+    );
+}
+
+/// Generate the report repeatedly, returning the mean time taken
+fn bench(db: &Database, lockfile: &Lockfile, settings: &report::Settings) -> std::time::Duration {
+    let started = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        let report = Report::generate(db, lockfile, settings);
+        assert_eq!(report.vulnerabilities.count, ADVISORIES);
+    }
+
+    started.elapsed() / ITERATIONS
+}
@@ -22,7 +22,11 @@ use crate::{
     fs,
 };
 use serde::{Deserialize, Serialize};
-use std::{path::Path, str::FromStr};
+use std::{
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
 
 /// RustSec Security Advisories
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -51,6 +55,40 @@ impl Advisory {
             .map_err(|e| format_err!(ErrorKind::Parse, "error parsing {}: {}", path.display(), e))
     }
 
+    /// Load an advisory from a `RUSTSEC-20XX-NNNN.md` file, only reading its
+    /// front matter and title: its description is left empty (see
+    /// [`Database::description`](crate::Database::description)).
+    pub fn load_file_without_description(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let io_error = |e: std::io::Error| {
+            format_err!(ErrorKind::Io, "couldn't open {}: {}", path.display(), e)
+        };
+
+        let mut reader = BufReader::new(fs::File::open(path).map_err(io_error)?);
+        let mut head = String::new();
+        let mut front_matter_ended = false;
+
+        // Read up to the end of the title, which follows the front matter
+        loop {
+            let start = head.len();
+
+            if reader.read_line(&mut head).map_err(io_error)? == 0 {
+                break;
+            }
+
+            let line = &head[start..];
+
+            if !front_matter_ended {
+                front_matter_ended = start > 0 && line.starts_with("```");
+            } else if line.starts_with("# ") {
+                break;
+            }
+        }
+
+        head.parse()
+            .map_err(|e| format_err!(ErrorKind::Parse, "error parsing {}: {}", path.display(), e))
+    }
+
     /// Get advisory ID
     pub fn id(&self) -> &Id {
         &self.metadata.id
@@ -80,6 +118,28 @@ impl Advisory {
     pub fn withdrawn(&self) -> bool {
         self.metadata.withdrawn.is_some()
     }
+
+    /// Convert this advisory to the [OSV format](https://ossf.github.io/osv-schema/).
+    ///
+    /// Without the database's git history, its modification time is the
+    /// date it was issued (or withdrawn, if it was).
+    #[cfg(feature = "osv-I-know-this-is-unstable")]
+    pub fn to_osv(&self) -> crate::osv::OsvAdvisory {
+        let modified = self
+            .metadata
+            .withdrawn
+            .as_ref()
+            .unwrap_or(&self.metadata.date);
+
+        crate::osv::OsvAdvisory::new(self.clone(), crate::osv::rustsec_date_to_rfc3339(modified))
+    }
+
+    /// Convert an advisory in the OSV format (e.g. from osv.dev) about a
+    /// crates.io package into a RustSec advisory
+    #[cfg(feature = "osv-I-know-this-is-unstable")]
+    pub fn from_osv(osv: &crate::osv::OsvAdvisory) -> Result<Self, Error> {
+        osv.to_rustsec()
+    }
 }
 
 impl FromStr for Advisory {
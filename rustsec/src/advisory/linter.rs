@@ -58,6 +58,7 @@ impl Linter {
         };
 
         linter.lint_advisory(&front_matter);
+        linter.lint_content();
         Ok(linter)
     }
 
@@ -210,6 +211,17 @@ impl Linter {
                             });
                         }
                     }
+                    "superseded_by" => {
+                        if self.advisory.metadata.superseded_by.as_ref()
+                            == Some(&self.advisory.metadata.id)
+                        {
+                            self.errors.push(Error {
+                                kind: ErrorKind::value("superseded_by", value.to_string()),
+                                section: Some("advisory"),
+                                message: Some("advisory can't be superseded by itself"),
+                            });
+                        }
+                    }
                     "aliases" | "cvss" | "keywords" | "package" | "references" | "related"
                     | "title" | "description" => (),
                     _ => self.errors.push(Error {
@@ -228,12 +240,49 @@ impl Linter {
         }
     }
 
+    /// Lint the parsed advisory for content which is required, or which is
+    /// well-formed but can't be what was meant
+    fn lint_content(&mut self) {
+        if self.advisory.metadata.title.trim().is_empty() {
+            self.errors.push(Error {
+                kind: ErrorKind::Malformed,
+                section: Some("advisory"),
+                message: Some("missing title"),
+            });
+        }
+
+        if self.advisory.metadata.description.trim().is_empty() {
+            self.errors.push(Error {
+                kind: ErrorKind::Malformed,
+                section: Some("advisory"),
+                message: Some("missing description"),
+            });
+        }
+
+        let versions = &self.advisory.versions;
+
+        for (key, requirements) in &[
+            ("patched", versions.patched()),
+            ("unaffected", versions.unaffected()),
+        ] {
+            for requirement in requirements.iter() {
+                if requirement.comparators.is_empty() {
+                    self.errors.push(Error {
+                        kind: ErrorKind::value(key, requirement.to_string()),
+                        section: Some("versions"),
+                        message: Some("requirement matches every version"),
+                    });
+                }
+            }
+        }
+    }
+
     /// Lint the `[versions]` section of an advisory
     fn lint_versions(&mut self, versions: &toml::Value) {
         if let Some(table) = versions.as_table() {
             for (key, _) in table {
                 match key.as_str() {
-                    "patched" | "unaffected" => (),
+                    "patched" | "unaffected" | "patched_commits" => (),
                     _ => self.errors.push(Error {
                         kind: ErrorKind::key(key),
                         section: Some("versions"),
@@ -76,4 +76,25 @@ pub struct Metadata {
     /// This can be used to soft-delete advisories which were filed in error.
     #[serde(default)]
     pub withdrawn: Option<Date>,
+
+    /// Advisory which supersedes this one (e.g. a broader advisory about the
+    /// same issue), which should be referred to instead
+    #[serde(default)]
+    pub superseded_by: Option<Id>,
+}
+
+impl Metadata {
+    /// All reference URLs: the announcement `url` first (if any), followed
+    /// by the `references` which aren't the same URL
+    pub fn reference_urls(&self) -> Vec<&Url> {
+        let mut urls: Vec<&Url> = self.url.iter().collect();
+
+        for url in &self.references {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+
+        urls
+    }
 }
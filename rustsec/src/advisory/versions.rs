@@ -9,7 +9,10 @@ use std::convert::{TryFrom, TryInto};
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 
-use crate::{osv, Error};
+use crate::{
+    error::{Error, ErrorKind},
+    osv,
+};
 
 /// The `[versions]` subsection of an advisory: future home to information
 /// about which versions are patched and/or unaffected.
@@ -22,6 +25,11 @@ pub struct Versions {
     /// Versions which were never affected in the first place
     #[serde(default)]
     unaffected: Vec<VersionReq>,
+
+    /// Git commits which fix the vulnerability: revisions of the crate's
+    /// repository which include one of them are patched, whatever their version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    patched_commits: Vec<String>,
 }
 
 impl Versions {
@@ -35,12 +43,22 @@ impl Versions {
         false
     }
 
+    /// Could a version matching the given requirement (e.g. from a
+    /// `Cargo.toml`) be vulnerable?
+    ///
+    /// Errors if the requirement can't be expressed as a single range of
+    /// versions, e.g. `1.*` or `^1.2, <1.5`.
+    pub fn intersects(&self, req: &VersionReq) -> Result<bool, Error> {
+        osv::requirement_is_affected(self, req)
+    }
+
     /// Creates a new `[versions]` entry.
     /// Checks consistency of the passed version requirements.
     pub fn new(patched: Vec<VersionReq>, unaffected: Vec<VersionReq>) -> Result<Self, Error> {
         RawVersions {
             patched,
             unaffected,
+            patched_commits: vec![],
         }
         .try_into()
     }
@@ -54,6 +72,12 @@ impl Versions {
     pub fn unaffected(&self) -> &[VersionReq] {
         self.unaffected.as_slice()
     }
+
+    /// Git commits (full or abbreviated hashes) which fix the vulnerability,
+    /// for auditing git dependencies by their locked revision
+    pub fn patched_commits(&self) -> &[String] {
+        self.patched_commits.as_slice()
+    }
 }
 
 impl TryFrom<RawVersions> for Versions {
@@ -61,9 +85,11 @@ impl TryFrom<RawVersions> for Versions {
 
     fn try_from(raw: RawVersions) -> Result<Self, Self::Error> {
         validate_ranges(&raw)?;
+        validate_commits(&raw)?;
         Ok(Versions {
             patched: raw.patched,
             unaffected: raw.unaffected,
+            patched_commits: raw.patched_commits,
         })
     }
 }
@@ -75,9 +101,26 @@ pub(crate) struct RawVersions {
 
     #[serde(default)]
     pub unaffected: Vec<VersionReq>,
+
+    #[serde(default)]
+    pub patched_commits: Vec<String>,
 }
 
 fn validate_ranges(versions: &RawVersions) -> Result<(), Error> {
     let _ = osv::ranges_for_unvalidated_advisory(versions)?;
     Ok(())
 }
+
+fn validate_commits(versions: &RawVersions) -> Result<(), Error> {
+    for commit in &versions.patched_commits {
+        if !(7..=40).contains(&commit.len()) || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+            fail!(
+                ErrorKind::Parse,
+                "invalid commit in patched_commits: {} (expected a hash of 7 to 40 hex digits)",
+                commit
+            );
+        }
+    }
+
+    Ok(())
+}
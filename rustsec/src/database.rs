@@ -3,21 +3,32 @@
 mod entries;
 mod index;
 mod query;
+mod stats;
 
 pub mod scope;
 
-pub use self::query::Query;
+pub use self::{query::Query, stats::Stats};
 
-use self::{entries::Entries, index::Index};
+use self::{
+    entries::{Entries, Slot},
+    index::Index,
+};
 use crate::{
-    advisory::{self, Advisory},
+    advisory::{self, Advisory, Category},
     collection::Collection,
-    error::Error,
+    error::{Error, ErrorKind},
     fs,
     lockfile::Lockfile,
+    package,
     vulnerability::Vulnerability,
+    Map, Set,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
 };
-use std::path::Path;
 
 #[cfg(feature = "git")]
 use crate::repository::git;
@@ -37,6 +48,22 @@ pub struct Database {
     /// Index of third party crates
     crate_index: Index,
 
+    /// Index of advisories by category
+    category_index: Index<Category>,
+
+    /// Index of advisories by keyword (lowercased)
+    keyword_index: Index<String>,
+
+    /// Index of advisories by their IDs and aliases (lowercased)
+    alias_index: Index<String>,
+
+    /// Names of the sources advisories came from (see [`Database::set_source`])
+    sources: Map<advisory::Id, String>,
+
+    /// Files of the advisories whose descriptions weren't loaded (see
+    /// [`Database::open_lazy`])
+    description_paths: Map<advisory::Id, PathBuf>,
+
     /// Information about the last git commit to the database
     #[cfg(feature = "git")]
     latest_commit: Option<git::Commit>,
@@ -45,45 +72,108 @@ pub struct Database {
 impl Database {
     /// Open [`Database`] located at the given local path
     pub fn open(path: &Path) -> Result<Self, Error> {
+        Self::open_files(path, false)
+    }
+
+    /// Open [`Database`] located at the given local path, only reading the
+    /// front matter and title of each advisory, which is all that's needed
+    /// to find the advisories affecting a lockfile.
+    ///
+    /// The advisories' descriptions are left empty (and aren't searched by
+    /// [`Query`]): read them with [`Database::description`], which needs the
+    /// files to be kept while the database is used.
+    pub fn open_lazy(path: &Path) -> Result<Self, Error> {
+        Self::open_files(path, true)
+    }
+
+    /// Open [`Database`] located at the given local path, leaving advisories'
+    /// descriptions in their files if `lazy`
+    fn open_files(path: &Path, lazy: bool) -> Result<Self, Error> {
         let mut advisory_paths = vec![];
 
         for collection in Collection::all() {
             let collection_path = path.join(collection.as_str());
 
-            if let Ok(collection_entry) = fs::read_dir(&collection_path) {
-                for dir_entry in collection_entry {
-                    for advisory_entry in fs::read_dir(dir_entry?.path())? {
-                        advisory_paths.push(advisory_entry?.path().to_owned());
+            match fs::read_dir(&collection_path) {
+                Ok(collection_entry) => {
+                    for dir_entry in collection_entry {
+                        for advisory_entry in fs::read_dir(dir_entry?.path())? {
+                            advisory_paths.push(advisory_entry?.path().to_owned());
+                        }
                     }
                 }
+                Err(e) => tracing::trace!("skipping {} collection: {}", collection, e),
             }
         }
 
-        let mut advisories = Entries::new();
-        let mut rust_index = Index::new();
-        let mut crate_index = Index::new();
+        let mut db = Self::empty();
 
         for path in &advisory_paths {
-            if let Some(slot) = advisories.load_file(path)? {
-                let advisory = advisories.get(slot).unwrap();
-                match advisory.metadata.collection.unwrap() {
-                    Collection::Crates => {
-                        crate_index.insert(&advisory.metadata.package, slot);
-                    }
-                    Collection::Rust => {
-                        rust_index.insert(&advisory.metadata.package, slot);
-                    }
+            if let Some(slot) = db.advisories.load_file(path, lazy)? {
+                db.index(slot);
+
+                if lazy {
+                    let id = db.advisories.get(slot).unwrap().id().clone();
+                    db.description_paths.insert(id, path.clone());
                 }
             }
         }
 
-        Ok(Self {
-            advisories,
-            crate_index,
-            rust_index,
-            #[cfg(feature = "git")]
-            latest_commit: None,
-        })
+        Ok(db)
+    }
+
+    /// Build a [`Database`] from advisories which were already parsed,
+    /// without any filesystem or network I/O.
+    ///
+    /// Advisories which don't say which collection they're in are assumed to
+    /// be about crates, and placeholder advisories are skipped.
+    pub fn from_advisories(advisories: impl IntoIterator<Item = Advisory>) -> Result<Self, Error> {
+        let mut db = Self::empty();
+
+        for mut advisory in advisories {
+            if advisory.metadata.id.is_placeholder() {
+                continue;
+            }
+
+            advisory
+                .metadata
+                .collection
+                .get_or_insert(Collection::Crates);
+
+            let id = advisory.metadata.id.clone();
+
+            match db.advisories.insert(advisory) {
+                Some(slot) => db.index(slot),
+                None => fail!(ErrorKind::Parse, "duplicate advisory ID: {}", id),
+            }
+        }
+
+        Ok(db)
+    }
+
+    /// Build a [`Database`] from the contents of advisory files (Markdown
+    /// with TOML front matter, as in the advisory database's repository),
+    /// e.g. received over the network, without any filesystem or network
+    /// I/O. See [`Database::from_advisories`].
+    pub fn from_markdown<I>(advisories: I) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let advisories = advisories
+            .into_iter()
+            .enumerate()
+            .map(|(i, data)| {
+                std::str::from_utf8(data.as_ref())
+                    .map_err(Error::from)
+                    .and_then(str::parse)
+                    .map_err(|e| {
+                        format_err!(ErrorKind::Parse, "error parsing advisory #{}: {}", i + 1, e)
+                    })
+            })
+            .collect::<Result<Vec<Advisory>, _>>()?;
+
+        Self::from_advisories(advisories)
     }
 
     /// Load [`Database`] from the given [`git::Repository`]
@@ -94,17 +184,178 @@ impl Database {
         Ok(db)
     }
 
+    /// Load [`Database`] as it was at the given commit of a
+    /// [`git::Repository`] (e.g. one found with [`git::Repository::commit_as_of`]),
+    /// without checking it out
+    #[cfg(feature = "git")]
+    pub fn load_from_commit(repo: &git::Repository, commit: git::Commit) -> Result<Self, Error> {
+        let mut db = Self::empty();
+
+        for collection in Collection::all() {
+            for (path, data) in repo.files_at(&commit, collection.as_str())? {
+                let advisory = std::str::from_utf8(&data)?.parse().map_err(|e| {
+                    format_err!(ErrorKind::Parse, "error parsing {}: {}", path.display(), e)
+                })?;
+
+                if let Some(slot) = db.advisories.load(&path, advisory)? {
+                    db.index(slot);
+                }
+            }
+        }
+
+        db.latest_commit = Some(commit);
+        Ok(db)
+    }
+
     /// Fetch the default advisory database from GitHub
     #[cfg(feature = "git")]
     pub fn fetch() -> Result<Self, Error> {
         git::Repository::fetch_default_repo().and_then(|repo| Self::load_from_repo(&repo))
     }
 
+    /// Merge the advisories from another database into this one, returning
+    /// how many were skipped because an advisory with the same ID is already
+    /// present (so advisories in this database take precedence). The latest
+    /// commit remains this database's, and merged advisories keep the
+    /// names of the sources they came from.
+    pub fn merge(&mut self, mut other: Database) -> usize {
+        let mut sources = std::mem::take(&mut other.sources);
+        let mut description_paths = std::mem::take(&mut other.description_paths);
+        let mut skipped = 0;
+
+        for advisory in other {
+            let id = advisory.id().clone();
+
+            match self.advisories.insert(advisory) {
+                Some(slot) => {
+                    self.index(slot);
+
+                    if let Some(path) = description_paths.remove(&id) {
+                        self.description_paths.insert(id.clone(), path);
+                    }
+
+                    if let Some(source) = sources.remove(&id) {
+                        self.sources.insert(id, source);
+                    }
+                }
+                None => skipped += 1,
+            }
+        }
+
+        skipped
+    }
+
+    /// Record that every advisory in the database came from the source with
+    /// the given name, so they can be told apart from those of other sources
+    /// once databases are merged
+    pub fn set_source(&mut self, name: &str) {
+        self.sources = self
+            .iter()
+            .map(|advisory| (advisory.id().clone(), name.to_owned()))
+            .collect();
+    }
+
+    /// Name of the source the advisory with the given ID came from, if the
+    /// database it was loaded from was given one
+    pub fn source(&self, id: &advisory::Id) -> Option<&str> {
+        self.sources.get(id).map(String::as_str)
+    }
+
+    /// Keep only the advisories for which the predicate holds (e.g. ones
+    /// issued before a given date), along with the latest commit and the
+    /// names of their sources
+    pub fn retain<F: FnMut(&Advisory) -> bool>(&mut self, mut keep: F) {
+        let mut db = Self::empty();
+        let mut sources = std::mem::take(&mut self.sources);
+        let mut description_paths = std::mem::take(&mut self.description_paths);
+
+        #[cfg(feature = "git")]
+        {
+            db.latest_commit = self.latest_commit.take();
+        }
+
+        for advisory in std::mem::take(&mut self.advisories) {
+            if !keep(&advisory) {
+                continue;
+            }
+
+            let id = advisory.id().clone();
+
+            if let Some(slot) = db.advisories.insert(advisory) {
+                db.index(slot);
+
+                if let Some(path) = description_paths.remove(&id) {
+                    db.description_paths.insert(id.clone(), path);
+                }
+
+                if let Some(source) = sources.remove(&id) {
+                    db.sources.insert(id, source);
+                }
+            }
+        }
+
+        *self = db;
+    }
+
+    /// Database without any advisories
+    fn empty() -> Self {
+        Self {
+            advisories: Entries::new(),
+            crate_index: Index::new(),
+            rust_index: Index::new(),
+            category_index: Index::new(),
+            keyword_index: Index::new(),
+            alias_index: Index::new(),
+            sources: Map::new(),
+            description_paths: Map::new(),
+            #[cfg(feature = "git")]
+            latest_commit: None,
+        }
+    }
+
+    /// Add the advisory in the given slot to the indexes
+    fn index(&mut self, slot: Slot) {
+        let advisory = self.advisories.get(slot).unwrap();
+        let metadata = &advisory.metadata;
+
+        match metadata.collection.unwrap_or(Collection::Crates) {
+            Collection::Crates => self.crate_index.insert(&metadata.package, slot),
+            Collection::Rust => self.rust_index.insert(&metadata.package, slot),
+        };
+
+        for category in &metadata.categories {
+            self.category_index.insert(category, slot);
+        }
+
+        for keyword in &metadata.keywords {
+            self.keyword_index
+                .insert(&keyword.as_str().to_lowercase(), slot);
+        }
+
+        for id in Some(&metadata.id).into_iter().chain(&metadata.aliases) {
+            self.alias_index
+                .insert(&id.as_str().to_ascii_lowercase(), slot);
+        }
+    }
+
     /// Look up an advisory by an advisory ID (e.g. "RUSTSEC-YYYY-XXXX")
     pub fn get(&self, id: &advisory::Id) -> Option<&Advisory> {
         self.advisories.find_by_id(id)
     }
 
+    /// Description of the advisory with the given ID, which is read from its
+    /// file if the database was opened with [`Database::open_lazy`]
+    pub fn description(&self, id: &advisory::Id) -> Result<Cow<'_, str>, Error> {
+        let advisory = self
+            .get(id)
+            .ok_or_else(|| format_err!(ErrorKind::NotFound, "no advisory {}", id))?;
+
+        match self.description_paths.get(id) {
+            Some(path) => Ok(Cow::Owned(Advisory::load_file(path)?.metadata.description)),
+            None => Ok(Cow::Borrowed(advisory.description())),
+        }
+    }
+
     /// Query the database according to the given query object
     pub fn query(&self, query: &Query) -> Vec<&Advisory> {
         // Use indexes if we know a package name and collection
@@ -124,11 +375,107 @@ impl Database {
             }
         }
 
+        if let Some(slots) = self.candidates(query) {
+            return slots
+                .into_iter()
+                .map(|slot| self.advisories.get(slot).unwrap())
+                .filter(|advisory| query.matches(advisory))
+                .collect();
+        }
+
         self.iter()
             .filter(|advisory| query.matches(advisory))
             .collect()
     }
 
+    /// Advisories which may match the query's categories and keywords (in
+    /// the order they were loaded), or `None` if it has neither
+    fn candidates(&self, query: &Query) -> Option<Set<Slot>> {
+        let mut candidates: Option<Set<Slot>> = None;
+
+        if !query.categories.is_empty() {
+            candidates = Some(
+                query
+                    .categories
+                    .iter()
+                    .filter_map(|category| self.category_index.get(category))
+                    .flatten()
+                    .copied()
+                    .collect(),
+            );
+        }
+
+        if !query.keywords.is_empty() {
+            let slots: Set<Slot> = query
+                .keywords
+                .iter()
+                .filter_map(|keyword| self.keyword_index.get(keyword))
+                .flatten()
+                .copied()
+                .collect();
+
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&slots).copied().collect(),
+                None => slots,
+            });
+        }
+
+        candidates
+    }
+
+    /// Advisories mentioning the given text (ignoring case) in their ID,
+    /// aliases, package name, title, description or keywords
+    pub fn query_by_keyword(&self, keyword: &str) -> Vec<&Advisory> {
+        self.query(&Query::new().text(keyword))
+    }
+
+    /// Advisories about the given crate
+    pub fn query_by_crate(&self, name: &package::Name) -> Vec<&Advisory> {
+        self.query(
+            &Query::new()
+                .collection(Collection::Crates)
+                .package(name.clone()),
+        )
+    }
+
+    /// Look up advisories by ID or by alias (e.g. a CVE or GHSA ID), ignoring
+    /// case. Several advisories can share an alias.
+    pub fn query_by_id(&self, id: &str) -> Vec<&Advisory> {
+        if let Some(advisory) = id.parse().ok().and_then(|id| self.get(&id)) {
+            return vec![advisory];
+        }
+
+        self.alias_index
+            .get(&id.to_ascii_lowercase())
+            .map(|slots| {
+                slots
+                    .map(|slot| self.advisories.get(*slot).unwrap())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Advisories which list the given ID (e.g. a CVE or GHSA ID) amongst
+    /// their aliases, ignoring case, so findings tracked by another
+    /// database's IDs can be resolved to the advisories in this one
+    pub fn query_by_alias(&self, alias: &advisory::Id) -> Vec<&Advisory> {
+        self.alias_index
+            .get(&alias.as_str().to_ascii_lowercase())
+            .map(|slots| {
+                slots
+                    .map(|slot| self.advisories.get(*slot).unwrap())
+                    .filter(|advisory| {
+                        advisory
+                            .metadata
+                            .aliases
+                            .iter()
+                            .any(|id| id.as_str().eq_ignore_ascii_case(alias.as_str()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     /// Find vulnerabilities in the provided `Lockfile` which match a given query.
     // TODO(tarcieri): move `package_scope` into `Query`?
     pub fn query_vulnerabilities(
@@ -138,27 +485,34 @@ impl Database {
         package_scope: impl Into<scope::Package>,
     ) -> Vec<Vulnerability> {
         let package_scope = package_scope.into();
-        let mut vulns = vec![];
-
-        for package in &lockfile.packages {
-            if package_scope.is_remote() && package.source.is_none() {
-                continue;
-            }
-
-            let advisories = self.query(
-                &query
-                    .clone()
-                    .package_version(package.name.clone(), package.version.clone()),
-            );
 
-            vulns.extend(
-                advisories
-                    .iter()
-                    .map(|advisory| Vulnerability::new(advisory, package)),
-            );
-        }
-
-        vulns
+        // Packages are matched in parallel (with the `parallel` feature): the
+        // database is only read here, and `collect` keeps the results in
+        // lockfile order regardless of how the work was scheduled, so reports
+        // remain deterministic.
+        #[cfg(feature = "parallel")]
+        let packages = lockfile.packages.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let packages = lockfile.packages.iter();
+
+        packages
+            .filter(|package| package_scope.includes(package))
+            .map(|package| {
+                self.query(
+                    &query
+                        .clone()
+                        .package_version(package.name.clone(), package.version.clone()),
+                )
+                .iter()
+                .map(|advisory| {
+                    let mut vulnerability = Vulnerability::new(advisory, package);
+                    vulnerability.source = self.source(advisory.id()).map(ToOwned::to_owned);
+                    vulnerability
+                })
+                .collect::<Vec<_>>()
+            })
+            .flatten()
+            .collect()
     }
 
     /// Scan for vulnerabilities in the provided `Lockfile`.
@@ -171,6 +525,11 @@ impl Database {
         self.advisories.iter()
     }
 
+    /// Compute statistics about the advisories in the database
+    pub fn stats(&self) -> Stats {
+        Stats::new(self)
+    }
+
     /// Get information about the latest commit to the repo
     #[cfg(feature = "git")]
     pub fn latest_commit(&self) -> Option<&git::Commit> {
@@ -33,11 +33,23 @@ impl Entries {
         Self::default()
     }
 
-    /// Load an advisory from a file and insert it into the database entry table
+    /// Load an advisory from a file (without its description if `lazy`) and
+    /// insert it into the database entry table
     // TODO(tarcieri): factor more of this into `advisory.rs`?
-    pub fn load_file(&mut self, path: &Path) -> Result<Option<Slot>, Error> {
-        let mut advisory = Advisory::load_file(path)?;
+    pub fn load_file(&mut self, path: &Path, lazy: bool) -> Result<Option<Slot>, Error> {
+        let advisory = if lazy {
+            Advisory::load_file_without_description(path)?
+        } else {
+            Advisory::load_file(path)?
+        };
+
+        self.load(path, advisory)
+    }
 
+    /// Insert an advisory loaded from the given path of the database (e.g. in
+    /// a past commit of its repository), ensuring it's named after its ID and
+    /// located in its package's directory
+    pub fn load(&mut self, path: &Path, mut advisory: Advisory) -> Result<Option<Slot>, Error> {
         // TODO(tarcieri): deprecate and remove legacy TOML-based advisory format
         let expected_filename = match path.extension().and_then(|ext| ext.to_str()) {
             Some("md") => OsString::from(format!("{}.md", advisory.metadata.id)),
@@ -118,6 +130,7 @@ impl Entries {
         // Ensure placeholder advisories load and parse correctly, but
         // don't actually insert them into the advisory database
         if advisory.metadata.id.is_placeholder() {
+            tracing::trace!("skipping placeholder advisory: {}", path.display());
             return Ok(None);
         }
 
@@ -137,6 +150,22 @@ impl Entries {
         Ok(Some(slot))
     }
 
+    /// Insert an advisory (e.g. from another database), unless one with the
+    /// same ID is already present
+    pub fn insert(&mut self, advisory: Advisory) -> Option<Slot> {
+        let slot = Slot(self.advisories.len());
+
+        match self.index.entry(advisory.metadata.id.clone()) {
+            map::Entry::Vacant(entry) => {
+                entry.insert(slot);
+            }
+            map::Entry::Occupied(_) => return None,
+        }
+
+        self.advisories.push(advisory);
+        Some(slot)
+    }
+
     /// Find an advisory by its `advisory::Id`
     pub fn find_by_id(&self, id: &advisory::Id) -> Option<&Advisory> {
         self.index.get(id).and_then(|slot| self.get(*slot))
@@ -5,18 +5,25 @@ pub use crate::set::Iter;
 use super::entries::Slot;
 use crate::{map, package, Map, Set};
 
-/// Database index which maps package names to a set of advisory IDs
-#[derive(Debug, Default)]
-pub(crate) struct Index(Map<package::Name, Set<Slot>>);
+/// Database index which maps keys (package names by default, but also e.g.
+/// categories) to a set of advisory IDs
+#[derive(Debug)]
+pub(crate) struct Index<K: Ord = package::Name>(Map<K, Set<Slot>>);
 
-impl Index {
+impl<K: Ord> Default for Index<K> {
+    fn default() -> Self {
+        Index(Map::new())
+    }
+}
+
+impl<K: Clone + Ord> Index<K> {
     /// Create a new index
     pub fn new() -> Self {
         Self::default()
     }
 
     /// Insert an entry into the index
-    pub fn insert(&mut self, key: &package::Name, slot: Slot) -> bool {
+    pub fn insert(&mut self, key: &K, slot: Slot) -> bool {
         let values = match self.0.entry(key.clone()) {
             map::Entry::Vacant(entry) => entry.insert(Set::new()),
             map::Entry::Occupied(entry) => entry.into_mut(),
@@ -25,8 +32,8 @@ impl Index {
         values.insert(slot)
     }
 
-    /// Get an iterator over advisory IDs for a given package name
-    pub fn get(&self, key: &package::Name) -> Option<Iter<'_, Slot>> {
+    /// Get an iterator over advisory IDs for a given key
+    pub fn get(&self, key: &K) -> Option<Iter<'_, Slot>> {
         self.0.get(key).map(|set| set.iter())
     }
 }
@@ -1,7 +1,7 @@
 //! Queries against the RustSec database
 //!
 use crate::{
-    advisory::{Advisory, Severity},
+    advisory::{Advisory, Category, Date, Severity},
     collection::Collection,
     database::scope,
     package,
@@ -24,6 +24,9 @@ pub struct Query {
     /// Severity threshold (i.e. minimum severity)
     severity: Option<Severity>,
 
+    /// Severity assumed for advisories without CVSS information
+    unscored_severity: Option<Severity>,
+
     /// Target architecture
     target_arch: Option<Arch>,
 
@@ -43,6 +46,21 @@ pub struct Query {
 
     /// Scope of packages which should be considered for audit
     package_scope: Option<scope::Package>,
+
+    /// Categories to search for (any of which match)
+    pub(super) categories: Vec<Category>,
+
+    /// Keywords to search for (any of which match, ignoring case)
+    pub(super) keywords: Vec<String>,
+
+    /// Text to search for (all of which has to match, ignoring case)
+    text: Vec<String>,
+
+    /// Earliest date the advisory was reported on
+    since: Option<Date>,
+
+    /// Latest date the advisory was reported on
+    until: Option<Date>,
 }
 
 impl Query {
@@ -90,12 +108,20 @@ impl Query {
     /// Qualitative Severity Rating Scale.
     ///
     /// Vulnerabilities without associated CVSS information will always
-    /// match regardless of what this is set to.
+    /// match regardless of what this is set to, unless a severity is
+    /// assumed for them with [`Query::unscored_severity`].
     pub fn severity(mut self, severity: Severity) -> Self {
         self.severity = Some(severity);
         self
     }
 
+    /// Assume advisories without associated CVSS information have the given
+    /// severity when comparing them to the severity threshold
+    pub fn unscored_severity(mut self, severity: Severity) -> Self {
+        self.unscored_severity = Some(severity);
+        self
+    }
+
     /// Set target architecture
     pub fn target_arch(mut self, arch: Arch) -> Self {
         self.target_arch = Some(arch);
@@ -122,6 +148,13 @@ impl Query {
         self
     }
 
+    /// Query for advisories whether or not they were withdrawn (e.g. to
+    /// research advisories which were filed in error)
+    pub fn any_withdrawn(mut self) -> Self {
+        self.withdrawn = None;
+        self
+    }
+
     /// Query for informational advisories. By default they will be omitted
     /// from query results.
     pub fn informational(mut self, setting: bool) -> Self {
@@ -129,6 +162,44 @@ impl Query {
         self
     }
 
+    /// Query for advisories in the given category. If several categories
+    /// are given, advisories in any of them match.
+    ///
+    /// Advisories without categories never match.
+    pub fn category(mut self, category: Category) -> Self {
+        self.categories.push(category);
+        self
+    }
+
+    /// Query for advisories with the given keyword (ignoring case). If
+    /// several keywords are given, advisories with any of them match.
+    ///
+    /// Advisories without keywords never match.
+    pub fn keyword(mut self, keyword: &str) -> Self {
+        self.keywords.push(keyword.to_lowercase());
+        self
+    }
+
+    /// Query for advisories mentioning the given text (ignoring case) in
+    /// their ID, aliases, package name, title, description or keywords. If
+    /// several texts are given, advisories have to mention all of them.
+    pub fn text(mut self, text: &str) -> Self {
+        self.text.push(text.to_lowercase());
+        self
+    }
+
+    /// Query for advisories reported on or after the given date
+    pub fn since(mut self, date: Date) -> Self {
+        self.since = Some(date);
+        self
+    }
+
+    /// Query for advisories reported on or before the given date
+    pub fn until(mut self, date: Date) -> Self {
+        self.until = Some(date);
+        self
+    }
+
     /// Does this query match a given advisory?
     pub fn matches(&self, advisory: &Advisory) -> bool {
         if let Some(collection) = self.collection {
@@ -150,7 +221,7 @@ impl Query {
         }
 
         if let Some(severity_threshold) = self.severity {
-            if let Some(advisory_severity) = advisory.severity() {
+            if let Some(advisory_severity) = advisory.severity().or(self.unscored_severity) {
                 if advisory_severity < severity_threshold {
                     return false;
                 }
@@ -191,6 +262,57 @@ impl Query {
             }
         }
 
-        true
+        if !self.categories.is_empty()
+            && !advisory
+                .metadata
+                .categories
+                .iter()
+                .any(|category| self.categories.contains(category))
+        {
+            return false;
+        }
+
+        if !self.keywords.is_empty()
+            && !advisory
+                .metadata
+                .keywords
+                .iter()
+                .any(|keyword| self.keywords.contains(&keyword.as_str().to_lowercase()))
+        {
+            return false;
+        }
+
+        if let Some(since) = &self.since {
+            if &advisory.metadata.date < since {
+                return false;
+            }
+        }
+
+        if let Some(until) = &self.until {
+            if &advisory.metadata.date > until {
+                return false;
+            }
+        }
+
+        self.text.iter().all(|text| mentions(advisory, text))
     }
 }
+
+/// Does the advisory mention the given (lowercase) text?
+fn mentions(advisory: &Advisory, text: &str) -> bool {
+    let metadata = &advisory.metadata;
+    let contains = |field: &str| field.to_lowercase().contains(text);
+
+    contains(metadata.id.as_str())
+        || metadata
+            .aliases
+            .iter()
+            .any(|alias| contains(alias.as_str()))
+        || contains(metadata.package.as_str())
+        || contains(&metadata.title)
+        || contains(&metadata.description)
+        || metadata
+            .keywords
+            .iter()
+            .any(|keyword| contains(keyword.as_str()))
+}
@@ -1,5 +1,6 @@
 //! Database scopes
 
+use crate::package;
 use serde::{Deserialize, Serialize};
 
 /// Registries where packages are located
@@ -36,9 +37,21 @@ impl Default for Registry {
 pub struct Package {
     /// Source of a package
     pub source: Vec<Registry>,
+
+    /// Skip packages from git repositories (e.g. forks used via `[patch]`)
+    #[serde(default)]
+    pub skip_git: bool,
 }
 
 impl Package {
+    /// Should the given package be matched against advisories?
+    pub fn includes(&self, package: &package::Package) -> bool {
+        match &package.source {
+            Some(source) => !(self.skip_git && source.is_git()),
+            None => !self.is_remote(),
+        }
+    }
+
     /// Is the scope only for remote crates?
     pub fn is_remote(&self) -> bool {
         self.source.iter().any(|source| match source {
@@ -68,6 +81,7 @@ impl From<Registry> for Package {
     fn from(registry: Registry) -> Self {
         Self {
             source: vec![registry],
+            skip_git: false,
         }
     }
 }
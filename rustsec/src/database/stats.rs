@@ -0,0 +1,103 @@
+//! Statistics about the advisories in a database
+
+use super::Database;
+use crate::advisory;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap as Map;
+
+/// Statistics about the advisories in a [`Database`]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Stats {
+    /// Total number of advisories
+    pub advisories: usize,
+
+    /// Number of advisories in each collection (`crates` or `rust`)
+    pub collections: Map<String, usize>,
+
+    /// Number of advisories for each year (as in their IDs)
+    pub years: Map<u32, usize>,
+
+    /// Number of informational advisories of each kind, e.g. `unmaintained`
+    pub informational: Map<String, usize>,
+
+    /// Number of advisories in each category, e.g. `memory-corruption`
+    pub categories: Map<String, usize>,
+
+    /// Number of advisories with a CVSS score
+    pub with_cvss: usize,
+
+    /// Number of withdrawn advisories
+    pub withdrawn: usize,
+
+    /// Date of the newest advisory
+    pub newest: Option<advisory::Date>,
+
+    /// ID of the latest commit to the database's repository (if loaded from
+    /// git)
+    pub commit: Option<String>,
+
+    /// Time of the latest commit to the database's repository, as an RFC 3339
+    /// timestamp (if loaded from git)
+    #[serde(default)]
+    pub last_updated: Option<String>,
+}
+
+impl Stats {
+    /// Compute statistics about the given database
+    pub fn new(database: &Database) -> Self {
+        let mut stats = Stats::default();
+
+        for advisory in database.iter() {
+            let metadata = &advisory.metadata;
+            stats.advisories += 1;
+
+            let collection = metadata
+                .collection
+                .map(|collection| collection.to_string())
+                .unwrap_or_else(|| "unknown".to_owned());
+            *stats.collections.entry(collection).or_default() += 1;
+
+            let year = metadata.id.year().unwrap_or_else(|| metadata.date.year());
+            *stats.years.entry(year).or_default() += 1;
+
+            if let Some(informational) = &metadata.informational {
+                *stats
+                    .informational
+                    .entry(informational.to_string())
+                    .or_default() += 1;
+            }
+
+            for category in &metadata.categories {
+                *stats.categories.entry(category.to_string()).or_default() += 1;
+            }
+
+            if metadata.cvss.is_some() {
+                stats.with_cvss += 1;
+            }
+
+            if metadata.withdrawn.is_some() {
+                stats.withdrawn += 1;
+            }
+
+            if stats
+                .newest
+                .as_ref()
+                .map_or(true, |newest| metadata.date > *newest)
+            {
+                stats.newest = Some(metadata.date.clone());
+            }
+        }
+
+        #[cfg(feature = "git")]
+        {
+            stats.commit = database
+                .latest_commit()
+                .map(|commit| commit.commit_id.clone());
+            stats.last_updated = database
+                .latest_commit()
+                .map(|commit| humantime::format_rfc3339_seconds(commit.timestamp).to_string());
+        }
+
+        stats
+    }
+}
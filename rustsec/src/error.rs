@@ -53,6 +53,11 @@ impl Error {
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
+
+    /// Obtain the description of this error (without its kind)
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
 }
 
 impl Display for Error {
@@ -96,6 +101,15 @@ pub enum ErrorKind {
     #[error("git operation failed")]
     Repo,
 
+    /// Signature on the advisory database couldn't be verified
+    #[cfg(feature = "signatures")]
+    #[error("bad signature")]
+    Signature,
+
+    /// Advisory database repository hasn't been updated recently
+    #[error("stale repository")]
+    Stale,
+
     /// Errors related to versions
     #[error("bad version")]
     Version,
@@ -133,6 +147,13 @@ impl From<git2::Error> for Error {
     }
 }
 
+#[cfg(feature = "signatures")]
+impl From<pgp::errors::Error> for Error {
+    fn from(other: pgp::errors::Error) -> Self {
+        format_err!(ErrorKind::Signature, &other)
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(other: io::Error) -> Self {
         format_err!(ErrorKind::Io, &other)
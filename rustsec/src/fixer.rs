@@ -2,41 +2,178 @@
 
 use crate::{
     error::{Error, ErrorKind},
-    vulnerability::Vulnerability,
+    package::{self, Version},
+    vulnerability::{is_compatible, Upgrade, Vulnerability},
+    VersionReq,
 };
-use semver::VersionReq;
-use std::path::Path;
+use std::{collections::BTreeMap as Map, path::Path};
 
 /// Auto-fixer for vulnerable dependencies
 pub struct Fixer {
     manifest: cargo_edit::LocalManifest,
+
+    /// Versions the requirements of dependencies have been raised to
+    raised: Map<package::Name, Version>,
+}
+
+/// Version bump which fixes a vulnerability: the oldest version which isn't
+/// vulnerable, preferring semver-compatible ones (which only `Cargo.lock`
+/// needs updating for, if `Cargo.toml` allows them)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Fix {
+    /// Name of the vulnerable package
+    pub package: package::Name,
+
+    /// Vulnerable version
+    pub from: Version,
+
+    /// Version to update to
+    pub to: Version,
+
+    /// Is the version a published release, rather than the lower bound of
+    /// the advisory's patched versions (which may not have been released)?
+    pub published: bool,
+
+    /// Requirement the dependency is raised to in `Cargo.toml`, if its
+    /// requirements don't already allow the version
+    pub requirement: Option<VersionReq>,
+}
+
+impl Fix {
+    /// Is `Cargo.lock` the only file the fix changes?
+    pub fn is_lockfile_only(&self) -> bool {
+        self.requirement.is_none()
+    }
+
+    /// Is the fix a semver-compatible update?
+    pub fn is_compatible(&self) -> bool {
+        is_compatible(&self.from, &self.to)
+    }
 }
 
 impl Fixer {
     /// Create a new [`Fixer`] for the given `Cargo.toml` file
     pub fn new(cargo_toml: impl AsRef<Path>) -> Result<Self, Error> {
         let manifest = cargo_edit::LocalManifest::try_new(cargo_toml.as_ref())?;
-        Ok(Self { manifest })
+
+        Ok(Self {
+            manifest,
+            raised: Map::new(),
+        })
+    }
+
+    /// Attempt to fix the given vulnerability, raising the dependency's
+    /// requirement in `Cargo.toml` if it doesn't allow the fixed version.
+    ///
+    /// `Cargo.lock` isn't changed: the returned [`Fix`] has the version to
+    /// update it to.
+    pub fn fix(&mut self, vulnerability: &Vulnerability, dry_run: bool) -> Result<Fix, Error> {
+        let fix = self.plan(vulnerability)?;
+
+        if fix.requirement.is_some() {
+            let dependency =
+                cargo_edit::Dependency::new(fix.package.as_str()).set_version(&fix.to.to_string());
+
+            // Only requirements which don't already allow the version change
+            self.manifest.upgrade(&dependency, dry_run, true)?;
+            self.raised.insert(fix.package.clone(), fix.to.clone());
+        }
+
+        Ok(fix)
     }
 
-    /// Attempt to fix the given vulnerability
-    pub fn fix(
-        &mut self,
-        vulnerability: &Vulnerability,
-        dry_run: bool,
-    ) -> Result<VersionReq, Error> {
-        // TODO(tarcieri): find semver-compatible fix?
-        let version_req = match vulnerability.versions.patched().get(0) {
-            Some(req) => req,
+    /// Find the smallest version bump which fixes the given vulnerability,
+    /// without changing anything
+    pub fn plan(&self, vulnerability: &Vulnerability) -> Result<Fix, Error> {
+        let package = &vulnerability.package;
+
+        let upgrade = match vulnerability
+            .upgrade
+            .clone()
+            .or_else(|| Upgrade::from_ranges(&package.version, &vulnerability.versions))
+        {
+            Some(upgrade) => upgrade,
             None => fail!(ErrorKind::Version, "no fixed version available"),
         };
 
-        let dependency = cargo_edit::Dependency::new(vulnerability.package.name.as_str())
-            .set_version(&version_req.to_string());
+        let to = match upgrade.compatible.or(upgrade.major) {
+            Some(to) => to,
+            None => fail!(ErrorKind::Version, "no fixed version available"),
+        };
+
+        let requirements = match self.raised.get(&package.name) {
+            Some(raised) => vec![raised.to_string()],
+            None => self.requirements(&package.name),
+        };
+
+        if requirements.is_empty() && !is_compatible(&package.version, &to) {
+            fail!(
+                ErrorKind::Fix,
+                "{} is only an indirect dependency of {}, so the dependencies requiring it \
+                 need to be upgraded to allow {} {}",
+                package.name,
+                self.manifest.path.display(),
+                package.name,
+                to
+            );
+        }
+
+        let allowed = requirements
+            .iter()
+            .all(|requirement| match VersionReq::parse(requirement) {
+                Ok(requirement) => requirement.matches(&to),
+                Err(_) => false,
+            });
+
+        let requirement = if allowed {
+            None
+        } else {
+            Some(VersionReq::parse(&to.to_string())?)
+        };
+
+        Ok(Fix {
+            package: package.name.clone(),
+            from: package.version.clone(),
+            to,
+            published: upgrade.published,
+            requirement,
+        })
+    }
+
+    /// Version requirements of the given dependency in `Cargo.toml` (in any
+    /// of its dependency tables, and under any name it's renamed to)
+    fn requirements(&self, name: &package::Name) -> Vec<String> {
+        let mut requirements = vec![];
+
+        for (_, table) in self.manifest.get_sections() {
+            let table = match table.as_table_like() {
+                Some(table) => table,
+                None => continue,
+            };
+
+            for (key, item) in table.iter() {
+                let dependency = item.as_table_like();
+
+                let package = dependency
+                    .and_then(|dependency| dependency.get("package"))
+                    .and_then(|package| package.as_str())
+                    .unwrap_or(key);
+
+                if package != name.as_str() {
+                    continue;
+                }
+
+                let requirement = match dependency {
+                    Some(dependency) => dependency.get("version").and_then(|v| v.as_str()),
+                    None => item.as_str(),
+                };
 
-        self.manifest.upgrade(&dependency, dry_run, false)?;
+                // Dependencies without a version (e.g. path or git ones)
+                // allow any version
+                requirements.push(requirement.unwrap_or("*").to_owned());
+            }
+        }
 
-        // TODO(tarcieri): return new version rather than req?
-        Ok(version_req.clone())
+        requirements
     }
 }
@@ -3,6 +3,50 @@
 //! This crate is primarily intended for use with the cargo-audit tool:
 //!
 //! <https://crates.io/crates/cargo-audit>
+//!
+//! ## Auditing in memory
+//!
+//! A [`Database`] can be built from the contents of advisory files, and a
+//! [`lockfile::Lockfile`] parsed from a string, so audits can run without any
+//! filesystem or network I/O (e.g. in a service which receives lockfiles over
+//! the wire):
+//!
+//! ```
+//! use rustsec::{lockfile::Lockfile, report, Database, Report};
+//!
+//! let advisory = concat!(
+//!     "```toml\n",
+//!     "[advisory]\n",
+//!     "id = \"RUSTSEC-2020-0001\"\n",
+//!     "package = \"foo\"\n",
+//!     "date = \"2020-01-01\"\n\n",
+//!     "[versions]\n",
+//!     "patched = [\">= 0.2.0\"]\n",
+//!     "```\n\n",
+//!     "# Vulnerability in foo\n\n",
+//!     "Description of the vulnerability.\n",
+//! );
+//!
+//! let lockfile: Lockfile = "[[package]]
+//! name = \"foo\"
+//! version = \"0.1.0\"
+//! source = \"registry+https://github.com/rust-lang/crates.io-index\"
+//! "
+//! .parse()?;
+//!
+//! let database = Database::from_markdown(&[advisory])?;
+//! let report = Report::generate(&database, &lockfile, &report::Settings::default());
+//! assert_eq!(report.vulnerabilities.count, 1);
+//! # Ok::<(), rustsec::Error>(())
+//! ```
+//!
+//! ## WebAssembly
+//!
+//! With `default-features = false`, fetching the database (the `git` and
+//! `tarball` features) and matching packages on a thread pool (the `parallel`
+//! feature) are left out, and the rest of the crate compiles to
+//! `wasm32-unknown-unknown`, e.g. to audit lockfiles in a browser against a
+//! preloaded snapshot of the database, as above.
 
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/RustSec/logos/main/rustsec-logo-lg.png",
@@ -9,7 +9,9 @@
 #[cfg(feature = "osv-I-know-this-is-unstable")]
 mod osv_advisory;
 #[cfg(feature = "osv-I-know-this-is-unstable")]
-pub use osv_advisory::OsvAdvisory;
+pub(crate) use osv_advisory::rustsec_date_to_rfc3339;
+#[cfg(feature = "osv-I-know-this-is-unstable")]
+pub use osv_advisory::{OsvAdvisory, SCHEMA_VERSION};
 
 // The rest are enabled unconditionally because the OSV range format
 // is used for determining whether a given version is affected or not
@@ -20,4 +22,5 @@ mod unaffected_range;
 
 pub use osv_range::OsvRange;
 pub use ranges_for_advisory::ranges_for_advisory;
-pub(crate) use ranges_for_advisory::ranges_for_unvalidated_advisory;
+pub(crate) use ranges_for_advisory::{ranges_for_unvalidated_advisory, requirement_is_affected};
+pub(crate) use unaffected_range::UnaffectedRange;
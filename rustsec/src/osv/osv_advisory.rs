@@ -1,87 +1,138 @@
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use serde::Serialize;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 use super::{ranges_for_advisory, OsvRange};
 
 use crate::{
-    advisory::{affected::FunctionPath, Affected, Category, Id, Informational},
+    advisory::{affected::FunctionPath, Affected, Category, Date, Id, Informational, Versions},
+    collection::Collection,
+    error::{Error, ErrorKind},
     repository::git::{GitModificationTimes, GitPath},
-    Advisory,
+    Advisory, Map,
 };
 
+/// Version of the OSV schema advisories are exported in
+pub const SCHEMA_VERSION: &str = "1.2.0";
+
 const ECOSYSTEM: &str = "crates.io";
 
 /// Security advisory in the format defined by https://github.com/google/osv
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OsvAdvisory {
+    #[serde(default)]
+    schema_version: String,
     id: Id,
-    modified: String,  // maybe add an rfc3339 newtype?
-    published: String, // maybe add an rfc3339 newtype?
-    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: String, // maybe add an rfc3339 newtype?
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    published: Option<String>, // maybe add an rfc3339 newtype?
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     withdrawn: Option<String>, // maybe add an rfc3339 newtype?
+    #[serde(default)]
     aliases: Vec<Id>,
+    #[serde(default)]
     related: Vec<Id>,
-    package: OsvPackage,
+    #[serde(default)]
     summary: String,
+    #[serde(default)]
     details: String,
-    affects: OsvAffected,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    severity: Vec<OsvSeverity>,
+    #[serde(default)]
+    affected: Vec<OsvAffected>,
+    #[serde(default)]
     references: Vec<OsvReference>,
-    ecosystem_specific: OsvEcosystemSpecific,
+    #[serde(default)]
     database_specific: OsvDatabaseSpecific,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OsvPackage {
     /// Set to a constant identifying crates.io
-    ecosystem: &'static str,
+    ecosystem: String,
     /// Crate name
     name: String,
     /// https://github.com/package-url/purl-spec derived from the other two
-    purl: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
 }
 
 impl From<&cargo_lock::Name> for OsvPackage {
     fn from(package: &cargo_lock::Name) -> Self {
         OsvPackage {
-            ecosystem: ECOSYSTEM,
+            ecosystem: ECOSYSTEM.to_owned(),
             name: package.to_string(),
-            purl: "pkg:cargo/".to_string() + package.as_str(),
+            purl: Some("pkg:cargo/".to_string() + package.as_str()),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OsvAffected {
+    package: OsvPackage,
     // Other fields are specified, but we never use them.
     // Ranges alone are sufficient.
+    #[serde(default)]
     ranges: Vec<OsvJsonRange>,
+    #[serde(default)]
+    ecosystem_specific: OsvEcosystemSpecific,
 }
 
-/// Same as `OsvRange`, but also has `type` field specified
+/// Same as `OsvRange`, but as a list of events, with the `type` field
 /// which is required in the OSV JSON representation.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OsvJsonRange {
     // 'type' is a reserved keyword in Rust
     #[serde(rename = "type")]
-    kind: &'static str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    introduced: Option<semver::Version>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    fixed: Option<semver::Version>,
+    kind: String,
+    events: Vec<OsvRangeEvent>,
 }
 
 impl From<OsvRange> for OsvJsonRange {
     fn from(range: OsvRange) -> Self {
+        // An `introduced` event is required, where `0` is before any version
+        let mut events = vec![OsvRangeEvent::Introduced(
+            range
+                .introduced
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "0".to_owned()),
+        )];
+
+        if let Some(fixed) = range.fixed {
+            events.push(OsvRangeEvent::Fixed(fixed.to_string()));
+        }
+
         OsvJsonRange {
-            kind: "SEMVER",
-            introduced: range.introduced,
-            fixed: range.fixed,
+            kind: "SEMVER".to_owned(),
+            events,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Event in the timeline of a range of versions
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OsvRangeEvent {
+    /// Version which introduced the vulnerability
+    Introduced(String),
+    /// Version which fixed the vulnerability
+    Fixed(String),
+    /// Last version which is affected
+    LastAffected(String),
+    /// Upper limit of the range (for git ranges)
+    Limit(String),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OsvSeverity {
+    // 'type' is a reserved keyword in Rust
+    #[serde(rename = "type")]
+    kind: String,
+    score: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OsvReference {
     // 'type' is a reserved keyword in Rust
     #[serde(rename = "type")]
@@ -99,27 +150,38 @@ impl From<Url> for OsvReference {
 }
 
 #[allow(dead_code)] // we don't (yet) construct all the variants
-#[derive(Debug, Clone, Serialize)]
+#[allow(clippy::upper_case_acronyms)] // as spelled in the OSV schema
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq, Serialize)]
 pub enum OsvReferenceKind {
     ADVISORY,
     ARTICLE,
+    DETECTION,
+    DISCUSSION,
     REPORT,
     FIX,
+    INTRODUCED,
+    GIT,
     PACKAGE,
+    EVIDENCE,
+    #[serde(other)]
     WEB,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct OsvEcosystemSpecific {
+    #[serde(default)]
     affects: OsvEcosystemSpecificAffected,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct OsvEcosystemSpecificAffected {
+    #[serde(default)]
     arch: Vec<platforms::target::Arch>,
+    #[serde(default)]
     os: Vec<platforms::target::OS>,
     /// We include function names only in order to allow changing
     /// the way versions are specified without an API break
+    #[serde(default)]
     functions: Vec<FunctionPath>,
 }
 
@@ -133,10 +195,13 @@ impl From<Affected> for OsvEcosystemSpecificAffected {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct OsvDatabaseSpecific {
+    #[serde(default)]
     categories: Vec<Category>,
+    #[serde(default)]
     cvss: Option<cvss::v3::Base>,
+    #[serde(default)]
     informational: Option<Informational>,
 }
 
@@ -148,6 +213,12 @@ impl OsvAdvisory {
         mod_times: &GitModificationTimes,
         path: GitPath<'_>,
     ) -> Self {
+        Self::new(advisory, git2_time_to_rfc3339(mod_times.for_path(path)))
+    }
+
+    /// Converts a single RustSec advisory to OSV format, with the given
+    /// RFC 3339 modification time
+    pub(crate) fn new(advisory: Advisory, modified: String) -> Self {
         let metadata = advisory.metadata;
 
         // Assemble the URLs to put into 'references' field
@@ -169,22 +240,31 @@ impl OsvAdvisory {
         reference_urls.extend(metadata.references.into_iter());
 
         OsvAdvisory {
+            schema_version: SCHEMA_VERSION.to_owned(),
             id: metadata.id,
-            modified: git2_time_to_rfc3339(mod_times.for_path(path)),
-            published: rustsec_date_to_rfc3339(&metadata.date),
-            affects: OsvAffected {
-                ranges: json_ranges_for_advisory(&advisory.versions),
-            },
+            modified,
+            published: Some(rustsec_date_to_rfc3339(&metadata.date)),
             withdrawn: metadata.withdrawn.map(|d| rustsec_date_to_rfc3339(&d)),
             aliases: metadata.aliases,
             related: metadata.related,
-            package: (&metadata.package).into(),
             summary: metadata.title,
             details: metadata.description,
+            severity: metadata
+                .cvss
+                .iter()
+                .map(|cvss| OsvSeverity {
+                    kind: "CVSS_V3".to_owned(),
+                    score: cvss.to_string(),
+                })
+                .collect(),
+            affected: vec![OsvAffected {
+                package: (&metadata.package).into(),
+                ranges: json_ranges_for_advisory(&advisory.versions),
+                ecosystem_specific: OsvEcosystemSpecific {
+                    affects: advisory.affected.unwrap_or_default().into(),
+                },
+            }],
             references: osv_references(reference_urls),
-            ecosystem_specific: OsvEcosystemSpecific {
-                affects: advisory.affected.unwrap_or_default().into(),
-            },
             database_specific: OsvDatabaseSpecific {
                 categories: metadata.categories,
                 cvss: metadata.cvss,
@@ -192,6 +272,264 @@ impl OsvAdvisory {
             },
         }
     }
+
+    /// ID of the advisory
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    /// Converts the advisory back to the RustSec format. It must affect a
+    /// crates.io package with `SEMVER` (or `ECOSYSTEM`) ranges.
+    ///
+    /// Links which are added on export (to the crate and the RustSec
+    /// advisory) are dropped, and the first remaining reference becomes
+    /// the advisory's `url`. Keywords aren't part of the OSV format.
+    pub fn to_rustsec(&self) -> Result<Advisory, Error> {
+        let affected = self
+            .affected
+            .iter()
+            .find(|affected| affected.package.ecosystem == ECOSYSTEM)
+            .ok_or_else(|| {
+                format_err!(
+                    ErrorKind::BadParam,
+                    "{} doesn't affect any {} package",
+                    self.id,
+                    ECOSYSTEM
+                )
+            })?;
+
+        let package: cargo_lock::Name = affected.package.name.parse()?;
+        let intervals = affected_intervals(&self.id, &affected.ranges)?;
+        let versions = versions_for_intervals(&intervals)?;
+
+        let affects = &affected.ecosystem_specific.affects;
+        let affected_reqs = intervals
+            .iter()
+            .map(Interval::to_req)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let rustsec_affected =
+            if affects.arch.is_empty() && affects.os.is_empty() && affects.functions.is_empty() {
+                None
+            } else {
+                Some(Affected {
+                    arch: affects.arch.clone(),
+                    os: affects.os.clone(),
+                    functions: affects
+                        .functions
+                        .iter()
+                        .map(|function| (function.clone(), affected_reqs.clone()))
+                        .collect::<Map<_, _>>(),
+                })
+            };
+
+        let cvss = match &self.database_specific.cvss {
+            Some(cvss) => Some(cvss.clone()),
+            None => match self
+                .severity
+                .iter()
+                .find(|severity| severity.kind == "CVSS_V3")
+            {
+                Some(severity) => Some(severity.score.parse().map_err(|e| {
+                    format_err!(
+                        ErrorKind::Parse,
+                        "invalid CVSS vector {:?}: {}",
+                        severity.score,
+                        e
+                    )
+                })?),
+                None => None,
+            },
+        };
+
+        let crate_url = format!("https://crates.io/crates/{}", package);
+        let advisory_url = format!("https://rustsec.org/advisories/{}", self.id);
+        let mut references = self
+            .references
+            .iter()
+            .map(|reference| reference.url.clone())
+            .filter(|url| {
+                let url = url.as_str().trim_end_matches('/');
+                url != crate_url && !url.starts_with(&advisory_url)
+            });
+
+        let date = match &self.published {
+            Some(published) => published,
+            None => &self.modified,
+        };
+
+        let mut advisory = Advisory {
+            metadata: crate::advisory::Metadata {
+                id: self.id.clone(),
+                package,
+                title: self.summary.clone(),
+                description: self.details.clone(),
+                date: rfc3339_to_rustsec_date(date)?,
+                aliases: self.aliases.clone(),
+                related: self.related.clone(),
+                collection: Some(Collection::Crates),
+                categories: self.database_specific.categories.clone(),
+                keywords: vec![],
+                cvss,
+                informational: self.database_specific.informational.clone(),
+                url: references.next(),
+                references: vec![],
+                withdrawn: match &self.withdrawn {
+                    Some(withdrawn) => Some(rfc3339_to_rustsec_date(withdrawn)?),
+                    None => None,
+                },
+                superseded_by: None,
+            },
+            affected: rustsec_affected,
+            versions,
+        };
+
+        advisory.metadata.references = references.collect();
+        Ok(advisory)
+    }
+}
+
+/// End of an interval of affected versions
+#[derive(Clone, Debug)]
+enum End {
+    /// Versions from this one on are no longer affected
+    Fixed(Version),
+    /// This is the last affected version
+    LastAffected(Version),
+    /// All later versions are affected
+    Unbounded,
+}
+
+/// Interval of affected versions, from OSV range events
+#[derive(Clone, Debug)]
+struct Interval {
+    /// First affected version (`None` for all versions before `end`)
+    start: Option<Version>,
+    /// End of the interval
+    end: End,
+}
+
+impl Interval {
+    /// Requirement matching the versions in the interval
+    fn to_req(&self) -> Result<VersionReq, Error> {
+        let mut comparators = vec![];
+
+        if let Some(start) = &self.start {
+            comparators.push(format!(">= {}", start));
+        }
+
+        match &self.end {
+            End::Fixed(v) => comparators.push(format!("< {}", v)),
+            End::LastAffected(v) => comparators.push(format!("<= {}", v)),
+            End::Unbounded => (),
+        }
+
+        if comparators.is_empty() {
+            comparators.push("*".to_owned());
+        }
+
+        Ok(comparators.join(", ").parse()?)
+    }
+}
+
+/// Intervals of affected versions, ordered by their start
+fn affected_intervals(id: &Id, ranges: &[OsvJsonRange]) -> Result<Vec<Interval>, Error> {
+    let mut intervals = vec![];
+
+    for range in ranges {
+        if range.kind != "SEMVER" && range.kind != "ECOSYSTEM" {
+            continue;
+        }
+
+        let mut start: Option<Option<Version>> = None;
+
+        for event in &range.events {
+            match event {
+                OsvRangeEvent::Introduced(v) => start = Some(parse_introduced(v)?),
+                OsvRangeEvent::Fixed(v) | OsvRangeEvent::LastAffected(v) => {
+                    let v: Version = v.parse()?;
+                    let end = match event {
+                        OsvRangeEvent::Fixed(_) => End::Fixed(v),
+                        _ => End::LastAffected(v),
+                    };
+
+                    intervals.push(Interval {
+                        start: start.take().unwrap_or(None),
+                        end,
+                    });
+                }
+                OsvRangeEvent::Limit(_) => (),
+            }
+        }
+
+        if let Some(start) = start {
+            intervals.push(Interval {
+                start,
+                end: End::Unbounded,
+            });
+        }
+    }
+
+    if intervals.is_empty() {
+        fail!(
+            ErrorKind::BadParam,
+            "{} has no SEMVER ranges of affected versions",
+            id
+        );
+    }
+
+    // Intervals without a start come first
+    intervals.sort_by(|a, b| a.start.cmp(&b.start));
+    Ok(intervals)
+}
+
+/// Parse the version of an `introduced` event, where `0` (or `0.0.0-0`)
+/// means every version
+fn parse_introduced(version: &str) -> Result<Option<Version>, Error> {
+    if version == "0" {
+        return Ok(None);
+    }
+
+    let version: Version = version.parse()?;
+
+    Ok(if version == Version::parse("0.0.0-0").unwrap() {
+        None
+    } else {
+        Some(version)
+    })
+}
+
+/// Patched and unaffected versions between the given intervals of affected
+/// versions: versions before the first interval are unaffected, and ones
+/// after any of them are patched
+fn versions_for_intervals(intervals: &[Interval]) -> Result<Versions, Error> {
+    let mut patched = vec![];
+    let mut unaffected = vec![];
+
+    if let Some(start) = intervals.first().and_then(|first| first.start.as_ref()) {
+        unaffected.push(format!("< {}", start).parse()?);
+    }
+
+    for (i, interval) in intervals.iter().enumerate() {
+        let after = match &interval.end {
+            End::Fixed(v) => format!(">= {}", v),
+            End::LastAffected(v) => format!("> {}", v),
+            End::Unbounded => continue,
+        };
+
+        let req = match intervals.get(i + 1).and_then(|next| next.start.as_ref()) {
+            Some(next) => match &interval.end {
+                // No versions between the intervals
+                End::Fixed(v) if v == next => continue,
+                _ => format!("{}, < {}", after, next),
+            },
+            None => after,
+        };
+
+        patched.push(req.parse()?);
+    }
+
+    Versions::new(patched, unaffected)
 }
 
 fn osv_references(references: Vec<Url>) -> Vec<OsvReference> {
@@ -214,7 +552,7 @@ fn guess_url_kind(url: &Url) -> OsvReferenceKind {
 
 /// Like ``ranges_for_advisory``, but also converts from ``OsvRange`` to ``OsvJsonRange``
 /// Assumes that the input has already been validated; panics if passed an invalid advisory.
-fn json_ranges_for_advisory(versions: &crate::advisory::Versions) -> Vec<OsvJsonRange> {
+fn json_ranges_for_advisory(versions: &Versions) -> Vec<OsvJsonRange> {
     ranges_for_advisory(versions)
         .into_iter()
         .map(|x| x.into())
@@ -227,8 +565,20 @@ fn git2_time_to_rfc3339(time: &git2::Time) -> String {
     DateTime::<Utc>::from_utc(time, Utc).to_rfc3339()
 }
 
-fn rustsec_date_to_rfc3339(d: &crate::advisory::Date) -> String {
+pub(crate) fn rustsec_date_to_rfc3339(d: &Date) -> String {
     let pub_date: NaiveDate = NaiveDate::from_ymd(d.year() as i32, d.month(), d.day());
     let pub_time = NaiveDateTime::new(pub_date, NaiveTime::from_hms(12, 0, 0));
     DateTime::<Utc>::from_utc(pub_time, Utc).to_rfc3339()
 }
+
+/// Date part of an RFC 3339 timestamp
+fn rfc3339_to_rustsec_date(timestamp: &str) -> Result<Date, Error> {
+    match timestamp.get(..10) {
+        Some(date) => date.parse(),
+        None => fail!(
+            ErrorKind::Parse,
+            "invalid RFC 3339 timestamp: {}",
+            timestamp
+        ),
+    }
+}
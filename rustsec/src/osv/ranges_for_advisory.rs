@@ -27,6 +27,34 @@ pub(crate) fn ranges_for_unvalidated_advisory(
     unaffected_to_osv_ranges(&versions.unaffected, &versions.patched)
 }
 
+/// Returns true if any version matching the given requirement is affected
+/// according to the given advisory (ignoring that requirements only match
+/// pre-releases when they name one).
+/// Errors if the requirement can't be expressed as a single range,
+/// e.g. `1.*` or `^1.2, <1.5`.
+pub(crate) fn requirement_is_affected(
+    versions: &Versions,
+    req: &VersionReq,
+) -> Result<bool, Error> {
+    // Requirements are converted to a range the same way as unaffected ones
+    let required: UnaffectedRange = req.try_into()?;
+
+    Ok(ranges_for_advisory(versions).iter().any(|range| {
+        let start = range
+            .introduced
+            .clone()
+            .map_or(Bound::Unbounded, Bound::Inclusive);
+        let end = range
+            .fixed
+            .clone()
+            .map_or(Bound::Unbounded, Bound::Exclusive);
+
+        UnaffectedRange::new(start, end)
+            .map(|affected| affected.overlaps(&required))
+            .unwrap_or(false)
+    }))
+}
+
 /// Converts a list of unaffected ranges to a range of affected OSV ranges.
 /// Since OSV ranges are a negation of the UNaffected ranges that RustSec stores,
 /// the entire list has to be passed at once, both patched and unaffected ranges.
@@ -161,8 +189,9 @@ fn increment(v: &Version) -> Version {
 
 #[cfg(test)]
 mod tests {
-    use super::increment;
-    use semver::Version;
+    use super::{increment, requirement_is_affected};
+    use crate::advisory::Versions;
+    use semver::{Version, VersionReq};
 
     #[test]
     fn increment_simple() {
@@ -218,4 +247,28 @@ mod tests {
         let expected = Version::parse("1.2.3-alpha.1.foo.0").unwrap();
         assert_eq!(expected, incremented);
     }
+
+    #[test]
+    fn requirement_affected() {
+        let versions = Versions::new(
+            vec![VersionReq::parse(">= 1.2.3").unwrap()],
+            vec![VersionReq::parse("< 1.0.0").unwrap()],
+        )
+        .unwrap();
+        let affected = |req: &str| {
+            requirement_is_affected(&versions, &VersionReq::parse(req).unwrap()).unwrap()
+        };
+
+        assert!(affected("1"));
+        assert!(affected("~1.2.0"));
+        assert!(affected("=1.2.2"));
+        assert!(affected("*"));
+        assert!(!affected("1.2.3"));
+        assert!(!affected("^0.9"));
+        assert!(!affected("=1.2.3"));
+        assert!(!affected(">= 1.3, < 2"));
+
+        let unsupported = VersionReq::parse("1.*").unwrap();
+        assert!(requirement_is_affected(&versions, &unsupported).is_err());
+    }
 }
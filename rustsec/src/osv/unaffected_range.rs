@@ -85,6 +85,20 @@ impl UnaffectedRange {
         // range check for well-formed ranges is `(Start1 <= End2) && (Start2 <= End1)`
         self.start.less_or_equal(&other.end) && other.start.less_or_equal(&self.end)
     }
+
+    /// Returns true if the version is in the range, according to semver
+    /// precedence (so pre-releases are ordered before their release)
+    pub fn contains(&self, v: &Version) -> bool {
+        (match &self.start {
+            Bound::Unbounded => true,
+            Bound::Exclusive(start_v) => v > start_v,
+            Bound::Inclusive(start_v) => v >= start_v,
+        }) && (match &self.end {
+            Bound::Unbounded => true,
+            Bound::Exclusive(end_v) => v < end_v,
+            Bound::Inclusive(end_v) => v <= end_v,
+        })
+    }
 }
 
 impl Display for UnaffectedRange {
@@ -463,4 +477,16 @@ mod tests {
         let result: UnaffectedRange = (&input).try_into().unwrap();
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn contains_by_precedence() {
+        let range: UnaffectedRange = (&VersionReq::parse(">= 1.0.0, < 2.0.0").unwrap())
+            .try_into()
+            .unwrap();
+        assert!(range.contains(&Version::parse("1.0.0").unwrap()));
+        assert!(range.contains(&Version::parse("1.1.0-beta").unwrap()));
+        assert!(range.contains(&Version::parse("2.0.0-rc.1").unwrap()));
+        assert!(!range.contains(&Version::parse("1.0.0-rc.1").unwrap()));
+        assert!(!range.contains(&Version::parse("2.0.0").unwrap()));
+    }
 }
@@ -5,8 +5,18 @@ use crate::{
     package,
 };
 
-/// Crates.io registry index (local copy)
-pub struct Index(crates_index::Index);
+/// Crates.io registry index (local copy), or the git index of a registry
+/// replacing it (e.g. a mirror)
+pub struct Index(Inner);
+
+/// Local copy of a registry index
+enum Inner {
+    /// Checkout of the crates.io index
+    Checkout(crates_index::Index),
+
+    /// Cargo's bare clone of another git index
+    Bare(crates_index::BareIndex),
+}
 
 impl Index {
     /// Open the local crates.io index, fetching it if it doesn't exist, and
@@ -20,7 +30,7 @@ impl Index {
             index.retrieve()?;
         }
 
-        Ok(Index(index))
+        Ok(Index(Inner::Checkout(index)))
     }
 
     /// Open the local crates.io index, erroring if it hasn't been fetched yet
@@ -34,7 +44,32 @@ impl Index {
             );
         }
 
-        Ok(Index(index))
+        Ok(Index(Inner::Checkout(index)))
+    }
+
+    /// Open the git index at the given URL (e.g. a mirror of crates.io),
+    /// cloning it into the same location as cargo does if it doesn't exist,
+    /// and updating it if it does.
+    pub fn fetch_url(url: &str) -> Result<Self, Error> {
+        let index = crates_index::BareIndex::from_url(url)?;
+        index.open_or_clone()?.retrieve()?;
+        Ok(Index(Inner::Bare(index)))
+    }
+
+    /// Open cargo's local copy of the git index at the given URL, erroring
+    /// if it hasn't been fetched yet
+    pub fn open_url(url: &str) -> Result<Self, Error> {
+        let index = crates_index::BareIndex::from_url(url)?;
+
+        if !index.path().exists() {
+            fail!(
+                ErrorKind::Registry,
+                "registry index {} has not been fetched yet",
+                url
+            );
+        }
+
+        Ok(Index(Inner::Bare(index)))
     }
 
     /// Find an entry for a particular package in the index
@@ -43,7 +78,12 @@ impl Index {
         package: &package::Name,
         version: &package::Version,
     ) -> Result<IndexPackage, Error> {
-        let crate_releases = self.0.crate_(package.as_str()).ok_or_else(|| {
+        let crate_releases = match &self.0 {
+            Inner::Checkout(index) => index.crate_(package.as_str()),
+            Inner::Bare(index) => index.open_or_clone()?.crate_(package.as_str()),
+        };
+
+        let crate_releases = crate_releases.ok_or_else(|| {
             format_err!(
                 ErrorKind::NotFound,
                 "no results for: {} {}",
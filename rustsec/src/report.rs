@@ -6,21 +6,40 @@
 use crate::{
     advisory,
     database::{scope, Database, Query},
+    error::{Error, ErrorKind},
     lockfile::Lockfile,
     map,
+    package::{self, Package},
     platforms::target::{Arch, OS},
-    vulnerability::Vulnerability,
+    vulnerability::{Scope, Vulnerability},
     warning::{self, Warning},
-    Map,
+    Map, Version,
 };
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt, path::PathBuf, str::FromStr};
 
 #[cfg(feature = "git")]
 use std::time::SystemTime;
 
+mod set;
+
+pub use self::set::{Counts, ReportSet, Statistics};
+
+/// Version of the JSON report's schema ([`JSON_SCHEMA`]), which is bumped
+/// whenever fields are removed or change meaning (but not when they're added)
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema (draft-07) of the JSON report
+pub const JSON_SCHEMA: &str = include_str!("report/schema.json");
+
 /// Vulnerability report for a given lockfile
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Report {
+    /// Version of the schema the report conforms to ([`SCHEMA_VERSION`] for
+    /// generated reports, 0 for reports which predate schema versions)
+    #[serde(default)]
+    pub schema_version: u32,
+
     /// Information about the advisory database
     #[cfg(feature = "git")]
     pub database: DatabaseInfo,
@@ -36,6 +55,21 @@ pub struct Report {
 
     /// Warnings about dependencies (from e.g. informational advisories)
     pub warnings: WarningInfo,
+
+    /// Summary of the vulnerabilities found
+    #[serde(default)]
+    pub summary: Summary,
+
+    /// Outcome of the audit (set by the tool which ran it, e.g. `cargo audit`,
+    /// which decides e.g. which warnings are denied)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<Outcome>,
+
+    /// Packages which were audited, and how many were skipped (set by the
+    /// tool which ran the audit if requested, e.g. with `cargo audit
+    /// --include-inventory`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inventory: Option<Inventory>,
 }
 
 impl Report {
@@ -43,25 +77,320 @@ impl Report {
     pub fn generate(db: &Database, lockfile: &Lockfile, settings: &Settings) -> Self {
         let package_scope = settings.package_scope.as_ref().cloned().unwrap_or_default();
 
-        let vulnerabilities = db
-            .query_vulnerabilities(lockfile, &settings.query(), package_scope)
+        let (vulnerabilities, mut warnings) = join(
+            || {
+                db.query_vulnerabilities(lockfile, &settings.query(), package_scope)
+                    .into_iter()
+                    .filter(|vuln| !settings.ignore.contains(&vuln.advisory.id))
+                    .map(|mut vuln| {
+                        if vuln.severity.is_none() {
+                            vuln.effective_severity = settings.unscored_severity;
+                        }
+
+                        vuln
+                    })
+                    .collect::<Vec<_>>()
+            },
+            || find_warnings(db, lockfile, settings),
+        );
+
+        let (ignored, vulnerabilities): (Vec<_>, Vec<_>) = vulnerabilities
             .into_iter()
-            .filter(|vuln| !settings.ignore.contains(&vuln.advisory.id))
-            .collect();
+            .partition(|vuln| settings.ignores_categories(&vuln.advisory));
 
-        let warnings = find_warnings(db, lockfile, settings);
+        // Advisories specific to other platforms than the target can't
+        // affect its builds, but are still worth knowing about
+        let (other_platform, vulnerabilities): (Vec<_>, Vec<_>) = vulnerabilities
+            .into_iter()
+            .partition(|vuln| settings.is_other_platform(vuln.affected.as_ref()));
 
-        Self {
+        if !other_platform.is_empty() {
+            warnings.insert(
+                warning::Kind::OtherPlatform,
+                other_platform
+                    .into_iter()
+                    .map(|vuln| {
+                        let mut warning = Warning::new(
+                            warning::Kind::OtherPlatform,
+                            &vuln.package,
+                            Some(vuln.advisory),
+                            Some(vuln.versions),
+                        );
+                        warning.source = vuln.source;
+                        warning
+                    })
+                    .collect(),
+            );
+        }
+
+        // Overridden packages match advisories by version, but may well be
+        // forks which already contain the fix
+        let (overridden, vulnerabilities): (Vec<_>, Vec<_>) =
+            vulnerabilities.into_iter().partition(|vuln| {
+                settings.overridden != OverriddenAction::Fail && is_overridden(&vuln.package)
+            });
+
+        if settings.overridden == OverriddenAction::Warn && !overridden.is_empty() {
+            warnings.insert(
+                warning::Kind::Overridden,
+                overridden
+                    .into_iter()
+                    .map(|vuln| {
+                        let mut warning = Warning::new(
+                            warning::Kind::Overridden,
+                            &vuln.package,
+                            Some(vuln.advisory),
+                            Some(vuln.versions),
+                        );
+                        warning.source = vuln.source;
+                        warning
+                    })
+                    .collect(),
+            );
+        }
+
+        let mut vulnerabilities = VulnerabilityInfo::new(vulnerabilities);
+        vulnerabilities.ignored = ignored;
+
+        let mut report = Self {
+            schema_version: SCHEMA_VERSION,
             #[cfg(feature = "git")]
             database: DatabaseInfo::new(db),
             lockfile: LockfileInfo::new(lockfile),
             settings: settings.clone(),
-            vulnerabilities: VulnerabilityInfo::new(vulnerabilities),
+            summary: Summary::new(&vulnerabilities),
+            vulnerabilities,
             warnings,
+            outcome: None,
+            inventory: None,
+        };
+
+        report.summary.count_warnings(&report.warnings);
+        report.sort();
+        report
+    }
+
+    /// Sort vulnerabilities by advisory ID, then by package, and warnings
+    /// (which are already grouped by kind) by package, then by advisory ID.
+    ///
+    /// Reports are sorted when generated, so two reports generated from the
+    /// same inputs serialize identically. Call this again after adding
+    /// vulnerabilities or warnings to a report.
+    pub fn sort(&mut self) {
+        for list in &mut [
+            &mut self.vulnerabilities.list,
+            &mut self.vulnerabilities.ignored,
+        ] {
+            list.sort_by(|a, b| {
+                (&a.advisory.id, package_key(&a.package))
+                    .cmp(&(&b.advisory.id, package_key(&b.package)))
+            });
+        }
+
+        self.vulnerabilities.acknowledged.sort_by(|a, b| {
+            let (a, b) = (&a.vulnerability, &b.vulnerability);
+            (&a.advisory.id, package_key(&a.package))
+                .cmp(&(&b.advisory.id, package_key(&b.package)))
+        });
+
+        self.vulnerabilities.vex.sort_by(|a, b| {
+            let (a, b) = (&a.vulnerability, &b.vulnerability);
+            (&a.advisory.id, package_key(&a.package))
+                .cmp(&(&b.advisory.id, package_key(&b.package)))
+        });
+
+        for warnings in self.warnings.values_mut() {
+            warnings.sort_by(|a, b| {
+                (
+                    package_key(&a.package),
+                    a.advisory.as_ref().map(|advisory| &advisory.id),
+                )
+                    .cmp(&(
+                        package_key(&b.package),
+                        b.advisory.as_ref().map(|advisory| &advisory.id),
+                    ))
+            });
         }
     }
 }
 
+/// Differences between the findings of two reports for the same project,
+/// e.g. before and after updating its dependencies.
+///
+/// Findings are matched by advisory and package name (and source), so e.g.
+/// upgrading a vulnerable package to a version which is still affected by the
+/// same advisory leaves the vulnerability unchanged. Yanked crate warnings
+/// have no advisory, so they're matched by package version instead.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Diff {
+    /// Findings in the newer report which aren't in the older one
+    pub introduced: Findings,
+
+    /// Findings in the older report which aren't in the newer one
+    pub resolved: Findings,
+
+    /// Findings in both reports (as they appear in the newer one)
+    pub unchanged: Findings,
+}
+
+impl Diff {
+    /// Compare the findings of the older report `from` to the newer report `to`
+    pub fn new(from: &Report, to: &Report) -> Self {
+        let mut diff = Self::default();
+
+        for vulnerability in &to.vulnerabilities.list {
+            let key = vulnerability_key(vulnerability);
+
+            if from
+                .vulnerabilities
+                .list
+                .iter()
+                .any(|other| vulnerability_key(other) == key)
+            {
+                diff.unchanged.vulnerabilities.push(vulnerability.clone());
+            } else {
+                diff.introduced.vulnerabilities.push(vulnerability.clone());
+            }
+        }
+
+        for vulnerability in &from.vulnerabilities.list {
+            let key = vulnerability_key(vulnerability);
+
+            if !to
+                .vulnerabilities
+                .list
+                .iter()
+                .any(|other| vulnerability_key(other) == key)
+            {
+                diff.resolved.vulnerabilities.push(vulnerability.clone());
+            }
+        }
+
+        for warning in to.warnings.values().flatten() {
+            let key = warning_key(warning);
+
+            if from
+                .warnings
+                .values()
+                .flatten()
+                .any(|other| warning_key(other) == key)
+            {
+                diff.unchanged.warnings.push(warning.clone());
+            } else {
+                diff.introduced.warnings.push(warning.clone());
+            }
+        }
+
+        for warning in from.warnings.values().flatten() {
+            let key = warning_key(warning);
+
+            if !to
+                .warnings
+                .values()
+                .flatten()
+                .any(|other| warning_key(other) == key)
+            {
+                diff.resolved.warnings.push(warning.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+/// Vulnerabilities and warnings in a [`Diff`]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Findings {
+    /// Vulnerabilities, in report order
+    pub vulnerabilities: Vec<Vulnerability>,
+
+    /// Warnings, in report order (i.e. grouped by kind)
+    pub warnings: Vec<Warning>,
+}
+
+impl Findings {
+    /// Number of vulnerabilities and warnings
+    pub fn len(&self) -> usize {
+        self.vulnerabilities.len() + self.warnings.len()
+    }
+
+    /// Are there no vulnerabilities or warnings?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Key vulnerabilities are matched by when diffing reports
+fn vulnerability_key(
+    vulnerability: &Vulnerability,
+) -> (&advisory::Id, &package::Name, Option<&package::SourceId>) {
+    (
+        &vulnerability.advisory.id,
+        &vulnerability.package.name,
+        vulnerability.package.source.as_ref(),
+    )
+}
+
+/// Key warnings are matched by when diffing reports
+fn warning_key(
+    warning: &Warning,
+) -> (
+    warning::Kind,
+    Option<&advisory::Id>,
+    &package::Name,
+    Option<&Version>,
+    Option<&package::SourceId>,
+) {
+    let advisory_id = warning.advisory.as_ref().map(|advisory| &advisory.id);
+
+    (
+        warning.kind,
+        advisory_id,
+        &warning.package.name,
+        // Warnings without an advisory (i.e. yanked crates) are about a
+        // particular version
+        if advisory_id.is_none() {
+            Some(&warning.package.version)
+        } else {
+            None
+        },
+        warning.package.source.as_ref(),
+    )
+}
+
+/// Key packages are sorted by in reports
+fn package_key(package: &Package) -> (&package::Name, &Version, Option<&package::SourceId>) {
+    (&package.name, &package.version, package.source.as_ref())
+}
+
+/// Is the given package's source overridden, e.g. with a git fork or local
+/// copy via `[patch]`?
+fn is_overridden(package: &Package) -> bool {
+    match &package.source {
+        Some(source) => source.is_git() || source.is_path(),
+        None => true,
+    }
+}
+
+/// Run both closures, in parallel with the `parallel` feature
+#[cfg(feature = "parallel")]
+fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    rayon::join(a, b)
+}
+
+/// Run both closures, one after the other (threads can't be spawned on e.g.
+/// `wasm32-unknown-unknown`)
+#[cfg(not(feature = "parallel"))]
+fn join<RA, RB>(a: impl FnOnce() -> RA, b: impl FnOnce() -> RB) -> (RA, RB) {
+    (a(), b())
+}
+
 /// Options to use when generating the report
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Settings {
@@ -74,37 +403,240 @@ pub struct Settings {
     /// Severity threshold to alert at
     pub severity: Option<advisory::Severity>,
 
+    /// Severity to assume for advisories without a CVSS score, for severity
+    /// thresholds and sorting (by default they're always alerted on)
+    #[serde(default)]
+    pub unscored_severity: Option<advisory::Severity>,
+
     /// List of advisory IDs to ignore
     pub ignore: Vec<advisory::Id>,
 
+    /// Categories of advisories to ignore: vulnerabilities are ignored if
+    /// all of their advisory's categories are in this list (and it has any)
+    #[serde(default)]
+    pub ignore_categories: Vec<advisory::Category>,
+
+    /// Only report vulnerabilities whose advisories have at least one of
+    /// these categories (if any are given), ignoring the others
+    #[serde(default)]
+    pub categories: Vec<advisory::Category>,
+
+    /// Ignore vulnerabilities whose advisories have any of these categories
+    #[serde(default)]
+    pub exclude_categories: Vec<advisory::Category>,
+
     /// Types of informational advisories to generate warnings for
     pub informational_warnings: Vec<advisory::Informational>,
 
     /// Scope of packages which should be considered for audit
     pub package_scope: Option<scope::Package>,
+
+    /// How to report vulnerabilities in packages whose source was overridden
+    #[serde(default)]
+    pub overridden: OverriddenAction,
+
+    /// Match withdrawn advisories too (they're ignored by default, as they
+    /// were filed in error)
+    #[serde(default)]
+    pub include_withdrawn: bool,
 }
 
 impl Settings {
     /// Get a query which corresponds to the configured report settings.
     /// Note that queries can't filter ignored advisories, so this happens in
-    /// a separate pass
+    /// a separate pass, and that advisories for other platforms than the
+    /// target aren't filtered either, as they're reported as warnings
     pub fn query(&self) -> Query {
         let mut query = Query::crate_scope();
 
-        if let Some(target_arch) = self.target_arch {
-            query = query.target_arch(target_arch);
+        if let Some(severity) = self.severity {
+            query = query.severity(severity);
         }
 
-        if let Some(target_os) = self.target_os {
-            query = query.target_os(target_os);
+        if let Some(severity) = self.unscored_severity {
+            query = query.unscored_severity(severity);
         }
 
-        if let Some(severity) = self.severity {
-            query = query.severity(severity);
+        if self.include_withdrawn {
+            query = query.any_withdrawn();
         }
 
         query
     }
+
+    /// Is an advisory with the given `affected` metadata specific to other
+    /// CPU architectures or operating systems than the target?
+    pub fn is_other_platform(&self, affected: Option<&advisory::Affected>) -> bool {
+        let affected = match affected {
+            Some(affected) => affected,
+            None => return false,
+        };
+
+        let other_arch = match self.target_arch {
+            Some(arch) => !affected.arch.is_empty() && !affected.arch.contains(&arch),
+            None => false,
+        };
+
+        let other_os = match self.target_os {
+            Some(os) => !affected.os.is_empty() && !affected.os.contains(&os),
+            None => false,
+        };
+
+        other_arch || other_os
+    }
+
+    /// Is the given advisory ignored by category, i.e. are all of its
+    /// categories (of which it has at least one) ignored, does it have an
+    /// excluded category, or none of the selected ones?
+    pub fn ignores_categories(&self, advisory: &advisory::Metadata) -> bool {
+        let ignored = !advisory.categories.is_empty()
+            && advisory
+                .categories
+                .iter()
+                .all(|category| self.ignore_categories.contains(category));
+
+        let excluded = advisory
+            .categories
+            .iter()
+            .any(|category| self.exclude_categories.contains(category));
+
+        let unselected = !self.categories.is_empty()
+            && !advisory
+                .categories
+                .iter()
+                .any(|category| self.categories.contains(category));
+
+        ignored || excluded || unselected
+    }
+}
+
+/// How to report vulnerabilities in packages whose source was overridden
+/// (i.e. git or path dependencies, e.g. forks used via `[patch]`), whose
+/// version matches an advisory even though they may contain a fix
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum OverriddenAction {
+    /// Report them as vulnerabilities
+    #[serde(rename = "fail")]
+    Fail,
+
+    /// Report them as `overridden` warnings, to be verified manually
+    #[serde(rename = "warn")]
+    Warn,
+
+    /// Don't report them
+    #[serde(rename = "ignore")]
+    Ignore,
+}
+
+impl Default for OverriddenAction {
+    fn default() -> Self {
+        OverriddenAction::Warn
+    }
+}
+
+impl FromStr for OverriddenAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match s {
+            "fail" => OverriddenAction::Fail,
+            "warn" => OverriddenAction::Warn,
+            "ignore" => OverriddenAction::Ignore,
+            other => fail!(ErrorKind::Parse, "invalid overridden action: {}", other),
+        })
+    }
+}
+
+/// Outcome of an audit, which tools map onto exit statuses.
+///
+/// Reports only ever have the outcomes of a completed audit: the others
+/// describe failures which prevent a report from being generated.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Outcome {
+    /// No vulnerabilities or denied warnings were found
+    #[serde(rename = "clean")]
+    Clean,
+
+    /// Vulnerabilities were found
+    #[serde(rename = "vulnerabilities-found")]
+    VulnerabilitiesFound,
+
+    /// Denied warnings (but no vulnerabilities) were found
+    #[serde(rename = "denied-warnings")]
+    DeniedWarnings,
+
+    /// Delivering the report to a webhook failed, when that was required
+    #[serde(rename = "notify-failed")]
+    NotifyFailed,
+
+    /// The advisory database is stale (i.e. hasn't been updated in 90 days)
+    #[serde(rename = "stale-db")]
+    StaleDb,
+
+    /// The advisory database couldn't be fetched or loaded
+    #[serde(rename = "database-error")]
+    DatabaseError,
+
+    /// Any other error, e.g. an unreadable lockfile
+    #[serde(rename = "operational-error")]
+    OperationalError,
+
+    /// The lockfile failed an integrity check, e.g. a checksum didn't match
+    /// the registry's
+    #[serde(rename = "integrity-failed")]
+    IntegrityFailed,
+}
+
+impl Outcome {
+    /// All outcomes
+    pub fn all() -> Vec<Self> {
+        vec![
+            Outcome::Clean,
+            Outcome::VulnerabilitiesFound,
+            Outcome::DeniedWarnings,
+            Outcome::NotifyFailed,
+            Outcome::StaleDb,
+            Outcome::DatabaseError,
+            Outcome::OperationalError,
+            Outcome::IntegrityFailed,
+        ]
+    }
+
+    /// Get the name of this outcome, as used in reports
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Clean => "clean",
+            Outcome::VulnerabilitiesFound => "vulnerabilities-found",
+            Outcome::DeniedWarnings => "denied-warnings",
+            Outcome::NotifyFailed => "notify-failed",
+            Outcome::StaleDb => "stale-db",
+            Outcome::DatabaseError => "database-error",
+            Outcome::OperationalError => "operational-error",
+            Outcome::IntegrityFailed => "integrity-failed",
+        }
+    }
+
+    /// Is this the outcome of a failed audit?
+    pub fn is_failure(self) -> bool {
+        self != Outcome::Clean
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::all()
+            .into_iter()
+            .find(|outcome| outcome.as_str() == s)
+            .ok_or_else(|| format_err!(ErrorKind::Parse, "invalid outcome: {}", s))
+    }
 }
 
 /// Information about the advisory database
@@ -122,6 +654,24 @@ pub struct DatabaseInfo {
     /// Date when the advisory database was last committed to
     #[serde(rename = "last-updated", with = "humantime_serde")]
     pub last_updated: Option<SystemTime>,
+
+    /// Date of the newest advisory in the database, which (unlike
+    /// `last_updated`) doesn't change when a mirror is merely re-fetched
+    #[serde(
+        rename = "newest-advisory",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub newest_advisory: Option<advisory::Date>,
+
+    /// Why findings' `first_published_in_db` dates are unknown (if they were
+    /// looked up and the database has no git history to look them up in)
+    #[serde(
+        rename = "first-published-note",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub first_published_note: Option<String>,
 }
 
 #[cfg(feature = "git")]
@@ -132,6 +682,8 @@ impl DatabaseInfo {
             advisory_count: db.iter().count(),
             last_commit: db.latest_commit().map(|c| c.commit_id.clone()),
             last_updated: db.latest_commit().map(|c| c.timestamp),
+            newest_advisory: db.iter().map(|a| &a.metadata.date).max().cloned(),
+            first_published_note: None,
         }
     }
 }
@@ -142,6 +694,10 @@ pub struct LockfileInfo {
     /// Number of dependencies in the lock file
     #[serde(rename = "dependency-count")]
     dependency_count: usize,
+
+    /// Path to the lock file (if known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
 }
 
 impl LockfileInfo {
@@ -149,8 +705,14 @@ impl LockfileInfo {
     pub fn new(lockfile: &Lockfile) -> Self {
         Self {
             dependency_count: lockfile.packages.len(),
+            path: None,
         }
     }
+
+    /// Number of dependencies in the lock file
+    pub fn dependency_count(&self) -> usize {
+        self.dependency_count
+    }
 }
 
 /// Information about detected vulnerabilities
@@ -164,17 +726,337 @@ pub struct VulnerabilityInfo {
 
     /// List of detected vulnerabilities
     pub list: Vec<Vulnerability>,
+
+    /// Detected vulnerabilities grouped by advisory, e.g. when several
+    /// versions of a package are affected by the same advisory
+    #[serde(default)]
+    pub groups: Vec<VulnerabilityGroup>,
+
+    /// Vulnerabilities ignored by category (see
+    /// [`Settings::ignores_categories`]), which aren't counted as found
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored: Vec<Vulnerability>,
+
+    /// Vulnerabilities whose advisories were acknowledged until a deadline
+    /// (set by the tool which ran the audit, e.g. `cargo audit`), which
+    /// aren't counted as found until their acknowledgments expire
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub acknowledged: Vec<AcknowledgedVulnerability>,
+
+    /// Vulnerabilities a VEX statement says the product isn't affected by
+    /// (applied by the tool which ran the audit, e.g. `cargo audit --vex`),
+    /// which aren't counted as found
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vex: Vec<VexSuppressedVulnerability>,
+}
+
+/// Vulnerability whose advisory was acknowledged until a deadline, e.g.
+/// while a ticket to remediate it is worked on
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AcknowledgedVulnerability {
+    /// The vulnerability
+    pub vulnerability: Vulnerability,
+
+    /// Reference to the ticket tracking its remediation
+    pub ticket: String,
+
+    /// Last day the acknowledgment is in effect
+    pub expires: advisory::Date,
+
+    /// Number of days left until the acknowledgment expires
+    #[serde(rename = "days-remaining")]
+    pub days_remaining: u64,
+}
+
+/// Vulnerability suppressed by a VEX (Vulnerability Exploitability
+/// eXchange) statement
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VexSuppressedVulnerability {
+    /// The vulnerability
+    pub vulnerability: Vulnerability,
+
+    /// ID of the VEX document the statement is in
+    pub document: String,
+
+    /// Status the statement gives the product, e.g. `not_affected` or `fixed`
+    pub status: String,
+
+    /// Why the product isn't affected (if the statement says)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub justification: Option<String>,
+
+    /// Explanation of why the product isn't affected (if the statement has
+    /// one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impact_statement: Option<String>,
 }
 
 impl VulnerabilityInfo {
     /// Create new vulnerability info
     pub fn new(list: Vec<Vulnerability>) -> Self {
+        let mut groups: Vec<VulnerabilityGroup> = vec![];
+
+        for vuln in &list {
+            match groups
+                .iter_mut()
+                .find(|group| group.advisory == vuln.advisory.id)
+            {
+                Some(group) => group.versions.push(vuln.package.version.clone()),
+                None => groups.push(VulnerabilityGroup {
+                    advisory: vuln.advisory.id.clone(),
+                    package: vuln.package.name.clone(),
+                    versions: vec![vuln.package.version.clone()],
+                }),
+            }
+        }
+
+        groups.sort_by(|a, b| a.advisory.cmp(&b.advisory));
+
+        for group in &mut groups {
+            group.versions.sort();
+            group.versions.dedup();
+        }
+
         Self {
             found: !list.is_empty(),
             count: list.len(),
             list,
+            groups,
+            ignored: vec![],
+            acknowledged: vec![],
+            vex: vec![],
         }
     }
+
+    /// Number of distinct advisories the vulnerabilities are for
+    pub fn advisory_count(&self) -> usize {
+        self.groups.len()
+    }
+}
+
+/// Summary of the vulnerabilities in a report, which gives a better idea of
+/// the effort needed to fix them than the number of vulnerabilities does
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Summary {
+    /// Number of distinct advisories vulnerabilities were found for
+    pub advisories: usize,
+
+    /// Number of distinct vulnerable package versions
+    #[serde(rename = "package-versions")]
+    pub package_versions: usize,
+
+    /// Number of distinct vulnerable crates (by name)
+    pub crates: usize,
+
+    /// Number of direct dependencies of the workspace which are, or
+    /// transitively depend on, a vulnerable package (if the dependency graph
+    /// is known)
+    #[serde(rename = "direct-dependencies", default)]
+    pub direct_dependencies: Option<usize>,
+
+    /// Number of vulnerabilities in direct dependencies of the workspace (if
+    /// the dependency graph is known)
+    #[serde(
+        rename = "direct-vulnerabilities",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub direct_vulnerabilities: Option<usize>,
+
+    /// Number of vulnerabilities in transitive dependencies of the workspace
+    /// (if the dependency graph is known)
+    #[serde(
+        rename = "transitive-vulnerabilities",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub transitive_vulnerabilities: Option<usize>,
+
+    /// Number of vulnerabilities of each (effective) severity
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub severities: Map<advisory::Severity, usize>,
+
+    /// Number of vulnerabilities without a severity
+    #[serde(default)]
+    pub unscored: usize,
+
+    /// Number of vulnerabilities which were found but ignored by category
+    /// policy, acknowledged, or suppressed by VEX
+    #[serde(default)]
+    pub ignored: usize,
+
+    /// Vulnerabilities in each kind of dependency (if a `fail-on` policy is
+    /// configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Map<Scope, ScopeSummary>>,
+
+    /// Warnings of each kind (omitting kinds without warnings)
+    #[serde(
+        rename = "warning-kinds",
+        default,
+        skip_serializing_if = "Map::is_empty"
+    )]
+    pub warning_kinds: Map<warning::Kind, WarningSummary>,
+}
+
+/// Vulnerabilities in a kind of dependency
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScopeSummary {
+    /// Number of vulnerabilities in packages reachable through the scope
+    pub vulnerabilities: usize,
+
+    /// Number of them which fail because of the scope's rule
+    pub failed: usize,
+}
+
+/// Warnings of a kind
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WarningSummary {
+    /// Number of distinct warnings (see [`warning::finding_count`])
+    pub findings: usize,
+
+    /// Number of package versions they're about
+    #[serde(rename = "package-versions")]
+    pub package_versions: usize,
+}
+
+impl Summary {
+    /// Summarize the given vulnerabilities (without the direct dependencies,
+    /// which are computed from the dependency graph)
+    pub fn new(vulnerabilities: &VulnerabilityInfo) -> Self {
+        let package_versions: BTreeSet<_> = vulnerabilities
+            .list
+            .iter()
+            .map(|vuln| package_key(&vuln.package))
+            .collect();
+
+        let crates: BTreeSet<_> = vulnerabilities
+            .list
+            .iter()
+            .map(|vuln| &vuln.package.name)
+            .collect();
+
+        let mut severities = Map::new();
+        let mut unscored = 0;
+
+        for vuln in &vulnerabilities.list {
+            match vuln.effective_severity.or(vuln.severity) {
+                Some(severity) => *severities.entry(severity).or_default() += 1,
+                None => unscored += 1,
+            }
+        }
+
+        Self {
+            advisories: vulnerabilities.advisory_count(),
+            package_versions: package_versions.len(),
+            crates: crates.len(),
+            direct_dependencies: None,
+            direct_vulnerabilities: None,
+            transitive_vulnerabilities: None,
+            severities,
+            unscored,
+            ignored: vulnerabilities.ignored.len()
+                + vulnerabilities.acknowledged.len()
+                + vulnerabilities.vex.len(),
+            scopes: None,
+            warning_kinds: Map::new(),
+        }
+    }
+
+    /// Count the warnings of each kind
+    pub fn count_warnings(&mut self, warnings: &WarningInfo) {
+        self.warning_kinds = warnings
+            .iter()
+            .filter(|(_, warnings)| !warnings.is_empty())
+            .map(|(kind, warnings)| {
+                let summary = WarningSummary {
+                    findings: warning::finding_count(warnings),
+                    package_versions: warnings.len(),
+                };
+
+                (*kind, summary)
+            })
+            .collect();
+    }
+}
+
+/// What an audit checked: the packages which were matched against
+/// advisories, and how many packages of the lockfile were skipped by each
+/// mechanism which skips them
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Inventory {
+    /// Packages which were matched against advisories
+    pub packages: Vec<InventoryPackage>,
+
+    /// Numbers of packages which were skipped
+    pub skipped: Skipped,
+}
+
+/// Package which was audited
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InventoryPackage {
+    /// Name of the package
+    pub name: package::Name,
+
+    /// Version of the package
+    pub version: Version,
+
+    /// Source of the package (`None` for workspace members and path
+    /// dependencies)
+    pub source: Option<package::SourceId>,
+
+    /// Checksum of the package (if the lockfile has one)
+    pub checksum: Option<package::Checksum>,
+}
+
+impl From<&Package> for InventoryPackage {
+    fn from(package: &Package) -> Self {
+        Self {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            source: package.source.clone(),
+            checksum: package.checksum.clone(),
+        }
+    }
+}
+
+/// Numbers of packages of the lockfile skipped by each mechanism
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Skipped {
+    /// Packages which aren't compiled with the selected features (including
+    /// dev-dependencies and dependencies for other targets), which were
+    /// removed before auditing
+    pub features: usize,
+
+    /// Packages without a source (i.e. workspace members and path
+    /// dependencies) outside the package scope, which weren't matched
+    /// against advisories
+    #[serde(rename = "source-scope")]
+    pub source_scope: usize,
+
+    /// Git packages skipped by the package scope, which weren't matched
+    /// against advisories
+    pub git: usize,
+
+    /// Packages (of those which weren't removed by the feature selection)
+    /// which weren't checked for yanked releases because they aren't from
+    /// crates.io, or `None` if yanked releases weren't checked for at all
+    #[serde(rename = "yanked-check")]
+    pub yanked_check: Option<usize>,
+}
+
+/// Vulnerabilities found for a single advisory (details about each affected
+/// package version are in [`VulnerabilityInfo::list`])
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VulnerabilityGroup {
+    /// Advisory the package is vulnerable to
+    pub advisory: advisory::Id,
+
+    /// Vulnerable package
+    pub package: package::Name,
+
+    /// Affected versions of the package in the lockfile
+    pub versions: Vec<Version>,
 }
 
 /// Information about warnings
@@ -182,12 +1064,22 @@ pub type WarningInfo = Map<warning::Kind, Vec<Warning>>;
 
 /// Find warnings from the given advisory [`Database`] and [`Lockfile`]
 pub fn find_warnings(db: &Database, lockfile: &Lockfile, settings: &Settings) -> WarningInfo {
-    let query = settings.query().informational(true);
+    let mut query = settings.query().informational(true);
     let package_scope = settings.package_scope.as_ref().cloned().unwrap_or_default();
 
     let mut warnings = WarningInfo::default();
 
     // TODO(tarcieri): abstract `Cargo.lock` query logic between vulnerabilities/warnings
+    // Informational advisories for other platforms than the target are left
+    // out altogether
+    if let Some(target_arch) = settings.target_arch {
+        query = query.target_arch(target_arch);
+    }
+
+    if let Some(target_os) = settings.target_os {
+        query = query.target_os(target_os);
+    }
+
     for advisory_vuln in db.query_vulnerabilities(lockfile, &query, package_scope) {
         let advisory = &advisory_vuln.advisory;
 
@@ -210,12 +1102,13 @@ pub fn find_warnings(db: &Database, lockfile: &Lockfile, settings: &Settings) ->
                 None => continue,
             };
 
-            let warning = Warning::new(
+            let mut warning = Warning::new(
                 warning_kind,
                 &advisory_vuln.package,
                 Some(advisory.clone()),
                 Some(advisory_vuln.versions.clone()),
             );
+            warning.source = advisory_vuln.source.clone();
 
             match warnings.entry(warning.kind) {
                 map::Entry::Occupied(entry) => (*entry.into_mut()).push(warning),
@@ -0,0 +1,276 @@
+//! Sets of reports, e.g. for the lockfiles of several workspaces
+
+use super::Report;
+use crate::{
+    advisory,
+    error::{Error, ErrorKind},
+    Map,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, convert::TryFrom};
+
+/// Reports for several lockfiles, keyed by an identifier (e.g. the path to
+/// each lockfile).
+///
+/// Every report must have been generated against the same advisory database
+/// commit, so the findings they're aggregated into are consistent.
+///
+/// When serialized, the full metadata of each advisory found is stored once
+/// in `advisories`, and the findings of the `reports` omit the advisories'
+/// descriptions (which are the bulk of the metadata) rather than repeating
+/// them for every lockfile.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(try_from = "Serialized", into = "Serialized")]
+pub struct ReportSet {
+    /// Reports, by identifier
+    reports: Map<String, Report>,
+}
+
+impl ReportSet {
+    /// Create an empty report set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the report with the given identifier to the set.
+    ///
+    /// Returns an error if the set already has a report with the identifier,
+    /// or the report was generated against a different advisory database
+    /// commit than the reports already in the set.
+    pub fn insert(&mut self, id: impl Into<String>, report: Report) -> Result<(), Error> {
+        let id = id.into();
+
+        if self.reports.contains_key(&id) {
+            return Err(Error::new(
+                ErrorKind::BadParam,
+                &format!("the report set already has a report for {}", id),
+            ));
+        }
+
+        #[cfg(feature = "git")]
+        {
+            if let Some((other_id, other)) = self.reports.iter().next() {
+                if other.database.last_commit != report.database.last_commit {
+                    return Err(Error::new(
+                        ErrorKind::BadParam,
+                        &format!(
+                            "the report for {} was generated against advisory database commit {}, \
+                             but the report for {} was generated against {}",
+                            id,
+                            report
+                                .database
+                                .last_commit
+                                .as_deref()
+                                .unwrap_or("(unknown)"),
+                            other_id,
+                            other.database.last_commit.as_deref().unwrap_or("(unknown)")
+                        ),
+                    ));
+                }
+            }
+        }
+
+        self.reports.insert(id, report);
+        Ok(())
+    }
+
+    /// Get the report with the given identifier
+    pub fn get(&self, id: &str) -> Option<&Report> {
+        self.reports.get(id)
+    }
+
+    /// Iterate over the reports, by identifier
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Report)> {
+        self.reports
+            .iter()
+            .map(|(id, report)| (id.as_str(), report))
+    }
+
+    /// Number of reports in the set
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    /// Is the set empty?
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+
+    /// Information about the advisory database the reports were generated
+    /// against (`None` if the set is empty)
+    #[cfg(feature = "git")]
+    pub fn database(&self) -> Option<&super::DatabaseInfo> {
+        self.reports.values().next().map(|report| &report.database)
+    }
+
+    /// Statistics aggregated across all of the reports
+    pub fn statistics(&self) -> Statistics {
+        let mut advisories = BTreeSet::new();
+        let mut vulnerabilities = BTreeSet::new();
+        let mut reports = Map::new();
+
+        for (id, report) in &self.reports {
+            for vuln in &report.vulnerabilities.list {
+                advisories.insert(vuln.advisory.id.clone());
+                vulnerabilities.insert(vuln.advisory.id.clone());
+            }
+
+            for warning in report.warnings.values().flatten() {
+                if let Some(advisory) = &warning.advisory {
+                    advisories.insert(advisory.id.clone());
+                }
+            }
+
+            reports.insert(id.clone(), Counts::new(report));
+        }
+
+        Statistics {
+            vulnerable_reports: reports
+                .values()
+                .filter(|counts| counts.vulnerabilities > 0)
+                .count(),
+            advisories: advisories.len(),
+            vulnerabilities: vulnerabilities.len(),
+            reports,
+        }
+    }
+}
+
+/// Statistics aggregated across the reports in a set
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Statistics {
+    /// Number of distinct advisories found across all of the reports, for
+    /// vulnerabilities or warnings
+    pub advisories: usize,
+
+    /// Number of distinct advisories for vulnerabilities found across all of
+    /// the reports
+    pub vulnerabilities: usize,
+
+    /// Number of reports with vulnerabilities
+    #[serde(rename = "vulnerable-reports")]
+    pub vulnerable_reports: usize,
+
+    /// Counts of the findings of each report, by identifier
+    pub reports: Map<String, Counts>,
+}
+
+/// Counts of the findings of a report
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Counts {
+    /// Number of vulnerabilities (i.e. vulnerable package versions)
+    pub vulnerabilities: usize,
+
+    /// Number of distinct advisories for the vulnerabilities
+    pub advisories: usize,
+
+    /// Number of warnings
+    pub warnings: usize,
+}
+
+impl Counts {
+    /// Count the findings of the given report
+    pub fn new(report: &Report) -> Self {
+        Self {
+            vulnerabilities: report.vulnerabilities.count,
+            advisories: report.vulnerabilities.advisory_count(),
+            warnings: report.warnings.values().map(Vec::len).sum(),
+        }
+    }
+}
+
+/// Serialized form of a report set, with advisory metadata deduplicated
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Serialized {
+    /// Statistics aggregated across all of the reports (ignored when
+    /// deserializing, as they're recomputed)
+    #[serde(default)]
+    statistics: Statistics,
+
+    /// Metadata of the advisories found, by ID
+    advisories: Map<advisory::Id, advisory::Metadata>,
+
+    /// Reports, by identifier, whose findings omit their advisories'
+    /// descriptions
+    reports: Map<String, Report>,
+}
+
+impl From<ReportSet> for Serialized {
+    fn from(set: ReportSet) -> Self {
+        let statistics = set.statistics();
+        let mut advisories = Map::new();
+        let mut reports = set.reports;
+
+        for report in reports.values_mut() {
+            for metadata in findings_metadata(report) {
+                if !advisories.contains_key(&metadata.id) {
+                    advisories.insert(metadata.id.clone(), metadata.clone());
+                }
+
+                metadata.description.clear();
+            }
+        }
+
+        Self {
+            statistics,
+            advisories,
+            reports,
+        }
+    }
+}
+
+impl TryFrom<Serialized> for ReportSet {
+    type Error = Error;
+
+    fn try_from(serialized: Serialized) -> Result<Self, Error> {
+        let mut set = ReportSet::new();
+
+        for (id, mut report) in serialized.reports {
+            for metadata in findings_metadata(&mut report) {
+                let full = serialized.advisories.get(&metadata.id).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Parse,
+                        &format!("no metadata for advisory {} in the report set", metadata.id),
+                    )
+                })?;
+
+                metadata.description = full.description.clone();
+            }
+
+            set.insert(id, report)?;
+        }
+
+        Ok(set)
+    }
+}
+
+/// Advisory metadata of the findings of a report
+fn findings_metadata(report: &mut Report) -> Vec<&mut advisory::Metadata> {
+    let vulnerabilities = &mut report.vulnerabilities;
+
+    vulnerabilities
+        .list
+        .iter_mut()
+        .chain(vulnerabilities.ignored.iter_mut())
+        .chain(
+            vulnerabilities
+                .acknowledged
+                .iter_mut()
+                .map(|acknowledged| &mut acknowledged.vulnerability),
+        )
+        .chain(
+            vulnerabilities
+                .vex
+                .iter_mut()
+                .map(|suppressed| &mut suppressed.vulnerability),
+        )
+        .map(|vuln| &mut vuln.advisory)
+        .chain(
+            report
+                .warnings
+                .values_mut()
+                .flatten()
+                .filter_map(|warning| warning.advisory.as_mut()),
+        )
+        .collect()
+}
@@ -2,5 +2,17 @@
 
 pub mod signature;
 
+#[cfg(any(feature = "git", feature = "tarball"))]
+mod fetch_options;
+
 #[cfg(feature = "git")]
 pub mod git;
+
+#[cfg(any(feature = "tarball", feature = "async"))]
+pub mod tarball;
+
+#[cfg(feature = "async")]
+pub mod transport;
+
+#[cfg(any(feature = "git", feature = "tarball"))]
+pub use self::fetch_options::{FetchOptions, Progress};
@@ -0,0 +1,92 @@
+//! Options for fetching the advisory database
+
+use std::{fmt, time::Duration};
+
+/// Progress of fetching the advisory database
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Progress {
+    /// Objects received so far (git only)
+    pub received_objects: usize,
+
+    /// Objects to receive (git only, zero until the server has said)
+    pub total_objects: usize,
+
+    /// Deltas resolved so far, once every object has been received (git
+    /// only)
+    pub indexed_deltas: usize,
+
+    /// Deltas to resolve (git only)
+    pub total_deltas: usize,
+
+    /// Bytes received so far
+    pub received_bytes: usize,
+
+    /// Bytes to receive, if the server said (tarballs only)
+    pub total_bytes: Option<usize>,
+}
+
+impl Progress {
+    /// Is the fetch resolving deltas, i.e. has every object been received?
+    pub fn is_resolving_deltas(&self) -> bool {
+        self.total_deltas > 0 && self.received_objects == self.total_objects
+    }
+}
+
+/// Options for fetching the advisory database
+#[derive(Default)]
+pub struct FetchOptions<'a> {
+    /// How long the fetch may go without making progress before it's
+    /// aborted
+    pub(crate) timeout: Option<Duration>,
+
+    /// URL of the proxy to connect through
+    pub(crate) proxy: Option<String>,
+
+    /// Called whenever the fetch makes progress
+    progress: Option<Box<dyn FnMut(&Progress) + 'a>>,
+}
+
+impl<'a> FetchOptions<'a> {
+    /// Fetch with the default options: no timeout, the proxy configured for
+    /// git (or in the environment), and no progress reporting
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort the fetch if it makes no progress for the given duration, e.g.
+    /// because a firewall silently drops the connection
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Connect through the proxy with the given URL (e.g.
+    /// `http://proxy.example.com:8080`)
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Call the given function whenever the fetch makes progress
+    pub fn progress(mut self, callback: impl FnMut(&Progress) + 'a) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Report the fetch's progress to the callback (if any)
+    pub(crate) fn report(&mut self, progress: &Progress) {
+        if let Some(callback) = &mut self.progress {
+            callback(progress);
+        }
+    }
+}
+
+impl fmt::Debug for FetchOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FetchOptions")
+            .field("timeout", &self.timeout)
+            .field("proxy", &self.proxy)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
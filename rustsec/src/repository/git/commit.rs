@@ -6,6 +6,9 @@ use crate::{
 };
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "signatures")]
+use crate::repository::signature::Keyring;
+
 /// Number of days after which the repo will be considered stale
 /// (90 days)
 const STALE_AFTER: Duration = Duration::from_secs(90 * 86400);
@@ -25,8 +28,8 @@ pub struct Commit {
     /// Commit time in number of seconds since the UNIX epoch
     pub timestamp: SystemTime,
 
-    /// Signature on the commit (mandatory for Repository::fetch)
-    // TODO: actually verify signatures
+    /// Signature on the commit (mandatory for Repository::fetch, and verified
+    /// by Repository::fetch_verified)
     pub signature: Option<Signature>,
 
     /// Signed data to verify along with this commit
@@ -46,6 +49,11 @@ impl Commit {
             )
         })?;
 
+        Self::from_oid(repo, oid)
+    }
+
+    /// Get information about the commit with the given ID
+    pub(crate) fn from_oid(repo: &Repository, oid: git2::Oid) -> Result<Self, Error> {
         let commit_id = oid.to_string();
         let commit_object = repo.repo.find_object(oid, Some(git2::ObjectType::Commit))?;
         let commit = commit_object.as_commit().unwrap();
@@ -87,6 +95,33 @@ impl Commit {
         self.signed_data.as_ref().map(|bytes| bytes.as_ref())
     }
 
+    /// Verify the commit's signature was made by one of the keys of the
+    /// keyring
+    #[cfg(feature = "signatures")]
+    pub fn verify(&self, keyring: &Keyring) -> Result<(), Error> {
+        let (signature, signed_data) = match (&self.signature, self.raw_signed_bytes()) {
+            (Some(signature), Some(signed_data)) => (signature, signed_data),
+            _ => fail!(
+                ErrorKind::Signature,
+                "no signature on commit {}: {} ({})",
+                self.commit_id,
+                self.summary,
+                self.author
+            ),
+        };
+
+        signature.verify(signed_data, keyring).map_err(|e| {
+            format_err!(
+                ErrorKind::Signature,
+                "couldn't verify commit {}: {} ({}): {}",
+                self.commit_id,
+                self.summary,
+                self.author,
+                e.msg()
+            )
+        })
+    }
+
     /// Reset the repository's state to match this commit
     pub(crate) fn reset(&self, repo: &Repository) -> Result<(), Error> {
         let commit_object = repo.repo.find_object(
@@ -2,10 +2,20 @@
 
 use super::{with_authentication, Commit, DEFAULT_URL};
 use crate::{
+    advisory::Date,
     error::{Error, ErrorKind},
     fs,
+    repository::{FetchOptions, Progress},
 };
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::UNIX_EPOCH,
+};
+
+#[cfg(feature = "signatures")]
+use crate::repository::signature::Keyring;
 
 /// Directory under `~/.cargo` where the advisory-db repo will be kept
 const ADVISORY_DB_DIRECTORY: &str = "advisory-db";
@@ -45,6 +55,68 @@ impl Repository {
         url: &str,
         into_path: P,
         ensure_fresh: bool,
+    ) -> Result<Self, Error> {
+        Self::fetch_with(url, into_path, ensure_fresh, &mut FetchOptions::new())
+    }
+
+    /// Fetch the repository like [`Repository::fetch`], with the given
+    /// timeout, proxy and progress reporting options
+    pub fn fetch_with<P: Into<PathBuf>>(
+        url: &str,
+        into_path: P,
+        ensure_fresh: bool,
+        options: &mut FetchOptions<'_>,
+    ) -> Result<Self, Error> {
+        Self::fetch_checked(url, into_path, ensure_fresh, options, &|_| Ok(()))
+    }
+
+    /// Fetch the repository like [`Repository::fetch`], but fail unless its
+    /// latest commit is signed by one of the keys of the keyring. The
+    /// repository is only reset to the fetched commit once its signature is
+    /// verified.
+    #[cfg(feature = "signatures")]
+    pub fn fetch_verified<P: Into<PathBuf>>(
+        url: &str,
+        into_path: P,
+        ensure_fresh: bool,
+        keyring: &Keyring,
+    ) -> Result<Self, Error> {
+        Self::fetch_verified_with(
+            url,
+            into_path,
+            ensure_fresh,
+            keyring,
+            &mut FetchOptions::new(),
+        )
+    }
+
+    /// Fetch and verify the repository like [`Repository::fetch_verified`],
+    /// with the given timeout, proxy and progress reporting options
+    #[cfg(feature = "signatures")]
+    pub fn fetch_verified_with<P: Into<PathBuf>>(
+        url: &str,
+        into_path: P,
+        ensure_fresh: bool,
+        keyring: &Keyring,
+        options: &mut FetchOptions<'_>,
+    ) -> Result<Self, Error> {
+        if keyring.is_empty() {
+            fail!(ErrorKind::BadParam, "no keys to verify {} with", url);
+        }
+
+        Self::fetch_checked(url, into_path, ensure_fresh, options, &|commit| {
+            commit.verify(keyring)
+        })
+    }
+
+    /// Fetch the repository, checking its latest commit before resetting the
+    /// repository's state to it
+    fn fetch_checked<P: Into<PathBuf>>(
+        url: &str,
+        into_path: P,
+        ensure_fresh: bool,
+        options: &mut FetchOptions<'_>,
+        check: &dyn Fn(&Commit) -> Result<(), Error>,
     ) -> Result<Self, Error> {
         if !url.starts_with("https://") {
             fail!(
@@ -72,14 +144,116 @@ impl Repository {
             fs::remove_dir(&path)?;
         }
 
+        Self::transfer(url, &path, options)?;
+
+        let repo = Self::open(path)?;
+        let latest_commit = repo.latest_commit()?;
+        check(&latest_commit)?;
+        latest_commit.reset(&repo)?;
+
+        // Any commits we fetch should always be signed
+        // TODO: verify signatures against GitHub's public key
+        if latest_commit.signature.is_none() {
+            fail!(
+                ErrorKind::Repo,
+                "no signature on commit {}: {} ({})",
+                latest_commit.commit_id,
+                latest_commit.summary,
+                latest_commit.author
+            );
+        }
+
+        // Ensure that the upstream repository hasn't gone stale
+        if ensure_fresh && !latest_commit.is_fresh() {
+            fail!(
+                ErrorKind::Stale,
+                "repository is stale (last commit: {:?})",
+                latest_commit.timestamp
+            );
+        }
+
+        Ok(repo)
+    }
+
+    /// Clone or fetch the repository on a worker thread, reporting its
+    /// progress, and giving up on it if it makes none for the configured
+    /// timeout (libgit2 can't be interrupted while it waits on the network,
+    /// so it's told to abort as soon as it reports progress again)
+    fn transfer(url: &str, path: &Path, options: &mut FetchOptions<'_>) -> Result<(), Error> {
+        let (sender, receiver) = mpsc::channel();
+        let worker_url = url.to_owned();
+        let worker_path = path.to_owned();
+        let proxy = options.proxy.clone();
+
+        thread::spawn(move || {
+            let result = Self::clone_or_fetch(&worker_url, &worker_path, proxy.as_deref(), &sender);
+            let _ = sender.send(Message::Done(result));
+        });
+
+        loop {
+            let message = match options.timeout {
+                Some(timeout) => match receiver.recv_timeout(timeout) {
+                    Ok(message) => Some(message),
+                    Err(RecvTimeoutError::Timeout) => fail!(
+                        ErrorKind::Io,
+                        "timed out: no progress fetching {} for {}s",
+                        url,
+                        timeout.as_secs()
+                    ),
+                    Err(RecvTimeoutError::Disconnected) => None,
+                },
+                None => receiver.recv().ok(),
+            };
+
+            match message {
+                Some(Message::Progress(progress)) => options.report(&progress),
+                Some(Message::Alive) => (),
+                Some(Message::Done(result)) => return result,
+                None => fail!(ErrorKind::Io, "fetching {} failed unexpectedly", url),
+            }
+        }
+    }
+
+    /// Clone the repository into the given path, or fetch it if it's already
+    /// there, sending the transfer's progress to the given channel
+    fn clone_or_fetch(
+        url: &str,
+        path: &Path,
+        proxy: Option<&str>,
+        sender: &Sender<Message>,
+    ) -> Result<(), Error> {
         let git_config = git2::Config::new()?;
 
         with_authentication(url, &git_config, |f| {
             let mut callbacks = git2::RemoteCallbacks::new();
             callbacks.credentials(f);
 
+            // Progress is only sent while someone's waiting for it
+            let progress = sender.clone();
+            callbacks.transfer_progress(move |stats| {
+                progress
+                    .send(Message::Progress(Progress {
+                        received_objects: stats.received_objects(),
+                        total_objects: stats.total_objects(),
+                        indexed_deltas: stats.indexed_deltas(),
+                        total_deltas: stats.total_deltas(),
+                        received_bytes: stats.received_bytes(),
+                        total_bytes: None,
+                    }))
+                    .is_ok()
+            });
+
+            // Messages from the server (e.g. `Counting objects`) show it's
+            // still there before any objects are sent
+            let alive = sender.clone();
+            callbacks.sideband_progress(move |_| alive.send(Message::Alive).is_ok());
+
             let mut proxy_opts = git2::ProxyOptions::new();
-            proxy_opts.auto();
+
+            match proxy {
+                Some(proxy) => proxy_opts.url(proxy),
+                None => proxy_opts.auto(),
+            };
 
             let mut fetch_opts = git2::FetchOptions::new();
             fetch_opts.remote_callbacks(callbacks);
@@ -112,7 +286,7 @@ impl Repository {
                         // TODO(tarcieri): remove this workaround after repos have migrated
                         let old_ref = repo.find_reference("refs/heads/master")?;
                         git2::Branch::wrap(old_ref).rename("main", true)?;
-                        Self::fetch(url, &path, ensure_fresh)?;
+                        Self::clone_or_fetch(url, path, proxy, sender)?;
                     }
                     Err(e) => {
                         return Err(e.into());
@@ -125,34 +299,7 @@ impl Repository {
             }
 
             Ok(())
-        })?;
-
-        let repo = Self::open(path)?;
-        let latest_commit = repo.latest_commit()?;
-        latest_commit.reset(&repo)?;
-
-        // Any commits we fetch should always be signed
-        // TODO: verify signatures against GitHub's public key
-        if latest_commit.signature.is_none() {
-            fail!(
-                ErrorKind::Repo,
-                "no signature on commit {}: {} ({})",
-                latest_commit.commit_id,
-                latest_commit.summary,
-                latest_commit.author
-            );
-        }
-
-        // Ensure that the upstream repository hasn't gone stale
-        if ensure_fresh && !latest_commit.is_fresh() {
-            fail!(
-                ErrorKind::Repo,
-                "repository is stale (last commit: {:?})",
-                latest_commit.timestamp
-            );
-        }
-
-        Ok(repo)
+        })
     }
 
     /// Open a repository at the given path
@@ -172,8 +319,86 @@ impl Repository {
         Commit::from_repo_head(self)
     }
 
+    /// Get information about the last commit on the first-parent history of
+    /// `HEAD` which was made on or before the given date (in UTC), if any.
+    /// Shallow clones only have the history they were cloned with.
+    pub fn commit_as_of(&self, date: &Date) -> Result<Option<Commit>, Error> {
+        let end_of_day = humantime::parse_rfc3339(&format!("{}T23:59:59Z", date.as_str()))
+            .map_err(|e| format_err!(ErrorKind::Parse, "invalid date {}: {}", date.as_str(), e))?;
+        let cutoff = end_of_day
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs() as i64)
+            .unwrap_or(0);
+
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.simplify_first_parent()?;
+
+        for oid in revwalk {
+            let oid = oid?;
+
+            if self.repo.find_commit(oid)?.time().seconds() <= cutoff {
+                return Commit::from_oid(self, oid).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Paths (relative to the repository) and contents of the advisory files
+    /// in the given directory of the tree at the given commit, i.e. those of
+    /// `<dir>/<package>/<file>`
+    pub(crate) fn files_at(
+        &self,
+        commit: &Commit,
+        dir: &str,
+    ) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+        let oid = git2::Oid::from_str(&commit.commit_id)?;
+        let tree = self.repo.find_commit(oid)?.tree()?;
+        let mut files = vec![];
+
+        let dir_tree = match tree.get_name(dir) {
+            Some(entry) => match entry.to_object(&self.repo)?.into_tree() {
+                Ok(dir_tree) => dir_tree,
+                Err(_) => return Ok(files),
+            },
+            None => return Ok(files),
+        };
+
+        for package in dir_tree.iter() {
+            let package_tree = match package.to_object(&self.repo)?.into_tree() {
+                Ok(package_tree) => package_tree,
+                Err(_) => continue,
+            };
+
+            for file in package_tree.iter() {
+                if let Ok(blob) = file.to_object(&self.repo)?.into_blob() {
+                    let path = Path::new(dir)
+                        .join(String::from_utf8_lossy(package.name_bytes()).as_ref())
+                        .join(String::from_utf8_lossy(file.name_bytes()).as_ref());
+
+                    files.push((path, blob.content().to_vec()));
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
     /// Path to the local checkout of a git repository
     pub fn path(&self) -> &Path {
         self.path.as_ref()
     }
 }
+
+/// Message from the thread a repository is transferred on
+enum Message {
+    /// The transfer made progress
+    Progress(Progress),
+
+    /// The server is still responding
+    Alive,
+
+    /// The transfer finished
+    Done(Result<(), Error>),
+}
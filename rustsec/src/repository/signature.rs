@@ -2,6 +2,13 @@
 
 use crate::error::Error;
 
+#[cfg(feature = "signatures")]
+use {
+    crate::{error::ErrorKind, fs},
+    pgp::{Deserializable, SignedPublicKey, StandaloneSignature},
+    std::{io::Cursor, path::Path},
+};
+
 /// Digital signatures (in OpenPGP format) on commits to the repository
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Signature(Vec<u8>);
@@ -12,6 +19,31 @@ impl Signature {
         // TODO: actually verify the signature is well-structured
         Ok(Signature(bytes.into()))
     }
+
+    /// Verify the signature over the given data was made by one of the keys
+    /// of the keyring (or their subkeys)
+    #[cfg(feature = "signatures")]
+    pub fn verify(&self, signed_data: &[u8], keyring: &Keyring) -> Result<(), Error> {
+        let (signature, _) = StandaloneSignature::from_armor_single(Cursor::new(&self.0))?;
+
+        let verified = keyring.keys.iter().any(|key| {
+            signature.verify(key, signed_data).is_ok()
+                || key
+                    .public_subkeys
+                    .iter()
+                    .any(|subkey| signature.verify(subkey, signed_data).is_ok())
+        });
+
+        if !verified {
+            fail!(
+                ErrorKind::Signature,
+                "signature wasn't made by any of the {} trusted key(s)",
+                keyring.keys.len()
+            );
+        }
+
+        Ok(())
+    }
 }
 
 impl AsRef<[u8]> for Signature {
@@ -19,3 +51,70 @@ impl AsRef<[u8]> for Signature {
         self.0.as_ref()
     }
 }
+
+/// Pinned set of OpenPGP public keys which the advisory database's commits
+/// must be signed by, e.g. GitHub's web-flow key (which signs commits merged
+/// through GitHub's UI), exported from <https://github.com/web-flow.gpg>
+#[cfg(feature = "signatures")]
+#[derive(Clone, Debug, Default)]
+pub struct Keyring {
+    /// Trusted public keys
+    keys: Vec<SignedPublicKey>,
+}
+
+#[cfg(feature = "signatures")]
+impl Keyring {
+    /// Parse ASCII-armored public keys (one or more key blocks)
+    pub fn from_armored(armored: &str) -> Result<Self, Error> {
+        let mut keyring = Self::default();
+        keyring.add_armored(armored)?;
+        Ok(keyring)
+    }
+
+    /// Load ASCII-armored public keys from the given files
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Self, Error> {
+        let mut keyring = Self::default();
+
+        for path in paths {
+            let path = path.as_ref();
+            let armored = fs::read_to_string(path)?;
+
+            keyring.add_armored(&armored).map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    &format!("invalid public key {}: {}", path.display(), e.msg()),
+                )
+            })?;
+        }
+
+        Ok(keyring)
+    }
+
+    /// Number of trusted keys (not counting subkeys)
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Are there no trusted keys?
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Add the keys in the given ASCII-armored key blocks, checking their
+    /// self-signatures
+    fn add_armored(&mut self, armored: &str) -> Result<(), Error> {
+        let (keys, _) = SignedPublicKey::from_armor_many(Cursor::new(armored.as_bytes()))?;
+
+        for key in keys {
+            let key = key?;
+            key.verify()?;
+            self.keys.push(key);
+        }
+
+        if self.keys.is_empty() {
+            fail!(ErrorKind::Signature, "no public keys found");
+        }
+
+        Ok(())
+    }
+}
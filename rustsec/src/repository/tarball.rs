@@ -0,0 +1,581 @@
+//! Tarball snapshots of the RustSec advisory DB
+//!
+//! Instead of cloning the advisory database's git repository, a snapshot of
+//! it can be downloaded over HTTPS as a `.tar.gz` archive (e.g. from GitHub's
+//! codeload service), which doesn't need libgit2. The `ETag` the archive was
+//! served with is kept alongside the unpacked snapshot, so it's only
+//! downloaded again once the database has changed.
+//!
+//! With the `async` feature, snapshots can also be downloaded asynchronously
+//! with an HTTP client of the caller's choosing (see the `transport` module).
+
+#[cfg(feature = "async")]
+use crate::repository::transport::{Request, Transport};
+#[cfg(feature = "tarball")]
+use crate::repository::{FetchOptions, Progress};
+use crate::{
+    error::{Error, ErrorKind},
+    fs,
+};
+#[cfg(feature = "tarball")]
+use reqwest::{header, StatusCode};
+#[cfg(feature = "tarball")]
+use std::io::Read;
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf},
+};
+
+/// Location of a tarball snapshot of the RustSec advisory database's `main`
+/// branch
+pub const DEFAULT_URL: &str =
+    "https://codeload.github.com/RustSec/advisory-db/tar.gz/refs/heads/main";
+
+/// File in a snapshot's directory which records the `ETag` it was served with
+pub const ETAG_FILE: &str = ".etag";
+
+/// Directory under `~/.cargo` where the snapshot will be kept
+const ADVISORY_DB_DIRECTORY: &str = "advisory-db-snapshot";
+
+/// Size of the blocks of a tar archive
+const BLOCK_SIZE: usize = 512;
+
+/// Snapshot of an advisory database, unpacked from a tarball
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    /// Path to the unpacked snapshot
+    path: PathBuf,
+
+    /// `ETag` the snapshot was served with (if any)
+    etag: Option<String>,
+
+    /// Was the snapshot downloaded by the last fetch (rather than being up
+    /// to date already)?
+    updated: bool,
+}
+
+impl Snapshot {
+    /// Location of the default snapshot of the advisory database for
+    /// crates.io
+    pub fn default_path() -> PathBuf {
+        home::cargo_home()
+            .unwrap_or_else(|err| {
+                panic!("Error locating Cargo home directory: {}", err);
+            })
+            .join(ADVISORY_DB_DIRECTORY)
+    }
+
+    /// Fetch the default snapshot
+    #[cfg(feature = "tarball")]
+    pub fn fetch_default() -> Result<Self, Error> {
+        Self::fetch(DEFAULT_URL, Self::default_path())
+    }
+
+    /// Download the tarball at the given URL and unpack it into the given
+    /// path, unless the snapshot already there has the tarball's `ETag`.
+    ///
+    /// The archive's top-level directory (e.g. `advisory-db-main`) is
+    /// stripped, and the previous snapshot is only replaced once the new one
+    /// has been unpacked.
+    #[cfg(feature = "tarball")]
+    pub fn fetch<P: Into<PathBuf>>(url: &str, into_path: P) -> Result<Self, Error> {
+        Self::fetch_with(url, into_path, &mut FetchOptions::new())
+    }
+
+    /// Fetch the snapshot like [`Snapshot::fetch`], with the given timeout,
+    /// proxy and progress reporting options
+    #[cfg(feature = "tarball")]
+    pub fn fetch_with<P: Into<PathBuf>>(
+        url: &str,
+        into_path: P,
+        options: &mut FetchOptions<'_>,
+    ) -> Result<Self, Error> {
+        let path = into_path.into();
+        let existing = Self::prepare(url, &path)?;
+
+        let mut builder = reqwest::blocking::Client::builder()
+            .user_agent(concat!("rustsec/", env!("CARGO_PKG_VERSION")));
+
+        if let Some(timeout) = options.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(proxy) = &options.proxy {
+            builder =
+                builder.proxy(reqwest::Proxy::all(proxy).map_err(|e| {
+                    format_err!(ErrorKind::BadParam, "invalid proxy {}: {}", proxy, e)
+                })?);
+        }
+
+        let client = builder.build().map_err(|e| download_error(url, &e))?;
+
+        let mut request = client.get(url);
+
+        if let Some(etag) = existing.as_ref().and_then(|snapshot| snapshot.etag()) {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+
+        let mut response = request.send().map_err(|e| download_error(url, &e))?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(snapshot) = existing {
+                return Ok(snapshot);
+            }
+        }
+
+        if !response.status().is_success() {
+            fail!(
+                ErrorKind::Io,
+                "couldn't download {}: server responded with status code: {}",
+                url,
+                response.status()
+            );
+        }
+
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(ToOwned::to_owned);
+
+        let mut progress = Progress {
+            total_bytes: response.content_length().map(|length| length as usize),
+            ..Progress::default()
+        };
+
+        let mut archive = vec![];
+        let mut buffer = [0; 64 * 1024];
+
+        loop {
+            let count = response
+                .read(&mut buffer)
+                .map_err(|e| format_err!(ErrorKind::Io, "couldn't download {}: {}", url, e))?;
+
+            if count == 0 {
+                break;
+            }
+
+            archive.extend_from_slice(&buffer[..count]);
+            progress.received_bytes = archive.len();
+            options.report(&progress);
+        }
+
+        Self::install(url, path, &archive, etag)
+    }
+
+    /// Fetch the default snapshot with the given transport
+    #[cfg(feature = "async")]
+    pub async fn fetch_default_async<T: Transport + ?Sized>(transport: &T) -> Result<Self, Error> {
+        Self::fetch_async(transport, DEFAULT_URL, Self::default_path()).await
+    }
+
+    /// Download the tarball at the given URL with the given transport, and
+    /// unpack it into the given path, as `Snapshot::fetch` does.
+    ///
+    /// Only the download is asynchronous: the archive is unpacked on the
+    /// calling task once it's been received.
+    #[cfg(feature = "async")]
+    pub async fn fetch_async<T, P>(transport: &T, url: &str, into_path: P) -> Result<Self, Error>
+    where
+        T: Transport + ?Sized,
+        P: Into<PathBuf>,
+    {
+        let path = into_path.into();
+        let existing = Self::prepare(url, &path)?;
+
+        let request = Request {
+            url: url.to_owned(),
+            if_none_match: existing
+                .as_ref()
+                .and_then(|snapshot| snapshot.etag())
+                .map(ToOwned::to_owned),
+        };
+
+        let response = transport
+            .get(&request)
+            .await
+            .map_err(|e| format_err!(ErrorKind::Io, "couldn't download {}: {}", url, e.msg()))?;
+
+        if response.is_not_modified() {
+            if let Some(snapshot) = existing {
+                return Ok(snapshot);
+            }
+        }
+
+        if !response.is_success() {
+            fail!(
+                ErrorKind::Io,
+                "couldn't download {}: server responded with status code: {}",
+                url,
+                response.status
+            );
+        }
+
+        Self::install(url, path, &response.body, response.etag)
+    }
+
+    /// Open the snapshot unpacked at the given path
+    pub fn open<P: Into<PathBuf>>(into_path: P) -> Result<Self, Error> {
+        let path = into_path.into();
+
+        if !path.is_dir() {
+            fail!(
+                ErrorKind::NotFound,
+                "no advisory database snapshot at {}",
+                path.display()
+            );
+        }
+
+        let etag = std::fs::read_to_string(path.join(ETAG_FILE))
+            .ok()
+            .map(|etag| etag.trim().to_owned())
+            .filter(|etag| !etag.is_empty());
+
+        Ok(Self {
+            path,
+            etag,
+            updated: false,
+        })
+    }
+
+    /// Check the URL and path a snapshot is to be fetched from and into,
+    /// creating the path's parent directory, and open the snapshot already
+    /// there (if any)
+    fn prepare(url: &str, path: &Path) -> Result<Option<Self>, Error> {
+        if !url.starts_with("https://") {
+            fail!(
+                ErrorKind::BadParam,
+                "expected {} to start with https://",
+                url
+            );
+        }
+
+        // Fail early if the snapshot couldn't be staged
+        staging_path(path)?;
+
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.is_dir() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        Ok(Self::open(path).ok())
+    }
+
+    /// Unpack an archive downloaded from the given URL into the given path,
+    /// replacing the snapshot there
+    fn install(
+        url: &str,
+        path: PathBuf,
+        archive: &[u8],
+        etag: Option<String>,
+    ) -> Result<Self, Error> {
+        // Unpack next to the snapshot, so a failed download never leaves a
+        // partial database behind
+        let staging = staging_path(&path)?;
+
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+
+        unpack(archive, &staging).map_err(|e| {
+            // Leftovers are removed before the next attempt anyway
+            let _ = fs::remove_dir_all(&staging);
+            Error::new(e.kind(), &format!("couldn't unpack {}: {}", url, e.msg()))
+        })?;
+
+        if let Some(etag) = &etag {
+            fs::write(staging.join(ETAG_FILE), etag)?;
+        }
+
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+
+        fs::rename(&staging, &path)?;
+
+        Ok(Self {
+            path,
+            etag,
+            updated: true,
+        })
+    }
+
+    /// Path to the unpacked snapshot
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `ETag` the snapshot was served with, if the server sent one
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// Was the snapshot downloaded when it was fetched, rather than being up
+    /// to date already?
+    pub fn was_updated(&self) -> bool {
+        self.updated
+    }
+}
+
+/// Entry in a tar archive
+#[derive(Clone, Debug)]
+pub struct Entry<'a> {
+    /// Path of the entry in the archive (from a long name or PAX extended
+    /// header, if it has one)
+    pub path: String,
+
+    /// Type of the entry: `b'0'` (or NUL) for regular files, `b'5'` for
+    /// directories, `b'1'` and `b'2'` for hard and symbolic links
+    pub kind: u8,
+
+    /// Permission bits of the entry
+    pub mode: u32,
+
+    /// Contents of the entry
+    pub data: &'a [u8],
+}
+
+impl<'a> Entry<'a> {
+    /// Is this entry a regular file?
+    pub fn is_file(&self) -> bool {
+        matches!(self.kind, b'0' | 0)
+    }
+
+    /// Is this entry a directory?
+    pub fn is_dir(&self) -> bool {
+        self.kind == b'5'
+    }
+}
+
+/// Decompress an archive if it's gzip-compressed, or borrow it if it isn't
+pub fn decompress(archive: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+    if archive.starts_with(&[0x1f, 0x8b]) {
+        Ok(Cow::Owned(gunzip(archive)?))
+    } else {
+        Ok(Cow::Borrowed(archive))
+    }
+}
+
+/// Read the entries of an (uncompressed) tar archive. Long name and PAX
+/// extended header entries are applied to the entries they describe rather
+/// than returned.
+pub fn entries(tar: &[u8]) -> Result<Vec<Entry<'_>>, Error> {
+    let mut entries = vec![];
+    let mut offset = 0;
+    let mut long_name: Option<String> = None;
+
+    while offset + BLOCK_SIZE <= tar.len() {
+        let header = &tar[offset..offset + BLOCK_SIZE];
+
+        // The archive ends with (at least) one block of zeros
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+
+        let size = parse_octal(&header[124..136])?;
+        let data_start = offset + BLOCK_SIZE;
+
+        let data = match tar.get(data_start..data_start + size) {
+            Some(data) => data,
+            None => fail!(ErrorKind::Parse, "truncated tar archive"),
+        };
+
+        // Entries' data is padded to a whole number of blocks
+        offset = data_start + size + (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE;
+
+        match header[156] {
+            // PAX extended header: its `path` applies to the next entry
+            b'x' => long_name = pax_path(data),
+            // GNU long name: the name of the next entry
+            b'L' => {
+                long_name = Some(
+                    String::from_utf8_lossy(data)
+                        .trim_end_matches('\0')
+                        .to_owned(),
+                )
+            }
+            kind => entries.push(Entry {
+                path: match long_name.take() {
+                    Some(name) => name,
+                    None => header_path(header),
+                },
+                kind,
+                mode: parse_octal(&header[100..108])? as u32,
+                data,
+            }),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Unpack a tar archive (optionally gzip-compressed) into the given
+/// directory, stripping the archive's top-level directory. Only regular
+/// files and directories are unpacked: links and other special entries are
+/// skipped.
+pub fn unpack(archive: &[u8], into_path: &Path) -> Result<(), Error> {
+    let tar = decompress(archive)?;
+    fs::create_dir_all(into_path)?;
+
+    for entry in entries(&tar)? {
+        if entry.is_file() {
+            if let Some(path) = entry_path(into_path, &entry.path)? {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                fs::write(&path, entry.data)?;
+            }
+        } else if entry.is_dir() {
+            if let Some(path) = entry_path(into_path, &entry.path)? {
+                fs::create_dir_all(&path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Path a snapshot is unpacked into before it replaces the one at the given
+/// path
+fn staging_path(path: &Path) -> Result<PathBuf, Error> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            Ok(parent.join(format!("{}.partial", name.to_string_lossy())))
+        }
+        _ => fail!(ErrorKind::BadParam, "invalid directory: {}", path.display()),
+    }
+}
+
+/// Decompress a gzip stream
+fn gunzip(archive: &[u8]) -> Result<Vec<u8>, Error> {
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+    const FHCRC: u8 = 2;
+
+    if archive.len() < 10 || archive[2] != 8 {
+        fail!(ErrorKind::Parse, "unsupported gzip compression");
+    }
+
+    let flags = archive[3];
+    let mut offset = 10;
+
+    if flags & FEXTRA != 0 {
+        let len = archive
+            .get(offset..offset + 2)
+            .map(|len| usize::from(len[0]) | usize::from(len[1]) << 8)
+            .unwrap_or_default();
+
+        offset += 2 + len;
+    }
+
+    for flag in &[FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            offset += archive
+                .get(offset..)
+                .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+                .map(|end| end + 1)
+                .unwrap_or(archive.len());
+        }
+    }
+
+    if flags & FHCRC != 0 {
+        offset += 2;
+    }
+
+    match archive.get(offset..) {
+        Some(deflated) => miniz_oxide::inflate::decompress_to_vec(deflated)
+            .map_err(|e| format_err!(ErrorKind::Parse, "invalid gzip data: {:?}", e)),
+        None => fail!(ErrorKind::Parse, "truncated gzip header"),
+    }
+}
+
+/// Path of a tar entry from its header: the ustar prefix (if any), then its
+/// name
+fn header_path(header: &[u8]) -> String {
+    let name = nul_terminated(&header[0..100]);
+
+    if &header[257..262] == b"ustar" {
+        let prefix = nul_terminated(&header[345..500]);
+
+        if !prefix.is_empty() {
+            return format!("{}/{}", prefix, name);
+        }
+    }
+
+    name
+}
+
+/// `path` record of a PAX extended header, if it has one
+fn pax_path(data: &[u8]) -> Option<String> {
+    let records = String::from_utf8_lossy(data);
+
+    // Records are `LENGTH KEY=VALUE\n`
+    records.lines().find_map(|record| {
+        let record = &record[record.find(' ')? + 1..];
+        let equals = record.find('=')?;
+
+        if &record[..equals] == "path" {
+            Some(record[equals + 1..].to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+/// Where to unpack an entry with the given archive path: `None` for the
+/// top-level directory itself. Paths which would escape the directory are
+/// rejected.
+fn entry_path(into_path: &Path, name: &str) -> Result<Option<PathBuf>, Error> {
+    let mut path = into_path.to_owned();
+    let mut components = Path::new(name).components();
+
+    // The top-level directory, e.g. `advisory-db-main`
+    components.next();
+
+    let mut nested = false;
+
+    for component in components {
+        match component {
+            Component::Normal(part) => {
+                path.push(part);
+                nested = true;
+            }
+            Component::CurDir => (),
+            _ => fail!(ErrorKind::Parse, "invalid path in tar archive: {}", name),
+        }
+    }
+
+    Ok(if nested { Some(path) } else { None })
+}
+
+/// Parse a NUL or space terminated octal number from a tar header
+fn parse_octal(field: &[u8]) -> Result<usize, Error> {
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+
+    if digits.is_empty() {
+        return Ok(0);
+    }
+
+    usize::from_str_radix(digits, 8)
+        .map_err(|_| format_err!(ErrorKind::Parse, "invalid tar header field: {:?}", digits))
+}
+
+/// Text of a NUL-terminated header field
+fn nul_terminated(field: &[u8]) -> String {
+    let end = field
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(field.len());
+
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Error for a download which failed
+#[cfg(feature = "tarball")]
+fn download_error(url: &str, error: &reqwest::Error) -> Error {
+    format_err!(ErrorKind::Io, "couldn't download {}: {}", url, error)
+}
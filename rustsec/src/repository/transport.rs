@@ -0,0 +1,60 @@
+//! Pluggable HTTP transports for fetching the advisory database
+//! asynchronously
+//!
+//! This crate doesn't depend on any async runtime or HTTP client: services
+//! which already have one (e.g. `hyper` or `reqwest` on `tokio`) implement
+//! [`Transport`] on top of it, and pass it to
+//! [`Snapshot::fetch_async`][crate::repository::tarball::Snapshot::fetch_async]
+//! to refresh the database without blocking their executor's threads.
+
+use crate::error::Error;
+use std::{future::Future, pin::Pin};
+
+/// `User-Agent` transports should send with their requests
+pub const USER_AGENT: &str = concat!("rustsec/", env!("CARGO_PKG_VERSION"));
+
+/// Future which resolves to the response to a request
+pub type ResponseFuture<'a> = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send + 'a>>;
+
+/// HTTP client which sends requests asynchronously
+pub trait Transport {
+    /// Send a `GET` request. Redirects should be followed, and responses with
+    /// any status code returned (rather than treated as errors).
+    fn get<'a>(&'a self, request: &'a Request) -> ResponseFuture<'a>;
+}
+
+/// `GET` request for an advisory database archive
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Request {
+    /// URL to request
+    pub url: String,
+
+    /// `ETag` to send in an `If-None-Match` header (if any), so the server
+    /// responds with `304 Not Modified` if it hasn't changed
+    pub if_none_match: Option<String>,
+}
+
+/// Response to a [`Request`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Response {
+    /// HTTP status code
+    pub status: u16,
+
+    /// Value of the `ETag` header (if any)
+    pub etag: Option<String>,
+
+    /// Body of the response
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Is this a `304 Not Modified` response?
+    pub fn is_not_modified(&self) -> bool {
+        self.status == 304
+    }
+
+    /// Is the status code successful (2xx)?
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
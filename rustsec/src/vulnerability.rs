@@ -3,9 +3,13 @@
 
 use crate::{
     advisory::{self, affected::FunctionPath, Advisory},
-    package::Package,
+    osv::UnaffectedRange,
+    package::{self, Package, Version},
+    VersionReq,
 };
+use semver::Op;
 use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, path::PathBuf};
 
 /// A vulnerable package and the associated advisory
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -21,6 +25,78 @@ pub struct Vulnerability {
 
     /// Vulnerable package
     pub package: Package,
+
+    /// Severity of the advisory according to its CVSS score, or `null` if it
+    /// has none
+    #[serde(default)]
+    pub severity: Option<advisory::Severity>,
+
+    /// Severity the vulnerability is treated as having for severity
+    /// thresholds and sorting: the advisory's, or the one assumed for
+    /// advisories without a CVSS score (if configured, otherwise `null`)
+    #[serde(default)]
+    pub effective_severity: Option<advisory::Severity>,
+
+    /// Likelihood of the vulnerability being exploited (if requested, and
+    /// the advisory has a CVE alias with a known score)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epss: Option<Epss>,
+
+    /// Is the vulnerability known to be exploited, i.e. is one of its CVE
+    /// aliases in the CISA Known Exploited Vulnerabilities catalog? (if checked)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_exploited: Option<bool>,
+
+    /// Is a release satisfying the patched versions published in the
+    /// package's registry? (if looked up)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patched_release: Option<PatchedRelease>,
+
+    /// Nearest published releases which aren't vulnerable (if looked up)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upgrade: Option<Upgrade>,
+
+    /// How the recommended upgrade can be applied, given the requirements
+    /// the package's dependents declare for it (if they're known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+
+    /// Date the advisory was first added to the advisory database's git
+    /// history (`YYYY-MM-DD`, if looked up), or `null` if the database has no
+    /// history to look it up in
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_looked_up"
+    )]
+    pub first_published_in_db: Option<Option<String>>,
+
+    /// Whether the functions the advisory lists as affected are referenced
+    /// in the audited workspace's source code (if checked, and it lists any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reachability: Option<Reachability>,
+
+    /// Kinds of dependency the package is reachable through, and which of
+    /// their failure rules the vulnerability breaks (if a `fail-on` policy is
+    /// configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Scopes>,
+
+    /// Workspace members the vulnerable package is reachable from (if the
+    /// dependency graph is known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<package::Name>>,
+
+    /// Is the vulnerable package a direct dependency of a workspace member
+    /// (`false` if it's only a transitive one), if the dependency graph is
+    /// known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direct: Option<bool>,
+
+    /// Advisory database source the advisory came from, if the database was
+    /// merged from named sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 impl Vulnerability {
@@ -31,9 +107,37 @@ impl Vulnerability {
             versions: advisory.versions.clone(),
             affected: advisory.affected.clone(),
             package: package.clone(),
+            severity: advisory.severity(),
+            effective_severity: advisory.severity(),
+            epss: None,
+            known_exploited: None,
+            patched_release: None,
+            upgrade: None,
+            resolution: None,
+            first_published_in_db: None,
+            reachability: None,
+            scopes: None,
+            members: None,
+            direct: None,
+            source: None,
         }
     }
 
+    /// Is the vulnerability's effective severity assumed, as its advisory
+    /// has no CVSS score?
+    pub fn is_severity_assumed(&self) -> bool {
+        self.severity.is_none() && self.effective_severity.is_some()
+    }
+
+    /// Get the CVE IDs this vulnerability's advisory is an alias of
+    pub fn cve_ids(&self) -> impl Iterator<Item = &advisory::Id> {
+        self.advisory
+            .aliases
+            .iter()
+            .chain(Some(&self.advisory.id))
+            .filter(|id| id.is_cve())
+    }
+
     /// Get the set of functions affected by this vulnerability (if available)
     pub fn affected_functions(&self) -> Option<Vec<FunctionPath>> {
         self.affected.as_ref().and_then(|affected| {
@@ -54,3 +158,733 @@ impl Vulnerability {
         })
     }
 }
+
+/// Deserialize the result of an optional lookup, which is `null` if the
+/// lookup was made but found nothing (rather than missing if it wasn't made)
+pub(crate) fn deserialize_looked_up<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Whether a vulnerability's affected functions are referenced in the
+/// audited workspace's source code, as found by a heuristic scan of it (a
+/// hint for triage, rather than proof either way)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Reachability {
+    /// Was any of the affected functions found to be referenced?
+    pub referenced: bool,
+
+    /// Where the affected functions were found to be referenced (up to a
+    /// limit)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<FunctionReference>,
+}
+
+/// Reference to an affected function in the audited workspace's source code
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FunctionReference {
+    /// Affected function which was referenced
+    pub function: FunctionPath,
+
+    /// Source file it was referenced in, relative to the workspace's root
+    pub path: PathBuf,
+
+    /// Line it was referenced on (1-based)
+    pub line: usize,
+}
+
+/// Exploit Prediction Scoring System (EPSS) score for a CVE, as published by
+/// FIRST: <https://www.first.org/epss/>
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Epss {
+    /// CVE the score is for
+    pub cve: advisory::Id,
+
+    /// Probability of the CVE being exploited in the next 30 days (0-1)
+    pub score: f64,
+
+    /// Percentile of the score amongst all scored CVEs (0-1)
+    pub percentile: f64,
+
+    /// Date the score was calculated (`YYYY-MM-DD`)
+    pub date: String,
+}
+
+// Scores are parsed from decimal numbers, so they're never NaN
+impl Eq for Epss {}
+
+/// Availability of a release which fixes a vulnerability, according to the
+/// vulnerable package's registry
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "status")]
+pub enum PatchedRelease {
+    /// The oldest (non-yanked) release satisfying the patched versions
+    Published {
+        /// Version of the release
+        version: Version,
+
+        /// Date the release was published (`YYYY-MM-DD`, if known)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        date: Option<String>,
+    },
+
+    /// No published release satisfies the patched versions yet
+    Unreleased,
+}
+
+/// Recommended upgrade from a vulnerable version: the nearest versions which
+/// aren't vulnerable, rather than the advisory's ranges (which are easily
+/// misread, e.g. `>=1.0.5, <2.0.0 OR >=2.1.3` as allowing 2.0.0)
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Upgrade {
+    /// Vulnerable version the upgrade is from
+    pub from: Version,
+
+    /// Nearest version which isn't vulnerable and is semver-compatible with
+    /// the vulnerable one (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compatible: Option<Version>,
+
+    /// Nearest version which isn't vulnerable and is a major
+    /// (semver-incompatible) bump (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub major: Option<Version>,
+
+    /// Are the versions published releases, rather than the lower bounds of
+    /// the advisory's ranges (which may not have been released)?
+    pub published: bool,
+}
+
+impl Upgrade {
+    /// Recommend an upgrade from a vulnerable version to the given releases
+    /// (which shouldn't include yanked ones). Pre-releases are only
+    /// recommended from a pre-release.
+    ///
+    /// Returns `None` if every newer release is vulnerable.
+    pub fn from_releases<'a>(
+        from: &Version,
+        versions: &advisory::Versions,
+        releases: impl IntoIterator<Item = &'a Version>,
+    ) -> Option<Self> {
+        let candidates = releases
+            .into_iter()
+            .filter(|version| {
+                version > &from
+                    && (version.pre.is_empty() || !from.pre.is_empty())
+                    && !versions.is_vulnerable(version)
+            })
+            .cloned()
+            .collect();
+
+        Self::nearest(from, candidates, true)
+    }
+
+    /// Recommend an upgrade from a vulnerable version to the lowest versions
+    /// of the advisory's patched and unaffected ranges, without knowing
+    /// which versions have been released.
+    ///
+    /// Returns `None` if no range starts after the vulnerable version.
+    pub fn from_ranges(from: &Version, versions: &advisory::Versions) -> Option<Self> {
+        let candidates = crate::osv::ranges_for_advisory(versions)
+            .into_iter()
+            .filter_map(|range| range.fixed)
+            .map(|mut version| {
+                // Exclusive bounds (e.g. `>1.0.4`) end affected ranges at the
+                // lowest version above them, a pre-release (`1.0.5-0`)
+                if version.pre.as_str() == "0" {
+                    version.pre = semver::Prerelease::EMPTY;
+                }
+
+                version
+            })
+            .filter(|version| version > from)
+            .collect();
+
+        Self::nearest(from, candidates, false)
+    }
+
+    /// Pick the nearest compatible and major upgrades from the candidates
+    fn nearest(from: &Version, mut candidates: Vec<Version>, published: bool) -> Option<Self> {
+        candidates.sort();
+
+        let compatible = candidates
+            .iter()
+            .find(|version| is_compatible(from, version))
+            .cloned();
+
+        let major = candidates
+            .into_iter()
+            .find(|version| !is_compatible(from, version));
+
+        if compatible.is_none() && major.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            from: from.clone(),
+            compatible,
+            major,
+            published,
+        })
+    }
+}
+
+impl fmt::Display for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let target = |version: &Version| {
+            if self.published {
+                version.to_string()
+            } else {
+                format!("at least {}", version)
+            }
+        };
+
+        write!(f, "You are on {} - upgrade to ", self.from)?;
+
+        match (&self.compatible, &self.major) {
+            (Some(compatible), Some(major)) => write!(
+                f,
+                "{} (or {} if you're ready for a major bump)",
+                target(compatible),
+                target(major)
+            ),
+            (Some(compatible), None) => f.write_str(&target(compatible)),
+            (None, Some(major)) => write!(f, "{} (a major bump)", target(major)),
+            (None, None) => f.write_str("a version which isn't vulnerable"),
+        }
+    }
+}
+
+/// Package which depends on a vulnerable package, and the requirement it
+/// declares for it
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Dependent {
+    /// Name of the dependent
+    pub name: package::Name,
+
+    /// Version of the dependent
+    pub version: Version,
+
+    /// Version requirement the dependent declares for the vulnerable package
+    pub requirement: VersionReq,
+
+    /// Is the dependent a workspace member, i.e. is its `Cargo.toml` yours to
+    /// edit?
+    pub member: bool,
+}
+
+/// How the upgrade recommended for a vulnerability can be applied, given
+/// the requirements the vulnerable package's dependents declare for it
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "action")]
+pub enum Resolution {
+    /// Every dependent's requirement allows the version, so updating the
+    /// lockfile (`cargo update -p`) suffices
+    Update {
+        /// Version to update to
+        version: Version,
+    },
+
+    /// Workspace members' requirements don't allow the version, and must be
+    /// bumped before updating the lockfile
+    Bump {
+        /// Version to upgrade to
+        version: Version,
+
+        /// Workspace members whose requirements must be bumped
+        dependents: Vec<Dependent>,
+    },
+
+    /// Requirements of dependents outside the workspace don't allow any
+    /// version which isn't vulnerable, so they must be upgraded (or
+    /// patched) first
+    Blocked {
+        /// Nearest version which isn't vulnerable
+        version: Version,
+
+        /// Dependents whose requirements don't allow the version
+        dependents: Vec<Dependent>,
+    },
+}
+
+impl Resolution {
+    /// Work out how to apply an upgrade, preferring versions every
+    /// dependent's requirement allows, then those only workspace members'
+    /// requirements need bumping for, and the compatible upgrade over the
+    /// major one.
+    ///
+    /// Returns `None` if the upgrade has no versions.
+    pub fn new(upgrade: &Upgrade, dependents: &[Dependent]) -> Option<Self> {
+        let candidates: Vec<&Version> = upgrade
+            .compatible
+            .iter()
+            .chain(upgrade.major.iter())
+            .collect();
+
+        let disallowing = |version: &Version| -> Vec<Dependent> {
+            dependents
+                .iter()
+                .filter(|dependent| !dependent.requirement.matches(version))
+                .cloned()
+                .collect()
+        };
+
+        for &version in &candidates {
+            if disallowing(version).is_empty() {
+                return Some(Resolution::Update {
+                    version: version.clone(),
+                });
+            }
+        }
+
+        for &version in &candidates {
+            let dependents = disallowing(version);
+
+            if dependents.iter().all(|dependent| dependent.member) {
+                return Some(Resolution::Bump {
+                    version: version.clone(),
+                    dependents,
+                });
+            }
+        }
+
+        let version = candidates.first()?;
+
+        Some(Resolution::Blocked {
+            version: (*version).clone(),
+            dependents: disallowing(version),
+        })
+    }
+
+    /// Version the resolution upgrades to
+    pub fn version(&self) -> &Version {
+        match self {
+            Resolution::Update { version }
+            | Resolution::Bump { version, .. }
+            | Resolution::Blocked { version, .. } => version,
+        }
+    }
+}
+
+/// Are the versions semver-compatible, i.e. would a `^from` requirement
+/// match `to`? (`1.x` releases are compatible with each other, as are
+/// `0.2.x` ones)
+pub(crate) fn is_compatible(from: &Version, to: &Version) -> bool {
+    if from.major != to.major {
+        false
+    } else if from.major != 0 {
+        true
+    } else if from.minor != to.minor {
+        false
+    } else {
+        from.minor != 0 || from.patch == to.patch
+    }
+}
+
+/// Trace of how it was decided whether a version is affected by an
+/// advisory, for debugging disagreements with a match
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Explanation {
+    /// Advisory the version was evaluated against
+    pub advisory: advisory::Id,
+
+    /// Version evaluated
+    pub version: Version,
+
+    /// The advisory's patched requirements, and whether each matched
+    pub patched: Vec<RequirementMatch>,
+
+    /// The advisory's unaffected requirements, and whether each matched
+    pub unaffected: Vec<RequirementMatch>,
+
+    /// Ranges of affected versions (the gaps between the requirements), and
+    /// whether each contains the version
+    pub affected: Vec<AffectedRange>,
+
+    /// Rules for pre-releases which applied to the version (empty if it
+    /// isn't a pre-release)
+    #[serde(rename = "prerelease-rules")]
+    pub prerelease_rules: Vec<String>,
+
+    /// Is the version vulnerable?
+    pub vulnerable: bool,
+}
+
+/// Whether a version matched one of an advisory's requirements
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RequirementMatch {
+    /// Patched or unaffected requirement
+    pub requirement: VersionReq,
+
+    /// Range of versions the requirement was converted to, e.g.
+    /// `[0.7.25, ∞)`
+    pub range: String,
+
+    /// Is the version in the range? (this is what the verdict is made from)
+    pub matched: bool,
+
+    /// Would cargo's requirement matching match the version? (which only
+    /// matches pre-releases of versions the requirement names a pre-release
+    /// of, so may differ for pre-releases)
+    #[serde(rename = "cargo-matched")]
+    pub cargo_matched: bool,
+}
+
+/// Range of versions affected by an advisory: `[introduced, fixed)`
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AffectedRange {
+    /// Lowest affected version (inclusive, unbounded if `None`)
+    pub introduced: Option<Version>,
+
+    /// Lowest version after it which isn't affected (exclusive, unbounded if
+    /// `None`)
+    pub fixed: Option<Version>,
+
+    /// Does the range contain the version?
+    pub contains: bool,
+}
+
+impl fmt::Display for AffectedRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.introduced {
+            Some(introduced) => write!(f, "[{}, ", introduced)?,
+            None => f.write_str("[0, ")?,
+        }
+
+        match &self.fixed {
+            Some(fixed) => write!(f, "{})", fixed),
+            None => f.write_str("∞)"),
+        }
+    }
+}
+
+/// Explain how it's decided whether the given version is affected by the
+/// advisory: which of its patched and unaffected requirements match the
+/// version, which affected range contains it, and how a pre-release version
+/// is handled
+pub fn explain(advisory: &Advisory, version: &Version) -> Explanation {
+    let versions = &advisory.versions;
+
+    let requirement_match = |req: &VersionReq| {
+        // Requirements have been validated on deserialization
+        let range = UnaffectedRange::try_from(req).unwrap();
+
+        RequirementMatch {
+            requirement: req.clone(),
+            range: range.to_string(),
+            matched: range.contains(version),
+            cargo_matched: req.matches(version),
+        }
+    };
+
+    let affected = crate::osv::ranges_for_advisory(versions)
+        .into_iter()
+        .map(|range| AffectedRange {
+            contains: range.affects(version),
+            introduced: range.introduced,
+            fixed: range.fixed,
+        })
+        .collect();
+
+    let mut prerelease_rules = vec![];
+
+    if !version.pre.is_empty() {
+        let ops = versions
+            .patched()
+            .iter()
+            .chain(versions.unaffected())
+            .flat_map(|req| req.comparators.iter().map(|comparator| comparator.op))
+            .collect::<Vec<_>>();
+
+        prerelease_rules.push(format!(
+            "{} is a pre-release, so it's matched by semver precedence, which orders \
+             pre-releases before their release (e.g. 1.0.0-rc.1 < 1.0.0), rather than by \
+             cargo's rule of only matching pre-releases of versions a requirement names a \
+             pre-release of",
+            version
+        ));
+
+        if ops.contains(&Op::Caret) || ops.contains(&Op::Tilde) {
+            prerelease_rules.push(
+                "`^` and `~` requirements end before the pre-releases of the next version \
+                 they exclude (e.g. ^1.2 is [1.2.0, 2.0.0-0)), so those pre-releases don't \
+                 match"
+                    .to_owned(),
+            );
+        }
+
+        if ops.contains(&Op::Greater) {
+            prerelease_rules.push(
+                "`>` requirements include the pre-releases of the next version (e.g. > 1.0.4 \
+                 matches 1.0.5-alpha)"
+                    .to_owned(),
+            );
+        }
+    }
+
+    Explanation {
+        advisory: advisory.metadata.id.clone(),
+        version: version.clone(),
+        patched: versions.patched().iter().map(requirement_match).collect(),
+        unaffected: versions
+            .unaffected()
+            .iter()
+            .map(requirement_match)
+            .collect(),
+        affected,
+        prerelease_rules,
+        vulnerable: versions.is_vulnerable(version),
+    }
+}
+
+/// Kind of dependency through which a package is reachable from the
+/// workspace
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// Compiled into the workspace's packages
+    Normal,
+
+    /// Runs while building them: build dependencies, proc-macros and their
+    /// dependencies
+    Build,
+
+    /// Only compiled for tests, examples and benchmarks
+    Dev,
+}
+
+impl Scope {
+    /// All scopes
+    pub fn all() -> Vec<Self> {
+        vec![Scope::Normal, Scope::Build, Scope::Dev]
+    }
+
+    /// Get a `str` representing a scope
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Normal => "normal",
+            Scope::Build => "build",
+            Scope::Dev => "dev",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Dependency scopes of a vulnerable package, as judged by a `fail-on` policy
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Scopes {
+    /// Kinds of dependency the package is reachable through
+    pub reachable: Vec<Scope>,
+
+    /// Scope with the strictest rule the vulnerability fails (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed: Option<Scope>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain, is_compatible, Dependent, Resolution, Upgrade};
+    use crate::{advisory::Versions, Advisory};
+    use semver::Version;
+
+    fn version(version: &str) -> Version {
+        version.parse().unwrap()
+    }
+
+    fn versions() -> Versions {
+        toml::from_str(r#"patched = [">= 1.0.5, < 2.0.0", ">= 2.1.3"]"#).unwrap()
+    }
+
+    #[test]
+    fn compatibility() {
+        assert!(is_compatible(&version("1.0.2"), &version("1.9.0")));
+        assert!(!is_compatible(&version("1.0.2"), &version("2.0.0")));
+        assert!(is_compatible(&version("0.2.1"), &version("0.2.9")));
+        assert!(!is_compatible(&version("0.2.1"), &version("0.3.0")));
+        assert!(!is_compatible(&version("0.0.1"), &version("0.0.2")));
+    }
+
+    #[test]
+    fn upgrade_from_releases() {
+        let releases = [
+            "1.0.2",
+            "1.0.4",
+            "1.0.5",
+            "1.1.0",
+            "2.0.0",
+            "2.1.3",
+            "2.2.0-rc.1",
+        ]
+        .iter()
+        .map(|v| version(v))
+        .collect::<Vec<_>>();
+
+        let upgrade = Upgrade::from_releases(&version("1.0.2"), &versions(), &releases).unwrap();
+        assert_eq!(upgrade.compatible, Some(version("1.0.5")));
+        assert_eq!(upgrade.major, Some(version("2.1.3")));
+        assert_eq!(
+            upgrade.to_string(),
+            "You are on 1.0.2 - upgrade to 1.0.5 (or 2.1.3 if you're ready for a major bump)"
+        );
+
+        let upgrade = Upgrade::from_releases(&version("2.0.0"), &versions(), &releases).unwrap();
+        assert_eq!(upgrade.to_string(), "You are on 2.0.0 - upgrade to 2.1.3");
+
+        assert!(Upgrade::from_releases(&version("1.0.2"), &versions(), &releases[..2]).is_none());
+    }
+
+    #[test]
+    fn upgrade_from_ranges() {
+        let upgrade = Upgrade::from_ranges(&version("1.0.2"), &versions()).unwrap();
+        assert_eq!(
+            upgrade.to_string(),
+            "You are on 1.0.2 - upgrade to at least 1.0.5 \
+             (or at least 2.1.3 if you're ready for a major bump)"
+        );
+
+        let exclusive: Versions = toml::from_str(r#"patched = ["> 1.0.4"]"#).unwrap();
+        let upgrade = Upgrade::from_ranges(&version("0.9.0"), &exclusive).unwrap();
+        assert_eq!(upgrade.major, Some(version("1.0.5")));
+        assert_eq!(
+            upgrade.to_string(),
+            "You are on 0.9.0 - upgrade to at least 1.0.5 (a major bump)"
+        );
+    }
+
+    fn dependent(name: &str, requirement: &str, member: bool) -> Dependent {
+        Dependent {
+            name: name.parse().unwrap(),
+            version: version("0.1.0"),
+            requirement: requirement.parse().unwrap(),
+            member,
+        }
+    }
+
+    #[test]
+    fn resolution() {
+        let upgrade = Upgrade::from_ranges(&version("1.0.2"), &versions()).unwrap();
+
+        let dependents = [dependent("app", "^1.0", true), dependent("lib", "*", false)];
+        assert_eq!(
+            Resolution::new(&upgrade, &dependents),
+            Some(Resolution::Update {
+                version: version("1.0.5")
+            })
+        );
+
+        // Pinned requirements in the workspace need bumping
+        let dependents = [
+            dependent("app", "=1.0.2", true),
+            dependent("lib", "1", false),
+        ];
+        assert_eq!(
+            Resolution::new(&upgrade, &dependents),
+            Some(Resolution::Bump {
+                version: version("1.0.5"),
+                dependents: vec![dependents[0].clone()],
+            })
+        );
+
+        // Dependents outside the workspace can't be bumped
+        let dependents = [
+            dependent("lib", ">=1.0, <1.0.4", false),
+            dependent("other", ">=1", false),
+        ];
+        assert_eq!(
+            Resolution::new(&upgrade, &dependents),
+            Some(Resolution::Blocked {
+                version: version("1.0.5"),
+                dependents: vec![dependents[0].clone()],
+            })
+        );
+
+        // Without a compatible upgrade, the major one is used
+        let upgrade = Upgrade::from_ranges(&version("0.9.0"), &versions()).unwrap();
+        let resolution = Resolution::new(&upgrade, &[dependent("lib", ">=0.9", false)]);
+        assert_eq!(
+            resolution,
+            Some(Resolution::Update {
+                version: version("1.0.5")
+            })
+        );
+
+        let resolution = Resolution::new(&upgrade, &[dependent("app", "0.9", true)]).unwrap();
+        assert_eq!(resolution.version(), &version("1.0.5"));
+        assert!(matches!(resolution, Resolution::Bump { .. }));
+    }
+
+    fn load_advisory(versions: &str) -> Advisory {
+        format!(
+            "```toml\n\
+             [advisory]\n\
+             id = \"RUSTSEC-2021-0001\"\n\
+             package = \"example\"\n\
+             date = \"2021-01-01\"\n\n\
+             [versions]\n\
+             {}\n\
+             ```\n\n\
+             # Example\n\n\
+             Example advisory.\n",
+            versions
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn explain_release() {
+        let advisory = load_advisory("patched = [\">= 0.7.25\"]\nunaffected = [\"< 0.7.0\"]");
+
+        let explanation = explain(&advisory, &version("0.7.25"));
+        assert!(!explanation.vulnerable);
+        assert_eq!(explanation.patched.len(), 1);
+        assert_eq!(explanation.patched[0].range, "[0.7.25, ∞)");
+        assert!(explanation.patched[0].matched);
+        assert!(explanation.patched[0].cargo_matched);
+        assert!(!explanation.unaffected[0].matched);
+        assert!(explanation.affected.iter().all(|range| !range.contains));
+        assert!(explanation.prerelease_rules.is_empty());
+
+        let explanation = explain(&advisory, &version("0.7.24"));
+        assert!(explanation.vulnerable);
+        assert!(!explanation.patched[0].matched);
+        assert_eq!(explanation.affected.len(), 1);
+        assert_eq!(explanation.affected[0].to_string(), "[0.7.0, 0.7.25)");
+        assert!(explanation.affected[0].contains);
+    }
+
+    #[test]
+    fn explain_prerelease() {
+        let advisory = load_advisory("patched = [\">= 0.7.25\"]");
+
+        // Pre-releases come before their release, so aren't patched
+        let explanation = explain(&advisory, &version("0.7.25-rc.1"));
+        assert!(explanation.vulnerable);
+        assert!(!explanation.patched[0].matched);
+        assert!(explanation.affected[0].contains);
+        assert_eq!(explanation.prerelease_rules.len(), 1);
+
+        // Pre-releases of later versions are patched, though cargo's matching
+        // (which doesn't match pre-releases of unnamed versions) disagrees
+        let explanation = explain(&advisory, &version("0.8.0-beta"));
+        assert!(!explanation.vulnerable);
+        assert!(explanation.patched[0].matched);
+        assert!(!explanation.patched[0].cargo_matched);
+
+        let caret = load_advisory("patched = [\"^0.7.25\"]");
+        let explanation = explain(&caret, &version("0.8.0-beta"));
+        assert!(explanation.vulnerable);
+        assert_eq!(explanation.patched[0].range, "[0.7.25, 0.8.0-0)");
+        assert_eq!(explanation.prerelease_rules.len(), 2);
+    }
+}
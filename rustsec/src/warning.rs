@@ -1,9 +1,13 @@
 //! Warnings sourced from the Advisory DB
 
 use crate::error::{Error, ErrorKind};
-use crate::{advisory, package::Package};
+use crate::{
+    advisory,
+    package::{self, Package},
+    Version,
+};
 use serde::{Deserialize, Serialize};
-use std::{fmt, str::FromStr};
+use std::{collections::BTreeSet, fmt, str::FromStr};
 
 /// Warnings sourced from the Advisory DB
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -19,6 +23,37 @@ pub struct Warning {
 
     /// Versions impacted by this warning
     pub versions: Option<advisory::Versions>,
+
+    /// Other versions of the package in the lockfile (for
+    /// `duplicate-versions` warnings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicates: Option<Duplicates>,
+
+    /// Workspace members the package is reachable from (if the dependency
+    /// graph is known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<package::Name>>,
+
+    /// Is the package a direct dependency of a workspace member
+    /// (`false` if it's only a transitive one), if the dependency graph is
+    /// known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direct: Option<bool>,
+
+    /// Date the advisory (if any) was first added to the advisory database's
+    /// git history (`YYYY-MM-DD`, if looked up), or `null` if the database
+    /// has no history to look it up in
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::vulnerability::deserialize_looked_up"
+    )]
+    pub first_published_in_db: Option<Option<String>>,
+
+    /// Advisory database source the advisory (if any) came from, if the
+    /// database was merged from named sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 impl Warning {
@@ -34,6 +69,11 @@ impl Warning {
             package: package.clone(),
             advisory,
             versions,
+            duplicates: None,
+            members: None,
+            direct: None,
+            first_published_in_db: None,
+            source: None,
         }
     }
 
@@ -56,6 +96,61 @@ impl Warning {
     pub fn is_yanked(&self) -> bool {
         self.kind == Kind::Yanked
     }
+
+    /// Is this a warning about a vulnerable crate whose source was overridden?
+    pub fn is_overridden(&self) -> bool {
+        self.kind == Kind::Overridden
+    }
+
+    /// Is this a warning about an older version of a crate which is also in
+    /// the lockfile with a newer version?
+    pub fn is_duplicate_version(&self) -> bool {
+        self.kind == Kind::DuplicateVersions
+    }
+
+    /// Is this a warning about a vulnerable crate which isn't compiled with
+    /// the enabled features?
+    pub fn is_optional(&self) -> bool {
+        self.kind == Kind::Optional
+    }
+
+    /// Is this a warning about a vulnerable crate whose advisory is specific
+    /// to other platforms than the target?
+    pub fn is_other_platform(&self) -> bool {
+        self.kind == Kind::OtherPlatform
+    }
+}
+
+/// Versions of a crate which appears in the lockfile more than once
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Duplicates {
+    /// Every version of the crate in the lockfile (oldest first)
+    pub versions: Vec<Version>,
+
+    /// Direct dependencies of the workspace which pull in the warned (older)
+    /// version, as `name version`
+    #[serde(default)]
+    pub pinned_by: Vec<String>,
+
+    /// Advisories affecting any of the crate's versions in the lockfile
+    #[serde(default)]
+    pub advisories: Vec<advisory::Id>,
+}
+
+/// Number of distinct findings among the given warnings: warnings of the
+/// same kind for the same advisory (e.g. about several versions of an
+/// unmaintained crate) count once, and warnings without an advisory (e.g.
+/// about yanked crates) once each
+pub fn finding_count<'a>(warnings: impl IntoIterator<Item = &'a Warning>) -> usize {
+    let mut advisories = BTreeSet::new();
+
+    warnings
+        .into_iter()
+        .filter(|warning| match &warning.advisory {
+            Some(advisory) => advisories.insert((warning.kind, &advisory.id)),
+            None => true,
+        })
+        .count()
 }
 
 /// Kinds of warnings
@@ -77,6 +172,28 @@ pub enum Kind {
     /// Yanked packages
     #[serde(rename = "yanked")]
     Yanked,
+
+    /// Vulnerable packages whose source was overridden, e.g. with a git fork
+    /// via `[patch]`, which may already contain a fix
+    #[serde(rename = "overridden")]
+    Overridden,
+
+    /// Older versions of crates which are also in the lockfile with a newer
+    /// version
+    #[serde(rename = "duplicate-versions")]
+    DuplicateVersions,
+
+    /// Vulnerable packages which aren't compiled with the project's enabled
+    /// features (e.g. optional dependencies), so the vulnerable code may be
+    /// unreachable
+    #[serde(rename = "optional")]
+    Optional,
+
+    /// Vulnerable packages whose advisory only affects other CPU
+    /// architectures or operating systems than the target, so the vulnerable
+    /// code isn't compiled for it
+    #[serde(rename = "other-platform")]
+    OtherPlatform,
 }
 
 impl Kind {
@@ -87,6 +204,10 @@ impl Kind {
             Self::Unmaintained => "unmaintained",
             Self::Unsound => "unsound",
             Self::Yanked => "yanked",
+            Self::Overridden => "overridden",
+            Self::DuplicateVersions => "duplicate-versions",
+            Self::Optional => "optional",
+            Self::OtherPlatform => "other-platform",
         }
     }
 }
@@ -100,6 +221,10 @@ impl FromStr for Kind {
             "unmaintained" => Kind::Unmaintained,
             "unsound" => Kind::Unsound,
             "yanked" => Kind::Yanked,
+            "overridden" => Kind::Overridden,
+            "duplicate-versions" => Kind::DuplicateVersions,
+            "optional" => Kind::Optional,
+            "other-platform" => Kind::OtherPlatform,
             other => fail!(ErrorKind::Parse, "invalid warning type: {}", other),
         })
     }
@@ -3,7 +3,7 @@
 #![warn(rust_2018_idioms, unused_qualifications)]
 
 use rustsec::advisory::Category;
-use std::path::Path;
+use std::{fs, path::Path};
 
 /// Example RustSec Advisory to use for tests
 const EXAMPLE_V3_ADVISORY_PATH: &str = "./tests/support/example_advisory_v3.md";
@@ -48,6 +48,41 @@ fn parse_metadata() {
     }
 }
 
+/// Advisories can be loaded without their descriptions, which databases
+/// opened with `Database::open_lazy` read from the files when asked for
+#[test]
+fn lazy_description() {
+    for path in &[
+        EXAMPLE_V3_ADVISORY_PATH,
+        "./tests/support/example_advisory_v4.md",
+    ] {
+        let mut advisory = rustsec::Advisory::load_file(Path::new(path)).unwrap();
+        let description = std::mem::take(&mut advisory.metadata.description);
+        assert_eq!(
+            advisory,
+            rustsec::Advisory::load_file_without_description(Path::new(path)).unwrap()
+        );
+
+        let db_dir = tempfile::tempdir().unwrap();
+        let advisory_path = db_dir
+            .path()
+            .join("crates")
+            .join("base")
+            .join("RUSTSEC-2001-2101.md");
+        fs::create_dir_all(advisory_path.parent().unwrap()).unwrap();
+        fs::copy(path, &advisory_path).unwrap();
+
+        let db = rustsec::Database::open_lazy(db_dir.path()).unwrap();
+        let id = advisory.id();
+        assert_eq!(db.get(id).unwrap().description(), "");
+        assert_eq!(db.description(id).unwrap(), description);
+
+        // Descriptions which can't be read are errors, rather than empty
+        fs::remove_file(&advisory_path).unwrap();
+        assert!(db.description(id).is_err());
+    }
+}
+
 /// Parsing of impact metadata
 #[test]
 fn parse_affected() {
@@ -102,3 +137,26 @@ fn parse_patched_version_reqs() {
     assert!(req.matches(&"1.2.3".parse().unwrap()));
     assert!(req.matches(&"1.2.4".parse().unwrap()));
 }
+
+/// Parsing of the commits which fix an advisory
+#[test]
+fn parse_patched_commits() {
+    let versions: rustsec::advisory::Versions = toml::from_str(
+        "patched = [\">= 1.2.3\"]\n\
+         patched_commits = [\"0123abc\", \"0123456789abcdef0123456789abcdef01234567\"]\n",
+    )
+    .unwrap();
+    assert_eq!(versions.patched_commits().len(), 2);
+
+    // Advisories without them have none
+    assert!(load_example_v3_advisory()
+        .versions
+        .patched_commits()
+        .is_empty());
+
+    // Commits must be (possibly abbreviated) hashes
+    assert!(toml::from_str::<rustsec::advisory::Versions>(
+        "patched = []\npatched_commits = [\"main\"]\n"
+    )
+    .is_err());
+}
@@ -0,0 +1,170 @@
+//! Tests for fetching tarball snapshots with a pluggable transport
+
+#![cfg(feature = "async")]
+
+use rustsec::{
+    repository::{
+        tarball::{Snapshot, ETAG_FILE},
+        transport::{Request, Response, ResponseFuture, Transport},
+    },
+    Database, Error,
+};
+use std::{
+    fs,
+    future::Future,
+    sync::Mutex,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+/// Transport which serves a single archive, and records its requests
+struct MockTransport {
+    /// Archive which is served
+    archive: Vec<u8>,
+
+    /// `ETag` of the archive
+    etag: &'static str,
+
+    /// Requests which were sent
+    requests: Mutex<Vec<Request>>,
+}
+
+impl Transport for MockTransport {
+    fn get<'a>(&'a self, request: &'a Request) -> ResponseFuture<'a> {
+        Box::pin(async move {
+            self.requests.lock().unwrap().push(request.clone());
+
+            if request.if_none_match.as_deref() == Some(self.etag) {
+                return Ok(Response {
+                    status: 304,
+                    ..Response::default()
+                });
+            }
+
+            Ok::<_, Error>(Response {
+                status: 200,
+                etag: Some(self.etag.to_owned()),
+                body: self.archive.clone(),
+            })
+        })
+    }
+}
+
+/// Run a future which never waits to completion
+fn block_on<F: Future>(future: F) -> F::Output {
+    fn raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+
+        fn noop(_: *const ()) {}
+
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+
+    let waker = unsafe { Waker::from_raw(raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// Uncompressed tar archive of an advisory database, laid out like GitHub's
+/// archives of a branch
+fn archive() -> Vec<u8> {
+    let mut tar = vec![];
+    let advisory = fs::read("tests/support/example_advisory_v3.md").unwrap();
+
+    for (path, data) in &[
+        (
+            "advisory-db-main/crates/base/RUSTSEC-2001-2101.md",
+            &advisory,
+        ),
+        ("advisory-db-main/README.md", &b"# Advisories\n".to_vec()),
+    ] {
+        let mut header = vec![0u8; 512];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[124..136].copy_from_slice(format!("{:011o}\0", data.len()).as_bytes());
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+
+        tar.extend(header);
+        tar.extend(data.iter());
+        tar.extend(vec![0; (512 - data.len() % 512) % 512]);
+    }
+
+    tar.extend(vec![0; 1024]);
+    tar
+}
+
+#[test]
+fn fetch_snapshot_async() {
+    let transport = MockTransport {
+        archive: archive(),
+        etag: "\"abc123\"",
+        requests: Mutex::new(vec![]),
+    };
+
+    let url = "https://example.com/advisory-db.tar.gz";
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot");
+
+    let snapshot = block_on(Snapshot::fetch_async(&transport, url, &path)).unwrap();
+    assert!(snapshot.was_updated());
+    assert_eq!(snapshot.etag(), Some("\"abc123\""));
+    assert_eq!(
+        fs::read_to_string(path.join(ETAG_FILE)).unwrap(),
+        "\"abc123\""
+    );
+
+    let database = Database::open(snapshot.path()).unwrap();
+    assert!(database
+        .get(&"RUSTSEC-2001-2101".parse().unwrap())
+        .is_some());
+
+    // The snapshot is up to date, so it's kept as it is
+    let snapshot = block_on(Snapshot::fetch_async(&transport, url, &path)).unwrap();
+    assert!(!snapshot.was_updated());
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].url, url);
+    assert_eq!(requests[0].if_none_match, None);
+    assert_eq!(requests[1].if_none_match.as_deref(), Some("\"abc123\""));
+}
+
+#[test]
+fn fetch_snapshot_async_errors() {
+    let transport = MockTransport {
+        archive: vec![0x1f, 0x8b, 0, 0],
+        etag: "\"abc123\"",
+        requests: Mutex::new(vec![]),
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot");
+
+    // Only HTTPS URLs are fetched
+    let result = block_on(Snapshot::fetch_async(
+        &transport,
+        "http://example.com/advisory-db.tar.gz",
+        &path,
+    ));
+    assert!(result.is_err());
+    assert!(transport.requests.lock().unwrap().is_empty());
+
+    // Archives which can't be unpacked leave nothing behind
+    let result = block_on(Snapshot::fetch_async(
+        &transport,
+        "https://example.com/advisory-db.tar.gz",
+        &path,
+    ));
+    assert!(result.is_err());
+    assert!(!path.exists());
+    assert!(!dir.path().join("snapshot.partial").exists());
+}
@@ -0,0 +1,103 @@
+//! Tests for finding the smallest version bumps which fix vulnerabilities
+
+#![cfg(feature = "fix")]
+#![warn(rust_2018_idioms, unused_qualifications)]
+
+use rustsec::{fixer::Fixer, lockfile::Lockfile, Advisory, Vulnerability};
+use std::fs;
+
+/// Advisory for `base`, patched in 1.2.3 and 2.1.0
+const ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2001-2101"
+package = "base"
+date = "2001-02-03"
+
+[versions]
+patched = ["~1.2.3", ">= 2.1.0"]
+```
+
+# All your base are belong to us
+
+You have no chance to survive. Make your time.
+"#;
+
+/// Vulnerability in the given version of `base`
+fn vulnerability(version: &str) -> Vulnerability {
+    let lockfile: Lockfile = format!("[[package]]\nname = \"base\"\nversion = \"{}\"\n", version)
+        .parse()
+        .unwrap();
+
+    Vulnerability::new(
+        &ADVISORY.parse::<Advisory>().unwrap(),
+        &lockfile.packages[0],
+    )
+}
+
+/// Fixer for a manifest depending on `base` with the given requirement
+fn fixer_for(dependency: &str) -> (tempfile::TempDir, Fixer) {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = dir.path().join("Cargo.toml");
+
+    fs::write(
+        &manifest_path,
+        format!(
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\n{}\n",
+            dependency
+        ),
+    )
+    .unwrap();
+
+    let fixer = Fixer::new(&manifest_path).unwrap();
+    (dir, fixer)
+}
+
+/// Semver-compatible fixes the requirement allows only update `Cargo.lock`
+#[test]
+fn compatible_fix() {
+    let (_dir, fixer) = fixer_for("base = \"1.2\"");
+    let fix = fixer.plan(&vulnerability("1.2.0")).unwrap();
+
+    assert_eq!(fix.to.to_string(), "1.2.3");
+    assert!(fix.is_compatible());
+    assert!(fix.is_lockfile_only());
+}
+
+/// Requirements which don't allow the fix are raised to it
+#[test]
+fn raised_requirement() {
+    let (dir, mut fixer) = fixer_for("base = { version = \"=1.2.0\", features = [\"all\"] }");
+    let fix = fixer.fix(&vulnerability("1.2.0"), false).unwrap();
+
+    assert_eq!(fix.to.to_string(), "1.2.3");
+    assert_eq!(fix.requirement.unwrap().to_string(), "^1.2.3");
+
+    let manifest = fs::read_to_string(dir.path().join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("version = \"1.2.3\""));
+    assert!(manifest.contains("features = [\"all\"]"));
+
+    // Renamed dependencies are found by their package name
+    let (_dir, fixer) = fixer_for("my-base = { version = \"=2.0.5\", package = \"base\" }");
+    let fix = fixer.plan(&vulnerability("2.0.5")).unwrap();
+    assert_eq!(fix.to.to_string(), "2.1.0");
+    assert!(fix.is_compatible());
+    assert!(!fix.is_lockfile_only());
+}
+
+/// Without a compatible fix, the nearest major bump is required (which can
+/// only be made for direct dependencies), and requirements narrower than
+/// semver compatibility are raised too
+#[test]
+fn major_fix() {
+    let (_dir, fixer) = fixer_for("base = \"~1.1\"");
+    let fix = fixer.plan(&vulnerability("1.1.0")).unwrap();
+    assert_eq!(fix.to.to_string(), "1.2.3");
+    assert_eq!(fix.requirement.unwrap().to_string(), "^1.2.3");
+
+    let (_dir, fixer) = fixer_for("other = \"1\"");
+    assert!(fixer.plan(&vulnerability("0.9.0")).is_err());
+    assert!(fixer
+        .plan(&vulnerability("1.1.0"))
+        .unwrap()
+        .is_lockfile_only());
+}
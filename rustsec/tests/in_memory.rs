@@ -0,0 +1,89 @@
+//! Tests for audits without filesystem or network I/O
+
+use rustsec::{lockfile::Lockfile, report, Advisory, Collection, Database, Report};
+
+/// Advisory for versions of the given package prior to 0.2.0
+fn advisory(id: &str, package: &str) -> String {
+    format!(
+        "```toml\n\
+         [advisory]\n\
+         id = \"{}\"\n\
+         package = \"{}\"\n\
+         date = \"2020-01-01\"\n\n\
+         [versions]\n\
+         patched = [\">= 0.2.0\"]\n\
+         ```\n\n\
+         # Test advisory\n\n\
+         Test advisory.\n",
+        id, package
+    )
+}
+
+/// Lockfile with `foo` and `bar` 0.1.0
+const LOCKFILE: &str = "\
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Advisories can be given as strings or bytes, and lockfiles as strings
+#[test]
+fn audit_in_memory() {
+    let advisories = vec![
+        advisory("RUSTSEC-2020-0001", "foo").into_bytes(),
+        advisory("RUSTSEC-2020-0002", "baz").into_bytes(),
+        advisory("RUSTSEC-0000-0000", "foo").into_bytes(),
+    ];
+
+    let database = Database::from_markdown(&advisories).unwrap();
+    assert_eq!(database.iter().count(), 2);
+
+    let id = "RUSTSEC-2020-0001".parse().unwrap();
+    let advisory = database.get(&id).unwrap();
+    assert_eq!(advisory.metadata.collection, Some(Collection::Crates));
+    assert_eq!(advisory.title(), "Test advisory");
+
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let report = Report::generate(&database, &lockfile, &report::Settings::default());
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert_eq!(report.vulnerabilities.list[0].advisory.id, id);
+    assert_eq!(report.database.advisory_count, 2);
+    assert_eq!(report.summary.unscored, 1);
+    assert!(report.summary.severities.is_empty());
+    assert_eq!(report.summary.ignored, 0);
+}
+
+/// Duplicate advisory IDs and invalid advisories are errors
+#[test]
+fn invalid_advisories() {
+    let error = Database::from_markdown(&[
+        advisory("RUSTSEC-2020-0001", "foo"),
+        advisory("RUSTSEC-2020-0001", "bar"),
+    ])
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("duplicate advisory ID"),
+        "{}",
+        error
+    );
+
+    let error = Database::from_markdown(&[&b"not an advisory"[..], &[0xff][..]]).unwrap_err();
+    assert!(
+        error.to_string().contains("error parsing advisory #1"),
+        "{}",
+        error
+    );
+
+    let advisories: Vec<Advisory> = ["RUSTSEC-2020-0001", "RUSTSEC-2020-0002"]
+        .iter()
+        .map(|id| advisory(id, "foo").parse().unwrap())
+        .collect();
+    let database = Database::from_advisories(advisories).unwrap();
+    assert_eq!(database.query_by_crate(&"foo".parse().unwrap()).len(), 2);
+}
@@ -99,3 +99,32 @@ fn invalid_example() {
     let invalid_section = lint.errors()[6].to_string();
     assert_eq!(invalid_section, "invalid key `invalid-section` in toplevel");
 }
+
+/// Advisory without a description, patched in every version
+const INCOMPLETE_ADVISORY_MD: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2001-0001"
+package = "base"
+date = "2001-02-03"
+
+[versions]
+patched = ["*"]
+```
+
+# All your base are belong to us
+"#;
+
+/// Required content is missing, and requirements match every version
+#[test]
+fn incomplete_example() {
+    let lint = rustsec::advisory::Linter::lint_string(INCOMPLETE_ADVISORY_MD).unwrap();
+    let errors: Vec<_> = lint.errors().iter().map(ToString::to_string).collect();
+
+    assert_eq!(
+        errors,
+        [
+            "malformed content in [advisory]: missing description",
+            "invalid value `*` for key `patched` in [versions]: requirement matches every version",
+        ]
+    );
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "osv-I-know-this-is-unstable")]
+
+use rustsec::{osv::OsvAdvisory, Advisory};
+
+fn load_example_advisory() -> Advisory {
+    Advisory::load_file("tests/support/example_advisory_v3.md").unwrap()
+}
+
+#[test]
+fn export_advisory() {
+    let advisory = load_example_advisory();
+    let osv = serde_json::to_value(advisory.to_osv()).unwrap();
+
+    assert_eq!(osv["schema_version"], rustsec::osv::SCHEMA_VERSION);
+    assert_eq!(osv["id"], "RUSTSEC-2001-2101");
+    assert_eq!(osv["published"], "2001-02-03T12:00:00+00:00");
+    assert_eq!(osv["modified"], osv["published"]);
+    assert_eq!(osv["aliases"][0], "CVE-2001-2101");
+    assert_eq!(osv["summary"], "All your base are belong to us");
+    assert_eq!(osv["severity"][0]["type"], "CVSS_V3");
+
+    let affected = &osv["affected"][0];
+    assert_eq!(affected["package"]["ecosystem"], "crates.io");
+    assert_eq!(affected["package"]["name"], "base");
+    assert_eq!(affected["package"]["purl"], "pkg:cargo/base");
+    assert_eq!(
+        affected["ranges"][0]["events"],
+        serde_json::json!([{ "introduced": "0" }, { "fixed": "0.1.2" }])
+    );
+    assert_eq!(
+        affected["ranges"][1]["events"],
+        serde_json::json!([{ "introduced": "0.2.0-0" }, { "fixed": "1.2.3" }])
+    );
+    assert_eq!(
+        affected["ecosystem_specific"]["affects"]["functions"][0],
+        "base::belongs::All"
+    );
+}
+
+#[test]
+fn import_advisory() {
+    let advisory = load_example_advisory();
+    let json = serde_json::to_string(&advisory.to_osv()).unwrap();
+    let osv: OsvAdvisory = serde_json::from_str(&json).unwrap();
+    let imported = Advisory::from_osv(&osv).unwrap();
+
+    assert_eq!(imported.metadata.id, advisory.metadata.id);
+    assert_eq!(imported.metadata.package, advisory.metadata.package);
+    assert_eq!(imported.metadata.date, advisory.metadata.date);
+    assert_eq!(imported.metadata.aliases, advisory.metadata.aliases);
+    assert_eq!(imported.metadata.categories, advisory.metadata.categories);
+    assert_eq!(imported.metadata.cvss, advisory.metadata.cvss);
+    assert_eq!(imported.metadata.url, advisory.metadata.url);
+    assert!(imported.metadata.references.is_empty());
+    assert_eq!(imported.title(), advisory.title());
+
+    for version in &[
+        "0.1.1", "0.1.2", "0.1.9", "0.2.0", "1.2.2", "1.2.3", "2.0.0",
+    ] {
+        let version = version.parse().unwrap();
+        assert_eq!(
+            imported.versions.is_vulnerable(&version),
+            advisory.versions.is_vulnerable(&version),
+            "{}",
+            version
+        );
+    }
+
+    let affected = imported.affected.unwrap();
+    assert_eq!(affected.arch, advisory.affected.as_ref().unwrap().arch);
+    assert_eq!(affected.functions.len(), 1);
+}
+
+/// Advisories from other databases use events such as `last_affected`, and
+/// may have ranges for other ecosystems
+#[test]
+fn import_other_advisory() {
+    let osv: OsvAdvisory = serde_json::from_str(
+        r#"{
+            "id": "GHSA-aaaa-bbbb-cccc",
+            "modified": "2023-04-05T06:07:08Z",
+            "published": "2023-01-02T03:04:05Z",
+            "summary": "Something bad",
+            "affected": [
+                { "package": { "ecosystem": "npm", "name": "foo" } },
+                {
+                    "package": { "ecosystem": "crates.io", "name": "foo" },
+                    "ranges": [{
+                        "type": "ECOSYSTEM",
+                        "events": [{ "introduced": "1.0.0" }, { "last_affected": "1.4.0" }]
+                    }]
+                }
+            ],
+            "references": [{ "type": "ADVISORY", "url": "https://example.com/advisory" }],
+            "database_specific": { "github_reviewed": true }
+        }"#,
+    )
+    .unwrap();
+
+    let advisory = Advisory::from_osv(&osv).unwrap();
+    assert_eq!(advisory.id().as_str(), "GHSA-aaaa-bbbb-cccc");
+    assert_eq!(advisory.date().as_str(), "2023-01-02");
+    assert_eq!(
+        advisory.metadata.url.unwrap().as_str(),
+        "https://example.com/advisory"
+    );
+
+    for (version, vulnerable) in &[
+        ("0.9.0", false),
+        ("1.0.0", true),
+        ("1.4.0", true),
+        ("1.4.1", false),
+    ] {
+        assert_eq!(
+            advisory.versions.is_vulnerable(&version.parse().unwrap()),
+            *vulnerable,
+            "{}",
+            version
+        );
+    }
+
+    let osv: OsvAdvisory = serde_json::from_str(
+        r#"{ "id": "GHSA-aaaa-bbbb-cccc", "modified": "2023-04-05T06:07:08Z" }"#,
+    )
+    .unwrap();
+    assert!(Advisory::from_osv(&osv).is_err());
+}
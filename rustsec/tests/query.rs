@@ -2,7 +2,12 @@
 
 #![warn(rust_2018_idioms, unused_qualifications)]
 
-use rustsec::{advisory::Severity, database::Query, package};
+use rustsec::{
+    advisory::{Category, Severity},
+    database::Query,
+    package, Database,
+};
+use std::fs;
 
 /// Load example advisory from the filesystem
 fn load_advisory() -> rustsec::Advisory {
@@ -40,3 +45,232 @@ fn matches_severity() {
     let query_matches = Query::new().severity(Severity::Critical);
     assert!(query_matches.matches(&advisory));
 }
+
+#[test]
+fn matches_category() {
+    let advisory = load_advisory();
+
+    let query_matches = Query::new()
+        .category(Category::MemoryCorruption)
+        .category(Category::CodeExecution);
+    assert!(query_matches.matches(&advisory));
+
+    let query_nomatch = Query::new().category(Category::CryptoFailure);
+    assert!(!query_nomatch.matches(&advisory));
+}
+
+#[test]
+fn matches_keyword() {
+    let advisory = load_advisory();
+
+    let query_matches = Query::new().keyword("Gentlemen");
+    assert!(query_matches.matches(&advisory));
+
+    let query_nomatch = Query::new().keyword("ladies");
+    assert!(!query_nomatch.matches(&advisory));
+
+    // Both categories and keywords have to match
+    let query_nomatch = Query::new()
+        .category(Category::CodeExecution)
+        .keyword("ladies");
+    assert!(!query_nomatch.matches(&advisory));
+}
+
+#[test]
+fn matches_text() {
+    let advisory = load_advisory();
+
+    assert!(Query::new().text("BASE ARE").matches(&advisory));
+    assert!(Query::new().text("cve-2001").matches(&advisory));
+    assert!(Query::new()
+        .text("survive")
+        .text("gentlemen")
+        .matches(&advisory));
+
+    // Every text has to match
+    assert!(!Query::new()
+        .text("survive")
+        .text("ladies")
+        .matches(&advisory));
+}
+
+#[test]
+fn matches_date() {
+    let advisory = load_advisory();
+    let date = |date: &str| date.parse().unwrap();
+
+    assert!(Query::new().since(date("2001-02-03")).matches(&advisory));
+    assert!(Query::new()
+        .since(date("2001-01-01"))
+        .until(date("2001-02-03"))
+        .matches(&advisory));
+    assert!(!Query::new().since(date("2001-02-04")).matches(&advisory));
+    assert!(!Query::new().until(date("2001-02-02")).matches(&advisory));
+}
+
+/// Looking advisories up by keyword, crate and ID (or alias)
+#[test]
+fn query_database_by_keyword_crate_and_id() {
+    let db_dir = tempfile::tempdir().unwrap();
+
+    for (id, package, title, aliases) in &[
+        (
+            "RUSTSEC-2020-0001",
+            "foo",
+            "Use after free",
+            r#"["CVE-2020-1111"]"#,
+        ),
+        (
+            "RUSTSEC-2020-0002",
+            "bar",
+            "Data race",
+            r#"["CVE-2020-2222"]"#,
+        ),
+        (
+            "RUSTSEC-2020-0003",
+            "foo",
+            "Data race",
+            r#"["GHSA-jfh8-c2jp-5v3q", "CVE-2020-2222"]"#,
+        ),
+    ] {
+        let package_dir = db_dir.path().join("crates").join(package);
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(
+            package_dir.join(format!("{}.md", id)),
+            format!(
+                "```toml\n\
+                 [advisory]\n\
+                 id = \"{}\"\n\
+                 package = \"{}\"\n\
+                 date = \"2020-01-01\"\n\
+                 aliases = {}\n\n\
+                 [versions]\n\
+                 patched = [\">= 1.0.0\"]\n\
+                 ```\n\n\
+                 # {}\n\n\
+                 Test advisory.\n",
+                id, package, aliases, title
+            ),
+        )
+        .unwrap();
+    }
+
+    let db = Database::open(db_dir.path()).unwrap();
+    let ids = |advisories: Vec<&rustsec::Advisory>| {
+        let mut ids: Vec<_> = advisories
+            .iter()
+            .map(|advisory| advisory.id().as_str().to_owned())
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(
+        ids(db.query_by_keyword("data RACE")),
+        ["RUSTSEC-2020-0002", "RUSTSEC-2020-0003"]
+    );
+    assert_eq!(
+        ids(db.query_by_crate(&"foo".parse().unwrap())),
+        ["RUSTSEC-2020-0001", "RUSTSEC-2020-0003"]
+    );
+    assert_eq!(
+        ids(db.query_by_id("RUSTSEC-2020-0002")),
+        ["RUSTSEC-2020-0002"]
+    );
+    assert_eq!(ids(db.query_by_id("cve-2020-1111")), ["RUSTSEC-2020-0001"]);
+    assert_eq!(
+        ids(db.query_by_id("rustsec-2020-0001")),
+        ["RUSTSEC-2020-0001"]
+    );
+    assert!(db.query_by_id("RUSTSEC-2020-9999").is_empty());
+
+    // Aliases resolve to every advisory listing them
+    assert_eq!(
+        ids(db.query_by_alias(&"CVE-2020-2222".parse().unwrap())),
+        ["RUSTSEC-2020-0002", "RUSTSEC-2020-0003"]
+    );
+    assert_eq!(
+        ids(db.query_by_alias(&"ghsa-JFH8-c2jp-5v3q".parse().unwrap())),
+        ["RUSTSEC-2020-0003"]
+    );
+    assert!(db
+        .query_by_alias(&"RUSTSEC-2020-0001".parse().unwrap())
+        .is_empty());
+}
+
+/// Category and keyword queries against a database (which uses its indexes
+/// for them)
+#[test]
+fn query_database_by_category_and_keyword() {
+    let db_dir = tempfile::tempdir().unwrap();
+
+    for (id, categories, keywords) in &[
+        (
+            "RUSTSEC-2020-0001",
+            r#"["memory-corruption"]"#,
+            r#"["ffi"]"#,
+        ),
+        ("RUSTSEC-2020-0002", r#"["crypto-failure"]"#, r#"["tls"]"#),
+        ("RUSTSEC-2020-0003", "[]", r#"["FFI"]"#),
+        ("RUSTSEC-2020-0004", r#"["memory-corruption"]"#, "[]"),
+    ] {
+        let package_dir = db_dir.path().join("crates").join("foo");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(
+            package_dir.join(format!("{}.md", id)),
+            format!(
+                "```toml\n\
+                 [advisory]\n\
+                 id = \"{}\"\n\
+                 package = \"foo\"\n\
+                 date = \"2020-01-01\"\n\
+                 categories = {}\n\
+                 keywords = {}\n\n\
+                 [versions]\n\
+                 patched = [\">= 1.0.0\"]\n\
+                 ```\n\n\
+                 # Test advisory\n\n\
+                 Test advisory.\n",
+                id, categories, keywords
+            ),
+        )
+        .unwrap();
+    }
+
+    let db = Database::open(db_dir.path()).unwrap();
+    let ids = |query: Query| {
+        let mut ids: Vec<_> = db
+            .query(&query)
+            .iter()
+            .map(|advisory| advisory.id().as_str().to_owned())
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(
+        ids(Query::new().category(Category::MemoryCorruption)),
+        ["RUSTSEC-2020-0001", "RUSTSEC-2020-0004"]
+    );
+    assert_eq!(
+        ids(Query::new()
+            .category(Category::MemoryCorruption)
+            .category(Category::CryptoFailure)),
+        [
+            "RUSTSEC-2020-0001",
+            "RUSTSEC-2020-0002",
+            "RUSTSEC-2020-0004"
+        ]
+    );
+    assert_eq!(
+        ids(Query::new().keyword("ffi")),
+        ["RUSTSEC-2020-0001", "RUSTSEC-2020-0003"]
+    );
+    assert_eq!(
+        ids(Query::new()
+            .category(Category::MemoryCorruption)
+            .keyword("ffi")),
+        ["RUSTSEC-2020-0001"]
+    );
+    assert!(ids(Query::new().category(Category::Other("unknown".to_owned()))).is_empty());
+}
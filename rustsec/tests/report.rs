@@ -0,0 +1,369 @@
+//! Report generation tests
+
+use rustsec::{advisory, lockfile::Lockfile, report, warning, Database, Report};
+use std::fs;
+
+/// Advisories as (ID, package, informational) triples, written to the
+/// database out of order
+const ADVISORIES: &[(&str, &str, Option<&str>)] = &[
+    ("RUSTSEC-2020-0003", "foo", None),
+    ("RUSTSEC-2020-0001", "bar", None),
+    ("RUSTSEC-2020-0002", "foo", None),
+    ("RUSTSEC-2020-0005", "qux", Some("unmaintained")),
+    ("RUSTSEC-2020-0004", "baz", Some("unmaintained")),
+    ("RUSTSEC-2020-0006", "quux", None),
+];
+
+/// Lockfile with packages out of order
+const LOCKFILE: &str = "\
+[[package]]
+name = \"qux\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"baz\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+";
+
+/// Write the advisories to a database in a temporary directory
+fn database() -> Database {
+    let db_dir = tempfile::tempdir().unwrap();
+
+    for (id, package, informational) in ADVISORIES {
+        let package_dir = db_dir.path().join("crates").join(package);
+        fs::create_dir_all(&package_dir).unwrap();
+
+        let informational = informational
+            .map(|kind| format!("informational = \"{}\"\n", kind))
+            .unwrap_or_default();
+
+        fs::write(
+            package_dir.join(format!("{}.md", id)),
+            format!(
+                "```toml\n\
+                 [advisory]\n\
+                 id = \"{}\"\n\
+                 package = \"{}\"\n\
+                 date = \"2020-01-01\"\n\
+                 {}\n\
+                 [versions]\n\
+                 patched = [\">= 1.0.0\"]\n\
+                 ```\n\n\
+                 # Test advisory\n\n\
+                 Test advisory.\n",
+                id, package, informational
+            ),
+        )
+        .unwrap();
+    }
+
+    Database::open(db_dir.path()).unwrap()
+}
+
+/// Report settings with warnings for unmaintained crates
+fn settings() -> report::Settings {
+    report::Settings {
+        informational_warnings: vec![advisory::Informational::Unmaintained],
+        ..Default::default()
+    }
+}
+
+/// Reports generated from the same inputs serialize identically, with
+/// vulnerabilities and warnings in a stable order
+#[test]
+fn deterministic_order() {
+    let db = database();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let settings = settings();
+
+    let report = Report::generate(&db, &lockfile, &settings);
+    let json = serde_json::to_string(&report).unwrap();
+
+    for _ in 0..10 {
+        let regenerated = Report::generate(&db, &lockfile, &settings);
+        assert_eq!(json, serde_json::to_string(&regenerated).unwrap());
+    }
+
+    let vulnerabilities: Vec<_> = report
+        .vulnerabilities
+        .list
+        .iter()
+        .map(|vuln| (vuln.advisory.id.as_str(), vuln.package.name.as_str()))
+        .collect();
+
+    assert_eq!(
+        vulnerabilities,
+        [
+            ("RUSTSEC-2020-0001", "bar"),
+            ("RUSTSEC-2020-0002", "foo"),
+            ("RUSTSEC-2020-0003", "foo"),
+        ]
+    );
+
+    let warnings: Vec<_> = report
+        .warnings
+        .values()
+        .flatten()
+        .map(|warning| warning.package.name.as_str())
+        .collect();
+
+    assert_eq!(warnings, ["baz", "qux"]);
+}
+
+/// Findings are matched by advisory and package across reports, regardless
+/// of the package's version
+#[test]
+fn diff_reports() {
+    let db = database();
+    let settings = settings();
+    let from = Report::generate(&db, &LOCKFILE.parse().unwrap(), &settings);
+
+    // Drop `bar` and `baz`, upgrade `foo` (which is still vulnerable) and add
+    // vulnerable `quux`
+    let lockfile: Lockfile = "\
+[[package]]
+name = \"foo\"
+version = \"0.2.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"quux\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"qux\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+"
+    .parse()
+    .unwrap();
+
+    let to = Report::generate(&db, &lockfile, &settings);
+    let diff = report::Diff::new(&from, &to);
+
+    let ids = |findings: &report::Findings| {
+        findings
+            .vulnerabilities
+            .iter()
+            .map(|vuln| vuln.advisory.id.as_str().to_owned())
+            .chain(
+                findings
+                    .warnings
+                    .iter()
+                    .map(|warning| warning.advisory.as_ref().unwrap().id.as_str().to_owned()),
+            )
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(ids(&diff.introduced), ["RUSTSEC-2020-0006"]);
+    assert_eq!(
+        ids(&diff.resolved),
+        ["RUSTSEC-2020-0001", "RUSTSEC-2020-0004"]
+    );
+    assert_eq!(
+        ids(&diff.unchanged),
+        [
+            "RUSTSEC-2020-0002",
+            "RUSTSEC-2020-0003",
+            "RUSTSEC-2020-0005"
+        ]
+    );
+    assert_eq!(
+        diff.unchanged.vulnerabilities[0]
+            .package
+            .version
+            .to_string(),
+        "0.2.0"
+    );
+}
+
+/// Vulnerable packages from git (e.g. `[patch]` forks) are reported as
+/// `overridden` warnings, unless configured otherwise
+#[test]
+fn overridden_packages() {
+    let db = database();
+    let lockfile: Lockfile = "\
+[[package]]
+name = \"bar\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+
+[[package]]
+name = \"quux\"
+version = \"0.1.0\"
+source = \"git+https://github.com/example/quux.git?branch=fix#0123456789abcdef0123456789abcdef01234567\"
+"
+    .parse()
+    .unwrap();
+
+    let report = Report::generate(&db, &lockfile, &settings());
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert_eq!(report.vulnerabilities.list[0].package.name.as_str(), "bar");
+
+    let overridden = &report.warnings[&warning::Kind::Overridden];
+    assert_eq!(overridden.len(), 1);
+    assert_eq!(
+        overridden[0].advisory.as_ref().unwrap().id.as_str(),
+        "RUSTSEC-2020-0006"
+    );
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json["warnings"]["overridden"][0]["package"]["source"],
+        "git+https://github.com/example/quux.git?branch=fix#0123456789abcdef0123456789abcdef01234567"
+    );
+
+    let mut settings = settings();
+    settings.overridden = report::OverriddenAction::Fail;
+    let report = Report::generate(&db, &lockfile, &settings);
+    assert_eq!(report.vulnerabilities.count, 2);
+    assert!(!report.warnings.contains_key(&warning::Kind::Overridden));
+
+    settings.overridden = report::OverriddenAction::Ignore;
+    let report = Report::generate(&db, &lockfile, &settings);
+    assert_eq!(report.vulnerabilities.count, 1);
+    assert!(!report.warnings.contains_key(&warning::Kind::Overridden));
+}
+
+/// Report sets aggregate statistics across reports, and store each
+/// advisory's description once when serialized
+#[test]
+fn report_sets() {
+    let db = database();
+    let settings = settings();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let foo_lockfile: Lockfile = "\
+[[package]]
+name = \"foo\"
+version = \"0.1.0\"
+source = \"registry+https://github.com/rust-lang/crates.io-index\"
+"
+    .parse()
+    .unwrap();
+
+    let mut set = report::ReportSet::new();
+    set.insert("a/Cargo.lock", Report::generate(&db, &lockfile, &settings))
+        .unwrap();
+    set.insert(
+        "b/Cargo.lock",
+        Report::generate(&db, &foo_lockfile, &settings),
+    )
+    .unwrap();
+
+    // Identifiers must be unique
+    assert!(set
+        .insert(
+            "b/Cargo.lock",
+            Report::generate(&db, &foo_lockfile, &settings)
+        )
+        .is_err());
+
+    let statistics = set.statistics();
+    assert_eq!(statistics.advisories, 5);
+    assert_eq!(statistics.vulnerabilities, 3);
+    assert_eq!(statistics.vulnerable_reports, 2);
+    assert_eq!(statistics.reports["a/Cargo.lock"].vulnerabilities, 3);
+    assert_eq!(statistics.reports["a/Cargo.lock"].warnings, 2);
+    assert_eq!(statistics.reports["b/Cargo.lock"].advisories, 2);
+
+    let json = serde_json::to_string(&set).unwrap();
+    assert_eq!(json.matches("Test advisory.").count(), 5);
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["statistics"]["vulnerable-reports"], 2);
+    assert_eq!(
+        value["reports"]["b/Cargo.lock"]["vulnerabilities"]["list"][0]["advisory"]["description"],
+        ""
+    );
+
+    // Descriptions are restored when deserializing
+    let mut set: report::ReportSet = serde_json::from_str(&json).unwrap();
+    let report = set.get("b/Cargo.lock").unwrap();
+    assert_eq!(
+        report.vulnerabilities.list[0].advisory.description.trim(),
+        "Test advisory."
+    );
+
+    // Reports generated against different database commits can't be mixed
+    let mut report = Report::generate(&db, &foo_lockfile, &settings);
+    report.database.last_commit = Some("0123456789abcdef".to_owned());
+    assert!(set.insert("c/Cargo.lock", report).is_err());
+}
+
+/// Reports carry the version of the published schema, and have the fields it
+/// requires
+#[test]
+fn schema_version() {
+    let db = database();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+    let report = Report::generate(&db, &lockfile, &settings());
+    assert_eq!(report.schema_version, report::SCHEMA_VERSION);
+
+    let json = serde_json::to_value(&report).unwrap();
+    let schema: serde_json::Value = serde_json::from_str(report::JSON_SCHEMA).unwrap();
+    assert_eq!(
+        json["schema_version"],
+        schema["properties"]["schema_version"]["const"]
+    );
+
+    for field in schema["required"].as_array().unwrap() {
+        assert!(json.get(field.as_str().unwrap()).is_some(), "{}", field);
+    }
+
+    // Reports which predate schema versions still deserialize
+    let mut json = json;
+    json.as_object_mut().unwrap().remove("schema_version");
+    let report: Report = serde_json::from_value(json).unwrap();
+    assert_eq!(report.schema_version, 0);
+}
+
+/// Withdrawn advisories are only matched when asked for, and keep the
+/// advisory superseding them
+#[test]
+fn withdrawn_advisories() {
+    let advisory = "```toml\n\
+                    [advisory]\n\
+                    id = \"RUSTSEC-2020-0007\"\n\
+                    package = \"foo\"\n\
+                    date = \"2020-01-01\"\n\
+                    withdrawn = \"2020-02-01\"\n\
+                    superseded_by = \"RUSTSEC-2020-0003\"\n\n\
+                    [versions]\n\
+                    patched = [\">= 1.0.0\"]\n\
+                    ```\n\n\
+                    # Test advisory\n\n\
+                    Test advisory.\n";
+
+    let database = Database::from_markdown(&[advisory]).unwrap();
+    let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+
+    let report = Report::generate(&database, &lockfile, &settings());
+    assert!(!report.vulnerabilities.found);
+
+    let settings = report::Settings {
+        include_withdrawn: true,
+        ..settings()
+    };
+    let report = Report::generate(&database, &lockfile, &settings);
+    assert_eq!(report.vulnerabilities.count, 1);
+
+    let advisory = &report.vulnerabilities.list[0].advisory;
+    assert!(advisory.withdrawn.is_some());
+    assert_eq!(
+        advisory.superseded_by.as_ref().map(|id| id.as_str()),
+        Some("RUSTSEC-2020-0003")
+    );
+}
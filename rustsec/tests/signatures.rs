@@ -0,0 +1,60 @@
+#![cfg(feature = "signatures")]
+
+use rustsec::{
+    error::ErrorKind,
+    repository::signature::{Keyring, Signature},
+};
+use std::fs;
+
+/// Path to a file of the signature fixtures
+fn fixture(name: &str) -> String {
+    format!("./tests/support/signatures/{}", name)
+}
+
+/// Signature made by the trusted key over `commit.txt`
+fn signature() -> Signature {
+    Signature::from_bytes(&fs::read(fixture("commit.txt.asc")).unwrap()).unwrap()
+}
+
+#[test]
+fn verify_trusted_signature() {
+    let keyring = Keyring::load(&[fixture("trusted.asc")]).unwrap();
+    let data = fs::read(fixture("commit.txt")).unwrap();
+
+    assert_eq!(keyring.len(), 1);
+    signature().verify(&data, &keyring).unwrap();
+}
+
+#[test]
+fn verify_with_several_keys() {
+    let keyring = Keyring::load(&[fixture("untrusted.asc"), fixture("trusted.asc")]).unwrap();
+    let data = fs::read(fixture("commit.txt")).unwrap();
+
+    assert_eq!(keyring.len(), 2);
+    signature().verify(&data, &keyring).unwrap();
+}
+
+#[test]
+fn reject_untrusted_signature() {
+    let keyring = Keyring::load(&[fixture("untrusted.asc")]).unwrap();
+    let data = fs::read(fixture("commit.txt")).unwrap();
+    let error = signature().verify(&data, &keyring).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::Signature);
+}
+
+#[test]
+fn reject_tampered_data() {
+    let keyring = Keyring::load(&[fixture("trusted.asc")]).unwrap();
+    let mut data = fs::read(fixture("commit.txt")).unwrap();
+    data.extend(b"Remove advisory\n");
+    let error = signature().verify(&data, &keyring).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::Signature);
+}
+
+#[test]
+fn reject_invalid_key() {
+    let error = Keyring::from_armored("not a key").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Signature);
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "tarball")]
+
+use rustsec::repository::tarball::{self, Snapshot};
+use rustsec::Database;
+use std::fs;
+
+/// Tar header for an entry with the given path, type and size
+fn header(path: &str, typeflag: u8, size: usize) -> Vec<u8> {
+    let mut header = vec![0u8; 512];
+    header[..path.len()].copy_from_slice(path.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[156] = typeflag;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header
+}
+
+/// Add an entry to a tar archive
+fn add_entry(archive: &mut Vec<u8>, path: &str, typeflag: u8, data: &[u8]) {
+    archive.extend(header(path, typeflag, data.len()));
+    archive.extend(data);
+    archive.extend(vec![0; (512 - data.len() % 512) % 512]);
+}
+
+/// Gzip-compressed tar archive of an advisory database, laid out like
+/// GitHub's archives of a branch
+fn archive(entries: &[(&str, u8, &[u8])]) -> Vec<u8> {
+    let mut tar = vec![];
+
+    add_entry(
+        &mut tar,
+        "pax_global_header",
+        b'g',
+        b"52 comment=0123456789abcdef\n",
+    );
+
+    for (path, typeflag, data) in entries {
+        add_entry(&mut tar, path, *typeflag, data);
+    }
+
+    tar.extend(vec![0; 1024]);
+
+    // The trailer's checksum isn't checked when unpacking
+    let mut gzip = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    gzip.extend(miniz_oxide::deflate::compress_to_vec(&tar, 6));
+    gzip.extend(&[0; 8]);
+    gzip
+}
+
+#[test]
+fn unpack_snapshot() {
+    let advisory = fs::read("tests/support/example_advisory_v3.md").unwrap();
+    let long_path = format!("x/docs/{}", "y".repeat(120));
+    let pax = format!("{} path={}\n", long_path.len() + 10, long_path);
+
+    let archive = archive(&[
+        ("advisory-db-main/", b'5', b""),
+        ("advisory-db-main/crates/", b'5', b""),
+        ("advisory-db-main/crates/base/", b'5', b""),
+        (
+            "advisory-db-main/crates/base/RUSTSEC-2001-2101.md",
+            b'0',
+            &advisory,
+        ),
+        ("advisory-db-main/README.md", b'0', b"# Advisories\n"),
+        ("advisory-db-main/link", b'2', b""),
+        ("", b'x', pax.as_bytes()),
+        ("ignored-name", b'0', b"long"),
+    ]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot");
+    tarball::unpack(&archive, &path).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(path.join("README.md")).unwrap(),
+        "# Advisories\n"
+    );
+    assert!(!path.join("link").exists());
+    assert!(!path.join("ignored-name").exists());
+    assert_eq!(
+        fs::read_to_string(path.join("docs").join("y".repeat(120))).unwrap(),
+        "long"
+    );
+
+    let database = Database::open(&path).unwrap();
+    assert!(database
+        .get(&"RUSTSEC-2001-2101".parse().unwrap())
+        .is_some());
+}
+
+#[test]
+fn unpack_rejects_escaping_paths() {
+    let archive = archive(&[("advisory-db-main/../escaped", b'0', b"")]);
+    let dir = tempfile::tempdir().unwrap();
+
+    assert!(tarball::unpack(&archive, &dir.path().join("snapshot")).is_err());
+    assert!(!dir.path().join("escaped").exists());
+}
+
+#[test]
+fn open_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(Snapshot::open(dir.path().join("missing")).is_err());
+
+    fs::write(dir.path().join(tarball::ETAG_FILE), "\"abc123\"\n").unwrap();
+    let snapshot = Snapshot::open(dir.path()).unwrap();
+    assert_eq!(snapshot.path(), dir.path());
+    assert_eq!(snapshot.etag(), Some("\"abc123\""));
+    assert!(!snapshot.was_updated());
+
+    assert!(Snapshot::fetch("http://example.com/advisory-db.tar.gz", dir.path()).is_err());
+}